@@ -31,9 +31,11 @@ use jj_lib::op_store::OperationId;
 use jj_lib::ref_name::WorkspaceName;
 use jj_lib::ref_name::WorkspaceNameBuf;
 use jj_lib::repo::ReadonlyRepo;
+use jj_lib::repo_path::RepoPath;
 use jj_lib::repo_path::RepoPathBuf;
 use jj_lib::settings::UserSettings;
 use jj_lib::signing::Signer;
+use jj_lib::sparse::SparseMode;
 use jj_lib::store::Store;
 use jj_lib::working_copy::CheckoutError;
 use jj_lib::working_copy::CheckoutOptions;
@@ -179,6 +181,14 @@ impl WorkingCopy for ConflictsWorkingCopy {
         self.inner.sparse_patterns()
     }
 
+    fn sparse_mode(&self) -> Result<SparseMode, WorkingCopyStateError> {
+        self.inner.sparse_mode()
+    }
+
+    fn sparse_profile(&self) -> Result<Option<&RepoPath>, WorkingCopyStateError> {
+        self.inner.sparse_profile()
+    }
+
     fn start_mutation(&self) -> Result<Box<dyn LockedWorkingCopy>, WorkingCopyStateError> {
         let inner = self.inner.start_mutation()?;
         Ok(Box::new(LockedConflictsWorkingCopy {
@@ -293,6 +303,14 @@ impl LockedWorkingCopy for LockedConflictsWorkingCopy {
         self.inner.sparse_patterns()
     }
 
+    fn sparse_mode(&self) -> Result<SparseMode, WorkingCopyStateError> {
+        self.inner.sparse_mode()
+    }
+
+    fn sparse_profile(&self) -> Result<Option<&RepoPath>, WorkingCopyStateError> {
+        self.inner.sparse_profile()
+    }
+
     fn set_sparse_patterns(
         &mut self,
         new_sparse_patterns: Vec<RepoPathBuf>,
@@ -301,6 +319,26 @@ impl LockedWorkingCopy for LockedConflictsWorkingCopy {
         self.inner.set_sparse_patterns(new_sparse_patterns, options)
     }
 
+    fn set_sparse_patterns_and_mode(
+        &mut self,
+        new_sparse_patterns: Vec<RepoPathBuf>,
+        new_sparse_mode: SparseMode,
+        options: &CheckoutOptions,
+    ) -> Result<CheckoutStats, CheckoutError> {
+        self.inner
+            .set_sparse_patterns_and_mode(new_sparse_patterns, new_sparse_mode, options)
+    }
+
+    fn set_sparse_profile(
+        &mut self,
+        profile_path: RepoPathBuf,
+        new_sparse_mode: SparseMode,
+        options: &CheckoutOptions,
+    ) -> Result<CheckoutStats, CheckoutError> {
+        self.inner
+            .set_sparse_profile(profile_path, new_sparse_mode, options)
+    }
+
     fn finish(
         self: Box<Self>,
         operation_id: OperationId,
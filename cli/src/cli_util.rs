@@ -66,6 +66,7 @@ use jj_lib::config::ConfigNamePathBuf;
 use jj_lib::config::ConfigSource;
 use jj_lib::config::StackedConfig;
 use jj_lib::conflicts::ConflictMarkerStyle;
+use jj_lib::conflicts::resolve_file_executable;
 use jj_lib::fileset;
 use jj_lib::fileset::FilesetDiagnostics;
 use jj_lib::fileset::FilesetExpression;
@@ -100,6 +101,8 @@ use jj_lib::repo_path::RepoPath;
 use jj_lib::repo_path::RepoPathBuf;
 use jj_lib::repo_path::RepoPathUiConverter;
 use jj_lib::repo_path::UiPathParseError;
+use jj_lib::rerere;
+use jj_lib::rerere::ResolutionCache;
 use jj_lib::revset;
 use jj_lib::revset::ResolvedRevsetExpression;
 use jj_lib::revset::RevsetAliasesMap;
@@ -121,10 +124,15 @@ use jj_lib::str_util::StringPattern;
 use jj_lib::transaction::Transaction;
 use jj_lib::view::View;
 use jj_lib::working_copy;
+use jj_lib::working_copy::CheckoutError;
 use jj_lib::working_copy::CheckoutOptions;
 use jj_lib::working_copy::CheckoutStats;
 use jj_lib::working_copy::SnapshotOptions;
+use jj_lib::working_copy::SnapshotRule;
+use jj_lib::working_copy::SnapshotRuleAction;
+use jj_lib::working_copy::SnapshotRules;
 use jj_lib::working_copy::SnapshotStats;
+use jj_lib::working_copy::SnapshotWarning;
 use jj_lib::working_copy::UntrackedReason;
 use jj_lib::working_copy::WorkingCopy;
 use jj_lib::working_copy::WorkingCopyFactory;
@@ -152,6 +160,7 @@ use crate::command_error::internal_error_with_message;
 use crate::command_error::print_parse_diagnostics;
 use crate::command_error::user_error;
 use crate::command_error::user_error_with_hint;
+use crate::command_error::user_error_with_message;
 use crate::commit_templater::CommitTemplateLanguage;
 use crate::commit_templater::CommitTemplateLanguageExtension;
 use crate::complete;
@@ -474,6 +483,21 @@ impl CommandHelper {
         WorkspaceCommandHelper::new(ui, workspace, repo, env, self.is_at_head_operation())
     }
 
+    /// Returns the working copy factory registered under `name`.
+    pub fn get_working_copy_factory_by_name(
+        &self,
+        name: &str,
+    ) -> Result<&dyn WorkingCopyFactory, CommandError> {
+        let factory = self.data.working_copy_factories.get(name).ok_or_else(|| {
+            let known_names = self.data.working_copy_factories.keys().sorted().join(", ");
+            user_error_with_hint(
+                format!("Unknown working copy type: {name}"),
+                format!("Known working copy types are: {known_names}"),
+            )
+        })?;
+        Ok(factory.as_ref())
+    }
+
     pub fn get_working_copy_factory(&self) -> Result<&dyn WorkingCopyFactory, CommandError> {
         let loader = self.workspace_loader()?;
 
@@ -532,15 +556,22 @@ impl CommandHelper {
         match workspace.repo_loader().load_operation(op_id) {
             Ok(op) => {
                 let repo = workspace.repo_loader().load_at(&op)?;
+                let interrupted_checkout =
+                    workspace.working_copy().interrupted_checkout()?.is_some();
                 let mut workspace_command = self.for_workable_repo(ui, workspace, repo)?;
 
                 // Snapshot the current working copy on top of the last known working-copy
                 // operation, then merge the divergent operations. The wc_commit_id of the
                 // merged repo wouldn't change because the old one wins, but it's probably
-                // fine if we picked the new wc_commit_id.
-                let stats = workspace_command
-                    .maybe_snapshot_impl(ui)
-                    .map_err(|err| err.into_command_error())?;
+                // fine if we picked the new wc_commit_id. The files of an interrupted
+                // checkout can't be snapshotted, but the checkout can be finished.
+                let stats = if interrupted_checkout {
+                    SnapshotStats::default()
+                } else {
+                    workspace_command
+                        .maybe_snapshot_impl(ui)
+                        .map_err(|err| err.into_command_error())?
+                };
 
                 let wc_commit_id = workspace_command.get_wc_commit_id().unwrap();
                 let repo = workspace_command.repo().clone();
@@ -552,11 +583,19 @@ impl CommandHelper {
                 let repo = workspace_command.repo().clone();
                 let (mut locked_ws, desired_wc_commit) =
                     workspace_command.unchecked_start_working_copy_mutation()?;
-                match WorkingCopyFreshness::check_stale(
+                let freshness = match WorkingCopyFreshness::check_stale(
                     locked_ws.locked_wc(),
                     &desired_wc_commit,
                     &repo,
                 )? {
+                    // The interrupted checkout has to be finished even if the
+                    // working copy is otherwise up to date.
+                    WorkingCopyFreshness::Fresh if interrupted_checkout => {
+                        WorkingCopyFreshness::WorkingCopyStale
+                    }
+                    freshness => freshness,
+                };
+                match freshness {
                     WorkingCopyFreshness::Fresh | WorkingCopyFreshness::Updated(_) => {
                         writeln!(
                             ui.status(),
@@ -705,38 +744,26 @@ impl ReadonlyUserRepo {
     }
 }
 
-/// A advanceable bookmark to satisfy the "advance-bookmarks" feature.
-///
-/// This is a helper for `WorkspaceCommandTransaction`. It provides a
-/// type-safe way to separate the work of checking whether a bookmark
-/// can be advanced and actually advancing it. Advancing the bookmark
-/// never fails, but can't be done until the new `CommitId` is
-/// available. Splitting the work in this way also allows us to
-/// identify eligible bookmarks without actually moving them and
-/// return config errors to the user early.
-pub struct AdvanceableBookmark {
-    name: RefNameBuf,
-    old_commit_id: CommitId,
-}
-
 /// Helper for parsing and evaluating settings for the advance-bookmarks
 /// feature. Settings are configured in the jj config.toml as lists of
-/// [`StringPattern`]s for enabled and disabled bookmarks. Example:
+/// [`StringPattern`]s for enabled and disabled bookmarks, and optionally a
+/// revset selecting the commits whose bookmarks are enabled. Example:
 /// ```toml
-/// [experimental-advance-branches]
-/// # Enable the feature for all branches except "main".
-/// enabled-branches = ["glob:*"]
-/// disabled-branches = ["main"]
+/// [advance-bookmarks]
+/// # Enable the feature for all bookmarks except "main".
+/// enabled-bookmarks = ["glob:*"]
+/// disabled-bookmarks = ["main"]
 /// ```
 struct AdvanceBookmarksSettings {
     enabled_bookmarks: Vec<StringPattern>,
     disabled_bookmarks: Vec<StringPattern>,
+    enabled_revset: Option<RevisionArg>,
 }
 
 impl AdvanceBookmarksSettings {
     fn from_settings(settings: &UserSettings) -> Result<Self, CommandError> {
         let get_setting = |setting_key| {
-            let name = ConfigNamePathBuf::from_iter(["experimental-advance-branches", setting_key]);
+            let name = ConfigNamePathBuf::from_iter(["advance-bookmarks", setting_key]);
             match settings.get::<Vec<String>>(&name).optional()? {
                 Some(patterns) => patterns
                     .into_iter()
@@ -750,14 +777,19 @@ impl AdvanceBookmarksSettings {
             }
         };
         Ok(Self {
-            enabled_bookmarks: get_setting("enabled-branches")?,
-            disabled_bookmarks: get_setting("disabled-branches")?,
+            enabled_bookmarks: get_setting("enabled-bookmarks")?,
+            disabled_bookmarks: get_setting("disabled-bookmarks")?,
+            enabled_revset: settings
+                .get_string("advance-bookmarks.enabled-revset")
+                .optional()?
+                .map(RevisionArg::from),
         })
     }
 
     /// Returns true if the advance-bookmarks feature is enabled for
-    /// `bookmark_name`.
-    fn bookmark_is_eligible(&self, bookmark_name: &RefName) -> bool {
+    /// `bookmark_name`. `in_enabled_revset` tells whether the commit the
+    /// bookmark points to is in the "enabled-revset".
+    fn bookmark_is_eligible(&self, bookmark_name: &RefName, in_enabled_revset: bool) -> bool {
         if self
             .disabled_bookmarks
             .iter()
@@ -765,18 +797,30 @@ impl AdvanceBookmarksSettings {
         {
             return false;
         }
-        self.enabled_bookmarks
-            .iter()
-            .any(|e| e.is_match(bookmark_name.as_str()))
+        in_enabled_revset
+            || self
+                .enabled_bookmarks
+                .iter()
+                .any(|e| e.is_match(bookmark_name.as_str()))
     }
 
-    /// Returns true if the config includes at least one "enabled-branches"
-    /// pattern.
+    /// Returns true if the config includes at least one "enabled-bookmarks"
+    /// pattern or an "enabled-revset".
     fn feature_enabled(&self) -> bool {
-        !self.enabled_bookmarks.is_empty()
+        !self.enabled_bookmarks.is_empty() || self.enabled_revset.is_some()
     }
 }
 
+/// Simplifies deserialization of the config values while building a
+/// `SnapshotRule`. The optional `max-new-file-size` is read separately.
+#[derive(Clone, Debug, serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
+struct RawSnapshotRule {
+    patterns: Vec<String>,
+    action: SnapshotRuleAction,
+    workspaces: Option<Vec<String>>,
+}
+
 /// Metadata and configuration loaded for a specific workspace.
 pub struct WorkspaceCommandEnvironment {
     command: CommandHelper,
@@ -1173,7 +1217,7 @@ impl WorkspaceCommandHelper {
             }
         } else {
             // Unlikely, but the HEAD ref got deleted by git?
-            self.finish_transaction(ui, tx, "import git head")?;
+            self.finish_transaction(ui, tx, "import git head", None)?;
         }
         Ok(())
     }
@@ -1206,7 +1250,7 @@ impl WorkspaceCommandHelper {
                 "Rebased {num_rebased} descendant commits off of commits rewritten from git"
             )?;
         }
-        self.finish_transaction(ui, tx, "import git refs")?;
+        self.finish_transaction(ui, tx, "import git refs", None)?;
         writeln!(
             ui.status(),
             "Done importing changes from the underlying Git repo."
@@ -1222,6 +1266,11 @@ impl WorkspaceCommandHelper {
         self.workspace.repo_path()
     }
 
+    /// Returns the store of recorded conflict resolutions.
+    pub fn resolution_cache(&self) -> ResolutionCache {
+        ResolutionCache::new(self.repo_path().join("rerere"))
+    }
+
     pub fn workspace(&self) -> &Workspace {
         &self.workspace
     }
@@ -1234,9 +1283,10 @@ impl WorkspaceCommandHelper {
         &self.env
     }
 
-    pub fn checkout_options(&self) -> CheckoutOptions {
+    pub fn checkout_options(&self) -> CheckoutOptions<'static> {
         CheckoutOptions {
             conflict_marker_style: self.env.conflict_marker_style(),
+            progress: None,
         }
     }
 
@@ -1371,8 +1421,62 @@ to the current parents may contain changes from multiple commits.
         Ok(expression.to_matcher())
     }
 
+    /// Parses the `snapshot.rules` config table. Rules restricted to other
+    /// workspaces are skipped.
+    pub fn snapshot_rules(&self, ui: &Ui) -> Result<SnapshotRules, CommandError> {
+        let settings = self.settings();
+        let rules: Vec<SnapshotRule> = settings
+            .table_keys("snapshot.rules")
+            // Sort keys early so errors are deterministic.
+            .sorted()
+            .map(|name| -> Result<Option<SnapshotRule>, CommandError> {
+                let rule: RawSnapshotRule = settings.get(["snapshot", "rules", name])?;
+                if rule
+                    .workspaces
+                    .as_ref()
+                    .is_some_and(|names| !names.iter().any(|n| n == self.workspace_name().as_str()))
+                {
+                    return Ok(None);
+                }
+                let max_new_file_size = settings
+                    .get_value_with(
+                        ["snapshot", "rules", name, "max-new-file-size"],
+                        TryInto::try_into,
+                    )
+                    .optional()?
+                    .map(|HumanByteSize(size): HumanByteSize| size);
+                let mut diagnostics = FilesetDiagnostics::new();
+                let expression = FilesetExpression::union_all(
+                    rule.patterns
+                        .iter()
+                        .map(|pattern| {
+                            fileset::parse(
+                                &mut diagnostics,
+                                pattern,
+                                &RepoPathUiConverter::Fs {
+                                    cwd: "".into(),
+                                    base: "".into(),
+                                },
+                            )
+                        })
+                        .try_collect()?,
+                );
+                print_parse_diagnostics(ui, &format!("In `snapshot.rules.{name}`"), &diagnostics)?;
+                Ok(Some(SnapshotRule {
+                    name: name.to_owned(),
+                    matcher: expression.to_matcher(),
+                    max_new_file_size,
+                    action: rule.action,
+                }))
+            })
+            .flatten_ok()
+            .try_collect()?;
+        Ok(SnapshotRules::new(rules))
+    }
+
     pub fn snapshot_options_with_start_tracking_matcher<'a>(
         &self,
+        ui: &Ui,
         start_tracking_matcher: &'a dyn Matcher,
     ) -> Result<SnapshotOptions<'a>, CommandError> {
         let base_ignores = self.base_ignores()?;
@@ -1383,6 +1487,10 @@ to the current parents may contain changes from multiple commits.
         if max_new_file_size == 0 {
             max_new_file_size = u64::MAX;
         }
+        // The rules are passed to worker threads by reference, so they don't
+        // need to be Send.
+        #[expect(clippy::arc_with_non_send_sync)]
+        let snapshot_rules = Arc::new(self.snapshot_rules(ui)?);
         let conflict_marker_style = self.env.conflict_marker_style();
         Ok(SnapshotOptions {
             base_ignores,
@@ -1390,6 +1498,7 @@ to the current parents may contain changes from multiple commits.
             progress: None,
             start_tracking_matcher,
             max_new_file_size,
+            snapshot_rules,
             conflict_marker_style,
         })
     }
@@ -1853,8 +1962,13 @@ to the current parents may contain changes from multiple commits.
             .auto_tracking_matcher(ui)
             .map_err(snapshot_command_error)?;
         let options = self
-            .snapshot_options_with_start_tracking_matcher(&auto_tracking_matcher)
+            .snapshot_options_with_start_tracking_matcher(ui, &auto_tracking_matcher)
             .map_err(snapshot_command_error)?;
+        let resolution_cache = self
+            .settings()
+            .get_bool("rerere.enabled")
+            .map_err(snapshot_command_error)?
+            .then(|| self.resolution_cache());
 
         // Compare working-copy tree and operation with repo's, and reload as needed.
         let mut locked_ws = self
@@ -1936,6 +2050,10 @@ See https://jj-vcs.github.io/jj/latest/working-copy/#stale-working-copy \
             mut_repo
                 .set_wc_commit(workspace_name, commit.id().clone())
                 .map_err(snapshot_command_error)?;
+            if let Some(resolution_cache) = &resolution_cache {
+                rerere::record_resolutions(mut_repo, resolution_cache)
+                    .map_err(snapshot_command_error)?;
+            }
 
             // Rebase descendants
             let num_rebased = mut_repo
@@ -1983,14 +2101,18 @@ See https://jj-vcs.github.io/jj/latest/working-copy/#stale-working-copy \
         new_commit: &Commit,
     ) -> Result<(), CommandError> {
         assert!(self.may_update_working_copy);
-        let checkout_options = self.checkout_options();
-        let stats = update_working_copy(
-            &self.user_repo.repo,
-            &mut self.workspace,
-            maybe_old_commit,
-            new_commit,
-            &checkout_options,
-        )?;
+        let stats = {
+            let mut checkout_options = self.checkout_options();
+            let progress = crate::progress::checkout_progress(ui);
+            checkout_options.progress = progress.as_ref().map(|x| x as _);
+            update_working_copy(
+                &self.user_repo.repo,
+                &mut self.workspace,
+                maybe_old_commit,
+                new_commit,
+                &checkout_options,
+            )?
+        };
         self.print_updated_working_copy_stats(ui, maybe_old_commit, new_commit, &stats)
     }
 
@@ -2016,7 +2138,7 @@ See https://jj-vcs.github.io/jj/latest/working-copy/#stale-working-copy \
                 }
             }
         }
-        print_checkout_stats(ui, stats, new_commit)?;
+        print_checkout_stats(ui, stats, new_commit, self.path_converter())?;
         if Some(new_commit) != maybe_old_commit {
             if let Some(mut formatter) = ui.status_formatter() {
                 if new_commit.has_conflict()? {
@@ -2039,14 +2161,108 @@ See https://jj-vcs.github.io/jj/latest/working-copy/#stale-working-copy \
             helper: self,
             tx,
             id_prefix_context,
+            advance_bookmarks: None,
+            advance_bookmarks_target: None,
         }
     }
 
+    /// Moves eligible bookmarks forward. Bookmarks pointing to a parent of the
+    /// `target` are advanced to the `target`.
+    ///
+    /// If no `target` is given, it is the new `@-` if the transaction moved the
+    /// working copy onto a different parent. Only bookmarks which pointed to
+    /// the old `@-` before the transaction are moved, and only if they still
+    /// point to a parent of the new `@-` after the rewrites made by the
+    /// transaction. Nothing is moved if the old or new `@` is a merge commit.
+    fn advance_bookmarks(
+        &self,
+        ui: &Ui,
+        tx: &mut Transaction,
+        settings: &AdvanceBookmarksSettings,
+        target: Option<&CommitId>,
+    ) -> Result<(), CommandError> {
+        let (target_id, old_commit_ids) = if let Some(target_id) = target {
+            let target = tx.repo().store().get_commit(target_id)?;
+            (target_id.clone(), target.parent_ids().to_vec())
+        } else {
+            let Some(wc_commit_id) = tx.repo().view().get_wc_commit_id(self.workspace_name())
+            else {
+                return Ok(());
+            };
+            let Some(old_wc_commit_id) = tx
+                .base_repo()
+                .view()
+                .get_wc_commit_id(self.workspace_name())
+            else {
+                return Ok(());
+            };
+            let wc_commit = tx.repo().store().get_commit(wc_commit_id)?;
+            let old_wc_commit = tx.base_repo().store().get_commit(old_wc_commit_id)?;
+            if old_wc_commit.parent_ids() == wc_commit.parent_ids() {
+                return Ok(());
+            }
+            let ([target_id], [old_parent_id]) =
+                (wc_commit.parent_ids(), old_wc_commit.parent_ids())
+            else {
+                return Ok(());
+            };
+            (target_id.clone(), vec![old_parent_id.clone()])
+        };
+
+        // Bookmarks are selected in the base repo, so a bookmark which was
+        // only moved onto a parent of the target by this transaction isn't
+        // advanced.
+        let mut names: Vec<RefNameBuf> = vec![];
+        for old_commit_id in &old_commit_ids {
+            let in_enabled_revset = match &settings.enabled_revset {
+                Some(revset) => {
+                    let mut expression = self.parse_revset(ui, revset)?;
+                    expression.intersect_with(&RevsetExpression::commit(old_commit_id.clone()));
+                    expression
+                        .evaluate_to_commit_ids()?
+                        .next()
+                        .transpose()?
+                        .is_some()
+                }
+                None => false,
+            };
+            names.extend(
+                tx.base_repo()
+                    .view()
+                    .local_bookmarks_for_commit(old_commit_id)
+                    .filter(|(name, _)| settings.bookmark_is_eligible(name, in_enabled_revset))
+                    .map(|(name, _)| name.to_owned()),
+            );
+        }
+
+        let target = tx.repo().store().get_commit(&target_id)?;
+        for name in names {
+            // The bookmark may have been rewritten by the transaction, so
+            // look up where it points now.
+            let current_target = tx.repo().view().get_local_bookmark(&name).clone();
+            for parent_id in target.parent_ids() {
+                if !current_target.added_ids().contains(parent_id) {
+                    continue;
+                }
+                // This removes the parent ID from the bookmark's RefTarget
+                // and replaces it with the target ID. A conflicted bookmark
+                // remains conflicted.
+                tx.repo_mut().merge_local_bookmark(
+                    &name,
+                    &RefTarget::normal(parent_id.clone()),
+                    &RefTarget::normal(target.id().clone()),
+                );
+            }
+        }
+        Ok(())
+    }
+
     fn finish_transaction(
         &mut self,
         ui: &Ui,
         mut tx: Transaction,
         description: impl Into<String>,
+        advance_bookmarks: Option<(&AdvanceBookmarksSettings, Option<&CommitId>)>,
     ) -> Result<(), CommandError> {
         if !tx.repo().has_changes() {
             writeln!(ui.status(), "Nothing changed.")?;
@@ -2056,6 +2272,12 @@ See https://jj-vcs.github.io/jj/latest/working-copy/#stale-working-copy \
         if num_rebased > 0 {
             writeln!(ui.status(), "Rebased {num_rebased} descendant commits")?;
         }
+        if let Some((settings, target)) = advance_bookmarks {
+            self.advance_bookmarks(ui, &mut tx, settings, target)?;
+        }
+        if self.settings().get_bool("rerere.enabled")? {
+            self.reuse_resolutions(ui, &mut tx)?;
+        }
 
         for (name, wc_commit_id) in &tx.repo().view().wc_commit_ids().clone() {
             if self
@@ -2155,6 +2377,26 @@ See https://jj-vcs.github.io/jj/latest/working-copy/#stale-working-copy \
         Ok(())
     }
 
+    /// Records the conflicts resolved in the transaction, and resolves the
+    /// conflicts in the new commits for which resolutions were recorded.
+    fn reuse_resolutions(&self, ui: &Ui, tx: &mut Transaction) -> Result<(), CommandError> {
+        let cache = self.resolution_cache();
+        rerere::record_resolutions(tx.repo(), &cache)?;
+        let reused = rerere::reuse_resolutions(tx.repo_mut(), &cache)?;
+        if reused.is_empty() {
+            return Ok(());
+        }
+        let paths = reused.iter().flat_map(|entry| &entry.paths).unique();
+        writeln!(
+            ui.status(),
+            "Resolved conflicts in these files using recorded resolutions:"
+        )?;
+        for path in paths {
+            writeln!(ui.status(), "  {}", self.format_file_path(path))?;
+        }
+        Ok(())
+    }
+
     /// Inform the user about important changes to the repo since the previous
     /// operation (when `old_repo` was loaded).
     fn report_repo_changes(
@@ -2324,45 +2566,6 @@ See https://jj-vcs.github.io/jj/latest/working-copy/#stale-working-copy \
         )?;
         Ok(())
     }
-
-    /// Identifies bookmarks which are eligible to be moved automatically
-    /// during `jj commit` and `jj new`. Whether a bookmark is eligible is
-    /// determined by its target and the user and repo config for
-    /// "advance-bookmarks".
-    ///
-    /// Returns a Vec of bookmarks in `repo` that point to any of the `from`
-    /// commits and that are eligible to advance. The `from` commits are
-    /// typically the parents of the target commit of `jj commit` or `jj new`.
-    ///
-    /// Bookmarks are not moved until
-    /// `WorkspaceCommandTransaction::advance_bookmarks()` is called with the
-    /// `AdvanceableBookmark`s returned by this function.
-    ///
-    /// Returns an empty `std::Vec` if no bookmarks are eligible to advance.
-    pub fn get_advanceable_bookmarks<'a>(
-        &self,
-        from: impl IntoIterator<Item = &'a CommitId>,
-    ) -> Result<Vec<AdvanceableBookmark>, CommandError> {
-        let ab_settings = AdvanceBookmarksSettings::from_settings(self.settings())?;
-        if !ab_settings.feature_enabled() {
-            // Return early if we know that there's no work to do.
-            return Ok(Vec::new());
-        }
-
-        let mut advanceable_bookmarks = Vec::new();
-        for from_commit in from {
-            for (name, _) in self.repo().view().local_bookmarks_for_commit(from_commit) {
-                if ab_settings.bookmark_is_eligible(name) {
-                    advanceable_bookmarks.push(AdvanceableBookmark {
-                        name: name.to_owned(),
-                        old_commit_id: from_commit.clone(),
-                    });
-                }
-            }
-        }
-
-        Ok(advanceable_bookmarks)
-    }
 }
 
 /// An ongoing [`Transaction`] tied to a particular workspace.
@@ -2378,6 +2581,11 @@ pub struct WorkspaceCommandTransaction<'a> {
     tx: Transaction,
     /// Cache of index built against the current MutableRepo state.
     id_prefix_context: OnceCell<IdPrefixContext>,
+    /// Settings for advancing bookmarks when the transaction is finished, if
+    /// enabled by the command.
+    advance_bookmarks: Option<AdvanceBookmarksSettings>,
+    /// The commit to advance bookmarks to, if not the new `@-`.
+    advance_bookmarks_target: Option<CommitId>,
 }
 
 impl WorkspaceCommandTransaction<'_> {
@@ -2404,6 +2612,11 @@ impl WorkspaceCommandTransaction<'_> {
         self.tx.repo_mut()
     }
 
+    /// Sets a tag on the operation to be committed.
+    pub fn set_tag(&mut self, key: String, value: String) {
+        self.tx.set_tag(key, value);
+    }
+
     pub fn check_out(&mut self, commit: &Commit) -> Result<Commit, CheckOutCommitError> {
         let name = self.helper.workspace_name().to_owned();
         self.id_prefix_context.take(); // invalidate
@@ -2458,8 +2671,37 @@ impl WorkspaceCommandTransaction<'_> {
         self.helper.env.parse_template(ui, &language, template_text)
     }
 
+    /// Advances bookmarks configured in "advance-bookmarks" from the old `@-`
+    /// to the new `@-` when the transaction is finished, if the working copy
+    /// moved onto a different parent.
+    ///
+    /// This is meant for commands which create new commits on top of the
+    /// working copy, such as `jj commit` and `jj squash`. Commands which only
+    /// move the working copy, such as `jj edit`, don't advance bookmarks.
+    /// Config errors are reported immediately.
+    pub fn advance_bookmarks(&mut self) -> Result<(), CommandError> {
+        let settings = AdvanceBookmarksSettings::from_settings(self.settings())?;
+        if settings.feature_enabled() {
+            self.advance_bookmarks = Some(settings);
+        }
+        Ok(())
+    }
+
+    /// Like [`Self::advance_bookmarks()`], but advances bookmarks pointing to
+    /// a parent of `target` to `target`, even if the working copy didn't move.
+    pub fn advance_bookmarks_to(&mut self, target: CommitId) -> Result<(), CommandError> {
+        self.advance_bookmarks()?;
+        self.advance_bookmarks_target = Some(target);
+        Ok(())
+    }
+
     pub fn finish(self, ui: &Ui, description: impl Into<String>) -> Result<(), CommandError> {
-        self.helper.finish_transaction(ui, self.tx, description)
+        let advance_bookmarks = self
+            .advance_bookmarks
+            .as_ref()
+            .map(|settings| (settings, self.advance_bookmarks_target.as_ref()));
+        self.helper
+            .finish_transaction(ui, self.tx, description, advance_bookmarks)
     }
 
     /// Returns the wrapped [`Transaction`] for circumstances where
@@ -2469,23 +2711,6 @@ impl WorkspaceCommandTransaction<'_> {
     pub fn into_inner(self) -> Transaction {
         self.tx
     }
-
-    /// Moves each bookmark in `bookmarks` from an old commit it's associated
-    /// with (configured by `get_advanceable_bookmarks`) to the `move_to`
-    /// commit. If the bookmark is conflicted before the update, it will
-    /// remain conflicted after the update, but the conflict will involve
-    /// the `move_to` commit instead of the old commit.
-    pub fn advance_bookmarks(&mut self, bookmarks: Vec<AdvanceableBookmark>, move_to: &CommitId) {
-        for bookmark in bookmarks {
-            // This removes the old commit ID from the bookmark's RefTarget and
-            // replaces it with the `move_to` ID.
-            self.repo_mut().merge_local_bookmark(
-                &bookmark.name,
-                &RefTarget::normal(bookmark.old_commit_id),
-                &RefTarget::normal(move_to.clone()),
-            );
-        }
-    }
 }
 
 pub fn find_workspace_dir(cwd: &Path) -> &Path {
@@ -2512,10 +2737,14 @@ jj git init --colocate",
                 user_error(message)
             }
         }
-        WorkspaceLoadError::RepoDoesNotExist(repo_dir) => user_error(format!(
-            "The repository directory at {} is missing. Was it moved?",
-            repo_dir.display(),
-        )),
+        WorkspaceLoadError::RepoDoesNotExist(repo_dir) => user_error_with_hint(
+            format!(
+                "The repository directory at {} is missing. Was it moved?",
+                repo_dir.display(),
+            ),
+            "Run `jj workspace repair --repo <path>` to point this workspace to the moved \
+             repository.",
+        ),
         WorkspaceLoadError::StoreLoadError(err @ StoreLoadError::UnsupportedType { .. }) => {
             internal_error_with_message(
                 "This version of the jj binary doesn't support this type of repo",
@@ -2661,6 +2890,11 @@ pub fn print_conflicted_paths(
                 "difficult",
             );
         }
+        if let Some(executable_merge) = conflict.to_executable_merge() {
+            if resolve_file_executable(&executable_merge).is_none() {
+                seen_objects.insert("an executable-bit conflict".to_string(), "difficult");
+            }
+        }
 
         write!(formatter, "{formatted_path} ")?;
         formatter.with_label("conflict_description", |formatter| {
@@ -2713,19 +2947,30 @@ fn build_untracked_reason_message(reason: &UntrackedReason) -> Option<String> {
                  ({max_size} bytes)",
             ))
         }
-        // Paths with UntrackedReason::FileNotAutoTracked shouldn't be warned about
-        // every time we make a snapshot. These paths will be printed by
-        // "jj status" instead.
-        UntrackedReason::FileNotAutoTracked => None,
+        UntrackedReason::RefusedByRule { rule, size } => {
+            Some(build_snapshot_rule_message(rule, *size))
+        }
+        // Paths with UntrackedReason::FileNotAutoTracked or IgnoredByRule
+        // shouldn't be warned about every time we make a snapshot. These paths
+        // will be printed by "jj status" instead.
+        UntrackedReason::FileNotAutoTracked | UntrackedReason::IgnoredByRule { .. } => None,
     }
 }
 
+fn build_snapshot_rule_message(rule: &str, size: u64) -> String {
+    let size_approx = HumanByteSize(size);
+    format!("{size_approx} ({size} bytes); matches snapshot rule `{rule}`")
+}
+
 /// Print a warning to the user, listing untracked files that he may care about
 pub fn print_untracked_files(
     ui: &Ui,
     untracked_paths: &BTreeMap<RepoPathBuf, UntrackedReason>,
     path_converter: &RepoPathUiConverter,
 ) -> io::Result<()> {
+    let has_refused_by_rule = untracked_paths
+        .values()
+        .any(|reason| matches!(reason, UntrackedReason::RefusedByRule { .. }));
     let mut untracked_paths = untracked_paths
         .iter()
         .filter_map(|(path, reason)| build_untracked_reason_message(reason).map(|m| (path, m)))
@@ -2740,6 +2985,35 @@ pub fn print_untracked_files(
         }
     }
 
+    if has_refused_by_rule {
+        writeln!(
+            ui.hint_default(),
+            "Files matching a rule in `snapshot.rules` with `action = \"refuse\"` are never \
+             snapshotted. Add them to `.gitignore` to silence this warning."
+        )?;
+    }
+    Ok(())
+}
+
+/// Print a warning to the user, listing new files that were tracked but
+/// matched a snapshot rule asking to report them
+pub fn print_warned_files(
+    ui: &Ui,
+    warned_paths: &BTreeMap<RepoPathBuf, SnapshotWarning>,
+    path_converter: &RepoPathUiConverter,
+) -> io::Result<()> {
+    if !warned_paths.is_empty() {
+        writeln!(
+            ui.warning_default(),
+            "Snapshotted some files matching snapshot rules:"
+        )?;
+        let mut formatter = ui.stderr_formatter();
+        for (path, warning) in warned_paths {
+            let ui_path = path_converter.format_file_path(path);
+            let message = build_snapshot_rule_message(&warning.rule, warning.size);
+            writeln!(formatter, "  {ui_path}: {message}")?;
+        }
+    }
     Ok(())
 }
 
@@ -2749,13 +3023,16 @@ pub fn print_snapshot_stats(
     path_converter: &RepoPathUiConverter,
 ) -> io::Result<()> {
     print_untracked_files(ui, &stats.untracked_paths, path_converter)?;
+    print_warned_files(ui, &stats.warned_paths, path_converter)?;
 
     let large_files_sizes = stats
         .untracked_paths
         .values()
         .filter_map(|reason| match reason {
             UntrackedReason::FileTooLarge { size, .. } => Some(size),
-            UntrackedReason::FileNotAutoTracked => None,
+            UntrackedReason::FileNotAutoTracked
+            | UntrackedReason::IgnoredByRule { .. }
+            | UntrackedReason::RefusedByRule { .. } => None,
         });
     if let Some(size) = large_files_sizes.max() {
         writedoc!(
@@ -2777,6 +3054,7 @@ pub fn print_checkout_stats(
     ui: &Ui,
     stats: &CheckoutStats,
     new_commit: &Commit,
+    path_converter: &RepoPathUiConverter,
 ) -> Result<(), std::io::Error> {
     if stats.added_files > 0 || stats.updated_files > 0 || stats.removed_files > 0 {
         writeln!(
@@ -2802,6 +3080,25 @@ Discard the conflicting changes with `jj restore --from {}`.",
             short_commit_hash(new_commit.id())
         )?;
     }
+    if !stats.failed_files.is_empty() {
+        writeln!(
+            ui.warning_default(),
+            "Failed to update {} files:",
+            stats.failed_files.len()
+        )?;
+        let mut formatter = ui.stderr_formatter();
+        for (path, message) in &stats.failed_files {
+            let ui_path = path_converter.format_file_path(path);
+            writeln!(formatter, "  {ui_path}: {message}")?;
+        }
+        drop(formatter);
+        writeln!(
+            ui.hint_default(),
+            "The files were left as they were on disk. Once the problem is fixed, discard their \
+             changes with `jj restore --from {}`.",
+            short_commit_hash(new_commit.id())
+        )?;
+    }
     Ok(())
 }
 
@@ -2850,10 +3147,15 @@ pub fn update_working_copy(
             options,
         )
         .map_err(|err| {
-            internal_error_with_message(
-                format!("Failed to check out commit {}", new_commit.id().hex()),
-                err,
-            )
+            let message = format!("Failed to check out commit {}", new_commit.id().hex());
+            match err {
+                err @ CheckoutError::PathCollision { .. } => user_error_with_message(message, err)
+                    .hinted(
+                        "Rename one of the paths in a workspace on a file system which can hold \
+                         both. `jj file list --collisions` lists all colliding paths.",
+                    ),
+                err => internal_error_with_message(message, err),
+            }
         })?;
     Ok(stats)
 }
@@ -3620,7 +3922,8 @@ fn parse_args(app: &Command, string_args: &[String]) -> Result<(ArgMatches, Args
     Ok((matches, args))
 }
 
-fn command_name(mut matches: &ArgMatches) -> String {
+/// Returns the names of the subcommands in `matches`, separated by spaces.
+pub fn command_name(mut matches: &ArgMatches) -> String {
     let mut command = String::new();
     while let Some((subcommand, new_matches)) = matches.subcommand() {
         if !command.is_empty() {
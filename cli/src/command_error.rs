@@ -24,6 +24,7 @@ use itertools::Itertools as _;
 use jj_lib::absorb::AbsorbError;
 use jj_lib::backend::BackendError;
 use jj_lib::backend::CommitId;
+use jj_lib::bisect::BisectError;
 use jj_lib::config::ConfigFileSaveError;
 use jj_lib::config::ConfigGetError;
 use jj_lib::config::ConfigLoadError;
@@ -40,17 +41,20 @@ use jj_lib::op_heads_store::OpHeadsStoreError;
 use jj_lib::op_store::OpStoreError;
 use jj_lib::op_walk::OpsetEvaluationError;
 use jj_lib::op_walk::OpsetResolutionError;
+use jj_lib::patch::PatchApplyError;
 use jj_lib::repo::CheckOutCommitError;
 use jj_lib::repo::EditCommitError;
 use jj_lib::repo::RepoLoaderError;
 use jj_lib::repo::RewriteRootCommit;
 use jj_lib::repo_path::RepoPathBuf;
 use jj_lib::repo_path::UiPathParseError;
+use jj_lib::rerere::RerereError;
 use jj_lib::revset;
 use jj_lib::revset::RevsetEvaluationError;
 use jj_lib::revset::RevsetParseError;
 use jj_lib::revset::RevsetParseErrorKind;
 use jj_lib::revset::RevsetResolutionError;
+use jj_lib::signing::SignError;
 use jj_lib::str_util::StringPatternParseError;
 use jj_lib::trailer::TrailerParseError;
 use jj_lib::transaction::TransactionCommitError;
@@ -60,6 +64,7 @@ use jj_lib::working_copy::ResetError;
 use jj_lib::working_copy::SnapshotError;
 use jj_lib::working_copy::WorkingCopyStateError;
 use jj_lib::workspace::WorkspaceInitError;
+use jj_lib::workspace_store::WorkspaceStoreError;
 use thiserror::Error;
 
 use crate::cli_util::short_operation_hash;
@@ -356,10 +361,17 @@ impl From<WorkspaceInitError> for CommandError {
             }
             WorkspaceInitError::SignInit(err) => user_error(err),
             WorkspaceInitError::TransactionCommit(err) => err.into(),
+            WorkspaceInitError::WorkspaceStore(err) => err.into(),
         }
     }
 }
 
+impl From<WorkspaceStoreError> for CommandError {
+    fn from(err: WorkspaceStoreError) -> Self {
+        internal_error_with_message("Failed to update the workspace store", err)
+    }
+}
+
 impl From<OpHeadResolutionError> for CommandError {
     fn from(err: OpHeadResolutionError) -> Self {
         match err {
@@ -388,7 +400,13 @@ impl From<OpsetEvaluationError> for CommandError {
 
 impl From<SnapshotError> for CommandError {
     fn from(err: SnapshotError) -> Self {
-        internal_error_with_message("Failed to snapshot the working copy", err)
+        match err {
+            SnapshotError::InterruptedCheckout => user_error_with_hint(
+                err,
+                "Run `jj workspace update-stale` to finish the checkout.",
+            ),
+            _ => internal_error_with_message("Failed to snapshot the working copy", err),
+        }
     }
 }
 
@@ -412,7 +430,14 @@ impl From<ResetError> for CommandError {
 
 impl From<TransactionCommitError> for CommandError {
     fn from(err: TransactionCommitError) -> Self {
-        internal_error(err)
+        if matches!(err, TransactionCommitError::Sign(SignError::NoBackend)) {
+            user_error_with_hint(
+                err,
+                "Configure `signing.backend`, or disable `signing.sign-operations`",
+            )
+        } else {
+            internal_error(err)
+        }
     }
 }
 
@@ -574,6 +599,7 @@ jj currently does not support partial clones. To use jj with this repository, tr
                 ),
                 GitFetchError::InvalidBranchPattern(_) => user_error(err),
                 GitFetchError::Subprocess(_) => user_error(err),
+                GitFetchError::Gix(_) => user_error(err),
             }
         }
     }
@@ -587,6 +613,7 @@ jj currently does not support partial clones. To use jj with this repository, tr
                     "Run `jj git remote rename` to give a different name.",
                 ),
                 GitPushError::Subprocess(_) => user_error(err),
+                GitPushError::Gix(_) => user_error(err),
                 GitPushError::UnexpectedBackend(_) => user_error(err),
             }
         }
@@ -719,6 +746,17 @@ impl From<AbsorbError> for CommandError {
     }
 }
 
+impl From<BisectError> for CommandError {
+    fn from(err: BisectError) -> Self {
+        match err {
+            BisectError::InvalidState { .. } => internal_error(err),
+            BisectError::OpStore(err) => err.into(),
+            BisectError::Backend(err) => err.into(),
+            BisectError::RevsetEvaluation(err) => err.into(),
+        }
+    }
+}
+
 impl From<FixError> for CommandError {
     fn from(err: FixError) -> Self {
         match err {
@@ -733,6 +771,25 @@ impl From<FixError> for CommandError {
     }
 }
 
+impl From<PatchApplyError> for CommandError {
+    fn from(err: PatchApplyError) -> Self {
+        match err {
+            PatchApplyError::Backend(err) => err.into(),
+            _ => user_error(err),
+        }
+    }
+}
+
+impl From<RerereError> for CommandError {
+    fn from(err: RerereError) -> Self {
+        match err {
+            RerereError::Backend(err) => err.into(),
+            RerereError::Io(_) => internal_error(err),
+            RerereError::RevsetEvaluation(err) => err.into(),
+        }
+    }
+}
+
 fn find_source_parse_error_hint(err: &dyn error::Error) -> Option<String> {
     let source = err.source()?;
     if let Some(source) = source.downcast_ref() {
@@ -0,0 +1,164 @@
+// Copyright 2025 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::fs;
+use std::io;
+use std::io::Read as _;
+use std::io::Write as _;
+
+use clap_complete::ArgValueCompleter;
+use itertools::Itertools as _;
+use jj_lib::patch::apply_file_patches;
+use jj_lib::patch::parse_mailbox;
+use jj_lib::repo::Repo as _;
+use tracing::instrument;
+
+use crate::cli_util::CommandHelper;
+use crate::cli_util::RevisionArg;
+use crate::command_error::CommandError;
+use crate::command_error::user_error;
+use crate::command_error::user_error_with_message;
+use crate::complete;
+use crate::ui::Ui;
+
+/// Create commits from patch files or mailboxes
+///
+/// Each file may contain a plain unified diff, a diff in git's extended
+/// format, or a mailbox such as produced by `git format-patch` with one patch
+/// per mail. A commit is created for each patch, on top of the previous one.
+/// For mails, the author, the author timestamp and the description of the
+/// commit are taken from the headers and the message.
+///
+/// Hunks which don't apply at their recorded position are searched for
+/// nearby, ignoring up to two lines of context at either end if needed. Hunks
+/// which still can't be applied are recorded as conflicts in the commit
+/// instead of aborting the import, as are files which are added but already
+/// exist. Changes to missing files, and binary patches which don't match the
+/// file, are skipped with a warning.
+#[derive(clap::Args, Clone, Debug)]
+pub(crate) struct ApplyArgs {
+    /// The patch or mailbox files to apply, or `-` to read from standard input
+    #[arg(required = true, value_hint = clap::ValueHint::FilePath)]
+    paths: Vec<String>,
+    /// The revision to apply the patches on top of
+    #[arg(
+        long, short,
+        default_value = "@",
+        value_name = "REVSET",
+        add = ArgValueCompleter::new(complete::revset_expression_all),
+    )]
+    destination: RevisionArg,
+}
+
+#[instrument(skip_all)]
+pub(crate) fn cmd_apply(
+    ui: &mut Ui,
+    command: &CommandHelper,
+    args: &ApplyArgs,
+) -> Result<(), CommandError> {
+    let mut workspace_command = command.workspace_helper(ui)?;
+    let mut parent = workspace_command.resolve_single_rev(ui, &args.destination)?;
+
+    let mut messages = vec![];
+    for path in &args.paths {
+        let data = if path == "-" {
+            let mut data = vec![];
+            io::stdin().read_to_end(&mut data)?;
+            data
+        } else {
+            fs::read(command.cwd().join(path)).map_err(|err| {
+                user_error_with_message(format!("Failed to read patch file {path}"), err)
+            })?
+        };
+        let parsed = parse_mailbox(&data).map_err(|err| {
+            user_error_with_message(format!("Failed to parse patch file {path}"), err)
+        })?;
+        messages.extend(parsed);
+    }
+    // Cover letters and replies don't contain any change.
+    let num_messages = messages.len();
+    messages.retain(|message| !message.files.is_empty());
+    if messages.len() < num_messages {
+        writeln!(
+            ui.warning_default(),
+            "Skipped {} messages without a patch",
+            num_messages - messages.len()
+        )?;
+    }
+    if messages.is_empty() {
+        return Err(user_error("No patches found"));
+    }
+
+    let mut tx = workspace_command.start_transaction();
+    let mut new_commits = vec![];
+    let mut skipped = vec![];
+    for message in &messages {
+        let applied = apply_file_patches(tx.repo().store(), &parent.tree()?, &message.files)?;
+        let mut commit_builder = tx
+            .repo_mut()
+            .new_commit(vec![parent.id().clone()], applied.tree_id)
+            .set_description(&message.description);
+        let mut author = commit_builder.author().clone();
+        if let Some((name, email)) = &message.author {
+            author.name = name.clone();
+            author.email = email.clone();
+        }
+        if let Some(timestamp) = message.date {
+            author.timestamp = timestamp;
+        }
+        commit_builder = commit_builder.set_author(author);
+        let new_commit = commit_builder.write()?;
+        new_commits.push((new_commit.clone(), applied.conflicted_paths));
+        skipped.extend(applied.skipped);
+        parent = new_commit;
+    }
+
+    if let Some(mut formatter) = ui.status_formatter() {
+        writeln!(formatter, "Applied {} patches:", new_commits.len())?;
+        for (commit, _) in &new_commits {
+            write!(formatter, "  ")?;
+            tx.write_commit_summary(formatter.as_mut(), commit)?;
+            writeln!(formatter)?;
+        }
+    }
+    let conflicted_paths: Vec<_> = new_commits
+        .iter()
+        .flat_map(|(_, paths)| paths)
+        .unique()
+        .collect();
+    if !conflicted_paths.is_empty() {
+        writeln!(
+            ui.warning_default(),
+            "Some hunks couldn't be applied and were recorded as conflicts in these files:"
+        )?;
+        for path in conflicted_paths {
+            writeln!(
+                ui.warning_no_heading(),
+                "  {}",
+                tx.base_workspace_helper().format_file_path(path)
+            )?;
+        }
+    }
+    if !skipped.is_empty() {
+        writeln!(
+            ui.warning_default(),
+            "Some changes couldn't be applied and were skipped:"
+        )?;
+        for err in skipped {
+            writeln!(ui.warning_no_heading(), "  {err}")?;
+        }
+    }
+    tx.finish(ui, format!("apply patches from {}", args.paths.join(", ")))?;
+    Ok(())
+}
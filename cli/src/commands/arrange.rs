@@ -0,0 +1,567 @@
+// Copyright 2025 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::HashSet;
+use std::io::Write as _;
+use std::mem;
+use std::slice;
+
+use clap_complete::ArgValueCompleter;
+use itertools::Itertools as _;
+use jj_lib::backend::ChangeId;
+use jj_lib::backend::CommitId;
+use jj_lib::commit::Commit;
+use jj_lib::commit::CommitIteratorExt as _;
+use jj_lib::repo::Repo as _;
+use jj_lib::rewrite::CommitWithSelection;
+use jj_lib::rewrite::MoveCommitsLocation;
+use jj_lib::rewrite::MoveCommitsTarget;
+use jj_lib::rewrite::RebaseOptions;
+use jj_lib::rewrite::RebasedCommit;
+use jj_lib::rewrite::move_commits;
+use jj_lib::rewrite::squash_commits;
+use tracing::instrument;
+
+use crate::cli_util::CommandHelper;
+use crate::cli_util::RevisionArg;
+use crate::cli_util::WorkspaceCommandTransaction;
+use crate::cli_util::short_change_hash;
+use crate::command_error::CommandError;
+use crate::command_error::user_error_with_hint;
+use crate::complete;
+use crate::description_util::TextEditor;
+use crate::description_util::description_template;
+use crate::description_util::edit_description;
+use crate::description_util::try_combine_messages;
+use crate::ui::Ui;
+
+/// Arrange revisions by editing a list of them
+///
+/// The revisions are listed in an editor from the oldest to the newest, each on
+/// a line starting with an action and the change ID:
+///
+/// * `pick`: keep the revision on top of the previous line
+/// * `reword`: like `pick`, but edit the description
+/// * `edit`: like `pick`, and edit the revision in the working copy afterwards
+/// * `squash`: squash the revision into the revision on the previous line
+/// * `parallel`: keep the revision as a sibling of the previous line
+/// * `drop`: abandon the revision
+///
+/// Reordering the lines reorders the revisions. The edited list is applied in
+/// a single operation, and descendants of the revisions are rebased onto their
+/// new location. If the list can't be applied, it's opened in the editor again
+/// with the errors. Removing all lines aborts.
+///
+/// The revisions must all be based on the same parents, such as a stack of
+/// revisions on top of `trunk()`.
+#[derive(clap::Args, Clone, Debug)]
+#[command(verbatim_doc_comment)]
+pub(crate) struct ArrangeArgs {
+    /// The revisions to arrange
+    #[arg(
+        long,
+        short,
+        required = true,
+        value_name = "REVSETS",
+        add = ArgValueCompleter::new(complete::revset_expression_mutable),
+    )]
+    revisions: Vec<RevisionArg>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum ArrangeAction {
+    Pick,
+    Reword,
+    Edit,
+    Squash,
+    Parallel,
+    Drop,
+}
+
+impl ArrangeAction {
+    const ALL: [Self; 6] = [
+        Self::Pick,
+        Self::Reword,
+        Self::Edit,
+        Self::Squash,
+        Self::Parallel,
+        Self::Drop,
+    ];
+
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Pick => "pick",
+            Self::Reword => "reword",
+            Self::Edit => "edit",
+            Self::Squash => "squash",
+            Self::Parallel => "parallel",
+            Self::Drop => "drop",
+        }
+    }
+}
+
+/// Error in the edited plan, with the index of the line it applies to.
+type PlanError = (Option<usize>, String);
+
+const PLAN_HELP: &str = "\
+JJ: Arrange the revisions above, which are listed from the oldest to the newest.
+JJ: Each line starts with an action, followed by the change ID:
+JJ:   pick      Keep the revision on top of the previous line
+JJ:   reword    Like pick, but edit the description
+JJ:   edit      Like pick, and edit the revision in the working copy afterwards
+JJ:   squash    Squash the revision into the revision on the previous line
+JJ:   parallel  Keep the revision as a sibling of the previous line
+JJ:   drop      Abandon the revision
+JJ: Reorder the lines to reorder the revisions. Remove all lines to abort.
+";
+
+const ERROR_PREFIX: &str = "JJ: Error: ";
+
+#[instrument(skip_all)]
+pub(crate) fn cmd_arrange(
+    ui: &mut Ui,
+    command: &CommandHelper,
+    args: &ArrangeArgs,
+) -> Result<(), CommandError> {
+    let mut workspace_command = command.workspace_helper(ui)?;
+    let mut commits: Vec<Commit> = workspace_command
+        .parse_union_revsets(ui, &args.revisions)?
+        .evaluate_to_commits()?
+        .try_collect()?;
+    if commits.is_empty() {
+        writeln!(ui.status(), "No revisions to arrange.")?;
+        return Ok(());
+    }
+    // List the revisions with parents before children
+    commits.reverse();
+    workspace_command.check_rewritable(commits.iter().ids())?;
+
+    let change_ids = commits
+        .iter()
+        .map(|commit| commit.change_id().clone())
+        .collect_vec();
+    if let Some(change_id) = change_ids.iter().duplicates().next() {
+        return Err(user_error_with_hint(
+            format!(
+                "Cannot arrange the divergent change {}",
+                short_change_hash(change_id)
+            ),
+            "Use `jj abandon` to abandon one of the commits first.",
+        ));
+    }
+    let target_ids: HashSet<&CommitId> = commits.iter().ids().collect();
+    let external_parent_ids = |commit: &Commit| {
+        commit
+            .parent_ids()
+            .iter()
+            .filter(|id| !target_ids.contains(id))
+            .cloned()
+            .collect_vec()
+    };
+    let base_parent_ids = external_parent_ids(&commits[0]);
+    if commits.iter().any(|commit| {
+        let parent_ids = external_parent_ids(commit);
+        !parent_ids.is_empty() && parent_ids != base_parent_ids
+    }) {
+        return Err(user_error_with_hint(
+            "The revisions to arrange must all be based on the same parents",
+            "Include the revisions between them, for example with `jj arrange -r 'base..tip'`.",
+        ));
+    }
+
+    let text_editor = workspace_command.text_editor()?;
+    let mut content = format_initial_plan(&commits, &base_parent_ids);
+    let plan = loop {
+        let edited = text_editor
+            .edit_str(&content, Some(".jjarrange"))
+            .map_err(|err| err.with_name("arrangement"))?;
+        match parse_plan(&edited, &change_ids) {
+            Ok(plan) => break plan,
+            Err(errors) => content = annotate_plan(&edited, &errors),
+        }
+    };
+    let Some(plan) = plan else {
+        writeln!(ui.status(), "Nothing changed.")?;
+        return Ok(());
+    };
+
+    let mut tx = workspace_command.start_transaction();
+    apply_plan(ui, &mut tx, &text_editor, &commits, &base_parent_ids, &plan)?;
+    tx.finish(ui, format!("arrange {} commits", commits.len()))
+}
+
+/// Lists the revisions with actions which keep them where they are.
+fn format_initial_plan(commits: &[Commit], base_parent_ids: &[CommitId]) -> String {
+    let mut heads = base_parent_ids.to_vec();
+    let mut group_parent_ids = base_parent_ids.to_vec();
+    let mut content = String::new();
+    for commit in commits {
+        let action = if commit.parent_ids() == heads || commit.parent_ids() != group_parent_ids {
+            group_parent_ids = mem::replace(&mut heads, vec![commit.id().clone()]);
+            ArrangeAction::Pick
+        } else {
+            heads.push(commit.id().clone());
+            ArrangeAction::Parallel
+        };
+        let summary = commit.description().lines().next().unwrap_or_default();
+        let line = format!(
+            "{} {} {summary}",
+            action.as_str(),
+            short_change_hash(commit.change_id())
+        );
+        content.push_str(line.trim_end());
+        content.push('\n');
+    }
+    content.push('\n');
+    content.push_str(PLAN_HELP);
+    content
+}
+
+/// Parses the edited plan into the action for each revision, in order. Returns
+/// `None` if the plan is empty.
+fn parse_plan(
+    text: &str,
+    change_ids: &[ChangeId],
+) -> Result<Option<Vec<(ArrangeAction, usize)>>, Vec<PlanError>> {
+    let change_hexes = change_ids.iter().map(ChangeId::reverse_hex).collect_vec();
+    let mut steps = vec![];
+    let mut errors = vec![];
+    let mut listed = vec![false; change_ids.len()];
+    let mut can_squash = false;
+    let mut has_edit = false;
+    for (line_index, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with("JJ:") {
+            continue;
+        }
+        let mut error = |message: String| errors.push((Some(line_index), message));
+        let mut words = line.split_whitespace();
+        let word = words.next().unwrap();
+        let Some(prefix) = words.next() else {
+            error(format!("Expected a change ID after `{word}`"));
+            continue;
+        };
+        let index = match change_hexes
+            .iter()
+            .positions(|hex| hex.starts_with(prefix))
+            .collect_vec()
+            .as_slice()
+        {
+            [index] => *index,
+            [] => {
+                error(format!("`{prefix}` isn't one of the listed revisions"));
+                continue;
+            }
+            _ => {
+                error(format!("`{prefix}` matches more than one revision"));
+                continue;
+            }
+        };
+        if mem::replace(&mut listed[index], true) {
+            error(format!("`{prefix}` is listed more than once"));
+            continue;
+        }
+        let Some(action) = ArrangeAction::ALL
+            .into_iter()
+            .find(|action| action.as_str() == word)
+        else {
+            error(format!("Unknown action `{word}`"));
+            continue;
+        };
+        match action {
+            ArrangeAction::Squash if !can_squash => {
+                error("`squash` must follow a `pick`, `reword`, `edit`, or `squash` line".into());
+            }
+            ArrangeAction::Edit if has_edit => {
+                error("Only one revision can be marked with `edit`".into());
+            }
+            _ => {}
+        }
+        match action {
+            ArrangeAction::Pick | ArrangeAction::Reword | ArrangeAction::Edit => can_squash = true,
+            ArrangeAction::Parallel => can_squash = false,
+            ArrangeAction::Squash | ArrangeAction::Drop => {}
+        }
+        has_edit |= action == ArrangeAction::Edit;
+        steps.push((action, index));
+    }
+    if steps.is_empty() && errors.is_empty() {
+        return Ok(None);
+    }
+    for (change_id, _) in change_ids.iter().zip(listed).filter(|(_, listed)| !listed) {
+        errors.push((
+            None,
+            format!(
+                "{} is missing, use `drop` to abandon it",
+                short_change_hash(change_id)
+            ),
+        ));
+    }
+    if errors.is_empty() {
+        Ok(Some(steps))
+    } else {
+        Err(errors)
+    }
+}
+
+/// Adds the errors to the edited plan as comments, replacing the previous ones.
+fn annotate_plan(text: &str, errors: &[PlanError]) -> String {
+    let mut content = String::new();
+    for (_, message) in errors.iter().filter(|(line_index, _)| line_index.is_none()) {
+        content.push_str(ERROR_PREFIX);
+        content.push_str(message);
+        content.push('\n');
+    }
+    for (line_index, line) in text.lines().enumerate() {
+        if line.starts_with(ERROR_PREFIX) {
+            continue;
+        }
+        content.push_str(line);
+        content.push('\n');
+        for (_, message) in errors
+            .iter()
+            .filter(|(error_index, _)| *error_index == Some(line_index))
+        {
+            content.push_str(ERROR_PREFIX);
+            content.push_str(message);
+            content.push('\n');
+        }
+    }
+    content
+}
+
+/// Rewrites the revisions as described by the plan.
+fn apply_plan(
+    ui: &Ui,
+    tx: &mut WorkspaceCommandTransaction,
+    text_editor: &TextEditor,
+    commits: &[Commit],
+    base_parent_ids: &[CommitId],
+    plan: &[(ArrangeAction, usize)],
+) -> Result<(), CommandError> {
+    // The parents of the next revision, and the parents of the revisions which
+    // are siblings of the previous revision. These are the old IDs, which are
+    // mapped to the rewritten commits as they are written. Rebasing the
+    // descendants would forget that mapping, so it's left to the end of the
+    // transaction.
+    let mut heads = base_parent_ids.to_vec();
+    let mut group_parent_ids = base_parent_ids.to_vec();
+    let mut squash_destination_id: Option<CommitId> = None;
+    let mut to_reword = vec![];
+    let mut to_edit = None;
+    for &(action, index) in plan {
+        let commit_id = commits[index].id();
+        match action {
+            ArrangeAction::Pick
+            | ArrangeAction::Reword
+            | ArrangeAction::Edit
+            | ArrangeAction::Parallel => {
+                let parent_ids = if action == ArrangeAction::Parallel {
+                    &group_parent_ids
+                } else {
+                    &heads
+                };
+                // Move the revision together with its descendants, which
+                // includes the revisions listed later and not placed yet.
+                let loc = MoveCommitsLocation {
+                    new_parent_ids: tx.repo().new_parents(parent_ids),
+                    new_child_ids: vec![],
+                    target: MoveCommitsTarget::Roots(vec![
+                        rewritten_commit(tx, commit_id)?.id().clone(),
+                    ]),
+                };
+                move_commits(tx.repo_mut(), &loc, &RebaseOptions::default())?;
+                if action == ArrangeAction::Parallel {
+                    heads.push(commit_id.clone());
+                    squash_destination_id = None;
+                } else {
+                    group_parent_ids = mem::replace(&mut heads, vec![commit_id.clone()]);
+                    squash_destination_id = Some(commit_id.clone());
+                }
+                match action {
+                    ArrangeAction::Reword => to_reword.push(commit_id.clone()),
+                    ArrangeAction::Edit => to_edit = Some(commit_id.clone()),
+                    _ => {}
+                }
+            }
+            ArrangeAction::Squash => {
+                let destination_id = squash_destination_id
+                    .as_ref()
+                    .expect("squash should follow a revision to squash into");
+                let destination = rewritten_commit(tx, destination_id)?;
+                let commit = rewritten_commit(tx, commit_id)?;
+                let source = CommitWithSelection {
+                    selected_tree: commit.tree()?,
+                    parent_tree: commit.parent_tree(tx.repo())?,
+                    commit,
+                };
+                if let Some(squashed) =
+                    squash_commits(tx.repo_mut(), slice::from_ref(&source), &destination, false)?
+                {
+                    let description =
+                        try_combine_messages(&squashed.abandoned_commits, &destination)
+                            .unwrap_or_else(|| {
+                                format!(
+                                    "{}\n{}",
+                                    destination.description(),
+                                    source.commit.description()
+                                )
+                            });
+                    squashed
+                        .commit_builder
+                        .set_description(description)
+                        .write()?;
+                }
+            }
+            ArrangeAction::Drop => {
+                let commit = rewritten_commit(tx, commit_id)?;
+                tx.repo_mut().record_abandoned_commit(&commit);
+            }
+        }
+    }
+
+    for commit_id in to_reword {
+        let commit = rewritten_commit(tx, &commit_id)?;
+        let template = description_template(ui, tx, "", &commit)?;
+        let description = edit_description(text_editor, &template)?;
+        if description != commit.description() {
+            tx.repo_mut()
+                .rewrite_commit(&commit)
+                .set_description(description)
+                .write()?;
+        }
+    }
+
+    if let Some(commit_id) = to_edit {
+        // Rebase the descendants first so that the working copy isn't moved
+        // away from the edited revision.
+        let mut commit = rewritten_commit(tx, &commit_id)?;
+        tx.repo_mut().rebase_descendants_with_options(
+            &RebaseOptions::default(),
+            |old_commit, rebased_commit| {
+                if let RebasedCommit::Rewritten(new_commit) = rebased_commit {
+                    if old_commit.id() == commit.id() {
+                        commit = new_commit;
+                    }
+                }
+            },
+        )?;
+        tx.edit(&commit)?;
+    }
+    Ok(())
+}
+
+/// Returns the commit which `commit_id` has been rewritten into so far.
+fn rewritten_commit(
+    tx: &WorkspaceCommandTransaction,
+    commit_id: &CommitId,
+) -> Result<Commit, CommandError> {
+    let new_id = tx
+        .repo()
+        .new_parents(slice::from_ref(commit_id))
+        .into_iter()
+        .exactly_one()
+        .expect("arranged revision should be rewritten into a single commit");
+    Ok(tx.repo().store().get_commit(&new_id)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn change_ids() -> Vec<ChangeId> {
+        // These are "zzzz...", "yyyy...", and "yxxx..." in reverse hex
+        vec![
+            ChangeId::from_hex("00000000000000000000000000000000"),
+            ChangeId::from_hex("11111111111111111111111111111111"),
+            ChangeId::from_hex("12222222222222222222222222222222"),
+        ]
+    }
+
+    #[test]
+    fn test_parse_plan() {
+        let change_ids = change_ids();
+        let plan = parse_plan(
+            "pick yx first\nJJ: comment\n\n  drop zz\nsquash yyy second line\n",
+            &change_ids,
+        );
+        assert_eq!(
+            plan,
+            Ok(Some(vec![
+                (ArrangeAction::Pick, 2),
+                (ArrangeAction::Drop, 0),
+                (ArrangeAction::Squash, 1),
+            ]))
+        );
+        assert_eq!(parse_plan("JJ: comment\n\n", &change_ids), Ok(None));
+    }
+
+    #[test]
+    fn test_parse_plan_errors() {
+        let change_ids = change_ids();
+        let errors = parse_plan("squash zz\nfoo yx\npick y\npick ww\npick zz\n", &change_ids);
+        insta::assert_debug_snapshot!(errors, @r#"
+        Err(
+            [
+                (
+                    Some(
+                        0,
+                    ),
+                    "`squash` must follow a `pick`, `reword`, `edit`, or `squash` line",
+                ),
+                (
+                    Some(
+                        1,
+                    ),
+                    "Unknown action `foo`",
+                ),
+                (
+                    Some(
+                        2,
+                    ),
+                    "`y` matches more than one revision",
+                ),
+                (
+                    Some(
+                        3,
+                    ),
+                    "`ww` isn't one of the listed revisions",
+                ),
+                (
+                    Some(
+                        4,
+                    ),
+                    "`zz` is listed more than once",
+                ),
+                (
+                    None,
+                    "yyyyyyyyyyyy is missing, use `drop` to abandon it",
+                ),
+            ],
+        )
+        "#);
+    }
+
+    #[test]
+    fn test_annotate_plan() {
+        let errors = vec![(None, "global".to_owned()), (Some(2), "about b".to_owned())];
+        let text = "pick a\nJJ: Error: old\npick b\n";
+        insta::assert_snapshot!(annotate_plan(text, &errors), @r"
+        JJ: Error: global
+        pick a
+        pick b
+        JJ: Error: about b
+        ");
+    }
+}
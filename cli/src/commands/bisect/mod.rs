@@ -0,0 +1,221 @@
+// Copyright 2025 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+mod bad;
+mod good;
+mod reset;
+mod run;
+mod skip;
+mod start;
+
+use std::io::Write as _;
+
+use clap::Subcommand;
+use itertools::Itertools as _;
+use jj_lib::bisect::BISECT_TAG;
+use jj_lib::bisect::BisectMark;
+use jj_lib::bisect::BisectState;
+use jj_lib::bisect::BisectionResult;
+use jj_lib::bisect::NextStep;
+use jj_lib::bisect::load_bisect_state;
+use tracing::instrument;
+
+use self::bad::BisectBadArgs;
+use self::bad::cmd_bisect_bad;
+use self::good::BisectGoodArgs;
+use self::good::cmd_bisect_good;
+use self::reset::BisectResetArgs;
+use self::reset::cmd_bisect_reset;
+use self::run::BisectRunArgs;
+use self::run::cmd_bisect_run;
+use self::skip::BisectSkipArgs;
+use self::skip::cmd_bisect_skip;
+use self::start::BisectStartArgs;
+use self::start::cmd_bisect_start;
+use crate::cli_util::CommandHelper;
+use crate::cli_util::RevisionArg;
+use crate::cli_util::WorkspaceCommandHelper;
+use crate::cli_util::WorkspaceCommandTransaction;
+use crate::cli_util::short_commit_hash;
+use crate::command_error::CommandError;
+use crate::command_error::user_error_with_hint;
+use crate::ui::Ui;
+
+/// Find the revision which introduced a bug by bisection
+///
+/// Start with `jj bisect start --good <rev> --bad <rev>`. Each revision to
+/// test is then checked out as with `jj new`, and marked with `jj bisect good`,
+/// `jj bisect bad`, or `jj bisect skip`, until the first bad revision is found.
+/// `jj bisect run` can do the testing with a command instead.
+///
+/// The progress is recorded in the operation log, so `jj op log` shows the
+/// marked revisions and the bisection can be continued from any workspace.
+#[derive(Subcommand, Clone, Debug)]
+pub(crate) enum BisectCommand {
+    Bad(BisectBadArgs),
+    Good(BisectGoodArgs),
+    Reset(BisectResetArgs),
+    Run(BisectRunArgs),
+    Skip(BisectSkipArgs),
+    Start(BisectStartArgs),
+}
+
+#[instrument(skip_all)]
+pub(crate) fn cmd_bisect(
+    ui: &mut Ui,
+    command: &CommandHelper,
+    subcommand: &BisectCommand,
+) -> Result<(), CommandError> {
+    match subcommand {
+        BisectCommand::Bad(args) => cmd_bisect_bad(ui, command, args),
+        BisectCommand::Good(args) => cmd_bisect_good(ui, command, args),
+        BisectCommand::Reset(args) => cmd_bisect_reset(ui, command, args),
+        BisectCommand::Run(args) => cmd_bisect_run(ui, command, args),
+        BisectCommand::Skip(args) => cmd_bisect_skip(ui, command, args),
+        BisectCommand::Start(args) => cmd_bisect_start(ui, command, args),
+    }
+}
+
+/// Loads the state of the bisection in progress, failing if there's none.
+fn load_state(workspace_command: &WorkspaceCommandHelper) -> Result<BisectState, CommandError> {
+    load_bisect_state(workspace_command.repo().operation())?.ok_or_else(|| {
+        user_error_with_hint(
+            "No bisection in progress",
+            "Start one with `jj bisect start --good <rev> --bad <rev>`.",
+        )
+    })
+}
+
+/// Marks `revisions`, or the revision being tested if empty, and checks out the
+/// next revision to test.
+fn mark_revisions(
+    ui: &mut Ui,
+    command: &CommandHelper,
+    revisions: &[RevisionArg],
+    mark: BisectMark,
+) -> Result<(), CommandError> {
+    let mut workspace_command = command.workspace_helper(ui)?;
+    let mut state = load_state(&workspace_command)?;
+    let commit_ids = if revisions.is_empty() {
+        let current = state.current.clone().ok_or_else(|| {
+            user_error_with_hint(
+                "No revision is being tested",
+                "Specify the revisions to mark.",
+            )
+        })?;
+        vec![current]
+    } else {
+        workspace_command
+            .parse_union_revsets(ui, revisions)?
+            .evaluate_to_commit_ids()?
+            .try_collect()?
+    };
+    let description = format!(
+        "bisect: mark {} as {}",
+        commit_ids.iter().map(short_commit_hash).join(", "),
+        mark.as_str()
+    );
+    for commit_id in commit_ids {
+        state.mark(commit_id, mark);
+    }
+    advance(ui, &mut workspace_command, state, description)?;
+    Ok(())
+}
+
+/// Records `state` and checks out the next revision to test, if any.
+fn advance(
+    ui: &Ui,
+    workspace_command: &mut WorkspaceCommandHelper,
+    mut state: BisectState,
+    description: String,
+) -> Result<NextStep, CommandError> {
+    let step = state.next_step(workspace_command.repo().as_ref())?;
+    let mut tx = workspace_command.start_transaction();
+    match &step {
+        NextStep::Evaluate { commit, .. } => {
+            tx.check_out(commit)?;
+            state.current = Some(commit.id().clone());
+        }
+        NextStep::Done(_) => {
+            state.current = None;
+        }
+    }
+    tx.set_tag(BISECT_TAG.to_owned(), state.to_tag_value());
+    finish_bisect_transaction(ui, tx, description)?;
+    print_step(ui, workspace_command, &step)?;
+    Ok(step)
+}
+
+/// Finishes `tx`, recording the operation even if only the bisection state
+/// changed.
+fn finish_bisect_transaction(
+    ui: &Ui,
+    tx: WorkspaceCommandTransaction<'_>,
+    description: impl Into<String>,
+) -> Result<(), CommandError> {
+    if tx.repo().has_changes() {
+        tx.finish(ui, description)
+    } else {
+        tx.into_inner().commit(description)?;
+        Ok(())
+    }
+}
+
+fn print_step(
+    ui: &Ui,
+    workspace_command: &WorkspaceCommandHelper,
+    step: &NextStep,
+) -> Result<(), CommandError> {
+    let Some(mut formatter) = ui.status_formatter() else {
+        return Ok(());
+    };
+    let result = match step {
+        NextStep::Evaluate { commit, remaining } => {
+            writeln!(formatter, "Bisecting: {remaining} revisions left to test")?;
+            write!(formatter, "Now evaluating: ")?;
+            workspace_command.write_commit_summary(formatter.as_mut(), commit)?;
+            writeln!(formatter)?;
+            return Ok(());
+        }
+        NextStep::Done(result) => result,
+    };
+    match result {
+        BisectionResult::Found(commits) if commits.len() == 1 => {
+            write!(formatter, "The first bad revision is: ")?;
+            workspace_command.write_commit_summary(formatter.as_mut(), &commits[0])?;
+            writeln!(formatter)?;
+        }
+        BisectionResult::Found(commits) | BisectionResult::Indeterminate(commits) => {
+            if matches!(result, BisectionResult::Found(_)) {
+                writeln!(formatter, "The first bad revision is one of:")?;
+            } else {
+                writeln!(
+                    formatter,
+                    "Some revisions were skipped, so the first bad revision is one of:"
+                )?;
+            }
+            for commit in commits {
+                write!(formatter, "  ")?;
+                workspace_command.write_commit_summary(formatter.as_mut(), commit)?;
+                writeln!(formatter)?;
+            }
+        }
+    }
+    drop(formatter);
+    writeln!(
+        ui.hint_default(),
+        "Run `jj bisect reset` to end the bisection."
+    )?;
+    Ok(())
+}
@@ -0,0 +1,48 @@
+// Copyright 2025 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use jj_lib::bisect::BISECT_TAG;
+use jj_lib::repo::Repo as _;
+use tracing::instrument;
+
+use super::finish_bisect_transaction;
+use super::load_state;
+use crate::cli_util::CommandHelper;
+use crate::command_error::CommandError;
+use crate::ui::Ui;
+
+/// End the bisection, and edit the working-copy revision it was started from
+#[derive(clap::Args, Clone, Debug)]
+pub(crate) struct BisectResetArgs {}
+
+#[instrument(skip_all)]
+pub(crate) fn cmd_bisect_reset(
+    ui: &mut Ui,
+    command: &CommandHelper,
+    _args: &BisectResetArgs,
+) -> Result<(), CommandError> {
+    let mut workspace_command = command.workspace_helper(ui)?;
+    let state = load_state(&workspace_command)?;
+    let original = state
+        .original
+        .map(|id| workspace_command.repo().store().get_commit(&id))
+        .transpose()?;
+    let mut tx = workspace_command.start_transaction();
+    if let Some(commit) = original {
+        tx.edit(&commit)?;
+    }
+    tx.set_tag(BISECT_TAG.to_owned(), String::new());
+    finish_bisect_transaction(ui, tx, "bisect: reset")?;
+    Ok(())
+}
@@ -0,0 +1,102 @@
+// Copyright 2025 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::io::Write as _;
+use std::process::Command;
+
+use jj_lib::bisect::BisectMark;
+use jj_lib::bisect::NextStep;
+use tracing::instrument;
+
+use super::advance;
+use super::load_state;
+use super::print_step;
+use crate::cli_util::CommandHelper;
+use crate::cli_util::short_commit_hash;
+use crate::command_error::CommandError;
+use crate::command_error::user_error;
+use crate::command_error::user_error_with_message;
+use crate::ui::Ui;
+
+/// Test the remaining revisions with a command
+///
+/// The command is run in the workspace root with each revision checked out.
+/// Exit status 0 marks the revision as good, 125 skips it, and any other
+/// status up to 127 marks it as bad. The bisection stops if the command exits
+/// with a higher status or is killed by a signal.
+///
+/// For example, `jj bisect run -- cargo test -p foo` finds the revision which
+/// broke the tests of `foo`.
+#[derive(clap::Args, Clone, Debug)]
+pub(crate) struct BisectRunArgs {
+    /// The command to run, followed by its arguments
+    #[arg(
+        required = true,
+        trailing_var_arg = true,
+        allow_hyphen_values = true,
+        value_name = "COMMAND",
+        value_hint = clap::ValueHint::CommandWithArguments,
+    )]
+    command: Vec<String>,
+}
+
+#[instrument(skip_all)]
+pub(crate) fn cmd_bisect_run(
+    ui: &mut Ui,
+    command: &CommandHelper,
+    args: &BisectRunArgs,
+) -> Result<(), CommandError> {
+    let (program, program_args) = args.command.split_first().unwrap();
+    loop {
+        let mut workspace_command = command.workspace_helper(ui)?;
+        let mut state = load_state(&workspace_command)?;
+        let Some(commit_id) = state.current.clone() else {
+            // The bisection was already finished.
+            let step = state.next_step(workspace_command.repo().as_ref())?;
+            print_step(ui, &workspace_command, &step)?;
+            return Ok(());
+        };
+        writeln!(
+            ui.status(),
+            "Running `{}` on {}",
+            args.command.join(" "),
+            short_commit_hash(&commit_id)
+        )?;
+        let status = Command::new(program)
+            .args(program_args)
+            .current_dir(workspace_command.workspace_root())
+            .status()
+            .map_err(|err| user_error_with_message(format!("Failed to run {program}"), err))?;
+        let mark = match status.code() {
+            Some(0) => BisectMark::Good,
+            Some(125) => BisectMark::Skip,
+            Some(1..=127) => BisectMark::Bad,
+            _ => {
+                return Err(user_error(format!(
+                    "Stopping the bisection because the command failed: {status}"
+                )));
+            }
+        };
+        let description = format!(
+            "bisect: mark {} as {}",
+            short_commit_hash(&commit_id),
+            mark.as_str()
+        );
+        state.mark(commit_id, mark);
+        let step = advance(ui, &mut workspace_command, state, description)?;
+        if matches!(step, NextStep::Done(_)) {
+            return Ok(());
+        }
+    }
+}
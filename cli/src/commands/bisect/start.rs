@@ -0,0 +1,93 @@
+// Copyright 2025 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use clap_complete::ArgValueCompleter;
+use itertools::Itertools as _;
+use jj_lib::bisect::BisectState;
+use jj_lib::bisect::load_bisect_state;
+use tracing::instrument;
+
+use super::advance;
+use crate::cli_util::CommandHelper;
+use crate::cli_util::RevisionArg;
+use crate::command_error::CommandError;
+use crate::command_error::user_error;
+use crate::command_error::user_error_with_hint;
+use crate::complete;
+use crate::ui::Ui;
+
+/// Start a bisection
+///
+/// The bug must be absent from the good revisions and present in the bad
+/// revisions. The revision halfway between them is checked out to be tested.
+#[derive(clap::Args, Clone, Debug)]
+pub(crate) struct BisectStartArgs {
+    /// Revisions without the bug
+    #[arg(
+        long,
+        short,
+        required = true,
+        value_name = "REVSETS",
+        add = ArgValueCompleter::new(complete::revset_expression_all),
+    )]
+    good: Vec<RevisionArg>,
+    /// Revisions with the bug
+    #[arg(
+        long,
+        short,
+        required = true,
+        value_name = "REVSETS",
+        add = ArgValueCompleter::new(complete::revset_expression_all),
+    )]
+    bad: Vec<RevisionArg>,
+}
+
+#[instrument(skip_all)]
+pub(crate) fn cmd_bisect_start(
+    ui: &mut Ui,
+    command: &CommandHelper,
+    args: &BisectStartArgs,
+) -> Result<(), CommandError> {
+    let mut workspace_command = command.workspace_helper(ui)?;
+    if load_bisect_state(workspace_command.repo().operation())?.is_some() {
+        return Err(user_error_with_hint(
+            "A bisection is already in progress",
+            "Run `jj bisect reset` to end it first.",
+        ));
+    }
+    let good = workspace_command
+        .parse_union_revsets(ui, &args.good)?
+        .evaluate_to_commit_ids()?
+        .try_collect()?;
+    let bad: Vec<_> = workspace_command
+        .parse_union_revsets(ui, &args.bad)?
+        .evaluate_to_commit_ids()?
+        .try_collect()?;
+    if bad.is_empty() {
+        return Err(user_error("No bad revisions were given"));
+    }
+    let state = BisectState {
+        good,
+        bad,
+        original: workspace_command.get_wc_commit_id().cloned(),
+        ..BisectState::default()
+    };
+    advance(
+        ui,
+        &mut workspace_command,
+        state,
+        "bisect: start".to_owned(),
+    )?;
+    Ok(())
+}
@@ -104,11 +104,11 @@ pub(crate) fn cmd_commit(
     let matcher = workspace_command
         .parse_file_patterns(ui, &args.paths)?
         .to_matcher();
-    let advanceable_bookmarks = workspace_command.get_advanceable_bookmarks(commit.parent_ids())?;
     let diff_selector =
         workspace_command.diff_selector(ui, args.tool.as_deref(), args.interactive)?;
     let text_editor = workspace_command.text_editor()?;
     let mut tx = workspace_command.start_transaction();
+    tx.advance_bookmarks()?;
     let base_tree = commit.parent_tree(tx.repo())?;
     let format_instructions = || {
         format!(
@@ -190,9 +190,6 @@ new working-copy commit.
             .new_commit(vec![new_commit.id().clone()], commit.tree_id().clone())
             .write()?;
 
-        // Does nothing if there's no bookmarks to advance.
-        tx.advance_bookmarks(advanceable_bookmarks, new_commit.id());
-
         for name in workspace_names {
             tx.repo_mut().edit(name, &new_wc_commit).unwrap();
         }
@@ -0,0 +1,69 @@
+// Copyright 2025 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::fmt::Debug;
+
+use crate::cli_util::CommandHelper;
+use crate::command_error::CommandError;
+use crate::command_error::user_error;
+#[cfg(all(target_os = "linux", feature = "fuse"))]
+use crate::command_error::user_error_with_message;
+use crate::ui::Ui;
+
+/// Mount a FUSE working copy and serve its files in the foreground
+///
+/// The workspace must have been created with `jj workspace add
+/// --working-copy-type=fuse`. Unmount it with `fusermount -u` to stop.
+#[derive(clap::Args, Clone, Debug)]
+pub struct DebugFuseMountArgs {}
+
+#[cfg(all(target_os = "linux", feature = "fuse"))]
+pub fn cmd_debug_fuse_mount(
+    _ui: &mut Ui,
+    command: &CommandHelper,
+    _args: &DebugFuseMountArgs,
+) -> Result<(), CommandError> {
+    use jj_lib::fuse_working_copy::FuseWorkingCopy;
+    use jj_lib::fuse_working_copy::daemon;
+
+    // Snapshotting isn't needed since the daemon serves the current state.
+    let workspace = command.load_workspace()?;
+    let Some(wc) = workspace
+        .working_copy()
+        .as_any()
+        .downcast_ref::<FuseWorkingCopy>()
+    else {
+        return Err(user_error("This command requires a FUSE working copy"));
+    };
+    daemon::run_daemon(
+        workspace.repo_loader().store().clone(),
+        workspace.workspace_root(),
+        wc.state_path(),
+        command.settings().get("ui.conflict-marker-style")?,
+    )
+    .map_err(|err| user_error_with_message("Failed to serve the FUSE working copy", err))?;
+    Ok(())
+}
+
+#[cfg(not(all(target_os = "linux", feature = "fuse")))]
+pub fn cmd_debug_fuse_mount(
+    _ui: &mut Ui,
+    _command: &CommandHelper,
+    _args: &DebugFuseMountArgs,
+) -> Result<(), CommandError> {
+    Err(user_error(
+        "Cannot mount a FUSE working copy because jj was not compiled with the `fuse` feature \
+         or isn't running on Linux",
+    ))
+}
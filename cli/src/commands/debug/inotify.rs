@@ -0,0 +1,196 @@
+// Copyright 2025 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::fmt::Debug;
+#[cfg(all(target_os = "linux", feature = "inotify"))]
+use std::io::Write as _;
+use std::path::PathBuf;
+
+use clap::Subcommand;
+#[cfg(all(target_os = "linux", feature = "inotify"))]
+use jj_lib::fsmonitor::FsmonitorSettings;
+#[cfg(all(target_os = "linux", feature = "inotify"))]
+use jj_lib::fsmonitor::InotifyConfig;
+
+#[cfg(all(target_os = "linux", feature = "inotify"))]
+use super::check_local_disk_wc;
+use crate::cli_util::CommandHelper;
+use crate::command_error::CommandError;
+use crate::command_error::user_error;
+#[cfg(all(target_os = "linux", feature = "inotify"))]
+use crate::command_error::user_error_with_message;
+use crate::ui::Ui;
+
+#[derive(Subcommand, Clone, Debug)]
+pub enum DebugInotifyCommand {
+    /// Check whether the inotify filesystem monitor is enabled and whether its
+    /// daemon is running
+    Status,
+    QueryCursor,
+    QueryChangedFiles,
+    ResetCursor,
+    /// Stop the daemon watching the current workspace
+    Stop,
+    /// Run the daemon watching a working copy in the foreground
+    Daemon(DebugInotifyDaemonArgs),
+}
+
+#[derive(clap::Args, Clone, Debug)]
+#[cfg_attr(not(all(target_os = "linux", feature = "inotify")), allow(dead_code))]
+pub struct DebugInotifyDaemonArgs {
+    /// Root directory of the working copy to watch
+    working_copy_path: PathBuf,
+    /// Working-copy state directory to create the socket in
+    state_path: PathBuf,
+    /// Maximum number of changed paths to remember
+    #[arg(long, default_value_t = 100_000)]
+    max_tracked_paths: usize,
+}
+
+#[cfg(all(target_os = "linux", feature = "inotify"))]
+pub fn cmd_debug_inotify(
+    ui: &mut Ui,
+    command: &CommandHelper,
+    subcommand: &DebugInotifyCommand,
+) -> Result<(), CommandError> {
+    use jj_lib::fsmonitor::inotify;
+    use jj_lib::local_working_copy::LockedLocalWorkingCopy;
+
+    if let DebugInotifyCommand::Daemon(args) = subcommand {
+        inotify::run_daemon(
+            &args.working_copy_path,
+            &args.state_path,
+            args.max_tracked_paths,
+        )
+        .map_err(|err| user_error_with_message("Failed to run the inotify daemon", err))?;
+        return Ok(());
+    }
+
+    // Snapshotting would consume the changes reported by the daemon.
+    let mut workspace_command = command.workspace_helper_no_snapshot(ui)?;
+    let repo = workspace_command.repo().clone();
+    let inotify_config = match workspace_command.settings().fsmonitor_settings()? {
+        FsmonitorSettings::Inotify(config) => config,
+        _ => InotifyConfig {
+            spawn_daemon: false,
+            daemon_command: vec![],
+            max_tracked_paths: workspace_command
+                .settings()
+                .get("fsmonitor.inotify.max-tracked-paths")?,
+        },
+    };
+    match subcommand {
+        DebugInotifyCommand::Status => {
+            match workspace_command.settings().fsmonitor_settings()? {
+                FsmonitorSettings::Inotify(config) => {
+                    writeln!(ui.stdout(), "inotify is enabled via `fsmonitor.backend`.")?;
+                    writeln!(
+                        ui.stdout(),
+                        "The daemon is {} automatically. Use `fsmonitor.inotify.spawn-daemon` \
+                         to control it.",
+                        if config.spawn_daemon {
+                            "started"
+                        } else {
+                            "not started"
+                        }
+                    )?;
+                }
+                _ => {
+                    writeln!(
+                        ui.stdout(),
+                        r#"inotify is disabled. Set `fsmonitor.backend="inotify"` to enable."#
+                    )?;
+                }
+            }
+            let wc = check_local_disk_wc(workspace_command.working_copy().as_any())?;
+            let status = match wc.inotify_fsmonitor(&inotify_config) {
+                Ok(fsmonitor) => fsmonitor.status().ok(),
+                Err(_) => None,
+            };
+            if let Some(status) = status {
+                writeln!(ui.stdout(), "The daemon is running.")?;
+                writeln!(ui.stdout(), "Cursor: {:?}", status.cursor)?;
+                writeln!(
+                    ui.stdout(),
+                    "Watched directories: {}",
+                    status.watched_directories
+                )?;
+                writeln!(
+                    ui.stdout(),
+                    "Tracked changed paths: {}",
+                    status.tracked_paths
+                )?;
+            } else {
+                writeln!(ui.stdout(), "The daemon is not running.")?;
+            }
+        }
+        DebugInotifyCommand::QueryCursor => {
+            let wc = check_local_disk_wc(workspace_command.working_copy().as_any())?;
+            let (cursor, _changed_files) = wc.query_inotify(&inotify_config)?;
+            writeln!(ui.stdout(), "Cursor: {cursor:?}")?;
+        }
+        DebugInotifyCommand::QueryChangedFiles => {
+            let wc = check_local_disk_wc(workspace_command.working_copy().as_any())?;
+            let (_cursor, changed_files) = wc.query_inotify(&inotify_config)?;
+            writeln!(ui.stdout(), "Changed files: {changed_files:?}")?;
+        }
+        DebugInotifyCommand::ResetCursor => {
+            let (mut locked_ws, _commit) = workspace_command.start_working_copy_mutation()?;
+            let Some(locked_local_wc): Option<&mut LockedLocalWorkingCopy> =
+                locked_ws.locked_wc().as_any_mut().downcast_mut()
+            else {
+                return Err(user_error(
+                    "This command requires a standard local-disk working copy",
+                ));
+            };
+            locked_local_wc.reset_inotify()?;
+            locked_ws.finish(repo.op_id().clone())?;
+            writeln!(ui.status(), "Reset inotify cursor")?;
+        }
+        DebugInotifyCommand::Stop => {
+            let wc = check_local_disk_wc(workspace_command.working_copy().as_any())?;
+            let stopped = match wc.inotify_fsmonitor(&InotifyConfig {
+                spawn_daemon: false,
+                ..inotify_config
+            }) {
+                Ok(fsmonitor) => {
+                    fsmonitor.stop().map_err(|err| {
+                        user_error_with_message("Failed to stop the inotify daemon", err)
+                    })?;
+                    true
+                }
+                Err(_) => false,
+            };
+            if stopped {
+                writeln!(ui.status(), "Stopped the inotify daemon")?;
+            } else {
+                writeln!(ui.status(), "The inotify daemon is not running")?;
+            }
+        }
+        DebugInotifyCommand::Daemon(_) => unreachable!(),
+    }
+    Ok(())
+}
+
+#[cfg(not(all(target_os = "linux", feature = "inotify")))]
+pub fn cmd_debug_inotify(
+    _ui: &mut Ui,
+    _command: &CommandHelper,
+    _subcommand: &DebugInotifyCommand,
+) -> Result<(), CommandError> {
+    Err(user_error(
+        "Cannot use the inotify filesystem monitor because jj was not compiled with the \
+         `inotify` feature or isn't running on Linux",
+    ))
+}
@@ -14,8 +14,10 @@
 
 mod copy_detection;
 mod fileset;
+mod fuse;
 mod index;
 mod init_simple;
+mod inotify;
 mod local_working_copy;
 mod operation;
 mod reindex;
@@ -36,10 +38,14 @@ use self::copy_detection::CopyDetectionArgs;
 use self::copy_detection::cmd_debug_copy_detection;
 use self::fileset::DebugFilesetArgs;
 use self::fileset::cmd_debug_fileset;
+use self::fuse::DebugFuseMountArgs;
+use self::fuse::cmd_debug_fuse_mount;
 use self::index::DebugIndexArgs;
 use self::index::cmd_debug_index;
 use self::init_simple::DebugInitSimpleArgs;
 use self::init_simple::cmd_debug_init_simple;
+use self::inotify::DebugInotifyCommand;
+use self::inotify::cmd_debug_inotify;
 use self::local_working_copy::DebugLocalWorkingCopyArgs;
 use self::local_working_copy::cmd_debug_local_working_copy;
 use self::operation::DebugOperationArgs;
@@ -69,8 +75,11 @@ use crate::ui::Ui;
 pub enum DebugCommand {
     CopyDetection(CopyDetectionArgs),
     Fileset(DebugFilesetArgs),
+    FuseMount(DebugFuseMountArgs),
     Index(DebugIndexArgs),
     InitSimple(DebugInitSimpleArgs),
+    #[command(subcommand)]
+    Inotify(DebugInotifyCommand),
     LocalWorkingCopy(DebugLocalWorkingCopyArgs),
     #[command(visible_alias = "view")]
     Operation(DebugOperationArgs),
@@ -92,8 +101,10 @@ pub fn cmd_debug(
     match subcommand {
         DebugCommand::CopyDetection(args) => cmd_debug_copy_detection(ui, command, args),
         DebugCommand::Fileset(args) => cmd_debug_fileset(ui, command, args),
+        DebugCommand::FuseMount(args) => cmd_debug_fuse_mount(ui, command, args),
         DebugCommand::Index(args) => cmd_debug_index(ui, command, args),
         DebugCommand::InitSimple(args) => cmd_debug_init_simple(ui, command, args),
+        DebugCommand::Inotify(args) => cmd_debug_inotify(ui, command, args),
         DebugCommand::LocalWorkingCopy(args) => cmd_debug_local_working_copy(ui, command, args),
         DebugCommand::Operation(args) => cmd_debug_operation(ui, command, args),
         DebugCommand::Reindex(args) => cmd_debug_reindex(ui, command, args),
@@ -0,0 +1,366 @@
+// Copyright 2025 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::borrow::Cow;
+use std::fmt::Write as _;
+use std::fs;
+use std::io::Write as _;
+
+use clap_complete::ArgValueCompleter;
+use itertools::Itertools as _;
+use jj_lib::backend::Signature;
+use jj_lib::commit::Commit;
+use jj_lib::copies::CopyRecords;
+use jj_lib::matchers::EverythingMatcher;
+use jj_lib::merged_tree::MergedTree;
+use jj_lib::object_id::ObjectId as _;
+use jj_lib::repo::Repo as _;
+use jj_lib::repo_path::RepoPathUiConverter;
+use pollster::FutureExt as _;
+use tracing::instrument;
+
+use crate::cli_util::CommandHelper;
+use crate::cli_util::RevisionArg;
+use crate::cli_util::WorkspaceCommandHelper;
+use crate::cli_util::short_commit_hash;
+use crate::command_error::CommandError;
+use crate::command_error::user_error;
+use crate::command_error::user_error_with_message;
+use crate::complete;
+use crate::diff_util::DiffStatOptions;
+use crate::diff_util::DiffStats;
+use crate::diff_util::UnifiedDiffOptions;
+use crate::diff_util::get_copy_records;
+use crate::diff_util::show_diff_stats;
+use crate::diff_util::show_git_diff;
+use crate::formatter::PlainTextFormatter;
+use crate::ui::Ui;
+
+/// Width of the diffstat, including the leading space
+const DIFF_STAT_WIDTH: usize = 72;
+
+/// Write revisions as patch mails
+///
+/// A mail is written for each revision, oldest first, in the format of `git
+/// format-patch`. The mails can be applied with `git am` or `jj apply`, or
+/// sent with `git send-email`.
+///
+/// The files are named after the position of the revision in the series and
+/// its subject, such as `0001-fix-the-frobnicator.patch`. The revisions must
+/// not be merges.
+#[derive(clap::Args, Clone, Debug)]
+pub(crate) struct ExportPatchArgs {
+    /// The revision(s) to export (default: @)
+    #[arg(
+        long,
+        short,
+        value_name = "REVSETS",
+        add = ArgValueCompleter::new(complete::revset_expression_all),
+    )]
+    revisions: Vec<RevisionArg>,
+    /// The directory to write the patch files to
+    #[arg(
+        long,
+        short,
+        default_value = ".",
+        value_hint = clap::ValueHint::DirPath,
+        conflicts_with = "stdout",
+    )]
+    output_directory: String,
+    /// Write the mails to standard output as a single mailbox instead
+    #[arg(long)]
+    stdout: bool,
+    /// Also write a cover letter introducing the series
+    ///
+    /// The cover letter lists the revisions using the
+    /// `templates.export_patch_cover_letter` template, followed by the
+    /// diffstat of the whole series. Its subject and blurb are placeholders to
+    /// be filled in before sending.
+    #[arg(long)]
+    cover_letter: bool,
+}
+
+#[instrument(skip_all)]
+pub(crate) fn cmd_export_patch(
+    ui: &mut Ui,
+    command: &CommandHelper,
+    args: &ExportPatchArgs,
+) -> Result<(), CommandError> {
+    let workspace_command = command.workspace_helper(ui)?;
+    let mut commits: Vec<Commit> = if args.revisions.is_empty() {
+        workspace_command.parse_revset(ui, &RevisionArg::AT)?
+    } else {
+        workspace_command.parse_union_revsets(ui, &args.revisions)?
+    }
+    .evaluate_to_commits()?
+    .try_collect()?;
+    if commits.is_empty() {
+        writeln!(ui.status(), "No revisions to export.")?;
+        return Ok(());
+    }
+    commits.reverse();
+    for commit in &commits {
+        if commit.parent_ids().len() != 1 {
+            return Err(user_error(format!(
+                "Cannot export revision {} because it doesn't have exactly one parent",
+                short_commit_hash(commit.id())
+            )));
+        }
+    }
+
+    let mut mails = vec![];
+    let total = commits.len();
+    let numbered = total > 1 || args.cover_letter;
+    if args.cover_letter {
+        let subject = format!("[PATCH 0/{total}] *** SUBJECT HERE ***");
+        let mut body = "*** BLURB HERE ***\n\n".to_owned();
+        let template_text = command
+            .settings()
+            .get_string("templates.export_patch_cover_letter")?;
+        let template = workspace_command.parse_commit_template(ui, &template_text)?;
+        for commit in &commits {
+            body.push_str(&String::from_utf8_lossy(
+                &template.format_plain_text(commit),
+            ));
+        }
+        body.push('\n');
+        let from_tree = commits[0].parent_tree(workspace_command.repo().as_ref())?;
+        let to_tree = commits[total - 1].tree()?;
+        let mut mail = vec![];
+        write_mail_headers(
+            &mut mail,
+            &"0".repeat(40),
+            &command.settings().signature(),
+            &subject,
+        )?;
+        mail.extend_from_slice(body.as_bytes());
+        write_diff_stats(
+            &mut mail,
+            &workspace_command,
+            &from_tree,
+            &to_tree,
+            &CopyRecords::default(),
+        )?;
+        mail.push(b'\n');
+        mails.push(("0000-cover-letter.patch".to_owned(), mail));
+    }
+    for (index, commit) in commits.iter().enumerate() {
+        let number = index + 1;
+        let (first_line, body) = split_description(commit.description());
+        let subject = if numbered {
+            format!("[PATCH {number}/{total}] {first_line}")
+        } else {
+            format!("[PATCH] {first_line}")
+        };
+        let mut mail = vec![];
+        write_mail_headers(&mut mail, &commit.id().hex(), commit.author(), &subject)?;
+        if !body.is_empty() {
+            mail.extend_from_slice(body.as_bytes());
+            mail.push(b'\n');
+        }
+        write_commit_patch(&mut mail, &workspace_command, commit)?;
+        mail.push(b'\n');
+        let file_name = format!("{number:04}-{}.patch", sanitize_subject(first_line));
+        mails.push((file_name, mail));
+    }
+
+    if args.stdout {
+        ui.request_pager();
+        let mut formatter = ui.stdout_formatter();
+        for (_, mail) in &mails {
+            formatter.write_all(mail)?;
+        }
+        return Ok(());
+    }
+    let output_dir = command.cwd().join(&args.output_directory);
+    fs::create_dir_all(&output_dir).map_err(|err| {
+        user_error_with_message(
+            format!("Failed to create directory {}", args.output_directory),
+            err,
+        )
+    })?;
+    for (file_name, mail) in &mails {
+        let path = output_dir.join(file_name);
+        fs::write(&path, mail).map_err(|err| {
+            user_error_with_message(format!("Failed to write {}", path.display()), err)
+        })?;
+    }
+    if let Some(mut formatter) = ui.status_formatter() {
+        writeln!(formatter, "Wrote {} patch files:", mails.len())?;
+        for (file_name, _) in &mails {
+            let path = output_dir.join(file_name);
+            let path = path.strip_prefix(command.cwd()).unwrap_or(&path);
+            writeln!(formatter, "  {}", path.display())?;
+        }
+    }
+    Ok(())
+}
+
+/// Splits a description into the subject line and the rest of the message.
+fn split_description(description: &str) -> (&str, &str) {
+    let (first_line, rest) = description.split_once('\n').unwrap_or((description, ""));
+    (first_line.trim(), rest.trim_matches('\n'))
+}
+
+fn write_mail_headers(
+    mail: &mut Vec<u8>,
+    commit_hex: &str,
+    author: &Signature,
+    subject: &str,
+) -> Result<(), CommandError> {
+    let date = author
+        .timestamp
+        .to_datetime()
+        .map_err(|err| user_error_with_message("Cannot format the author date", err))?;
+    // The date in the separator line is fixed, so that it can be recognized
+    // as coming from `git format-patch`.
+    writeln!(mail, "From {commit_hex} Mon Sep 17 00:00:00 2001")?;
+    writeln!(
+        mail,
+        "From: {} <{}>",
+        format_display_name(&author.name),
+        author.email
+    )?;
+    writeln!(mail, "Date: {}", date.to_rfc2822())?;
+    writeln!(mail, "Subject: {}", encode_header_value(subject))?;
+    writeln!(mail, "MIME-Version: 1.0")?;
+    writeln!(mail, "Content-Type: text/plain; charset=UTF-8")?;
+    writeln!(mail, "Content-Transfer-Encoding: 8bit")?;
+    writeln!(mail)?;
+    Ok(())
+}
+
+/// Writes the diffstat and the diff of the commit compared to its parent.
+fn write_commit_patch(
+    mail: &mut Vec<u8>,
+    workspace_command: &WorkspaceCommandHelper,
+    commit: &Commit,
+) -> Result<(), CommandError> {
+    let repo = workspace_command.repo();
+    let parent_id = &commit.parent_ids()[0];
+    let from_tree = commit.parent_tree(repo.as_ref())?;
+    let to_tree = commit.tree()?;
+    let mut copy_records = CopyRecords::default();
+    let records = get_copy_records(repo.store(), parent_id, commit.id(), &EverythingMatcher)?;
+    copy_records.add_records(records)?;
+
+    mail.extend_from_slice(b"---\n");
+    write_diff_stats(mail, workspace_command, &from_tree, &to_tree, &copy_records)?;
+    mail.push(b'\n');
+    let mut options = UnifiedDiffOptions::from_settings(workspace_command.settings())?;
+    options.binary = true;
+    let tree_diff = from_tree.diff_stream_with_copies(&to_tree, &EverythingMatcher, &copy_records);
+    let mut formatter = PlainTextFormatter::new(&mut *mail);
+    show_git_diff(
+        &mut formatter,
+        repo.store(),
+        tree_diff,
+        &options,
+        workspace_command.env().conflict_marker_style(),
+    )
+    .block_on()?;
+    Ok(())
+}
+
+/// Writes the diffstat indented by a space, with paths relative to the
+/// workspace root.
+fn write_diff_stats(
+    mail: &mut Vec<u8>,
+    workspace_command: &WorkspaceCommandHelper,
+    from_tree: &MergedTree,
+    to_tree: &MergedTree,
+    copy_records: &CopyRecords,
+) -> Result<(), CommandError> {
+    let tree_diff = from_tree.diff_stream_with_copies(to_tree, &EverythingMatcher, copy_records);
+    let stats = DiffStats::calculate(
+        workspace_command.repo().store(),
+        tree_diff,
+        &DiffStatOptions::default(),
+        workspace_command.env().conflict_marker_style(),
+    )
+    .block_on()?;
+    let root = workspace_command.workspace_root();
+    let path_converter = RepoPathUiConverter::Fs {
+        cwd: root.to_owned(),
+        base: root.to_owned(),
+    };
+    let mut output = vec![];
+    show_diff_stats(
+        &mut PlainTextFormatter::new(&mut output),
+        &stats,
+        &path_converter,
+        DIFF_STAT_WIDTH - 1,
+    )?;
+    for line in output.split_inclusive(|&b| b == b'\n') {
+        mail.push(b' ');
+        mail.extend_from_slice(line);
+    }
+    Ok(())
+}
+
+/// Formats the name in an address, quoting or encoding it if needed.
+fn format_display_name(name: &str) -> Cow<'_, str> {
+    if !name.is_ascii() {
+        encode_header_value(name)
+    } else if name.contains(|c| "()<>[]:;@\\,.\"".contains(c)) {
+        let escaped = name.replace('\\', "\\\\").replace('"', "\\\"");
+        Cow::Owned(format!("\"{escaped}\""))
+    } else {
+        Cow::Borrowed(name)
+    }
+}
+
+/// Encodes a header value as an RFC 2047 encoded word if it isn't plain ASCII.
+fn encode_header_value(value: &str) -> Cow<'_, str> {
+    if value.is_ascii() {
+        return Cow::Borrowed(value);
+    }
+    let mut encoded = "=?UTF-8?q?".to_owned();
+    for b in value.bytes() {
+        if b.is_ascii_alphanumeric() || b"!*+-/".contains(&b) {
+            encoded.push(char::from(b));
+        } else {
+            write!(encoded, "={b:02X}").unwrap();
+        }
+    }
+    encoded.push_str("?=");
+    Cow::Owned(encoded)
+}
+
+/// Turns a subject into a file name component the way `git format-patch`
+/// does.
+fn sanitize_subject(subject: &str) -> String {
+    const MAX_LEN: usize = 52;
+    let mut name = String::new();
+    let mut pending_dash = false;
+    for c in subject.chars() {
+        if c == '.' && name.ends_with('.') {
+            continue;
+        }
+        if c.is_ascii_alphanumeric() || c == '_' || c == '.' {
+            if pending_dash && !name.is_empty() {
+                name.push('-');
+            }
+            pending_dash = false;
+            name.push(c);
+        } else {
+            pending_dash = true;
+        }
+        if name.len() >= MAX_LEN {
+            name.truncate(MAX_LEN);
+            break;
+        }
+    }
+    name.trim_end_matches(['.', '-']).to_owned()
+}
@@ -12,12 +12,23 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use std::collections::HashSet;
+
 use clap_complete::ArgValueCompleter;
+use itertools::Itertools as _;
+use jj_lib::matchers::Matcher;
+use jj_lib::merged_tree::MergedTree;
+use jj_lib::path_collision::PathCollision;
+use jj_lib::path_collision::PathFolding;
+use jj_lib::path_collision::find_path_collisions;
+use jj_lib::repo_path::RepoPath;
 use tracing::instrument;
 
 use crate::cli_util::CommandHelper;
 use crate::cli_util::RevisionArg;
+use crate::cli_util::WorkspaceCommandHelper;
 use crate::command_error::CommandError;
+use crate::command_error::user_error;
 use crate::commit_templater::TreeEntry;
 use crate::complete;
 use crate::templater::TemplateRenderer;
@@ -49,6 +60,14 @@ pub(crate) struct FileListArgs {
     #[arg(long, short = 'T')]
     template: Option<String>,
 
+    /// Only list files whose paths would collide with other paths on file
+    /// systems which ignore case or Unicode normalization
+    ///
+    /// The command fails if there are any such files, so it can be used to
+    /// check revisions before they're pushed.
+    #[arg(long)]
+    collisions: bool,
+
     /// Only list files matching these prefixes (instead of all files)
     #[arg(value_name = "FILESETS", value_hint = clap::ValueHint::AnyPath)]
     paths: Vec<String>,
@@ -77,6 +96,10 @@ pub(crate) fn cmd_file_list(
             .labeled(["file_list"])
     };
 
+    if args.collisions {
+        return list_colliding_files(ui, &workspace_command, &tree, matcher.as_ref(), &template);
+    }
+
     ui.request_pager();
     let mut formatter = ui.stdout_formatter();
     for (path, value) in tree.entries_matching(matcher.as_ref()) {
@@ -88,3 +111,48 @@ pub(crate) fn cmd_file_list(
     }
     Ok(())
 }
+
+fn list_colliding_files(
+    ui: &mut Ui,
+    workspace_command: &WorkspaceCommandHelper,
+    tree: &MergedTree,
+    matcher: &dyn Matcher,
+    template: &TemplateRenderer<TreeEntry>,
+) -> Result<(), CommandError> {
+    let entries = tree.entries_matching(matcher).collect_vec();
+    let collisions = find_path_collisions(
+        entries.iter().map(|(path, _value)| path.as_ref()),
+        PathFolding::ALL,
+    );
+    let colliding_paths: HashSet<&RepoPath> = collisions
+        .iter()
+        .flat_map(|collision| [collision.path.as_ref(), collision.other_path.as_ref()])
+        .collect();
+    {
+        let mut formatter = ui.stdout_formatter();
+        for (path, value) in entries {
+            if !path.ancestors().any(|path| colliding_paths.contains(path)) {
+                continue;
+            }
+            let entry = TreeEntry {
+                path,
+                value: value?,
+            };
+            template.format(&entry, formatter.as_mut())?;
+        }
+    }
+    if collisions.is_empty() {
+        return Ok(());
+    }
+    for PathCollision { path, other_path } in &collisions {
+        writeln!(
+            ui.warning_default(),
+            "{} collides with {}",
+            workspace_command.format_file_path(path),
+            workspace_command.format_file_path(other_path)
+        )?;
+    }
+    Err(user_error(
+        "Some paths would collide on file systems which ignore case or Unicode normalization",
+    ))
+}
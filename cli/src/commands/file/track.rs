@@ -24,6 +24,7 @@ use tracing::instrument;
 
 use crate::cli_util::CommandHelper;
 use crate::cli_util::print_untracked_files;
+use crate::cli_util::print_warned_files;
 use crate::command_error::CommandError;
 use crate::ui::Ui;
 
@@ -54,7 +55,7 @@ pub(crate) fn cmd_file_track(
     let matcher = workspace_command
         .parse_file_patterns(ui, &args.paths)?
         .to_matcher();
-    let options = workspace_command.snapshot_options_with_start_tracking_matcher(&matcher)?;
+    let options = workspace_command.snapshot_options_with_start_tracking_matcher(ui, &matcher)?;
 
     let mut tx = workspace_command.start_transaction().into_inner();
     let (mut locked_ws, _wc_commit) = workspace_command.start_working_copy_mutation()?;
@@ -93,12 +94,17 @@ pub fn print_track_snapshot_stats(
     }
 
     print_untracked_files(ui, &merged_untracked_paths, path_converter)?;
+    let mut merged_warned_paths = auto_stats.warned_paths;
+    merged_warned_paths.extend(track_stats.warned_paths);
+    print_warned_files(ui, &merged_warned_paths, path_converter)?;
 
     let (large_files, sizes): (Vec<_>, Vec<_>) = merged_untracked_paths
         .iter()
         .filter_map(|(path, reason)| match reason {
             UntrackedReason::FileTooLarge { size, .. } => Some((path, *size)),
-            UntrackedReason::FileNotAutoTracked => None,
+            UntrackedReason::FileNotAutoTracked
+            | UntrackedReason::IgnoredByRule { .. }
+            | UntrackedReason::RefusedByRule { .. } => None,
         })
         .unzip();
     if let Some(size) = sizes.iter().max() {
@@ -14,8 +14,10 @@
 
 use std::collections::HashMap;
 use std::io::Write as _;
+use std::ops::Range;
 use std::path::Path;
 use std::process::Stdio;
+use std::slice;
 
 use clap_complete::ArgValueCompleter;
 use itertools::Itertools as _;
@@ -28,6 +30,8 @@ use jj_lib::fix::FileToFix;
 use jj_lib::fix::FixError;
 use jj_lib::fix::ParallelFileFixer;
 use jj_lib::fix::fix_files;
+use jj_lib::fix::restrict_changes_to_lines;
+use jj_lib::fix::translate_changed_lines;
 use jj_lib::matchers::Matcher;
 use jj_lib::repo_path::RepoPathUiConverter;
 use jj_lib::settings::UserSettings;
@@ -43,6 +47,8 @@ use crate::command_error::config_error;
 use crate::command_error::print_parse_diagnostics;
 use crate::complete;
 use crate::config::CommandNameAndArgs;
+use crate::config::find_all_variables;
+use crate::config::interpolate_variables;
 use crate::ui::Ui;
 
 /// Update files with formatting fixes or other changes
@@ -71,7 +77,17 @@ use crate::ui::Ui;
 ///    which will be replaced with the repo-relative path of the file being
 ///    fixed. It is useful to provide the path to tools that include the path in
 ///    error messages, or behave differently based on the directory or file
-///    name.
+///    name. Arguments containing the substrings `$first_line` and
+///    `$last_line` are repeated for each range of lines that were changed in
+///    the revision, with the substrings replaced by the 1-based line numbers
+///    of the first and last line of the range. This can be used to format
+///    only the changed lines with tools like `clang-format --lines`. The
+///    substring `$line_ranges` is replaced with all ranges in a single
+///    argument, in the JSON format of `rustfmt --file-lines` for a file read
+///    from standard input. If the command refers to changed lines, the
+///    tool is not run if no lines were changed, e.g. if lines were only
+///    deleted. If unchanged files are fixed or the file was added, the whole
+///    file is considered changed.
 ///  - `patterns`: Determines which files the tool will affect. If this list is
 ///    empty, no files will be affected by the tool. If there are multiple
 ///    patterns, the tool is applied only once to each file in the union of the
@@ -79,6 +95,9 @@ use crate::ui::Ui;
 ///  - `enabled`: Enables or disables the tool. If omitted, the tool is enabled.
 ///    This is useful for defining disabled tools in user configuration that can
 ///    be enabled in individual repositories with one config setting.
+///  - `changed-lines-only`: If true, changes made by the tool outside the
+///    lines that were changed in the revision are discarded. This is useful
+///    for tools that can only format whole files. Defaults to false.
 ///
 /// For example, the following configuration defines how two code formatters
 /// (`clang-format` and `black`) will apply to three different file extensions
@@ -95,6 +114,21 @@ use crate::ui::Ui;
 /// patterns = ["glob:'**/*.py'"]
 /// ```
 ///
+/// To only format the lines changed in each revision with `clang-format`, the
+/// command could be written like this instead:
+///
+/// ```toml
+/// command = ["/usr/bin/clang-format", "--assume-filename=$path",
+///            "--lines=$first_line:$last_line"]
+/// ```
+///
+/// Or with `rustfmt`, which takes all ranges in one argument:
+///
+/// ```toml
+/// command = ["rustfmt", "--emit=stdout", "--unstable-features",
+///            "--file-lines=$line_ranges"]
+/// ```
+///
 /// Execution order of tools that affect the same file is deterministic, but
 /// currently unspecified, and may change between releases. If two tools affect
 /// the same file, the second tool to run will receive its input from the
@@ -207,22 +241,41 @@ async fn fix_one_file(
             .read_file(&file_to_fix.repo_path, &file_to_fix.file_id)
             .await?;
         read.read_to_end(&mut old_content).await?;
-        let new_content = matching_tools.fold(old_content.clone(), |prev_content, tool_config| {
-            match run_tool(
+        // The changed lines of the content passed to the next tool. The changes
+        // made by a tool count as changed lines for the following tools.
+        let mut changed_lines = file_to_fix.changed_lines.clone();
+        let mut new_content = old_content.clone();
+        for tool_config in matching_tools {
+            let lines = changed_lines
+                .clone()
+                .unwrap_or_else(|| all_lines(&new_content));
+            if lines.is_empty() && (tool_config.uses_line_ranges || tool_config.changed_lines_only)
+            {
+                continue;
+            }
+            let next_content = match run_tool(
                 ui,
                 workspace_root,
                 path_converter,
                 &tool_config.command,
                 file_to_fix,
-                &prev_content,
+                &new_content,
+                &lines,
             ) {
+                Ok(next_content) if tool_config.changed_lines_only && changed_lines.is_some() => {
+                    restrict_changes_to_lines(&new_content, &next_content, &lines)
+                }
                 Ok(next_content) => next_content,
                 // TODO: Because the stderr is passed through, this isn't always failing
                 // silently, but it should do something better will the exit code, tool
                 // name, etc.
-                Err(_) => prev_content,
+                Err(_) => continue,
+            };
+            if let Some(ranges) = &mut changed_lines {
+                *ranges = translate_changed_lines(&new_content, &next_content, ranges);
             }
-        });
+            new_content = next_content;
+        }
         if new_content != old_content {
             // TODO: send futures back over channel
             let new_file_id = store
@@ -249,12 +302,43 @@ fn run_tool(
     tool_command: &CommandNameAndArgs,
     file_to_fix: &FileToFix,
     old_content: &[u8],
+    changed_lines: &[Range<usize>],
 ) -> Result<Vec<u8>, ()> {
     // TODO: Pipe stderr so we can tell the user which commit, file, and tool it is
     // associated with.
-    let mut vars: HashMap<&str, &str> = HashMap::new();
-    vars.insert("path", file_to_fix.repo_path.as_internal_file_string());
-    let mut command = tool_command.to_command_with_variables(&vars);
+    let path = file_to_fix.repo_path.as_internal_file_string();
+    // The ranges as passed to `rustfmt --file-lines`, which reads from stdin
+    let line_ranges = format!(
+        "[{}]",
+        changed_lines
+            .iter()
+            .map(|range| format!(
+                r#"{{"file":"stdin","range":[{},{}]}}"#,
+                range.start + 1,
+                range.end
+            ))
+            .join(",")
+    );
+    let mut command = tool_command.to_command_with_expanded_args(|arg| {
+        if !repeats_per_line_range(arg) {
+            let vars = HashMap::from([
+                ("path", path.to_owned()),
+                ("line_ranges", line_ranges.clone()),
+            ]);
+            return interpolate_variables(slice::from_ref(arg), &vars);
+        }
+        changed_lines
+            .iter()
+            .flat_map(|range| {
+                let vars = HashMap::from([
+                    ("path", path.to_owned()),
+                    ("first_line", (range.start + 1).to_string()),
+                    ("last_line", range.end.to_string()),
+                ]);
+                interpolate_variables(slice::from_ref(arg), &vars)
+            })
+            .collect()
+    });
     tracing::debug!(?command, ?file_to_fix.repo_path, "spawning fix tool");
     let mut child = command
         .current_dir(workspace_root)
@@ -290,6 +374,28 @@ fn run_tool(
     }
 }
 
+/// Returns whether the arguments refer to the `$first_line`, `$last_line`, or
+/// `$line_ranges` variables.
+fn uses_line_ranges(args: &[String]) -> bool {
+    find_all_variables(args).any(|name| ["first_line", "last_line", "line_ranges"].contains(&name))
+}
+
+/// Returns whether the argument is repeated for each range of changed lines.
+fn repeats_per_line_range(arg: &str) -> bool {
+    find_all_variables(slice::from_ref(&arg.to_owned()))
+        .any(|name| ["first_line", "last_line"].contains(&name))
+}
+
+/// Returns the range of all lines in the content, or no ranges if the content
+/// is empty.
+fn all_lines(content: &[u8]) -> Vec<Range<usize>> {
+    let num_lines = content.split_inclusive(|b| *b == b'\n').count();
+    (num_lines > 0)
+        .then_some(0..num_lines)
+        .into_iter()
+        .collect()
+}
+
 /// Represents an entry in the `fix.tools` config table.
 struct ToolConfig {
     /// The command that will be run to fix a matching file.
@@ -298,6 +404,10 @@ struct ToolConfig {
     matcher: Box<dyn Matcher>,
     /// Whether the tool is enabled
     enabled: bool,
+    /// Whether the command refers to the changed line ranges.
+    uses_line_ranges: bool,
+    /// Whether changes outside the changed lines should be discarded.
+    changed_lines_only: bool,
     // TODO: Store the `name` field here and print it with the command's stderr, to clearly
     // associate any errors/warnings with the tool and its configuration entry.
 }
@@ -317,6 +427,8 @@ struct RawToolConfig {
     patterns: Vec<String>,
     #[serde(default = "default_tool_enabled")]
     enabled: bool,
+    #[serde(default)]
+    changed_lines_only: bool,
 }
 
 fn default_tool_enabled() -> bool {
@@ -352,10 +464,13 @@ fn get_tools_config(ui: &mut Ui, settings: &UserSettings) -> Result<ToolsConfig,
                     .try_collect()?,
             );
             print_parse_diagnostics(ui, &format!("In `fix.tools.{name}`"), &diagnostics)?;
+            let uses_line_ranges = uses_line_ranges(&tool.command.split_name_and_args().1);
             Ok(ToolConfig {
                 command: tool.command,
                 matcher: expression.to_matcher(),
                 enabled: tool.enabled,
+                uses_line_ranges,
+                changed_lines_only: tool.changed_lines_only,
             })
         })
         .try_collect()?;
@@ -0,0 +1,164 @@
+// Copyright 2025 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::HashSet;
+use std::io::Write as _;
+
+use clap_complete::ArgValueCompleter;
+use itertools::Itertools as _;
+use jj_lib::backend::Timestamp;
+use jj_lib::commit::Commit;
+use jj_lib::commit::CommitIteratorExt as _;
+use jj_lib::object_id::ObjectId as _;
+use tracing::instrument;
+
+use crate::cli_util::CommandHelper;
+use crate::cli_util::RevisionArg;
+use crate::command_error::CommandError;
+use crate::complete;
+use crate::text_util::parse_author;
+use crate::text_util::parse_timestamp;
+use crate::ui::Ui;
+
+/// Modify the metadata of revisions without changing their content
+///
+/// Unlike `jj describe`, this leaves the description alone, and the committer
+/// is kept as is unless `--update-committer-timestamp` is given. Descendants
+/// of the revisions are rebased onto the modified revisions.
+#[derive(clap::Args, Clone, Debug)]
+pub(crate) struct MetaeditArgs {
+    /// The revision(s) to modify (default: @)
+    #[arg(
+        long,
+        short,
+        value_name = "REVSETS",
+        add = ArgValueCompleter::new(complete::revset_expression_mutable)
+    )]
+    revisions: Vec<RevisionArg>,
+    /// Set author to the provided string
+    ///
+    /// This changes author name and email while retaining author timestamp.
+    #[arg(long, value_parser = parse_author)]
+    author: Option<(String, String)>,
+    /// Set the author timestamp
+    ///
+    /// The timestamp is in RFC 3339 format, such as
+    /// `2000-01-23T01:23:45-08:00`.
+    #[arg(long, value_name = "TIMESTAMP", value_parser = parse_timestamp)]
+    author_timestamp: Option<Timestamp>,
+    /// Update the committer timestamp to the current time
+    #[arg(long)]
+    update_committer_timestamp: bool,
+    /// Generate a new change ID
+    ///
+    /// This can be used to split a pair of divergent revisions apart. Each of
+    /// the revisions gets a different change ID.
+    #[arg(long)]
+    update_change_id: bool,
+}
+
+#[instrument(skip_all)]
+pub(crate) fn cmd_metaedit(
+    ui: &mut Ui,
+    command: &CommandHelper,
+    args: &MetaeditArgs,
+) -> Result<(), CommandError> {
+    let mut workspace_command = command.workspace_helper(ui)?;
+    let commits: Vec<Commit> = if args.revisions.is_empty() {
+        workspace_command.parse_revset(ui, &RevisionArg::AT)?
+    } else {
+        workspace_command.parse_union_revsets(ui, &args.revisions)?
+    }
+    .evaluate_to_commits()?
+    .try_collect()?;
+    if commits.is_empty() {
+        writeln!(ui.status(), "No revisions to modify.")?;
+        return Ok(());
+    }
+    workspace_command.check_rewritable(commits.iter().ids())?;
+
+    let mut tx = workspace_command.start_transaction();
+    let tx_description = match commits.as_slice() {
+        [] => unreachable!(),
+        [commit] => format!("edit commit metadata for commit {}", commit.id().hex()),
+        [first_commit, remaining_commits @ ..] => format!(
+            "edit commit metadata for commit {} and {} more",
+            first_commit.id().hex(),
+            remaining_commits.len()
+        ),
+    };
+
+    let target_ids: HashSet<_> = commits.iter().ids().cloned().collect();
+    let mut num_modified = 0;
+    let mut num_reparented = 0;
+    tx.repo_mut().transform_descendants(
+        commits.iter().ids().cloned().collect(),
+        |mut rewriter| {
+            let old_commit = rewriter.old_commit().clone();
+            if !target_ids.contains(old_commit.id()) {
+                if rewriter.parents_changed() {
+                    rewriter.reparent().write()?;
+                    num_reparented += 1;
+                }
+                return Ok(());
+            }
+
+            let mut author = old_commit.author().clone();
+            if let Some((name, email)) = &args.author {
+                author.name = name.clone();
+                author.email = email.clone();
+            }
+            if let Some(timestamp) = args.author_timestamp {
+                author.timestamp = timestamp;
+            }
+            let new_parents = rewriter.new_parents().to_vec();
+            let mut_repo = rewriter.mut_repo();
+            let mut commit_builder = mut_repo
+                .rewrite_commit(&old_commit)
+                .set_parents(new_parents);
+            let mut committer = old_commit.committer().clone();
+            if args.update_committer_timestamp {
+                committer.timestamp = commit_builder.committer().timestamp;
+            }
+            if !args.update_change_id
+                && author == *old_commit.author()
+                && committer == *old_commit.committer()
+                && commit_builder.parents() == old_commit.parent_ids()
+            {
+                return Ok(());
+            }
+
+            commit_builder = commit_builder.set_author(author).set_committer(committer);
+            if args.update_change_id {
+                commit_builder = commit_builder.generate_new_change_id();
+            }
+            let new_commit = commit_builder.write()?;
+            if args.update_change_id {
+                // The commit isn't recorded as rewritten if the change ID
+                // differs, so the descendants wouldn't be rebased onto it.
+                mut_repo.set_rewritten_commit(old_commit.id().clone(), new_commit.id().clone());
+            }
+            num_modified += 1;
+            Ok(())
+        },
+    )?;
+    if num_modified > 0 {
+        writeln!(ui.status(), "Modified {num_modified} commits")?;
+    }
+    if num_reparented > 0 {
+        writeln!(ui.status(), "Rebased {num_reparented} descendant commits")?;
+    }
+    tx.finish(ui, tx_description)?;
+    Ok(())
+}
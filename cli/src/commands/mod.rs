@@ -14,9 +14,12 @@
 
 mod abandon;
 mod absorb;
+mod apply;
+mod arrange;
 mod backout;
 #[cfg(feature = "bench")]
 mod bench;
+mod bisect;
 mod bookmark;
 mod commit;
 mod config;
@@ -27,6 +30,7 @@ mod diffedit;
 mod duplicate;
 mod edit;
 mod evolog;
+mod export_patch;
 mod file;
 mod fix;
 #[cfg(feature = "git")]
@@ -34,17 +38,21 @@ mod git;
 mod help;
 mod interdiff;
 mod log;
+mod metaedit;
 mod new;
 mod next;
 mod operation;
 mod parallelize;
 mod prev;
 mod rebase;
+mod redo;
 mod resolve;
+mod resolve_divergence;
 mod restore;
 mod revert;
 mod root;
 mod run;
+mod serialize;
 mod show;
 mod sign;
 mod simplify_parents;
@@ -53,6 +61,7 @@ mod split;
 mod squash;
 mod status;
 mod tag;
+mod undo;
 mod unsign;
 mod util;
 mod version;
@@ -88,12 +97,16 @@ const STYLES: Styles = Styles::styled()
 enum Command {
     Abandon(abandon::AbandonArgs),
     Absorb(absorb::AbsorbArgs),
+    Apply(apply::ApplyArgs),
+    Arrange(arrange::ArrangeArgs),
     // TODO: Remove in jj 0.34+
     Backout(backout::BackoutArgs),
     #[cfg(feature = "bench")]
     #[command(subcommand)]
     Bench(bench::BenchCommand),
     #[command(subcommand)]
+    Bisect(bisect::BisectCommand),
+    #[command(subcommand)]
     Bookmark(bookmark::BookmarkCommand),
     Commit(commit::CommitArgs),
     #[command(subcommand)]
@@ -107,6 +120,7 @@ enum Command {
     Edit(edit::EditArgs),
     #[command(alias = "obslog", visible_alias = "evolution-log")]
     Evolog(evolog::EvologArgs),
+    ExportPatch(export_patch::ExportPatchArgs),
     #[command(subcommand)]
     File(file::FileCommand),
     Fix(fix::FixArgs),
@@ -116,6 +130,7 @@ enum Command {
     Help(help::HelpArgs),
     Interdiff(interdiff::InterdiffArgs),
     Log(log::LogArgs),
+    Metaedit(metaedit::MetaeditArgs),
     New(new::NewArgs),
     Next(next::NextArgs),
     #[command(subcommand)]
@@ -124,13 +139,16 @@ enum Command {
     Parallelize(parallelize::ParallelizeArgs),
     Prev(prev::PrevArgs),
     Rebase(rebase::RebaseArgs),
+    Redo(redo::RedoArgs),
     Resolve(resolve::ResolveArgs),
+    ResolveDivergence(resolve_divergence::ResolveDivergenceArgs),
     Restore(restore::RestoreArgs),
     Revert(revert::RevertArgs),
     Root(root::RootArgs),
     #[command(hide = true)]
     // TODO: Flesh out.
     Run(run::RunArgs),
+    Serialize(serialize::SerializeArgs),
     Show(show::ShowArgs),
     Sign(sign::SignArgs),
     SimplifyParents(simplify_parents::SimplifyParentsArgs),
@@ -141,8 +159,7 @@ enum Command {
     Status(status::StatusArgs),
     #[command(subcommand)]
     Tag(tag::TagCommand),
-    /// Undo an operation (shortcut for `jj op undo`)
-    Undo(operation::undo::OperationUndoArgs),
+    Undo(undo::UndoArgs),
     Unsign(unsign::UnsignArgs),
     #[command(subcommand)]
     Util(util::UtilCommand),
@@ -161,9 +178,12 @@ pub fn run_command(ui: &mut Ui, command_helper: &CommandHelper) -> Result<(), Co
     match &subcommand {
         Command::Abandon(args) => abandon::cmd_abandon(ui, command_helper, args),
         Command::Absorb(args) => absorb::cmd_absorb(ui, command_helper, args),
+        Command::Apply(args) => apply::cmd_apply(ui, command_helper, args),
+        Command::Arrange(args) => arrange::cmd_arrange(ui, command_helper, args),
         Command::Backout(args) => backout::cmd_backout(ui, command_helper, args),
         #[cfg(feature = "bench")]
         Command::Bench(args) => bench::cmd_bench(ui, command_helper, args),
+        Command::Bisect(args) => bisect::cmd_bisect(ui, command_helper, args),
         Command::Bookmark(args) => bookmark::cmd_bookmark(ui, command_helper, args),
         Command::Commit(args) => commit::cmd_commit(ui, command_helper, args),
         Command::Config(args) => config::cmd_config(ui, command_helper, args),
@@ -173,6 +193,7 @@ pub fn run_command(ui: &mut Ui, command_helper: &CommandHelper) -> Result<(), Co
         Command::Diffedit(args) => diffedit::cmd_diffedit(ui, command_helper, args),
         Command::Duplicate(args) => duplicate::cmd_duplicate(ui, command_helper, args),
         Command::Edit(args) => edit::cmd_edit(ui, command_helper, args),
+        Command::ExportPatch(args) => export_patch::cmd_export_patch(ui, command_helper, args),
         Command::File(args) => file::cmd_file(ui, command_helper, args),
         Command::Fix(args) => fix::cmd_fix(ui, command_helper, args),
         #[cfg(feature = "git")]
@@ -180,6 +201,7 @@ pub fn run_command(ui: &mut Ui, command_helper: &CommandHelper) -> Result<(), Co
         Command::Help(args) => help::cmd_help(ui, command_helper, args),
         Command::Interdiff(args) => interdiff::cmd_interdiff(ui, command_helper, args),
         Command::Log(args) => log::cmd_log(ui, command_helper, args),
+        Command::Metaedit(args) => metaedit::cmd_metaedit(ui, command_helper, args),
         Command::New(args) => new::cmd_new(ui, command_helper, args),
         Command::Next(args) => next::cmd_next(ui, command_helper, args),
         Command::Evolog(args) => evolog::cmd_evolog(ui, command_helper, args),
@@ -187,11 +209,16 @@ pub fn run_command(ui: &mut Ui, command_helper: &CommandHelper) -> Result<(), Co
         Command::Parallelize(args) => parallelize::cmd_parallelize(ui, command_helper, args),
         Command::Prev(args) => prev::cmd_prev(ui, command_helper, args),
         Command::Rebase(args) => rebase::cmd_rebase(ui, command_helper, args),
+        Command::Redo(args) => redo::cmd_redo(ui, command_helper, args),
         Command::Resolve(args) => resolve::cmd_resolve(ui, command_helper, args),
+        Command::ResolveDivergence(args) => {
+            resolve_divergence::cmd_resolve_divergence(ui, command_helper, args)
+        }
         Command::Restore(args) => restore::cmd_restore(ui, command_helper, args),
         Command::Revert(args) => revert::cmd_revert(ui, command_helper, args),
         Command::Root(args) => root::cmd_root(ui, command_helper, args),
         Command::Run(args) => run::cmd_run(ui, command_helper, args),
+        Command::Serialize(args) => serialize::cmd_serialize(ui, command_helper, args),
         Command::SimplifyParents(args) => {
             simplify_parents::cmd_simplify_parents(ui, command_helper, args)
         }
@@ -202,7 +229,7 @@ pub fn run_command(ui: &mut Ui, command_helper: &CommandHelper) -> Result<(), Co
         Command::Squash(args) => squash::cmd_squash(ui, command_helper, args),
         Command::Status(args) => status::cmd_status(ui, command_helper, args),
         Command::Tag(args) => tag::cmd_tag(ui, command_helper, args),
-        Command::Undo(args) => operation::undo::cmd_op_undo(ui, command_helper, args),
+        Command::Undo(args) => undo::cmd_undo(ui, command_helper, args),
         Command::Unsign(args) => unsign::cmd_unsign(ui, command_helper, args),
         Command::Util(args) => util::cmd_util(ui, command_helper, args),
         Command::Version(args) => version::cmd_version(ui, command_helper, args),
@@ -170,21 +170,17 @@ pub(crate) fn cmd_new(
         .iter()
         .map(|commit_id| workspace_command.repo().store().get_commit(commit_id))
         .try_collect()?;
-    let mut advance_bookmarks_target = None;
-    let mut advanceable_bookmarks = vec![];
-
-    if args.insert_before.is_none() && args.insert_after.is_none() {
-        let should_advance_bookmarks = parent_commits.len() == 1;
-        if should_advance_bookmarks {
-            advance_bookmarks_target = Some(parent_commit_ids[0].clone());
-            advanceable_bookmarks =
-                workspace_command.get_advanceable_bookmarks(parent_commits[0].parent_ids())?;
-        }
-    };
-
     let parent_commit_ids_set: HashSet<CommitId> = parent_commit_ids.iter().cloned().collect();
 
     let mut tx = workspace_command.start_transaction();
+    // The bookmarks are advanced to the parent of the new commit even if it
+    // was already the parent of the working copy, or if the working copy
+    // doesn't move because of `--no-edit`.
+    if args.insert_before.is_none() && args.insert_after.is_none() {
+        if let [parent_commit_id] = parent_commit_ids.as_slice() {
+            tx.advance_bookmarks_to(parent_commit_id.clone())?;
+        }
+    }
     let merged_tree = merge_commit_trees(tx.repo(), &parent_commits).block_on()?;
     let mut commit_builder = tx
         .repo_mut()
@@ -234,11 +230,6 @@ pub(crate) fn cmd_new(
         writeln!(ui.status(), "Rebased {num_rebased} descendant commits")?;
     }
 
-    // Does nothing if there's no bookmarks to advance.
-    if let Some(target) = advance_bookmarks_target {
-        tx.advance_bookmarks(advanceable_bookmarks, &target);
-    }
-
     tx.finish(ui, "new empty commit")?;
     Ok(())
 }
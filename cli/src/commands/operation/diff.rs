@@ -26,9 +26,11 @@ use jj_lib::evolution::accumulate_predecessors;
 use jj_lib::graph::GraphEdgeType;
 use jj_lib::graph::TopoGroupedGraphIterator;
 use jj_lib::matchers::EverythingMatcher;
+use jj_lib::op_store::OperationId;
 use jj_lib::op_store::RefTarget;
 use jj_lib::op_store::RemoteRef;
 use jj_lib::op_store::RemoteRefState;
+use jj_lib::operation::Operation;
 use jj_lib::refs::diff_named_commit_ids;
 use jj_lib::refs::diff_named_ref_targets;
 use jj_lib::refs::diff_named_remote_refs;
@@ -83,6 +85,13 @@ pub struct OperationDiffArgs {
     /// contaminated by unrelated changes.
     #[arg(long, short = 'p')]
     patch: bool,
+    /// Print the repository changes as a JSON object
+    ///
+    /// The object lists the added and removed heads, the rewritten commits
+    /// with their predecessors, and the changed working copies, bookmarks,
+    /// tags, and remote bookmarks.
+    #[arg(long, conflicts_with_all = ["no_graph", "patch"])]
+    json: bool,
     #[command(flatten)]
     diff_format: DiffFormatArgs,
 }
@@ -119,6 +128,18 @@ pub fn cmd_op_diff(
     tx.repo_mut().merge_index(&from_repo);
     let merged_repo = tx.repo();
 
+    if args.json {
+        ui.request_pager();
+        let mut formatter = ui.stdout_formatter();
+        return write_op_diff_json(
+            formatter.as_mut(),
+            merged_repo,
+            &from_ops,
+            &from_repo,
+            &to_repo,
+        );
+    }
+
     let diff_renderer = {
         let formats = diff_formats_for_log(settings, &args.diff_format, args.patch)?;
         let path_converter = workspace_env.path_converter();
@@ -387,6 +408,167 @@ pub fn show_op_diff(
     Ok(())
 }
 
+/// Repository changes between two operations in machine-readable form.
+#[derive(Debug, serde::Serialize)]
+struct OperationDiffSummary<'a> {
+    from_operations: Vec<&'a OperationId>,
+    to_operation: &'a OperationId,
+    added_heads: Vec<&'a CommitId>,
+    removed_heads: Vec<&'a CommitId>,
+    changed_commits: Vec<ModifiedChangeSummary>,
+    working_copies: Vec<RefChangeSummary<'a, Option<&'a CommitId>>>,
+    local_bookmarks: Vec<RefChangeSummary<'a, &'a RefTarget>>,
+    tags: Vec<RefChangeSummary<'a, &'a RefTarget>>,
+    remote_bookmarks: Vec<RemoteRefChangeSummary<'a>>,
+}
+
+/// Parent operations of a merge operation in machine-readable form.
+#[derive(Debug, serde::Serialize)]
+struct MergeOperationSummary<'a> {
+    from_operations: Vec<&'a OperationId>,
+    to_operation: &'a OperationId,
+}
+
+/// Rewrite mapping of a created, rewritten, or abandoned commit.
+#[derive(Debug, serde::Serialize)]
+struct ModifiedChangeSummary {
+    change_id: ChangeId,
+    /// New commit, or `None` if the commit was abandoned.
+    commit: Option<CommitId>,
+    /// Commits replaced by the new commit, or the abandoned commit.
+    predecessors: Vec<CommitId>,
+}
+
+#[derive(Debug, serde::Serialize)]
+struct RefChangeSummary<'a, T> {
+    name: &'a str,
+    from: T,
+    to: T,
+}
+
+#[derive(Debug, serde::Serialize)]
+struct RemoteRefChangeSummary<'a> {
+    name: &'a str,
+    remote: &'a str,
+    from: &'a RefTarget,
+    to: &'a RefTarget,
+    from_tracked: bool,
+    to_tracked: bool,
+}
+
+/// Writes the differences between two operations as a JSON object.
+///
+/// `current_repo` should contain the indices of both repos.
+pub fn write_op_diff_json(
+    formatter: &mut dyn Formatter,
+    current_repo: &dyn Repo,
+    from_ops: &[Operation],
+    from_repo: &ReadonlyRepo,
+    to_repo: &ReadonlyRepo,
+) -> Result<(), CommandError> {
+    let from_view = from_repo.view();
+    let to_view = to_repo.view();
+    let changes = compute_operation_commits_diff(current_repo, from_repo, to_repo)?;
+    let revset =
+        RevsetExpression::commits(changes.keys().cloned().collect()).evaluate(current_repo)?;
+    let changed_commits: Vec<_> = revset
+        .iter()
+        .map_ok(|commit_id| {
+            let (commit, predecessors) = match &changes[&commit_id] {
+                ModifiedChange::Existing {
+                    commit,
+                    predecessors,
+                } => (Some(commit), predecessors.as_slice()),
+                ModifiedChange::Abandoned { commit } => (None, slice::from_ref(commit)),
+            };
+            ModifiedChangeSummary {
+                change_id: commit.unwrap_or(&predecessors[0]).change_id().clone(),
+                commit: commit.map(|commit| commit.id().clone()),
+                predecessors: predecessors.iter().map(|c| c.id().clone()).collect(),
+            }
+        })
+        .try_collect()?;
+
+    let summary = OperationDiffSummary {
+        from_operations: from_ops.iter().map(|op| op.id()).collect(),
+        to_operation: to_repo.op_id(),
+        added_heads: to_view
+            .heads()
+            .iter()
+            .filter(|id| !from_view.heads().contains(*id))
+            .sorted()
+            .collect(),
+        removed_heads: from_view
+            .heads()
+            .iter()
+            .filter(|id| !to_view.heads().contains(*id))
+            .sorted()
+            .collect(),
+        changed_commits,
+        working_copies: diff_named_commit_ids(from_view.wc_commit_ids(), to_view.wc_commit_ids())
+            .map(|(name, (from, to))| RefChangeSummary {
+                name: name.as_str(),
+                from,
+                to,
+            })
+            .collect(),
+        local_bookmarks: diff_named_ref_targets(
+            from_view.local_bookmarks(),
+            to_view.local_bookmarks(),
+        )
+        .map(|(name, (from, to))| RefChangeSummary {
+            name: name.as_str(),
+            from,
+            to,
+        })
+        .collect(),
+        tags: diff_named_ref_targets(from_view.tags(), to_view.tags())
+            .map(|(name, (from, to))| RefChangeSummary {
+                name: name.as_str(),
+                from,
+                to,
+            })
+            .collect(),
+        remote_bookmarks: diff_named_remote_refs(
+            from_view.all_remote_bookmarks(),
+            to_view.all_remote_bookmarks(),
+        )
+        // Skip updates to the local git repo, as the human-readable output does.
+        .filter(|(symbol, _)| !jj_lib::git::is_special_git_remote(symbol.remote))
+        .map(|(symbol, (from, to))| RemoteRefChangeSummary {
+            name: symbol.name.as_str(),
+            remote: symbol.remote.as_str(),
+            from: &from.target,
+            to: &to.target,
+            from_tracked: from.is_tracked(),
+            to_tracked: to.is_tracked(),
+        })
+        .collect(),
+    };
+    serde_json::to_writer(&mut *formatter, &summary).map_err(std::io::Error::from)?;
+    writeln!(formatter)?;
+    Ok(())
+}
+
+/// Writes the parents of a merge operation as a JSON object.
+///
+/// Only the `from_operations` and `to_operation` fields of
+/// `write_op_diff_json()` are written since the changes in the merge aren't
+/// computed.
+pub fn write_merge_op_json(
+    formatter: &mut dyn Formatter,
+    parent_ops: &[Operation],
+    op: &Operation,
+) -> Result<(), CommandError> {
+    let summary = MergeOperationSummary {
+        from_operations: parent_ops.iter().map(|op| op.id()).collect(),
+        to_operation: op.id(),
+    };
+    serde_json::to_writer(&mut *formatter, &summary).map_err(std::io::Error::from)?;
+    writeln!(formatter)?;
+    Ok(())
+}
+
 /// Writes a summary for the given `ModifiedChange`.
 fn write_modified_change_summary(
     formatter: &mut dyn Formatter,
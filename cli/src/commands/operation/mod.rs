@@ -18,6 +18,7 @@ mod log;
 mod restore;
 mod show;
 pub mod undo;
+mod verify;
 
 use abandon::OperationAbandonArgs;
 use abandon::cmd_op_abandon;
@@ -32,6 +33,8 @@ use show::OperationShowArgs;
 use show::cmd_op_show;
 use undo::OperationUndoArgs;
 use undo::cmd_op_undo;
+use verify::OperationVerifyArgs;
+use verify::cmd_op_verify;
 
 use crate::cli_util::CommandHelper;
 use crate::command_error::CommandError;
@@ -51,6 +54,7 @@ pub enum OperationCommand {
     Restore(OperationRestoreArgs),
     Show(OperationShowArgs),
     Undo(OperationUndoArgs),
+    Verify(OperationVerifyArgs),
 }
 
 pub fn cmd_operation(
@@ -65,11 +69,12 @@ pub fn cmd_operation(
         OperationCommand::Restore(args) => cmd_op_restore(ui, command, args),
         OperationCommand::Show(args) => cmd_op_show(ui, command, args),
         OperationCommand::Undo(args) => cmd_op_undo(ui, command, args),
+        OperationCommand::Verify(args) => cmd_op_verify(ui, command, args),
     }
 }
 
 #[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, clap::ValueEnum)]
-enum UndoWhatToRestore {
+pub enum UndoWhatToRestore {
     /// The jj repo state and local bookmarks
     Repo,
     /// The remote-tracking bookmarks. Do not restore these if you'd like to
@@ -77,11 +82,11 @@ enum UndoWhatToRestore {
     RemoteTracking,
 }
 
-const DEFAULT_UNDO_WHAT: [UndoWhatToRestore; 2] =
+pub const DEFAULT_UNDO_WHAT: [UndoWhatToRestore; 2] =
     [UndoWhatToRestore::Repo, UndoWhatToRestore::RemoteTracking];
 
 /// Restore only the portions of the view specified by the `what` argument
-fn view_with_desired_portions_restored(
+pub fn view_with_desired_portions_restored(
     view_being_restored: &jj_lib::op_store::View,
     current_view: &jj_lib::op_store::View,
     what: &[UndoWhatToRestore],
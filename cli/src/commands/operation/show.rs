@@ -17,6 +17,8 @@ use itertools::Itertools as _;
 use jj_lib::operation::Operation;
 
 use super::diff::show_op_diff;
+use super::diff::write_merge_op_json;
+use super::diff::write_op_diff_json;
 use crate::cli_util::CommandHelper;
 use crate::cli_util::LogContentFormat;
 use crate::command_error::CommandError;
@@ -44,6 +46,12 @@ pub struct OperationShowArgs {
     /// contaminated by unrelated changes.
     #[arg(long, short = 'p')]
     patch: bool,
+    /// Print the repository changes as a JSON object
+    ///
+    /// See `jj op diff --json` for the format. Changes in a merge operation
+    /// aren't shown, so only the parent operations are listed.
+    #[arg(long, conflicts_with_all = ["no_graph", "patch"])]
+    json: bool,
     #[command(flatten)]
     diff_format: DiffFormatArgs,
 }
@@ -63,6 +71,23 @@ pub fn cmd_op_show(
     let parent_repo = repo_loader.load_at(&merged_parent_op)?;
     let repo = repo_loader.load_at(&op)?;
 
+    if args.json {
+        // TODO: Merged repo may have newly rebased commits, which wouldn't
+        // exist in the index. (#4465)
+        ui.request_pager();
+        let mut formatter = ui.stdout_formatter();
+        if parent_ops.len() > 1 {
+            return write_merge_op_json(formatter.as_mut(), &parent_ops, &op);
+        }
+        return write_op_diff_json(
+            formatter.as_mut(),
+            repo.as_ref(),
+            &parent_ops,
+            &parent_repo,
+            &repo,
+        );
+    }
+
     let id_prefix_context = workspace_env.new_id_prefix_context();
     let commit_summary_template = {
         let language = workspace_env.commit_template_language(repo.as_ref(), &id_prefix_context);
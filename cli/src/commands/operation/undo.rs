@@ -16,6 +16,7 @@ use clap_complete::ArgValueCandidates;
 use itertools::Itertools as _;
 use jj_lib::object_id::ObjectId as _;
 use jj_lib::op_store::OpStoreError;
+use jj_lib::op_store::OperationId;
 use jj_lib::operation::Operation;
 use jj_lib::repo::Repo as _;
 
@@ -23,6 +24,7 @@ use super::DEFAULT_UNDO_WHAT;
 use super::UndoWhatToRestore;
 use super::view_with_desired_portions_restored;
 use crate::cli_util::CommandHelper;
+use crate::cli_util::WorkspaceCommandTransaction;
 use crate::command_error::CommandError;
 use crate::command_error::user_error;
 use crate::complete;
@@ -38,13 +40,57 @@ pub struct OperationUndoArgs {
     ///
     /// Use `jj op log` to find an operation to undo.
     #[arg(default_value = "@", add = ArgValueCandidates::new(complete::operations))]
-    operation: String,
+    pub operation: String,
 
     /// What portions of the local state to restore (can be repeated)
     ///
     /// This option is EXPERIMENTAL.
     #[arg(long, value_enum, default_values_t = DEFAULT_UNDO_WHAT)]
-    what: Vec<UndoWhatToRestore>,
+    pub what: Vec<UndoWhatToRestore>,
+}
+
+/// Operation tag recording the ID of the operation undone by an undo
+/// operation.
+pub const UNDO_OF_TAG: &str = "undo-of";
+
+/// Operation tag recording the ID of the undo operation reverted by a redo
+/// operation.
+pub const REDO_OF_TAG: &str = "redo-of";
+
+/// Returns the operation ID recorded in the `tag` of the `op` metadata.
+pub fn tagged_operation_id(op: &Operation, tag: &str) -> Option<OperationId> {
+    let hex = op.metadata().tags.get(tag)?;
+    OperationId::try_from_hex(hex)
+}
+
+/// Returns the single parent of the operation to be reverted.
+pub fn parent_to_revert(op: &Operation, verb: &str) -> Result<Operation, CommandError> {
+    match op.parents().at_most_one() {
+        Ok(Some(parent_op)) => Ok(parent_op?),
+        Ok(None) => Err(user_error(format!("Cannot {verb} root operation"))),
+        Err(_) => Err(user_error(format!("Cannot {verb} a merge operation"))),
+    }
+}
+
+/// Applies the inverse of the change made by `op` on top of the current
+/// transaction state.
+pub fn revert_operation(
+    tx: &mut WorkspaceCommandTransaction,
+    op: &Operation,
+    parent_op: &Operation,
+    what: &[UndoWhatToRestore],
+) -> Result<(), CommandError> {
+    let repo_loader = tx.base_repo().loader();
+    let op_repo = repo_loader.load_at(op)?;
+    let parent_repo = repo_loader.load_at(parent_op)?;
+    tx.repo_mut().merge(&op_repo, &parent_repo)?;
+    let new_view = view_with_desired_portions_restored(
+        tx.repo().view().store_view(),
+        tx.base_repo().view().store_view(),
+        what,
+    );
+    tx.repo_mut().set_view(new_view);
+    Ok(())
 }
 
 // Checks whether `op` resets the view of `parent_op` to the view of the
@@ -60,7 +106,7 @@ fn resets_view_of(op: &Operation, parent_op: &Operation) -> Result<bool, OpStore
     Ok(op.view_id() == grandparent_op?.view_id())
 }
 
-fn tx_description(op: &Operation) -> String {
+pub fn tx_description(op: &Operation) -> String {
     format!("undo operation {}", op.id().hex())
 }
 
@@ -71,23 +117,12 @@ pub fn cmd_op_undo(
 ) -> Result<(), CommandError> {
     let mut workspace_command = command.workspace_helper(ui)?;
     let bad_op = workspace_command.resolve_single_op(&args.operation)?;
-    let parent_of_bad_op = match bad_op.parents().at_most_one() {
-        Ok(Some(parent_of_bad_op)) => parent_of_bad_op?,
-        Ok(None) => return Err(user_error("Cannot undo root operation")),
-        Err(_) => return Err(user_error("Cannot undo a merge operation")),
-    };
+    let parent_of_bad_op = parent_to_revert(&bad_op, "undo")?;
 
     let mut tx = workspace_command.start_transaction();
-    let repo_loader = tx.base_repo().loader();
-    let bad_repo = repo_loader.load_at(&bad_op)?;
-    let parent_repo = repo_loader.load_at(&parent_of_bad_op)?;
-    tx.repo_mut().merge(&bad_repo, &parent_repo)?;
-    let new_view = view_with_desired_portions_restored(
-        tx.repo().view().store_view(),
-        tx.base_repo().view().store_view(),
-        &args.what,
-    );
-    tx.repo_mut().set_view(new_view);
+    // Unlike `jj undo`, this doesn't record the `undo-of` tag, so `jj redo`
+    // won't revert an explicit undo of an arbitrary operation.
+    revert_operation(&mut tx, &bad_op, &parent_of_bad_op, &args.what)?;
     if let Some(mut formatter) = ui.status_formatter() {
         write!(formatter, "Undid operation: ")?;
         let template = tx.base_workspace_helper().operation_summary_template();
@@ -127,8 +162,8 @@ pub fn cmd_op_undo(
         )?;
         writeln!(
             ui.hint_default(),
-            "To undo multiple operations, use `jj op log` to see past states and `jj op restore` \
-             to restore one of these states."
+            "To undo multiple operations, run `jj undo` without arguments repeatedly, or use `jj \
+             op restore` to restore one of the past states shown by `jj op log`."
         )?;
     }
 
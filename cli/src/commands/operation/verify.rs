@@ -0,0 +1,148 @@
+// Copyright 2025 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::HashSet;
+use std::io::Write as _;
+
+use clap_complete::ArgValueCandidates;
+use jj_lib::op_store::OpStoreError;
+use jj_lib::operation::Operation;
+use jj_lib::signing::SigStatus;
+use jj_lib::signing::SignError;
+
+use crate::cli_util::CommandHelper;
+use crate::cli_util::short_operation_hash;
+use crate::command_error::CommandError;
+use crate::command_error::user_error;
+use crate::command_error::user_error_with_message;
+use crate::complete;
+use crate::ui::Ui;
+
+/// Verify the integrity of the operation log
+///
+/// Walks the given operation and all its ancestors, checking that every
+/// parent operation exists and that every signed operation has a good
+/// signature. Operations are signed when `signing.sign-operations` is
+/// enabled.
+///
+/// Operations rewritten by `jj op abandon` lose their signatures.
+#[derive(clap::Args, Clone, Debug)]
+pub struct OperationVerifyArgs {
+    /// The operation to start verifying from
+    #[arg(default_value = "@", add = ArgValueCandidates::new(complete::operations))]
+    operation: String,
+    /// Also fail if an operation other than the root operation isn't signed
+    #[arg(long)]
+    require_signatures: bool,
+}
+
+pub fn cmd_op_verify(
+    ui: &mut Ui,
+    command: &CommandHelper,
+    args: &OperationVerifyArgs,
+) -> Result<(), CommandError> {
+    let workspace_command = command.workspace_helper_no_snapshot(ui)?;
+    let repo_loader = workspace_command.workspace().repo_loader();
+    let op_store = repo_loader.op_store();
+    let signer = repo_loader.store().signer();
+    let head_op = workspace_command.resolve_single_op(&args.operation)?;
+
+    let mut checked_count = 0;
+    let mut good_count = 0;
+    let mut unsigned_count = 0;
+    let mut problem_count = 0;
+    let mut formatter = ui.stdout_formatter();
+    let mut visited = HashSet::from([head_op.id().clone()]);
+    let mut pending = vec![head_op];
+    while let Some(op) = pending.pop() {
+        checked_count += 1;
+        let op_hash = short_operation_hash(op.id());
+        match op.verification(signer) {
+            Ok(Some(verification)) => match verification.status {
+                SigStatus::Good => good_count += 1,
+                SigStatus::Unknown => {
+                    problem_count += 1;
+                    writeln!(
+                        formatter,
+                        "Operation {op_hash}: signature could not be verified"
+                    )?;
+                }
+                SigStatus::Bad => {
+                    problem_count += 1;
+                    writeln!(
+                        formatter,
+                        "Operation {op_hash}: bad signature by {}",
+                        verification
+                            .display
+                            .or(verification.key)
+                            .as_deref()
+                            .unwrap_or("(unknown)")
+                    )?;
+                }
+            },
+            Ok(None) if op.id() == op_store.root_operation_id() => {}
+            Ok(None) => {
+                unsigned_count += 1;
+                if args.require_signatures {
+                    problem_count += 1;
+                    writeln!(formatter, "Operation {op_hash}: not signed")?;
+                }
+            }
+            Err(SignError::InvalidSignatureFormat) => {
+                problem_count += 1;
+                writeln!(formatter, "Operation {op_hash}: invalid signature")?;
+            }
+            Err(err) => {
+                return Err(user_error_with_message(
+                    format!("Failed to verify signature of operation {op_hash}"),
+                    err,
+                ));
+            }
+        }
+
+        for parent_id in op.parent_ids() {
+            if !visited.insert(parent_id.clone()) {
+                continue;
+            }
+            match op_store.read_operation(parent_id) {
+                Ok(data) => {
+                    pending.push(Operation::new(op_store.clone(), parent_id.clone(), data));
+                }
+                Err(OpStoreError::ObjectNotFound { .. }) => {
+                    problem_count += 1;
+                    writeln!(
+                        formatter,
+                        "Operation {op_hash}: parent operation {} is missing",
+                        short_operation_hash(parent_id)
+                    )?;
+                }
+                Err(err) => return Err(err.into()),
+            }
+        }
+    }
+    drop(formatter);
+
+    writeln!(
+        ui.status(),
+        "Checked {checked_count} operations: {good_count} with good signatures, \
+         {unsigned_count} unsigned."
+    )?;
+    if problem_count > 0 {
+        return Err(user_error(format!(
+            "Found {problem_count} problem{} in the operation log",
+            if problem_count == 1 { "" } else { "s" }
+        )));
+    }
+    Ok(())
+}
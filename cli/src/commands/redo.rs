@@ -0,0 +1,96 @@
+// Copyright 2025 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use itertools::Itertools as _;
+use jj_lib::object_id::ObjectId as _;
+use jj_lib::operation::Operation;
+
+use crate::cli_util::CommandHelper;
+use crate::command_error::CommandError;
+use crate::command_error::user_error;
+use crate::commands::operation::DEFAULT_UNDO_WHAT;
+use crate::commands::operation::UndoWhatToRestore;
+use crate::commands::operation::undo::REDO_OF_TAG;
+use crate::commands::operation::undo::UNDO_OF_TAG;
+use crate::commands::operation::undo::parent_to_revert;
+use crate::commands::operation::undo::revert_operation;
+use crate::commands::operation::undo::tagged_operation_id;
+use crate::ui::Ui;
+
+/// Redo the most recently undone operation
+///
+/// This reverts the latest `jj undo`. Running `jj redo` repeatedly reapplies
+/// the operations undone by consecutive `jj undo` commands. There's nothing to
+/// redo once another operation has been made after the undo. Operations undone
+/// with `jj op undo` aren't redone.
+#[derive(clap::Args, Clone, Debug)]
+pub struct RedoArgs {
+    /// What portions of the local state to restore (can be repeated)
+    ///
+    /// This option is EXPERIMENTAL.
+    #[arg(long, value_enum, default_values_t = DEFAULT_UNDO_WHAT)]
+    what: Vec<UndoWhatToRestore>,
+}
+
+pub fn cmd_redo(ui: &mut Ui, command: &CommandHelper, args: &RedoArgs) -> Result<(), CommandError> {
+    let mut workspace_command = command.workspace_helper(ui)?;
+    let head_op = workspace_command.repo().operation().clone();
+    let Some(undo_op) = find_undo_op_to_revert(head_op)? else {
+        return Err(user_error("Nothing to redo"));
+    };
+    let parent_of_undo_op = parent_to_revert(&undo_op, "redo")?;
+    let undone_id = tagged_operation_id(&undo_op, UNDO_OF_TAG).unwrap();
+    let undone_op = workspace_command
+        .repo()
+        .loader()
+        .load_operation(&undone_id)?;
+
+    let mut tx = workspace_command.start_transaction();
+    revert_operation(&mut tx, &undo_op, &parent_of_undo_op, &args.what)?;
+    tx.set_tag(REDO_OF_TAG.to_owned(), undo_op.id().hex());
+    if let Some(mut formatter) = ui.status_formatter() {
+        write!(formatter, "Redid operation: ")?;
+        let template = tx.base_workspace_helper().operation_summary_template();
+        template.format(&undone_op, formatter.as_mut())?;
+        writeln!(formatter)?;
+    }
+    tx.finish(ui, format!("redo operation {}", undone_op.id().hex()))?;
+    Ok(())
+}
+
+/// Returns the undo operation to be reverted next at `op`.
+///
+/// If `op` reverted the undo operation that would have been reverted next at
+/// its parent, the search continues from the parent of the reverted undo.
+fn find_undo_op_to_revert(op: Operation) -> Result<Option<Operation>, CommandError> {
+    if tagged_operation_id(&op, UNDO_OF_TAG).is_some() {
+        return Ok(Some(op));
+    }
+    let Some(reverted_id) = tagged_operation_id(&op, REDO_OF_TAG) else {
+        return Ok(None);
+    };
+    let Ok(parent_op) = op.parents().exactly_one() else {
+        return Ok(None);
+    };
+    let Some(reverted_op) = find_undo_op_to_revert(parent_op?)? else {
+        return Ok(None);
+    };
+    if reverted_op.id() != &reverted_id {
+        return Ok(None);
+    }
+    match reverted_op.parents().exactly_one() {
+        Ok(parent_of_reverted_op) => find_undo_op_to_revert(parent_of_reverted_op?),
+        Err(_) => Ok(None),
+    }
+}
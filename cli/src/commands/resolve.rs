@@ -12,14 +12,23 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use std::io::Write as _;
+use std::slice;
+
 use clap_complete::ArgValueCandidates;
 use clap_complete::ArgValueCompleter;
 use itertools::Itertools as _;
+use jj_lib::commit::Commit;
+use jj_lib::evolution::walk_predecessors;
+use jj_lib::matchers::Matcher;
+use jj_lib::merged_tree::MergedTreeBuilder;
 use jj_lib::object_id::ObjectId as _;
+use jj_lib::repo::Repo as _;
 use tracing::instrument;
 
 use crate::cli_util::CommandHelper;
 use crate::cli_util::RevisionArg;
+use crate::cli_util::WorkspaceCommandHelper;
 use crate::cli_util::print_conflicted_paths;
 use crate::command_error::CommandError;
 use crate::command_error::cli_error;
@@ -69,6 +78,15 @@ pub(crate) struct ResolveArgs {
         add = ArgValueCandidates::new(complete::merge_editors),
     )]
     tool: Option<String>,
+    /// Forget the recorded resolutions of the conflicts instead of resolving
+    /// them
+    ///
+    /// With `rerere.enabled`, the resolutions of conflicts are recorded and
+    /// reused when the same conflicts appear again. Files which were resolved
+    /// with the forgotten resolutions are turned back into conflicts, unless
+    /// they were modified since.
+    #[arg(long, conflicts_with_all = ["list", "tool"])]
+    forget: bool,
     /// Only resolve conflicts in these paths. You can use the `--list` argument
     /// to find paths to use here.
     #[arg(
@@ -90,6 +108,9 @@ pub(crate) fn cmd_resolve(
         .parse_file_patterns(ui, &args.paths)?
         .to_matcher();
     let commit = workspace_command.resolve_single_rev(ui, &args.revision)?;
+    if args.forget {
+        return forget_resolutions(ui, &mut workspace_command, &commit, matcher.as_ref());
+    }
     let tree = commit.tree()?;
     let conflicts = tree
         .conflicts()
@@ -151,3 +172,83 @@ pub(crate) fn cmd_resolve(
     }
     Ok(())
 }
+
+fn forget_resolutions(
+    ui: &mut Ui,
+    workspace_command: &mut WorkspaceCommandHelper,
+    commit: &Commit,
+    matcher: &dyn Matcher,
+) -> Result<(), CommandError> {
+    let repo = workspace_command.repo().clone();
+    let store = repo.store();
+    let cache = workspace_command.resolution_cache();
+    let tree = commit.tree()?;
+    let mut conflicts = vec![];
+    for (path, conflict) in tree.conflicts() {
+        if matcher.matches(&path) {
+            conflicts.push((path, conflict?));
+        }
+    }
+    // Files resolved with recorded resolutions are still conflicted in the
+    // predecessor with the same parents.
+    let mut restored = vec![];
+    if let Some(entry) = walk_predecessors(&repo, slice::from_ref(commit.id())).next() {
+        for predecessor in entry?.predecessors() {
+            let predecessor = predecessor?;
+            if predecessor.parent_ids() != commit.parent_ids() {
+                continue;
+            }
+            for (path, conflict) in predecessor.tree()?.conflicts() {
+                if !matcher.matches(&path) {
+                    continue;
+                }
+                let conflict = conflict?;
+                let value = tree.path_value(&path)?;
+                if value.is_resolved()
+                    && cache.resolve(store, &path, &conflict)?.as_ref() == Some(&value)
+                {
+                    restored.push((path.clone(), conflict.clone()));
+                    conflicts.push((path, conflict));
+                }
+            }
+        }
+    }
+    if !restored.is_empty() {
+        workspace_command.check_rewritable([commit.id()])?;
+    }
+
+    let mut num_forgotten = 0;
+    for (path, conflict) in &conflicts {
+        num_forgotten += cache.forget(store, path, conflict)?;
+    }
+    if num_forgotten == 0 {
+        writeln!(ui.status(), "No recorded resolutions found")?;
+        return Ok(());
+    }
+    writeln!(
+        ui.status(),
+        "Forgot {num_forgotten} recorded conflict resolutions"
+    )?;
+    if restored.is_empty() {
+        return Ok(());
+    }
+
+    let mut tree_builder = MergedTreeBuilder::new(commit.tree_id().clone());
+    for (path, conflict) in restored {
+        tree_builder.set_or_remove(path, conflict);
+    }
+    let new_tree_id = tree_builder.write_tree(store)?;
+    let mut tx = workspace_command.start_transaction();
+    tx.repo_mut()
+        .rewrite_commit(commit)
+        .set_tree_id(new_tree_id)
+        .write()?;
+    tx.finish(
+        ui,
+        format!(
+            "Forget conflict resolutions in commit {}",
+            commit.id().hex()
+        ),
+    )?;
+    Ok(())
+}
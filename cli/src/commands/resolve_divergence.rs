@@ -0,0 +1,304 @@
+// Copyright 2025 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::io::Write as _;
+use std::slice;
+
+use clap_complete::ArgValueCompleter;
+use itertools::Itertools as _;
+use jj_lib::commit::Commit;
+use jj_lib::commit::CommitIteratorExt as _;
+use jj_lib::matchers::EverythingMatcher;
+use jj_lib::object_id::HexPrefix;
+use jj_lib::revset::RevsetExpression;
+use jj_lib::rewrite::rebase_to_dest_parent;
+use pollster::FutureExt as _;
+use tracing::instrument;
+
+use crate::cli_util::CommandHelper;
+use crate::cli_util::RevisionArg;
+use crate::cli_util::WorkspaceCommandHelper;
+use crate::cli_util::print_updated_commits;
+use crate::cli_util::short_change_hash;
+use crate::cli_util::short_commit_hash;
+use crate::command_error::CommandError;
+use crate::command_error::user_error;
+use crate::command_error::user_error_with_hint;
+use crate::complete;
+use crate::description_util::combine_messages_for_editing;
+use crate::description_util::description_template;
+use crate::description_util::edit_description;
+use crate::diff_util::DiffFormat;
+use crate::ui::Ui;
+
+/// Resolve a divergent change
+///
+/// A change is divergent when several visible commits have its change ID,
+/// typically because it was rewritten concurrently, e.g. on different
+/// machines. This command shows how the divergent commits differ in their
+/// parents, descriptions and contents, and then either keeps one of them or
+/// merges them into a single commit. The other commits are abandoned, and their
+/// descendants are rebased onto the remaining commit.
+///
+/// Unless `--keep` or `--merge` is specified, you will be asked which commit to
+/// keep.
+///
+/// When merging, the changes from the other commits are applied on top of the
+/// first listed commit, which may result in conflicts. The merged commit has
+/// all the divergent commits as predecessors, so `jj evolog` shows where it
+/// came from.
+#[derive(clap::Args, Clone, Debug)]
+pub(crate) struct ResolveDivergenceArgs {
+    /// The change ID (or a unique prefix of it) of the divergent change
+    #[arg(value_name = "CHANGE_ID")]
+    change_id: String,
+    /// Keep this commit and abandon the other divergent commits
+    #[arg(
+        long,
+        value_name = "REVSET",
+        conflicts_with = "merge",
+        add = ArgValueCompleter::new(complete::revset_expression_mutable),
+    )]
+    keep: Option<RevisionArg>,
+    /// Merge the divergent commits into one commit
+    #[arg(long)]
+    merge: bool,
+}
+
+enum Resolution {
+    Keep(usize),
+    Merge,
+}
+
+#[instrument(skip_all)]
+pub(crate) fn cmd_resolve_divergence(
+    ui: &mut Ui,
+    command: &CommandHelper,
+    args: &ResolveDivergenceArgs,
+) -> Result<(), CommandError> {
+    let mut workspace_command = command.workspace_helper(ui)?;
+    let prefix = HexPrefix::try_from_reverse_hex(&args.change_id)
+        .ok_or_else(|| user_error(format!("Invalid change ID: {}", args.change_id)))?;
+    let commits: Vec<Commit> = workspace_command
+        .attach_revset_evaluator(RevsetExpression::change_id_prefix(prefix))
+        .evaluate_to_commits()?
+        .try_collect()?;
+    let change_id = match commits.first() {
+        Some(commit) => short_change_hash(commit.change_id()),
+        None => {
+            return Err(user_error(format!(
+                "Change ID `{}` doesn't exist",
+                args.change_id
+            )));
+        }
+    };
+    if commits.len() == 1 {
+        return Err(user_error(format!("Change {change_id} is not divergent")));
+    }
+    let commit_ids = commits.iter().ids().cloned().collect_vec();
+    let has_ancestor_commits = !RevsetExpression::commits(commit_ids.clone())
+        .parents()
+        .ancestors()
+        .intersection(&RevsetExpression::commits(commit_ids.clone()))
+        .evaluate(workspace_command.repo().as_ref())?
+        .is_empty();
+    if has_ancestor_commits {
+        return Err(user_error_with_hint(
+            "Cannot resolve divergence when one divergent commit is an ancestor of another",
+            "Use `jj abandon` or `jj squash` to combine the commits instead",
+        ));
+    }
+    workspace_command.check_rewritable(&commit_ids)?;
+
+    let resolution = if let Some(keep) = &args.keep {
+        let kept = workspace_command.resolve_single_rev(ui, keep)?;
+        let index = commits
+            .iter()
+            .position(|commit| commit.id() == kept.id())
+            .ok_or_else(|| {
+                user_error(format!(
+                    "Commit {} is not one of the divergent commits",
+                    short_commit_hash(kept.id())
+                ))
+            })?;
+        Resolution::Keep(index)
+    } else if args.merge {
+        Resolution::Merge
+    } else {
+        show_divergent_commits(ui, &workspace_command, &change_id, &commits)?;
+        if !Ui::can_prompt() {
+            return Err(user_error_with_hint(
+                "Cannot prompt for input since the output is not connected to a terminal",
+                "Use `--keep` or `--merge` to choose how to resolve the divergence",
+            ));
+        }
+        ui.prompt_choice_with(
+            r#"Enter the number of the commit to keep, or "m" to merge them"#,
+            None,
+            |input| {
+                if input.eq_ignore_ascii_case("m") {
+                    return Ok(Resolution::Merge);
+                }
+                match input.parse::<usize>() {
+                    Ok(n) if (1..=commits.len()).contains(&n) => Ok(Resolution::Keep(n - 1)),
+                    _ => Err("unrecognized response"),
+                }
+            },
+        )?
+    };
+
+    let text_editor = workspace_command.text_editor()?;
+    let mut tx = workspace_command.start_transaction();
+    let merge = matches!(resolution, Resolution::Merge);
+    let (result, others) = match resolution {
+        Resolution::Keep(index) => {
+            let others = commits
+                .iter()
+                .enumerate()
+                .filter(|&(i, _)| i != index)
+                .map(|(_, commit)| commit.clone())
+                .collect_vec();
+            (commits[index].clone(), others)
+        }
+        Resolution::Merge => {
+            let (destination, sources) = commits.split_first().unwrap();
+            let mut tree = destination.tree()?;
+            for source in sources {
+                tree = tree
+                    .merge(source.parent_tree(tx.repo())?, source.tree()?)
+                    .block_on()?;
+            }
+            let mut commit_builder = tx
+                .repo_mut()
+                .rewrite_commit(destination)
+                .set_tree_id(tree.id())
+                .set_predecessors(commit_ids.clone())
+                .detach();
+            let descriptions = commits
+                .iter()
+                .map(|commit| commit.description())
+                .filter(|description| !description.is_empty())
+                .unique()
+                .collect_vec();
+            let description = match *descriptions.as_slice() {
+                [] => String::new(),
+                [description] => description.to_owned(),
+                [_, _, ..] => {
+                    let combined = combine_messages_for_editing(
+                        ui,
+                        &tx,
+                        sources,
+                        destination,
+                        &commit_builder,
+                    )?;
+                    commit_builder.set_description(combined);
+                    let temp_commit = commit_builder.write_hidden()?;
+                    let intro = "Enter a description for the merged commit.";
+                    let template = description_template(ui, &tx, intro, &temp_commit)?;
+                    edit_description(&text_editor, &template)?
+                }
+            };
+            commit_builder.set_description(description);
+            let merged = commit_builder.write(tx.repo_mut())?;
+            (merged, sources.to_vec())
+        }
+    };
+    for commit in &others {
+        tx.repo_mut()
+            .set_rewritten_commit(commit.id().clone(), result.id().clone());
+    }
+    let num_rebased = tx.repo_mut().rebase_descendants()?;
+
+    if let Some(mut formatter) = ui.status_formatter() {
+        if merge {
+            write!(formatter, "Merged the divergent commits into ")?;
+        } else {
+            write!(formatter, "Kept commit ")?;
+        }
+        tx.write_commit_summary(formatter.as_mut(), &result)?;
+        writeln!(formatter)?;
+        writeln!(formatter, "Abandoned {} divergent commits:", others.len())?;
+        print_updated_commits(
+            formatter.as_mut(),
+            &tx.base_workspace_helper().commit_summary_template(),
+            &others,
+        )?;
+        if num_rebased > 0 {
+            writeln!(formatter, "Rebased {num_rebased} descendant commits")?;
+        }
+    }
+    tx.finish(ui, format!("resolve divergence of change {change_id}"))?;
+    Ok(())
+}
+
+/// Prints the divergent commits and how they differ from the first one.
+fn show_divergent_commits(
+    ui: &Ui,
+    workspace_command: &WorkspaceCommandHelper,
+    change_id: &str,
+    commits: &[Commit],
+) -> Result<(), CommandError> {
+    let Some(mut formatter) = ui.status_formatter() else {
+        return Ok(());
+    };
+    let repo = workspace_command.repo();
+    let template = workspace_command.commit_summary_template();
+    writeln!(
+        formatter,
+        "Change {change_id} has {} divergent commits:",
+        commits.len()
+    )?;
+    for (i, commit) in commits.iter().enumerate() {
+        write!(formatter, "{}: ", i + 1)?;
+        template.format(commit, formatter.as_mut())?;
+        writeln!(formatter)?;
+        for parent in commit.parents() {
+            write!(formatter, "   Parent: ")?;
+            template.format(&parent?, formatter.as_mut())?;
+            writeln!(formatter)?;
+        }
+    }
+
+    let diff_renderer = workspace_command.diff_renderer(vec![DiffFormat::Summary]);
+    let first = &commits[0];
+    for (i, commit) in commits.iter().enumerate().skip(1) {
+        writeln!(formatter, "Differences between 1 and {}:", i + 1)?;
+        let mut same = true;
+        if commit.parent_ids() != first.parent_ids() {
+            writeln!(formatter, "  The parents differ")?;
+            same = false;
+        }
+        if commit.description() != first.description() {
+            writeln!(formatter, "  The descriptions differ")?;
+            same = false;
+        }
+        let from_tree = rebase_to_dest_parent(repo.as_ref(), slice::from_ref(first), commit)?;
+        if from_tree.id() != *commit.tree_id() {
+            writeln!(formatter, "  The contents differ:")?;
+            diff_renderer.show_inter_diff(
+                ui,
+                formatter.as_mut(),
+                slice::from_ref(first),
+                commit,
+                &EverythingMatcher,
+                ui.term_width(),
+            )?;
+            same = false;
+        }
+        if same {
+            writeln!(formatter, "  No differences")?;
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,177 @@
+// Copyright 2025 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::io::Write as _;
+
+use clap_complete::ArgValueCompleter;
+use itertools::Itertools as _;
+use jj_lib::backend::CommitId;
+use jj_lib::commit::Commit;
+use jj_lib::repo::Repo as _;
+use jj_lib::rewrite::MoveCommitsLocation;
+use jj_lib::rewrite::MoveCommitsTarget;
+use jj_lib::rewrite::RebaseOptions;
+use jj_lib::rewrite::RebasedCommit;
+use jj_lib::rewrite::move_commits;
+use tracing::instrument;
+
+use crate::cli_util::CommandHelper;
+use crate::cli_util::RevisionArg;
+use crate::cli_util::short_change_hash;
+use crate::command_error::CommandError;
+use crate::command_error::user_error_with_hint;
+use crate::complete;
+use crate::ui::Ui;
+
+/// Serialize revisions into a linear chain
+///
+/// This is the inverse of `jj parallelize`. Running `jj serialize -r '1 | 2'`
+/// will transform the history like this:
+/// ```text
+///                 3
+///    3            |
+///   / \           2
+///  1   2    ->    |
+///   \ /           1
+///    0            |
+///                 0
+/// ```
+///
+/// The first revision in the chain stays where it is. Each following revision
+/// is rebased, together with its descendants, onto the previous one. Merge
+/// commits that end up with one parent being an ancestor of another, like 3
+/// above, are simplified. Bookmarks pointing to the rebased revisions are
+/// updated, and any conflicts caused by the new order are recorded in the
+/// rebased revisions.
+///
+/// The revisions are ordered by `--order`. A revision cannot be placed after
+/// one of its descendants. Merge commits cannot be serialized, unless they come
+/// first and thus stay where they are.
+#[derive(clap::Args, Clone, Debug)]
+#[command(verbatim_doc_comment)]
+pub(crate) struct SerializeArgs {
+    /// Revisions to serialize
+    #[arg(
+        long,
+        short,
+        value_name = "REVSETS",
+        required = true,
+        add = ArgValueCompleter::new(complete::revset_expression_mutable),
+    )]
+    revisions: Vec<RevisionArg>,
+    /// The order to stack the revisions in
+    #[arg(long, value_enum, default_value_t = SerializeOrder::Topo)]
+    order: SerializeOrder,
+}
+
+/// The order to stack revisions in.
+#[derive(clap::ValueEnum, Clone, Copy, Debug, Eq, PartialEq)]
+enum SerializeOrder {
+    /// Ancestors before descendants, and older revisions first otherwise.
+    Topo,
+    /// By author timestamp, oldest first.
+    Date,
+    /// By description, in lexicographic order.
+    Description,
+}
+
+#[instrument(skip_all)]
+pub(crate) fn cmd_serialize(
+    ui: &mut Ui,
+    command: &CommandHelper,
+    args: &SerializeArgs,
+) -> Result<(), CommandError> {
+    let mut workspace_command = command.workspace_helper(ui)?;
+    // The revset is evaluated with children before parents. Reversing it gives
+    // the topological order, which the other orders use to break ties.
+    let mut target_commits: Vec<Commit> = workspace_command
+        .parse_union_revsets(ui, &args.revisions)?
+        .evaluate_to_commits()?
+        .try_collect()?;
+    target_commits.reverse();
+    match args.order {
+        SerializeOrder::Topo => {}
+        SerializeOrder::Date => {
+            target_commits.sort_by_key(|commit| commit.author().timestamp.timestamp);
+        }
+        SerializeOrder::Description => {
+            target_commits.sort_by(|a, b| a.description().cmp(b.description()));
+        }
+    }
+    let original_ids = target_commits
+        .iter()
+        .map(|commit| commit.id().clone())
+        .collect_vec();
+    workspace_command.check_rewritable(&original_ids)?;
+
+    let mut tx = workspace_command.start_transaction();
+    let options = RebaseOptions {
+        simplify_ancestor_merge: true,
+        ..Default::default()
+    };
+    // The current IDs of the target commits, and the original commits of all
+    // commits rewritten so far.
+    let mut current_ids = original_ids.clone();
+    let mut original_of: HashMap<CommitId, CommitId> = HashMap::new();
+    let mut rewritten: HashSet<CommitId> = HashSet::new();
+    for i in 1..current_ids.len() {
+        let (parent_id, commit_id) = (&current_ids[i - 1], &current_ids[i]);
+        if target_commits[i].parent_ids().len() > 1 {
+            return Err(user_error_with_hint(
+                format!(
+                    "Cannot place merge change {} onto a single parent",
+                    short_change_hash(target_commits[i].change_id()),
+                ),
+                "Leave the merge out of the revisions, or place it first",
+            ));
+        }
+        if tx.repo().index().is_ancestor(commit_id, parent_id) {
+            return Err(user_error_with_hint(
+                format!(
+                    "Cannot place change {} after its descendant {}",
+                    short_change_hash(target_commits[i].change_id()),
+                    short_change_hash(target_commits[i - 1].change_id()),
+                ),
+                "Use `--order topo` to keep ancestors before their descendants",
+            ));
+        }
+        let loc = MoveCommitsLocation {
+            new_parent_ids: vec![parent_id.clone()],
+            new_child_ids: vec![],
+            target: MoveCommitsTarget::Roots(vec![commit_id.clone()]),
+        };
+        let stats = move_commits(tx.repo_mut(), &loc, &options)?;
+        for (old_id, rebased_commit) in stats.rebased_commits {
+            let RebasedCommit::Rewritten(new_commit) = rebased_commit else {
+                continue;
+            };
+            if let Some(current_id) = current_ids.iter_mut().find(|id| **id == old_id) {
+                *current_id = new_commit.id().clone();
+            }
+            let original_id = original_of.remove(&old_id).unwrap_or(old_id);
+            original_of.insert(new_commit.id().clone(), original_id.clone());
+            rewritten.insert(original_id);
+        }
+    }
+
+    if let Some(mut formatter) = ui.status_formatter() {
+        if !rewritten.is_empty() {
+            writeln!(formatter, "Rebased {} commits", rewritten.len())?;
+        }
+    }
+    tx.finish(ui, format!("serialize {} commits", original_ids.len()))?;
+    Ok(())
+}
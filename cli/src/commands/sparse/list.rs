@@ -15,6 +15,7 @@
 use std::io::Write as _;
 use std::path::Path;
 
+use jj_lib::sparse::SparseMode;
 use tracing::instrument;
 
 use crate::cli_util::CommandHelper;
@@ -26,6 +27,9 @@ use crate::ui::Ui;
 /// By default, a newly cloned or initialized repo will have have a pattern
 /// matching all files from the repo root. That pattern is rendered as `.` (a
 /// single period).
+///
+/// If the patterns are in cone mode, or are read from a sparse profile, that is
+/// reported on stderr.
 #[derive(clap::Args, Clone, Debug)]
 pub struct SparseListArgs {}
 
@@ -36,12 +40,23 @@ pub fn cmd_sparse_list(
     _args: &SparseListArgs,
 ) -> Result<(), CommandError> {
     let workspace_command = command.workspace_helper(ui)?;
-    for path in workspace_command.working_copy().sparse_patterns()? {
+    let working_copy = workspace_command.working_copy();
+    for path in working_copy.sparse_patterns()? {
         writeln!(
             ui.stdout(),
             "{}",
             path.to_fs_path_unchecked(Path::new("")).display()
         )?;
     }
+    if working_copy.sparse_mode()? == SparseMode::Cone {
+        writeln!(ui.status(), "The patterns are in cone mode.")?;
+    }
+    if let Some(profile) = working_copy.sparse_profile()? {
+        writeln!(
+            ui.status(),
+            "The patterns are read from the sparse profile {}",
+            profile.to_fs_path_unchecked(Path::new("")).display()
+        )?;
+    }
     Ok(())
 }
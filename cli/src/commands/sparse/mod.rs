@@ -19,6 +19,10 @@ mod set;
 
 use clap::Subcommand;
 use jj_lib::repo_path::RepoPathBuf;
+use jj_lib::working_copy::CheckoutError;
+use jj_lib::working_copy::CheckoutOptions;
+use jj_lib::working_copy::CheckoutStats;
+use jj_lib::working_copy::LockedWorkingCopy;
 use tracing::instrument;
 
 use self::edit::SparseEditArgs;
@@ -34,6 +38,7 @@ use crate::cli_util::WorkspaceCommandHelper;
 use crate::cli_util::print_checkout_stats;
 use crate::command_error::CommandError;
 use crate::command_error::internal_error_with_message;
+use crate::command_error::user_error;
 use crate::ui::Ui;
 
 /// Manage which paths from the working-copy commit are present in the working
@@ -64,16 +69,36 @@ fn update_sparse_patterns_with(
     ui: &mut Ui,
     workspace_command: &mut WorkspaceCommandHelper,
     f: impl FnOnce(&mut Ui, &[RepoPathBuf]) -> Result<Vec<RepoPathBuf>, CommandError>,
+) -> Result<(), CommandError> {
+    update_sparse_with(ui, workspace_command, |ui, locked_wc, checkout_options| {
+        let new_patterns = f(ui, locked_wc.sparse_patterns()?)?;
+        locked_wc
+            .set_sparse_patterns(new_patterns, checkout_options)
+            .map_err(sparse_update_error)
+    })
+}
+
+fn update_sparse_with(
+    ui: &mut Ui,
+    workspace_command: &mut WorkspaceCommandHelper,
+    f: impl FnOnce(
+        &mut Ui,
+        &mut dyn LockedWorkingCopy,
+        &CheckoutOptions,
+    ) -> Result<CheckoutStats, CommandError>,
 ) -> Result<(), CommandError> {
     let checkout_options = workspace_command.checkout_options();
     let (mut locked_ws, wc_commit) = workspace_command.start_working_copy_mutation()?;
-    let new_patterns = f(ui, locked_ws.locked_wc().sparse_patterns()?)?;
-    let stats = locked_ws
-        .locked_wc()
-        .set_sparse_patterns(new_patterns, &checkout_options)
-        .map_err(|err| internal_error_with_message("Failed to update working copy paths", err))?;
+    let stats = f(ui, locked_ws.locked_wc(), &checkout_options)?;
     let operation_id = locked_ws.locked_wc().old_operation_id().clone();
     locked_ws.finish(operation_id)?;
-    print_checkout_stats(ui, &stats, &wc_commit)?;
+    print_checkout_stats(ui, &stats, &wc_commit, workspace_command.path_converter())?;
     Ok(())
 }
+
+fn sparse_update_error(err: CheckoutError) -> CommandError {
+    match err {
+        err @ CheckoutError::SparseProfile { .. } => user_error(err),
+        err => internal_error_with_message("Failed to update working copy paths", err),
+    }
+}
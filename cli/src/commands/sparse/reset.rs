@@ -13,14 +13,19 @@
 // limitations under the License.
 
 use jj_lib::repo_path::RepoPathBuf;
+use jj_lib::sparse::SparseMode;
 use tracing::instrument;
 
-use super::update_sparse_patterns_with;
+use super::sparse_update_error;
+use super::update_sparse_with;
 use crate::cli_util::CommandHelper;
 use crate::command_error::CommandError;
 use crate::ui::Ui;
 
 /// Reset the patterns to include all files in the working copy
+///
+/// This also turns off cone mode and stops reading the patterns from the
+/// sparse profile.
 #[derive(clap::Args, Clone, Debug)]
 pub struct SparseResetArgs {}
 
@@ -31,7 +36,9 @@ pub fn cmd_sparse_reset(
     _args: &SparseResetArgs,
 ) -> Result<(), CommandError> {
     let mut workspace_command = command.workspace_helper(ui)?;
-    update_sparse_patterns_with(ui, &mut workspace_command, |_ui, _old_patterns| {
-        Ok(vec![RepoPathBuf::root()])
+    update_sparse_with(ui, &mut workspace_command, |_ui, locked_wc, options| {
+        locked_wc
+            .set_sparse_patterns_and_mode(vec![RepoPathBuf::root()], SparseMode::Prefix, options)
+            .map_err(sparse_update_error)
     })
 }
@@ -16,9 +16,11 @@ use std::collections::HashSet;
 
 use itertools::Itertools as _;
 use jj_lib::repo_path::RepoPathBuf;
+use jj_lib::sparse::SparseMode;
 use tracing::instrument;
 
-use super::update_sparse_patterns_with;
+use super::sparse_update_error;
+use super::update_sparse_with;
 use crate::cli_util::CommandHelper;
 use crate::command_error::CommandError;
 use crate::ui::Ui;
@@ -28,6 +30,18 @@ use crate::ui::Ui;
 /// For example, if all you need is the `README.md` and the `lib/`
 /// directory, use `jj sparse set --clear --add README.md --add lib`.
 /// If you no longer need the `lib` directory, use `jj sparse set --remove lib`.
+///
+/// With `--cone`, the files directly in the parent directories of each pattern
+/// are also present, like Git's cone mode. For example, `jj sparse set --cone
+/// --clear --add lib/core` includes all files in `lib/core`, plus the files
+/// in `lib/` and in the repository root.
+///
+/// The patterns can also be read from a sparse profile checked into the
+/// repository, e.g. `jj sparse set --profile tools/profiles/backend.sparse`.
+/// The profile lists one path per line. Blank lines and lines starting with
+/// `#` are ignored. The patterns are read again whenever the profile changes in
+/// the checked-out commit, until they are updated by `--add`, `--remove`, or
+/// `--clear`.
 #[derive(clap::Args, Clone, Debug)]
 pub struct SparseSetArgs {
     /// Patterns to add to the working copy
@@ -48,6 +62,21 @@ pub struct SparseSetArgs {
     /// Include no files in the working copy (combine with --add)
     #[arg(long)]
     clear: bool,
+    /// Also include the files in the parent directories of the patterns
+    #[arg(long, conflicts_with = "no_cone")]
+    cone: bool,
+    /// Include only the files under the patterns (the default)
+    #[arg(long)]
+    no_cone: bool,
+    /// Read the patterns from a sparse profile in the working-copy commit
+    #[arg(
+        long,
+        value_name = "PATH",
+        conflicts_with_all = ["add", "remove", "clear"],
+        value_hint = clap::ValueHint::FilePath,
+        value_parser = |s: &str| RepoPathBuf::from_relative_path(s),
+    )]
+    profile: Option<RepoPathBuf>,
 }
 
 #[instrument(skip_all)]
@@ -57,17 +86,40 @@ pub fn cmd_sparse_set(
     args: &SparseSetArgs,
 ) -> Result<(), CommandError> {
     let mut workspace_command = command.workspace_helper(ui)?;
-    update_sparse_patterns_with(ui, &mut workspace_command, |_ui, old_patterns| {
-        let mut new_patterns = HashSet::new();
-        if !args.clear {
-            new_patterns.extend(old_patterns.iter().cloned());
-            for path in &args.remove {
-                new_patterns.remove(path);
+    let new_mode = if args.cone {
+        Some(SparseMode::Cone)
+    } else if args.no_cone {
+        Some(SparseMode::Prefix)
+    } else {
+        None
+    };
+    update_sparse_with(ui, &mut workspace_command, |_ui, locked_wc, options| {
+        let mode = match new_mode {
+            Some(mode) => mode,
+            None => locked_wc.sparse_mode()?,
+        };
+        let stats = if let Some(profile) = &args.profile {
+            locked_wc.set_sparse_profile(profile.clone(), mode, options)
+        } else if !args.add.is_empty() || !args.remove.is_empty() || args.clear {
+            let mut new_patterns = HashSet::new();
+            if !args.clear {
+                new_patterns.extend(locked_wc.sparse_patterns()?.iter().cloned());
+                for path in &args.remove {
+                    new_patterns.remove(path);
+                }
+            }
+            for path in &args.add {
+                new_patterns.insert(path.to_owned());
             }
-        }
-        for path in &args.add {
-            new_patterns.insert(path.to_owned());
-        }
-        Ok(new_patterns.into_iter().sorted_unstable().collect())
+            let new_patterns = new_patterns.into_iter().sorted_unstable().collect();
+            locked_wc.set_sparse_patterns_and_mode(new_patterns, mode, options)
+        } else if let Some(profile) = locked_wc.sparse_profile()?.map(ToOwned::to_owned) {
+            // Keep reading the patterns from the profile
+            locked_wc.set_sparse_profile(profile, mode, options)
+        } else {
+            let patterns = locked_wc.sparse_patterns()?.to_vec();
+            locked_wc.set_sparse_patterns_and_mode(patterns, mode, options)
+        };
+        stats.map_err(sparse_update_error)
     })
 }
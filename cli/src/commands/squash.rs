@@ -169,6 +169,7 @@ pub(crate) fn cmd_squash(
         .check_rewritable(sources.iter().chain(std::iter::once(&destination)).ids())?;
 
     let mut tx = workspace_command.start_transaction();
+    tx.advance_bookmarks()?;
     let tx_description = format!("squash commits into {}", destination.id().hex());
     let source_commits = select_diff(&tx, &sources, &destination, &matcher, &diff_selector)?;
     if let Some(squashed) = rewrite::squash_commits(
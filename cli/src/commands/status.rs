@@ -20,6 +20,7 @@ use jj_lib::repo_path::RepoPath;
 use jj_lib::repo_path::RepoPathBuf;
 use jj_lib::revset::RevsetExpression;
 use jj_lib::revset::RevsetFilterPredicate;
+use jj_lib::working_copy::UntrackedReason;
 use pollster::FutureExt as _;
 use tracing::instrument;
 
@@ -103,25 +104,34 @@ pub(crate) fn cmd_status(
             }
 
             if wc_has_untracked {
+                let untracked_paths = &snapshot_stats.untracked_paths;
                 writeln!(formatter, "Untracked paths:")?;
                 formatter.with_label("diff", |formatter| {
-                    visit_collapsed_untracked_files(
-                        snapshot_stats.untracked_paths.keys(),
-                        tree,
-                        |path, is_dir| {
-                            let ui_path = workspace_command.path_converter().format_file_path(path);
-                            writeln!(
-                                formatter.labeled("untracked"),
-                                "? {ui_path}{}",
-                                if is_dir {
-                                    std::path::MAIN_SEPARATOR_STR
-                                } else {
-                                    ""
-                                }
-                            )?;
-                            Ok(())
-                        },
-                    )
+                    visit_collapsed_untracked_files(untracked_paths.keys(), tree, |path, is_dir| {
+                        let ui_path = workspace_command.path_converter().format_file_path(path);
+                        // A collapsed directory is only annotated if all the files
+                        // in it are untracked for the same reason.
+                        let reason = untracked_paths
+                            .range(path.to_owned()..)
+                            .take_while(|(untracked_path, _)| untracked_path.starts_with(path))
+                            .map(|(_, reason)| untracked_reason_label(reason))
+                            .all_equal_value()
+                            .ok();
+                        write!(
+                            formatter.labeled("untracked"),
+                            "? {ui_path}{}",
+                            if is_dir {
+                                std::path::MAIN_SEPARATOR_STR
+                            } else {
+                                ""
+                            }
+                        )?;
+                        if let Some(reason) = reason {
+                            write!(formatter.labeled("untracked"), " ({reason})")?;
+                        }
+                        writeln!(formatter)?;
+                        Ok(())
+                    })
                     .block_on()
                 })?;
             }
@@ -227,6 +237,18 @@ pub(crate) fn cmd_status(
     Ok(())
 }
 
+/// Describes why a new file isn't tracked, briefly.
+fn untracked_reason_label(reason: &UntrackedReason) -> String {
+    match reason {
+        UntrackedReason::FileTooLarge { .. } => "too large".to_owned(),
+        UntrackedReason::FileNotAutoTracked => "not auto-tracked".to_owned(),
+        UntrackedReason::IgnoredByRule { rule } => format!("ignored by snapshot rule `{rule}`"),
+        UntrackedReason::RefusedByRule { rule, .. } => {
+            format!("refused by snapshot rule `{rule}`")
+        }
+    }
+}
+
 async fn visit_collapsed_untracked_files(
     untracked_paths: impl IntoIterator<Item = impl AsRef<RepoPath>>,
     tree: MergedTree,
@@ -0,0 +1,105 @@
+// Copyright 2025 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use clap_complete::ArgValueCandidates;
+use itertools::Itertools as _;
+use jj_lib::object_id::ObjectId as _;
+use jj_lib::operation::Operation;
+use jj_lib::repo::RepoLoader;
+
+use crate::cli_util::CommandHelper;
+use crate::command_error::CommandError;
+use crate::commands::operation::DEFAULT_UNDO_WHAT;
+use crate::commands::operation::UndoWhatToRestore;
+use crate::commands::operation::undo::OperationUndoArgs;
+use crate::commands::operation::undo::UNDO_OF_TAG;
+use crate::commands::operation::undo::cmd_op_undo;
+use crate::commands::operation::undo::parent_to_revert;
+use crate::commands::operation::undo::revert_operation;
+use crate::commands::operation::undo::tagged_operation_id;
+use crate::commands::operation::undo::tx_description;
+use crate::complete;
+use crate::ui::Ui;
+
+/// Undo the last operation
+///
+/// If the last operation was created by `jj undo`, the operation before the
+/// undone one is undone instead. Running `jj undo` repeatedly therefore walks
+/// backward through the operation log. Use `jj redo` to reapply the undone
+/// operations.
+///
+/// Use `jj op log` to see the recorded `undo-of` tags.
+#[derive(clap::Args, Clone, Debug)]
+pub struct UndoArgs {
+    /// Undo only this operation (same as `jj op undo`)
+    ///
+    /// Use `jj op log` to find an operation to undo.
+    #[arg(add = ArgValueCandidates::new(complete::operations))]
+    operation: Option<String>,
+
+    /// What portions of the local state to restore (can be repeated)
+    ///
+    /// This option is EXPERIMENTAL.
+    #[arg(long, value_enum, default_values_t = DEFAULT_UNDO_WHAT)]
+    what: Vec<UndoWhatToRestore>,
+}
+
+pub fn cmd_undo(ui: &mut Ui, command: &CommandHelper, args: &UndoArgs) -> Result<(), CommandError> {
+    if let Some(operation) = &args.operation {
+        let args = OperationUndoArgs {
+            operation: operation.clone(),
+            what: args.what.clone(),
+        };
+        return cmd_op_undo(ui, command, &args);
+    }
+
+    let mut workspace_command = command.workspace_helper(ui)?;
+    let head_op = workspace_command.repo().operation().clone();
+    let bad_op = find_op_to_undo(workspace_command.repo().loader(), head_op)?;
+    let parent_of_bad_op = parent_to_revert(&bad_op, "undo")?;
+
+    let mut tx = workspace_command.start_transaction();
+    revert_operation(&mut tx, &bad_op, &parent_of_bad_op, &args.what)?;
+    tx.set_tag(UNDO_OF_TAG.to_owned(), bad_op.id().hex());
+    if let Some(mut formatter) = ui.status_formatter() {
+        write!(formatter, "Undid operation: ")?;
+        let template = tx.base_workspace_helper().operation_summary_template();
+        template.format(&bad_op, formatter.as_mut())?;
+        writeln!(formatter)?;
+    }
+    tx.finish(ui, tx_description(&bad_op))?;
+    Ok(())
+}
+
+/// Returns the operation whose changes are visible at `op` and should be
+/// undone next.
+///
+/// If `op` undid the operation that would have been undone next at its parent,
+/// the undo chain continues from the parent of the undone operation.
+fn find_op_to_undo(repo_loader: &RepoLoader, op: Operation) -> Result<Operation, CommandError> {
+    let Some(undone_id) = tagged_operation_id(&op, UNDO_OF_TAG) else {
+        return Ok(op);
+    };
+    let Ok(parent_op) = op.parents().exactly_one() else {
+        return Ok(op);
+    };
+    if find_op_to_undo(repo_loader, parent_op?)?.id() != &undone_id {
+        return Ok(op);
+    }
+    let undone_op = repo_loader.load_operation(&undone_id)?;
+    match undone_op.parents().exactly_one() {
+        Ok(parent_of_undone_op) => find_op_to_undo(repo_loader, parent_of_undone_op?),
+        Err(_) => Ok(op),
+    }
+}
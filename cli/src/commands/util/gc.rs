@@ -12,13 +12,22 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use std::collections::HashSet;
+use std::io::Write as _;
 use std::slice;
 use std::time::Duration;
 use std::time::SystemTime;
 
+use itertools::Itertools as _;
+use jj_lib::config::ConfigGetResultExt as _;
+use jj_lib::op_store::OperationId;
+use jj_lib::op_walk;
+use jj_lib::operation::Operation;
 use jj_lib::repo::Repo as _;
 
 use crate::cli_util::CommandHelper;
+use crate::cli_util::command_name;
+use crate::cli_util::short_operation_hash;
 use crate::command_error::CommandError;
 use crate::command_error::user_error;
 use crate::ui::Ui;
@@ -27,6 +36,11 @@ use crate::ui::Ui;
 ///
 /// To garbage-collect old operations and the commits/objects referenced by
 /// them, run `jj op abandon ..<some old operation>` before `jj util gc`.
+///
+/// If the `gc.operations` retention policy is configured, the operation log is
+/// rewritten according to the policy before garbage collection. Operations
+/// abandoned by the policy are then pruned like the ones abandoned by `jj op
+/// abandon`.
 #[derive(clap::Args, Clone, Debug)]
 pub struct UtilGcArgs {
     /// Time threshold
@@ -56,7 +70,12 @@ pub fn cmd_util_gc(
         Some("now") => SystemTime::now() - Duration::ZERO,
         _ => return Err(user_error("--expire only accepts 'now'")),
     };
-    let workspace_command = command.workspace_helper(ui)?;
+    let mut workspace_command = command.workspace_helper(ui)?;
+    if let Some(mut policy) = OperationRetentionPolicy::from_settings(command)? {
+        apply_operation_retention_policy(ui, command, &mut policy)?;
+        // Reload the repo at the rewritten head operation.
+        workspace_command = command.workspace_helper_no_snapshot(ui)?;
+    }
 
     let repo = workspace_command.repo();
     repo.op_store()
@@ -64,3 +83,210 @@ pub fn cmd_util_gc(
     repo.store().gc(repo.index(), keep_newer)?;
     Ok(())
 }
+
+/// Which operations to keep when rewriting the operation log.
+#[derive(Clone, Debug)]
+struct OperationRetentionPolicy {
+    /// The CLI, for parsing the command lines of operations.
+    app: clap::Command,
+    /// Number of recent operations to keep.
+    keep_last: Option<usize>,
+    /// Commands (such as `["git", "push"]`) whose operations are always kept.
+    keep_commands: Vec<Vec<String>>,
+    /// Whether to keep only the latest operation of consecutive snapshots.
+    squash_snapshots: bool,
+}
+
+impl OperationRetentionPolicy {
+    /// Loads the policy, or returns `None` if no operation would be abandoned.
+    fn from_settings(command: &CommandHelper) -> Result<Option<Self>, CommandError> {
+        let settings = command.settings();
+        let keep_last = settings
+            .get::<usize>("gc.operations.keep-last")
+            .optional()?;
+        let keep_commands = settings
+            .get::<Vec<String>>("gc.operations.keep-commands")?
+            .iter()
+            .map(|command| command.split_whitespace().map(str::to_owned).collect())
+            .collect();
+        let squash_snapshots = settings.get_bool("gc.operations.squash-snapshots")?;
+        if keep_last.is_none() && !squash_snapshots {
+            return Ok(None);
+        }
+        Ok(Some(Self {
+            app: command.app().clone().ignore_errors(true),
+            keep_last,
+            keep_commands,
+            squash_snapshots,
+        }))
+    }
+
+    fn is_kept_command(&mut self, op: &Operation) -> bool {
+        let metadata = op.metadata();
+        if metadata.is_snapshot {
+            return false;
+        }
+        let Some(args) = metadata.tags.get("args") else {
+            return false;
+        };
+        // The command line is recorded after aliases are expanded, so parsing it
+        // finds the subcommand even if global flags come before it.
+        let Ok(matches) = self.app.try_get_matches_from_mut(split_recorded_args(args)) else {
+            return false;
+        };
+        let name = command_name(&matches);
+        let words = name.split(' ').collect_vec();
+        self.keep_commands
+            .iter()
+            .any(|command| words.starts_with(&command.iter().map(String::as_str).collect_vec()))
+    }
+
+    /// Selects operations to be abandoned. `ops` should be ordered from the
+    /// head operation to the root operation.
+    fn select_abandoned(&mut self, ops: &[Operation]) -> HashSet<OperationId> {
+        let mut abandoned_ids = HashSet::new();
+        for (index, op) in ops.iter().enumerate().skip(1) {
+            let child_op = &ops[index - 1];
+            // The root operation has no parents, and merge operations can't be
+            // abandoned without losing the information how they were merged.
+            if op.parent_ids().len() != 1 || self.is_kept_command(op) {
+                continue;
+            }
+            let expired = self.keep_last.is_some_and(|n| index >= n);
+            let squashed = self.squash_snapshots
+                && op.metadata().is_snapshot
+                && child_op.metadata().is_snapshot
+                && child_op.parent_ids() == slice::from_ref(op.id());
+            if expired || squashed {
+                abandoned_ids.insert(op.id().clone());
+            }
+        }
+        abandoned_ids
+    }
+}
+
+/// Splits the command line recorded in the `args` tag of an operation, undoing
+/// the quoting of `start_repo_transaction()`.
+fn split_recorded_args(args: &str) -> Vec<String> {
+    let mut words = vec![];
+    let mut chars = args.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c.is_whitespace() {
+            continue;
+        }
+        let mut word = String::new();
+        let mut next = Some(c);
+        while let Some(c) = next.filter(|c| !c.is_whitespace()) {
+            if c == '\'' {
+                while let Some(c) = chars.next() {
+                    match c {
+                        '\\' if chars.peek() == Some(&'\'') => word.extend(chars.next()),
+                        '\'' => break,
+                        _ => word.push(c),
+                    }
+                }
+            } else {
+                word.push(c);
+            }
+            next = chars.next();
+        }
+        words.push(word);
+    }
+    words
+}
+
+fn apply_operation_retention_policy(
+    ui: &mut Ui,
+    command: &CommandHelper,
+    policy: &mut OperationRetentionPolicy,
+) -> Result<(), CommandError> {
+    let mut workspace = command.load_workspace()?;
+    let repo_loader = workspace.repo_loader();
+    let op_store = repo_loader.op_store();
+    let op_heads_store = repo_loader.op_heads_store();
+    let current_head_ops = op_walk::get_current_head_ops(op_store, op_heads_store.as_ref())?;
+    let [head_op] = current_head_ops.as_slice() else {
+        return Err(user_error(
+            "Cannot rewrite the operation log with concurrent operations",
+        ));
+    };
+
+    // Operations are selected along the first-parent chain, so every
+    // abandoned range is an ancestor of the ranges closer to the head.
+    let mut ops = vec![head_op.clone()];
+    loop {
+        let Some(parent_op) = ops.last().unwrap().parents().next().transpose()? else {
+            break;
+        };
+        ops.push(parent_op);
+    }
+    let abandoned_ids = policy.select_abandoned(&ops);
+    if abandoned_ids.is_empty() {
+        return Ok(());
+    }
+
+    // Abandon each run of selected operations by reparenting the operations
+    // above it onto the parent of the run. Runs are processed from the head,
+    // which leaves the operations below the current run unchanged.
+    let mut new_head_op = head_op.clone();
+    let mut unreachable_count = 0;
+    let mut rewritten_count = 0;
+    let mut index = 0;
+    while index < ops.len() {
+        if !abandoned_ids.contains(ops[index].id()) {
+            index += 1;
+            continue;
+        }
+        let root_op = &ops[index];
+        while abandoned_ids.contains(ops[index].id()) {
+            index += 1;
+        }
+        // The root operation is never abandoned, so the run has a parent.
+        let dest_op = &ops[index];
+        let stats = op_walk::reparent_range(
+            op_store.as_ref(),
+            slice::from_ref(root_op),
+            slice::from_ref(&new_head_op),
+            dest_op,
+        )?;
+        new_head_op = repo_loader.load_operation(&stats.new_head_ids[0])?;
+        unreachable_count += stats.unreachable_count;
+        // The last range contains all operations rewritten by the earlier ones.
+        rewritten_count = stats.rewritten_count;
+    }
+    let new_head_id = new_head_op.id();
+    writeln!(
+        ui.status(),
+        "Abandoned {unreachable_count} operations and reparented {rewritten_count} descendant \
+         operations."
+    )?;
+    op_heads_store.update_op_heads(slice::from_ref(head_op.id()), new_head_id)?;
+    if !command.global_args().ignore_working_copy {
+        let mut locked_ws = workspace.start_working_copy_mutation()?;
+        if locked_ws.locked_wc().old_operation_id() == head_op.id() {
+            locked_ws.finish(new_head_id.clone())?;
+        } else {
+            writeln!(
+                ui.warning_default(),
+                "The working copy operation {} is not updated because it differs from the repo \
+                 {}.",
+                short_operation_hash(locked_ws.locked_wc().old_operation_id()),
+                short_operation_hash(head_op.id()),
+            )?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_recorded_args() {
+        assert_eq!(
+            split_recorded_args(r"jj --no-pager describe -m 'it\'s a  test' 'a'b"),
+            ["jj", "--no-pager", "describe", "-m", "it's a  test", "ab"]
+        );
+    }
+}
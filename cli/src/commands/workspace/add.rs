@@ -13,23 +13,30 @@
 // limitations under the License.
 
 use std::fs;
+use std::path::Path;
 
+use clap_complete::ArgValueCandidates;
 use itertools::Itertools as _;
 use jj_lib::commit::CommitIteratorExt as _;
 use jj_lib::file_util;
 use jj_lib::file_util::IoResultExt as _;
+use jj_lib::local_working_copy::LocalWorkingCopy;
+use jj_lib::local_working_copy::LockedLocalWorkingCopy;
 use jj_lib::ref_name::WorkspaceNameBuf;
 use jj_lib::repo::Repo as _;
 use jj_lib::rewrite::merge_commit_trees;
 use jj_lib::workspace::Workspace;
+use jj_lib::workspace_store::SimpleWorkspaceStore;
 use pollster::FutureExt as _;
 use tracing::instrument;
 
 use crate::cli_util::CommandHelper;
 use crate::cli_util::RevisionArg;
+use crate::cli_util::start_repo_transaction;
 use crate::command_error::CommandError;
 use crate::command_error::internal_error_with_message;
 use crate::command_error::user_error;
+use crate::complete;
 use crate::ui::Ui;
 
 /// How to handle sparse patterns when creating a new workspace.
@@ -74,6 +81,31 @@ pub struct WorkspaceAddArgs {
     /// How to handle sparse patterns when creating a new workspace.
     #[arg(long, value_enum, default_value_t = SparseInheritance::Copy)]
     sparse_patterns: SparseInheritance,
+    /// Clone the files of an existing workspace instead of checking out all
+    /// files from scratch
+    ///
+    /// All files of the workspace, including ignored ones such as build
+    /// outputs, are copied. On filesystems which support it (e.g. Btrfs and
+    /// XFS), the copies share their data with the original files. Only the
+    /// files which differ in the new working-copy commit are then updated. The
+    /// sparse patterns of the workspace are reused.
+    #[arg(
+        long,
+        value_name = "WORKSPACE",
+        conflicts_with = "sparse_patterns",
+        add = ArgValueCandidates::new(complete::workspaces),
+    )]
+    clone_from: Option<WorkspaceNameBuf>,
+    /// The type of working copy to create
+    ///
+    /// The default is the type of the current workspace. The `fuse` type is
+    /// only available on Linux if jj was built with the `fuse` feature. Its
+    /// files are served by `jj debug fuse-mount` and are only fetched from
+    /// the repository when they're accessed. Since the mount hides the
+    /// destination directory, its `.jj` directory is kept in the repository
+    /// and linked from there.
+    #[arg(long, value_name = "TYPE", conflicts_with = "clone_from")]
+    working_copy_type: Option<String>,
 }
 
 #[instrument(skip_all)]
@@ -83,6 +115,49 @@ pub fn cmd_workspace_add(
     args: &WorkspaceAddArgs,
 ) -> Result<(), CommandError> {
     let old_workspace_command = command.workspace_helper(ui)?;
+    // The workspace to clone is snapshotted so its files match its state.
+    let other_source_command = match &args.clone_from {
+        Some(name) if name != old_workspace_command.workspace_name() => {
+            let path = SimpleWorkspaceStore::load(old_workspace_command.repo_path())
+                .get_workspace_path(name)?
+                .ok_or_else(|| {
+                    user_error(format!(
+                        "The location of workspace {} is unknown",
+                        name.as_symbol()
+                    ))
+                })?;
+            let settings = command.settings_for_new_workspace(&path)?;
+            let workspace = command.load_workspace_at(&path, &settings)?;
+            if workspace.workspace_name() != name {
+                return Err(user_error(format!(
+                    "Workspace {} is no longer at \"{}\"",
+                    name.as_symbol(),
+                    path.display()
+                )));
+            }
+            let repo = workspace.repo_loader().load_at_head()?;
+            let mut source_command = command.for_workable_repo(ui, workspace, repo)?;
+            source_command.maybe_snapshot(ui)?;
+            Some(source_command)
+        }
+        _ => None,
+    };
+    let clone_source = args.clone_from.is_some().then(|| {
+        other_source_command
+            .as_ref()
+            .unwrap_or(&old_workspace_command)
+    });
+    let clone_source_wc = clone_source
+        .map(|source| {
+            source
+                .working_copy()
+                .as_any()
+                .downcast_ref::<LocalWorkingCopy>()
+                .ok_or_else(|| {
+                    user_error("Can only clone workspaces with a standard local-disk working copy")
+                })
+        })
+        .transpose()?;
     let destination_path = command.cwd().join(&args.destination);
     if destination_path.exists() {
         return Err(user_error("Workspace already exists"));
@@ -98,7 +173,8 @@ pub fn cmd_workspace_add(
             .ok_or_else(|| user_error("Destination path is not valid UTF-8"))?
             .into()
     };
-    let repo = old_workspace_command.repo();
+    // The workspace to clone may have been snapshotted at a newer operation.
+    let repo = clone_source.unwrap_or(&old_workspace_command).repo();
     if repo.view().get_wc_commit_id(&workspace_name).is_some() {
         return Err(user_error(format!(
             "Workspace named '{name}' already exists",
@@ -106,17 +182,23 @@ pub fn cmd_workspace_add(
         )));
     }
 
-    let working_copy_factory = command.get_working_copy_factory()?;
+    let working_copy_factory = match &args.working_copy_type {
+        Some(name) => command.get_working_copy_factory_by_name(name)?,
+        None => command.get_working_copy_factory()?,
+    };
     let repo_path = old_workspace_command.repo_path();
     // If we add per-workspace configuration, we'll need to reload settings for
     // the new workspace.
-    let (new_workspace, repo) = Workspace::init_workspace_with_existing_repo(
+    let (mut new_workspace, repo) = Workspace::init_workspace_with_existing_repo(
         &destination_path,
         repo_path,
         repo,
         working_copy_factory,
         workspace_name.clone(),
     )?;
+    if new_workspace.working_copy().name() == "fuse" {
+        move_jj_dir_out_of_mount(new_workspace.workspace_root(), repo_path)?;
+    }
     writeln!(
         ui.status(),
         "Created workspace in \"{}\"",
@@ -133,9 +215,68 @@ pub fn cmd_workspace_add(
         )?;
     }
 
+    let repo = if let Some((source, source_wc)) = clone_source.zip(clone_source_wc) {
+        if !new_workspace
+            .working_copy()
+            .as_any()
+            .is::<LocalWorkingCopy>()
+        {
+            return Err(user_error(
+                "Can only clone into a standard local-disk working copy",
+            ));
+        }
+        let source_root = source.workspace_root();
+        let new_root = new_workspace.workspace_root();
+        // Skip the repo and the other workspaces nested in the source one.
+        file_util::clone_dir_all(source_root, new_root, &|path: &Path| {
+            path == source_root.join(".jj")
+                || path == source_root.join(".git")
+                || path == new_root
+                || path.join(".jj").is_dir()
+        })?;
+
+        // Take over the state of the source working copy, and its working-copy
+        // commit to match. The new working-copy commit is then checked out
+        // from there.
+        let source_name = source.workspace_name();
+        let source_wc_commit_id = repo.view().get_wc_commit_id(source_name).ok_or_else(|| {
+            user_error(format!(
+                "Nothing checked out in workspace {}",
+                source_name.as_symbol()
+            ))
+        })?;
+        let source_wc_commit = repo.store().get_commit(source_wc_commit_id)?;
+        let mut tx = start_repo_transaction(&repo, command.string_args());
+        tx.repo_mut()
+            .edit(workspace_name.clone(), &source_wc_commit)?;
+        // The empty working-copy commit of the new workspace is abandoned.
+        tx.repo_mut().rebase_descendants()?;
+        let mut locked_ws = new_workspace.start_working_copy_mutation()?;
+        let locked_local_wc: &mut LockedLocalWorkingCopy =
+            locked_ws.locked_wc().as_any_mut().downcast_mut().unwrap();
+        locked_local_wc.adopt_tree_state(source_wc)?;
+        let repo = tx.commit(format!(
+            "clone files of workspace {source} into workspace {name}",
+            source = source_name.as_symbol(),
+            name = workspace_name.as_symbol()
+        ))?;
+        locked_ws.finish(repo.op_id().clone())?;
+        writeln!(
+            ui.status(),
+            "Cloned the files of workspace {}",
+            source_name.as_symbol()
+        )?;
+        repo
+    } else {
+        repo
+    };
+
     let mut new_workspace_command = command.for_workable_repo(ui, new_workspace, repo)?;
 
     let sparsity = match args.sparse_patterns {
+        _ if args.clone_from.is_some() => None,
+        // Other types of working copies may not support sparse patterns.
+        _ if new_workspace_command.working_copy().name() != LocalWorkingCopy::name() => None,
         SparseInheritance::Full => None,
         SparseInheritance::Empty => Some(vec![]),
         SparseInheritance::Copy => {
@@ -200,3 +341,30 @@ pub fn cmd_workspace_add(
     )?;
     Ok(())
 }
+
+/// Moves the `.jj` directory of a workspace into the repository and links it
+/// from the workspace, so that it remains reachable when the workspace is
+/// mounted over.
+fn move_jj_dir_out_of_mount(workspace_root: &Path, repo_path: &Path) -> Result<(), CommandError> {
+    let jj_dir = workspace_root.join(".jj");
+    let backing_root = repo_path.join("fuse_workspaces");
+    fs::create_dir_all(&backing_root).context(&backing_root)?;
+    let backing_dir = tempfile::Builder::new()
+        .prefix("workspace-")
+        .tempdir_in(&backing_root)
+        .context(&backing_root)?
+        .keep();
+    fs::rename(&jj_dir, &backing_dir).context(&jj_dir)?;
+    symlink_dir(&backing_dir, &jj_dir).context(&jj_dir)?;
+    Ok(())
+}
+
+#[cfg(unix)]
+fn symlink_dir(original: &Path, link: &Path) -> std::io::Result<()> {
+    std::os::unix::fs::symlink(original, link)
+}
+
+#[cfg(windows)]
+fn symlink_dir(original: &Path, link: &Path) -> std::io::Result<()> {
+    std::os::windows::fs::symlink_dir(original, link)
+}
@@ -15,6 +15,7 @@
 use clap_complete::ArgValueCandidates;
 use itertools::Itertools as _;
 use jj_lib::ref_name::WorkspaceNameBuf;
+use jj_lib::workspace_store::SimpleWorkspaceStore;
 use tracing::instrument;
 
 use crate::cli_util::CommandHelper;
@@ -62,6 +63,7 @@ pub fn cmd_workspace_forget(
 
     // bundle every workspace forget into a single transaction, so that e.g.
     // undo correctly restores all of them at once.
+    let workspace_store = SimpleWorkspaceStore::load(workspace_command.repo_path());
     let mut tx = workspace_command.start_transaction();
     wss.iter()
         .try_for_each(|ws| tx.repo_mut().remove_wc_commit(ws))?;
@@ -75,5 +77,6 @@ pub fn cmd_workspace_forget(
     };
 
     tx.finish(ui, description)?;
+    workspace_store.forget(&wss.iter().map(|ws| &**ws).collect_vec())?;
     Ok(())
 }
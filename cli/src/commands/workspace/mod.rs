@@ -15,11 +15,20 @@
 mod add;
 mod forget;
 mod list;
+mod r#move;
 mod rename;
+mod repair;
 mod root;
 mod update_stale;
 
+use std::fs;
+use std::path::Path;
+use std::path::PathBuf;
+
 use clap::Subcommand;
+use jj_lib::file_util;
+use jj_lib::file_util::IoResultExt as _;
+use jj_lib::workspace::set_workspace_repo_path;
 use tracing::instrument;
 
 use self::add::WorkspaceAddArgs;
@@ -28,8 +37,12 @@ use self::forget::WorkspaceForgetArgs;
 use self::forget::cmd_workspace_forget;
 use self::list::WorkspaceListArgs;
 use self::list::cmd_workspace_list;
+use self::r#move::WorkspaceMoveArgs;
+use self::r#move::cmd_workspace_move;
 use self::rename::WorkspaceRenameArgs;
 use self::rename::cmd_workspace_rename;
+use self::repair::WorkspaceRepairArgs;
+use self::repair::cmd_workspace_repair;
 use self::root::WorkspaceRootArgs;
 use self::root::cmd_workspace_root;
 use self::update_stale::WorkspaceUpdateStaleArgs;
@@ -54,7 +67,9 @@ pub(crate) enum WorkspaceCommand {
     Add(WorkspaceAddArgs),
     Forget(WorkspaceForgetArgs),
     List(WorkspaceListArgs),
+    Move(WorkspaceMoveArgs),
     Rename(WorkspaceRenameArgs),
+    Repair(WorkspaceRepairArgs),
     Root(WorkspaceRootArgs),
     UpdateStale(WorkspaceUpdateStaleArgs),
 }
@@ -69,8 +84,46 @@ pub(crate) fn cmd_workspace(
         WorkspaceCommand::Add(args) => cmd_workspace_add(ui, command, args),
         WorkspaceCommand::Forget(args) => cmd_workspace_forget(ui, command, args),
         WorkspaceCommand::List(args) => cmd_workspace_list(ui, command, args),
+        WorkspaceCommand::Move(args) => cmd_workspace_move(ui, command, args),
         WorkspaceCommand::Rename(args) => cmd_workspace_rename(ui, command, args),
+        WorkspaceCommand::Repair(args) => cmd_workspace_repair(ui, command, args),
         WorkspaceCommand::Root(args) => cmd_workspace_root(ui, command, args),
         WorkspaceCommand::UpdateStale(args) => cmd_workspace_update_stale(ui, command, args),
     }
 }
+
+/// Points the workspaces at `workspace_roots` whose repo pointer has become
+/// dangling to the repo at `repo_path`.
+///
+/// Workspaces which no longer exist, or which point to an existing repo, are
+/// left alone. Returns true if any workspace was updated.
+fn repoint_workspaces(
+    ui: &Ui,
+    command: &CommandHelper,
+    workspace_roots: &[PathBuf],
+    repo_path: &Path,
+) -> Result<bool, CommandError> {
+    let mut updated = false;
+    for workspace_root in workspace_roots {
+        let jj_dir = workspace_root.join(".jj");
+        let repo_file_path = jj_dir.join("repo");
+        if !repo_file_path.is_file() {
+            continue;
+        }
+        let buf = fs::read(&repo_file_path).context(&repo_file_path)?;
+        let Ok(old_repo_path) = file_util::path_from_bytes(&buf) else {
+            continue;
+        };
+        if jj_dir.join(old_repo_path).exists() {
+            continue;
+        }
+        set_workspace_repo_path(workspace_root, repo_path)?;
+        writeln!(
+            ui.status(),
+            "Updated the repo path of the workspace in \"{}\"",
+            file_util::relative_path(command.cwd(), workspace_root).display()
+        )?;
+        updated = true;
+    }
+    Ok(updated)
+}
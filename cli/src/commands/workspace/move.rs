@@ -0,0 +1,98 @@
+// Copyright 2025 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::fs;
+
+use itertools::Itertools as _;
+use jj_lib::file_util;
+use jj_lib::file_util::IoResultExt as _;
+use jj_lib::workspace_store::SimpleWorkspaceStore;
+use tracing::instrument;
+
+use super::repoint_workspaces;
+use crate::cli_util::CommandHelper;
+use crate::command_error::CommandError;
+use crate::command_error::user_error;
+use crate::ui::Ui;
+
+/// Move the current workspace to another directory
+///
+/// The workspace directory is renamed on disk and the repo's record of the
+/// workspace location is updated. If the workspace contains the repo, the
+/// other workspaces are pointed to its new location.
+#[derive(clap::Args, Clone, Debug)]
+pub struct WorkspaceMoveArgs {
+    /// Where to move the workspace to
+    ///
+    /// The destination must not exist yet.
+    #[arg(value_hint = clap::ValueHint::DirPath)]
+    destination: String,
+}
+
+#[instrument(skip_all)]
+pub fn cmd_workspace_move(
+    ui: &mut Ui,
+    command: &CommandHelper,
+    args: &WorkspaceMoveArgs,
+) -> Result<(), CommandError> {
+    // Snapshot before moving so no changes made in the old location are lost.
+    let workspace_command = command.workspace_helper(ui)?;
+    let workspace_name = workspace_command.workspace_name().to_owned();
+    let old_root = workspace_command.workspace_root().to_owned();
+    let old_repo_path = workspace_command.repo_path().to_owned();
+
+    let destination_path = command.cwd().join(&args.destination);
+    if destination_path.exists() {
+        return Err(user_error(format!(
+            "Destination \"{}\" already exists",
+            args.destination
+        )));
+    }
+    let (Some(parent), Some(file_name)) = (destination_path.parent(), destination_path.file_name())
+    else {
+        return Err(user_error(format!(
+            "Invalid destination \"{}\"",
+            args.destination
+        )));
+    };
+    // The destination doesn't exist, but its parent must.
+    let new_root = dunce::canonicalize(parent).context(parent)?.join(file_name);
+    if new_root.starts_with(&old_root) {
+        return Err(user_error("Cannot move a workspace into itself"));
+    }
+
+    let contains_repo = old_root.join(".jj").join("repo").is_dir();
+    let other_roots = SimpleWorkspaceStore::load(&old_repo_path)
+        .workspace_paths()?
+        .into_iter()
+        .filter(|(name, _)| *name != workspace_name)
+        .map(|(_, path)| path)
+        .collect_vec();
+
+    fs::rename(&old_root, &new_root).context(&old_root)?;
+    let new_repo_path = if contains_repo {
+        let new_repo_path = new_root.join(".jj").join("repo");
+        repoint_workspaces(ui, command, &other_roots, &new_repo_path)?;
+        new_repo_path
+    } else {
+        old_repo_path
+    };
+    SimpleWorkspaceStore::load(&new_repo_path).add(&workspace_name, &new_root)?;
+    writeln!(
+        ui.status(),
+        "Moved workspace to \"{}\"",
+        file_util::relative_path(command.cwd(), &new_root).display()
+    )?;
+    Ok(())
+}
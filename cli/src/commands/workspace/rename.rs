@@ -13,6 +13,7 @@
 // limitations under the License.
 
 use jj_lib::ref_name::WorkspaceNameBuf;
+use jj_lib::workspace_store::SimpleWorkspaceStore;
 use tracing::instrument;
 
 use crate::cli_util::CommandHelper;
@@ -58,6 +59,7 @@ pub fn cmd_workspace_rename(
         )));
     }
 
+    let workspace_store = SimpleWorkspaceStore::load(workspace_command.repo_path());
     let mut tx = workspace_command.start_transaction().into_inner();
     let (mut locked_ws, _wc_commit) = workspace_command.start_working_copy_mutation()?;
 
@@ -71,6 +73,7 @@ pub fn cmd_workspace_rename(
         new = new_name.as_symbol()
     ))?;
     locked_ws.finish(repo.op_id().clone())?;
+    workspace_store.rename(&old_name, new_name)?;
 
     Ok(())
 }
@@ -0,0 +1,202 @@
+// Copyright 2025 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::path::Path;
+use std::path::PathBuf;
+
+use itertools::Itertools as _;
+use jj_lib::file_util;
+use jj_lib::ref_name::WorkspaceName;
+use jj_lib::ref_name::WorkspaceNameBuf;
+use jj_lib::workspace::set_workspace_repo_path;
+use jj_lib::workspace_store::SimpleWorkspaceStore;
+use tracing::instrument;
+
+use super::repoint_workspaces;
+use crate::cli_util::CommandHelper;
+use crate::cli_util::find_workspace_dir;
+use crate::command_error::CommandError;
+use crate::command_error::user_error;
+use crate::command_error::user_error_with_hint;
+use crate::ui::Ui;
+
+/// Repair the current workspace after it was moved or copied on disk
+///
+/// If the repo was moved, pass its new location with `--repo` to point the
+/// workspace to it. If the current workspace contains the repo, the other
+/// workspaces whose repo pointers are broken are pointed to it.
+///
+/// A copied workspace has the same name, and therefore the same working-copy
+/// commit, as the original one. The copy needs to be renamed, either with
+/// `--rename` or at the prompt. It then gets its own working-copy commit with
+/// the same parents and contents.
+#[derive(clap::Args, Clone, Debug)]
+pub struct WorkspaceRepairArgs {
+    /// Path to the moved repo, or to the workspace containing it
+    #[arg(long, value_hint = clap::ValueHint::DirPath)]
+    repo: Option<String>,
+    /// New name for the workspace if it turns out to be a copy
+    #[arg(long, value_name = "NAME")]
+    rename: Option<WorkspaceNameBuf>,
+}
+
+#[instrument(skip_all)]
+pub fn cmd_workspace_repair(
+    ui: &mut Ui,
+    command: &CommandHelper,
+    args: &WorkspaceRepairArgs,
+) -> Result<(), CommandError> {
+    // The workspace loader fails if the repo pointer is broken.
+    let workspace_root = match command.workspace_loader() {
+        Ok(loader) => loader.workspace_root().to_owned(),
+        Err(_) => find_workspace_dir(command.cwd()).to_owned(),
+    };
+    let mut repaired = false;
+    if let Some(repo) = &args.repo {
+        if workspace_root.join(".jj").join("repo").is_dir() {
+            return Err(user_error("The current workspace contains the repo"));
+        }
+        let repo_path = find_repo_path(&command.cwd().join(repo))
+            .ok_or_else(|| user_error(format!("There is no jj repo in \"{repo}\"")))?;
+        set_workspace_repo_path(&workspace_root, &repo_path)?;
+        writeln!(
+            ui.status(),
+            "Updated the repo path of the current workspace"
+        )?;
+        repaired = true;
+    }
+
+    let workspace = command.load_workspace_at(&workspace_root, command.settings())?;
+    let repo = workspace.repo_loader().load_at_head()?;
+    // Don't snapshot: a copied workspace would otherwise record its changes in
+    // the working-copy commit of the original one.
+    let mut workspace_command = command.for_workable_repo(ui, workspace, repo)?;
+    let workspace_name = workspace_command.workspace_name().to_owned();
+    let workspace_root = workspace_command.workspace_root().to_owned();
+    let repo_path = workspace_command.repo_path().to_owned();
+    let workspace_store = SimpleWorkspaceStore::load(&repo_path);
+    let recorded_paths = workspace_store.workspace_paths()?;
+
+    if workspace_root.join(".jj").join("repo").is_dir() {
+        let other_roots = recorded_paths
+            .iter()
+            .filter(|(name, _)| **name != workspace_name)
+            .map(|(_, path)| path.clone())
+            .collect_vec();
+        repaired |= repoint_workspaces(ui, command, &other_roots, &repo_path)?;
+    }
+
+    match recorded_paths.get(&workspace_name) {
+        Some(path) if *path == workspace_root => {}
+        Some(path) if is_same_workspace(command, path, &workspace_name, &repo_path) => {
+            writeln!(
+                ui.warning_default(),
+                "Workspace {name} also exists in \"{path}\". The current workspace appears to \
+                 be a copy of it.",
+                name = workspace_name.as_symbol(),
+                path = file_util::relative_path(command.cwd(), path).display()
+            )?;
+            let new_name = match &args.rename {
+                Some(new_name) => new_name.clone(),
+                None if Ui::can_prompt() => ui.prompt("New name for the current workspace")?.into(),
+                None => {
+                    return Err(user_error_with_hint(
+                        "The copied workspace needs a new name",
+                        "Run `jj workspace repair --rename <NAME>` to rename it.",
+                    ));
+                }
+            };
+            if new_name.as_str().is_empty() {
+                return Err(user_error("New workspace name cannot be empty"));
+            }
+            if workspace_command
+                .repo()
+                .view()
+                .get_wc_commit_id(&new_name)
+                .is_some()
+            {
+                return Err(user_error(format!(
+                    "Workspace named '{name}' already exists",
+                    name = new_name.as_symbol()
+                )));
+            }
+
+            let tree_id = workspace_command.working_copy().tree_id()?.clone();
+            let mut tx = workspace_command.start_transaction().into_inner();
+            let (mut locked_ws, wc_commit) =
+                workspace_command.unchecked_start_working_copy_mutation()?;
+            let new_wc_commit = tx
+                .repo_mut()
+                .new_commit(wc_commit.parent_ids().to_vec(), tree_id)
+                .write()?;
+            tx.repo_mut().edit(new_name.clone(), &new_wc_commit)?;
+            locked_ws.locked_wc().rename_workspace(new_name.clone());
+            let repo = tx.commit(format!(
+                "repair copied workspace {old} as {new}",
+                old = workspace_name.as_symbol(),
+                new = new_name.as_symbol()
+            ))?;
+            locked_ws.finish(repo.op_id().clone())?;
+            workspace_store.add(&new_name, &workspace_root)?;
+            writeln!(
+                ui.status(),
+                "Renamed the current workspace to {}",
+                new_name.as_symbol()
+            )?;
+            repaired = true;
+        }
+        _ => {
+            workspace_store.add(&workspace_name, &workspace_root)?;
+            writeln!(
+                ui.status(),
+                "Updated the recorded location of workspace {}",
+                workspace_name.as_symbol()
+            )?;
+            repaired = true;
+        }
+    }
+
+    if !repaired {
+        writeln!(ui.status(), "Nothing to repair.")?;
+    }
+    Ok(())
+}
+
+/// Returns the repo directory at `path`, which may be the repo directory itself
+/// or the root of the workspace containing it.
+fn find_repo_path(path: &Path) -> Option<PathBuf> {
+    let workspace_repo_path = path.join(".jj").join("repo");
+    if workspace_repo_path.is_dir() {
+        Some(workspace_repo_path)
+    } else if path.join("store").is_dir() && path.join("op_store").is_dir() {
+        Some(path.to_owned())
+    } else {
+        None
+    }
+}
+
+/// Returns true if there's a workspace named `name` at `workspace_root` which
+/// uses the repo at `repo_path`.
+fn is_same_workspace(
+    command: &CommandHelper,
+    workspace_root: &Path,
+    name: &WorkspaceName,
+    repo_path: &Path,
+) -> bool {
+    command
+        .load_workspace_at(workspace_root, command.settings())
+        .is_ok_and(|workspace| {
+            workspace.workspace_name() == name && workspace.repo_path() == repo_path
+        })
+}
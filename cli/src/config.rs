@@ -21,6 +21,7 @@ use std::fmt;
 use std::path::Path;
 use std::path::PathBuf;
 use std::process::Command;
+use std::slice;
 use std::sync::LazyLock;
 
 use etcetera::BaseStrategy as _;
@@ -595,6 +596,18 @@ pub fn default_config_layers() -> Vec<ConfigLayer> {
     if cfg!(windows) {
         layers.push(parse(include_str!("config/windows.toml")));
     }
+    // The inotify filesystem monitor daemon is run by this executable.
+    if let Some(path) = env::current_exe()
+        .ok()
+        .and_then(|path| path.into_os_string().into_string().ok())
+    {
+        let mut layer = ConfigLayer::empty(ConfigSource::Default);
+        let command = ConfigValue::from_iter([path.as_str(), "debug", "inotify", "daemon"]);
+        layer
+            .set_value("fsmonitor.inotify.daemon-command", command)
+            .unwrap();
+        layers.push(layer);
+    }
     layers
 }
 
@@ -759,6 +772,16 @@ pub fn default_config_migrations() -> Vec<ConfigMigrationRule> {
             "core.watchman.register-snapshot-trigger",
             "fsmonitor.watchman.register-snapshot-trigger",
         ),
+        // TODO: Delete in jj 0.38.0+
+        ConfigMigrationRule::rename_value(
+            "experimental-advance-branches.enabled-branches",
+            "advance-bookmarks.enabled-bookmarks",
+        ),
+        // TODO: Delete in jj 0.38.0+
+        ConfigMigrationRule::rename_value(
+            "experimental-advance-branches.disabled-branches",
+            "advance-bookmarks.disabled-bookmarks",
+        ),
     ]
 }
 
@@ -821,13 +844,24 @@ impl CommandNameAndArgs {
     pub fn to_command_with_variables<V: AsRef<str>>(
         &self,
         variables: &HashMap<&str, V>,
+    ) -> Command {
+        self.to_command_with_expanded_args(|arg| {
+            interpolate_variables(slice::from_ref(arg), variables)
+        })
+    }
+
+    /// Returns process builder configured with this after replacing each
+    /// argument with the arguments returned by `expand_arg`.
+    pub fn to_command_with_expanded_args<I: IntoIterator<Item = String>>(
+        &self,
+        expand_arg: impl FnMut(&String) -> I,
     ) -> Command {
         let (name, args) = self.split_name_and_args();
         let mut cmd = Command::new(name.as_ref());
         if let Self::Structured { env, .. } = self {
             cmd.envs(env);
         }
-        cmd.args(interpolate_variables(&args, variables));
+        cmd.args(args.iter().flat_map(expand_arg));
         cmd
     }
 }
@@ -63,6 +63,7 @@ use jj_lib::merge::Merge;
 use jj_lib::merge::MergedTreeValue;
 use jj_lib::merged_tree::MergedTree;
 use jj_lib::object_id::ObjectId as _;
+use jj_lib::patch::format_binary_patch;
 use jj_lib::repo::Repo;
 use jj_lib::repo_path::InvalidRepoPathError;
 use jj_lib::repo_path::RepoPath;
@@ -1499,9 +1500,9 @@ fn git_diff_part(
     value: MaterializedTreeValue,
     conflict_marker_style: ConflictMarkerStyle,
 ) -> Result<GitDiffPart, DiffRenderError> {
-    const DUMMY_HASH: &str = "0000000000";
+    const DUMMY_HASH: &str = "0000000000000000000000000000000000000000";
     let mode;
-    let mut hash;
+    let hash;
     let content;
     match value {
         MaterializedTreeValue::Absent => {
@@ -1566,7 +1567,6 @@ fn git_diff_part(
             panic!("Unexpected tree in diff at path {path:?}");
         }
     }
-    hash.truncate(10);
     Ok(GitDiffPart {
         mode: Some(mode),
         hash,
@@ -1580,6 +1580,9 @@ pub struct UnifiedDiffOptions {
     pub context: usize,
     /// How lines are tokenized and compared.
     pub line_diff: LineDiffOptions,
+    /// Whether to emit binary patches and full object IDs, so the diff can be
+    /// applied by Git.
+    pub binary: bool,
 }
 
 impl UnifiedDiffOptions {
@@ -1587,6 +1590,7 @@ impl UnifiedDiffOptions {
         Ok(Self {
             context: settings.get("diff.git.context")?,
             line_diff: LineDiffOptions::default(),
+            binary: false,
         })
     }
 
@@ -1837,8 +1841,12 @@ pub async fn show_git_diff(
         let right_path_string = right_path.as_internal_file_string();
         let (left_value, right_value) = values?;
 
-        let left_part = git_diff_part(left_path, left_value, conflict_marker_style)?;
-        let right_part = git_diff_part(right_path, right_value, conflict_marker_style)?;
+        let mut left_part = git_diff_part(left_path, left_value, conflict_marker_style)?;
+        let mut right_part = git_diff_part(right_path, right_value, conflict_marker_style)?;
+        if !options.binary {
+            left_part.hash.truncate(10);
+            right_part.hash.truncate(10);
+        }
 
         formatter.with_label("file_header", |formatter| {
             writeln!(
@@ -1893,8 +1901,14 @@ pub async fn show_git_diff(
             Some(_) => format!("b/{right_path_string}"),
             None => "/dev/null".to_owned(),
         };
-        if left_part.content.is_binary || right_part.content.is_binary {
-            // TODO: add option to emit Git binary diff
+        let is_binary = left_part.content.is_binary || right_part.content.is_binary;
+        if is_binary && options.binary {
+            write!(
+                formatter,
+                "{}",
+                format_binary_patch(&left_part.content.contents, &right_part.content.contents)
+            )?;
+        } else if is_binary {
             writeln!(
                 formatter,
                 "Binary files {left_path} and {right_path} differ"
@@ -23,6 +23,10 @@ use std::mem;
 use std::path::Path;
 use std::path::PathBuf;
 use std::process::Stdio;
+use std::sync::Arc;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::Ordering;
+use std::sync::mpsc;
 use std::time::Duration;
 use std::time::Instant;
 
@@ -275,7 +279,22 @@ pub fn with_remote_git_callbacks<T>(ui: &Ui, f: impl FnOnce(git::RemoteCallbacks
         |url: &str| Some((terminal_get_username(ui, url)?, terminal_get_pw(ui, url)?));
     callbacks.get_username_password = Some(&mut get_user_pw);
 
+    // On Ctrl-C, ask the in-process transport to stop, and give it a moment to
+    // clean up before the process is terminated.
+    let should_interrupt = Arc::new(AtomicBool::new(false));
+    let (done_tx, done_rx) = mpsc::channel::<()>();
+    let interrupt_guard = {
+        let should_interrupt = should_interrupt.clone();
+        CleanupGuard::new(move || {
+            should_interrupt.store(true, Ordering::Relaxed);
+            _ = done_rx.recv_timeout(Duration::from_secs(1));
+        })
+    };
+    callbacks.should_interrupt = Some(&should_interrupt);
+
     let result = f(callbacks);
+    drop(done_tx);
+    drop(interrupt_guard);
     _ = sideband_progress_writer.flush(ui);
     result
 }
@@ -311,6 +311,7 @@ diff editing in mind and be a little inaccurate.
             progress: None,
             start_tracking_matcher: &EverythingMatcher,
             max_new_file_size: u64::MAX,
+            snapshot_rules: Arc::default(),
             conflict_marker_style,
         })?;
         Ok(output_tree_state.current_tree_id().clone())
@@ -390,6 +390,7 @@ pub fn edit_diff_external(
         .unwrap_or(default_conflict_marker_style);
     let options = CheckoutOptions {
         conflict_marker_style,
+        progress: None,
     };
 
     let got_output_field = find_all_variables(&editor.edit_args).contains(&"output");
@@ -438,6 +439,7 @@ pub fn generate_diff(
         .unwrap_or(default_conflict_marker_style);
     let options = CheckoutOptions {
         conflict_marker_style,
+        progress: None,
     };
     let store = left_tree.store();
     let diff_wc = check_out_trees(store, left_tree, right_tree, matcher, None, &options)?;
@@ -16,14 +16,21 @@ use std::any::Any;
 use std::cmp::Ordering;
 use std::collections::HashMap;
 use std::io;
+use std::sync::Arc;
 
 use itertools::Itertools as _;
+use jj_lib::bisect::BISECT_TAG;
 use jj_lib::extensions_map::ExtensionsMap;
 use jj_lib::object_id::ObjectId as _;
 use jj_lib::op_store::OperationId;
 use jj_lib::operation::Operation;
 use jj_lib::repo::RepoLoader;
 use jj_lib::settings::UserSettings;
+use jj_lib::signing::SigStatus;
+use jj_lib::signing::SignError;
+use jj_lib::signing::SignResult;
+use jj_lib::signing::Verification;
+use jj_lib::store::Store;
 
 use crate::template_builder;
 use crate::template_builder::BuildContext;
@@ -131,6 +138,13 @@ impl TemplateLanguage<'static> for OperationTemplateLanguage {
                 let build = template_parser::lookup_method(type_name, table, function)?;
                 build(self, diagnostics, build_ctx, property, function)
             }
+            OperationTemplatePropertyKind::CryptographicSignatureOpt(property) => {
+                let type_name = "CryptographicSignature";
+                let table = &self.build_fn_table.cryptographic_signature_methods;
+                let build = template_parser::lookup_method(type_name, table, function)?;
+                let inner_property = property.try_unwrap(type_name).into_dyn();
+                build(self, diagnostics, build_ctx, inner_property, function)
+            }
         }
     }
 }
@@ -146,6 +160,7 @@ pub enum OperationTemplatePropertyKind {
     Operation(BoxedTemplateProperty<'static, Operation>),
     OperationList(BoxedTemplateProperty<'static, Vec<Operation>>),
     OperationId(BoxedTemplateProperty<'static, OperationId>),
+    CryptographicSignatureOpt(BoxedTemplateProperty<'static, Option<OperationSignature>>),
 }
 
 template_builder::impl_core_property_wrappers!(OperationTemplatePropertyKind => Core);
@@ -153,6 +168,7 @@ template_builder::impl_property_wrappers!(OperationTemplatePropertyKind {
     Operation(Operation),
     OperationList(Vec<Operation>),
     OperationId(OperationId),
+    CryptographicSignatureOpt(Option<OperationSignature>),
 });
 
 impl CoreTemplatePropertyVar<'static> for OperationTemplatePropertyKind {
@@ -170,6 +186,7 @@ impl CoreTemplatePropertyVar<'static> for OperationTemplatePropertyKind {
             Self::Operation(_) => "Operation",
             Self::OperationList(_) => "List<Operation>",
             Self::OperationId(_) => "OperationId",
+            Self::CryptographicSignatureOpt(_) => "Option<CryptographicSignature>",
         }
     }
 
@@ -179,6 +196,9 @@ impl CoreTemplatePropertyVar<'static> for OperationTemplatePropertyKind {
             Self::Operation(_) => None,
             Self::OperationList(property) => Some(property.map(|l| !l.is_empty()).into_dyn()),
             Self::OperationId(_) => None,
+            Self::CryptographicSignatureOpt(property) => {
+                Some(property.map(|sig| sig.is_some()).into_dyn())
+            }
         }
     }
 
@@ -205,6 +225,7 @@ impl CoreTemplatePropertyVar<'static> for OperationTemplatePropertyKind {
             Self::Operation(property) => Some(property.into_serialize()),
             Self::OperationList(property) => Some(property.into_serialize()),
             Self::OperationId(property) => Some(property.into_serialize()),
+            Self::CryptographicSignatureOpt(_) => None,
         }
     }
 
@@ -214,6 +235,7 @@ impl CoreTemplatePropertyVar<'static> for OperationTemplatePropertyKind {
             Self::Operation(_) => None,
             Self::OperationList(_) => None,
             Self::OperationId(property) => Some(property.into_template()),
+            Self::CryptographicSignatureOpt(_) => None,
         }
     }
 
@@ -224,6 +246,7 @@ impl CoreTemplatePropertyVar<'static> for OperationTemplatePropertyKind {
             (Self::Operation(_), _) => None,
             (Self::OperationList(_), _) => None,
             (Self::OperationId(_), _) => None,
+            (Self::CryptographicSignatureOpt(_), _) => None,
         }
     }
 
@@ -234,6 +257,7 @@ impl CoreTemplatePropertyVar<'static> for OperationTemplatePropertyKind {
             (Self::Operation(_), _) => None,
             (Self::OperationList(_), _) => None,
             (Self::OperationId(_), _) => None,
+            (Self::CryptographicSignatureOpt(_), _) => None,
         }
     }
 }
@@ -248,6 +272,7 @@ pub struct OperationTemplateBuildFnTable {
     pub operation_methods: OperationTemplateBuildMethodFnMap<Operation>,
     pub operation_list_methods: OperationTemplateBuildMethodFnMap<Vec<Operation>>,
     pub operation_id_methods: OperationTemplateBuildMethodFnMap<OperationId>,
+    pub cryptographic_signature_methods: OperationTemplateBuildMethodFnMap<OperationSignature>,
 }
 
 impl OperationTemplateBuildFnTable {
@@ -258,6 +283,7 @@ impl OperationTemplateBuildFnTable {
             operation_methods: builtin_operation_methods(),
             operation_list_methods: template_builder::builtin_unformattable_list_methods(),
             operation_id_methods: builtin_operation_id_methods(),
+            cryptographic_signature_methods: builtin_cryptographic_signature_methods(),
         }
     }
 
@@ -267,6 +293,7 @@ impl OperationTemplateBuildFnTable {
             operation_methods: HashMap::new(),
             operation_list_methods: HashMap::new(),
             operation_id_methods: HashMap::new(),
+            cryptographic_signature_methods: HashMap::new(),
         }
     }
 
@@ -276,12 +303,17 @@ impl OperationTemplateBuildFnTable {
             operation_methods,
             operation_list_methods,
             operation_id_methods,
+            cryptographic_signature_methods,
         } = other;
 
         self.core.merge(core);
         merge_fn_map(&mut self.operation_methods, operation_methods);
         merge_fn_map(&mut self.operation_list_methods, operation_list_methods);
         merge_fn_map(&mut self.operation_id_methods, operation_id_methods);
+        merge_fn_map(
+            &mut self.cryptographic_signature_methods,
+            cryptographic_signature_methods,
+        );
     }
 }
 
@@ -323,6 +355,9 @@ fn builtin_operation_methods() -> OperationTemplateBuildMethodFnMap<Operation> {
                 op.metadata()
                     .tags
                     .iter()
+                    // The bisection state is only meant to be read by `jj bisect`.
+                    .filter(|(key, _)| *key != BISECT_TAG)
+                    .sorted()
                     .map(|(key, value)| format!("{key}: {value}"))
                     .join("\n")
             });
@@ -376,6 +411,16 @@ fn builtin_operation_methods() -> OperationTemplateBuildMethodFnMap<Operation> {
             Ok(out_property.into_dyn_wrapped())
         },
     );
+    map.insert(
+        "signature",
+        |language, _diagnostics, _build_ctx, self_property, function| {
+            function.expect_no_arguments()?;
+            let store = language.repo_loader.store().clone();
+            let out_property =
+                self_property.map(move |op| OperationSignature::new(op, store.clone()));
+            Ok(out_property.into_dyn_wrapped())
+        },
+    );
     map
 }
 
@@ -413,3 +458,74 @@ fn builtin_operation_id_methods() -> OperationTemplateBuildMethodFnMap<Operation
     );
     map
 }
+
+/// Cryptographic signature of an operation.
+pub struct OperationSignature {
+    op: Operation,
+    store: Arc<Store>,
+}
+
+impl OperationSignature {
+    fn new(op: Operation, store: Arc<Store>) -> Option<Self> {
+        op.is_signed().then_some(Self { op, store })
+    }
+
+    fn verify(&self) -> SignResult<Verification> {
+        self.op
+            .verification(self.store.signer())
+            .transpose()
+            .expect("must have signature")
+    }
+
+    fn status(&self) -> SignResult<SigStatus> {
+        self.verify().map(|verification| verification.status)
+    }
+
+    /// Defaults to empty string if key is not present.
+    fn key(&self) -> SignResult<String> {
+        self.verify()
+            .map(|verification| verification.key.unwrap_or_default())
+    }
+
+    /// Defaults to empty string if display is not present.
+    fn display(&self) -> SignResult<String> {
+        self.verify()
+            .map(|verification| verification.display.unwrap_or_default())
+    }
+}
+
+fn builtin_cryptographic_signature_methods() -> OperationTemplateBuildMethodFnMap<OperationSignature>
+{
+    // Not using maplit::hashmap!{} or custom declarative macro here because
+    // code completion inside macro is quite restricted.
+    let mut map = OperationTemplateBuildMethodFnMap::<OperationSignature>::new();
+    map.insert(
+        "status",
+        |_language, _diagnostics, _build_ctx, self_property, function| {
+            function.expect_no_arguments()?;
+            let out_property = self_property.and_then(|sig| match sig.status() {
+                Ok(status) => Ok(status.to_string()),
+                Err(SignError::InvalidSignatureFormat) => Ok("invalid".to_string()),
+                Err(err) => Err(err.into()),
+            });
+            Ok(out_property.into_dyn_wrapped())
+        },
+    );
+    map.insert(
+        "key",
+        |_language, _diagnostics, _build_ctx, self_property, function| {
+            function.expect_no_arguments()?;
+            let out_property = self_property.and_then(|sig| Ok(sig.key()?));
+            Ok(out_property.into_dyn_wrapped())
+        },
+    );
+    map.insert(
+        "display",
+        |_language, _diagnostics, _build_ctx, self_property, function| {
+            function.expect_no_arguments()?;
+            let out_property = self_property.and_then(|sig| Ok(sig.display()?));
+            Ok(out_property.into_dyn_wrapped())
+        },
+    );
+    map
+}
@@ -16,6 +16,15 @@ pub const UPDATE_HZ: u32 = 30;
 pub const INITIAL_DELAY: Duration = Duration::from_millis(250);
 
 pub fn snapshot_progress(ui: &Ui) -> Option<impl Fn(&RepoPath) + use<>> {
+    path_progress(ui, "Snapshotting")
+}
+
+pub fn checkout_progress(ui: &Ui) -> Option<impl Fn(&RepoPath) + use<>> {
+    path_progress(ui, "Updating")
+}
+
+/// Displays the path currently being processed, prefixed by `verb`.
+fn path_progress(ui: &Ui, verb: &'static str) -> Option<impl Fn(&RepoPath) + use<>> {
     struct State {
         guard: Option<OutputGuard>,
         output: ProgressOutput<std::io::Stderr>,
@@ -51,14 +60,14 @@ pub fn snapshot_progress(ui: &Ui) -> Option<impl Fn(&RepoPath) + use<>> {
         }
 
         let line_width = state.output.term_width().map(usize::from).unwrap_or(80);
-        let max_path_width = line_width.saturating_sub(13); // Account for "Snapshotting "
+        let max_path_width = line_width.saturating_sub(verb.len() + 1);
         let fs_path = path.to_fs_path_unchecked(Path::new(""));
         let (display_path, _) =
             text_util::elide_start(fs_path.to_str().unwrap(), "...", max_path_width);
 
         _ = write!(
             state.output,
-            "\r{}Snapshotting {display_path}",
+            "\r{}{verb} {display_path}",
             Clear(ClearType::CurrentLine),
         );
         _ = state.output.flush();
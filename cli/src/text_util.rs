@@ -17,6 +17,8 @@ use std::cmp;
 use std::io;
 
 use bstr::ByteSlice as _;
+use chrono::DateTime;
+use jj_lib::backend::Timestamp;
 use unicode_width::UnicodeWidthChar as _;
 use unicode_width::UnicodeWidthStr as _;
 
@@ -547,6 +549,13 @@ pub fn parse_author(author: &str) -> Result<(String, String), &'static str> {
     Ok((captures["name"].to_string(), captures["email"].to_string()))
 }
 
+/// Parses an RFC 3339 timestamp, such as `2000-01-23T01:23:45-08:00`.
+pub fn parse_timestamp(timestamp: &str) -> Result<Timestamp, chrono::ParseError> {
+    Ok(Timestamp::from_datetime(DateTime::parse_from_rfc3339(
+        timestamp,
+    )?))
+}
+
 #[cfg(test)]
 mod tests {
     use std::io::Write as _;
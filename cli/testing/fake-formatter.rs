@@ -64,6 +64,16 @@ struct Args {
     /// Duplicate stdout into this file.
     #[arg(long)]
     tee: Option<PathBuf>,
+
+    /// Only change the lines in this range of 1-based line numbers, given as
+    /// `FIRST:LAST`. Can be repeated.
+    #[arg(long)]
+    lines: Vec<String>,
+
+    /// Only change the lines in these ranges, given in the JSON format of
+    /// `rustfmt --file-lines`.
+    #[arg(long)]
+    file_lines: Option<String>,
 }
 
 fn main() -> ExitCode {
@@ -80,9 +90,31 @@ fn main() -> ExitCode {
         assert!(args.append.is_none());
         data
     } else {
+        let mut ranges = args
+            .lines
+            .iter()
+            .map(|range| {
+                let (first, last) = range.split_once(':').unwrap();
+                first.parse::<usize>().unwrap()..=last.parse::<usize>().unwrap()
+            })
+            .collect_vec();
+        if let Some(file_lines) = &args.file_lines {
+            let file_lines: serde_json::Value = serde_json::from_str(file_lines).unwrap();
+            for entry in file_lines.as_array().unwrap() {
+                assert_eq!(entry["file"], "stdin");
+                let range = entry["range"].as_array().unwrap();
+                let first = usize::try_from(range[0].as_u64().unwrap()).unwrap();
+                let last = usize::try_from(range[1].as_u64().unwrap()).unwrap();
+                ranges.push(first..=last);
+            }
+        }
         let mut stdout = std::io::stdin()
             .lines()
-            .map(|line| {
+            .enumerate()
+            .map(|(index, line)| {
+                if !ranges.is_empty() && !ranges.iter().any(|range| range.contains(&(index + 1))) {
+                    return format!("{}\n", line.unwrap());
+                }
                 format!("{}\n", {
                     let line = if args.reverse {
                         line.unwrap().chars().rev().collect()
@@ -13,7 +13,10 @@ mod test_absorb_command;
 mod test_acls;
 mod test_advance_bookmarks;
 mod test_alias;
+mod test_apply_command;
+mod test_arrange_command;
 mod test_backout_command;
+mod test_bisect_command;
 mod test_bookmark_command;
 mod test_builtin_aliases;
 mod test_commit_command;
@@ -31,8 +34,10 @@ mod test_diffedit_command;
 mod test_duplicate_command;
 mod test_edit_command;
 mod test_evolog_command;
+mod test_export_patch_command;
 mod test_file_annotate_command;
 mod test_file_chmod_command;
+mod test_file_list_command;
 mod test_file_show_command;
 mod test_file_track_untrack_commands;
 mod test_fix_command;
@@ -52,6 +57,7 @@ mod test_help_command;
 mod test_immutable_commits;
 mod test_interdiff_command;
 mod test_log_command;
+mod test_metaedit_command;
 mod test_new_command;
 mod test_next_prev_commands;
 mod test_operations;
@@ -59,10 +65,12 @@ mod test_parallelize_command;
 mod test_rebase_command;
 mod test_repo_change_report;
 mod test_resolve_command;
+mod test_resolve_divergence_command;
 mod test_restore_command;
 mod test_revert_command;
 mod test_revset_output;
 mod test_root;
+mod test_serialize_command;
 mod test_show_command;
 mod test_sign_unsign_commands;
 mod test_simplify_parents_command;
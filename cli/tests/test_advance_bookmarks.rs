@@ -29,14 +29,14 @@ fn get_log_output_with_bookmarks(work_dir: &TestWorkDir) -> CommandOutput {
 fn set_advance_bookmarks(test_env: &TestEnvironment, enabled: bool) {
     if enabled {
         test_env.add_config(
-            r#"[experimental-advance-branches]
-        enabled-branches = ["glob:*"]
+            r#"[advance-bookmarks]
+        enabled-bookmarks = ["glob:*"]
         "#,
         );
     } else {
         test_env.add_config(
-            r#"[experimental-advance-branches]
-        enabled-branches = []
+            r#"[advance-bookmarks]
+        enabled-bookmarks = []
         "#,
         );
     }
@@ -427,6 +427,58 @@ fn test_new_advance_bookmarks_after() {
     ");
 }
 
+// `jj new --no-edit` advances bookmarks even though the working copy doesn't
+// move.
+#[test]
+fn test_new_advance_bookmarks_no_edit() {
+    let test_env = TestEnvironment::default();
+    test_env.run_jj_in(".", ["git", "init", "repo"]).success();
+    let work_dir = test_env.work_dir("repo");
+
+    set_advance_bookmarks(&test_env, true);
+    work_dir
+        .run_jj(["bookmark", "create", "-r", "@-", "test_bookmark"])
+        .success();
+
+    work_dir.run_jj(["describe", "-m", "first"]).success();
+    work_dir.run_jj(["new", "--no-edit", "@"]).success();
+    insta::assert_snapshot!(get_log_output_with_bookmarks(&work_dir), @r"
+    ○  bookmarks{} desc:
+    @  bookmarks{test_bookmark} desc: first
+    ◆  bookmarks{} desc:
+    [EOF]
+    ");
+}
+
+// `jj new` advances bookmarks even if the working copy already was a child of
+// the new parent.
+#[test]
+fn test_new_advance_bookmarks_same_parent() {
+    let test_env = TestEnvironment::default();
+    test_env.run_jj_in(".", ["git", "init", "repo"]).success();
+    let work_dir = test_env.work_dir("repo");
+
+    work_dir
+        .run_jj(["bookmark", "create", "-r", "@-", "test_bookmark"])
+        .success();
+    work_dir.run_jj(["commit", "-m", "first"]).success();
+    insta::assert_snapshot!(get_log_output_with_bookmarks(&work_dir), @r"
+    @  bookmarks{} desc:
+    ○  bookmarks{} desc: first
+    ◆  bookmarks{test_bookmark} desc:
+    [EOF]
+    ");
+
+    set_advance_bookmarks(&test_env, true);
+    work_dir.run_jj(["new", "@-"]).success();
+    insta::assert_snapshot!(get_log_output_with_bookmarks(&work_dir), @r"
+    @  bookmarks{} desc:
+    ○  bookmarks{test_bookmark} desc: first
+    ◆  bookmarks{} desc:
+    [EOF]
+    ");
+}
+
 #[test]
 fn test_new_advance_bookmarks_merge_children() {
     let test_env = TestEnvironment::default();
@@ -474,3 +526,133 @@ fn test_new_advance_bookmarks_merge_children() {
     [EOF]
     ");
 }
+
+// Only bookmarks matching the enabled patterns are advanced.
+#[test_case(commit_cmd ; "commit")]
+#[test_case(describe_new_cmd; "new")]
+fn test_advance_bookmarks_patterns(make_commit: CommitFn) {
+    let test_env = TestEnvironment::default();
+    test_env.run_jj_in(".", ["git", "init", "repo"]).success();
+    let work_dir = test_env.work_dir("repo");
+
+    test_env.add_config(
+        r#"[advance-bookmarks]
+    enabled-bookmarks = ["glob:feat/*"]
+    "#,
+    );
+    work_dir
+        .run_jj(["bookmark", "create", "-r", "@-", "feat/a", "main"])
+        .success();
+
+    make_commit(&work_dir, "first");
+    insta::allow_duplicates! {
+    insta::assert_snapshot!(get_log_output_with_bookmarks(&work_dir), @r"
+    @  bookmarks{} desc:
+    ○  bookmarks{feat/a} desc: first
+    ◆  bookmarks{main} desc:
+    [EOF]
+    ");
+    }
+}
+
+// Bookmarks can be selected by the commits they point to.
+#[test_case(commit_cmd ; "commit")]
+#[test_case(describe_new_cmd; "new")]
+fn test_advance_bookmarks_revset(make_commit: CommitFn) {
+    let test_env = TestEnvironment::default();
+    test_env.run_jj_in(".", ["git", "init", "repo"]).success();
+    let work_dir = test_env.work_dir("repo");
+
+    test_env.add_config(
+        r#"[advance-bookmarks]
+    enabled-revset = "root()"
+    disabled-bookmarks = ["main"]
+    "#,
+    );
+    work_dir
+        .run_jj(["bookmark", "create", "-r", "@-", "feature", "main"])
+        .success();
+
+    make_commit(&work_dir, "first");
+    insta::allow_duplicates! {
+    insta::assert_snapshot!(get_log_output_with_bookmarks(&work_dir), @r"
+    @  bookmarks{} desc:
+    ○  bookmarks{feature} desc: first
+    ◆  bookmarks{main} desc:
+    [EOF]
+    ");
+    }
+
+    // The bookmark now points to a commit outside the revset.
+    make_commit(&work_dir, "second");
+    insta::allow_duplicates! {
+    insta::assert_snapshot!(get_log_output_with_bookmarks(&work_dir), @r"
+    @  bookmarks{} desc:
+    ○  bookmarks{} desc: second
+    ○  bookmarks{feature} desc: first
+    ◆  bookmarks{main} desc:
+    [EOF]
+    ");
+    }
+}
+
+// `jj squash` only advances bookmarks which pointed to the old `@-`. Bookmarks
+// further down the stack stay where they are, even though the new `@-` is now
+// their child.
+#[test]
+fn test_squash_advance_bookmarks() {
+    let test_env = TestEnvironment::default();
+    test_env.run_jj_in(".", ["git", "init", "repo"]).success();
+    let work_dir = test_env.work_dir("repo");
+
+    work_dir
+        .run_jj(["bookmark", "create", "-r", "@-", "test_bookmark"])
+        .success();
+    work_dir.run_jj(["commit", "-m", "first"]).success();
+    work_dir.write_file("file", "contents\n");
+    work_dir.run_jj(["commit", "-m", "second"]).success();
+    insta::assert_snapshot!(get_log_output_with_bookmarks(&work_dir), @r"
+    @  bookmarks{} desc:
+    ○  bookmarks{} desc: second
+    ○  bookmarks{} desc: first
+    ◆  bookmarks{test_bookmark} desc:
+    [EOF]
+    ");
+
+    set_advance_bookmarks(&test_env, true);
+    work_dir
+        .run_jj(["squash", "-r", "@-", "--use-destination-message"])
+        .success();
+    insta::assert_snapshot!(get_log_output_with_bookmarks(&work_dir), @r"
+    @  bookmarks{} desc:
+    ○  bookmarks{} desc: first
+    ◆  bookmarks{test_bookmark} desc:
+    [EOF]
+    ");
+}
+
+#[test]
+fn test_advance_bookmarks_deprecated_config() {
+    let test_env = TestEnvironment::default();
+    test_env.run_jj_in(".", ["git", "init", "repo"]).success();
+    let work_dir = test_env.work_dir("repo");
+
+    test_env.add_config(
+        r#"[experimental-advance-branches]
+    enabled-branches = ["glob:*"]
+    "#,
+    );
+    work_dir
+        .run_jj(["bookmark", "create", "-r", "@-", "test_bookmark"])
+        .success();
+    work_dir.run_jj(["commit", "-m", "first"]).success();
+    insta::assert_snapshot!(get_log_output_with_bookmarks(&work_dir), @r"
+    @  bookmarks{} desc:
+    ○  bookmarks{test_bookmark} desc: first
+    ◆  bookmarks{} desc:
+    [EOF]
+    ------- stderr -------
+    Warning: Deprecated user-level config: experimental-advance-branches.enabled-branches is renamed to advance-bookmarks.enabled-bookmarks
+    [EOF]
+    ");
+}
@@ -0,0 +1,258 @@
+// Copyright 2025 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use indoc::indoc;
+
+use crate::common::CommandOutput;
+use crate::common::TestEnvironment;
+use crate::common::TestWorkDir;
+
+const SERIES: &str = indoc! {"
+    From 1111111111111111111111111111111111111111 Mon Sep 17 00:00:00 2001
+    From: Alice <alice@example.com>
+    Date: Mon, 1 Jan 2024 10:00:00 +0100
+    Subject: [PATCH 1/2] Change the middle
+
+    The hunk is recorded two lines too early.
+    ---
+     file | 2 +-
+     1 file changed, 1 insertion(+), 1 deletion(-)
+
+    diff --git a/file b/file
+    index 0123456..789abcd 100644
+    --- a/file
+    +++ b/file
+    @@ -2,3 +2,3 @@
+     4
+    -5
+    +five
+     6
+    --
+    2.40.0
+
+    From 2222222222222222222222222222222222222222 Mon Sep 17 00:00:00 2001
+    From: Bob <bob@example.com>
+    Date: Tue, 2 Jan 2024 10:00:00 +0100
+    Subject: [PATCH 2/2] Rename the file and add another
+
+    ---
+     file => renamed | 2 +-
+     new             | 1 +
+     2 files changed, 2 insertions(+), 1 deletion(-)
+     rename file => renamed (88%)
+     create mode 100644 new
+
+    diff --git a/file b/renamed
+    similarity index 88%
+    rename from file
+    rename to renamed
+    index 789abcd..fedcba9 100644
+    --- a/file
+    +++ b/renamed
+    @@ -7,3 +7,3 @@
+     7
+     8
+    -9
+    +nine
+    diff --git a/new b/new
+    new file mode 100644
+    index 0000000..3e75765
+    --- /dev/null
+    +++ b/new
+    @@ -0,0 +1 @@
+    +new
+    --
+    2.40.0
+"};
+
+#[test]
+fn test_apply_mailbox() {
+    let test_env = TestEnvironment::default();
+    test_env.run_jj_in(".", ["git", "init", "repo"]).success();
+    test_env.add_config(r#"templates.commit_summary = 'description.first_line()'"#);
+    let work_dir = test_env.work_dir("repo");
+    work_dir.write_file("file", "1\n2\n3\n4\n5\n6\n7\n8\n9\n");
+    work_dir.run_jj(["describe", "-m", "base"]).success();
+    std::fs::write(test_env.env_root().join("series.mbox"), SERIES).unwrap();
+
+    let output = work_dir.run_jj(["apply", "../series.mbox"]);
+    insta::assert_snapshot!(output, @r"
+    ------- stderr -------
+    Applied 2 patches:
+      Change the middle
+      Rename the file and add another
+    [EOF]
+    ");
+    insta::assert_snapshot!(get_log_output(&work_dir), @r"
+    ○  Rename the file and add another Bob 2024-01-02 10:00:00.000 +01:00
+    ○  Change the middle Alice 2024-01-01 10:00:00.000 +01:00
+    @  base Test User 2001-02-03 04:05:08.000 +07:00
+    ◆  1970-01-01 00:00:00.000 +00:00
+    [EOF]
+    ");
+    let output = work_dir.run_jj(["log", "--no-graph", "-r@+", "-T", "description"]);
+    insta::assert_snapshot!(output, @r"
+    Change the middle
+
+    The hunk is recorded two lines too early.
+    [EOF]
+    ");
+    let output = work_dir.run_jj(["file", "list", "-r@++"]);
+    insta::assert_snapshot!(output, @r"
+    new
+    renamed
+    [EOF]
+    ");
+    let output = work_dir.run_jj(["file", "show", "-r@++", "renamed"]);
+    insta::assert_snapshot!(output, @r"
+    1
+    2
+    3
+    4
+    five
+    6
+    7
+    8
+    nine
+    [EOF]
+    ");
+}
+
+#[test]
+fn test_apply_failed_hunk() {
+    let test_env = TestEnvironment::default();
+    test_env.run_jj_in(".", ["git", "init", "repo"]).success();
+    test_env.add_config(
+        r#"
+        templates.commit_summary = 'if(description, description.first_line(), "(no description set)")'
+        hints.resolving-conflicts = false
+        "#,
+    );
+    let work_dir = test_env.work_dir("repo");
+    work_dir.write_file("file", "1\n2\n3\n4\n5\n6\n7\n8\n9\n");
+    work_dir.run_jj(["describe", "-m", "base"]).success();
+    // The second hunk doesn't match anything
+    std::fs::write(
+        test_env.env_root().join("fix.patch"),
+        indoc! {"
+            --- a/file
+            +++ b/file
+            @@ -1,3 +1,3 @@
+            -1
+            +one
+             2
+             3
+            @@ -20,3 +20,3 @@
+             x
+            -y
+            +Y
+             z
+        "},
+    )
+    .unwrap();
+
+    let output = work_dir.run_jj(["apply", "../fix.patch"]);
+    insta::assert_snapshot!(output, @r"
+    ------- stderr -------
+    Applied 1 patches:
+      (no description set)
+    Warning: Some hunks couldn't be applied and were recorded as conflicts in these files:
+      file
+    New conflicts appeared in 1 commits:
+      (no description set)
+    [EOF]
+    ");
+    let output = work_dir.run_jj(["resolve", "--list", "-r@+"]);
+    insta::assert_snapshot!(output, @r"
+    file    2-sided conflict
+    [EOF]
+    ");
+
+    // An empty patch
+    std::fs::write(test_env.env_root().join("empty.patch"), "").unwrap();
+    let output = work_dir.run_jj(["apply", "../empty.patch"]);
+    insta::assert_snapshot!(output, @r"
+    ------- stderr -------
+    Warning: Skipped 1 messages without a patch
+    Error: No patches found
+    [EOF]
+    [exit status: 1]
+    ");
+}
+
+#[test]
+fn test_apply_missing_and_existing_files() {
+    let test_env = TestEnvironment::default();
+    test_env.run_jj_in(".", ["git", "init", "repo"]).success();
+    test_env.add_config(
+        r#"
+        templates.commit_summary = 'if(description, description.first_line(), "(no description set)")'
+        hints.resolving-conflicts = false
+        "#,
+    );
+    let work_dir = test_env.work_dir("repo");
+    work_dir.write_file("file", "old\n");
+    work_dir.run_jj(["describe", "-m", "base"]).success();
+    // The first file doesn't exist, and the second file already exists
+    std::fs::write(
+        test_env.env_root().join("fix.patch"),
+        indoc! {"
+            --- a/missing
+            +++ b/missing
+            @@ -1 +1 @@
+            -a
+            +b
+            --- /dev/null
+            +++ b/file
+            @@ -0,0 +1 @@
+            +new
+        "},
+    )
+    .unwrap();
+
+    let output = work_dir.run_jj(["apply", "../fix.patch"]);
+    insta::assert_snapshot!(output, @r"
+    ------- stderr -------
+    Applied 1 patches:
+      (no description set)
+    Warning: Some hunks couldn't be applied and were recorded as conflicts in these files:
+      file
+    Warning: Some changes couldn't be applied and were skipped:
+      Cannot patch missing: the file doesn't exist
+    New conflicts appeared in 1 commits:
+      (no description set)
+    [EOF]
+    ");
+    let output = work_dir.run_jj(["file", "show", "-r@+", "file"]);
+    insta::assert_snapshot!(output, @r"
+    <<<<<<< Conflict 1 of 1
+    %%%%%%% Changes from base to side #1
+    +old
+    +++++++ Contents of side #2
+    new
+    >>>>>>> Conflict 1 of 1 ends
+    [EOF]
+    ");
+    let output = work_dir.run_jj(["file", "list", "-r@+"]);
+    insta::assert_snapshot!(output, @r"
+    file
+    [EOF]
+    ");
+}
+
+#[must_use]
+fn get_log_output(work_dir: &TestWorkDir) -> CommandOutput {
+    let template = r#"separate(" ", description.first_line(), author.name(), author.timestamp())"#;
+    work_dir.run_jj(["log", "-T", template])
+}
@@ -0,0 +1,259 @@
+// Copyright 2025 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::common::CommandOutput;
+use crate::common::TestEnvironment;
+use crate::common::TestWorkDir;
+use crate::common::create_commit;
+
+fn set_up_stack(test_env: &mut TestEnvironment) {
+    test_env.run_jj_in(".", ["git", "init", "repo"]).success();
+    test_env.add_config(
+        r#"templates.commit_summary = 'separate(" ", change_id.short(3), description.first_line())'"#,
+    );
+    let work_dir = test_env.work_dir("repo");
+    create_commit(&work_dir, "a", &[]);
+    create_commit(&work_dir, "b", &["a"]);
+    create_commit(&work_dir, "c", &["b"]);
+}
+
+#[test]
+fn test_arrange_reorder_and_drop() {
+    let mut test_env = TestEnvironment::default();
+    set_up_stack(&mut test_env);
+    let edit_script = test_env.set_up_fake_editor();
+    let work_dir = test_env.work_dir("repo");
+    insta::assert_snapshot!(get_log_output(&work_dir), @r"
+    @  roy c
+    ○  zsu b
+    ○  rlv a
+    ◆  zzz
+    [EOF]
+    ");
+
+    std::fs::write(
+        &edit_script,
+        "dump plan\0write\npick royxmykx\npick rlvkpnrz\ndrop zsuskuln\n",
+    )
+    .unwrap();
+    let output = work_dir.run_jj(["arrange", "-r", "a::c"]);
+    insta::assert_snapshot!(output, @r"
+    ------- stderr -------
+    Working copy  (@) now at: roy c
+    Parent commit (@-)      : zzz
+    Added 0 files, modified 0 files, removed 2 files
+    [EOF]
+    ");
+    insta::assert_snapshot!(
+        std::fs::read_to_string(test_env.env_root().join("plan")).unwrap(), @r"
+    pick rlvkpnrzqnoo a
+    pick zsuskulnrvyr b
+    pick royxmykxtrkr c
+
+    JJ: Arrange the revisions above, which are listed from the oldest to the newest.
+    JJ: Each line starts with an action, followed by the change ID:
+    JJ:   pick      Keep the revision on top of the previous line
+    JJ:   reword    Like pick, but edit the description
+    JJ:   edit      Like pick, and edit the revision in the working copy afterwards
+    JJ:   squash    Squash the revision into the revision on the previous line
+    JJ:   parallel  Keep the revision as a sibling of the previous line
+    JJ:   drop      Abandon the revision
+    JJ: Reorder the lines to reorder the revisions. Remove all lines to abort.
+    ");
+    insta::assert_snapshot!(get_log_output(&work_dir), @r"
+    ○  rlv a
+    @  roy c
+    ◆  zzz
+    [EOF]
+    ");
+}
+
+#[test]
+fn test_arrange_squash_and_reword() {
+    let mut test_env = TestEnvironment::default();
+    set_up_stack(&mut test_env);
+    let edit_script = test_env.set_up_fake_editor();
+    let work_dir = test_env.work_dir("repo");
+
+    std::fs::write(
+        &edit_script,
+        [
+            "write\npick rlvkpnrz\nsquash zsuskuln\nreword royxmykx\n",
+            "next invocation\n",
+            "write\nnew c\n",
+        ]
+        .join("\0"),
+    )
+    .unwrap();
+    work_dir.run_jj(["arrange", "-r", "a::c"]).success();
+    insta::assert_snapshot!(get_log_output(&work_dir), @r"
+    @  roy new c
+    ○  rlv a
+    ◆  zzz
+    [EOF]
+    ");
+    let output = work_dir.run_jj(["log", "--no-graph", "-r", "a", "-T", "description"]);
+    insta::assert_snapshot!(output, @r"
+    a
+
+    b
+    [EOF]
+    ");
+    let output = work_dir.run_jj(["file", "list", "-r", "a"]);
+    insta::assert_snapshot!(output, @r"
+    a
+    b
+    [EOF]
+    ");
+}
+
+#[test]
+fn test_arrange_parallel() {
+    let mut test_env = TestEnvironment::default();
+    set_up_stack(&mut test_env);
+    let edit_script = test_env.set_up_fake_editor();
+    let work_dir = test_env.work_dir("repo");
+
+    // "c" becomes a sibling of "b", and "a" is placed on top of both
+    std::fs::write(
+        &edit_script,
+        "write\npick zsuskuln\nparallel royxmykx\npick rlvkpnrz\n",
+    )
+    .unwrap();
+    let output = work_dir.run_jj(["arrange", "-r", "a::c"]);
+    insta::assert_snapshot!(output, @r"
+    ------- stderr -------
+    Working copy  (@) now at: roy c
+    Parent commit (@-)      : zzz
+    Added 0 files, modified 0 files, removed 2 files
+    [EOF]
+    ");
+    insta::assert_snapshot!(get_log_output(&work_dir), @r"
+    ○    rlv a
+    ├─╮
+    │ @  roy c
+    ○ │  zsu b
+    ├─╯
+    ◆  zzz
+    [EOF]
+    ");
+}
+
+#[test]
+fn test_arrange_edit() {
+    let mut test_env = TestEnvironment::default();
+    set_up_stack(&mut test_env);
+    let edit_script = test_env.set_up_fake_editor();
+    let work_dir = test_env.work_dir("repo");
+
+    // The working copy moves to the edited revision, and the revisions placed
+    // after it are rebased onto it
+    std::fs::write(
+        &edit_script,
+        "write\nedit zsuskuln\npick rlvkpnrz\npick royxmykx\n",
+    )
+    .unwrap();
+    let output = work_dir.run_jj(["arrange", "-r", "a::c"]);
+    insta::assert_snapshot!(output, @r"
+    ------- stderr -------
+    Working copy  (@) now at: zsu b
+    Parent commit (@-)      : zzz
+    Added 0 files, modified 0 files, removed 2 files
+    [EOF]
+    ");
+    insta::assert_snapshot!(get_log_output(&work_dir), @r"
+    ○  roy c
+    ○  rlv a
+    @  zsu b
+    ◆  zzz
+    [EOF]
+    ");
+    let output = work_dir.run_jj(["file", "list", "-r", "c"]);
+    insta::assert_snapshot!(output, @r"
+    a
+    b
+    c
+    [EOF]
+    ");
+}
+
+#[test]
+fn test_arrange_invalid_plan() {
+    let mut test_env = TestEnvironment::default();
+    set_up_stack(&mut test_env);
+    let edit_script = test_env.set_up_fake_editor();
+    let work_dir = test_env.work_dir("repo");
+
+    // The errors are shown in the editor until the plan is fixed
+    std::fs::write(
+        &edit_script,
+        [
+            "write\nsquash rlvkpnrz\nfoo zsuskuln\n",
+            "next invocation\n",
+            "dump plan",
+            "write\npick rlvkpnrz\nparallel zsuskuln\nparallel royxmykx\n",
+        ]
+        .join("\0"),
+    )
+    .unwrap();
+    work_dir.run_jj(["arrange", "-r", "a::c"]).success();
+    insta::assert_snapshot!(
+        std::fs::read_to_string(test_env.env_root().join("plan")).unwrap(), @r"
+    JJ: Error: royxmykxtrkr is missing, use `drop` to abandon it
+    squash rlvkpnrz
+    JJ: Error: `squash` must follow a `pick`, `reword`, `edit`, or `squash` line
+    foo zsuskuln
+    JJ: Error: Unknown action `foo`
+    ");
+    insta::assert_snapshot!(get_log_output(&work_dir), @r"
+    @  roy c
+    │ ○  zsu b
+    ├─╯
+    │ ○  rlv a
+    ├─╯
+    ◆  zzz
+    [EOF]
+    ");
+
+    // Removing all lines aborts
+    std::fs::write(&edit_script, "write\n").unwrap();
+    let output = work_dir.run_jj(["arrange", "-r", "a|b|c"]);
+    insta::assert_snapshot!(output, @r"
+    ------- stderr -------
+    Nothing changed.
+    [EOF]
+    ");
+}
+
+#[test]
+fn test_arrange_unrelated_revisions() {
+    let mut test_env = TestEnvironment::default();
+    set_up_stack(&mut test_env);
+    let work_dir = test_env.work_dir("repo");
+
+    let output = work_dir.run_jj(["arrange", "-r", "a|c"]);
+    insta::assert_snapshot!(output, @r"
+    ------- stderr -------
+    Error: The revisions to arrange must all be based on the same parents
+    Hint: Include the revisions between them, for example with `jj arrange -r 'base..tip'`.
+    [EOF]
+    [exit status: 1]
+    ");
+}
+
+#[must_use]
+fn get_log_output(work_dir: &TestWorkDir) -> CommandOutput {
+    let template = r#"separate(" ", change_id.short(3), description.first_line())"#;
+    work_dir.run_jj(["log", "-T", template])
+}
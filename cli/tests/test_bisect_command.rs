@@ -0,0 +1,167 @@
+// Copyright 2025 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::common::TestEnvironment;
+use crate::common::create_commit;
+
+fn set_up_linear_history(test_env: &TestEnvironment) {
+    test_env.run_jj_in(".", ["git", "init", "repo"]).success();
+    test_env.add_config(
+        r#"templates.commit_summary = 'if(description, description.first_line(), "(no description)")'"#,
+    );
+    let work_dir = test_env.work_dir("repo");
+    create_commit(&work_dir, "a", &[]);
+    create_commit(&work_dir, "b", &["a"]);
+    create_commit(&work_dir, "c", &["b"]);
+    create_commit(&work_dir, "d", &["c"]);
+    create_commit(&work_dir, "e", &["d"]);
+}
+
+#[test]
+fn test_bisect_good_bad() {
+    let test_env = TestEnvironment::default();
+    set_up_linear_history(&test_env);
+    let work_dir = test_env.work_dir("repo");
+
+    let output = work_dir.run_jj(["bisect", "start", "--good=a", "--bad=e"]);
+    insta::assert_snapshot!(output, @r"
+    ------- stderr -------
+    Working copy  (@) now at: (no description)
+    Parent commit (@-)      : c
+    Added 0 files, modified 0 files, removed 2 files
+    Bisecting: 3 revisions left to test
+    Now evaluating: c
+    [EOF]
+    ");
+
+    // A second bisection can't be started
+    let output = work_dir.run_jj(["bisect", "start", "--good=a", "--bad=e"]);
+    insta::assert_snapshot!(output, @r"
+    ------- stderr -------
+    Error: A bisection is already in progress
+    Hint: Run `jj bisect reset` to end it first.
+    [EOF]
+    [exit status: 1]
+    ");
+
+    let output = work_dir.run_jj(["bisect", "good"]);
+    insta::assert_snapshot!(output, @r"
+    ------- stderr -------
+    Working copy  (@) now at: (no description)
+    Parent commit (@-)      : d
+    Added 1 files, modified 0 files, removed 0 files
+    Bisecting: 1 revisions left to test
+    Now evaluating: d
+    [EOF]
+    ");
+
+    let output = work_dir.run_jj(["bisect", "bad"]);
+    insta::assert_snapshot!(output, @r"
+    ------- stderr -------
+    The first bad revision is: d
+    Hint: Run `jj bisect reset` to end the bisection.
+    [EOF]
+    ");
+
+    // The state is recorded in the operation log
+    let output = work_dir.run_jj([
+        "op",
+        "log",
+        "--no-graph",
+        "-n3",
+        "-T",
+        r#"description ++ "\n""#,
+    ]);
+    let descriptions = output.stdout.normalized().lines().collect::<Vec<_>>();
+    assert!(descriptions[0].starts_with("bisect: mark "));
+    assert!(descriptions[0].ends_with(" as bad"));
+    assert!(descriptions[1].ends_with(" as good"));
+    assert_eq!(descriptions[2], "bisect: start");
+
+    // The state isn't shown as an operation tag
+    let output = work_dir.run_jj(["op", "log", "--no-graph", "-n1", "-T", r#"tags ++ "\n""#]);
+    insta::assert_snapshot!(output, @r"
+    args: jj bisect bad
+    [EOF]
+    ");
+
+    let output = work_dir.run_jj(["bisect", "reset"]);
+    insta::assert_snapshot!(output, @r"
+    ------- stderr -------
+    Working copy  (@) now at: e
+    Parent commit (@-)      : d
+    Added 1 files, modified 0 files, removed 0 files
+    [EOF]
+    ");
+
+    let output = work_dir.run_jj(["bisect", "good"]);
+    insta::assert_snapshot!(output, @r"
+    ------- stderr -------
+    Error: No bisection in progress
+    Hint: Start one with `jj bisect start --good <rev> --bad <rev>`.
+    [EOF]
+    [exit status: 1]
+    ");
+}
+
+#[test]
+fn test_bisect_skip() {
+    let test_env = TestEnvironment::default();
+    set_up_linear_history(&test_env);
+    let work_dir = test_env.work_dir("repo");
+
+    work_dir
+        .run_jj(["bisect", "start", "--good=a", "--bad=d"])
+        .success();
+    let output = work_dir.run_jj(["bisect", "skip"]);
+    insta::assert_snapshot!(output, @r"
+    ------- stderr -------
+    Working copy  (@) now at: (no description)
+    Parent commit (@-)      : c
+    Added 1 files, modified 0 files, removed 0 files
+    Bisecting: 1 revisions left to test
+    Now evaluating: c
+    [EOF]
+    ");
+
+    let output = work_dir.run_jj(["bisect", "bad"]);
+    insta::assert_snapshot!(output, @r"
+    ------- stderr -------
+    Some revisions were skipped, so the first bad revision is one of:
+      c
+      b
+    Hint: Run `jj bisect reset` to end the bisection.
+    [EOF]
+    ");
+}
+
+#[cfg(unix)]
+#[test]
+fn test_bisect_run() {
+    let test_env = TestEnvironment::default();
+    set_up_linear_history(&test_env);
+    let work_dir = test_env.work_dir("repo");
+
+    work_dir
+        .run_jj(["bisect", "start", "--good=a", "--bad=e"])
+        .success();
+    // The bug is that the file "d" exists
+    let output = work_dir.run_jj(["bisect", "run", "--", "test", "!", "-e", "d"]);
+    let stderr = output.stderr.normalized();
+    assert!(
+        stderr.contains("The first bad revision is: d\n"),
+        "unexpected output: {stderr}"
+    );
+    assert_eq!(stderr.matches("Running `test ! -e d` on ").count(), 2);
+}
@@ -1015,6 +1015,9 @@ fn test_config() {
     let output = test_env.complete_fish(["config", "get", "f"]);
     insta::assert_snapshot!(output, @r"
     fsmonitor.backend	Whether to use an external filesystem monitor, useful for large repos
+    fsmonitor.inotify.daemon-command	Program and arguments to start the inotify daemon with. Defaults to `jj debug inotify daemon`.
+    fsmonitor.inotify.max-tracked-paths	Maximum number of changed paths the inotify daemon remembers before falling back to a full scan.
+    fsmonitor.inotify.spawn-daemon	Whether to start the inotify daemon automatically if it isn't running.
     fsmonitor.watchman.register-snapshot-trigger	Whether to use triggers to monitor for changes in the background.
     [EOF]
     ");
@@ -1023,6 +1026,10 @@ fn test_config() {
     insta::assert_snapshot!(output, @r"
     fsmonitor	External filesystem monitor settings, useful for large repos
     fsmonitor.backend	Whether to use an external filesystem monitor, useful for large repos
+    fsmonitor.inotify
+    fsmonitor.inotify.daemon-command	Program and arguments to start the inotify daemon with. Defaults to `jj debug inotify daemon`.
+    fsmonitor.inotify.max-tracked-paths	Maximum number of changed paths the inotify daemon remembers before falling back to a full scan.
+    fsmonitor.inotify.spawn-daemon	Whether to start the inotify daemon automatically if it isn't running.
     fsmonitor.watchman
     fsmonitor.watchman.register-snapshot-trigger	Whether to use triggers to monitor for changes in the background.
     [EOF]
@@ -1031,6 +1038,9 @@ fn test_config() {
     let output = test_env.complete_fish(["log", "--config", "f"]);
     insta::assert_snapshot!(output, @r"
     fsmonitor.backend=	Whether to use an external filesystem monitor, useful for large repos
+    fsmonitor.inotify.daemon-command=	Program and arguments to start the inotify daemon with. Defaults to `jj debug inotify daemon`.
+    fsmonitor.inotify.max-tracked-paths=	Maximum number of changed paths the inotify daemon remembers before falling back to a full scan.
+    fsmonitor.inotify.spawn-daemon=	Whether to start the inotify daemon automatically if it isn't running.
     fsmonitor.watchman.register-snapshot-trigger=	Whether to use triggers to monitor for changes in the background.
     [EOF]
     ");
@@ -0,0 +1,241 @@
+// Copyright 2025 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use testutils::git;
+
+use crate::common::TestEnvironment;
+
+#[test]
+fn test_export_patch_stdout() {
+    let test_env = TestEnvironment::default();
+    test_env.run_jj_in(".", ["git", "init", "repo"]).success();
+    let work_dir = test_env.work_dir("repo");
+    work_dir.write_file("file", "1\n2\n3\n");
+    work_dir.run_jj(["describe", "-m", "base"]).success();
+    work_dir
+        .run_jj(["new", "-m", "Change the file\n\nWith some details."])
+        .success();
+    work_dir.write_file("file", "1\ntwo\n3\n");
+
+    let output = work_dir
+        .run_jj(["export-patch", "--stdout"])
+        .normalize_stdout_with(normalize_commit_ids);
+    insta::assert_snapshot!(output, @r"
+    From [commit] Mon Sep 17 00:00:00 2001
+    From: Test User <test.user@example.com>
+    Date: Sat, 3 Feb 2001 04:05:09 +0700
+    Subject: [PATCH] Change the file
+    MIME-Version: 1.0
+    Content-Type: text/plain; charset=UTF-8
+    Content-Transfer-Encoding: 8bit
+
+    With some details.
+    ---
+     file | 2 +-
+     1 file changed, 1 insertion(+), 1 deletion(-)
+
+    diff --git a/file b/file
+    index 01e79c32a8c99c557f0757da7cb6d65b3414466d..d8eb09865eea17463394416e1be16d5bc553da88 100644
+    --- a/file
+    +++ b/file
+    @@ -1,3 +1,3 @@
+     1
+    -2
+    +two
+     3
+
+    [EOF]
+    ");
+
+    let output = work_dir.run_jj(["export-patch", "--stdout", "-r", "none()"]);
+    insta::assert_snapshot!(output, @r"
+    ------- stderr -------
+    No revisions to export.
+    [EOF]
+    ");
+
+    let output = work_dir.run_jj(["export-patch", "--stdout", "-r", "root()"]);
+    insta::assert_snapshot!(output, @r"
+    ------- stderr -------
+    Error: Cannot export revision 000000000000 because it doesn't have exactly one parent
+    [EOF]
+    [exit status: 1]
+    ");
+}
+
+#[test]
+fn test_export_patch_files_round_trip() {
+    let test_env = TestEnvironment::default();
+    test_env.run_jj_in(".", ["git", "init", "repo"]).success();
+    test_env.add_config(r#"templates.commit_summary = 'description.first_line()'"#);
+    let work_dir = test_env.work_dir("repo");
+    work_dir.write_file("file", "1\n2\n3\n");
+    work_dir.run_jj(["describe", "-m", "base"]).success();
+    work_dir.run_jj(["new", "-m", "Change the file"]).success();
+    work_dir.write_file("file", "1\ntwo\n3\n");
+    work_dir.run_jj(["new", "-m", "Add another file"]).success();
+    work_dir.write_file("new", "new\n");
+
+    let output = work_dir.run_jj([
+        "export-patch",
+        "-r=@-::@",
+        "--cover-letter",
+        "-o=../patches",
+    ]);
+    insta::assert_snapshot!(output.normalize_backslash(), @r"
+    ------- stderr -------
+    Wrote 3 patch files:
+      ../patches/0000-cover-letter.patch
+      ../patches/0001-Change-the-file.patch
+      ../patches/0002-Add-another-file.patch
+    [EOF]
+    ");
+    let patches_dir = test_env.env_root().join("patches");
+    let cover_letter =
+        std::fs::read_to_string(patches_dir.join("0000-cover-letter.patch")).unwrap();
+    insta::assert_snapshot!(normalize_commit_ids(cover_letter), @r"
+    From [commit] Mon Sep 17 00:00:00 2001
+    From: Test User <test.user@example.com>
+    Date: Sat, 3 Feb 2001 04:05:11 +0700
+    Subject: [PATCH 0/2] *** SUBJECT HERE ***
+    MIME-Version: 1.0
+    Content-Type: text/plain; charset=UTF-8
+    Content-Transfer-Encoding: 8bit
+
+    *** BLURB HERE ***
+
+      Change the file
+      Add another file
+
+     file | 2 +-
+     new  | 1 +
+     2 files changed, 2 insertions(+), 1 deletion(-)
+    ");
+
+    // The patches can be applied to recreate the revisions
+    let output = work_dir.run_jj([
+        "apply",
+        "-d=description(base)",
+        "../patches/0000-cover-letter.patch",
+        "../patches/0001-Change-the-file.patch",
+        "../patches/0002-Add-another-file.patch",
+    ]);
+    insta::assert_snapshot!(output, @r"
+    ------- stderr -------
+    Warning: Skipped 1 messages without a patch
+    Applied 2 patches:
+      Change the file
+      Add another file
+    [EOF]
+    ");
+    let output = work_dir.run_jj(["diff", "--from=@", "--to=visible_heads() ~ @"]);
+    insta::assert_snapshot!(output, @"");
+    let output = work_dir.run_jj([
+        "log",
+        "--no-graph",
+        "-r=visible_heads() ~ @",
+        r#"-T=author ++ " " ++ author.timestamp() ++ "\n""#,
+    ]);
+    insta::assert_snapshot!(output, @r"
+    Test User <test.user@example.com> 2001-02-03 04:05:10.000 +07:00
+    [EOF]
+    ");
+}
+
+#[test]
+fn test_export_patch_git_am() {
+    let test_env = TestEnvironment::default();
+    test_env.run_jj_in(".", ["git", "init", "repo"]).success();
+    let work_dir = test_env.work_dir("repo");
+    work_dir.write_file("file", "1\n2\n3\n");
+    work_dir.write_file("binary", b"\0old\n");
+    work_dir.run_jj(["describe", "-m", "Add files"]).success();
+    work_dir.run_jj(["new", "-m", "Change the files"]).success();
+    work_dir.write_file("file", "1\ntwo\n3\n");
+    let binary: Vec<u8> = (0..=255).cycle().take(1000).collect();
+    work_dir.write_file("binary", &binary);
+    work_dir.run_jj(["new"]).success();
+
+    work_dir
+        .run_jj(["export-patch", "-r=..@-", "-o=../patches"])
+        .success();
+    let patches_dir = test_env.env_root().join("patches");
+    let mut patches = std::fs::read_dir(&patches_dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .collect::<Vec<_>>();
+    patches.sort();
+
+    // `git am` recreates the same trees in a plain Git repo
+    let git_repo_path = test_env.env_root().join("git-repo");
+    let git_repo = git::init(&git_repo_path);
+    let output = std::process::Command::new("git")
+        .current_dir(&git_repo_path)
+        .env("GIT_CONFIG_GLOBAL", "/dev/null")
+        .env("GIT_CONFIG_SYSTEM", "/dev/null")
+        .args([
+            "-c",
+            "user.name=Someone",
+            "-c",
+            "user.email=someone@example.com",
+        ])
+        .arg("am")
+        .args(&patches)
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "git am failed:\n{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let commit_id = work_dir
+        .run_jj(["log", "--no-graph", "-r=@-", "-T=commit_id"])
+        .success()
+        .stdout
+        .into_raw();
+    let jj_git_repo = git::open(work_dir.root().join(".jj/repo/store/git"));
+    let expected_tree_id = jj_git_repo
+        .find_commit(gix::ObjectId::from_hex(commit_id.as_bytes()).unwrap())
+        .unwrap()
+        .tree_id()
+        .unwrap()
+        .detach();
+    let actual_tree_id = git_repo.head_commit().unwrap().tree_id().unwrap().detach();
+    assert_eq!(actual_tree_id, expected_tree_id);
+    let output = std::process::Command::new("git")
+        .current_dir(&git_repo_path)
+        .env("GIT_CONFIG_GLOBAL", "/dev/null")
+        .env("GIT_CONFIG_SYSTEM", "/dev/null")
+        .args(["log", "--format=%an <%ae> %s"])
+        .output()
+        .unwrap();
+    insta::assert_snapshot!(String::from_utf8(output.stdout).unwrap(), @r"
+    Test User <test.user@example.com> Change the files
+    Test User <test.user@example.com> Add files
+    ");
+}
+
+/// Replaces the commit IDs in the mbox separator lines.
+fn normalize_commit_ids(text: String) -> String {
+    text.split_inclusive('\n')
+        .map(|line| match line.strip_prefix("From ") {
+            Some(rest) if rest.starts_with(|c: char| c.is_ascii_hexdigit()) => {
+                let (_, rest) = rest.split_once(' ').unwrap();
+                format!("From [commit] {rest}")
+            }
+            _ => line.to_owned(),
+        })
+        .collect()
+}
@@ -0,0 +1,71 @@
+// Copyright 2025 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use testutils::git;
+
+use crate::common::TestEnvironment;
+
+#[test]
+fn test_file_list_collisions() {
+    let test_env = TestEnvironment::default();
+    test_env.run_jj_in(".", ["git", "init", "repo"]).success();
+    let work_dir = test_env.work_dir("repo");
+    let git_repo = git::open(work_dir.root().join(".jj/repo/store/git"));
+
+    // The colliding paths are created in Git so that the test doesn't depend on
+    // the file system of the working copy.
+    let mut parents = vec![];
+    for path in ["Dir/a", "dir/b", "Foo.txt", "foo.txt", "other"] {
+        let commit_id = git::add_commit(
+            &git_repo,
+            "refs/heads/collisions",
+            path,
+            b"contents\n",
+            path,
+            &parents,
+        )
+        .commit_id;
+        parents = vec![commit_id];
+    }
+    work_dir.run_jj(["git", "import"]).success();
+
+    let output = work_dir.run_jj(["file", "list", "-rcollisions"]);
+    insta::assert_snapshot!(output, @r"
+    Dir/a
+    Foo.txt
+    dir/b
+    foo.txt
+    other
+    [EOF]
+    ");
+
+    let output = work_dir.run_jj(["file", "list", "-rcollisions", "--collisions"]);
+    insta::assert_snapshot!(output, @r"
+    Dir/a
+    Foo.txt
+    dir/b
+    foo.txt
+    [EOF]
+    ------- stderr -------
+    Warning: Dir collides with dir
+    Warning: Foo.txt collides with foo.txt
+    Error: Some paths would collide on file systems which ignore case or Unicode normalization
+    [EOF]
+    [exit status: 1]
+    ");
+
+    // No collisions among the matched files
+    let output = work_dir.run_jj(["file", "list", "-rcollisions", "--collisions", "other"]);
+    insta::assert_snapshot!(output, @"");
+}
@@ -1482,3 +1482,110 @@ fn test_all_files() {
     [EOF]
     ");
 }
+
+#[test]
+fn test_fix_changed_lines() {
+    let test_env = TestEnvironment::default();
+    test_env.run_jj_in(".", ["git", "init", "repo"]).success();
+    test_env.add_config(r#"templates.commit_summary = 'description.first_line()'"#);
+    set_up_fake_formatter(
+        &test_env,
+        &["--uppercase", "--lines=$first_line:$last_line"],
+    );
+    let work_dir = test_env.work_dir("repo");
+
+    work_dir.write_file("file", "a\nb\nc\nd\n");
+    work_dir.run_jj(["commit", "-m=parent"]).success();
+    work_dir.run_jj(["describe", "-m=child"]).success();
+    work_dir.write_file("file", "a\nx\nc\nd\ny\n");
+    let output = work_dir.run_jj(["fix", "-s", "@"]);
+    insta::assert_snapshot!(output, @r"
+    ------- stderr -------
+    Fixed 1 commits of 1 checked.
+    Working copy  (@) now at: child
+    Parent commit (@-)      : parent
+    Added 0 files, modified 1 files, removed 0 files
+    [EOF]
+    ");
+    insta::assert_snapshot!(work_dir.read_file("file"), @r"
+    a
+    X
+    c
+    d
+    Y
+    ");
+
+    // The tool is not run if lines were only removed
+    work_dir.run_jj(["new", "-m=deletion"]).success();
+    work_dir.write_file("file", "a\nX\nc\nY\n");
+    let output = work_dir.run_jj(["fix", "-s", "@"]);
+    insta::assert_snapshot!(output, @r"
+    ------- stderr -------
+    Fixed 0 commits of 1 checked.
+    Nothing changed.
+    [EOF]
+    ");
+}
+
+#[test]
+fn test_fix_changed_line_ranges_in_one_argument() {
+    let test_env = TestEnvironment::default();
+    test_env.run_jj_in(".", ["git", "init", "repo"]).success();
+    test_env.add_config(r#"templates.commit_summary = 'description.first_line()'"#);
+    // The formatter only accepts a single --file-lines argument
+    set_up_fake_formatter(&test_env, &["--uppercase", "--file-lines=$line_ranges"]);
+    let work_dir = test_env.work_dir("repo");
+
+    work_dir.write_file("file", "a\nb\nc\nd\n");
+    work_dir.run_jj(["commit", "-m=parent"]).success();
+    work_dir.run_jj(["describe", "-m=child"]).success();
+    work_dir.write_file("file", "a\nx\nc\nd\ny\nz\n");
+    let output = work_dir.run_jj(["fix", "-s", "@"]);
+    insta::assert_snapshot!(output, @r"
+    ------- stderr -------
+    Fixed 1 commits of 1 checked.
+    Working copy  (@) now at: child
+    Parent commit (@-)      : parent
+    Added 0 files, modified 1 files, removed 0 files
+    [EOF]
+    ");
+    insta::assert_snapshot!(work_dir.read_file("file"), @r"
+    a
+    X
+    c
+    d
+    Y
+    Z
+    ");
+}
+
+#[test]
+fn test_fix_changed_lines_only() {
+    let test_env = TestEnvironment::default();
+    test_env.run_jj_in(".", ["git", "init", "repo"]).success();
+    test_env.add_config(r#"templates.commit_summary = 'description.first_line()'"#);
+    set_up_fake_formatter(&test_env, &["--uppercase"]);
+    test_env.add_config("fix.tools.fake-formatter.changed-lines-only = true");
+    let work_dir = test_env.work_dir("repo");
+
+    work_dir.write_file("file", "a\nb\nc\n");
+    work_dir.run_jj(["commit", "-m=parent"]).success();
+    work_dir.run_jj(["describe", "-m=child"]).success();
+    work_dir.write_file("file", "a\nx\nc\nz\n");
+    let output = work_dir.run_jj(["fix", "-s", "@"]);
+    insta::assert_snapshot!(output, @r"
+    ------- stderr -------
+    Fixed 1 commits of 1 checked.
+    Working copy  (@) now at: child
+    Parent commit (@-)      : parent
+    Added 0 files, modified 1 files, removed 0 files
+    [EOF]
+    ");
+    // Only the changed lines were uppercased
+    insta::assert_snapshot!(work_dir.read_file("file"), @r"
+    a
+    X
+    c
+    Z
+    ");
+}
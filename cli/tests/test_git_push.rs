@@ -2244,6 +2244,70 @@ fn test_git_push_rejected_by_remote() {
     });
 }
 
+#[test]
+fn test_git_push_and_fetch_with_gix_without_git_executable() {
+    let mut test_env = TestEnvironment::default();
+    set_up(&test_env);
+    let empty_dir = test_env.env_root().join("empty-path");
+    std::fs::create_dir(&empty_dir).unwrap();
+    test_env.add_env_var("PATH", empty_dir.to_str().unwrap());
+    test_env.add_config(r#"git.transport = "gix""#);
+    let origin_dir = test_env.work_dir("origin");
+    let work_dir = test_env.work_dir("local");
+
+    work_dir
+        .run_jj(["new", "bookmark1", "-m=new commit"])
+        .success();
+    work_dir
+        .run_jj(["bookmark", "set", "bookmark1", "-r@"])
+        .success();
+    let output = work_dir.run_jj(["git", "push"]);
+    insta::assert_snapshot!(output, @r"
+    ------- stderr -------
+    Changes to push to origin:
+      Move forward bookmark bookmark1 from 9b2e76de3920 to b1ab2dfdf0c7
+    [EOF]
+    ");
+
+    // The lease check rejects the deletion of a bookmark moved on the remote
+    origin_dir
+        .run_jj(["describe", "bookmark2", "-m=modified on origin"])
+        .success();
+    origin_dir.run_jj(["git", "export"]).success();
+    work_dir
+        .run_jj(["bookmark", "delete", "bookmark2"])
+        .success();
+    let output = work_dir.run_jj(["git", "push", "--deleted"]);
+    insta::assert_snapshot!(output, @r"
+    ------- stderr -------
+    Changes to push to origin:
+      Delete bookmark bookmark2 from 38a204733702
+    Error: Failed to push some bookmarks
+    Hint: The following references unexpectedly moved on the remote:
+      refs/heads/bookmark2 (reason: stale info)
+    Hint: Try fetching from the remote, then make the bookmark point to where you want it to be, and push again.
+    [EOF]
+    [exit status: 1]
+    ");
+
+    let output = work_dir.run_jj(["git", "fetch"]);
+    insta::assert_snapshot!(output, @r"
+    ------- stderr -------
+    bookmark: bookmark2@origin [updated] tracked
+    Abandoned 1 commits that are no longer reachable.
+    [EOF]
+    ");
+    insta::assert_snapshot!(get_bookmark_output(&work_dir), @r"
+    bookmark1: vruxwmqv b1ab2dfd (empty) new commit
+      @origin: vruxwmqv b1ab2dfd (empty) new commit
+    bookmark2 (conflicted):
+      - zsuskuln hidden 38a20473 (empty) description 2
+      + zsuskuln 5d91b692 (empty) modified on origin
+      @origin: zsuskuln 5d91b692 (empty) modified on origin
+    [EOF]
+    ");
+}
+
 #[must_use]
 fn get_bookmark_output(work_dir: &TestWorkDir) -> CommandOutput {
     // --quiet to suppress deleted bookmarks hint
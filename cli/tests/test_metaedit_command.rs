@@ -0,0 +1,116 @@
+// Copyright 2025 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::common::CommandOutput;
+use crate::common::TestEnvironment;
+use crate::common::TestWorkDir;
+
+#[test]
+fn test_metaedit_author_and_timestamps() {
+    let test_env = TestEnvironment::default();
+    test_env.run_jj_in(".", ["git", "init", "repo"]).success();
+    let work_dir = test_env.work_dir("repo");
+    work_dir.run_jj(["describe", "-m", "first"]).success();
+    work_dir.run_jj(["new", "-m", "second"]).success();
+
+    work_dir
+        .run_jj([
+            "metaedit",
+            "-r@-",
+            "--author=Alice <alice@example.com>",
+            "--author-timestamp=2000-01-01T00:00:00+00:00",
+        ])
+        .success();
+    insta::assert_snapshot!(get_log_output(&work_dir), @r"
+    @  second Test User 2001-02-03 04:05:09.000 +07:00 2001-02-03 04:05:10.000 +07:00
+    ○  first Alice 2000-01-01 00:00:00.000 +00:00 2001-02-03 04:05:08.000 +07:00
+    ◆  1970-01-01 00:00:00.000 +00:00 1970-01-01 00:00:00.000 +00:00
+    [EOF]
+    ");
+
+    work_dir
+        .run_jj(["metaedit", "--update-committer-timestamp"])
+        .success();
+    insta::assert_snapshot!(get_log_output(&work_dir), @r"
+    @  second Test User 2001-02-03 04:05:09.000 +07:00 2001-02-03 04:05:12.000 +07:00
+    ○  first Alice 2000-01-01 00:00:00.000 +00:00 2001-02-03 04:05:08.000 +07:00
+    ◆  1970-01-01 00:00:00.000 +00:00 1970-01-01 00:00:00.000 +00:00
+    [EOF]
+    ");
+
+    // Nothing to change
+    let output = work_dir.run_jj(["metaedit", "--author=Test User <test.user@example.com>"]);
+    insta::assert_snapshot!(output, @r"
+    ------- stderr -------
+    Nothing changed.
+    [EOF]
+    ");
+
+    let output = work_dir.run_jj(["metaedit", "--author-timestamp=yesterday"]);
+    insta::assert_snapshot!(output, @r"
+    ------- stderr -------
+    error: invalid value 'yesterday' for '--author-timestamp <TIMESTAMP>': input contains invalid characters
+
+    For more information, try '--help'.
+    [EOF]
+    [exit status: 2]
+    ");
+}
+
+#[test]
+fn test_metaedit_update_change_id() {
+    let test_env = TestEnvironment::default();
+    test_env.run_jj_in(".", ["git", "init", "repo"]).success();
+    let work_dir = test_env.work_dir("repo");
+    work_dir.run_jj(["describe", "-m", "first"]).success();
+    work_dir.run_jj(["new", "-m", "second"]).success();
+
+    let get_change_id = || {
+        work_dir
+            .run_jj(["log", "--no-graph", "-r=description(first)", "-T=change_id"])
+            .success()
+            .stdout
+            .into_raw()
+    };
+    let old_change_id = get_change_id();
+    work_dir
+        .run_jj(["metaedit", "-r=description(first)", "--update-change-id"])
+        .success();
+    assert_ne!(get_change_id(), old_change_id);
+
+    // The old commit is hidden, and the descendant is rebased
+    let output = work_dir.run_jj([
+        "log",
+        "--no-graph",
+        "-r=description(first)::",
+        r#"-T=description.first_line() ++ "\n""#,
+    ]);
+    insta::assert_snapshot!(output, @r"
+    second
+    first
+    [EOF]
+    ");
+}
+
+#[must_use]
+fn get_log_output(work_dir: &TestWorkDir) -> CommandOutput {
+    let template = r#"
+    separate(" ",
+        description.first_line(),
+        author.name(),
+        author.timestamp(),
+        committer.timestamp()
+    )"#;
+    work_dir.run_jj(["log", "-T", template])
+}
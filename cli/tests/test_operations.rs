@@ -15,6 +15,7 @@
 use std::path::Path;
 use std::path::PathBuf;
 
+use bstr::ByteSlice as _;
 use itertools::Itertools as _;
 use regex::Regex;
 use testutils::git;
@@ -734,14 +735,15 @@ fn test_op_abandon_ancestors() {
     [EOF]
     ");
     insta::assert_snapshot!(work_dir.run_jj(["debug", "local-working-copy", "--ignore-working-copy"]), @r#"
-    Current operation: OperationId("4666f57b051cb0d73145999d689331e34224bf6bad69c367498d7fc25f0d89d230146e7234526004426d515b396b13b9de8daadf54003eaf678f4264c0a7d992")
+    Current operation: OperationId("8e5d8ddbe29516ebde2fe2c7d699c720532501a70abacca01fb32662df22405976b5307228af675b584a86b1b35affb0bc67fe1ec286a55a70d26f6419d6eb14")
     Current tree: Merge(Resolved(TreeId("4b825dc642cb6eb9a060e54bf8d69288fbee4904")))
     [EOF]
     "#);
     insta::assert_snapshot!(work_dir.run_jj(["op", "log"]), @r"
-    @  4666f57b051c test-username@host.example.com 2001-02-03 04:05:21.000 +07:00 - 2001-02-03 04:05:21.000 +07:00
+    @  8e5d8ddbe295 test-username@host.example.com 2001-02-03 04:05:21.000 +07:00 - 2001-02-03 04:05:21.000 +07:00
     │  undo operation 9df33337d49450b21bf694025557ede1ac4c63c7b17f593add0d7adc81b394d363f1edffa025b323f88ec947dcd9214f46e82e742e7a74adbfff4c2d96321133
     │  args: jj undo
+    │  undo-of: 9df33337d49450b21bf694025557ede1ac4c63c7b17f593add0d7adc81b394d363f1edffa025b323f88ec947dcd9214f46e82e742e7a74adbfff4c2d96321133
     ○  1675333b7de8 test-username@host.example.com 2001-02-03 04:05:09.000 +07:00 - 2001-02-03 04:05:09.000 +07:00
     │  commit 4e0592f3dd52e7a4998a97d9a1f354e2727a856b
     │  args: jj commit -m 'commit 2'
@@ -757,9 +759,10 @@ fn test_op_abandon_ancestors() {
     [EOF]
     ");
     insta::assert_snapshot!(work_dir.run_jj(["op", "log", "-n1"]), @r"
-    @  4666f57b051c test-username@host.example.com 2001-02-03 04:05:21.000 +07:00 - 2001-02-03 04:05:21.000 +07:00
+    @  8e5d8ddbe295 test-username@host.example.com 2001-02-03 04:05:21.000 +07:00 - 2001-02-03 04:05:21.000 +07:00
     │  undo operation 9df33337d49450b21bf694025557ede1ac4c63c7b17f593add0d7adc81b394d363f1edffa025b323f88ec947dcd9214f46e82e742e7a74adbfff4c2d96321133
     │  args: jj undo
+    │  undo-of: 9df33337d49450b21bf694025557ede1ac4c63c7b17f593add0d7adc81b394d363f1edffa025b323f88ec947dcd9214f46e82e742e7a74adbfff4c2d96321133
     [EOF]
     ");
 }
@@ -2215,6 +2218,87 @@ fn test_op_diff_word_wrap() {
     ");
 }
 
+#[test]
+fn test_op_diff_json() {
+    let test_env = TestEnvironment::default();
+    test_env.run_jj_in(".", ["git", "init", "repo"]).success();
+    let work_dir = test_env.work_dir("repo");
+    let from_op_id = work_dir.current_operation_id();
+    work_dir.run_jj(["describe", "-m", "first"]).success();
+    work_dir
+        .run_jj(["bookmark", "create", "-r", "@", "bookmark-1"])
+        .success();
+    let to_op_id = work_dir.current_operation_id();
+
+    let get_log_output = |op_id: &str, template: &str| {
+        let args = ["log", "--at-op", op_id, "--no-graph", "-r@", "-T", template];
+        work_dir.run_jj(args).success().stdout.into_raw()
+    };
+    let old_commit_id = get_log_output(&from_op_id, "commit_id");
+    let new_commit_id = get_log_output(&to_op_id, "commit_id");
+    let change_id = get_log_output(&to_op_id, "change_id");
+
+    let output = work_dir.run_jj(["op", "diff", "--from", &from_op_id, "--json"]);
+    let value: serde_json::Value = serde_json::from_str(output.success().stdout.raw()).unwrap();
+    assert_eq!(
+        value,
+        serde_json::json!({
+            "from_operations": [from_op_id],
+            "to_operation": to_op_id,
+            "added_heads": [new_commit_id],
+            "removed_heads": [old_commit_id],
+            "changed_commits": [{
+                "change_id": change_id,
+                "commit": new_commit_id,
+                "predecessors": [old_commit_id],
+            }],
+            "working_copies": [{
+                "name": "default",
+                "from": old_commit_id,
+                "to": new_commit_id,
+            }],
+            "local_bookmarks": [{
+                "name": "bookmark-1",
+                "from": [null],
+                "to": [new_commit_id],
+            }],
+            "tags": [],
+            "remote_bookmarks": [],
+        })
+    );
+
+    // `op show` prints the same object for the last operation
+    let output = work_dir.run_jj(["op", "show", "--json"]);
+    let value: serde_json::Value = serde_json::from_str(output.success().stdout.raw()).unwrap();
+    assert_eq!(value["to_operation"], to_op_id);
+    assert_eq!(value["changed_commits"], serde_json::json!([]));
+    assert_eq!(
+        value["local_bookmarks"][0]["to"],
+        serde_json::json!([new_commit_id])
+    );
+
+    // JSON output can't be combined with the graph options
+    let output = work_dir.run_jj(["op", "diff", "--json", "--patch"]);
+    assert!(!output.status.success());
+
+    // A merge operation lists its parents
+    work_dir
+        .run_jj(["describe", "--at-op", &from_op_id, "-m", "concurrent"])
+        .success();
+    let output = work_dir.run_jj(["op", "log", "--no-graph", "-n1", "-T", "json(self)"]);
+    let merge_op: serde_json::Value = serde_json::from_str(output.success().stdout.raw()).unwrap();
+    assert_eq!(merge_op["parents"].as_array().unwrap().len(), 2);
+    let output = work_dir.run_jj(["op", "show", "--json"]);
+    let value: serde_json::Value = serde_json::from_str(output.success().stdout.raw()).unwrap();
+    assert_eq!(
+        value,
+        serde_json::json!({
+            "from_operations": merge_op["parents"],
+            "to_operation": merge_op["id"],
+        })
+    );
+}
+
 #[test]
 fn test_op_show() {
     let test_env = TestEnvironment::default();
@@ -2771,6 +2855,98 @@ fn test_op_log_parents() {
     "###);
 }
 
+#[test]
+fn test_op_verify() {
+    let test_env = TestEnvironment::default();
+    test_env.run_jj_in(".", ["git", "init", "repo"]).success();
+    let work_dir = test_env.work_dir("repo");
+    test_env.add_config(
+        r#"
+        signing.backend = "test"
+        signing.sign-operations = true
+        ui.show-cryptographic-signatures = true
+        "#,
+    );
+    work_dir
+        .run_jj(["describe", "-m", "description 0"])
+        .success();
+
+    // The operation created before signing was enabled isn't signed
+    let output = work_dir.run_jj(["op", "log"]);
+    insta::assert_snapshot!(output, @r"
+    @  04041135258c test-username@host.example.com 2001-02-03 04:05:08.000 +07:00 - 2001-02-03 04:05:08.000 +07:00 [✓︎]
+    │  describe commit e8849ae12c709f2321908879bc724fdb2ab8a781
+    │  args: jj describe -m 'description 0'
+    ○  8f47435a3990 test-username@host.example.com 2001-02-03 04:05:07.000 +07:00 - 2001-02-03 04:05:07.000 +07:00
+    │  add workspace 'default'
+    ○  000000000000 root()
+    [EOF]
+    ");
+    let output = work_dir.run_jj(["op", "verify"]);
+    insta::assert_snapshot!(output, @r"
+    ------- stderr -------
+    Checked 3 operations: 1 with good signatures, 1 unsigned.
+    [EOF]
+    ");
+    let output = work_dir.run_jj(["op", "verify", "--require-signatures"]);
+    insta::assert_snapshot!(output, @r"
+    Operation 8f47435a3990: not signed
+    [EOF]
+    ------- stderr -------
+    Checked 3 operations: 1 with good signatures, 1 unsigned.
+    Error: Found 1 problem in the operation log
+    [EOF]
+    [exit status: 1]
+    ");
+
+    // Tamper with the signed operation
+    let op_dir = work_dir.root().join(".jj/repo/op_store/operations");
+    for entry in std::fs::read_dir(&op_dir).unwrap() {
+        let path = entry.unwrap().path();
+        let data = std::fs::read(&path).unwrap();
+        let tampered = data.replace(b"describe commit", b"describe COMMIT");
+        if tampered != data {
+            std::fs::write(&path, tampered).unwrap();
+        }
+    }
+    let output = work_dir.run_jj(["op", "log", "--ignore-working-copy", "-n1"]);
+    insta::assert_snapshot!(output, @r"
+    @  04041135258c test-username@host.example.com 2001-02-03 04:05:08.000 +07:00 - 2001-02-03 04:05:08.000 +07:00 [x]
+    │  describe COMMIT e8849ae12c709f2321908879bc724fdb2ab8a781
+    │  args: jj describe -m 'description 0'
+    [EOF]
+    ");
+    let output = work_dir.run_jj(["op", "verify", "--ignore-working-copy"]);
+    insta::assert_snapshot!(output, @r"
+    Operation 04041135258c: bad signature by test-display
+    [EOF]
+    ------- stderr -------
+    Checked 3 operations: 0 with good signatures, 1 unsigned.
+    Error: Found 1 problem in the operation log
+    [EOF]
+    [exit status: 1]
+    ");
+}
+
+#[test]
+fn test_op_sign_without_backend() {
+    let test_env = TestEnvironment::default();
+    test_env.run_jj_in(".", ["git", "init", "repo"]).success();
+    let work_dir = test_env.work_dir("repo");
+    test_env.add_config("signing.sign-operations = true");
+
+    // The operation isn't written unsigned
+    let output = work_dir.run_jj(["describe", "-m", "description 0"]);
+    insta::assert_snapshot!(output, @r"
+    ------- stderr -------
+    Error: Failed to commit new operation
+    Caused by: No signing backend is configured
+    Hint: Configure `signing.backend`, or disable `signing.sign-operations`
+    [EOF]
+    [exit status: 1]
+    ");
+}
+
 fn init_bare_git_repo(git_repo_path: &Path) -> gix::Repository {
     let git_repo = git::init_bare(git_repo_path);
     let commit_result = git::add_commit(
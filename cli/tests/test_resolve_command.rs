@@ -1186,9 +1186,9 @@ fn test_resolve_change_delete_executable() {
 
     // Test the setup
     insta::assert_snapshot!(work_dir.run_jj(["resolve", "--list"]), @r"
-    file1    2-sided conflict including 1 deletion and an executable
-    file2    2-sided conflict including 1 deletion and an executable
-    file3    2-sided conflict including an executable
+    file1    2-sided conflict including 1 deletion, an executable and an executable-bit conflict
+    file2    2-sided conflict including 1 deletion, an executable and an executable-bit conflict
+    file3    2-sided conflict including an executable and an executable-bit conflict
     file4    2-sided conflict including 1 deletion
     file5    2-sided conflict including 1 deletion and an executable
     [EOF]
@@ -1308,7 +1308,7 @@ fn test_resolve_change_delete_executable() {
     Parent commit (@-)      : vruxwmqv 888b6cc3 b | b
     Added 0 files, modified 1 files, removed 0 files
     Warning: There are unresolved conflicts at these paths:
-    file3    2-sided conflict including an executable
+    file3    2-sided conflict including an executable and an executable-bit conflict
     file4    2-sided conflict including 1 deletion
     file5    2-sided conflict including 1 deletion and an executable
     [EOF]
@@ -2091,3 +2091,86 @@ fn test_resolve_with_contents_of_side() {
     [exit status: 2]
     "#);
 }
+
+#[test]
+fn test_resolve_reuse_recorded_resolution() {
+    let test_env = TestEnvironment::default();
+    test_env.run_jj_in(".", ["git", "init", "repo"]).success();
+    test_env.add_config(
+        r#"
+        rerere.enabled = true
+        templates.commit_summary = 'description.first_line()'
+        hints.resolving-conflicts = false
+        "#,
+    );
+    let work_dir = test_env.work_dir("repo");
+
+    create_commit_with_files(&work_dir, "base", &[], &[("file", "1\n2\n3\n")]);
+    create_commit_with_files(&work_dir, "a", &["base"], &[("file", "1\na\n3\n")]);
+    create_commit_with_files(&work_dir, "b", &["base"], &[("file", "1\nb\n3\n")]);
+    create_commit_with_files(&work_dir, "conflict", &["a", "b"], &[]);
+    // Resolve the conflict in the working copy, which records the resolution
+    work_dir.write_file("file", "1\nab\n3\n");
+
+    // The same conflict is resolved automatically
+    let output = work_dir.run_jj(["new", "-m=again", "a", "b"]);
+    insta::assert_snapshot!(output, @r"
+    ------- stderr -------
+    Resolved conflicts in these files using recorded resolutions:
+      file
+    Working copy  (@) now at: again
+    Parent commit (@-)      : a
+    Parent commit (@-)      : b
+    [EOF]
+    ");
+    insta::assert_snapshot!(work_dir.read_file("file"), @r"
+    1
+    ab
+    3
+    ");
+
+    // Also if the sides are swapped
+    let output = work_dir.run_jj(["new", "-m=reversed", "b", "a"]);
+    insta::assert_snapshot!(output, @r"
+    ------- stderr -------
+    Resolved conflicts in these files using recorded resolutions:
+      file
+    Working copy  (@) now at: reversed
+    Parent commit (@-)      : b
+    Parent commit (@-)      : a
+    [EOF]
+    ");
+
+    // Forgetting the resolution restores the conflict
+    let output = work_dir.run_jj(["resolve", "--forget"]);
+    insta::assert_snapshot!(output, @r"
+    ------- stderr -------
+    Forgot 1 recorded conflict resolutions
+    Working copy  (@) now at: reversed
+    Parent commit (@-)      : b
+    Parent commit (@-)      : a
+    Added 0 files, modified 1 files, removed 0 files
+    Warning: There are unresolved conflicts at these paths:
+    file    2-sided conflict
+    [EOF]
+    ");
+    let output = work_dir.run_jj(["resolve", "--forget"]);
+    insta::assert_snapshot!(output, @r"
+    ------- stderr -------
+    No recorded resolutions found
+    [EOF]
+    ");
+
+    // The conflict isn't resolved automatically anymore
+    let output = work_dir.run_jj(["new", "-m=after forget", "a", "b"]);
+    insta::assert_snapshot!(output, @r"
+    ------- stderr -------
+    Working copy  (@) now at: after forget
+    Parent commit (@-)      : a
+    Parent commit (@-)      : b
+    Added 0 files, modified 1 files, removed 0 files
+    Warning: There are unresolved conflicts at these paths:
+    file    2-sided conflict
+    [EOF]
+    ");
+}
@@ -0,0 +1,207 @@
+// Copyright 2025 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::common::CommandOutput;
+use crate::common::TestEnvironment;
+use crate::common::TestWorkDir;
+
+#[test]
+fn test_resolve_divergence_keep() {
+    let test_env = TestEnvironment::default();
+    test_env.run_jj_in(".", ["git", "init", "repo"]).success();
+    test_env.add_config(r#"templates.commit_summary = 'description.first_line()'"#);
+    let work_dir = test_env.work_dir("repo");
+    create_divergent_change(&work_dir);
+    work_dir.run_jj(["new", "-m=child"]).success();
+
+    let output = work_dir.run_jj(["resolve-divergence", "rlvkpnrz", "--keep=subject(2a)"]);
+    insta::assert_snapshot!(output, @r"
+    ------- stderr -------
+    Kept commit 2a
+    Abandoned 1 divergent commits:
+      2b
+    Rebased 1 descendant commits
+    Working copy  (@) now at: child
+    Parent commit (@-)      : 2a
+    Added 0 files, modified 1 files, removed 0 files
+    [EOF]
+    ");
+    insta::assert_snapshot!(get_log_output(&work_dir), @r"
+    @  child
+    ○  2a
+    ○  1
+    ◆
+    [EOF]
+    ");
+
+    let output = work_dir.run_jj(["resolve-divergence", "rlvkpnrz"]);
+    insta::assert_snapshot!(output, @r"
+    ------- stderr -------
+    Error: Change rlvkpnrzqnoo is not divergent
+    [EOF]
+    [exit status: 1]
+    ");
+    let output = work_dir.run_jj(["resolve-divergence", "kkkkkkkk"]);
+    insta::assert_snapshot!(output, @r"
+    ------- stderr -------
+    Error: Change ID `kkkkkkkk` doesn't exist
+    [EOF]
+    [exit status: 1]
+    ");
+    let output = work_dir.run_jj(["resolve-divergence", "abc"]);
+    insta::assert_snapshot!(output, @r"
+    ------- stderr -------
+    Error: Invalid change ID: abc
+    [EOF]
+    [exit status: 1]
+    ");
+}
+
+#[test]
+fn test_resolve_divergence_merge() {
+    let mut test_env = TestEnvironment::default();
+    let edit_script = test_env.set_up_fake_editor();
+    test_env.run_jj_in(".", ["git", "init", "repo"]).success();
+    test_env.add_config(r#"templates.commit_summary = 'description.first_line()'"#);
+    let work_dir = test_env.work_dir("repo");
+    create_divergent_change(&work_dir);
+
+    // The descriptions differ, so the combined description is edited
+    std::fs::write(&edit_script, "write\n2ab").unwrap();
+    let output = work_dir.run_jj(["resolve-divergence", "rlvkpnrz", "--merge"]);
+    insta::assert_snapshot!(output, @r"
+    ------- stderr -------
+    Merged the divergent commits into 2ab
+    Abandoned 1 divergent commits:
+      2a
+    Working copy  (@) now at: 2ab
+    Parent commit (@-)      : 1
+    Added 0 files, modified 1 files, removed 0 files
+    [EOF]
+    ");
+    insta::assert_snapshot!(work_dir.read_file("file"), @r"
+    2a
+    1
+    2b
+    ");
+    insta::assert_snapshot!(get_log_output(&work_dir), @r"
+    @  2ab
+    ○  1
+    ◆
+    [EOF]
+    ");
+
+    // Both divergent commits are predecessors of the merged commit
+    let output = work_dir
+        .run_jj([
+            "evolog",
+            "--no-graph",
+            "-T",
+            r#"if(description, description.first_line(), "(no description set)") ++ "\n""#,
+        ])
+        .normalize_stdout_with(|stdout| {
+            stdout
+                .lines()
+                .filter(|line| !line.starts_with("--"))
+                .map(|line| format!("{line}\n"))
+                .collect()
+        });
+    insta::assert_snapshot!(output, @r"
+    2ab
+    2b
+    (no description set)
+    2a
+    (no description set)
+    (no description set)
+    [EOF]
+    ");
+}
+
+#[test]
+fn test_resolve_divergence_prompt() {
+    let test_env = TestEnvironment::default();
+    test_env.run_jj_in(".", ["git", "init", "repo"]).success();
+    test_env.add_config(r#"templates.commit_summary = 'description.first_line()'"#);
+    let work_dir = test_env.work_dir("repo");
+    create_divergent_change(&work_dir);
+
+    // Cannot prompt in a non-interactive session
+    let output = work_dir.run_jj(["resolve-divergence", "rlvkpnrz"]);
+    insta::assert_snapshot!(output, @r#"
+    ------- stderr -------
+    Change rlvkpnrzqnoo has 2 divergent commits:
+    1: 2b
+       Parent: 1
+    2: 2a
+       Parent: 1
+    Differences between 1 and 2:
+      The descriptions differ
+      The contents differ:
+    M file
+    Error: Cannot prompt for input since the output is not connected to a terminal
+    Hint: Use `--keep` or `--merge` to choose how to resolve the divergence
+    [EOF]
+    [exit status: 1]
+    "#);
+
+    let output = work_dir.run_jj_with(|cmd| {
+        cmd.env("JJ_INTERACTIVE", "1")
+            .args(["resolve-divergence", "rlvkpnrz"])
+            .write_stdin("3\n2\n")
+    });
+    insta::assert_snapshot!(output, @r#"
+    ------- stderr -------
+    Change rlvkpnrzqnoo has 2 divergent commits:
+    1: 2b
+       Parent: 1
+    2: 2a
+       Parent: 1
+    Differences between 1 and 2:
+      The descriptions differ
+      The contents differ:
+    M file
+    Enter the number of the commit to keep, or "m" to merge them: unrecognized response
+    Enter the number of the commit to keep, or "m" to merge them: Kept commit 2a
+    Abandoned 1 divergent commits:
+      2b
+    Working copy  (@) now at: 2a
+    Parent commit (@-)      : 1
+    Added 0 files, modified 1 files, removed 0 files
+    [EOF]
+    "#);
+    insta::assert_snapshot!(get_log_output(&work_dir), @r"
+    @  2a
+    ○  1
+    ◆
+    [EOF]
+    ");
+}
+
+/// Creates change `rlvkpnrz` with divergent commits "2a" and "2b" on top of
+/// commit "1". The working copy is at "2b".
+fn create_divergent_change(work_dir: &TestWorkDir) {
+    work_dir.write_file("file", "1\n");
+    work_dir.run_jj(["commit", "-m=1"]).success();
+    work_dir.write_file("file", "2a\n1\n");
+    work_dir.run_jj(["describe", "-m=2a"]).success();
+    work_dir.run_jj(["edit", "at_operation(@--, @)"]).success();
+    work_dir.write_file("file", "1\n2b\n");
+    work_dir.run_jj(["describe", "-m=2b"]).success();
+}
+
+#[must_use]
+fn get_log_output(work_dir: &TestWorkDir) -> CommandOutput {
+    let template = r#"description.first_line() ++ if(divergent, " !divergence!")"#;
+    work_dir.run_jj(["log", "-T", template])
+}
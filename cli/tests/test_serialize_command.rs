@@ -0,0 +1,205 @@
+// Copyright 2025 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::common::CommandOutput;
+use crate::common::TestEnvironment;
+use crate::common::TestWorkDir;
+
+#[test]
+fn test_serialize_siblings_with_merge() {
+    let test_env = TestEnvironment::default();
+    test_env.run_jj_in(".", ["git", "init", "repo"]).success();
+    test_env.add_config(r#"templates.commit_summary = 'description.first_line()'"#);
+    let work_dir = test_env.work_dir("repo");
+
+    work_dir.run_jj(["describe", "-m=0"]).success();
+    work_dir.run_jj(["new", "-m=1"]).success();
+    work_dir.write_file("file1", "1\n");
+    work_dir.run_jj(["new", "-m=2", "subject(0)"]).success();
+    work_dir.write_file("file2", "2\n");
+    work_dir
+        .run_jj(["new", "-m=3", "subject(1)", "subject(2)"])
+        .success();
+    insta::assert_snapshot!(get_log_output(&work_dir), @r"
+    @    3 parents: 1 2
+    ├─╮
+    │ ○  2 parents: 0
+    ○ │  1 parents: 0
+    ├─╯
+    ○  0 parents:
+    ◆  parents:
+    [EOF]
+    ");
+
+    let output = work_dir.run_jj(["serialize", "-r", "subject(1) | subject(2)"]);
+    insta::assert_snapshot!(output, @r"
+    ------- stderr -------
+    Rebased 2 commits
+    Working copy  (@) now at: 3
+    Parent commit (@-)      : 2
+    [EOF]
+    ");
+    insta::assert_snapshot!(get_log_output(&work_dir), @r"
+    @  3 parents: 2
+    ○  2 parents: 1
+    ○  1 parents: 0
+    ○  0 parents:
+    ◆  parents:
+    [EOF]
+    ");
+
+    // Serializing an already linear chain is a no-op
+    let output = work_dir.run_jj(["serialize", "-r", "subject(1) | subject(2)"]);
+    insta::assert_snapshot!(output, @r"
+    ------- stderr -------
+    Nothing changed.
+    [EOF]
+    ");
+}
+
+#[test]
+fn test_serialize_order() {
+    let test_env = TestEnvironment::default();
+    test_env.run_jj_in(".", ["git", "init", "repo"]).success();
+    test_env.add_config(r#"templates.commit_summary = 'description.first_line()'"#);
+    let work_dir = test_env.work_dir("repo");
+
+    for description in ["c", "a", "b"] {
+        work_dir
+            .run_jj(["new", &format!("-m={description}"), "root()"])
+            .success();
+    }
+    work_dir
+        .run_jj(["bookmark", "create", "-r=subject(a)", "a"])
+        .success();
+    work_dir.run_jj(["new", "-m=wc", "subject(c)"]).success();
+    insta::assert_snapshot!(get_log_output(&work_dir), @r"
+    @  wc parents: c
+    ○  c parents:
+    │ ○  b parents:
+    ├─╯
+    │ ○  a a parents:
+    ├─╯
+    ◆  parents:
+    [EOF]
+    ");
+
+    let output = work_dir.run_jj([
+        "serialize",
+        "-r",
+        "subject(a) | subject(b) | subject(c)",
+        "--order=description",
+    ]);
+    insta::assert_snapshot!(output, @r"
+    ------- stderr -------
+    Rebased 3 commits
+    Working copy  (@) now at: wc
+    Parent commit (@-)      : c
+    [EOF]
+    ");
+    insta::assert_snapshot!(get_log_output(&work_dir), @r"
+    @  wc parents: c
+    ○  c parents: b
+    ○  b parents: a
+    ○  a a parents:
+    ◆  parents:
+    [EOF]
+    ");
+
+    // "c" is the oldest commit, but it is now a descendant of "a"
+    let output = work_dir.run_jj([
+        "serialize",
+        "-r",
+        "subject(a) | subject(b) | subject(c)",
+        "--order=date",
+    ]);
+    insta::assert_snapshot!(output, @r"
+    ------- stderr -------
+    Error: Cannot place change kkmpptxzrspx after its descendant rlvkpnrzqnoo
+    Hint: Use `--order topo` to keep ancestors before their descendants
+    [EOF]
+    [exit status: 1]
+    ");
+}
+
+#[test]
+fn test_serialize_merge() {
+    let test_env = TestEnvironment::default();
+    test_env.run_jj_in(".", ["git", "init", "repo"]).success();
+    test_env.add_config(r#"templates.commit_summary = 'description.first_line()'"#);
+    let work_dir = test_env.work_dir("repo");
+
+    work_dir.run_jj(["describe", "-m=1"]).success();
+    work_dir.run_jj(["new", "-m=2", "root()"]).success();
+    work_dir.run_jj(["new", "-m=z", "root()"]).success();
+    work_dir
+        .run_jj(["new", "-m=merge", "subject(1)", "subject(2)"])
+        .success();
+    insta::assert_snapshot!(get_log_output(&work_dir), @r"
+    @    merge parents: 1 2
+    ├─╮
+    │ ○  2 parents:
+    ○ │  1 parents:
+    ├─╯
+    │ ○  z parents:
+    ├─╯
+    ◆  parents:
+    [EOF]
+    ");
+
+    // The merge would lose its other parent
+    let output = work_dir.run_jj(["serialize", "-r", "subject(z) | subject(merge)"]);
+    insta::assert_snapshot!(output, @r"
+    ------- stderr -------
+    Error: Cannot place merge change mzvwutvlkqwt onto a single parent
+    Hint: Leave the merge out of the revisions, or place it first
+    [EOF]
+    [exit status: 1]
+    ");
+
+    // A merge placed first stays where it is
+    let output = work_dir.run_jj([
+        "serialize",
+        "-r",
+        "subject(merge) | subject(z)",
+        "--order=description",
+    ]);
+    insta::assert_snapshot!(output, @r"
+    ------- stderr -------
+    Rebased 1 commits
+    [EOF]
+    ");
+    insta::assert_snapshot!(get_log_output(&work_dir), @r"
+    ○  z parents: merge
+    @    merge parents: 1 2
+    ├─╮
+    │ ○  2 parents:
+    ○ │  1 parents:
+    ├─╯
+    ◆  parents:
+    [EOF]
+    ");
+}
+
+#[must_use]
+fn get_log_output(work_dir: &TestWorkDir) -> CommandOutput {
+    let template = r#"
+    separate(" ",
+        bookmarks,
+        description.first_line(),
+        "parents:",
+        parents.map(|c|c.description().first_line())
+    )"#;
+    work_dir.run_jj(["log", "-T", template])
+}
@@ -182,6 +182,143 @@ fn test_sparse_manage_patterns() {
     ");
 }
 
+#[test]
+fn test_sparse_cone_mode() {
+    let test_env = TestEnvironment::default();
+    test_env.run_jj_in(".", ["git", "init", "repo"]).success();
+    let work_dir = test_env.work_dir("repo");
+
+    work_dir.write_file("file", "contents");
+    work_dir.create_dir_all("lib/core/sub");
+    work_dir.write_file("lib/file", "contents");
+    work_dir.write_file("lib/core/file", "contents");
+    work_dir.write_file("lib/core/sub/file", "contents");
+    work_dir.create_dir("other");
+    work_dir.write_file("other/file", "contents");
+
+    // Files in the parent directories of the pattern are kept
+    let output = work_dir.run_jj(["sparse", "set", "--cone", "--clear", "--add", "lib/core"]);
+    insta::assert_snapshot!(output, @r"
+    ------- stderr -------
+    Added 0 files, modified 0 files, removed 1 files
+    [EOF]
+    ");
+    assert!(work_dir.root().join("file").exists());
+    assert!(work_dir.root().join("lib/file").exists());
+    assert!(work_dir.root().join("lib/core/sub/file").exists());
+    assert!(!work_dir.root().join("other/file").exists());
+    let output = work_dir.run_jj(["sparse", "list"]);
+    insta::assert_snapshot!(output, @r"
+    lib/core
+    [EOF]
+    ------- stderr -------
+    The patterns are in cone mode.
+    [EOF]
+    ");
+
+    // Turning off cone mode removes them
+    let output = work_dir.run_jj(["sparse", "set", "--no-cone"]);
+    insta::assert_snapshot!(output, @r"
+    ------- stderr -------
+    Added 0 files, modified 0 files, removed 2 files
+    [EOF]
+    ");
+    assert!(!work_dir.root().join("file").exists());
+    assert!(!work_dir.root().join("lib/file").exists());
+    assert!(work_dir.root().join("lib/core/file").exists());
+
+    // Resetting restores all files and turns off cone mode
+    work_dir.run_jj(["sparse", "set", "--cone"]).success();
+    let output = work_dir.run_jj(["sparse", "reset"]);
+    insta::assert_snapshot!(output, @r"
+    ------- stderr -------
+    Added 1 files, modified 0 files, removed 0 files
+    [EOF]
+    ");
+    let output = work_dir.run_jj(["sparse", "list"]);
+    insta::assert_snapshot!(output, @r"
+    .
+    [EOF]
+    ");
+}
+
+#[test]
+fn test_sparse_profile() {
+    let test_env = TestEnvironment::default();
+    test_env.run_jj_in(".", ["git", "init", "repo"]).success();
+    let work_dir = test_env.work_dir("repo");
+
+    work_dir.write_file("backend.sparse", "# Backend team\nbackend.sparse\nlib/\n");
+    work_dir.create_dir("lib");
+    work_dir.write_file("lib/file", "contents");
+    work_dir.create_dir("docs");
+    work_dir.write_file("docs/file", "contents");
+    work_dir.run_jj(["commit", "-m", "first"]).success();
+
+    // The profile must exist in the working-copy commit
+    let output = work_dir.run_jj(["sparse", "set", "--profile", "missing.sparse"]);
+    insta::assert_snapshot!(output, @r"
+    ------- stderr -------
+    Error: Failed to read sparse profile missing.sparse
+    Caused by: No such file
+    [EOF]
+    [exit status: 1]
+    ");
+
+    let output = work_dir.run_jj(["sparse", "set", "--profile", "backend.sparse"]);
+    insta::assert_snapshot!(output, @r"
+    ------- stderr -------
+    Added 0 files, modified 0 files, removed 1 files
+    [EOF]
+    ");
+    assert!(work_dir.root().join("lib/file").exists());
+    assert!(!work_dir.root().join("docs/file").exists());
+    let output = work_dir.run_jj(["sparse", "list"]);
+    insta::assert_snapshot!(output, @r"
+    backend.sparse
+    lib
+    [EOF]
+    ------- stderr -------
+    The patterns are read from the sparse profile backend.sparse
+    [EOF]
+    ");
+
+    // The patterns follow the profile in the checked-out commit
+    work_dir.write_file("backend.sparse", "backend.sparse\ndocs\n");
+    work_dir.run_jj(["commit", "-m", "second"]).success();
+    assert!(work_dir.root().join("lib/file").exists());
+    work_dir.run_jj(["new", "description(first)"]).success();
+    assert!(work_dir.root().join("lib/file").exists());
+    assert!(!work_dir.root().join("docs/file").exists());
+    work_dir.run_jj(["new", "description(second)"]).success();
+    assert!(!work_dir.root().join("lib/file").exists());
+    assert!(work_dir.root().join("docs/file").exists());
+    let output = work_dir.run_jj(["sparse", "list"]);
+    insta::assert_snapshot!(output, @r"
+    backend.sparse
+    docs
+    [EOF]
+    ------- stderr -------
+    The patterns are read from the sparse profile backend.sparse
+    [EOF]
+    ");
+
+    // Editing the patterns stops following the profile
+    let output = work_dir.run_jj(["sparse", "set", "--add", "lib"]);
+    insta::assert_snapshot!(output, @r"
+    ------- stderr -------
+    Added 1 files, modified 0 files, removed 0 files
+    [EOF]
+    ");
+    let output = work_dir.run_jj(["sparse", "list"]);
+    insta::assert_snapshot!(output, @r"
+    backend.sparse
+    docs
+    lib
+    [EOF]
+    ");
+}
+
 #[test]
 fn test_sparse_editor_avoids_unc() {
     use std::path::PathBuf;
@@ -317,7 +317,7 @@ fn test_squash_partial() {
     ");
 
     // We get a warning if we pass a positional argument that looks like a revset
-    work_dir.run_jj(["undo"]).success();
+    work_dir.run_jj(["op", "undo"]).success();
     let output = work_dir.run_jj(["squash", "b"]);
     insta::assert_snapshot!(output, @r#"
     ------- stderr -------
@@ -327,7 +327,7 @@ fn test_squash_partial() {
     "#);
 
     // we can use --interactive and fileset together
-    work_dir.run_jj(["undo"]).success();
+    work_dir.run_jj(["op", "undo"]).success();
     work_dir.write_file("file3", "foo\n");
     std::fs::write(&edit_script, "reset file1").unwrap();
     let output = work_dir.run_jj(["squash", "-i", "file1", "file3"]);
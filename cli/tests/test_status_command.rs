@@ -390,9 +390,9 @@ fn test_status_untracked_files() {
     let output = work_dir.run_jj(["status"]);
     insta::assert_snapshot!(output.normalize_backslash(), @r"
     Untracked paths:
-    ? always-untracked-file
-    ? initially-untracked-file
-    ? sub/
+    ? always-untracked-file (not auto-tracked)
+    ? initially-untracked-file (not auto-tracked)
+    ? sub/ (not auto-tracked)
     Working copy  (@) : qpvuntsm e8849ae1 (empty) (no description set)
     Parent commit (@-): zzzzzzzz 00000000 (empty) (no description set)
     [EOF]
@@ -413,8 +413,8 @@ fn test_status_untracked_files() {
     A initially-untracked-file
     A sub/initially-untracked
     Untracked paths:
-    ? always-untracked-file
-    ? sub/always-untracked
+    ? always-untracked-file (not auto-tracked)
+    ? sub/always-untracked (not auto-tracked)
     Working copy  (@) : qpvuntsm b8c1286d (no description set)
     Parent commit (@-): zzzzzzzz 00000000 (empty) (no description set)
     [EOF]
@@ -425,8 +425,8 @@ fn test_status_untracked_files() {
     let output = work_dir.run_jj(["status"]);
     insta::assert_snapshot!(output.normalize_backslash(), @r"
     Untracked paths:
-    ? always-untracked-file
-    ? sub/always-untracked
+    ? always-untracked-file (not auto-tracked)
+    ? sub/always-untracked (not auto-tracked)
     Working copy  (@) : mzvwutvl daa133b8 (empty) (no description set)
     Parent commit (@-): qpvuntsm b8c1286d (no description set)
     [EOF]
@@ -446,9 +446,9 @@ fn test_status_untracked_files() {
     D initially-untracked-file
     D sub/initially-untracked
     Untracked paths:
-    ? always-untracked-file
-    ? initially-untracked-file
-    ? sub/
+    ? always-untracked-file (not auto-tracked)
+    ? initially-untracked-file (not auto-tracked)
+    ? sub/ (not auto-tracked)
     Working copy  (@) : mzvwutvl 240f261a (no description set)
     Parent commit (@-): qpvuntsm b8c1286d (no description set)
     [EOF]
@@ -459,9 +459,9 @@ fn test_status_untracked_files() {
     let output = work_dir.run_jj(["status"]);
     insta::assert_snapshot!(output.normalize_backslash(), @r"
     Untracked paths:
-    ? always-untracked-file
-    ? initially-untracked-file
-    ? sub/
+    ? always-untracked-file (not auto-tracked)
+    ? initially-untracked-file (not auto-tracked)
+    ? sub/ (not auto-tracked)
     Working copy  (@) : yostqsxw 50beac0d (empty) (no description set)
     Parent commit (@-): mzvwutvl 240f261a (no description set)
     [EOF]
@@ -470,9 +470,9 @@ fn test_status_untracked_files() {
     let output = work_dir.dir("sub").run_jj(["status"]);
     insta::assert_snapshot!(output.normalize_backslash(), @r"
     Untracked paths:
-    ? ../always-untracked-file
-    ? ../initially-untracked-file
-    ? ./
+    ? ../always-untracked-file (not auto-tracked)
+    ? ../initially-untracked-file (not auto-tracked)
+    ? ./ (not auto-tracked)
     Working copy  (@) : yostqsxw 50beac0d (empty) (no description set)
     Parent commit (@-): mzvwutvl 240f261a (no description set)
     [EOF]
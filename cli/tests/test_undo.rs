@@ -440,7 +440,7 @@ fn test_bookmark_track_untrack_undo() {
     [EOF]
     ");
 
-    work_dir.run_jj(["undo"]).success();
+    work_dir.run_jj(["redo"]).success();
     insta::assert_snapshot!(get_bookmark_output(&work_dir), @r"
     feature1: qpvuntsm bab5b5ef (empty) commit
     feature1@origin: qpvuntsm bab5b5ef (empty) commit
@@ -468,36 +468,192 @@ fn test_bookmark_track_untrack_undo() {
 }
 
 #[test]
-fn test_undo_latest_undo_implicitly() {
+fn test_undo_consecutive_undos() {
     let test_env = TestEnvironment::default();
     test_env.run_jj_in(".", ["git", "init", "repo"]).success();
     let work_dir = test_env.work_dir("repo");
+    work_dir.run_jj(["describe", "-m=A"]).success();
+    work_dir.run_jj(["new", "-m=B"]).success();
+    work_dir.run_jj(["new", "-m=C"]).success();
 
-    // Double-undo creation of child
-    work_dir.run_jj(["new"]).success();
-    work_dir.run_jj(["undo"]).success();
+    // The second `jj undo` undoes the operation before the undone one
     let output = work_dir.run_jj(["undo"]);
     insta::assert_snapshot!(output, @r"
     ------- stderr -------
-    Undid operation: 6de77c1b46a3 (2001-02-03 08:05:09) undo operation dbcb2561b6fee72ea6de79511b6b62f1fff2424f79d16dd30339f94621100f77c86ca7450f7b1ec1bd95d4d56b7a54fe3f3e612353e62cedc682366211b4144e
-    Working copy  (@) now at: rlvkpnrz 43444d88 (empty) (no description set)
-    Parent commit (@-)      : qpvuntsm e8849ae1 (empty) (no description set)
-    Warning: The second-last `jj undo` was reverted by the latest `jj undo`. The repo is now in the same state as it was before the second-last `jj undo`.
-    Hint: To undo multiple operations, use `jj op log` to see past states and `jj op restore` to restore one of these states.
+    Undid operation: a886ba2caae6 (2001-02-03 08:05:10) new empty commit
+    Working copy  (@) now at: kkmpptxz 807cecf2 (empty) B
+    Parent commit (@-)      : qpvuntsm 8777db25 (empty) A
+    [EOF]
+    ");
+    let output = work_dir.run_jj(["undo"]);
+    insta::assert_snapshot!(output, @r"
+    ------- stderr -------
+    Undid operation: 48631817a82e (2001-02-03 08:05:09) new empty commit
+    Working copy  (@) now at: qpvuntsm 8777db25 (empty) A
+    Parent commit (@-)      : zzzzzzzz 00000000 (empty) (no description set)
+    [EOF]
+    ");
+    insta::assert_snapshot!(work_dir.run_jj(["log", "-T=description"]), @r"
+    @  A
+    ◆
+    [EOF]
+    ");
+}
+
+#[test]
+fn test_redo() {
+    let test_env = TestEnvironment::default();
+    test_env.run_jj_in(".", ["git", "init", "repo"]).success();
+    let work_dir = test_env.work_dir("repo");
+    work_dir.run_jj(["describe", "-m=A"]).success();
+    work_dir.run_jj(["new", "-m=B"]).success();
+    work_dir.run_jj(["new", "-m=C"]).success();
+    work_dir.run_jj(["undo"]).success();
+    work_dir.run_jj(["undo"]).success();
+
+    // Redo reapplies the undone operations in order
+    let output = work_dir.run_jj(["redo"]);
+    insta::assert_snapshot!(output, @r"
+    ------- stderr -------
+    Redid operation: 48631817a82e (2001-02-03 08:05:09) new empty commit
+    Working copy  (@) now at: kkmpptxz 807cecf2 (empty) B
+    Parent commit (@-)      : qpvuntsm 8777db25 (empty) A
+    [EOF]
+    ");
+    insta::assert_snapshot!(work_dir.run_jj(["log", "-T=description"]), @r"
+    @  B
+    ○  A
+    ◆
+    [EOF]
+    ");
+    let output = work_dir.run_jj(["redo"]);
+    insta::assert_snapshot!(output, @r"
+    ------- stderr -------
+    Redid operation: a886ba2caae6 (2001-02-03 08:05:10) new empty commit
+    Working copy  (@) now at: zsuskuln 70967132 (empty) C
+    Parent commit (@-)      : kkmpptxz 807cecf2 (empty) B
+    [EOF]
+    ");
+    insta::assert_snapshot!(work_dir.run_jj(["log", "-T=description"]), @r"
+    @  C
+    ○  B
+    ○  A
+    ◆
     [EOF]
     ");
 
-    // Double-undo creation of sibling
-    work_dir.run_jj(["new", "@-"]).success();
+    let output = work_dir.run_jj(["redo"]);
+    insta::assert_snapshot!(output, @r"
+    ------- stderr -------
+    Error: Nothing to redo
+    [EOF]
+    [exit status: 1]
+    ");
+}
+
+#[test]
+fn test_undo_after_redo() {
+    let test_env = TestEnvironment::default();
+    test_env.run_jj_in(".", ["git", "init", "repo"]).success();
+    let work_dir = test_env.work_dir("repo");
+    work_dir.run_jj(["describe", "-m=A"]).success();
+    work_dir.run_jj(["new", "-m=B"]).success();
+    work_dir.run_jj(["new", "-m=C"]).success();
+    work_dir.run_jj(["undo"]).success();
     work_dir.run_jj(["undo"]).success();
+    work_dir.run_jj(["redo"]).success();
+
+    // Undoing a redo reverts it, and further undos continue walking back
     let output = work_dir.run_jj(["undo"]);
     insta::assert_snapshot!(output, @r"
     ------- stderr -------
-    Undid operation: b77c991c5a2f (2001-02-03 08:05:12) undo operation 58f57841c00da755413d291ed9e1a1d9a58dd4311b5000a8703f1bf93339dd12cdbc2c6e1c8cd5f43cb584cabddcf8366153a007c244d89ee80a4e42e513058d
-    Working copy  (@) now at: mzvwutvl 8afc18ff (empty) (no description set)
-    Parent commit (@-)      : qpvuntsm e8849ae1 (empty) (no description set)
-    Warning: The second-last `jj undo` was reverted by the latest `jj undo`. The repo is now in the same state as it was before the second-last `jj undo`.
-    Hint: To undo multiple operations, use `jj op log` to see past states and `jj op restore` to restore one of these states.
+    Undid operation: fa93f3faf4ae (2001-02-03 08:05:13) redo operation 48631817a82e41941565339f3705487add99a7bb288ce0f24e8d4fffc15c4a155d494548084550b288a80764edbf766306553c5906362849d221a4b47785702f
+    Working copy  (@) now at: qpvuntsm 8777db25 (empty) A
+    Parent commit (@-)      : zzzzzzzz 00000000 (empty) (no description set)
+    [EOF]
+    ");
+    let output = work_dir.run_jj(["undo"]);
+    insta::assert_snapshot!(output, @r"
+    ------- stderr -------
+    Undid operation: b5b01256379e (2001-02-03 08:05:08) describe commit e8849ae12c709f2321908879bc724fdb2ab8a781
+    Working copy  (@) now at: qpvuntsm e8849ae1 (empty) (no description set)
+    Parent commit (@-)      : zzzzzzzz 00000000 (empty) (no description set)
+    [EOF]
+    ");
+
+    let output = work_dir.run_jj(["redo"]);
+    insta::assert_snapshot!(output, @r"
+    ------- stderr -------
+    Redid operation: b5b01256379e (2001-02-03 08:05:08) describe commit e8849ae12c709f2321908879bc724fdb2ab8a781
+    Working copy  (@) now at: qpvuntsm 8777db25 (empty) A
+    Parent commit (@-)      : zzzzzzzz 00000000 (empty) (no description set)
+    [EOF]
+    ");
+}
+
+#[test]
+fn test_redo_after_new_operation() {
+    let test_env = TestEnvironment::default();
+    test_env.run_jj_in(".", ["git", "init", "repo"]).success();
+    let work_dir = test_env.work_dir("repo");
+    work_dir.run_jj(["describe", "-m=A"]).success();
+    work_dir.run_jj(["new", "-m=B"]).success();
+    work_dir.run_jj(["new", "-m=C"]).success();
+    work_dir.run_jj(["undo"]).success();
+    work_dir.run_jj(["new", "-m=D"]).success();
+
+    let output = work_dir.run_jj(["redo"]);
+    insta::assert_snapshot!(output, @r"
+    ------- stderr -------
+    Error: Nothing to redo
+    [EOF]
+    [exit status: 1]
+    ");
+}
+
+#[test]
+fn test_redo_after_op_undo() {
+    let test_env = TestEnvironment::default();
+    test_env.run_jj_in(".", ["git", "init", "repo"]).success();
+    let work_dir = test_env.work_dir("repo");
+    work_dir.run_jj(["describe", "-m=A"]).success();
+    work_dir.run_jj(["new", "-m=B"]).success();
+    work_dir.run_jj(["op", "undo", "@-"]).success();
+
+    // An explicit undo of an older operation isn't reverted by redo
+    let output = work_dir.run_jj(["redo"]);
+    insta::assert_snapshot!(output, @r"
+    ------- stderr -------
+    Error: Nothing to redo
+    [EOF]
+    [exit status: 1]
+    ");
+}
+
+#[test]
+fn test_undo_redo_op_log() {
+    let test_env = TestEnvironment::default();
+    test_env.run_jj_in(".", ["git", "init", "repo"]).success();
+    let work_dir = test_env.work_dir("repo");
+    work_dir.run_jj(["describe", "-m=A"]).success();
+    work_dir.run_jj(["new", "-m=B"]).success();
+    work_dir.run_jj(["new", "-m=C"]).success();
+    work_dir.run_jj(["undo"]).success();
+    work_dir.run_jj(["redo"]).success();
+
+    let output = work_dir.run_jj(["op", "log", "-n3"]);
+    insta::assert_snapshot!(output, @r"
+    @  d220f4ea3872 test-username@host.example.com 2001-02-03 04:05:12.000 +07:00 - 2001-02-03 04:05:12.000 +07:00
+    │  redo operation a886ba2caae6f1c6f364f68a389ff81b463c9d42ce32337a8d13c7ee98ea190ba95710e9438ea6aca907f9b3567d8dfc14e8e16f851038cb797ab20968b71cfe
+    │  args: jj redo
+    │  redo-of: d4672a1e1d1f27d399fce773e2865cb99b8f52afc1ca1bddb162fab217e85928d9af29e8a38b5017d8eb90452ce56bf17360ddcfcf9f34c06a677d34abc1c373
+    ○  d4672a1e1d1f test-username@host.example.com 2001-02-03 04:05:11.000 +07:00 - 2001-02-03 04:05:11.000 +07:00
+    │  undo operation a886ba2caae6f1c6f364f68a389ff81b463c9d42ce32337a8d13c7ee98ea190ba95710e9438ea6aca907f9b3567d8dfc14e8e16f851038cb797ab20968b71cfe
+    │  args: jj undo
+    │  undo-of: a886ba2caae6f1c6f364f68a389ff81b463c9d42ce32337a8d13c7ee98ea190ba95710e9438ea6aca907f9b3567d8dfc14e8e16f851038cb797ab20968b71cfe
+    ○  a886ba2caae6 test-username@host.example.com 2001-02-03 04:05:10.000 +07:00 - 2001-02-03 04:05:10.000 +07:00
+    │  new empty commit
+    │  args: jj new '-m=C'
     [EOF]
     ");
 }
@@ -517,7 +673,7 @@ fn test_undo_latest_undo_explicitly() {
     let output = work_dir.run_jj(["undo", op_id_hex]);
     insta::assert_snapshot!(output, @r"
     ------- stderr -------
-    Undid operation: 6de77c1b46a3 (2001-02-03 08:05:09) undo operation dbcb2561b6fee72ea6de79511b6b62f1fff2424f79d16dd30339f94621100f77c86ca7450f7b1ec1bd95d4d56b7a54fe3f3e612353e62cedc682366211b4144e
+    Undid operation: 4d95c1805f1d (2001-02-03 08:05:09) undo operation dbcb2561b6fee72ea6de79511b6b62f1fff2424f79d16dd30339f94621100f77c86ca7450f7b1ec1bd95d4d56b7a54fe3f3e612353e62cedc682366211b4144e
     Working copy  (@) now at: rlvkpnrz 43444d88 (empty) (no description set)
     Parent commit (@-)      : qpvuntsm e8849ae1 (empty) (no description set)
     [EOF]
@@ -528,11 +684,11 @@ fn test_undo_latest_undo_explicitly() {
     let output = work_dir.run_jj(["undo", "@"]);
     insta::assert_snapshot!(output, @r"
     ------- stderr -------
-    Undid operation: c99ea8473832 (2001-02-03 08:05:13) undo operation 09cbd5c994ee9e437950a49fa4400d3c6e9d5c1e44ea55b38806a9692317716647578328c53b887a758a40270ad8ab6d5b67c1675d088281879cc7c74a2da6cc
+    Undid operation: 4ac0af8ef30b (2001-02-03 08:05:13) undo operation ed4696eebb4466a7e468968f540f9485b0e21a7c8618839c99aeb56aa10d74d9cf71ad9e2bcbf5d50e75b135ad00b404db548240e1db26785a5a6f2ee3d7cea8
     Working copy  (@) now at: royxmykx ba0e5dca (empty) (no description set)
     Parent commit (@-)      : rlvkpnrz 43444d88 (empty) (no description set)
     Warning: The second-last `jj undo` was reverted by the latest `jj undo`. The repo is now in the same state as it was before the second-last `jj undo`.
-    Hint: To undo multiple operations, use `jj op log` to see past states and `jj op restore` to restore one of these states.
+    Hint: To undo multiple operations, run `jj undo` without arguments repeatedly, or use `jj op restore` to restore one of the past states shown by `jj op log`.
     [EOF]
     ");
 }
@@ -554,7 +710,7 @@ fn test_undo_an_older_undo() {
     let output = work_dir.run_jj(["undo", op_id_hex]);
     insta::assert_snapshot!(output, @r"
     ------- stderr -------
-    Undid operation: 6de77c1b46a3 (2001-02-03 08:05:09) undo operation dbcb2561b6fee72ea6de79511b6b62f1fff2424f79d16dd30339f94621100f77c86ca7450f7b1ec1bd95d4d56b7a54fe3f3e612353e62cedc682366211b4144e
+    Undid operation: 4d95c1805f1d (2001-02-03 08:05:09) undo operation dbcb2561b6fee72ea6de79511b6b62f1fff2424f79d16dd30339f94621100f77c86ca7450f7b1ec1bd95d4d56b7a54fe3f3e612353e62cedc682366211b4144e
     [EOF]
     ");
 
@@ -565,7 +721,7 @@ fn test_undo_an_older_undo() {
     let output = work_dir.run_jj(["undo", "@-"]);
     insta::assert_snapshot!(output, @r"
     ------- stderr -------
-    Undid operation: ffea82d2bf89 (2001-02-03 08:05:14) undo operation 2b3480d4ece49d59f5da51e1284b742773e6b858935f122bebab625430137f9aae1aaebf7a8c081e0715e9cd24deee9d0d039743fbb1dd61804c633c9c7f17a2
+    Undid operation: 21a52f6c85a7 (2001-02-03 08:05:14) undo operation 3ba68f4a5aa3ee2f191417034fe68e33cb16db0b79d1a511fb47917c7c828a358e2df492f1a7d31c9e0bfe86f291c6e5e4afe26a0ca909a28551714192497002
     [EOF]
     ");
 }
@@ -577,48 +733,48 @@ fn test_undo_an_undo_multiple_times() {
     let work_dir = test_env.work_dir("repo");
 
     work_dir.run_jj(["new"]).success();
-    work_dir.run_jj(["undo"]).success();
-    let output = work_dir.run_jj(["undo"]);
+    work_dir.run_jj(["op", "undo"]).success();
+    let output = work_dir.run_jj(["op", "undo"]);
     insta::assert_snapshot!(output, @r"
     ------- stderr -------
-    Undid operation: 6de77c1b46a3 (2001-02-03 08:05:09) undo operation dbcb2561b6fee72ea6de79511b6b62f1fff2424f79d16dd30339f94621100f77c86ca7450f7b1ec1bd95d4d56b7a54fe3f3e612353e62cedc682366211b4144e
+    Undid operation: 7dca7d0c2e2f (2001-02-03 08:05:09) undo operation dbcb2561b6fee72ea6de79511b6b62f1fff2424f79d16dd30339f94621100f77c86ca7450f7b1ec1bd95d4d56b7a54fe3f3e612353e62cedc682366211b4144e
     Working copy  (@) now at: rlvkpnrz 43444d88 (empty) (no description set)
     Parent commit (@-)      : qpvuntsm e8849ae1 (empty) (no description set)
     Warning: The second-last `jj undo` was reverted by the latest `jj undo`. The repo is now in the same state as it was before the second-last `jj undo`.
-    Hint: To undo multiple operations, use `jj op log` to see past states and `jj op restore` to restore one of these states.
+    Hint: To undo multiple operations, run `jj undo` without arguments repeatedly, or use `jj op restore` to restore one of the past states shown by `jj op log`.
     [EOF]
     ");
-    let output = work_dir.run_jj(["undo"]);
+    let output = work_dir.run_jj(["op", "undo"]);
     insta::assert_snapshot!(output, @r"
     ------- stderr -------
-    Undid operation: f17b43a603fd (2001-02-03 08:05:10) undo operation 6de77c1b46a39115938845d2fffb5780a49a4991d1f8f99b60509705faa38d496b06cdbb427a497ff22d1f2b81613ac80a8dd6b97ca2fa35d7cc66d2a33059e4
+    Undid operation: 7bd8973324ef (2001-02-03 08:05:10) undo operation 7dca7d0c2e2f4f42c91c31480316a73e32c78e968401e131caac7615457cdba9d5ae2646ec9d7bd60da260f27102fcc577680973d3d235cdd1a523b375df124f
     Working copy  (@) now at: qpvuntsm e8849ae1 (empty) (no description set)
     Parent commit (@-)      : zzzzzzzz 00000000 (empty) (no description set)
     Warning: The second-last `jj undo` was reverted by the latest `jj undo`. The repo is now in the same state as it was before the second-last `jj undo`.
-    Hint: To undo multiple operations, use `jj op log` to see past states and `jj op restore` to restore one of these states.
+    Hint: To undo multiple operations, run `jj undo` without arguments repeatedly, or use `jj op restore` to restore one of the past states shown by `jj op log`.
     [EOF]
     ");
 
     work_dir.run_jj(["new"]).success();
-    work_dir.run_jj(["undo"]).success();
-    let output = work_dir.run_jj(["undo"]);
+    work_dir.run_jj(["op", "undo"]).success();
+    let output = work_dir.run_jj(["op", "undo"]);
     insta::assert_snapshot!(output, @r"
     ------- stderr -------
-    Undid operation: 1eee1feca2d3 (2001-02-03 08:05:13) undo operation 18ee4719e7c7a2da3fbeec66678dfb03c4f3deebbe887dbc46270102c86894615a07a223713981d12b3ddc757e5104c7e9f7b391202747cd1868e0d2ac01f35d
+    Undid operation: f10f894dd8e9 (2001-02-03 08:05:13) undo operation 36e6eef9968a7e7e9ed923fe7b3e92b0372371bba3973926dd71e1798ca0e70631b3adb1200fcca1d13edc2c625788bb7eb3f1be8eb10d0c5f14c3996dc88b68
     Working copy  (@) now at: royxmykx e7d0d5fd (empty) (no description set)
     Parent commit (@-)      : qpvuntsm e8849ae1 (empty) (no description set)
     Warning: The second-last `jj undo` was reverted by the latest `jj undo`. The repo is now in the same state as it was before the second-last `jj undo`.
-    Hint: To undo multiple operations, use `jj op log` to see past states and `jj op restore` to restore one of these states.
+    Hint: To undo multiple operations, run `jj undo` without arguments repeatedly, or use `jj op restore` to restore one of the past states shown by `jj op log`.
     [EOF]
     ");
     let output = work_dir.run_jj(["undo", "@"]);
     insta::assert_snapshot!(output, @r"
     ------- stderr -------
-    Undid operation: 079cb7121929 (2001-02-03 08:05:14) undo operation 1eee1feca2d334e1c7c956048eec1e54a35d58b39aaec88c8a48ce7ce216b30e287f49c0305c51ad86292c6a2e11dd98ed97d8ef0a497e7114cf2aea99d4e71d
+    Undid operation: 214b1bf5fd19 (2001-02-03 08:05:14) undo operation f10f894dd8e9510052312496db1b43a5080cd0d3fd76eb229aeff00ad25ada2a5ddf9d683f07d15e887449e1b294a5945c2f3b15eed768e0804e4784a40f6dde
     Working copy  (@) now at: qpvuntsm e8849ae1 (empty) (no description set)
     Parent commit (@-)      : zzzzzzzz 00000000 (empty) (no description set)
     Warning: The second-last `jj undo` was reverted by the latest `jj undo`. The repo is now in the same state as it was before the second-last `jj undo`.
-    Hint: To undo multiple operations, use `jj op log` to see past states and `jj op restore` to restore one of these states.
+    Hint: To undo multiple operations, run `jj undo` without arguments repeatedly, or use `jj op restore` to restore one of the past states shown by `jj op log`.
     [EOF]
     ");
 }
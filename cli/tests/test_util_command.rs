@@ -100,6 +100,94 @@ fn test_gc_operation_log() {
     "#);
 }
 
+#[test]
+fn test_gc_operation_retention() {
+    let test_env = TestEnvironment::default();
+    test_env.run_jj_in(".", ["git", "init", "repo"]).success();
+    let work_dir = test_env.work_dir("repo");
+    test_env.add_config(
+        r#"
+        [gc.operations]
+        squash-snapshots = true
+        keep-commands = ["describe"]
+        "#,
+    );
+    let op_log = || {
+        let template = r#"id.short() ++ " " ++ description.first_line() ++ "\n""#;
+        work_dir.run_jj(["op", "log", "-T", template])
+    };
+
+    for content in ["a", "b", "c"] {
+        work_dir.write_file("file", content);
+        work_dir.run_jj(["debug", "snapshot"]).success();
+    }
+    // Global flags before the subcommand don't affect the matching
+    work_dir
+        .run_jj(["--no-pager", "describe", "-m=first"])
+        .success();
+    for content in ["d", "e"] {
+        work_dir.write_file("file", content);
+        work_dir.run_jj(["debug", "snapshot"]).success();
+    }
+    work_dir.run_jj(["new"]).success();
+    insta::assert_snapshot!(op_log(), @r"
+    @  d9e845f07294 new empty commit
+    ○  011651982598 snapshot working copy
+    ○  1336e23ad250 snapshot working copy
+    ○  7532e508cceb describe commit 53b5f4a188e5d5c432a34acedcd5a95df6a070f7
+    ○  957bc6796e01 snapshot working copy
+    ○  f30e698011b5 snapshot working copy
+    ○  ccce446f2a03 snapshot working copy
+    ○  8f47435a3990 add workspace 'default'
+    ○  000000000000
+    [EOF]
+    ");
+
+    // Runs of snapshots are squashed into the latest one
+    let output = work_dir.run_jj(["util", "gc"]);
+    insta::assert_snapshot!(output, @r"
+    ------- stderr -------
+    Abandoned 3 operations and reparented 4 descendant operations.
+    [EOF]
+    ");
+    insta::assert_snapshot!(op_log(), @r"
+    @  9f97167343da new empty commit
+    ○  a4e30ff5cfe5 snapshot working copy
+    ○  26e9ec2dc661 describe commit 53b5f4a188e5d5c432a34acedcd5a95df6a070f7
+    ○  ac81b781630d snapshot working copy
+    ○  8f47435a3990 add workspace 'default'
+    ○  000000000000
+    [EOF]
+    ");
+
+    // Old operations are abandoned unless created by the kept commands
+    test_env.add_config("gc.operations.keep-last = 2");
+    let output = work_dir.run_jj(["util", "gc", "--expire=now"]);
+    insta::assert_snapshot!(output, @r"
+    ------- stderr -------
+    Abandoned 2 operations and reparented 3 descendant operations.
+    [EOF]
+    ");
+    insta::assert_snapshot!(op_log(), @r"
+    @  1fa5d6acd01f new empty commit
+    ○  e82cf22a905d snapshot working copy
+    ○  c2ce9d5fd21c describe commit 53b5f4a188e5d5c432a34acedcd5a95df6a070f7
+    ○  000000000000
+    [EOF]
+    ");
+    let output = work_dir.run_jj(["status"]);
+    insta::assert_snapshot!(output, @r"
+    The working copy has no changes.
+    Working copy  (@) : vruxwmqv d499ff0e (empty) (no description set)
+    Parent commit (@-): qpvuntsm 6c03bed4 first
+    [EOF]
+    ");
+
+    // Nothing changes if the policy is already satisfied
+    let output = work_dir.run_jj(["util", "gc"]);
+    insta::assert_snapshot!(output, @"");
+}
+
 #[test]
 fn test_shell_completions() {
     #[track_caller]
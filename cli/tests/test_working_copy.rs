@@ -162,6 +162,88 @@ fn test_snapshot_large_file_restore() {
     ");
 }
 
+#[test]
+fn test_snapshot_rules() {
+    let test_env = TestEnvironment::default();
+    test_env.run_jj_in(".", ["git", "init", "repo"]).success();
+    let work_dir = test_env.work_dir("repo");
+    test_env.add_config(indoc! {r#"
+        templates.commit_summary = 'change_id.shortest(8)'
+        snapshot.max-new-file-size = 10
+
+        [snapshot.rules.logs]
+        patterns = ["glob:'**/*.log'"]
+        action = "ignore"
+
+        [snapshot.rules.assets]
+        patterns = ["assets"]
+        max-new-file-size = 100
+        action = "warn"
+
+        [snapshot.rules.secrets]
+        patterns = ["glob:'secrets/**'"]
+        action = "refuse"
+
+        [snapshot.rules.other-workspace]
+        patterns = ["all()"]
+        action = "refuse"
+        workspaces = ["other"]
+    "#});
+
+    work_dir.write_file("build.log", "log");
+    work_dir.write_file("large", "a lot of text");
+    work_dir.write_file("assets/small", "less than 100 bytes");
+    work_dir.write_file("assets/large", "x".repeat(200));
+    work_dir.write_file("secrets/key", "key");
+    let output = work_dir.run_jj(["status"]);
+    insta::assert_snapshot!(output, @r#"
+    Working copy changes:
+    A assets/large
+    A assets/small
+    Untracked paths:
+    ? build.log (ignored by snapshot rule `logs`)
+    ? large (too large)
+    ? secrets/ (refused by snapshot rule `secrets`)
+    Working copy  (@) : qpvuntsm
+    Parent commit (@-): zzzzzzzz
+    [EOF]
+    ------- stderr -------
+    Warning: Refused to snapshot some files:
+      large: 13.0B (13 bytes); the maximum size allowed is 10.0B (10 bytes)
+      secrets/key: 3.0B (3 bytes); matches snapshot rule `secrets`
+    Hint: Files matching a rule in `snapshot.rules` with `action = "refuse"` are never snapshotted. Add them to `.gitignore` to silence this warning.
+    Warning: Snapshotted some files matching snapshot rules:
+      assets/large: 200.0B (200 bytes); matches snapshot rule `assets`
+    Hint: This is to prevent large files from being added by accident. You can fix this by:
+      - Adding the file to `.gitignore`
+      - Run `jj config set --repo snapshot.max-new-file-size 13`
+        This will increase the maximum file size allowed for new files, in this repository only.
+      - Run `jj --config snapshot.max-new-file-size=13 st`
+        This will increase the maximum file size allowed for new files, for this command only.
+    [EOF]
+    "#);
+
+    // The warning about tracked files is only printed once
+    let output = work_dir.run_jj(["file", "list"]);
+    insta::assert_snapshot!(output, @r#"
+    assets/large
+    assets/small
+    [EOF]
+    ------- stderr -------
+    Warning: Refused to snapshot some files:
+      large: 13.0B (13 bytes); the maximum size allowed is 10.0B (10 bytes)
+      secrets/key: 3.0B (3 bytes); matches snapshot rule `secrets`
+    Hint: Files matching a rule in `snapshot.rules` with `action = "refuse"` are never snapshotted. Add them to `.gitignore` to silence this warning.
+    Hint: This is to prevent large files from being added by accident. You can fix this by:
+      - Adding the file to `.gitignore`
+      - Run `jj config set --repo snapshot.max-new-file-size 13`
+        This will increase the maximum file size allowed for new files, in this repository only.
+      - Run `jj --config snapshot.max-new-file-size=13 st`
+        This will increase the maximum file size allowed for new files, for this command only.
+    [EOF]
+    "#);
+}
+
 #[test]
 fn test_materialize_and_snapshot_different_conflict_markers() {
     let test_env = TestEnvironment::default();
@@ -367,7 +449,7 @@ fn test_conflict_marker_length_stored_in_working_copy() {
     insta::assert_snapshot!(output.normalize_stdout_with(redact_output), @r#"
     Current operation: OperationId("da3b34243efe5ea04830cd2211b5be79444fbc2ef23681361fd2f551ebb86772bff21695da95b72388306e028bf04c6d76db10bf4cbd3a08eb34bf744c8900c7")
     Current tree: Merge(Conflicted([TreeId("381273b50cf73f8c81b3f1502ee89e9bbd6c1518"), TreeId("771f3d31c4588ea40a8864b2a981749888e596c2"), TreeId("f56b8223da0dab22b03b8323ced4946329aeb4e0")]))
-    Normal { <executable> }           249 <timestamp> Some(MaterializedConflictData { conflict_marker_len: 11 }) "file"
+    Normal { <executable> }           249 <timestamp> Some(MaterializedConflictData { conflict_marker_len: 11, sidecar_files: false }) "file"
     [EOF]
     "#);
 
@@ -430,7 +512,7 @@ fn test_conflict_marker_length_stored_in_working_copy() {
     insta::assert_snapshot!(output.normalize_stdout_with(redact_output), @r#"
     Current operation: OperationId("3de33bbfe3a9df8a052cc243aeedac6a3240d6115cb88f2779a1b6f1289288c6e78153875e48e41c17c098418f681bc872c54743e76b9e210f08533c50fc5a26")
     Current tree: Merge(Conflicted([TreeId("381273b50cf73f8c81b3f1502ee89e9bbd6c1518"), TreeId("771f3d31c4588ea40a8864b2a981749888e596c2"), TreeId("3329c18c95f7b7a55c278c2259e9c4ce711fae59")]))
-    Normal { <executable> }           289 <timestamp> Some(MaterializedConflictData { conflict_marker_len: 11 }) "file"
+    Normal { <executable> }           289 <timestamp> Some(MaterializedConflictData { conflict_marker_len: 11, sidecar_files: false }) "file"
     [EOF]
     "#);
 
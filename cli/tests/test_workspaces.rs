@@ -12,6 +12,8 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use std::path::Path;
+
 use test_case::test_case;
 
 use crate::common::CommandOutput;
@@ -253,6 +255,90 @@ fn test_workspaces_add_at_operation() {
     ");
 }
 
+/// Test adding a workspace by cloning the files of another one
+#[test]
+fn test_workspaces_add_clone_from() {
+    let test_env = TestEnvironment::default();
+    test_env.add_config("templates.commit_summary = 'description.first_line()'");
+    test_env.run_jj_in(".", ["git", "init", "main"]).success();
+    let main_dir = test_env.work_dir("main");
+    let secondary_dir = test_env.work_dir("secondary");
+    let third_dir = test_env.work_dir("third");
+
+    main_dir.write_file(".gitignore", "target/\n");
+    main_dir.write_file("file", "contents");
+    main_dir.run_jj(["commit", "-m", "initial"]).success();
+    main_dir.write_file("target/output", "build output");
+    main_dir.write_file("uncommitted", "changes");
+
+    // Ignored files are cloned too, and the uncommitted changes are removed by
+    // checking out the new working-copy commit
+    let output = main_dir.run_jj([
+        "workspace",
+        "add",
+        "--clone-from",
+        "default",
+        "--name",
+        "second",
+        "../secondary",
+    ]);
+    insta::assert_snapshot!(output.normalize_backslash(), @r#"
+    ------- stderr -------
+    Created workspace in "../secondary"
+    Cloned the files of workspace default
+    Working copy  (@) now at: 
+    Parent commit (@-)      : initial
+    Added 0 files, modified 0 files, removed 1 files
+    [EOF]
+    "#);
+    assert_eq!(
+        std::fs::read_to_string(secondary_dir.root().join("target").join("output")).unwrap(),
+        "build output"
+    );
+    assert!(!secondary_dir.root().join("uncommitted").exists());
+    let output = secondary_dir.run_jj(["diff", "--summary"]);
+    insta::assert_snapshot!(output, @"");
+    let output = main_dir.run_jj(["diff", "--summary"]);
+    insta::assert_snapshot!(output, @r"
+    A uncommitted
+    [EOF]
+    ");
+
+    // Another workspace can be cloned by name
+    let output = main_dir.run_jj([
+        "workspace",
+        "add",
+        "--clone-from",
+        "second",
+        "--name",
+        "third",
+        "../third",
+    ]);
+    insta::assert_snapshot!(output.normalize_backslash(), @r#"
+    ------- stderr -------
+    Created workspace in "../third"
+    Cloned the files of workspace second
+    Working copy  (@) now at: 
+    Parent commit (@-)      : initial
+    [EOF]
+    "#);
+    let output = third_dir.run_jj(["file", "list"]);
+    insta::assert_snapshot!(output, @r"
+    .gitignore
+    file
+    [EOF]
+    ");
+    assert!(third_dir.root().join("target").join("output").exists());
+
+    let output = main_dir.run_jj(["workspace", "add", "--clone-from", "unknown", "../fourth"]);
+    insta::assert_snapshot!(output, @r"
+    ------- stderr -------
+    Error: The location of workspace unknown is unknown
+    [EOF]
+    [exit status: 1]
+    ");
+}
+
 /// Test adding a workspace, but at a specific revision using '-r'
 #[test]
 fn test_workspaces_add_workspace_at_revision() {
@@ -1380,6 +1466,206 @@ fn test_workspaces_rename_workspace() {
     ");
 }
 
+/// Test moving a secondary workspace
+#[test]
+fn test_workspaces_move_secondary() {
+    let test_env = TestEnvironment::default();
+    test_env.run_jj_in(".", ["git", "init", "main"]).success();
+    let main_dir = test_env.work_dir("main");
+    main_dir
+        .run_jj(["workspace", "add", "--name", "second", "../secondary"])
+        .success();
+    let secondary_dir = test_env.work_dir("secondary");
+    secondary_dir.write_file("file", "contents");
+
+    let output = secondary_dir.run_jj(["workspace", "move", "../main"]);
+    insta::assert_snapshot!(output, @r#"
+    ------- stderr -------
+    Error: Destination "../main" already exists
+    [EOF]
+    [exit status: 1]
+    "#);
+
+    let output = secondary_dir.run_jj(["workspace", "move", "../moved"]);
+    insta::assert_snapshot!(output.normalize_backslash(), @r#"
+    ------- stderr -------
+    Moved workspace to "../moved"
+    [EOF]
+    "#);
+    assert!(!secondary_dir.root().exists());
+
+    // The changes made before moving were snapshotted
+    let moved_dir = test_env.work_dir("moved");
+    let output = moved_dir.run_jj(["diff", "--summary"]);
+    insta::assert_snapshot!(output, @r"
+    A file
+    [EOF]
+    ");
+    let output = moved_dir.run_jj(["workspace", "root"]);
+    insta::assert_snapshot!(output, @r"
+    $TEST_ENV/moved
+    [EOF]
+    ");
+
+    let output = moved_dir.run_jj(["workspace", "repair"]);
+    insta::assert_snapshot!(output, @r"
+    ------- stderr -------
+    Nothing to repair.
+    [EOF]
+    ");
+}
+
+/// Test moving the workspace containing the repo
+#[test]
+fn test_workspaces_move_repo_workspace() {
+    let test_env = TestEnvironment::default();
+    test_env.run_jj_in(".", ["git", "init", "main"]).success();
+    let main_dir = test_env.work_dir("main");
+    main_dir
+        .run_jj(["workspace", "add", "--name", "second", "../secondary"])
+        .success();
+    let secondary_dir = test_env.work_dir("secondary");
+
+    let output = main_dir.run_jj(["workspace", "move", "../moved"]);
+    insta::assert_snapshot!(output.normalize_backslash(), @r#"
+    ------- stderr -------
+    Updated the repo path of the workspace in "../secondary"
+    Moved workspace to "../moved"
+    [EOF]
+    "#);
+
+    // The other workspace now uses the repo in the new location
+    let output = secondary_dir.run_jj(["workspace", "list"]);
+    insta::assert_snapshot!(output, @r"
+    default: qpvuntsm e8849ae1 (empty) (no description set)
+    second: uuqppmxq 94f41578 (empty) (no description set)
+    [EOF]
+    ");
+}
+
+/// Test repairing workspaces after the workspace containing the repo was moved
+/// without jj
+#[test]
+fn test_workspaces_repair_moved_repo() {
+    let test_env = TestEnvironment::default();
+    test_env.run_jj_in(".", ["git", "init", "main"]).success();
+    let main_dir = test_env.work_dir("main");
+    main_dir
+        .run_jj(["workspace", "add", "--name", "second", "../secondary"])
+        .success();
+    main_dir
+        .run_jj(["workspace", "add", "--name", "third", "../third"])
+        .success();
+    let secondary_dir = test_env.work_dir("secondary");
+    let third_dir = test_env.work_dir("third");
+    std::fs::rename(main_dir.root(), test_env.env_root().join("moved")).unwrap();
+    let moved_dir = test_env.work_dir("moved");
+
+    let output = secondary_dir.run_jj(["workspace", "list"]);
+    insta::assert_snapshot!(output.normalize_backslash(), @r"
+    ------- stderr -------
+    Error: The repository directory at $TEST_ENV/main/.jj/repo is missing. Was it moved?
+    Hint: Run `jj workspace repair --repo <path>` to point this workspace to the moved repository.
+    [EOF]
+    [exit status: 1]
+    ");
+
+    // A workspace can be pointed to the moved repo
+    let output = secondary_dir.run_jj(["workspace", "repair", "--repo", "../moved"]);
+    insta::assert_snapshot!(output, @r"
+    ------- stderr -------
+    Updated the repo path of the current workspace
+    [EOF]
+    ");
+    let output = secondary_dir.run_jj(["workspace", "root"]);
+    insta::assert_snapshot!(output, @r"
+    $TEST_ENV/secondary
+    [EOF]
+    ");
+
+    // Repairing the workspace containing the repo fixes the other workspaces
+    let output = moved_dir.run_jj(["workspace", "repair"]);
+    insta::assert_snapshot!(output.normalize_backslash(), @r#"
+    ------- stderr -------
+    Updated the repo path of the workspace in "../third"
+    Updated the recorded location of workspace default
+    [EOF]
+    "#);
+    let output = third_dir.run_jj(["workspace", "root"]);
+    insta::assert_snapshot!(output, @r"
+    $TEST_ENV/third
+    [EOF]
+    ");
+}
+
+/// Test repairing a copied workspace
+#[test]
+fn test_workspaces_repair_copied_workspace() {
+    let test_env = TestEnvironment::default();
+    test_env.run_jj_in(".", ["git", "init", "main"]).success();
+    let main_dir = test_env.work_dir("main");
+    main_dir
+        .run_jj(["workspace", "add", "--name", "second", "../secondary"])
+        .success();
+    let secondary_dir = test_env.work_dir("secondary");
+    secondary_dir.write_file("file", "contents");
+    secondary_dir.run_jj(["debug", "snapshot"]).success();
+    copy_dir_all(secondary_dir.root(), &test_env.env_root().join("copy"));
+    let copy_dir = test_env.work_dir("copy");
+
+    let output = copy_dir.run_jj(["workspace", "repair"]);
+    insta::assert_snapshot!(output.normalize_backslash(), @r#"
+    ------- stderr -------
+    Warning: Workspace second also exists in "../secondary". The current workspace appears to be a copy of it.
+    Error: The copied workspace needs a new name
+    Hint: Run `jj workspace repair --rename <NAME>` to rename it.
+    [EOF]
+    [exit status: 1]
+    "#);
+
+    let output = copy_dir.run_jj(["workspace", "repair", "--rename", "default"]);
+    insta::assert_snapshot!(output.normalize_backslash(), @r#"
+    ------- stderr -------
+    Warning: Workspace second also exists in "../secondary". The current workspace appears to be a copy of it.
+    Error: Workspace named 'default' already exists
+    [EOF]
+    [exit status: 1]
+    "#);
+
+    let output = copy_dir.run_jj(["workspace", "repair", "--rename", "copy"]);
+    insta::assert_snapshot!(output.normalize_backslash(), @r#"
+    ------- stderr -------
+    Warning: Workspace second also exists in "../secondary". The current workspace appears to be a copy of it.
+    Renamed the current workspace to copy
+    [EOF]
+    "#);
+
+    // The copy has its own working-copy commit with the same contents
+    let output = copy_dir.run_jj(["workspace", "list", "-T", r#"name ++ "\n""#]);
+    insta::assert_snapshot!(output, @r"
+    copy
+    default
+    second
+    [EOF]
+    ");
+    let output = copy_dir.run_jj(["diff", "--summary"]);
+    insta::assert_snapshot!(output, @r"
+    A file
+    [EOF]
+    ");
+    let output = secondary_dir.run_jj(["diff", "--summary"]);
+    insta::assert_snapshot!(output, @r"
+    A file
+    [EOF]
+    ");
+    let output = copy_dir.run_jj(["workspace", "repair"]);
+    insta::assert_snapshot!(output, @r"
+    ------- stderr -------
+    Nothing to repair.
+    [EOF]
+    ");
+}
+
 #[must_use]
 fn get_log_output(work_dir: &TestWorkDir) -> CommandOutput {
     let template = r#"
@@ -1391,3 +1677,15 @@ fn get_log_output(work_dir: &TestWorkDir) -> CommandOutput {
     "#;
     work_dir.run_jj(["log", "-T", template, "-r", "all()"])
 }
+
+fn copy_dir_all(src: &Path, dst: &Path) {
+    std::fs::create_dir(dst).unwrap();
+    for entry in std::fs::read_dir(src).unwrap() {
+        let entry = entry.unwrap();
+        if entry.file_type().unwrap().is_dir() {
+            copy_dir_all(&entry.path(), &dst.join(entry.file_name()));
+        } else {
+            std::fs::copy(entry.path(), dst.join(entry.file_name())).unwrap();
+        }
+    }
+}
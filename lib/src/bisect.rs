@@ -0,0 +1,285 @@
+// Copyright 2025 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Bisection of a range of commits to find the commit which introduced a bug.
+//!
+//! The state of a bisection is recorded in the tags of the operations which
+//! update it, so it follows the operation log.
+
+use std::rc::Rc;
+use std::slice;
+
+use itertools::Itertools as _;
+use thiserror::Error;
+
+use crate::backend::BackendError;
+use crate::backend::CommitId;
+use crate::commit::Commit;
+use crate::object_id::ObjectId as _;
+use crate::op_store::OpStoreError;
+use crate::op_walk;
+use crate::operation::Operation;
+use crate::repo::Repo;
+use crate::revset::ResolvedRevsetExpression;
+use crate::revset::RevsetEvaluationError;
+use crate::revset::RevsetIteratorExt as _;
+
+/// Operation tag which records the bisection state. An empty value means that
+/// the bisection was ended.
+pub const BISECT_TAG: &str = "bisect";
+
+/// Error while loading or advancing a bisection.
+#[derive(Debug, Error)]
+pub enum BisectError {
+    /// The bisection state recorded in an operation is invalid.
+    #[error("Invalid bisection state in operation {op_id}")]
+    InvalidState {
+        /// The hex id of the operation.
+        op_id: String,
+    },
+    /// Failed to walk the operation log.
+    #[error(transparent)]
+    OpStore(#[from] OpStoreError),
+    /// Failed to load a commit.
+    #[error(transparent)]
+    Backend(#[from] BackendError),
+    /// Failed to evaluate the remaining candidates.
+    #[error(transparent)]
+    RevsetEvaluation(#[from] RevsetEvaluationError),
+}
+
+/// How a commit was marked during a bisection.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BisectMark {
+    /// The commit doesn't have the bug.
+    Good,
+    /// The commit has the bug.
+    Bad,
+    /// The commit couldn't be tested.
+    Skip,
+}
+
+impl BisectMark {
+    /// The name of the mark, such as "good".
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Good => "good",
+            Self::Bad => "bad",
+            Self::Skip => "skip",
+        }
+    }
+}
+
+/// State of a bisection in progress.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct BisectState {
+    /// Commits known not to have the bug.
+    pub good: Vec<CommitId>,
+    /// Commits known to have the bug.
+    pub bad: Vec<CommitId>,
+    /// Commits which couldn't be tested.
+    pub skipped: Vec<CommitId>,
+    /// The commit currently being tested, if any.
+    pub current: Option<CommitId>,
+    /// The working-copy commit when the bisection was started.
+    pub original: Option<CommitId>,
+}
+
+/// What to do next in a bisection.
+#[derive(Clone, Debug)]
+pub enum NextStep {
+    /// The commit should be tested.
+    Evaluate {
+        /// The commit to test.
+        commit: Commit,
+        /// The number of commits which may still have introduced the bug,
+        /// excluding the skipped commits.
+        remaining: usize,
+    },
+    /// There are no commits left to test.
+    Done(BisectionResult),
+}
+
+/// The outcome of a finished bisection.
+#[derive(Clone, Debug)]
+pub enum BisectionResult {
+    /// The bug was introduced by one of the commits. There's usually a single
+    /// one.
+    Found(Vec<Commit>),
+    /// The bug was introduced by one of the commits, but some of them were
+    /// skipped so it's unknown which.
+    Indeterminate(Vec<Commit>),
+}
+
+impl BisectState {
+    /// Records that `commit_id` was tested with the given result.
+    pub fn mark(&mut self, commit_id: CommitId, mark: BisectMark) {
+        self.good.retain(|id| *id != commit_id);
+        self.bad.retain(|id| *id != commit_id);
+        self.skipped.retain(|id| *id != commit_id);
+        match mark {
+            BisectMark::Good => self.good.push(commit_id),
+            BisectMark::Bad => self.bad.push(commit_id),
+            BisectMark::Skip => self.skipped.push(commit_id),
+        }
+    }
+
+    /// Commits which may have introduced the bug, including the bad and
+    /// skipped commits.
+    ///
+    /// This assumes that the bug persists once introduced, so it was
+    /// introduced by an ancestor of all bad commits which isn't an ancestor of
+    /// a good commit.
+    fn suspects_expression(&self) -> Rc<ResolvedRevsetExpression> {
+        self.bad
+            .iter()
+            .map(|id| ResolvedRevsetExpression::commit(id.clone()).ancestors())
+            .reduce(|acc, ancestors| acc.intersection(&ancestors))
+            .unwrap_or_else(ResolvedRevsetExpression::none)
+            .minus(&ResolvedRevsetExpression::commits(self.good.clone()).ancestors())
+            .minus(&ResolvedRevsetExpression::root())
+    }
+
+    /// Picks the commit to test next, or returns the result if there's none
+    /// left.
+    pub fn next_step(&self, repo: &dyn Repo) -> Result<NextStep, BisectError> {
+        let suspects = self.suspects_expression();
+        let bad = ResolvedRevsetExpression::commits(self.bad.clone());
+        let skipped = ResolvedRevsetExpression::commits(self.skipped.clone());
+        let candidates = suspects.minus(&bad.descendants()).minus(&skipped);
+        let candidate_ids: Vec<_> = candidates.clone().evaluate(repo)?.iter().try_collect()?;
+        if !candidate_ids.is_empty() {
+            let commit_id = candidates
+                .bisect()
+                .evaluate(repo)?
+                .iter()
+                .next()
+                .expect("bisect() of a non-empty set shouldn't be empty")?;
+            return Ok(NextStep::Evaluate {
+                commit: repo.store().get_commit(&commit_id)?,
+                remaining: candidate_ids.len(),
+            });
+        }
+
+        let first_bad = suspects.intersection(&bad).roots();
+        let skipped_suspects = suspects.intersection(&skipped);
+        let has_skipped_suspects = skipped_suspects
+            .clone()
+            .evaluate(repo)?
+            .iter()
+            .next()
+            .is_some();
+        let commits: Vec<_> = first_bad
+            .union(&skipped_suspects)
+            .evaluate(repo)?
+            .iter()
+            .commits(repo.store())
+            .try_collect()?;
+        let result = if has_skipped_suspects {
+            BisectionResult::Indeterminate(commits)
+        } else {
+            BisectionResult::Found(commits)
+        };
+        Ok(NextStep::Done(result))
+    }
+
+    /// Serializes the state to an operation tag value.
+    pub fn to_tag_value(&self) -> String {
+        let mut lines = vec![];
+        for (mark, ids) in [
+            (BisectMark::Good, &self.good),
+            (BisectMark::Bad, &self.bad),
+            (BisectMark::Skip, &self.skipped),
+        ] {
+            lines.extend(
+                ids.iter()
+                    .map(|id| format!("{} {}", mark.as_str(), id.hex())),
+            );
+        }
+        if let Some(id) = &self.current {
+            lines.push(format!("current {}", id.hex()));
+        }
+        if let Some(id) = &self.original {
+            lines.push(format!("original {}", id.hex()));
+        }
+        lines.join("\n")
+    }
+
+    /// Parses the state from an operation tag value.
+    pub fn from_tag_value(value: &str) -> Option<Self> {
+        let mut state = Self::default();
+        for line in value.lines() {
+            let (keyword, hex) = line.split_once(' ')?;
+            let id = CommitId::try_from_hex(hex)?;
+            match keyword {
+                "good" => state.good.push(id),
+                "bad" => state.bad.push(id),
+                "skip" => state.skipped.push(id),
+                "current" => state.current = Some(id),
+                "original" => state.original = Some(id),
+                _ => return None,
+            }
+        }
+        Some(state)
+    }
+}
+
+/// Loads the state of the bisection in progress at `op`, if any.
+pub fn load_bisect_state(op: &Operation) -> Result<Option<BisectState>, BisectError> {
+    for op in op_walk::walk_ancestors(slice::from_ref(op)) {
+        let op = op?;
+        let Some(value) = op.metadata().tags.get(BISECT_TAG) else {
+            continue;
+        };
+        if value.is_empty() {
+            return Ok(None);
+        }
+        return BisectState::from_tag_value(value).map(Some).ok_or_else(|| {
+            BisectError::InvalidState {
+                op_id: op.id().hex(),
+            }
+        });
+    }
+    Ok(None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tag_value_round_trip() {
+        let state = BisectState {
+            good: vec![CommitId::from_hex("aa"), CommitId::from_hex("bb")],
+            bad: vec![CommitId::from_hex("cc")],
+            skipped: vec![],
+            current: Some(CommitId::from_hex("dd")),
+            original: Some(CommitId::from_hex("ee")),
+        };
+        let value = state.to_tag_value();
+        assert_eq!(value, "good aa\ngood bb\nbad cc\ncurrent dd\noriginal ee");
+        assert_eq!(BisectState::from_tag_value(&value), Some(state));
+        assert_eq!(BisectState::from_tag_value("good xyz"), None);
+        assert_eq!(BisectState::from_tag_value("unknown aa"), None);
+    }
+
+    #[test]
+    fn test_mark_replaces_previous_mark() {
+        let mut state = BisectState::default();
+        state.mark(CommitId::from_hex("aa"), BisectMark::Good);
+        state.mark(CommitId::from_hex("aa"), BisectMark::Bad);
+        assert_eq!(state.good, vec![]);
+        assert_eq!(state.bad, vec![CommitId::from_hex("aa")]);
+    }
+}
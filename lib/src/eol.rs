@@ -30,7 +30,7 @@ pub(crate) fn create_target_eol_strategy(
     }
 }
 
-fn is_binary(bytes: &[u8]) -> bool {
+pub(crate) fn is_binary(bytes: &[u8]) -> bool {
     // TODO(06393993): align the algorithm with git so that the git config autocrlf
     // users won't see different decisions on whether a file is binary and needs to
     // perform EOL conversion.
@@ -222,6 +222,63 @@ pub fn persist_content_addressed_temp_file<P: AsRef<Path>>(
     }
 }
 
+/// Copies the file `from` to `to`, sharing the data blocks if the
+/// filesystem supports it.
+///
+/// On Linux, the file is cloned by the `FICLONE` ioctl (supported by e.g. Btrfs
+/// and XFS), falling back to a plain copy. On macOS, `fs::copy()` already
+/// clones the file on APFS. The permissions and the modification time are
+/// preserved.
+pub fn clone_file(from: &Path, to: &Path) -> io::Result<()> {
+    #[cfg(target_os = "linux")]
+    {
+        let mut src = File::open(from)?;
+        let metadata = src.metadata()?;
+        let mut dst = File::options().write(true).create_new(true).open(to)?;
+        if rustix::fs::ioctl_ficlone(&dst, &src).is_err() {
+            io::copy(&mut src, &mut dst)?;
+        }
+        dst.set_permissions(metadata.permissions())?;
+        dst.set_modified(metadata.modified()?)
+    }
+    #[cfg(not(target_os = "linux"))]
+    {
+        fs::copy(from, to)?;
+        let modified = fs::metadata(from)?.modified()?;
+        File::options().write(true).open(to)?.set_modified(modified)
+    }
+}
+
+/// Recursively clones the contents of the directory `from` into the existing
+/// directory `to` by using [`clone_file()`]. Symlinks are recreated.
+///
+/// Entries of `from` for which `exclude` returns true are skipped.
+pub fn clone_dir_all(
+    from: &Path,
+    to: &Path,
+    exclude: &dyn Fn(&Path) -> bool,
+) -> Result<(), PathError> {
+    for entry in from.read_dir().context(from)? {
+        let entry = entry.context(from)?;
+        let src_path = entry.path();
+        if exclude(&src_path) {
+            continue;
+        }
+        let dst_path = to.join(entry.file_name());
+        let file_type = entry.file_type().context(&src_path)?;
+        if file_type.is_dir() {
+            fs::create_dir(&dst_path).context(&dst_path)?;
+            clone_dir_all(&src_path, &dst_path, exclude)?;
+        } else if file_type.is_symlink() {
+            let target = fs::read_link(&src_path).context(&src_path)?;
+            try_symlink(target, &dst_path).context(&dst_path)?;
+        } else {
+            clone_file(&src_path, &dst_path).context(&dst_path)?;
+        }
+    }
+    Ok(())
+}
+
 /// Reads from an async source and writes to a sync destination. Does not spawn
 /// a task, so writes will block.
 pub async fn copy_async_to_sync<R: AsyncRead, W: Write + ?Sized>(
@@ -447,6 +504,39 @@ mod tests {
         assert!(persist_content_addressed_temp_file(temp_file, &target).is_ok());
     }
 
+    #[test]
+    fn test_clone_dir_all() {
+        let temp_dir = new_temp_dir();
+        let src = temp_dir.path().join("src");
+        let dst = temp_dir.path().join("dst");
+        fs::create_dir_all(src.join("dir")).unwrap();
+        fs::create_dir_all(src.join("excluded")).unwrap();
+        fs::create_dir(&dst).unwrap();
+        fs::write(src.join("file"), "contents").unwrap();
+        fs::write(src.join("dir").join("file"), "nested").unwrap();
+        fs::write(src.join("excluded").join("file"), "").unwrap();
+        let modified = std::time::SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(1000);
+        File::options()
+            .write(true)
+            .open(src.join("file"))
+            .unwrap()
+            .set_modified(modified)
+            .unwrap();
+
+        let excluded = src.join("excluded");
+        clone_dir_all(&src, &dst, &|path: &Path| path == excluded).unwrap();
+        assert_eq!(fs::read_to_string(dst.join("file")).unwrap(), "contents");
+        assert_eq!(
+            fs::metadata(dst.join("file")).unwrap().modified().unwrap(),
+            modified
+        );
+        assert_eq!(
+            fs::read_to_string(dst.join("dir").join("file")).unwrap(),
+            "nested"
+        );
+        assert!(!dst.join("excluded").exists());
+    }
+
     #[test]
     fn test_copy_async_to_sync_small() {
         let input = b"hello";
@@ -17,6 +17,8 @@
 
 use std::collections::HashMap;
 use std::collections::HashSet;
+use std::iter;
+use std::ops::Range;
 use std::sync::mpsc::channel;
 
 use futures::StreamExt as _;
@@ -25,12 +27,16 @@ use jj_lib::backend::BackendError;
 use jj_lib::backend::CommitId;
 use jj_lib::backend::FileId;
 use jj_lib::backend::TreeValue;
+use jj_lib::diff::Diff;
+use jj_lib::diff::DiffHunkKind;
 use jj_lib::matchers::Matcher;
+use jj_lib::merge::MergedTreeValue;
 use jj_lib::merged_tree::MergedTree;
 use jj_lib::merged_tree::MergedTreeBuilder;
 use jj_lib::merged_tree::TreeDiffEntry;
 use jj_lib::repo::MutableRepo;
 use jj_lib::repo::Repo as _;
+use jj_lib::repo_path::RepoPath;
 use jj_lib::repo_path::RepoPathBuf;
 use jj_lib::revset::RevsetExpression;
 use jj_lib::revset::RevsetIteratorExt as _;
@@ -38,13 +44,11 @@ use jj_lib::store::Store;
 use jj_lib::tree::Tree;
 use rayon::iter::IntoParallelIterator as _;
 use rayon::prelude::ParallelIterator as _;
+use tokio::io::AsyncReadExt as _;
 
 use crate::revset::RevsetEvaluationError;
 
 /// Represents a file whose content may be transformed by a FileFixer.
-// TODO: Add the set of changed line/byte ranges, so those can be passed into code formatters via
-// flags. This will help avoid introducing unrelated changes when working on code with out of date
-// formatting.
 #[derive(Debug, PartialEq, Eq, Hash, Clone)]
 pub struct FileToFix {
     /// Unique identifier for the file content.
@@ -55,6 +59,15 @@ pub struct FileToFix {
     ///  - Update parts of the file's content that should be derived from the
     ///    file's path.
     pub repo_path: RepoPathBuf,
+
+    /// The lines that were changed compared to the parents of the commit, as
+    /// sorted, non-overlapping ranges of 0-based line numbers. This can be
+    /// passed into code formatters via flags, which helps avoid introducing
+    /// unrelated changes when working on code with out of date formatting.
+    ///
+    /// `None` means that the whole file is considered changed, e.g. because it
+    /// was added or unchanged files are being fixed.
+    pub changed_lines: Option<Vec<Range<usize>>>,
 }
 
 /// Error fixing files.
@@ -209,32 +222,44 @@ pub async fn fix_files(
     );
 
     let mut unique_files_to_fix: HashSet<FileToFix> = HashSet::new();
-    let mut commit_paths: HashMap<CommitId, HashSet<RepoPathBuf>> = HashMap::new();
+    let mut commit_files: HashMap<CommitId, HashMap<RepoPathBuf, Vec<FileToFix>>> = HashMap::new();
     for commit in commits.iter().rev() {
-        let mut paths: HashSet<RepoPathBuf> = HashSet::new();
+        let mut files: HashMap<RepoPathBuf, Vec<FileToFix>> = HashMap::new();
 
         // If --include-unchanged-files, we always fix every matching file in the tree.
         // Otherwise, we fix the matching changed files in this commit, plus any that
         // were fixed in ancestors, so we don't lose those changes. We do this
         // instead of rebasing onto those changes, to avoid merge conflicts.
+        let mut inherited_files: HashMap<RepoPathBuf, Vec<FileToFix>> = HashMap::new();
         let parent_tree = if include_unchanged_files {
             MergedTree::resolved(Tree::empty(repo_mut.store().clone(), RepoPathBuf::root()))
         } else {
             for parent_id in commit.parent_ids() {
-                if let Some(parent_paths) = commit_paths.get(parent_id) {
-                    paths.extend(parent_paths.iter().cloned());
+                if let Some(parent_files) = commit_files.get(parent_id) {
+                    for (repo_path, files_to_fix) in parent_files {
+                        inherited_files
+                            .entry(repo_path.clone())
+                            .or_default()
+                            .extend(files_to_fix.iter().cloned());
+                    }
                 }
             }
             commit.parent_tree(repo_mut)?
         };
         // TODO: handle copy tracking
-        let mut diff_stream = parent_tree.diff_stream(&commit.tree_async().await?, &matcher);
+        let tree = commit.tree_async().await?;
+        let mut changed_paths: HashSet<RepoPathBuf> = HashSet::new();
+        let mut diff_stream = parent_tree.diff_stream(&tree, &matcher);
         while let Some(TreeDiffEntry {
             path: repo_path,
             values,
         }) = diff_stream.next().await
         {
-            let (_before, after) = values?;
+            let (before, after) = values?;
+            changed_paths.insert(repo_path.clone());
+            let inherited = inherited_files
+                .get(&repo_path)
+                .map_or(&[][..], Vec::as_slice);
             // Deleted files have no file content to fix, and they have no terms in `after`,
             // so we don't add any files-to-fix for them. Conflicted files produce one
             // file-to-fix for each side of the conflict.
@@ -249,17 +274,54 @@ pub async fn fix_files(
                 {
                     // TODO: Skip the file if its content is larger than some configured size,
                     // preferably without actually reading it yet.
+                    let changed_lines = if include_unchanged_files {
+                        None
+                    } else {
+                        find_changed_lines(repo_mut.store(), &repo_path, &before, &id, inherited)
+                            .await?
+                    };
+                    let file_to_fix = FileToFix {
+                        file_id: id,
+                        repo_path: repo_path.clone(),
+                        changed_lines,
+                    };
+                    unique_files_to_fix.insert(file_to_fix.clone());
+                    files
+                        .entry(repo_path.clone())
+                        .or_default()
+                        .push(file_to_fix);
+                }
+            }
+        }
+
+        // The files fixed in ancestors which are unchanged in this commit have the
+        // same content as in a parent, so they are fixed the same way.
+        for (repo_path, inherited) in inherited_files {
+            if changed_paths.contains(&repo_path) {
+                continue;
+            }
+            let value = tree.path_value_async(&repo_path).await?;
+            for term in value.into_iter().flatten() {
+                if let TreeValue::File { id, .. } = term {
+                    let changed_lines = inherited
+                        .iter()
+                        .find(|file| file.file_id == id)
+                        .and_then(|file| file.changed_lines.clone());
                     let file_to_fix = FileToFix {
-                        file_id: id.clone(),
+                        file_id: id,
                         repo_path: repo_path.clone(),
+                        changed_lines,
                     };
                     unique_files_to_fix.insert(file_to_fix.clone());
-                    paths.insert(repo_path.clone());
+                    files
+                        .entry(repo_path.clone())
+                        .or_default()
+                        .push(file_to_fix);
                 }
             }
         }
 
-        commit_paths.insert(commit.id().clone(), paths);
+        commit_files.insert(commit.id().clone(), files);
     }
 
     tracing::debug!(
@@ -279,11 +341,11 @@ pub async fn fix_files(
         // TODO: Build the trees in parallel before `transform_descendants()` and only
         // keep the tree IDs in memory, so we can pass them to the rewriter.
         let old_commit_id = rewriter.old_commit().id().clone();
-        let repo_paths = commit_paths.get(&old_commit_id).unwrap();
+        let files = commit_files.get(&old_commit_id).unwrap();
         let old_tree = rewriter.old_commit().tree()?;
         let mut tree_builder = MergedTreeBuilder::new(old_tree.id().clone());
         let mut has_changes = false;
-        for repo_path in repo_paths {
+        for (repo_path, files_to_fix) in files {
             let old_value = old_tree.path_value(repo_path)?;
             let new_value = old_value.map(|old_term| {
                 if let Some(TreeValue::File {
@@ -292,11 +354,11 @@ pub async fn fix_files(
                     copy_id,
                 }) = old_term
                 {
-                    let file_to_fix = FileToFix {
-                        file_id: id.clone(),
-                        repo_path: repo_path.clone(),
-                    };
-                    if let Some(new_id) = fixed_file_ids.get(&file_to_fix) {
+                    let new_id = files_to_fix
+                        .iter()
+                        .find(|file| file.file_id == *id)
+                        .and_then(|file| fixed_file_ids.get(file));
+                    if let Some(new_id) = new_id {
                         return Some(TreeValue::File {
                             id: new_id.clone(),
                             executable: *executable,
@@ -332,3 +394,138 @@ pub async fn fix_files(
     tracing::debug!(?summary);
     Ok(summary)
 }
+
+/// Finds the lines of the file `id` at `repo_path` that were changed compared
+/// to the `before` value in the parents, or that were changed in the
+/// `inherited` files fixed in the parents.
+async fn find_changed_lines(
+    store: &Store,
+    repo_path: &RepoPath,
+    before: &MergedTreeValue,
+    id: &FileId,
+    inherited: &[FileToFix],
+) -> Result<Option<Vec<Range<usize>>>, FixError> {
+    if let Some(file) = inherited.iter().find(|file| file.file_id == *id) {
+        return Ok(file.changed_lines.clone());
+    }
+    let Some(Some(TreeValue::File { id: old_id, .. })) = before.as_resolved() else {
+        // The file was added, or the parents have a conflict, so we can't tell
+        // which lines were changed.
+        return Ok(None);
+    };
+    let old_content = read_file(store, repo_path, old_id).await?;
+    let new_content = read_file(store, repo_path, id).await?;
+    let mut changed_lines = translate_changed_lines(&old_content, &new_content, &[]);
+    for file in inherited {
+        let Some(ranges) = &file.changed_lines else {
+            return Ok(None);
+        };
+        let content = read_file(store, repo_path, &file.file_id).await?;
+        changed_lines.extend(translate_changed_lines(&content, &new_content, ranges));
+    }
+    Ok(Some(merge_line_ranges(changed_lines)))
+}
+
+async fn read_file(store: &Store, repo_path: &RepoPath, id: &FileId) -> Result<Vec<u8>, FixError> {
+    let mut content = vec![];
+    store
+        .read_file(repo_path, id)
+        .await?
+        .read_to_end(&mut content)
+        .await?;
+    Ok(content)
+}
+
+/// Returns the ranges of lines in `new` which differ from `old`, plus the
+/// `old_ranges` of lines in `old` translated to the corresponding lines in
+/// `new`. Line numbers are 0-based, and the returned ranges are sorted and
+/// don't overlap.
+pub fn translate_changed_lines(
+    old: &[u8],
+    new: &[u8],
+    old_ranges: &[Range<usize>],
+) -> Vec<Range<usize>> {
+    let mut ranges = vec![];
+    let mut old_line = 0;
+    let mut new_line = 0;
+    for hunk in Diff::by_line([old, new]).hunks() {
+        let old_count = count_lines(hunk.contents[0]);
+        let new_count = count_lines(hunk.contents[1]);
+        match hunk.kind {
+            DiffHunkKind::Matching => {
+                for range in old_ranges {
+                    let start = range.start.max(old_line);
+                    let end = range.end.min(old_line + old_count);
+                    if start < end {
+                        ranges.push(start - old_line + new_line..end - old_line + new_line);
+                    }
+                }
+            }
+            DiffHunkKind::Different => {
+                if new_count > 0 {
+                    ranges.push(new_line..new_line + new_count);
+                }
+            }
+        }
+        old_line += old_count;
+        new_line += new_count;
+    }
+    merge_line_ranges(ranges)
+}
+
+/// Returns `new` with the changes compared to `old` reverted, except for the
+/// changes touching the given ranges of lines in `old`.
+pub fn restrict_changes_to_lines(old: &[u8], new: &[u8], old_ranges: &[Range<usize>]) -> Vec<u8> {
+    let in_ranges = |line: usize| old_ranges.iter().any(|range| range.contains(&line));
+    let mut content = vec![];
+    let mut old_line = 0;
+    for hunk in Diff::by_line([old, new]).hunks() {
+        let old_count = count_lines(hunk.contents[0]);
+        if hunk.kind == DiffHunkKind::Different && old_count == count_lines(hunk.contents[1]) {
+            // Lines replaced one for one are selected individually, so that
+            // changes to adjacent lines can be told apart.
+            let old_lines = hunk.contents[0].split_inclusive(|b| *b == b'\n');
+            let new_lines = hunk.contents[1].split_inclusive(|b| *b == b'\n');
+            for (i, (old_text, new_text)) in iter::zip(old_lines, new_lines).enumerate() {
+                let text = if in_ranges(old_line + i) {
+                    new_text
+                } else {
+                    old_text
+                };
+                content.extend_from_slice(text);
+            }
+            old_line += old_count;
+            continue;
+        }
+        let is_selected = match hunk.kind {
+            DiffHunkKind::Matching => false,
+            // Lines inserted right before or after a range count as touching it.
+            DiffHunkKind::Different if old_count == 0 => old_ranges
+                .iter()
+                .any(|range| range.start <= old_line && old_line <= range.end),
+            DiffHunkKind::Different => old_ranges
+                .iter()
+                .any(|range| range.start < old_line + old_count && old_line < range.end),
+        };
+        let side = if is_selected { 1 } else { 0 };
+        content.extend_from_slice(hunk.contents[side]);
+        old_line += old_count;
+    }
+    content
+}
+
+fn count_lines(text: &[u8]) -> usize {
+    text.split_inclusive(|b| *b == b'\n').count()
+}
+
+fn merge_line_ranges(mut ranges: Vec<Range<usize>>) -> Vec<Range<usize>> {
+    ranges.sort_by_key(|range| range.start);
+    let mut merged: Vec<Range<usize>> = Vec::with_capacity(ranges.len());
+    for range in ranges {
+        match merged.last_mut() {
+            Some(last) if range.start <= last.end => last.end = last.end.max(range.end),
+            _ => merged.push(range),
+        }
+    }
+    merged
+}
@@ -25,6 +25,7 @@
 use std::path::PathBuf;
 
 use crate::config::ConfigGetError;
+use crate::config::ConfigGetResultExt as _;
 use crate::settings::UserSettings;
 
 /// Config for Watchman filesystem monitor (<https://facebook.github.io/watchman/>).
@@ -34,12 +35,30 @@ pub struct WatchmanConfig {
     pub register_trigger: bool,
 }
 
+/// Config for the built-in inotify filesystem monitor (Linux only).
+#[derive(Eq, PartialEq, Clone, Debug)]
+pub struct InotifyConfig {
+    /// Whether to start the monitor daemon if it isn't running.
+    pub spawn_daemon: bool,
+    /// Program and arguments to start the monitor daemon with. The working
+    /// copy path, the working-copy state path, and `--max-tracked-paths=N`
+    /// are appended. The daemon can't be started if this is empty.
+    pub daemon_command: Vec<String>,
+    /// Maximum number of changed paths the daemon remembers. If more paths
+    /// change between two snapshots, the next snapshot scans the whole working
+    /// copy.
+    pub max_tracked_paths: usize,
+}
+
 /// The recognized kinds of filesystem monitors.
 #[derive(Eq, PartialEq, Clone, Debug)]
 pub enum FsmonitorSettings {
     /// The Watchman filesystem monitor (<https://facebook.github.io/watchman/>).
     Watchman(WatchmanConfig),
 
+    /// The built-in filesystem monitor based on Linux inotify.
+    Inotify(InotifyConfig),
+
     /// Only used in tests.
     Test {
         /// The set of changed files to pretend that the filesystem monitor is
@@ -63,6 +82,14 @@ impl FsmonitorSettings {
                 register_trigger: settings
                     .get_bool("fsmonitor.watchman.register-snapshot-trigger")?,
             })),
+            "inotify" => Ok(Self::Inotify(InotifyConfig {
+                spawn_daemon: settings.get_bool("fsmonitor.inotify.spawn-daemon")?,
+                daemon_command: settings
+                    .get("fsmonitor.inotify.daemon-command")
+                    .optional()?
+                    .unwrap_or_default(),
+                max_tracked_paths: settings.get("fsmonitor.inotify.max-tracked-paths")?,
+            })),
             "test" => Err(ConfigGetError::Type {
                 name: name.to_owned(),
                 error: "Cannot use test fsmonitor in real repository".into(),
@@ -78,6 +105,9 @@ impl FsmonitorSettings {
     }
 }
 
+#[cfg(all(target_os = "linux", feature = "inotify"))]
+pub mod inotify;
+
 /// Filesystem monitor integration using Watchman
 /// (<https://facebook.github.io/watchman/>). Requires `watchman` to already be
 /// installed on the system.
@@ -0,0 +1,886 @@
+// Copyright 2025 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Filesystem monitor built on Linux inotify.
+//!
+//! inotify only reports events while a watch is held, so a small daemon per
+//! workspace watches the working copy and remembers which paths changed. The
+//! daemon listens on a Unix socket in the working-copy state directory. Each
+//! query passes the cursor returned by the previous one, and the daemon
+//! replies with the paths changed since then. If the daemon can't tell (it was
+//! restarted, or too many events were dropped), the caller has to crawl the
+//! entire working copy.
+
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::ffi::OsStr;
+use std::ffi::OsString;
+use std::fs;
+use std::fs::File;
+use std::io;
+use std::io::BufRead as _;
+use std::io::BufReader;
+use std::io::Read as _;
+use std::io::Write as _;
+use std::net::Shutdown;
+use std::ops::ControlFlow;
+use std::os::unix::ffi::OsStrExt as _;
+use std::os::unix::net::UnixListener;
+use std::os::unix::net::UnixStream;
+use std::os::unix::process::CommandExt as _;
+use std::path::Path;
+use std::path::PathBuf;
+use std::process;
+use std::process::Command;
+use std::process::Stdio;
+use std::str;
+use std::sync::Condvar;
+use std::sync::Mutex;
+use std::thread;
+use std::time::Duration;
+use std::time::Instant;
+use std::time::SystemTime;
+
+use bstr::ByteSlice as _;
+use inotify::EventMask;
+use inotify::Inotify;
+use inotify::WatchDescriptor;
+use inotify::WatchMask;
+use itertools::Itertools as _;
+use thiserror::Error;
+use tracing::info;
+use tracing::instrument;
+
+use super::InotifyConfig;
+
+/// Name of the daemon socket in the working-copy state directory.
+const SOCKET_FILE_NAME: &str = "fsmonitor.sock";
+
+/// Prefix of the files the daemon creates in the working-copy root to make
+/// sure all preceding events have been processed.
+const COOKIE_PREFIX: &str = ".jj-fsmonitor-cookie-";
+
+/// Directories in the working-copy root which aren't watched.
+const EXCLUDED_DIRS: [&str; 2] = [".git", ".jj"];
+
+const WATCH_MASK: WatchMask = WatchMask::ATTRIB
+    .union(WatchMask::CLOSE_WRITE)
+    .union(WatchMask::CREATE)
+    .union(WatchMask::DELETE)
+    .union(WatchMask::DELETE_SELF)
+    .union(WatchMask::MODIFY)
+    .union(WatchMask::MOVE_SELF)
+    .union(WatchMask::MOVED_FROM)
+    .union(WatchMask::MOVED_TO)
+    .union(WatchMask::DONT_FOLLOW)
+    .union(WatchMask::ONLYDIR);
+
+/// How long to wait for a newly spawned daemon to start listening.
+const DAEMON_START_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// How long the daemon waits for its own cookie file to be reported.
+const SYNC_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Represents an instant in time from the perspective of the daemon.
+///
+/// The cursor is only meaningful to the daemon instance which issued it. If
+/// the daemon has been restarted since, the next query will report a fresh
+/// instance.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Cursor {
+    instance_id: String,
+    sequence: u64,
+}
+
+impl From<crate::protos::working_copy::InotifyCursor> for Cursor {
+    fn from(cursor: crate::protos::working_copy::InotifyCursor) -> Self {
+        Self {
+            instance_id: cursor.instance_id,
+            sequence: cursor.sequence,
+        }
+    }
+}
+
+impl From<Cursor> for crate::protos::working_copy::InotifyCursor {
+    fn from(cursor: Cursor) -> Self {
+        Self {
+            instance_id: cursor.instance_id,
+            sequence: cursor.sequence,
+        }
+    }
+}
+
+/// State of a running daemon.
+#[derive(Clone, Debug)]
+pub struct DaemonStatus {
+    /// The current position in the daemon's change journal.
+    pub cursor: Cursor,
+    /// Number of directories being watched.
+    pub watched_directories: usize,
+    /// Number of changed paths the daemon currently remembers.
+    pub tracked_paths: usize,
+}
+
+#[expect(missing_docs)]
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("Failed to initialize inotify")]
+    Init(#[source] io::Error),
+
+    #[error("Failed to watch directory {path}")]
+    Watch { path: PathBuf, source: io::Error },
+
+    #[error("Failed to listen on {path}")]
+    Listen { path: PathBuf, source: io::Error },
+
+    #[error("Failed to start the filesystem monitor daemon")]
+    SpawnDaemon(#[source] io::Error),
+
+    #[error("The filesystem monitor daemon is not running")]
+    DaemonNotRunning,
+
+    #[error("Failed to communicate with the filesystem monitor daemon")]
+    Communicate(#[source] io::Error),
+
+    #[error("Unexpected response from the filesystem monitor daemon: {0}")]
+    Protocol(String),
+}
+
+/// Handle to the daemon watching a working copy.
+#[derive(Debug)]
+pub struct Fsmonitor {
+    socket_path: PathBuf,
+}
+
+impl Fsmonitor {
+    /// Connects to the daemon for the working copy, starting it if it isn't
+    /// running and `config.spawn_daemon` is set.
+    ///
+    /// The daemon is started by running `config.daemon_command`, which should
+    /// call [`run_daemon()`] with the appended arguments.
+    #[instrument]
+    pub fn init(
+        working_copy_path: &Path,
+        state_path: &Path,
+        config: &InotifyConfig,
+    ) -> Result<Self, Error> {
+        let monitor = Self {
+            socket_path: state_path.join(SOCKET_FILE_NAME),
+        };
+        if monitor.is_running() {
+            return Ok(monitor);
+        }
+        if !config.spawn_daemon || config.daemon_command.is_empty() {
+            return Err(Error::DaemonNotRunning);
+        }
+        info!("Starting inotify filesystem monitor daemon...");
+        spawn_daemon(working_copy_path, state_path, config)?;
+        // The daemon starts listening only after it has set up all watches, so
+        // no change made after this point can be missed.
+        let deadline = Instant::now() + DAEMON_START_TIMEOUT;
+        while Instant::now() < deadline {
+            thread::sleep(Duration::from_millis(10));
+            if monitor.is_running() {
+                return Ok(monitor);
+            }
+        }
+        Err(Error::DaemonNotRunning)
+    }
+
+    fn is_running(&self) -> bool {
+        UnixStream::connect(&self.socket_path).is_ok()
+    }
+
+    /// Query for changed files since the previous point in time.
+    ///
+    /// The returned list of paths is relative to the working copy root. A
+    /// path may refer to a directory, in which case anything below it may have
+    /// changed. If the list is `None`, then the caller must crawl the entire
+    /// working copy themselves.
+    #[instrument(skip(self))]
+    pub fn query_changed_files(
+        &self,
+        previous_cursor: Option<Cursor>,
+    ) -> Result<(Cursor, Option<Vec<PathBuf>>), Error> {
+        info!("Querying inotify daemon for changed files...");
+        let request = match &previous_cursor {
+            Some(cursor) => format!("query {} {}\n", cursor.instance_id, cursor.sequence),
+            None => "query\n".to_owned(),
+        };
+        let response = self.request(&request)?;
+        let (header, body) = split_header(&response)?;
+        let (kind, cursor) = match header.split(' ').collect_vec().as_slice() {
+            [kind, instance_id, sequence] => (*kind, parse_cursor(instance_id, sequence)?),
+            _ => return Err(Error::Protocol(header.to_owned())),
+        };
+        match kind {
+            "fresh" => Ok((cursor, None)),
+            "changes" => {
+                let paths = body
+                    .split(|&b| b == b'\0')
+                    .filter(|path| !path.is_empty())
+                    .map(|path| PathBuf::from(OsStr::from_bytes(path)))
+                    .collect();
+                Ok((cursor, Some(paths)))
+            }
+            _ => Err(Error::Protocol(header.to_owned())),
+        }
+    }
+
+    /// Returns the state of the running daemon.
+    #[instrument(skip(self))]
+    pub fn status(&self) -> Result<DaemonStatus, Error> {
+        let response = self.request("status\n")?;
+        let (header, _) = split_header(&response)?;
+        match header.split(' ').collect_vec().as_slice() {
+            [
+                "status",
+                instance_id,
+                sequence,
+                watched_directories,
+                tracked_paths,
+            ] => Ok(DaemonStatus {
+                cursor: parse_cursor(instance_id, sequence)?,
+                watched_directories: parse_number(watched_directories)?,
+                tracked_paths: parse_number(tracked_paths)?,
+            }),
+            _ => Err(Error::Protocol(header.to_owned())),
+        }
+    }
+
+    /// Asks the daemon to exit.
+    #[instrument(skip(self))]
+    pub fn stop(&self) -> Result<(), Error> {
+        let response = self.request("stop\n")?;
+        match split_header(&response)?.0 {
+            "ok" => Ok(()),
+            header => Err(Error::Protocol(header.to_owned())),
+        }
+    }
+
+    fn request(&self, request: &str) -> Result<Vec<u8>, Error> {
+        let mut stream = UnixStream::connect(&self.socket_path).map_err(Error::Communicate)?;
+        stream
+            .write_all(request.as_bytes())
+            .map_err(Error::Communicate)?;
+        stream
+            .shutdown(Shutdown::Write)
+            .map_err(Error::Communicate)?;
+        let mut response = Vec::new();
+        stream
+            .read_to_end(&mut response)
+            .map_err(Error::Communicate)?;
+        Ok(response)
+    }
+}
+
+fn split_header(response: &[u8]) -> Result<(&str, &[u8]), Error> {
+    let (header, body) = response
+        .split_once_str(b"\n")
+        .ok_or_else(|| Error::Protocol(String::from_utf8_lossy(response).into_owned()))?;
+    let header = str::from_utf8(header)
+        .map_err(|_| Error::Protocol(String::from_utf8_lossy(header).into_owned()))?;
+    Ok((header, body))
+}
+
+fn parse_cursor(instance_id: &str, sequence: &str) -> Result<Cursor, Error> {
+    Ok(Cursor {
+        instance_id: instance_id.to_owned(),
+        sequence: parse_number(sequence)?,
+    })
+}
+
+fn parse_number<T: str::FromStr>(value: &str) -> Result<T, Error> {
+    value
+        .parse()
+        .map_err(|_| Error::Protocol(format!("invalid number: {value}")))
+}
+
+fn spawn_daemon(
+    working_copy_path: &Path,
+    state_path: &Path,
+    config: &InotifyConfig,
+) -> Result<(), Error> {
+    let (program, args) = config.daemon_command.split_first().unwrap();
+    Command::new(program)
+        .args(args)
+        .arg(working_copy_path)
+        .arg(state_path)
+        .arg(format!("--max-tracked-paths={}", config.max_tracked_paths))
+        .current_dir(working_copy_path)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        // Don't forward Ctrl-C in the terminal to the daemon.
+        .process_group(0)
+        .spawn()
+        .map_err(Error::SpawnDaemon)?;
+    Ok(())
+}
+
+/// Runs the daemon for the working copy until it's asked to stop or the
+/// working copy root is removed.
+///
+/// Returns immediately if a daemon is already listening on the socket.
+#[instrument]
+pub fn run_daemon(
+    working_copy_path: &Path,
+    state_path: &Path,
+    max_tracked_paths: usize,
+) -> Result<(), Error> {
+    let socket_path = state_path.join(SOCKET_FILE_NAME);
+    if UnixStream::connect(&socket_path).is_ok() {
+        info!("The inotify filesystem monitor daemon is already running");
+        return Ok(());
+    }
+    let mut watcher = Watcher::new(working_copy_path)?;
+    let root_watch = watcher.root_watch();
+    let mut watches = watcher.inotify.watches();
+    let shared = Shared {
+        state: Mutex::new(DaemonState {
+            journal: Journal::new(new_instance_id(), max_tracked_paths),
+            seen_cookies: HashSet::new(),
+            watched_directories: watcher.watch_paths.len(),
+        }),
+        cookie_seen: Condvar::new(),
+    };
+    match fs::remove_file(&socket_path) {
+        Ok(()) => {}
+        Err(err) if err.kind() == io::ErrorKind::NotFound => {}
+        Err(source) => {
+            return Err(Error::Listen {
+                path: socket_path,
+                source,
+            });
+        }
+    }
+    let listener = UnixListener::bind(&socket_path).map_err(|source| Error::Listen {
+        path: socket_path.clone(),
+        source,
+    })?;
+    info!(
+        ?socket_path,
+        "The inotify filesystem monitor daemon is listening"
+    );
+    thread::scope(|s| {
+        s.spawn(|| {
+            watcher.run(&shared);
+            // Wake up the listener if the working copy has gone away.
+            if let Ok(mut stream) = UnixStream::connect(&socket_path) {
+                stream.write_all(b"stop\n").ok();
+            }
+        });
+        serve(&listener, &shared, working_copy_path);
+        // Removing the root watch makes the watcher thread exit.
+        if let Some(wd) = root_watch {
+            watches.remove(wd).ok();
+        }
+    });
+    fs::remove_file(&socket_path).ok();
+    Ok(())
+}
+
+fn new_instance_id() -> String {
+    let nanos = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_nanos();
+    format!("{}-{nanos}", process::id())
+}
+
+/// Paths changed since the daemon started.
+#[derive(Debug)]
+struct Journal {
+    instance_id: String,
+    /// Incremented for every recorded change.
+    sequence: u64,
+    /// Changed paths and the sequence number of their latest change.
+    changed_paths: HashMap<PathBuf, u64>,
+    /// Changes before this sequence number have been forgotten.
+    oldest_sequence: u64,
+    max_tracked_paths: usize,
+}
+
+impl Journal {
+    fn new(instance_id: String, max_tracked_paths: usize) -> Self {
+        Self {
+            instance_id,
+            sequence: 0,
+            changed_paths: HashMap::new(),
+            oldest_sequence: 0,
+            max_tracked_paths,
+        }
+    }
+
+    fn cursor(&self) -> Cursor {
+        Cursor {
+            instance_id: self.instance_id.clone(),
+            sequence: self.sequence,
+        }
+    }
+
+    fn record(&mut self, path: PathBuf) {
+        self.sequence += 1;
+        self.changed_paths.insert(path, self.sequence);
+        if self.changed_paths.len() > self.max_tracked_paths {
+            self.overflow();
+        }
+    }
+
+    /// Forgets all changes, so that queries from earlier cursors will crawl
+    /// the entire working copy.
+    fn overflow(&mut self) {
+        self.sequence += 1;
+        self.changed_paths.clear();
+        self.oldest_sequence = self.sequence;
+    }
+
+    /// Returns the paths changed after the `cursor`, or `None` if they aren't
+    /// known.
+    fn changes_since(&self, cursor: Option<&Cursor>) -> Option<Vec<PathBuf>> {
+        let cursor = cursor?;
+        if cursor.instance_id != self.instance_id
+            || cursor.sequence < self.oldest_sequence
+            || cursor.sequence > self.sequence
+        {
+            return None;
+        }
+        let paths = self
+            .changed_paths
+            .iter()
+            .filter(|&(_, &sequence)| sequence > cursor.sequence)
+            .map(|(path, _)| path.clone())
+            .sorted_unstable()
+            .collect();
+        Some(paths)
+    }
+}
+
+#[derive(Debug)]
+struct DaemonState {
+    journal: Journal,
+    /// Cookie files reported by inotify but not yet seen by the listener.
+    seen_cookies: HashSet<OsString>,
+    watched_directories: usize,
+}
+
+#[derive(Debug)]
+struct Shared {
+    state: Mutex<DaemonState>,
+    cookie_seen: Condvar,
+}
+
+struct Watcher {
+    root: PathBuf,
+    inotify: Inotify,
+    /// Watched directories relative to the root.
+    watch_paths: HashMap<WatchDescriptor, PathBuf>,
+}
+
+impl Watcher {
+    fn new(root: &Path) -> Result<Self, Error> {
+        let inotify = Inotify::init().map_err(Error::Init)?;
+        let mut watcher = Self {
+            root: root.to_owned(),
+            inotify,
+            watch_paths: HashMap::new(),
+        };
+        watcher
+            .add_watches(Path::new(""))
+            .map_err(|source| Error::Watch {
+                path: root.to_owned(),
+                source,
+            })?;
+        Ok(watcher)
+    }
+
+    fn root_watch(&self) -> Option<WatchDescriptor> {
+        self.watch_paths
+            .iter()
+            .find(|(_, path)| path.as_os_str().is_empty())
+            .map(|(wd, _)| wd.clone())
+    }
+
+    /// Watches `dir` and all directories below it.
+    fn add_watches(&mut self, dir: &Path) -> io::Result<()> {
+        let mut pending = vec![dir.to_owned()];
+        while let Some(dir) = pending.pop() {
+            let disk_dir = self.root.join(&dir);
+            // Add the watch before listing the directory so that no entry
+            // created in between is missed.
+            let wd = match self.inotify.watches().add(&disk_dir, WATCH_MASK) {
+                Ok(wd) => wd,
+                // The directory was removed or replaced in the meantime.
+                Err(err)
+                    if matches!(
+                        err.kind(),
+                        io::ErrorKind::NotFound | io::ErrorKind::NotADirectory
+                    ) =>
+                {
+                    continue;
+                }
+                Err(err) => return Err(err),
+            };
+            let entries = match fs::read_dir(&disk_dir) {
+                Ok(entries) => entries,
+                Err(err) if err.kind() == io::ErrorKind::NotFound => continue,
+                Err(err) => return Err(err),
+            };
+            for entry in entries {
+                let entry = entry?;
+                if !entry.file_type()?.is_dir() {
+                    continue;
+                }
+                let name = entry.file_name();
+                if dir.as_os_str().is_empty() && EXCLUDED_DIRS.iter().any(|&ex| name == ex) {
+                    continue;
+                }
+                pending.push(dir.join(name));
+            }
+            self.watch_paths.insert(wd, dir);
+        }
+        Ok(())
+    }
+
+    /// Stops watching `dir` and all directories below it.
+    fn remove_watches(&mut self, dir: &Path) {
+        let wds = self
+            .watch_paths
+            .iter()
+            .filter(|(_, path)| path.starts_with(dir))
+            .map(|(wd, _)| wd.clone())
+            .collect_vec();
+        for wd in wds {
+            self.watch_paths.remove(&wd);
+            self.inotify.watches().remove(wd).ok();
+        }
+    }
+
+    fn run(&mut self, shared: &Shared) {
+        let mut buffer = vec![0; 64 * 1024];
+        loop {
+            let events = match self.inotify.read_events_blocking(&mut buffer) {
+                Ok(events) => events,
+                Err(err) => {
+                    tracing::error!(?err, "Failed to read inotify events");
+                    return;
+                }
+            };
+            let mut state = shared.state.lock().unwrap();
+            let mut keep_running = true;
+            for event in events {
+                keep_running &= self.handle_event(&event, &mut state);
+            }
+            state.watched_directories = self.watch_paths.len();
+            drop(state);
+            shared.cookie_seen.notify_all();
+            if !keep_running {
+                info!("The working copy root is gone, stopping");
+                return;
+            }
+        }
+    }
+
+    /// Records the event in the journal. Returns `false` if the working copy
+    /// root is no longer watched.
+    fn handle_event(&mut self, event: &inotify::Event<&OsStr>, state: &mut DaemonState) -> bool {
+        if event.mask.contains(EventMask::Q_OVERFLOW) {
+            info!("The inotify event queue overflowed");
+            state.journal.overflow();
+            // Directories created in the meantime might not be watched.
+            let wds = self.watch_paths.drain().map(|(wd, _)| wd).collect_vec();
+            for wd in wds {
+                self.inotify.watches().remove(wd).ok();
+            }
+            return self.add_watches(Path::new("")).is_ok();
+        }
+        let Some(dir) = self.watch_paths.get(&event.wd).cloned() else {
+            return true;
+        };
+        let is_root = dir.as_os_str().is_empty();
+        if event.mask.contains(EventMask::IGNORED) {
+            self.watch_paths.remove(&event.wd);
+            return !is_root;
+        }
+        let Some(name) = event.name else {
+            // The watched directory itself was deleted or moved. The event for
+            // its parent directory will record the change.
+            return !is_root;
+        };
+        if is_root {
+            if EXCLUDED_DIRS.iter().any(|&ex| name == ex) {
+                return true;
+            }
+            if name.as_bytes().starts_with(COOKIE_PREFIX.as_bytes()) {
+                if event.mask.contains(EventMask::CREATE) {
+                    state.seen_cookies.insert(name.to_owned());
+                }
+                return true;
+            }
+        }
+        let path = dir.join(name);
+        if event.mask.contains(EventMask::ISDIR) {
+            if event
+                .mask
+                .intersects(EventMask::CREATE | EventMask::MOVED_TO)
+            {
+                if let Err(err) = self.add_watches(&path) {
+                    tracing::warn!(?err, ?path, "Failed to watch directory");
+                    state.journal.overflow();
+                }
+            } else if event.mask.contains(EventMask::MOVED_FROM) {
+                self.remove_watches(&path);
+            }
+        }
+        state.journal.record(path);
+        true
+    }
+}
+
+fn serve(listener: &UnixListener, shared: &Shared, root: &Path) {
+    let mut cookie_counter = 0;
+    for stream in listener.incoming() {
+        let result =
+            stream.and_then(|stream| handle_client(&stream, shared, root, &mut cookie_counter));
+        match result {
+            Ok(ControlFlow::Continue(())) => {}
+            Ok(ControlFlow::Break(())) => return,
+            Err(err) => tracing::warn!(?err, "Failed to serve filesystem monitor client"),
+        }
+    }
+}
+
+fn handle_client(
+    stream: &UnixStream,
+    shared: &Shared,
+    root: &Path,
+    cookie_counter: &mut u64,
+) -> io::Result<ControlFlow<()>> {
+    let mut line = String::new();
+    BufReader::new(stream).read_line(&mut line)?;
+    let mut writer = stream;
+    match line.split_whitespace().collect_vec().as_slice() {
+        // Connection check
+        [] => {}
+        ["query", previous_cursor @ ..] => {
+            let previous_cursor = match previous_cursor {
+                [instance_id, sequence] => parse_cursor(instance_id, sequence).ok(),
+                _ => None,
+            };
+            let synced = sync_with_watcher(shared, root, cookie_counter);
+            let state = shared.state.lock().unwrap();
+            let cursor = state.journal.cursor();
+            let changed_paths = synced
+                .then(|| state.journal.changes_since(previous_cursor.as_ref()))
+                .flatten();
+            drop(state);
+            let mut response = Vec::new();
+            match changed_paths {
+                None => {
+                    writeln!(response, "fresh {} {}", cursor.instance_id, cursor.sequence)?;
+                }
+                Some(paths) => {
+                    writeln!(
+                        response,
+                        "changes {} {}",
+                        cursor.instance_id, cursor.sequence
+                    )?;
+                    for path in paths {
+                        response.extend_from_slice(path.as_os_str().as_bytes());
+                        response.push(b'\0');
+                    }
+                }
+            }
+            writer.write_all(&response)?;
+        }
+        ["status"] => {
+            let state = shared.state.lock().unwrap();
+            let cursor = state.journal.cursor();
+            writeln!(
+                writer,
+                "status {} {} {} {}",
+                cursor.instance_id,
+                cursor.sequence,
+                state.watched_directories,
+                state.journal.changed_paths.len()
+            )?;
+        }
+        ["stop"] => {
+            writeln!(writer, "ok")?;
+            return Ok(ControlFlow::Break(()));
+        }
+        _ => {
+            writeln!(writer, "error unknown command")?;
+        }
+    }
+    Ok(ControlFlow::Continue(()))
+}
+
+/// Makes sure that the watcher has processed all events preceding this call
+/// by creating a cookie file and waiting for it to be reported.
+fn sync_with_watcher(shared: &Shared, root: &Path, cookie_counter: &mut u64) -> bool {
+    *cookie_counter += 1;
+    let name = OsString::from(format!("{COOKIE_PREFIX}{}-{cookie_counter}", process::id()));
+    let path = root.join(&name);
+    if let Err(err) = File::create(&path) {
+        tracing::warn!(?err, ?path, "Failed to create cookie file");
+        return false;
+    }
+    let state = shared.state.lock().unwrap();
+    let (mut state, _) = shared
+        .cookie_seen
+        .wait_timeout_while(state, SYNC_TIMEOUT, |state| {
+            !state.seen_cookies.contains(&name)
+        })
+        .unwrap();
+    let seen = state.seen_cookies.remove(&name);
+    drop(state);
+    fs::remove_file(&path).ok();
+    seen
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cursor(instance_id: &str, sequence: u64) -> Cursor {
+        Cursor {
+            instance_id: instance_id.to_owned(),
+            sequence,
+        }
+    }
+
+    #[test]
+    fn test_journal_changes_since() {
+        let mut journal = Journal::new("a".to_owned(), 10);
+        assert_eq!(journal.changes_since(None), None);
+        assert_eq!(journal.changes_since(Some(&cursor("a", 0))), Some(vec![]));
+
+        journal.record(PathBuf::from("foo"));
+        journal.record(PathBuf::from("bar"));
+        let first = journal.cursor();
+        journal.record(PathBuf::from("foo"));
+        journal.record(PathBuf::from("dir/baz"));
+        assert_eq!(
+            journal.changes_since(Some(&cursor("a", 0))),
+            Some(vec![
+                PathBuf::from("bar"),
+                PathBuf::from("dir/baz"),
+                PathBuf::from("foo"),
+            ])
+        );
+        assert_eq!(
+            journal.changes_since(Some(&first)),
+            Some(vec![PathBuf::from("dir/baz"), PathBuf::from("foo")])
+        );
+        assert_eq!(journal.changes_since(Some(&journal.cursor())), Some(vec![]));
+
+        // Cursors from another instance or from the future aren't trusted
+        assert_eq!(journal.changes_since(Some(&cursor("b", 1))), None);
+        assert_eq!(journal.changes_since(Some(&cursor("a", 100))), None);
+    }
+
+    #[test]
+    fn test_journal_overflow() {
+        let mut journal = Journal::new("a".to_owned(), 2);
+        let start = journal.cursor();
+        journal.record(PathBuf::from("a"));
+        journal.record(PathBuf::from("b"));
+        assert_eq!(journal.changes_since(Some(&start)).unwrap().len(), 2);
+
+        journal.record(PathBuf::from("c"));
+        assert_eq!(journal.changes_since(Some(&start)), None);
+        let after_overflow = journal.cursor();
+        journal.record(PathBuf::from("d"));
+        assert_eq!(
+            journal.changes_since(Some(&after_overflow)),
+            Some(vec![PathBuf::from("d")])
+        );
+    }
+
+    #[test]
+    fn test_daemon() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let root = temp_dir.path().join("repo");
+        let state_path = temp_dir.path().join("state");
+        fs::create_dir_all(root.join("existing/dir")).unwrap();
+        fs::create_dir_all(root.join(".jj")).unwrap();
+        fs::create_dir(&state_path).unwrap();
+        let config = InotifyConfig {
+            spawn_daemon: false,
+            daemon_command: vec![],
+            max_tracked_paths: 100,
+        };
+
+        thread::scope(|s| {
+            let daemon = s.spawn(|| run_daemon(&root, &state_path, config.max_tracked_paths));
+            let deadline = Instant::now() + DAEMON_START_TIMEOUT;
+            let monitor = loop {
+                match Fsmonitor::init(&root, &state_path, &config) {
+                    Ok(monitor) => break monitor,
+                    Err(Error::DaemonNotRunning) if Instant::now() < deadline => {
+                        thread::sleep(Duration::from_millis(10));
+                    }
+                    Err(err) => panic!("daemon didn't start: {err}"),
+                }
+            };
+
+            // The first query has no cursor to compare against
+            let (cursor, changed) = monitor.query_changed_files(None).unwrap();
+            assert_eq!(changed, None);
+
+            fs::write(root.join("file"), "contents").unwrap();
+            fs::write(root.join("existing/dir/file"), "contents").unwrap();
+            fs::write(root.join(".jj/ignored"), "contents").unwrap();
+            let (cursor, changed) = monitor.query_changed_files(Some(cursor)).unwrap();
+            assert_eq!(
+                changed,
+                Some(vec![
+                    PathBuf::from("existing/dir/file"),
+                    PathBuf::from("file"),
+                ])
+            );
+
+            // New directories are watched as well
+            fs::create_dir_all(root.join("new/nested")).unwrap();
+            // "new/nested" may be reported separately if it was created after
+            // the watch for "new" was added.
+            let (cursor, changed) = monitor.query_changed_files(Some(cursor)).unwrap();
+            let changed = changed.unwrap();
+            assert!(changed.contains(&PathBuf::from("new")));
+            assert!(changed.iter().all(|path| path.starts_with("new")));
+            fs::write(root.join("new/nested/file"), "contents").unwrap();
+            let (cursor, changed) = monitor.query_changed_files(Some(cursor)).unwrap();
+            assert_eq!(changed, Some(vec![PathBuf::from("new/nested/file")]));
+
+            // Watches follow renamed directories
+            fs::rename(root.join("new"), root.join("renamed")).unwrap();
+            let (cursor, changed) = monitor.query_changed_files(Some(cursor)).unwrap();
+            assert_eq!(
+                changed,
+                Some(vec![PathBuf::from("new"), PathBuf::from("renamed")])
+            );
+            fs::write(root.join("renamed/nested/file"), "updated").unwrap();
+            let (_cursor, changed) = monitor.query_changed_files(Some(cursor)).unwrap();
+            assert_eq!(changed, Some(vec![PathBuf::from("renamed/nested/file")]));
+
+            let status = monitor.status().unwrap();
+            assert_eq!(status.watched_directories, 5);
+
+            monitor.stop().unwrap();
+            daemon.join().unwrap().unwrap();
+        });
+        assert!(!state_path.join(SOCKET_FILE_NAME).exists());
+    }
+}
@@ -0,0 +1,1265 @@
+// Copyright 2025 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Working copy served by a FUSE filesystem (Linux only).
+//!
+//! Writing out every file of a huge repo takes a long time. Instead, the FUSE
+//! working copy mounts a filesystem on the workspace root, which serves the
+//! files of the checked-out tree straight from the backend. Files written
+//! through the mount are stored in an overlay directory within the
+//! working-copy state directory, and removed paths are recorded as
+//! "whiteouts". A snapshot only has to look at the overlay, and a checkout only
+//! swaps the tree the files are served from, so both take time proportional to
+//! the changes.
+//!
+//! The overlay of each base tree lives in its own "generation" directory. A
+//! checkout starts a new generation, and the daemon serving the mount switches
+//! to it when it notices that the state file has changed.
+//!
+//! The mount hides the `.jj` directory of the workspace, so it has to be a
+//! symlink to a directory outside the workspace. The filesystem serves the same
+//! symlink.
+
+#![allow(missing_docs)]
+
+pub mod daemon;
+
+use std::any::Any;
+use std::collections::BTreeSet;
+use std::collections::HashMap;
+use std::fs;
+use std::fs::File;
+use std::fs::OpenOptions;
+use std::io;
+use std::io::Write as _;
+use std::os::unix::fs::PermissionsExt as _;
+use std::path::Path;
+use std::path::PathBuf;
+use std::str;
+use std::sync::Arc;
+
+use futures::StreamExt as _;
+use once_cell::unsync::OnceCell;
+use pollster::FutureExt as _;
+use prost::Message as _;
+use tempfile::NamedTempFile;
+use thiserror::Error;
+use tokio::io::AsyncReadExt as _;
+use tracing::instrument;
+
+use crate::backend::BackendError;
+use crate::backend::CopyId;
+use crate::backend::MergedTreeId;
+use crate::backend::TreeId;
+use crate::backend::TreeValue;
+use crate::commit::Commit;
+use crate::conflicts;
+use crate::conflicts::ConflictMarkerStyle;
+use crate::conflicts::MIN_CONFLICT_MARKER_LEN;
+use crate::conflicts::MaterializedTreeValue;
+use crate::conflicts::choose_materialized_conflict_marker_len;
+use crate::conflicts::materialize_merge_result_to_bytes_with_marker_len;
+use crate::conflicts::materialize_tree_value;
+use crate::file_util::BlockingAsyncReader;
+use crate::gitignore::GitIgnoreFile;
+use crate::lock::FileLock;
+use crate::matchers::EverythingMatcher;
+use crate::matchers::PrefixMatcher;
+use crate::merge::Merge;
+use crate::merge::MergeBuilder;
+use crate::merge::MergedTreeValue;
+use crate::merged_tree::MergedTree;
+use crate::merged_tree::MergedTreeBuilder;
+use crate::object_id::ObjectId as _;
+use crate::op_store::OperationId;
+use crate::ref_name::WorkspaceName;
+use crate::ref_name::WorkspaceNameBuf;
+use crate::repo_path::RepoPath;
+use crate::repo_path::RepoPathBuf;
+use crate::repo_path::RepoPathComponent;
+use crate::settings::UserSettings;
+use crate::sparse::SparseMode;
+use crate::store::Store;
+use crate::working_copy::CheckoutError;
+use crate::working_copy::CheckoutOptions;
+use crate::working_copy::CheckoutStats;
+use crate::working_copy::LockedWorkingCopy;
+use crate::working_copy::ResetError;
+use crate::working_copy::SnapshotError;
+use crate::working_copy::SnapshotOptions;
+use crate::working_copy::SnapshotRuleAction;
+use crate::working_copy::SnapshotRuleCheck;
+use crate::working_copy::SnapshotStats;
+use crate::working_copy::SnapshotWarning;
+use crate::working_copy::UntrackedReason;
+use crate::working_copy::WorkingCopy;
+use crate::working_copy::WorkingCopyFactory;
+use crate::working_copy::WorkingCopyStateError;
+
+/// Name of the file in the state directory recording the trees and the
+/// current generation.
+const STATE_FILE_NAME: &str = "fuse_state";
+
+/// Directory in the state directory containing one overlay per generation.
+const OVERLAY_DIR_NAME: &str = "overlay";
+
+/// Directory in an overlay containing the added and modified files.
+const UPPER_DIR_NAME: &str = "files";
+
+/// File in an overlay listing the removed paths, separated by NUL bytes.
+const WHITEOUTS_FILE_NAME: &str = "whiteouts";
+
+/// Names which are never snapshotted from the root of the overlay.
+const RESERVED_DIR_NAMES: &[&str] = &[".git", ".jj"];
+
+/// Error while reading or writing the state of a FUSE working copy.
+#[derive(Debug, Error)]
+pub enum FuseStateError {
+    #[error("Reading FUSE working copy state from {path}")]
+    Read { path: PathBuf, source: io::Error },
+    #[error("Decoding FUSE working copy state from {path}")]
+    Decode {
+        path: PathBuf,
+        source: prost::DecodeError,
+    },
+    #[error("Writing FUSE working copy state to {path}")]
+    Write { path: PathBuf, source: io::Error },
+}
+
+/// Working copy state stored in the "fuse_state" file.
+#[derive(Clone, Debug)]
+struct FuseState {
+    /// The tree the overlay applies to.
+    base_tree_id: MergedTreeId,
+    /// The tree at the last snapshot or checkout. Files which aren't in it are
+    /// untracked.
+    tree_id: MergedTreeId,
+    generation: u64,
+}
+
+impl FuseState {
+    fn load(state_path: &Path) -> Result<Self, FuseStateError> {
+        let path = state_path.join(STATE_FILE_NAME);
+        let buf = fs::read(&path).map_err(|source| FuseStateError::Read {
+            path: path.clone(),
+            source,
+        })?;
+        let proto = crate::protos::working_copy::FuseState::decode(&*buf)
+            .map_err(|source| FuseStateError::Decode { path, source })?;
+        Ok(Self {
+            base_tree_id: tree_id_from_proto(&proto.base_tree_ids),
+            tree_id: tree_id_from_proto(&proto.tree_ids),
+            generation: proto.generation,
+        })
+    }
+
+    fn save(&self, state_path: &Path) -> Result<(), FuseStateError> {
+        let proto = crate::protos::working_copy::FuseState {
+            base_tree_ids: tree_id_to_proto(&self.base_tree_id),
+            tree_ids: tree_id_to_proto(&self.tree_id),
+            generation: self.generation,
+        };
+        let path = state_path.join(STATE_FILE_NAME);
+        let write_error = |source| FuseStateError::Write {
+            path: path.clone(),
+            source,
+        };
+        let mut temp_file = NamedTempFile::new_in(state_path).map_err(write_error)?;
+        temp_file
+            .as_file_mut()
+            .write_all(&proto.encode_to_vec())
+            .map_err(write_error)?;
+        temp_file
+            .persist(&path)
+            .map_err(|err| write_error(err.error))?;
+        Ok(())
+    }
+
+    fn overlay(&self, state_path: &Path) -> Overlay {
+        Overlay::new(state_path, self.generation)
+    }
+}
+
+fn tree_id_from_proto(tree_ids: &[Vec<u8>]) -> MergedTreeId {
+    let tree_ids_builder: MergeBuilder<TreeId> =
+        tree_ids.iter().map(|id| TreeId::new(id.clone())).collect();
+    MergedTreeId::Merge(tree_ids_builder.build())
+}
+
+fn tree_id_to_proto(tree_id: &MergedTreeId) -> Vec<Vec<u8>> {
+    tree_id.to_merge().iter().map(|id| id.to_bytes()).collect()
+}
+
+/// Changes on top of the base tree of one generation.
+#[derive(Clone, Debug)]
+struct Overlay {
+    dir: PathBuf,
+}
+
+impl Overlay {
+    fn new(state_path: &Path, generation: u64) -> Self {
+        Self {
+            dir: state_path
+                .join(OVERLAY_DIR_NAME)
+                .join(generation.to_string()),
+        }
+    }
+
+    fn create(&self) -> io::Result<()> {
+        fs::create_dir_all(self.upper_dir())
+    }
+
+    fn upper_dir(&self) -> PathBuf {
+        self.dir.join(UPPER_DIR_NAME)
+    }
+
+    /// Path of the overlay entry for `path`, which may not exist.
+    fn disk_path(&self, path: &RepoPath) -> PathBuf {
+        path.to_fs_path_unchecked(&self.upper_dir())
+    }
+
+    /// Returns true if `path` is hidden by a file of the overlay at one of its
+    /// ancestors.
+    fn is_shadowed(&self, path: &RepoPath) -> bool {
+        path.ancestors().skip(1).any(|ancestor| {
+            fs::symlink_metadata(self.disk_path(ancestor)).is_ok_and(|metadata| !metadata.is_dir())
+        })
+    }
+
+    fn read_whiteouts(&self) -> io::Result<BTreeSet<RepoPathBuf>> {
+        let content = match fs::read(self.dir.join(WHITEOUTS_FILE_NAME)) {
+            Ok(content) => content,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(BTreeSet::new()),
+            Err(err) => return Err(err),
+        };
+        content
+            .split(|b| *b == b'\0')
+            .filter(|entry| !entry.is_empty())
+            .map(|entry| {
+                let path = str::from_utf8(entry)
+                    .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+                RepoPathBuf::from_internal_string(path)
+                    .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+            })
+            .collect()
+    }
+
+    fn write_whiteouts(&self, whiteouts: &BTreeSet<RepoPathBuf>) -> io::Result<()> {
+        let mut temp_file = NamedTempFile::new_in(&self.dir)?;
+        for path in whiteouts {
+            temp_file.write_all(path.as_internal_file_string().as_bytes())?;
+            temp_file.write_all(b"\0")?;
+        }
+        temp_file
+            .persist(self.dir.join(WHITEOUTS_FILE_NAME))
+            .map_err(|err| err.error)?;
+        Ok(())
+    }
+}
+
+/// Returns true if `path` or one of its ancestors was removed from the base
+/// tree.
+fn is_whited_out(whiteouts: &BTreeSet<RepoPathBuf>, path: &RepoPath) -> bool {
+    path.ancestors()
+        .any(|ancestor| whiteouts.contains(ancestor))
+}
+
+/// Returns the content to serve for a file, symlink, or conflict of the base
+/// tree, or `None` if there's nothing to serve.
+async fn materialize_base_value(
+    store: &Store,
+    path: &RepoPath,
+    value: MergedTreeValue,
+    conflict_marker_style: ConflictMarkerStyle,
+) -> Result<Option<MaterializedBaseFile>, BackendError> {
+    let file = match materialize_tree_value(store, path, value).await? {
+        MaterializedTreeValue::Absent
+        | MaterializedTreeValue::AccessDenied(_)
+        | MaterializedTreeValue::GitSubmodule(_)
+        | MaterializedTreeValue::Tree(_) => return Ok(None),
+        MaterializedTreeValue::File(mut file) => MaterializedBaseFile {
+            is_symlink: false,
+            executable: file.executable,
+            content: file.read_all(path).await?,
+        },
+        MaterializedTreeValue::Symlink { id: _, target } => MaterializedBaseFile {
+            is_symlink: true,
+            executable: false,
+            content: target.into_bytes(),
+        },
+        MaterializedTreeValue::FileConflict(file) => {
+            let conflict_marker_len = choose_materialized_conflict_marker_len(&file.contents);
+            MaterializedBaseFile {
+                is_symlink: false,
+                executable: file.executable.unwrap_or(false),
+                content: materialize_merge_result_to_bytes_with_marker_len(
+                    &file.contents,
+                    conflict_marker_style,
+                    conflict_marker_len,
+                )
+                .into(),
+            }
+        }
+        MaterializedTreeValue::OtherConflict { id } => MaterializedBaseFile {
+            is_symlink: false,
+            executable: false,
+            content: id.describe().into_bytes(),
+        },
+    };
+    Ok(Some(file))
+}
+
+/// File served from the base tree.
+#[derive(Debug)]
+struct MaterializedBaseFile {
+    is_symlink: bool,
+    executable: bool,
+    /// File content, or symlink target.
+    content: Vec<u8>,
+}
+
+/// Records the files of the overlay in a new tree.
+struct OverlaySnapshotter<'a> {
+    store: &'a Arc<Store>,
+    working_copy_path: &'a Path,
+    options: &'a SnapshotOptions<'a>,
+    base_tree: MergedTree,
+    /// The tree at the last snapshot, which decides which files are tracked.
+    current_tree: MergedTree,
+    overlay: &'a Overlay,
+    whiteouts: BTreeSet<RepoPathBuf>,
+    tree_builder: MergedTreeBuilder,
+    git_ignores: HashMap<RepoPathBuf, Arc<GitIgnoreFile>>,
+    stats: SnapshotStats,
+}
+
+impl OverlaySnapshotter<'_> {
+    fn snapshot(mut self) -> Result<(MergedTreeId, SnapshotStats), SnapshotError> {
+        for path in self.whiteouts.clone() {
+            self.remove_base_entries(&path)?;
+        }
+        let upper_dir = self.overlay.upper_dir();
+        self.visit_directory(RepoPath::root(), &upper_dir)?;
+        let tree_id = self.tree_builder.write_tree(self.store)?;
+        Ok((tree_id, self.stats))
+    }
+
+    fn remove_base_entries(&mut self, path: &RepoPath) -> Result<(), SnapshotError> {
+        let value = self.base_tree.path_value(path)?;
+        if value.is_tree() {
+            // Removing the tree entry alone would resurrect the other entries of
+            // the directory if the overlay adds new files to it.
+            let matcher = PrefixMatcher::new([path]);
+            for (entry_path, _) in self.base_tree.entries_matching(&matcher) {
+                self.tree_builder.set_or_remove(entry_path, Merge::absent());
+            }
+        } else if value.is_present() {
+            self.tree_builder
+                .set_or_remove(path.to_owned(), Merge::absent());
+        }
+        Ok(())
+    }
+
+    fn visit_directory(&mut self, dir: &RepoPath, disk_dir: &Path) -> Result<(), SnapshotError> {
+        let git_ignore = self.git_ignore_for_dir(dir)?;
+        let entries = fs::read_dir(disk_dir).map_err(|err| SnapshotError::Other {
+            message: format!("Failed to read directory {}", disk_dir.display()),
+            err: err.into(),
+        })?;
+        for entry in entries {
+            let entry = entry.map_err(|err| SnapshotError::Other {
+                message: format!("Failed to read directory {}", disk_dir.display()),
+                err: err.into(),
+            })?;
+            let name = entry
+                .file_name()
+                .into_string()
+                .map_err(|path| SnapshotError::InvalidUtf8Path { path })?;
+            if dir.is_root() && RESERVED_DIR_NAMES.contains(&name.as_str()) {
+                continue;
+            }
+            let path = dir.join(RepoPathComponent::new(&name).unwrap());
+            let file_type = entry.file_type().map_err(|err| SnapshotError::Other {
+                message: format!("Failed to stat file {}", entry.path().display()),
+                err: err.into(),
+            })?;
+            let current_value = self.current_tree.path_value(&path)?;
+            if file_type.is_dir() {
+                // Paths in an ignored directory can't become tracked, and
+                // .gitignore files in it don't apply.
+                if current_value.is_tree() || !git_ignore.matches(&path.to_internal_dir_string()) {
+                    self.visit_directory(&path, &entry.path())?;
+                }
+                continue;
+            }
+            if let Some(progress) = self.options.progress {
+                progress(&path);
+            }
+            let is_new = current_value.is_absent() || current_value.is_tree();
+            let mut warning = None;
+            if is_new {
+                if git_ignore.matches(path.as_internal_file_string()) {
+                    continue;
+                }
+                if !self.options.start_tracking_matcher.matches(&path) {
+                    self.stats
+                        .untracked_paths
+                        .insert(path, UntrackedReason::FileNotAutoTracked);
+                    continue;
+                }
+                let metadata = entry.metadata().map_err(|err| SnapshotError::Other {
+                    message: format!("Failed to stat file {}", entry.path().display()),
+                    err: err.into(),
+                })?;
+                let size = metadata.len();
+                let max_size = self.options.max_new_file_size;
+                let reason = match self.options.snapshot_rules.check(&path, size) {
+                    SnapshotRuleCheck::Unmatched if size > max_size => {
+                        Some(UntrackedReason::FileTooLarge { size, max_size })
+                    }
+                    SnapshotRuleCheck::Unmatched | SnapshotRuleCheck::Allowed => None,
+                    SnapshotRuleCheck::Applied(rule) => match rule.action {
+                        SnapshotRuleAction::Warn => {
+                            warning = Some(SnapshotWarning {
+                                rule: rule.name.clone(),
+                                size,
+                            });
+                            None
+                        }
+                        SnapshotRuleAction::Ignore => Some(UntrackedReason::IgnoredByRule {
+                            rule: rule.name.clone(),
+                        }),
+                        SnapshotRuleAction::Refuse => Some(UntrackedReason::RefusedByRule {
+                            rule: rule.name.clone(),
+                            size,
+                        }),
+                    },
+                };
+                if let Some(reason) = reason {
+                    self.stats.untracked_paths.insert(path, reason);
+                    continue;
+                }
+            }
+            let new_value = if file_type.is_symlink() {
+                self.write_symlink_to_store(&path, &entry.path())
+                    .block_on()?
+            } else if file_type.is_file() {
+                self.write_file_to_store(&path, &entry.path(), &current_value)
+                    .block_on()?
+            } else {
+                // Special files aren't considered present
+                continue;
+            };
+            if let Some(warning) = warning {
+                self.stats.warned_paths.insert(path.clone(), warning);
+            }
+            self.tree_builder.set_or_remove(path, new_value);
+        }
+        Ok(())
+    }
+
+    /// Returns the ignore patterns applying to the files in `dir`, including
+    /// the `.gitignore` file in it.
+    fn git_ignore_for_dir(&mut self, dir: &RepoPath) -> Result<Arc<GitIgnoreFile>, SnapshotError> {
+        if let Some(git_ignore) = self.git_ignores.get(dir) {
+            return Ok(git_ignore.clone());
+        }
+        let parent_ignore = match dir.parent() {
+            Some(parent) => self.git_ignore_for_dir(parent)?,
+            None => self.options.base_ignores.clone(),
+        };
+        let ignore_path = dir.join(RepoPathComponent::new(".gitignore").unwrap());
+        let git_ignore = match self.read_visible_file(&ignore_path)? {
+            Some(content) => parent_ignore.chain(
+                &dir.to_internal_dir_string(),
+                &ignore_path.to_fs_path_unchecked(self.working_copy_path),
+                &content,
+            )?,
+            None => parent_ignore,
+        };
+        self.git_ignores.insert(dir.to_owned(), git_ignore.clone());
+        Ok(git_ignore)
+    }
+
+    /// Reads the regular file at `path` as seen through the mount.
+    fn read_visible_file(&self, path: &RepoPath) -> Result<Option<Vec<u8>>, SnapshotError> {
+        let disk_path = self.overlay.disk_path(path);
+        match fs::symlink_metadata(&disk_path) {
+            Ok(metadata) if metadata.is_file() => {
+                let content = fs::read(&disk_path).map_err(|err| SnapshotError::Other {
+                    message: format!("Failed to read file {}", disk_path.display()),
+                    err: err.into(),
+                })?;
+                return Ok(Some(content));
+            }
+            Ok(_) => return Ok(None),
+            Err(_) => {}
+        }
+        if is_whited_out(&self.whiteouts, path) {
+            return Ok(None);
+        }
+        match self.base_tree.path_value(path)?.into_resolved() {
+            Ok(Some(TreeValue::File { id, .. })) => {
+                let mut content = vec![];
+                self.store
+                    .read_file(path, &id)
+                    .block_on()?
+                    .read_to_end(&mut content)
+                    .block_on()
+                    .map_err(|err| SnapshotError::Other {
+                        message: format!("Failed to read file {}", path.as_internal_file_string()),
+                        err: err.into(),
+                    })?;
+                Ok(Some(content))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    async fn write_file_to_store(
+        &self,
+        path: &RepoPath,
+        disk_path: &Path,
+        current_value: &MergedTreeValue,
+    ) -> Result<MergedTreeValue, SnapshotError> {
+        let metadata = fs::metadata(disk_path).map_err(|err| SnapshotError::Other {
+            message: format!("Failed to stat file {}", disk_path.display()),
+            err: err.into(),
+        })?;
+        let executable = metadata.permissions().mode() & 0o111 != 0;
+        if let Some(current_value) = current_value.as_resolved() {
+            let file = File::open(disk_path).map_err(|err| SnapshotError::Other {
+                message: format!("Failed to open file {}", disk_path.display()),
+                err: err.into(),
+            })?;
+            let id = self
+                .store
+                .write_file(path, &mut BlockingAsyncReader::new(file))
+                .await?;
+            // Preserve the copy id from the current tree
+            let copy_id = match current_value {
+                Some(TreeValue::File { copy_id, .. }) => copy_id.clone(),
+                _ => CopyId::placeholder(),
+            };
+            Ok(Merge::normal(TreeValue::File {
+                id,
+                executable,
+                copy_id,
+            }))
+        } else if let Some(old_file_ids) = current_value.to_file_merge() {
+            // The file was served as a materialized conflict. Parse the
+            // conflict markers to update the conflict.
+            let copy_id = current_value
+                .to_copy_id_merge()
+                .unwrap()
+                .resolve_trivial()
+                .cloned()
+                .flatten()
+                .unwrap_or_else(CopyId::placeholder);
+            let content = fs::read(disk_path).map_err(|err| SnapshotError::Other {
+                message: format!("Failed to read file {}", disk_path.display()),
+                err: err.into(),
+            })?;
+            // The conflict was materialized with the marker length chosen
+            // for the current contents, so choose it the same way.
+            let conflict_marker_len =
+                match materialize_tree_value(self.store, path, current_value.clone()).await? {
+                    MaterializedTreeValue::FileConflict(file) => {
+                        choose_materialized_conflict_marker_len(&file.contents)
+                    }
+                    _ => MIN_CONFLICT_MARKER_LEN,
+                };
+            let new_file_ids = conflicts::update_from_content(
+                &old_file_ids,
+                self.store,
+                path,
+                &content,
+                self.options.conflict_marker_style,
+                conflict_marker_len,
+            )
+            .await?;
+            match new_file_ids.into_resolved() {
+                Ok(file_id) => Ok(Merge::normal(TreeValue::File {
+                    id: file_id.unwrap(),
+                    executable,
+                    copy_id,
+                })),
+                Err(new_file_ids) => Ok(current_value.with_new_file_ids(&new_file_ids)),
+            }
+        } else {
+            Ok(current_value.clone())
+        }
+    }
+
+    async fn write_symlink_to_store(
+        &self,
+        path: &RepoPath,
+        disk_path: &Path,
+    ) -> Result<MergedTreeValue, SnapshotError> {
+        let target = disk_path.read_link().map_err(|err| SnapshotError::Other {
+            message: format!("Failed to read symlink {}", disk_path.display()),
+            err: err.into(),
+        })?;
+        let target = target
+            .to_str()
+            .ok_or_else(|| SnapshotError::InvalidUtf8SymlinkTarget {
+                path: disk_path.to_path_buf(),
+            })?;
+        let id = self.store.write_symlink(path, target).await?;
+        Ok(Merge::normal(TreeValue::Symlink(id)))
+    }
+}
+
+/// Moves the untracked entries of the overlay directory `dir` to
+/// `new_overlay`. Tracked changes are recorded in `old_tree` and are therefore
+/// dropped.
+fn carry_over_untracked(
+    old_tree: &MergedTree,
+    new_tree: &MergedTree,
+    dir: &RepoPath,
+    disk_dir: &Path,
+    new_overlay: &Overlay,
+    stats: &mut CheckoutStats,
+) -> Result<(), CheckoutError> {
+    let io_error = |path: &Path, err: io::Error| CheckoutError::Other {
+        message: format!("Failed to move untracked file {}", path.display()),
+        err: err.into(),
+    };
+    let entries = fs::read_dir(disk_dir).map_err(|err| io_error(disk_dir, err))?;
+    for entry in entries {
+        let entry = entry.map_err(|err| io_error(disk_dir, err))?;
+        let disk_path = entry.path();
+        let is_dir = entry
+            .file_type()
+            .map_err(|err| io_error(&disk_path, err))?
+            .is_dir();
+        let file_name = entry.file_name();
+        let Some(name) = file_name
+            .to_str()
+            .and_then(|name| RepoPathComponent::new(name).ok())
+        else {
+            // Paths which aren't valid UTF-8 can't be tracked.
+            let new_disk_path = new_overlay.disk_path(dir).join(&file_name);
+            move_entry(&disk_path, &new_disk_path).map_err(|err| io_error(&disk_path, err))?;
+            continue;
+        };
+        let path = dir.join(name);
+        let old_value = old_tree.path_value(&path)?;
+        if is_dir && old_value.is_tree() {
+            carry_over_untracked(old_tree, new_tree, &path, &disk_path, new_overlay, stats)?;
+        } else if is_dir || old_value.is_absent() || old_value.is_tree() {
+            if new_tree.path_value(&path)?.is_present() {
+                // The untracked entry takes precedence over the new file.
+                stats.skipped_files += 1;
+            }
+            move_entry(&disk_path, &new_overlay.disk_path(&path))
+                .map_err(|err| io_error(&disk_path, err))?;
+        }
+    }
+    Ok(())
+}
+
+fn move_entry(from: &Path, to: &Path) -> io::Result<()> {
+    fs::create_dir_all(to.parent().unwrap())?;
+    fs::rename(from, to)
+}
+
+pub struct FuseWorkingCopy {
+    store: Arc<Store>,
+    working_copy_path: PathBuf,
+    state_path: PathBuf,
+    checkout_state: OnceCell<CheckoutState>,
+    fuse_state: OnceCell<FuseState>,
+    /// The FUSE working copy always contains all files.
+    sparse_patterns: Vec<RepoPathBuf>,
+}
+
+/// Working copy state stored in "checkout" file.
+#[derive(Clone, Debug)]
+struct CheckoutState {
+    operation_id: OperationId,
+    workspace_name: WorkspaceNameBuf,
+}
+
+impl WorkingCopy for FuseWorkingCopy {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn name(&self) -> &str {
+        Self::name()
+    }
+
+    fn workspace_name(&self) -> &WorkspaceName {
+        &self.checkout_state().workspace_name
+    }
+
+    fn operation_id(&self) -> &OperationId {
+        &self.checkout_state().operation_id
+    }
+
+    fn tree_id(&self) -> Result<&MergedTreeId, WorkingCopyStateError> {
+        Ok(&self.fuse_state()?.tree_id)
+    }
+
+    fn sparse_patterns(&self) -> Result<&[RepoPathBuf], WorkingCopyStateError> {
+        Ok(&self.sparse_patterns)
+    }
+
+    fn sparse_mode(&self) -> Result<SparseMode, WorkingCopyStateError> {
+        Ok(SparseMode::Prefix)
+    }
+
+    fn sparse_profile(&self) -> Result<Option<&RepoPath>, WorkingCopyStateError> {
+        Ok(None)
+    }
+
+    fn start_mutation(&self) -> Result<Box<dyn LockedWorkingCopy>, WorkingCopyStateError> {
+        let lock_path = self.state_path.join("working_copy.lock");
+        let lock = FileLock::lock(lock_path).map_err(|err| WorkingCopyStateError {
+            message: "Failed to lock working copy".to_owned(),
+            err: err.into(),
+        })?;
+
+        let wc = Self {
+            store: self.store.clone(),
+            working_copy_path: self.working_copy_path.clone(),
+            state_path: self.state_path.clone(),
+            // Empty so we re-read the state after taking the lock
+            checkout_state: OnceCell::new(),
+            fuse_state: OnceCell::new(),
+            sparse_patterns: self.sparse_patterns.clone(),
+        };
+        let old_operation_id = wc.operation_id().clone();
+        let old_tree_id = wc.tree_id()?.clone();
+        Ok(Box::new(LockedFuseWorkingCopy {
+            wc,
+            lock,
+            old_operation_id,
+            old_tree_id,
+            fuse_state_dirty: false,
+            obsolete_overlays: vec![],
+            new_workspace_name: None,
+        }))
+    }
+}
+
+impl FuseWorkingCopy {
+    pub fn name() -> &'static str {
+        "fuse"
+    }
+
+    /// Initializes a new working copy at `working_copy_path`. The working
+    /// copy's state will be stored in the `state_path` directory. The working
+    /// copy will have the empty tree checked out.
+    pub fn init(
+        store: Arc<Store>,
+        working_copy_path: PathBuf,
+        state_path: PathBuf,
+        operation_id: OperationId,
+        workspace_name: WorkspaceNameBuf,
+    ) -> Result<Self, WorkingCopyStateError> {
+        let proto = crate::protos::working_copy::Checkout {
+            operation_id: operation_id.to_bytes(),
+            workspace_name: workspace_name.into(),
+        };
+        let mut file = OpenOptions::new()
+            .create_new(true)
+            .write(true)
+            .open(state_path.join("checkout"))
+            .unwrap();
+        file.write_all(&proto.encode_to_vec()).unwrap();
+        let fuse_state = FuseState {
+            base_tree_id: store.empty_merged_tree_id(),
+            tree_id: store.empty_merged_tree_id(),
+            generation: 0,
+        };
+        fuse_state
+            .overlay(&state_path)
+            .create()
+            .map_err(|err| WorkingCopyStateError {
+                message: "Failed to create the overlay directory".to_string(),
+                err: err.into(),
+            })?;
+        fuse_state
+            .save(&state_path)
+            .map_err(|err| WorkingCopyStateError {
+                message: "Failed to initialize working copy state".to_string(),
+                err: err.into(),
+            })?;
+        Ok(Self {
+            store,
+            working_copy_path,
+            state_path,
+            checkout_state: OnceCell::new(),
+            fuse_state: OnceCell::with_value(fuse_state),
+            sparse_patterns: vec![RepoPathBuf::root()],
+        })
+    }
+
+    pub fn load(store: Arc<Store>, working_copy_path: PathBuf, state_path: PathBuf) -> Self {
+        Self {
+            store,
+            working_copy_path,
+            state_path,
+            checkout_state: OnceCell::new(),
+            fuse_state: OnceCell::new(),
+            sparse_patterns: vec![RepoPathBuf::root()],
+        }
+    }
+
+    pub fn state_path(&self) -> &Path {
+        &self.state_path
+    }
+
+    /// Directory containing the files added or modified through the mount
+    /// since the last checkout.
+    pub fn overlay_path(&self) -> Result<PathBuf, WorkingCopyStateError> {
+        Ok(self.fuse_state()?.overlay(&self.state_path).upper_dir())
+    }
+
+    fn write_proto(&self, proto: crate::protos::working_copy::Checkout) {
+        let mut temp_file = NamedTempFile::new_in(&self.state_path).unwrap();
+        temp_file
+            .as_file_mut()
+            .write_all(&proto.encode_to_vec())
+            .unwrap();
+        temp_file.persist(self.state_path.join("checkout")).unwrap();
+    }
+
+    fn checkout_state(&self) -> &CheckoutState {
+        self.checkout_state.get_or_init(|| {
+            let buf = fs::read(self.state_path.join("checkout")).unwrap();
+            let proto = crate::protos::working_copy::Checkout::decode(&*buf).unwrap();
+            CheckoutState {
+                operation_id: OperationId::new(proto.operation_id),
+                workspace_name: proto.workspace_name.into(),
+            }
+        })
+    }
+
+    fn checkout_state_mut(&mut self) -> &mut CheckoutState {
+        self.checkout_state(); // ensure loaded
+        self.checkout_state.get_mut().unwrap()
+    }
+
+    #[instrument(skip_all)]
+    fn fuse_state(&self) -> Result<&FuseState, WorkingCopyStateError> {
+        self.fuse_state.get_or_try_init(|| {
+            FuseState::load(&self.state_path).map_err(|err| WorkingCopyStateError {
+                message: "Failed to read working copy state".to_string(),
+                err: err.into(),
+            })
+        })
+    }
+
+    fn fuse_state_mut(&mut self) -> Result<&mut FuseState, WorkingCopyStateError> {
+        self.fuse_state()?; // ensure loaded
+        Ok(self.fuse_state.get_mut().unwrap())
+    }
+
+    #[instrument(skip_all)]
+    fn save(&mut self) {
+        self.write_proto(crate::protos::working_copy::Checkout {
+            operation_id: self.operation_id().to_bytes(),
+            workspace_name: self.workspace_name().into(),
+        });
+    }
+}
+
+pub struct FuseWorkingCopyFactory {}
+
+impl WorkingCopyFactory for FuseWorkingCopyFactory {
+    fn init_working_copy(
+        &self,
+        store: Arc<Store>,
+        working_copy_path: PathBuf,
+        state_path: PathBuf,
+        operation_id: OperationId,
+        workspace_name: WorkspaceNameBuf,
+        _settings: &UserSettings,
+    ) -> Result<Box<dyn WorkingCopy>, WorkingCopyStateError> {
+        Ok(Box::new(FuseWorkingCopy::init(
+            store,
+            working_copy_path,
+            state_path,
+            operation_id,
+            workspace_name,
+        )?))
+    }
+
+    fn load_working_copy(
+        &self,
+        store: Arc<Store>,
+        working_copy_path: PathBuf,
+        state_path: PathBuf,
+        _settings: &UserSettings,
+    ) -> Result<Box<dyn WorkingCopy>, WorkingCopyStateError> {
+        Ok(Box::new(FuseWorkingCopy::load(
+            store,
+            working_copy_path,
+            state_path,
+        )))
+    }
+}
+
+/// A FUSE working copy that's locked on disk. The lock is held until you call
+/// `finish()` or `discard()`.
+pub struct LockedFuseWorkingCopy {
+    wc: FuseWorkingCopy,
+    #[expect(dead_code)]
+    lock: FileLock,
+    old_operation_id: OperationId,
+    old_tree_id: MergedTreeId,
+    fuse_state_dirty: bool,
+    /// Overlays of previous generations, to be deleted once the new state has
+    /// been written.
+    obsolete_overlays: Vec<Overlay>,
+    new_workspace_name: Option<WorkspaceNameBuf>,
+}
+
+impl LockedFuseWorkingCopy {
+    fn state(&mut self) -> Result<FuseState, WorkingCopyStateError> {
+        Ok(self.wc.fuse_state_mut()?.clone())
+    }
+
+    fn set_state(&mut self, new_state: FuseState) -> Result<(), WorkingCopyStateError> {
+        *self.wc.fuse_state_mut()? = new_state;
+        self.fuse_state_dirty = true;
+        Ok(())
+    }
+
+    /// Moves the overlay to a new generation on top of `new_tree`, keeping
+    /// the whiteouts. Returns the new overlay.
+    fn move_overlay_to_new_generation(
+        &mut self,
+        new_tree: &MergedTree,
+    ) -> Result<(Overlay, BTreeSet<RepoPathBuf>), ResetError> {
+        let state = self.state().map_err(|err| ResetError::Other {
+            message: "Failed to read the working copy state".to_string(),
+            err: err.into(),
+        })?;
+        let old_overlay = state.overlay(&self.wc.state_path);
+        let new_state = FuseState {
+            base_tree_id: new_tree.id(),
+            tree_id: new_tree.id(),
+            generation: state.generation + 1,
+        };
+        let new_overlay = new_state.overlay(&self.wc.state_path);
+        let io_error = |err: io::Error| ResetError::Other {
+            message: "Failed to move the overlay directory".to_string(),
+            err: err.into(),
+        };
+        let whiteouts = old_overlay.read_whiteouts().map_err(io_error)?;
+        fs::rename(&old_overlay.dir, &new_overlay.dir).map_err(io_error)?;
+        self.set_state(new_state).map_err(|err| ResetError::Other {
+            message: "Failed to read the working copy state".to_string(),
+            err: err.into(),
+        })?;
+        Ok((new_overlay, whiteouts))
+    }
+}
+
+impl LockedWorkingCopy for LockedFuseWorkingCopy {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+
+    fn old_operation_id(&self) -> &OperationId {
+        &self.old_operation_id
+    }
+
+    fn old_tree_id(&self) -> &MergedTreeId {
+        &self.old_tree_id
+    }
+
+    fn snapshot(
+        &mut self,
+        options: &SnapshotOptions,
+    ) -> Result<(MergedTreeId, SnapshotStats), SnapshotError> {
+        let state = self.state().map_err(|err| SnapshotError::Other {
+            message: "Failed to read the working copy state".to_string(),
+            err: err.into(),
+        })?;
+        let overlay = state.overlay(&self.wc.state_path);
+        let whiteouts = overlay
+            .read_whiteouts()
+            .map_err(|err| SnapshotError::Other {
+                message: "Failed to read the removed paths".to_string(),
+                err: err.into(),
+            })?;
+        let store = &self.wc.store;
+        let snapshotter = OverlaySnapshotter {
+            store,
+            working_copy_path: &self.wc.working_copy_path,
+            options,
+            base_tree: store.get_root_tree(&state.base_tree_id)?,
+            current_tree: store.get_root_tree(&state.tree_id)?,
+            overlay: &overlay,
+            whiteouts,
+            tree_builder: MergedTreeBuilder::new(state.base_tree_id.clone()),
+            git_ignores: HashMap::new(),
+            stats: SnapshotStats::default(),
+        };
+        let (tree_id, stats) = snapshotter.snapshot()?;
+        if tree_id != state.tree_id {
+            self.set_state(FuseState {
+                tree_id: tree_id.clone(),
+                ..state
+            })
+            .map_err(|err| SnapshotError::Other {
+                message: "Failed to read the working copy state".to_string(),
+                err: err.into(),
+            })?;
+        }
+        Ok((tree_id, stats))
+    }
+
+    fn check_out(
+        &mut self,
+        commit: &Commit,
+        _options: &CheckoutOptions,
+    ) -> Result<CheckoutStats, CheckoutError> {
+        let state = self.state().map_err(|err| CheckoutError::Other {
+            message: "Failed to load the working copy state".to_string(),
+            err: err.into(),
+        })?;
+        if state.tree_id == *commit.tree_id() {
+            return Ok(CheckoutStats::default());
+        }
+        let new_tree = commit.tree()?;
+        let old_tree = self
+            .wc
+            .store
+            .get_root_tree(&state.tree_id)
+            .map_err(|err| match err {
+                err @ BackendError::ObjectNotFound { .. } => CheckoutError::SourceNotFound {
+                    source: Box::new(err),
+                },
+                other => CheckoutError::InternalBackendError(other),
+            })?;
+        // TODO: Make interrupted checkouts resumable. The untracked files
+        // moved so far would be hidden until the new state is written.
+        let old_overlay = state.overlay(&self.wc.state_path);
+        let new_state = FuseState {
+            base_tree_id: new_tree.id(),
+            tree_id: new_tree.id(),
+            generation: state.generation + 1,
+        };
+        let new_overlay = new_state.overlay(&self.wc.state_path);
+        new_overlay.create().map_err(|err| CheckoutError::Other {
+            message: "Failed to create the overlay directory".to_string(),
+            err: err.into(),
+        })?;
+        let mut stats = CheckoutStats::default();
+        carry_over_untracked(
+            &old_tree,
+            &new_tree,
+            RepoPath::root(),
+            &old_overlay.upper_dir(),
+            &new_overlay,
+            &mut stats,
+        )?;
+        // The files aren't written, but report what changed in the working
+        // copy like other implementations do.
+        let mut diff_stream = old_tree.diff_stream(&new_tree, &EverythingMatcher);
+        while let Some(entry) = diff_stream.next().block_on() {
+            let (before, after) = entry.values?;
+            if after.is_absent() {
+                stats.removed_files += 1;
+            } else if before.is_absent() {
+                stats.added_files += 1;
+            } else {
+                stats.updated_files += 1;
+            }
+        }
+        self.obsolete_overlays.push(old_overlay);
+        self.set_state(new_state)
+            .map_err(|err| CheckoutError::Other {
+                message: "Failed to load the working copy state".to_string(),
+                err: err.into(),
+            })?;
+        Ok(stats)
+    }
+
+    fn rename_workspace(&mut self, new_name: WorkspaceNameBuf) {
+        self.new_workspace_name = Some(new_name);
+    }
+
+    fn reset(&mut self, commit: &Commit) -> Result<(), ResetError> {
+        let new_tree = commit.tree()?;
+        let state = self.state().map_err(|err| ResetError::Other {
+            message: "Failed to read the working copy state".to_string(),
+            err: err.into(),
+        })?;
+        let old_base_tree = self
+            .wc
+            .store
+            .get_root_tree(&state.base_tree_id)
+            .map_err(|err| match err {
+                err @ BackendError::ObjectNotFound { .. } => ResetError::SourceNotFound {
+                    source: Box::new(err),
+                },
+                other => ResetError::InternalBackendError(other),
+            })?;
+        let (overlay, mut whiteouts) = self.move_overlay_to_new_generation(&new_tree)?;
+        // The files must look the same as before, so the paths which differ
+        // between the old and the new base tree are recorded in the overlay.
+        let io_error = |path: &RepoPath, err: io::Error| ResetError::Other {
+            message: format!(
+                "Failed to update the overlay at {}",
+                path.as_internal_file_string()
+            ),
+            err: err.into(),
+        };
+        let mut diff_stream = old_base_tree.diff_stream(&new_tree, &EverythingMatcher);
+        while let Some(entry) = diff_stream.next().block_on() {
+            let path = entry.path;
+            let (before, after) = entry.values?;
+            if fs::symlink_metadata(overlay.disk_path(&path)).is_ok()
+                || overlay.is_shadowed(&path)
+                || is_whited_out(&whiteouts, &path)
+            {
+                continue;
+            }
+            if before.is_present() {
+                // The conflict marker style only matters for conflicts, which
+                // are parsed back without relying on the style.
+                let file = materialize_base_value(
+                    &self.wc.store,
+                    &path,
+                    before,
+                    ConflictMarkerStyle::default(),
+                )
+                .block_on()?;
+                if let Some(file) = file {
+                    write_overlay_file(&overlay.disk_path(&path), &file)
+                        .map_err(|err| io_error(&path, err))?;
+                }
+            }
+            if after.is_present() {
+                whiteouts.insert(path);
+            }
+        }
+        overlay
+            .write_whiteouts(&whiteouts)
+            .map_err(|err| io_error(RepoPath::root(), err))?;
+        Ok(())
+    }
+
+    fn recover(&mut self, commit: &Commit) -> Result<(), ResetError> {
+        // The old base tree may be missing, so its files can't be recorded in
+        // the overlay. They're replaced by the files of the new tree.
+        let new_tree = commit.tree()?;
+        self.move_overlay_to_new_generation(&new_tree)?;
+        Ok(())
+    }
+
+    fn sparse_patterns(&self) -> Result<&[RepoPathBuf], WorkingCopyStateError> {
+        self.wc.sparse_patterns()
+    }
+
+    fn sparse_mode(&self) -> Result<SparseMode, WorkingCopyStateError> {
+        self.wc.sparse_mode()
+    }
+
+    fn sparse_profile(&self) -> Result<Option<&RepoPath>, WorkingCopyStateError> {
+        self.wc.sparse_profile()
+    }
+
+    fn set_sparse_patterns(
+        &mut self,
+        new_sparse_patterns: Vec<RepoPathBuf>,
+        options: &CheckoutOptions,
+    ) -> Result<CheckoutStats, CheckoutError> {
+        self.set_sparse_patterns_and_mode(new_sparse_patterns, SparseMode::Prefix, options)
+    }
+
+    fn set_sparse_patterns_and_mode(
+        &mut self,
+        new_sparse_patterns: Vec<RepoPathBuf>,
+        _new_sparse_mode: SparseMode,
+        _options: &CheckoutOptions,
+    ) -> Result<CheckoutStats, CheckoutError> {
+        if new_sparse_patterns == self.wc.sparse_patterns {
+            Ok(CheckoutStats::default())
+        } else {
+            Err(sparse_not_supported_error())
+        }
+    }
+
+    fn set_sparse_profile(
+        &mut self,
+        _profile_path: RepoPathBuf,
+        _new_sparse_mode: SparseMode,
+        _options: &CheckoutOptions,
+    ) -> Result<CheckoutStats, CheckoutError> {
+        Err(sparse_not_supported_error())
+    }
+
+    #[instrument(skip_all)]
+    fn finish(
+        mut self: Box<Self>,
+        operation_id: OperationId,
+    ) -> Result<Box<dyn WorkingCopy>, WorkingCopyStateError> {
+        assert!(self.fuse_state_dirty || &self.old_tree_id == self.wc.tree_id()?);
+        if self.fuse_state_dirty {
+            self.wc
+                .fuse_state()?
+                .save(&self.wc.state_path)
+                .map_err(|err| WorkingCopyStateError {
+                    message: "Failed to write working copy state".to_string(),
+                    err: Box::new(err),
+                })?;
+        }
+        for overlay in self.obsolete_overlays.drain(..) {
+            // The daemon may still be reading from the old overlay, but open
+            // files remain readable after being deleted.
+            fs::remove_dir_all(&overlay.dir).ok();
+        }
+        if self.old_operation_id != operation_id || self.new_workspace_name.is_some() {
+            if let Some(new_name) = self.new_workspace_name {
+                self.wc.checkout_state_mut().workspace_name = new_name;
+            }
+            self.wc.checkout_state_mut().operation_id = operation_id;
+            self.wc.save();
+        }
+        Ok(Box::new(self.wc))
+    }
+}
+
+fn sparse_not_supported_error() -> CheckoutError {
+    CheckoutError::Other {
+        message: "Failed to update the sparse patterns".to_string(),
+        err: "The FUSE working copy always contains all files".into(),
+    }
+}
+
+/// Writes the materialized base `file` at `disk_path` in the overlay.
+fn write_overlay_file(disk_path: &Path, file: &MaterializedBaseFile) -> io::Result<()> {
+    fs::create_dir_all(disk_path.parent().unwrap())?;
+    if file.is_symlink {
+        let target = str::from_utf8(&file.content)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+        std::os::unix::fs::symlink(target, disk_path)
+    } else {
+        fs::write(disk_path, &file.content)?;
+        let mode = if file.executable { 0o755 } else { 0o644 };
+        fs::set_permissions(disk_path, fs::Permissions::from_mode(mode))
+    }
+}
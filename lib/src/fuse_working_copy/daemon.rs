@@ -0,0 +1,1236 @@
+// Copyright 2025 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! The filesystem serving a FUSE working copy.
+//!
+//! Directories are the union of the base tree and the overlay. An entry of the
+//! overlay hides the base entry at the same path. Files are copied to the
+//! overlay before they're modified. Removing an entry of the base tree records
+//! a whiteout, which hides the base entry and everything below it.
+
+use std::collections::BTreeMap;
+use std::collections::BTreeSet;
+use std::collections::HashMap;
+use std::ffi::OsStr;
+use std::fs;
+use std::fs::File;
+use std::fs::OpenOptions;
+use std::io;
+use std::ops::Bound;
+use std::os::unix::fs::FileExt as _;
+use std::os::unix::fs::MetadataExt as _;
+use std::os::unix::fs::OpenOptionsExt as _;
+use std::os::unix::fs::PermissionsExt as _;
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+use std::time::SystemTime;
+
+use fuser::FileAttr;
+use fuser::FileType;
+use fuser::Filesystem;
+use fuser::MountOption;
+use fuser::ReplyAttr;
+use fuser::ReplyCreate;
+use fuser::ReplyData;
+use fuser::ReplyDirectory;
+use fuser::ReplyEmpty;
+use fuser::ReplyEntry;
+use fuser::ReplyOpen;
+use fuser::ReplyWrite;
+use fuser::Request;
+use fuser::TimeOrNow;
+use itertools::Itertools as _;
+use pollster::FutureExt as _;
+use thiserror::Error;
+use tracing::info;
+use tracing::warn;
+
+use super::FuseState;
+use super::FuseStateError;
+use super::MaterializedBaseFile;
+use super::Overlay;
+use super::STATE_FILE_NAME;
+use super::is_whited_out;
+use super::materialize_base_value;
+use super::write_overlay_file;
+use crate::backend::TreeValue;
+use crate::conflicts::ConflictMarkerStyle;
+use crate::merged_tree::MergedTree;
+use crate::repo_path::RepoPath;
+use crate::repo_path::RepoPathBuf;
+use crate::repo_path::RepoPathComponent;
+use crate::store::Store;
+
+/// How long the kernel may cache attributes and directory entries. Changes
+/// made by checkouts become visible after that.
+const TTL: Duration = Duration::from_secs(1);
+
+/// The served base files are kept in memory up to this size in total.
+const MAX_CACHED_BYTES: usize = 256 << 20;
+
+const DOT_JJ: &str = ".jj";
+
+/// Error while running the FUSE daemon.
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error(
+        "The .jj directory of the workspace at {0} must be a symlink, since the mount hides it"
+    )]
+    DotJjNotSymlink(PathBuf),
+
+    #[error(transparent)]
+    State(#[from] FuseStateError),
+
+    #[error("Failed to read the tree of the working copy")]
+    Backend(#[from] crate::backend::BackendError),
+
+    #[error("Failed to read the overlay of the working copy")]
+    Overlay(#[source] io::Error),
+
+    #[error("Failed to mount the working copy at {path}")]
+    Mount { path: PathBuf, source: io::Error },
+}
+
+/// Mounts the FUSE working copy with state in `state_path` at
+/// `working_copy_path`, and serves it until it's unmounted.
+pub fn run_daemon(
+    store: Arc<Store>,
+    working_copy_path: &Path,
+    state_path: &Path,
+    conflict_marker_style: ConflictMarkerStyle,
+) -> Result<(), Error> {
+    let filesystem =
+        JjFilesystem::new(store, working_copy_path, state_path, conflict_marker_style)?;
+    info!(?working_copy_path, "Mounting FUSE working copy");
+    fuser::mount2(filesystem, working_copy_path, &mount_options()).map_err(|source| Error::Mount {
+        path: working_copy_path.to_owned(),
+        source,
+    })
+}
+
+/// A FUSE working copy served by a background thread. The working copy is
+/// unmounted when this is dropped.
+pub struct DaemonSession {
+    _session: fuser::BackgroundSession,
+}
+
+/// Like [`run_daemon()`], but serves the working copy from a background
+/// thread.
+pub fn spawn_daemon(
+    store: Arc<Store>,
+    working_copy_path: &Path,
+    state_path: &Path,
+    conflict_marker_style: ConflictMarkerStyle,
+) -> Result<DaemonSession, Error> {
+    let filesystem =
+        JjFilesystem::new(store, working_copy_path, state_path, conflict_marker_style)?;
+    info!(?working_copy_path, "Mounting FUSE working copy");
+    let session =
+        fuser::spawn_mount2(filesystem, working_copy_path, &mount_options()).map_err(|source| {
+            Error::Mount {
+                path: working_copy_path.to_owned(),
+                source,
+            }
+        })?;
+    Ok(DaemonSession { _session: session })
+}
+
+fn mount_options() -> [MountOption; 3] {
+    [
+        MountOption::FSName("jj".to_owned()),
+        MountOption::Subtype("jj".to_owned()),
+        MountOption::DefaultPermissions,
+    ]
+}
+
+/// Identifies a version of the state file. The file is replaced on every
+/// update, so the inode number changes.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+struct StateStamp {
+    ino: u64,
+    mtime: i64,
+    mtime_nsec: i64,
+}
+
+impl StateStamp {
+    fn new(metadata: &fs::Metadata) -> Self {
+        Self {
+            ino: metadata.ino(),
+            mtime: metadata.mtime(),
+            mtime_nsec: metadata.mtime_nsec(),
+        }
+    }
+}
+
+/// Maps inode numbers to paths. An inode keeps its number until the kernel
+/// forgets it.
+struct InodeTable {
+    paths: HashMap<u64, InodeEntry>,
+    /// Ordered by path, so that the inodes below a directory are adjacent.
+    inodes: BTreeMap<RepoPathBuf, u64>,
+    next_inode: u64,
+}
+
+struct InodeEntry {
+    path: RepoPathBuf,
+    /// The number of times the inode was returned to the kernel by a lookup
+    /// or a creation, minus the number of times the kernel forgot it.
+    lookups: u64,
+}
+
+impl InodeTable {
+    fn new() -> Self {
+        let root = RepoPathBuf::root();
+        let root_entry = InodeEntry {
+            path: root.clone(),
+            lookups: 1,
+        };
+        Self {
+            paths: HashMap::from([(fuser::FUSE_ROOT_ID, root_entry)]),
+            inodes: BTreeMap::from([(root, fuser::FUSE_ROOT_ID)]),
+            next_inode: fuser::FUSE_ROOT_ID + 1,
+        }
+    }
+
+    fn path(&self, inode: u64) -> Option<&RepoPath> {
+        self.paths.get(&inode).map(|entry| entry.path.as_ref())
+    }
+
+    fn get(&self, path: &RepoPath) -> Option<u64> {
+        self.inodes.get(path).copied()
+    }
+
+    /// Returns the inode of `path` for an entry reply to the kernel, which
+    /// keeps it until it's forgotten.
+    fn lookup(&mut self, path: &RepoPath) -> u64 {
+        if let Some(inode) = self.get(path) {
+            self.paths.get_mut(&inode).unwrap().lookups += 1;
+            return inode;
+        }
+        let inode = self.allocate();
+        self.paths.insert(
+            inode,
+            InodeEntry {
+                path: path.to_owned(),
+                lookups: 1,
+            },
+        );
+        self.inodes.insert(path.to_owned(), inode);
+        inode
+    }
+
+    /// Returns the inode of `path` to report in a directory listing. The
+    /// kernel doesn't keep the inodes of listed entries, so a new number isn't
+    /// recorded.
+    fn get_or_allocate(&mut self, path: &RepoPath) -> u64 {
+        self.get(path).unwrap_or_else(|| self.allocate())
+    }
+
+    fn allocate(&mut self) -> u64 {
+        let inode = self.next_inode;
+        self.next_inode += 1;
+        inode
+    }
+
+    /// Drops the inode once the kernel has forgotten all its lookups.
+    fn forget(&mut self, inode: u64, nlookup: u64) {
+        if inode == fuser::FUSE_ROOT_ID {
+            return;
+        }
+        let Some(entry) = self.paths.get_mut(&inode) else {
+            return;
+        };
+        entry.lookups = entry.lookups.saturating_sub(nlookup);
+        if entry.lookups == 0 {
+            let entry = self.paths.remove(&inode).unwrap();
+            if self.inodes.get(&entry.path) == Some(&inode) {
+                self.inodes.remove(&entry.path);
+            }
+        }
+    }
+
+    /// Updates the paths of the inodes at and below `from` after a rename.
+    fn rename(&mut self, from: &RepoPath, to: &RepoPath) {
+        let moved = self
+            .inodes
+            .range::<RepoPath, _>((Bound::Included(from), Bound::Unbounded))
+            .take_while(|(path, _)| path.starts_with(from))
+            .map(|(path, _)| path.clone())
+            .collect_vec();
+        // The inodes at the destination refer to entries which aren't
+        // reachable anymore.
+        let replaced = self
+            .inodes
+            .range::<RepoPath, _>((Bound::Included(to), Bound::Unbounded))
+            .take_while(|(path, _)| path.starts_with(to))
+            .map(|(path, _)| path.clone())
+            .collect_vec();
+        for path in replaced {
+            let inode = self.inodes.remove(&path).unwrap();
+            self.paths.remove(&inode);
+        }
+        for old_path in moved {
+            let inode = self.inodes.remove(&old_path).unwrap();
+            let new_path = old_path
+                .strip_prefix(from)
+                .unwrap()
+                .components()
+                .fold(to.to_owned(), |path, name| path.join(name));
+            self.paths.get_mut(&inode).unwrap().path = new_path.clone();
+            self.inodes.insert(new_path, inode);
+        }
+    }
+}
+
+/// An entry as seen through the mount.
+enum Entry {
+    /// File, symlink, or directory of the overlay.
+    Overlay(fs::Metadata),
+    /// Directory of the base tree.
+    BaseDir,
+    /// File, symlink, or materialized conflict of the base tree.
+    BaseFile(Arc<MaterializedBaseFile>),
+    /// The `.jj` symlink in the root directory.
+    DotJj,
+}
+
+impl Entry {
+    fn is_dir(&self) -> bool {
+        match self {
+            Self::Overlay(metadata) => metadata.is_dir(),
+            Self::BaseDir => true,
+            Self::BaseFile(_) | Self::DotJj => false,
+        }
+    }
+}
+
+struct JjFilesystem {
+    store: Arc<Store>,
+    state_path: PathBuf,
+    dot_jj_target: PathBuf,
+    conflict_marker_style: ConflictMarkerStyle,
+    uid: u32,
+    gid: u32,
+    /// Version of the state file the current view was loaded from.
+    state_stamp: Option<StateStamp>,
+    generation: u64,
+    base_tree: MergedTree,
+    /// Modification time reported for the base entries. Updated when the base
+    /// tree changes so that tools notice the changed files.
+    base_time: SystemTime,
+    overlay: Overlay,
+    whiteouts: BTreeSet<RepoPathBuf>,
+    inodes: InodeTable,
+    base_files: HashMap<RepoPathBuf, Arc<MaterializedBaseFile>>,
+    cached_bytes: usize,
+    /// Files of the overlay opened through the mount.
+    handles: HashMap<u64, File>,
+    next_handle: u64,
+}
+
+fn errno(err: &io::Error) -> i32 {
+    err.raw_os_error().unwrap_or(libc::EIO)
+}
+
+impl JjFilesystem {
+    fn new(
+        store: Arc<Store>,
+        working_copy_path: &Path,
+        state_path: &Path,
+        conflict_marker_style: ConflictMarkerStyle,
+    ) -> Result<Self, Error> {
+        let dot_jj_target = fs::read_link(working_copy_path.join(DOT_JJ))
+            .map_err(|_| Error::DotJjNotSymlink(working_copy_path.to_owned()))?;
+        let state_metadata = fs::metadata(state_path.join(STATE_FILE_NAME)).map_err(|source| {
+            FuseStateError::Read {
+                path: state_path.join(STATE_FILE_NAME),
+                source,
+            }
+        })?;
+        let state = FuseState::load(state_path)?;
+        let overlay = state.overlay(state_path);
+        Ok(Self {
+            base_tree: store.get_root_tree(&state.base_tree_id)?,
+            store,
+            state_path: state_path.to_owned(),
+            dot_jj_target,
+            conflict_marker_style,
+            uid: state_metadata.uid(),
+            gid: state_metadata.gid(),
+            state_stamp: Some(StateStamp::new(&state_metadata)),
+            generation: state.generation,
+            base_time: state_metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH),
+            whiteouts: overlay.read_whiteouts().map_err(Error::Overlay)?,
+            overlay,
+            inodes: InodeTable::new(),
+            base_files: HashMap::new(),
+            cached_bytes: 0,
+            handles: HashMap::new(),
+            next_handle: 1,
+        })
+    }
+
+    /// Switches to the overlay and the base tree of the current generation if
+    /// a checkout happened since the last request.
+    fn refresh(&mut self) {
+        let state_file = self.state_path.join(STATE_FILE_NAME);
+        let stamp = match fs::metadata(&state_file) {
+            Ok(metadata) => StateStamp::new(&metadata),
+            Err(err) => {
+                warn!(?err, "Failed to stat the working copy state");
+                return;
+            }
+        };
+        if self.state_stamp == Some(stamp) {
+            return;
+        }
+        self.state_stamp = Some(stamp);
+        let state = match FuseState::load(&self.state_path) {
+            Ok(state) => state,
+            Err(err) => {
+                warn!(?err, "Failed to read the working copy state");
+                return;
+            }
+        };
+        if state.generation == self.generation {
+            return;
+        }
+        let base_tree = match self.store.get_root_tree(&state.base_tree_id) {
+            Ok(tree) => tree,
+            Err(err) => {
+                warn!(?err, "Failed to read the base tree");
+                return;
+            }
+        };
+        let overlay = state.overlay(&self.state_path);
+        let whiteouts = match overlay.read_whiteouts() {
+            Ok(whiteouts) => whiteouts,
+            Err(err) => {
+                warn!(?err, "Failed to read the removed paths");
+                return;
+            }
+        };
+        info!(generation = state.generation, "Switching to new overlay");
+        self.generation = state.generation;
+        self.base_tree = base_tree;
+        self.base_time = SystemTime::now();
+        self.overlay = overlay;
+        self.whiteouts = whiteouts;
+        self.base_files.clear();
+        self.cached_bytes = 0;
+    }
+
+    fn path(&self, inode: u64) -> Result<RepoPathBuf, i32> {
+        self.inodes
+            .path(inode)
+            .map(ToOwned::to_owned)
+            .ok_or(libc::ENOENT)
+    }
+
+    fn child_path(&self, parent: u64, name: &OsStr) -> Result<RepoPathBuf, i32> {
+        let parent = self.path(parent)?;
+        let name = name
+            .to_str()
+            .and_then(|name| RepoPathComponent::new(name).ok())
+            .ok_or(libc::EINVAL)?;
+        Ok(parent.join(name))
+    }
+
+    fn is_dot_jj(path: &RepoPath) -> bool {
+        path.as_internal_file_string() == DOT_JJ
+    }
+
+    /// Returns the entry at `path` as seen through the mount.
+    fn entry(&mut self, path: &RepoPath) -> Result<Option<Entry>, i32> {
+        if path.is_root() {
+            return Ok(Some(Entry::BaseDir));
+        }
+        if Self::is_dot_jj(path) {
+            return Ok(Some(Entry::DotJj));
+        }
+        if self.overlay.is_shadowed(path) {
+            return Ok(None);
+        }
+        match fs::symlink_metadata(self.overlay.disk_path(path)) {
+            Ok(metadata) => return Ok(Some(Entry::Overlay(metadata))),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => {}
+            Err(err) => return Err(errno(&err)),
+        }
+        if is_whited_out(&self.whiteouts, path) {
+            return Ok(None);
+        }
+        if let Some(file) = self.base_files.get(path) {
+            return Ok(Some(Entry::BaseFile(file.clone())));
+        }
+        let value = self.base_tree.path_value(path).map_err(|err| {
+            warn!(?err, ?path, "Failed to read the base tree");
+            libc::EIO
+        })?;
+        if value.is_absent() {
+            return Ok(None);
+        }
+        if value.is_tree() {
+            return Ok(Some(Entry::BaseDir));
+        }
+        let file = materialize_base_value(&self.store, path, value, self.conflict_marker_style)
+            .block_on()
+            .map_err(|err| {
+                warn!(?err, ?path, "Failed to read file from the base tree");
+                libc::EIO
+            })?;
+        let Some(file) = file else {
+            return Ok(None);
+        };
+        let file = Arc::new(file);
+        if self.cached_bytes + file.content.len() > MAX_CACHED_BYTES {
+            self.base_files.clear();
+            self.cached_bytes = 0;
+        }
+        self.cached_bytes += file.content.len();
+        self.base_files.insert(path.to_owned(), file.clone());
+        Ok(Some(Entry::BaseFile(file)))
+    }
+
+    /// Returns true if the base tree has an entry at `path` which isn't hidden
+    /// by a whiteout, so that removing `path` requires a whiteout.
+    fn has_visible_base_entry(&self, path: &RepoPath) -> Result<bool, i32> {
+        if is_whited_out(&self.whiteouts, path) {
+            return Ok(false);
+        }
+        let value = self.base_tree.path_value(path).map_err(|_| libc::EIO)?;
+        Ok(value.is_present())
+    }
+
+    fn add_whiteout(&mut self, path: &RepoPath) -> Result<(), i32> {
+        self.whiteouts.insert(path.to_owned());
+        self.overlay
+            .write_whiteouts(&self.whiteouts)
+            .map_err(|err| errno(&err))
+    }
+
+    fn attr(&self, inode: u64, entry: &Entry) -> FileAttr {
+        let (kind, size, perm, mtime) = match entry {
+            Entry::Overlay(metadata) => {
+                let kind = if metadata.is_dir() {
+                    FileType::Directory
+                } else if metadata.is_symlink() {
+                    FileType::Symlink
+                } else {
+                    FileType::RegularFile
+                };
+                let mtime = metadata.modified().unwrap_or(self.base_time);
+                let perm = (metadata.permissions().mode() & 0o7777) as u16;
+                (kind, metadata.len(), perm, mtime)
+            }
+            Entry::BaseDir => (FileType::Directory, 0, 0o755, self.base_time),
+            Entry::BaseFile(file) => {
+                let (kind, perm) = if file.is_symlink {
+                    (FileType::Symlink, 0o777)
+                } else if file.executable {
+                    (FileType::RegularFile, 0o755)
+                } else {
+                    (FileType::RegularFile, 0o644)
+                };
+                (kind, file.content.len() as u64, perm, self.base_time)
+            }
+            Entry::DotJj => (
+                FileType::Symlink,
+                self.dot_jj_target.as_os_str().len() as u64,
+                0o777,
+                self.base_time,
+            ),
+        };
+        FileAttr {
+            ino: inode,
+            size,
+            blocks: size.div_ceil(512),
+            atime: mtime,
+            mtime,
+            ctime: mtime,
+            crtime: mtime,
+            kind,
+            perm,
+            nlink: if kind == FileType::Directory { 2 } else { 1 },
+            uid: self.uid,
+            gid: self.gid,
+            rdev: 0,
+            blksize: 4096,
+            flags: 0,
+        }
+    }
+
+    fn existing_entry(&mut self, path: &RepoPath) -> Result<Entry, i32> {
+        self.entry(path)?.ok_or(libc::ENOENT)
+    }
+
+    /// Returns the attributes of the entry at `path` for an entry reply. The
+    /// kernel keeps the inode until it forgets it.
+    fn lookup_attr(&mut self, path: &RepoPath) -> Result<FileAttr, i32> {
+        let entry = self.existing_entry(path)?;
+        let inode = self.inodes.lookup(path);
+        Ok(self.attr(inode, &entry))
+    }
+
+    fn inode_attr(&mut self, inode: u64) -> Result<FileAttr, i32> {
+        let path = self.path(inode)?;
+        let entry = self.existing_entry(&path)?;
+        Ok(self.attr(inode, &entry))
+    }
+
+    /// Lists the names and types of the entries in the directory `dir`.
+    fn list_dir(&mut self, dir: &RepoPath) -> Result<BTreeMap<String, FileType>, i32> {
+        let mut entries = BTreeMap::new();
+        if !is_whited_out(&self.whiteouts, dir) {
+            let sub_tree = self
+                .base_tree
+                .sub_tree_recursive(dir)
+                .block_on()
+                .map_err(|_| libc::EIO)?;
+            if let Some(sub_tree) = sub_tree {
+                for name in sub_tree.names() {
+                    let path = dir.join(name);
+                    if self.whiteouts.contains(&path) {
+                        continue;
+                    }
+                    let value = sub_tree.value(name);
+                    let kind = if value.is_tree() {
+                        FileType::Directory
+                    } else {
+                        match value.as_resolved() {
+                            Some(Some(TreeValue::Symlink(_))) => FileType::Symlink,
+                            Some(Some(TreeValue::GitSubmodule(_))) => continue,
+                            _ => FileType::RegularFile,
+                        }
+                    };
+                    entries.insert(name.as_internal_str().to_owned(), kind);
+                }
+            }
+        }
+        let disk_dir = self.overlay.disk_path(dir);
+        match fs::read_dir(&disk_dir) {
+            Ok(dir_entries) => {
+                for entry in dir_entries {
+                    let entry = entry.map_err(|err| errno(&err))?;
+                    let Ok(name) = entry.file_name().into_string() else {
+                        continue;
+                    };
+                    let file_type = entry.file_type().map_err(|err| errno(&err))?;
+                    let kind = if file_type.is_dir() {
+                        FileType::Directory
+                    } else if file_type.is_symlink() {
+                        FileType::Symlink
+                    } else {
+                        FileType::RegularFile
+                    };
+                    entries.insert(name, kind);
+                }
+            }
+            Err(err) if err.kind() == io::ErrorKind::NotFound => {}
+            Err(err) if err.raw_os_error() == Some(libc::ENOTDIR) => {}
+            Err(err) => return Err(errno(&err)),
+        }
+        if dir.is_root() {
+            entries.insert(DOT_JJ.to_owned(), FileType::Symlink);
+        }
+        Ok(entries)
+    }
+
+    /// Makes sure the directory `dir` exists in the overlay.
+    fn copy_up_dir(&mut self, dir: &RepoPath) -> Result<(), i32> {
+        if !self.existing_entry(dir)?.is_dir() {
+            return Err(libc::ENOTDIR);
+        }
+        fs::create_dir_all(self.overlay.disk_path(dir)).map_err(|err| errno(&err))
+    }
+
+    /// Copies the entry at `path` to the overlay so that it can be modified.
+    /// The contents of directories remain served from the base tree.
+    fn copy_up(&mut self, path: &RepoPath) -> Result<(), i32> {
+        match self.existing_entry(path)? {
+            Entry::Overlay(_) => Ok(()),
+            Entry::BaseDir => self.copy_up_dir(path),
+            Entry::BaseFile(file) => {
+                self.copy_up_dir(path.parent().unwrap())?;
+                write_overlay_file(&self.overlay.disk_path(path), &file).map_err(|err| errno(&err))
+            }
+            Entry::DotJj => Err(libc::EPERM),
+        }
+    }
+
+    /// Copies the entry at `path` and everything below it to the overlay.
+    fn copy_up_recursive(&mut self, path: &RepoPath) -> Result<(), i32> {
+        let is_dir = self.existing_entry(path)?.is_dir();
+        self.copy_up(path)?;
+        if is_dir {
+            for name in self.list_dir(path)?.into_keys() {
+                let name = RepoPathComponent::new(&name).map_err(|_| libc::EINVAL)?;
+                self.copy_up_recursive(&path.join(name))?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Removes the entry at `path`, which must be a file or an empty
+    /// directory.
+    fn remove(&mut self, path: &RepoPath) -> Result<(), i32> {
+        let disk_path = self.overlay.disk_path(path);
+        match self.existing_entry(path)? {
+            Entry::DotJj => return Err(libc::EPERM),
+            Entry::Overlay(metadata) if metadata.is_dir() => {
+                fs::remove_dir(&disk_path).map_err(|err| errno(&err))?;
+            }
+            Entry::Overlay(_) => {
+                fs::remove_file(&disk_path).map_err(|err| errno(&err))?;
+            }
+            Entry::BaseDir | Entry::BaseFile(_) => {}
+        }
+        if self.has_visible_base_entry(path)? {
+            self.add_whiteout(path)?;
+        }
+        self.base_files.remove(path);
+        Ok(())
+    }
+
+    fn open_handle(&mut self, path: &RepoPath, flags: i32) -> Result<u64, i32> {
+        let access_mode = flags & libc::O_ACCMODE;
+        let file = OpenOptions::new()
+            .read(access_mode != libc::O_WRONLY)
+            .write(access_mode != libc::O_RDONLY)
+            .truncate(flags & libc::O_TRUNC != 0)
+            .open(self.overlay.disk_path(path))
+            .map_err(|err| errno(&err))?;
+        let handle = self.next_handle;
+        self.next_handle += 1;
+        self.handles.insert(handle, file);
+        Ok(handle)
+    }
+
+    fn read_at(file: &File, offset: u64, size: usize) -> io::Result<Vec<u8>> {
+        let mut buf = vec![0; size];
+        let mut len = 0;
+        while len < size {
+            let read = file.read_at(&mut buf[len..], offset + len as u64)?;
+            if read == 0 {
+                break;
+            }
+            len += read;
+        }
+        buf.truncate(len);
+        Ok(buf)
+    }
+
+    fn do_create(
+        &mut self,
+        parent: u64,
+        name: &OsStr,
+        mode: u32,
+        flags: i32,
+    ) -> Result<(FileAttr, u64), i32> {
+        let path = self.child_path(parent, name)?;
+        if self.entry(&path)?.is_some() {
+            return Err(libc::EEXIST);
+        }
+        self.copy_up_dir(path.parent().unwrap())?;
+        let disk_path = self.overlay.disk_path(&path);
+        OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .mode(mode)
+            .open(&disk_path)
+            .map_err(|err| errno(&err))?;
+        let handle = self.open_handle(&path, flags & !libc::O_TRUNC)?;
+        Ok((self.lookup_attr(&path)?, handle))
+    }
+
+    fn do_mkdir(&mut self, parent: u64, name: &OsStr, mode: u32) -> Result<FileAttr, i32> {
+        let path = self.child_path(parent, name)?;
+        if self.entry(&path)?.is_some() {
+            return Err(libc::EEXIST);
+        }
+        self.copy_up_dir(path.parent().unwrap())?;
+        let disk_path = self.overlay.disk_path(&path);
+        fs::create_dir(&disk_path).map_err(|err| errno(&err))?;
+        fs::set_permissions(&disk_path, fs::Permissions::from_mode(mode))
+            .map_err(|err| errno(&err))?;
+        self.lookup_attr(&path)
+    }
+
+    fn do_symlink(&mut self, parent: u64, name: &OsStr, target: &Path) -> Result<FileAttr, i32> {
+        let path = self.child_path(parent, name)?;
+        if self.entry(&path)?.is_some() {
+            return Err(libc::EEXIST);
+        }
+        self.copy_up_dir(path.parent().unwrap())?;
+        std::os::unix::fs::symlink(target, self.overlay.disk_path(&path))
+            .map_err(|err| errno(&err))?;
+        self.lookup_attr(&path)
+    }
+
+    fn do_rename(
+        &mut self,
+        parent: u64,
+        name: &OsStr,
+        new_parent: u64,
+        new_name: &OsStr,
+        flags: u32,
+    ) -> Result<(), i32> {
+        if flags & libc::RENAME_EXCHANGE != 0 {
+            return Err(libc::EINVAL);
+        }
+        let from = self.child_path(parent, name)?;
+        let to = self.child_path(new_parent, new_name)?;
+        if Self::is_dot_jj(&from) || Self::is_dot_jj(&to) {
+            return Err(libc::EPERM);
+        }
+        if to.starts_with(&from) && to != from {
+            return Err(libc::EINVAL);
+        }
+        let from_is_dir = self.existing_entry(&from)?.is_dir();
+        if let Some(to_entry) = self.entry(&to)? {
+            if flags & libc::RENAME_NOREPLACE != 0 {
+                return Err(libc::EEXIST);
+            }
+            if from == to {
+                return Ok(());
+            }
+            match (from_is_dir, to_entry.is_dir()) {
+                (true, false) => return Err(libc::ENOTDIR),
+                (false, true) => return Err(libc::EISDIR),
+                (true, true) if !self.list_dir(&to)?.is_empty() => return Err(libc::ENOTEMPTY),
+                _ => {}
+            }
+            self.remove(&to)?;
+        }
+        // TODO: Renaming a directory of the base tree copies all its files to
+        // the overlay. Record renames instead.
+        self.copy_up_recursive(&from)?;
+        self.copy_up_dir(to.parent().unwrap())?;
+        fs::rename(self.overlay.disk_path(&from), self.overlay.disk_path(&to))
+            .map_err(|err| errno(&err))?;
+        if self.has_visible_base_entry(&from)? {
+            self.add_whiteout(&from)?;
+        }
+        self.inodes.rename(&from, &to);
+        Ok(())
+    }
+
+    fn do_setattr(
+        &mut self,
+        inode: u64,
+        mode: Option<u32>,
+        size: Option<u64>,
+        handle: Option<u64>,
+    ) -> Result<FileAttr, i32> {
+        let path = self.path(inode)?;
+        if mode.is_some() || size.is_some() {
+            self.copy_up(&path)?;
+            self.base_files.remove(&path);
+        }
+        let disk_path = self.overlay.disk_path(&path);
+        if let Some(size) = size {
+            match handle.and_then(|handle| self.handles.get(&handle)) {
+                Some(file) => file.set_len(size),
+                None => OpenOptions::new()
+                    .write(true)
+                    .open(&disk_path)
+                    .and_then(|file| file.set_len(size)),
+            }
+            .map_err(|err| errno(&err))?;
+        }
+        if let Some(mode) = mode {
+            fs::set_permissions(&disk_path, fs::Permissions::from_mode(mode & 0o7777))
+                .map_err(|err| errno(&err))?;
+        }
+        // Timestamps of the base files aren't recorded, so they're ignored.
+        self.inode_attr(inode)
+    }
+
+    fn do_read(&mut self, inode: u64, handle: u64, offset: i64, size: u32) -> Result<Vec<u8>, i32> {
+        let offset = u64::try_from(offset).map_err(|_| libc::EINVAL)?;
+        if let Some(file) = self.handles.get(&handle) {
+            return Self::read_at(file, offset, size as usize).map_err(|err| errno(&err));
+        }
+        let path = self.path(inode)?;
+        match self.existing_entry(&path)? {
+            Entry::Overlay(_) => {
+                let file = File::open(self.overlay.disk_path(&path)).map_err(|err| errno(&err))?;
+                Self::read_at(&file, offset, size as usize).map_err(|err| errno(&err))
+            }
+            Entry::BaseFile(file) => {
+                let start = (offset as usize).min(file.content.len());
+                let end = (start + size as usize).min(file.content.len());
+                Ok(file.content[start..end].to_vec())
+            }
+            Entry::BaseDir => Err(libc::EISDIR),
+            Entry::DotJj => Err(libc::EINVAL),
+        }
+    }
+}
+
+fn reply_entry(reply: ReplyEntry, result: Result<FileAttr, i32>) {
+    match result {
+        Ok(attr) => reply.entry(&TTL, &attr, 0),
+        Err(err) => reply.error(err),
+    }
+}
+
+fn reply_empty(reply: ReplyEmpty, result: Result<(), i32>) {
+    match result {
+        Ok(()) => reply.ok(),
+        Err(err) => reply.error(err),
+    }
+}
+
+impl Filesystem for JjFilesystem {
+    fn lookup(&mut self, _req: &Request<'_>, parent: u64, name: &OsStr, reply: ReplyEntry) {
+        self.refresh();
+        let result = self
+            .child_path(parent, name)
+            .and_then(|path| self.lookup_attr(&path));
+        reply_entry(reply, result);
+    }
+
+    fn forget(&mut self, _req: &Request<'_>, ino: u64, nlookup: u64) {
+        self.inodes.forget(ino, nlookup);
+    }
+
+    fn getattr(&mut self, _req: &Request<'_>, ino: u64, _fh: Option<u64>, reply: ReplyAttr) {
+        self.refresh();
+        match self.inode_attr(ino) {
+            Ok(attr) => reply.attr(&TTL, &attr),
+            Err(err) => reply.error(err),
+        }
+    }
+
+    fn setattr(
+        &mut self,
+        _req: &Request<'_>,
+        ino: u64,
+        mode: Option<u32>,
+        _uid: Option<u32>,
+        _gid: Option<u32>,
+        size: Option<u64>,
+        _atime: Option<TimeOrNow>,
+        _mtime: Option<TimeOrNow>,
+        _ctime: Option<SystemTime>,
+        fh: Option<u64>,
+        _crtime: Option<SystemTime>,
+        _chgtime: Option<SystemTime>,
+        _bkuptime: Option<SystemTime>,
+        _flags: Option<u32>,
+        reply: ReplyAttr,
+    ) {
+        self.refresh();
+        match self.do_setattr(ino, mode, size, fh) {
+            Ok(attr) => reply.attr(&TTL, &attr),
+            Err(err) => reply.error(err),
+        }
+    }
+
+    fn readlink(&mut self, _req: &Request<'_>, ino: u64, reply: ReplyData) {
+        self.refresh();
+        let result = self.path(ino).and_then(|path| {
+            let target = match self.existing_entry(&path)? {
+                Entry::Overlay(_) => fs::read_link(self.overlay.disk_path(&path))
+                    .map_err(|err| errno(&err))?
+                    .into_os_string()
+                    .into_encoded_bytes(),
+                Entry::BaseFile(file) if file.is_symlink => file.content.clone(),
+                Entry::DotJj => self
+                    .dot_jj_target
+                    .clone()
+                    .into_os_string()
+                    .into_encoded_bytes(),
+                Entry::BaseFile(_) | Entry::BaseDir => return Err(libc::EINVAL),
+            };
+            Ok(target)
+        });
+        match result {
+            Ok(target) => reply.data(&target),
+            Err(err) => reply.error(err),
+        }
+    }
+
+    fn mkdir(
+        &mut self,
+        _req: &Request<'_>,
+        parent: u64,
+        name: &OsStr,
+        mode: u32,
+        umask: u32,
+        reply: ReplyEntry,
+    ) {
+        self.refresh();
+        let result = self.do_mkdir(parent, name, mode & !umask);
+        reply_entry(reply, result);
+    }
+
+    fn unlink(&mut self, _req: &Request<'_>, parent: u64, name: &OsStr, reply: ReplyEmpty) {
+        self.refresh();
+        let result = self.child_path(parent, name).and_then(|path| {
+            if self.existing_entry(&path)?.is_dir() {
+                return Err(libc::EISDIR);
+            }
+            self.remove(&path)
+        });
+        reply_empty(reply, result);
+    }
+
+    fn rmdir(&mut self, _req: &Request<'_>, parent: u64, name: &OsStr, reply: ReplyEmpty) {
+        self.refresh();
+        let result = self.child_path(parent, name).and_then(|path| {
+            if !self.existing_entry(&path)?.is_dir() {
+                return Err(libc::ENOTDIR);
+            }
+            if !self.list_dir(&path)?.is_empty() {
+                return Err(libc::ENOTEMPTY);
+            }
+            self.remove(&path)
+        });
+        reply_empty(reply, result);
+    }
+
+    fn symlink(
+        &mut self,
+        _req: &Request<'_>,
+        parent: u64,
+        link_name: &OsStr,
+        target: &Path,
+        reply: ReplyEntry,
+    ) {
+        self.refresh();
+        let result = self.do_symlink(parent, link_name, target);
+        reply_entry(reply, result);
+    }
+
+    fn rename(
+        &mut self,
+        _req: &Request<'_>,
+        parent: u64,
+        name: &OsStr,
+        newparent: u64,
+        newname: &OsStr,
+        flags: u32,
+        reply: ReplyEmpty,
+    ) {
+        self.refresh();
+        let result = self.do_rename(parent, name, newparent, newname, flags);
+        reply_empty(reply, result);
+    }
+
+    fn open(&mut self, _req: &Request<'_>, ino: u64, flags: i32, reply: ReplyOpen) {
+        self.refresh();
+        let result = self.path(ino).and_then(|path| {
+            let writes = flags & libc::O_ACCMODE != libc::O_RDONLY || flags & libc::O_TRUNC != 0;
+            if writes {
+                self.copy_up(&path)?;
+                self.base_files.remove(&path);
+            }
+            match self.existing_entry(&path)? {
+                Entry::Overlay(_) => self.open_handle(&path, flags),
+                // Read from the cached content.
+                Entry::BaseFile(_) => Ok(0),
+                Entry::BaseDir => Err(libc::EISDIR),
+                Entry::DotJj => Err(libc::EINVAL),
+            }
+        });
+        match result {
+            Ok(handle) => reply.opened(handle, 0),
+            Err(err) => reply.error(err),
+        }
+    }
+
+    fn read(
+        &mut self,
+        _req: &Request<'_>,
+        ino: u64,
+        fh: u64,
+        offset: i64,
+        size: u32,
+        _flags: i32,
+        _lock_owner: Option<u64>,
+        reply: ReplyData,
+    ) {
+        self.refresh();
+        match self.do_read(ino, fh, offset, size) {
+            Ok(data) => reply.data(&data),
+            Err(err) => reply.error(err),
+        }
+    }
+
+    fn write(
+        &mut self,
+        _req: &Request<'_>,
+        _ino: u64,
+        fh: u64,
+        offset: i64,
+        data: &[u8],
+        _write_flags: u32,
+        _flags: i32,
+        _lock_owner: Option<u64>,
+        reply: ReplyWrite,
+    ) {
+        let Some(file) = self.handles.get(&fh) else {
+            reply.error(libc::EBADF);
+            return;
+        };
+        let result = u64::try_from(offset)
+            .map_err(|_| libc::EINVAL)
+            .and_then(|offset| file.write_all_at(data, offset).map_err(|err| errno(&err)));
+        match result {
+            Ok(()) => reply.written(data.len() as u32),
+            Err(err) => reply.error(err),
+        }
+    }
+
+    fn fsync(&mut self, _req: &Request<'_>, _ino: u64, fh: u64, datasync: bool, reply: ReplyEmpty) {
+        let result = match self.handles.get(&fh) {
+            Some(file) if datasync => file.sync_data(),
+            Some(file) => file.sync_all(),
+            None => Ok(()),
+        };
+        reply_empty(reply, result.map_err(|err| errno(&err)));
+    }
+
+    fn release(
+        &mut self,
+        _req: &Request<'_>,
+        _ino: u64,
+        fh: u64,
+        _flags: i32,
+        _lock_owner: Option<u64>,
+        _flush: bool,
+        reply: ReplyEmpty,
+    ) {
+        self.handles.remove(&fh);
+        reply.ok();
+    }
+
+    fn readdir(
+        &mut self,
+        _req: &Request<'_>,
+        ino: u64,
+        _fh: u64,
+        offset: i64,
+        mut reply: ReplyDirectory,
+    ) {
+        self.refresh();
+        let result = self.path(ino).and_then(|dir| {
+            if !self.existing_entry(&dir)?.is_dir() {
+                return Err(libc::ENOTDIR);
+            }
+            let parent_inode = match dir.parent() {
+                Some(parent) => self.inodes.get_or_allocate(parent),
+                None => ino,
+            };
+            let mut entries = vec![
+                (ino, FileType::Directory, ".".to_owned()),
+                (parent_inode, FileType::Directory, "..".to_owned()),
+            ];
+            for (name, kind) in self.list_dir(&dir)? {
+                let name_component = RepoPathComponent::new(&name).map_err(|_| libc::EINVAL)?;
+                let inode = self.inodes.get_or_allocate(&dir.join(name_component));
+                entries.push((inode, kind, name));
+            }
+            Ok(entries)
+        });
+        match result {
+            Ok(entries) => {
+                let offset = usize::try_from(offset).unwrap_or(0);
+                for (i, (inode, kind, name)) in entries.into_iter().enumerate().skip(offset) {
+                    if reply.add(inode, (i + 1) as i64, kind, name) {
+                        break;
+                    }
+                }
+                reply.ok();
+            }
+            Err(err) => reply.error(err),
+        }
+    }
+
+    fn create(
+        &mut self,
+        _req: &Request<'_>,
+        parent: u64,
+        name: &OsStr,
+        mode: u32,
+        umask: u32,
+        flags: i32,
+        reply: ReplyCreate,
+    ) {
+        self.refresh();
+        match self.do_create(parent, name, mode & !umask, flags) {
+            Ok((attr, handle)) => reply.created(&TTL, &attr, 0, handle, 0),
+            Err(err) => reply.error(err),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn repo_path(value: &str) -> &RepoPath {
+        RepoPath::from_internal_string(value).unwrap()
+    }
+
+    #[test]
+    fn test_inode_table_forget() {
+        let mut inodes = InodeTable::new();
+        let a = inodes.lookup(repo_path("a"));
+        assert_eq!(inodes.lookup(repo_path("a")), a);
+        assert_eq!(inodes.path(a), Some(repo_path("a")));
+
+        // Listed entries don't get an inode recorded
+        let b = inodes.get_or_allocate(repo_path("b"));
+        assert_eq!(inodes.path(b), None);
+        assert_eq!(inodes.get_or_allocate(repo_path("a")), a);
+
+        // The inode is dropped once all its lookups are forgotten
+        inodes.forget(a, 1);
+        assert_eq!(inodes.path(a), Some(repo_path("a")));
+        inodes.forget(a, 1);
+        assert_eq!(inodes.path(a), None);
+        assert_eq!(inodes.get(repo_path("a")), None);
+        assert_ne!(inodes.lookup(repo_path("a")), a);
+
+        // The root is never forgotten
+        inodes.forget(fuser::FUSE_ROOT_ID, 1);
+        assert_eq!(inodes.path(fuser::FUSE_ROOT_ID), Some(RepoPath::root()));
+    }
+
+    #[test]
+    fn test_inode_table_rename() {
+        let mut inodes = InodeTable::new();
+        let dir = inodes.lookup(repo_path("dir"));
+        let file = inodes.lookup(repo_path("dir/sub/file"));
+        let sibling = inodes.lookup(repo_path("dir-2/file"));
+        let replaced = inodes.lookup(repo_path("new/file"));
+
+        inodes.rename(repo_path("dir"), repo_path("new"));
+        assert_eq!(inodes.path(dir), Some(repo_path("new")));
+        assert_eq!(inodes.path(file), Some(repo_path("new/sub/file")));
+        assert_eq!(inodes.path(sibling), Some(repo_path("dir-2/file")));
+        assert_eq!(inodes.path(replaced), None);
+        assert_eq!(inodes.get(repo_path("dir")), None);
+        assert_eq!(inodes.get(repo_path("dir/sub/file")), None);
+        assert_eq!(inodes.get(repo_path("new/file")), None);
+
+        // Forgetting an inode which was replaced is a no-op
+        inodes.forget(replaced, 1);
+        assert_eq!(inodes.path(file), Some(repo_path("new/sub/file")));
+    }
+}
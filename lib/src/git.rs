@@ -24,6 +24,7 @@ use std::num::NonZeroU32;
 use std::path::PathBuf;
 use std::str;
 use std::sync::Arc;
+use std::sync::atomic::AtomicBool;
 
 use bstr::BStr;
 use bstr::BString;
@@ -40,6 +41,8 @@ use crate::commit::Commit;
 use crate::file_util::IoResultExt as _;
 use crate::file_util::PathError;
 use crate::git_backend::GitBackend;
+use crate::git_gix::GitGixContext;
+use crate::git_gix::GitGixError;
 use crate::git_subprocess::GitSubprocessContext;
 use crate::git_subprocess::GitSubprocessError;
 use crate::matchers::EverythingMatcher;
@@ -64,6 +67,7 @@ use crate::repo::Repo;
 use crate::repo_path::RepoPath;
 use crate::revset::RevsetExpression;
 use crate::settings::GitSettings;
+use crate::settings::GitTransport;
 use crate::store::Store;
 use crate::str_util::StringPattern;
 use crate::view::View;
@@ -146,7 +150,7 @@ pub(crate) struct RefSpec {
     // Source and destination may be fully-qualified ref name, glob pattern, or
     // object ID. The GitRefNameBuf type shouldn't be used.
     source: Option<String>,
-    destination: String,
+    pub(crate) destination: String,
 }
 
 impl RefSpec {
@@ -1643,7 +1647,7 @@ fn add_ref(
     }
 }
 
-fn remove_ref(reference: gix::Reference) -> gix::refs::transaction::RefEdit {
+pub(crate) fn remove_ref(reference: gix::Reference) -> gix::refs::transaction::RefEdit {
     gix::refs::transaction::RefEdit {
         change: gix::refs::transaction::Change::Delete {
             expected: gix::refs::transaction::PreviousValue::MustExistAndMatch(
@@ -2097,6 +2101,8 @@ pub enum GitFetchError {
     RemoteName(#[from] GitRemoteNameError),
     #[error(transparent)]
     Subprocess(#[from] GitSubprocessError),
+    #[error(transparent)]
+    Gix(#[from] GitGixError),
 }
 
 struct FetchedBranches {
@@ -2134,6 +2140,7 @@ pub struct GitFetch<'a> {
     mut_repo: &'a mut MutableRepo,
     git_repo: Box<gix::Repository>,
     git_ctx: GitSubprocessContext<'a>,
+    gix_ctx: GitGixContext,
     git_settings: &'a GitSettings,
     fetched: Vec<FetchedBranches>,
 }
@@ -2147,10 +2154,12 @@ impl<'a> GitFetch<'a> {
         let git_repo = Box::new(git_backend.git_repo());
         let git_ctx =
             GitSubprocessContext::from_git_backend(git_backend, &git_settings.executable_path);
+        let gix_ctx = GitGixContext::from_git_backend(git_backend);
         Ok(GitFetch {
             mut_repo,
             git_repo,
             git_ctx,
+            gix_ctx,
             git_settings,
             fetched: vec![],
         })
@@ -2181,12 +2190,36 @@ impl<'a> GitFetch<'a> {
         }
         // At this point, we are only updating Git's remote tracking branches, not the
         // local branches.
-        let mut remaining_refspecs: Vec<_> = expand_fetch_refspecs(remote_name, branch_names)?;
-        if remaining_refspecs.is_empty() {
+        let refspecs: Vec<_> = expand_fetch_refspecs(remote_name, branch_names)?;
+        if refspecs.is_empty() {
             // Don't fall back to the base refspecs.
             return Ok(());
         }
 
+        match self.git_settings.transport {
+            GitTransport::Subprocess => {
+                self.fetch_with_subprocess(remote_name, refspecs, &mut callbacks, depth)?;
+            }
+            GitTransport::Gix => {
+                self.gix_ctx
+                    .fetch(remote_name, &refspecs, &mut callbacks, depth)?;
+            }
+        }
+
+        self.fetched.push(FetchedBranches {
+            remote: remote_name.to_owned(),
+            branches: branch_names.to_vec(),
+        });
+        Ok(())
+    }
+
+    fn fetch_with_subprocess(
+        &self,
+        remote_name: &RemoteName,
+        mut remaining_refspecs: Vec<RefSpec>,
+        callbacks: &mut RemoteCallbacks<'_>,
+        depth: Option<NonZeroU32>,
+    ) -> Result<(), GitFetchError> {
         let mut branches_to_prune = Vec::new();
         // git unfortunately errors out if one of the many refspecs is not found
         //
@@ -2197,7 +2230,7 @@ impl<'a> GitFetch<'a> {
         // meaning that the below cycle runs in O(#failed refspecs)
         while let Some(failing_refspec) =
             self.git_ctx
-                .spawn_fetch(remote_name, &remaining_refspecs, &mut callbacks, depth)?
+                .spawn_fetch(remote_name, &remaining_refspecs, callbacks, depth)?
         {
             tracing::debug!(failing_refspec, "failed to fetch ref");
             remaining_refspecs.retain(|r| r.source.as_ref() != Some(&failing_refspec));
@@ -2213,11 +2246,6 @@ impl<'a> GitFetch<'a> {
         // Even if git fetch has --prune, if a branch is not found it will not be
         // pruned on fetch
         self.git_ctx.spawn_branch_prune(&branches_to_prune)?;
-
-        self.fetched.push(FetchedBranches {
-            remote: remote_name.to_owned(),
-            branches: branch_names.to_vec(),
-        });
         Ok(())
     }
 
@@ -2234,7 +2262,10 @@ impl<'a> GitFetch<'a> {
         {
             return Err(GitFetchError::NoSuchRemote(remote_name.to_owned()));
         }
-        let default_branch = self.git_ctx.spawn_remote_show(remote_name)?;
+        let default_branch = match self.git_settings.transport {
+            GitTransport::Subprocess => self.git_ctx.spawn_remote_show(remote_name)?,
+            GitTransport::Gix => self.gix_ctx.remote_default_branch(remote_name)?,
+        };
         tracing::debug!(?default_branch);
         Ok(default_branch)
     }
@@ -2283,6 +2314,8 @@ pub enum GitPushError {
     #[error(transparent)]
     Subprocess(#[from] GitSubprocessError),
     #[error(transparent)]
+    Gix(#[from] GitGixError),
+    #[error(transparent)]
     UnexpectedBackend(#[from] UnexpectedGitBackendError),
 }
 
@@ -2385,12 +2418,21 @@ pub fn push_updates(
         return Err(GitPushError::NoSuchRemote(remote_name.to_owned()));
     }
 
-    let refs_to_push: Vec<RefToPush> = refspecs
-        .iter()
-        .map(|full_refspec| RefToPush::new(full_refspec, &qualified_remote_refs_expected_locations))
-        .collect();
-
-    let mut push_stats = git_ctx.spawn_push(remote_name, &refs_to_push, &mut callbacks)?;
+    let mut push_stats = match git_settings.transport {
+        GitTransport::Subprocess => {
+            let refs_to_push: Vec<RefToPush> = refspecs
+                .iter()
+                .map(|full_refspec| {
+                    RefToPush::new(full_refspec, &qualified_remote_refs_expected_locations)
+                })
+                .collect();
+            git_ctx.spawn_push(remote_name, &refs_to_push, &mut callbacks)?
+        }
+        GitTransport::Gix => {
+            let gix_ctx = GitGixContext::from_git_backend(git_backend);
+            gix_ctx.push(remote_name, updates, &mut callbacks)?
+        }
+    };
     push_stats.pushed.sort();
     push_stats.rejected.sort();
     push_stats.remote_rejected.sort();
@@ -2406,6 +2448,8 @@ pub struct RemoteCallbacks<'a> {
     pub get_ssh_keys: Option<&'a mut dyn FnMut(&str) -> Vec<PathBuf>>,
     pub get_password: Option<&'a mut dyn FnMut(&str, &str) -> Option<String>>,
     pub get_username_password: Option<&'a mut dyn FnMut(&str) -> Option<(String, String)>>,
+    /// Flag to stop the in-process transport early, e.g. on Ctrl-C
+    pub should_interrupt: Option<&'a AtomicBool>,
 }
 
 #[derive(Clone, Debug)]
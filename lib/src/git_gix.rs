@@ -0,0 +1,1247 @@
+// Copyright 2025 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::collections::VecDeque;
+use std::io;
+use std::io::Write as _;
+use std::num::NonZeroU32;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::Ordering;
+use std::sync::mpsc;
+use std::thread;
+use std::time::Duration;
+
+use bstr::BString;
+use bstr::ByteSlice as _;
+use gix::ObjectId;
+use gix::progress::Task;
+use gix::progress::tree;
+use gix::protocol::transport::Service;
+use gix::protocol::transport::client::MessageKind;
+use gix::protocol::transport::client::TransportWithoutIO as _;
+use gix::protocol::transport::client::WriteMode;
+use gix::refs::Target;
+use gix::refs::transaction::Change;
+use gix::refs::transaction::LogChange;
+use gix::refs::transaction::PreviousValue;
+use gix::refs::transaction::RefEdit;
+use gix::refs::transaction::RefLog;
+use gix::refspec::match_group;
+use gix::refspec::parse::Operation;
+use gix::remote::Direction;
+use gix::remote::fetch::Tags;
+use gix_pack::data::output;
+use itertools::Itertools as _;
+use thiserror::Error;
+
+use crate::git::GitPushStats;
+use crate::git::GitRefUpdate;
+use crate::git::Progress;
+use crate::git::RefSpec;
+use crate::git::RemoteCallbacks;
+use crate::git_backend::GitBackend;
+use crate::object_id::ObjectId as _;
+use crate::ref_name::GitRefName;
+use crate::ref_name::GitRefNameBuf;
+use crate::ref_name::RefNameBuf;
+use crate::ref_name::RemoteName;
+
+/// How often the fetch and push progress is reported.
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(100);
+
+type BoxedError = Box<dyn std::error::Error + Send + Sync>;
+
+/// Error originating from an in-process Git fetch or push
+#[derive(Error, Debug)]
+pub enum GitGixError {
+    #[error("Failed to look up the Git remote")]
+    FindRemote(#[source] Box<gix::remote::find::existing::Error>),
+    #[error("Failed to open the Git repository of the remote")]
+    OpenRemote(#[source] Box<gix::open::Error>),
+    #[error("Invalid refspec")]
+    RefSpec(#[source] gix::refspec::parse::Error),
+    #[error("Failed to connect to the Git remote")]
+    Connect(#[source] Box<gix::remote::connect::Error>),
+    #[error("Failed to list refs on the Git remote")]
+    RefMap(#[source] BoxedError),
+    #[error("Failed to fetch from the Git remote")]
+    Fetch(#[source] BoxedError),
+    #[error("Failed to push to the Git remote")]
+    Push(#[source] BoxedError),
+    #[error("Unexpected response from the Git remote: {0}")]
+    Protocol(String),
+    #[error("Failed to prune remote-tracking refs")]
+    Prune(#[source] BoxedError),
+    #[error("The in-process Git {0} panicked")]
+    Panicked(&'static str),
+}
+
+/// Context for running Git operations in-process
+pub(crate) struct GitGixContext {
+    git_repo: gix::ThreadSafeRepository,
+}
+
+impl GitGixContext {
+    pub(crate) fn from_git_backend(git_backend: &GitBackend) -> Self {
+        Self {
+            git_repo: git_backend.git_repo().into_sync(),
+        }
+    }
+
+    /// Fetches the `refspecs` from the remote, and prunes remote-tracking refs
+    /// matching the `refspecs` which no longer exist on the remote.
+    ///
+    /// Unlike `git fetch`, refspecs that don't match any remote ref aren't
+    /// reported as errors.
+    pub(crate) fn fetch(
+        &self,
+        remote_name: &RemoteName,
+        refspecs: &[RefSpec],
+        callbacks: &mut RemoteCallbacks<'_>,
+        depth: Option<NonZeroU32>,
+    ) -> Result<(), GitGixError> {
+        if refspecs.is_empty() {
+            return Ok(());
+        }
+        self.run_in_thread("fetch", callbacks, |progress, should_interrupt| {
+            self.fetch_in_thread(remote_name, refspecs, depth, progress, should_interrupt)
+        })
+    }
+
+    /// Pushes the `updates` to the remote, and updates the remote-tracking
+    /// branches of the pushed refs.
+    ///
+    /// Like `git push --force-with-lease`, a ref is only updated if it's at
+    /// the expected position on the remote.
+    pub(crate) fn push(
+        &self,
+        remote_name: &RemoteName,
+        updates: &[GitRefUpdate],
+        callbacks: &mut RemoteCallbacks<'_>,
+    ) -> Result<GitPushStats, GitGixError> {
+        self.run_in_thread("push", callbacks, |progress, should_interrupt| {
+            self.push_in_thread(remote_name, updates, progress, should_interrupt)
+        })
+    }
+
+    /// Runs `f` in a new thread, and reports its progress to the `callbacks`
+    /// until it finishes.
+    fn run_in_thread<T: Send>(
+        &self,
+        name: &'static str,
+        callbacks: &mut RemoteCallbacks<'_>,
+        f: impl FnOnce(tree::Item, &AtomicBool) -> Result<T, GitGixError> + Send,
+    ) -> Result<T, GitGixError> {
+        // gix::Repository isn't Sync, so the operation runs with its own
+        // thread-local repository while the calling thread reports the progress.
+        let root = tree::Root::new();
+        let not_interrupted = AtomicBool::new(false);
+        let should_interrupt = callbacks.should_interrupt.unwrap_or(&not_interrupted);
+        thread::scope(|s| {
+            let (tx, rx) = mpsc::channel();
+            let progress = root.add_child(name);
+            let handle = s.spawn(move || {
+                tx.send(f(progress, should_interrupt)).ok();
+            });
+            let mut tasks = Vec::new();
+            loop {
+                match rx.recv_timeout(PROGRESS_INTERVAL) {
+                    Ok(result) => return result,
+                    Err(mpsc::RecvTimeoutError::Timeout) => {
+                        if let Some(cb) = callbacks.progress.as_mut() {
+                            root.sorted_snapshot(&mut tasks);
+                            if let Some(progress) = to_progress(&tasks) {
+                                cb(&progress);
+                            }
+                        }
+                    }
+                    Err(mpsc::RecvTimeoutError::Disconnected) => {
+                        // The thread panicked without sending the result. Joining
+                        // it keeps the panic from propagating out of the scope.
+                        handle.join().ok();
+                        return Err(GitGixError::Panicked(name));
+                    }
+                }
+            }
+        })
+    }
+
+    fn fetch_in_thread(
+        &self,
+        remote_name: &RemoteName,
+        refspecs: &[RefSpec],
+        depth: Option<NonZeroU32>,
+        mut progress: tree::Item,
+        should_interrupt: &AtomicBool,
+    ) -> Result<(), GitGixError> {
+        let git_repo = self.git_repo.to_thread_local();
+        let mut remote = find_remote(&git_repo, remote_name)?;
+        if let Some(remote_repo) = open_local_remote(&remote, Direction::Fetch)? {
+            return fetch_from_local_repo(
+                &remote,
+                &remote_repo,
+                remote_name,
+                refspecs,
+                depth,
+                progress,
+                should_interrupt,
+            );
+        }
+        // Don't fall back to the refspecs configured for the remote.
+        remote
+            .replace_refspecs(
+                refspecs
+                    .iter()
+                    .map(|spec| BString::from(spec.to_git_format())),
+                Direction::Fetch,
+            )
+            .map_err(GitGixError::RefSpec)?;
+        let prepare = remote
+            .connect(Direction::Fetch)
+            .map_err(|err| GitGixError::Connect(Box::new(err)))?
+            .prepare_fetch(progress.add_child("list refs"), Default::default())
+            .map_err(|err| match err {
+                gix::remote::fetch::prepare::Error::RefMap(err) => GitGixError::RefMap(err.into()),
+                gix::remote::fetch::prepare::Error::MissingRefSpecs => {
+                    unreachable!("refspecs should be non-empty")
+                }
+            })?;
+        let fetched_refs: HashSet<BString> = prepare
+            .ref_map()
+            .mappings
+            .iter()
+            .filter_map(|mapping| mapping.local.clone())
+            .collect();
+        let shallow = match depth {
+            Some(depth) => gix::remote::fetch::Shallow::DepthAtRemote(depth),
+            None => gix::remote::fetch::Shallow::NoChange,
+        };
+        match prepare
+            .with_shallow(shallow)
+            .receive(progress, should_interrupt)
+        {
+            Ok(_) => {}
+            // None of the refspecs matched, so there's nothing to fetch, but
+            // the remote-tracking refs may still have to be pruned.
+            Err(gix::remote::fetch::Error::NoMapping { .. }) => {}
+            Err(err) => return Err(GitGixError::Fetch(err.into())),
+        }
+
+        prune_remote_tracking_refs(&git_repo, remote_name, refspecs, &fetched_refs)
+            .map_err(GitGixError::Prune)
+    }
+
+    fn push_in_thread(
+        &self,
+        remote_name: &RemoteName,
+        updates: &[GitRefUpdate],
+        progress: tree::Item,
+        should_interrupt: &AtomicBool,
+    ) -> Result<GitPushStats, GitGixError> {
+        let git_repo = self.git_repo.to_thread_local();
+        let remote = find_remote(&git_repo, remote_name)?;
+        let push_stats = if let Some(remote_repo) = open_local_remote(&remote, Direction::Push)? {
+            push_to_local_repo(&git_repo, &remote_repo, updates, progress, should_interrupt)?
+        } else {
+            push_to_transport(&remote, updates, progress, should_interrupt)?
+        };
+        update_remote_tracking_refs(&git_repo, remote_name, updates, &push_stats.pushed)
+            .map_err(GitGixError::Push)?;
+        Ok(push_stats)
+    }
+
+    /// Queries the remote for the branch its `HEAD` points to.
+    pub(crate) fn remote_default_branch(
+        &self,
+        remote_name: &RemoteName,
+    ) -> Result<Option<RefNameBuf>, GitGixError> {
+        let git_repo = self.git_repo.to_thread_local();
+        let mut remote = find_remote(&git_repo, remote_name)?;
+        if let Some(remote_repo) = open_local_remote(&remote, Direction::Fetch)? {
+            let head = remote_repo
+                .head()
+                .map_err(|err| GitGixError::RefMap(err.into()))?;
+            let default_branch = match &head.kind {
+                gix::head::Kind::Symbolic(reference) => reference
+                    .name
+                    .as_bstr()
+                    .to_str()
+                    .ok()
+                    .and_then(|name| name.strip_prefix("refs/heads/"))
+                    .map(RefNameBuf::from),
+                gix::head::Kind::Unborn(_) | gix::head::Kind::Detached { .. } => None,
+            };
+            tracing::debug!(?default_branch);
+            return Ok(default_branch);
+        }
+        remote
+            .replace_refspecs(["HEAD"], Direction::Fetch)
+            .map_err(GitGixError::RefSpec)?;
+        let (ref_map, _handshake) = remote
+            .connect(Direction::Fetch)
+            .map_err(|err| GitGixError::Connect(Box::new(err)))?
+            .ref_map(gix::progress::Discard, Default::default())
+            .map_err(|err| GitGixError::RefMap(err.into()))?;
+        let default_branch = ref_map.remote_refs.iter().find_map(|remote_ref| {
+            let gix::protocol::handshake::Ref::Symbolic {
+                full_ref_name,
+                target,
+                ..
+            } = remote_ref
+            else {
+                return None;
+            };
+            if full_ref_name != "HEAD" {
+                return None;
+            }
+            let name = target.to_str().ok()?.strip_prefix("refs/heads/")?;
+            Some(RefNameBuf::from(name))
+        });
+        tracing::debug!(?default_branch);
+        Ok(default_branch)
+    }
+}
+
+fn find_remote<'repo>(
+    git_repo: &'repo gix::Repository,
+    remote_name: &RemoteName,
+) -> Result<gix::Remote<'repo>, GitGixError> {
+    git_repo
+        .find_remote(remote_name.as_str())
+        .map_err(|err| GitGixError::FindRemote(Box::new(err)))
+}
+
+/// Opens the repository of the remote if it's on the local filesystem.
+///
+/// Such remotes are accessed directly instead of spawning `git-upload-pack` or
+/// `git-receive-pack`.
+fn open_local_remote(
+    remote: &gix::Remote,
+    direction: Direction,
+) -> Result<Option<gix::Repository>, GitGixError> {
+    let Some(url) = remote.url(direction) else {
+        return Ok(None);
+    };
+    if url.scheme != gix::url::Scheme::File {
+        return Ok(None);
+    }
+    let path = gix::path::from_bstr(url.path.as_bstr());
+    let remote_repo = gix::open(path).map_err(|err| GitGixError::OpenRemote(Box::new(err)))?;
+    Ok(Some(remote_repo))
+}
+
+/// Ref in the repository of a local remote
+struct LocalRef {
+    name: BString,
+    target: ObjectId,
+    peeled: ObjectId,
+}
+
+/// Lists the refs that `git-upload-pack` would advertise.
+fn list_local_refs(git_repo: &gix::Repository) -> Result<Vec<LocalRef>, BoxedError> {
+    let mut refs = Vec::new();
+    for reference in git_repo.references()?.all()? {
+        let mut reference = reference?;
+        let target = reference.follow_to_object()?.detach();
+        let peeled = reference.peel_to_id_in_place()?.detach();
+        refs.push(LocalRef {
+            name: reference.name().as_bstr().to_owned(),
+            target,
+            peeled,
+        });
+    }
+    Ok(refs)
+}
+
+/// Fetches from the repository of a local remote, which is read directly.
+fn fetch_from_local_repo(
+    remote: &gix::Remote,
+    remote_repo: &gix::Repository,
+    remote_name: &RemoteName,
+    refspecs: &[RefSpec],
+    depth: Option<NonZeroU32>,
+    mut progress: tree::Item,
+    should_interrupt: &AtomicBool,
+) -> Result<(), GitGixError> {
+    let git_repo = remote.repo();
+    let remote_refs = list_local_refs(remote_repo).map_err(GitGixError::RefMap)?;
+    let specs: Vec<BString> = refspecs
+        .iter()
+        .map(|spec| spec.to_git_format().into())
+        .collect();
+    let specs: Vec<_> = specs
+        .iter()
+        .map(|spec| gix::refspec::parse(spec.as_ref(), Operation::Fetch))
+        .try_collect()
+        .map_err(GitGixError::RefSpec)?;
+    let items = remote_refs.iter().map(|remote_ref| match_group::Item {
+        full_ref_name: remote_ref.name.as_ref(),
+        target: &remote_ref.target,
+        object: (remote_ref.peeled != remote_ref.target).then_some(&*remote_ref.peeled),
+    });
+    let mappings: Vec<(BString, ObjectId)> = gix::refspec::MatchGroup::from_fetch_specs(specs)
+        .match_lhs(items)
+        .mappings
+        .into_iter()
+        .filter_map(|mapping| {
+            let remote_ref = &remote_refs[mapping.item_index?];
+            Some((mapping.rhs?.into_owned(), remote_ref.target))
+        })
+        .collect();
+
+    let fetch_tags = remote.fetch_tags();
+    let mut tips = mappings.iter().map(|(_, id)| *id).collect_vec();
+    if fetch_tags == Tags::All {
+        tips.extend(
+            remote_refs
+                .iter()
+                .filter(|remote_ref| remote_ref.name.starts_with(b"refs/tags/"))
+                .map(|remote_ref| remote_ref.target),
+        );
+    }
+    let mut objects = find_objects_to_send(remote_repo, tips, |id| git_repo.has_object(id), depth)
+        .map_err(GitGixError::Fetch)?;
+    // Like `git fetch`, follow the tags pointing to the fetched history
+    // unless they exist locally.
+    let mut tags = Vec::new();
+    if fetch_tags != Tags::None {
+        let fetched_commits: HashSet<ObjectId> = objects.commits().collect();
+        for remote_ref in &remote_refs {
+            if !remote_ref.name.starts_with(b"refs/tags/")
+                || (fetch_tags == Tags::Included
+                    && !fetched_commits.contains(&remote_ref.peeled)
+                    && !git_repo.has_object(remote_ref.peeled))
+                || git_repo
+                    .try_find_reference(remote_ref.name.as_bstr())
+                    .map_err(|err| GitGixError::Fetch(err.into()))?
+                    .is_some()
+            {
+                continue;
+            }
+            if !git_repo.has_object(remote_ref.target) {
+                objects.as_is.push(remote_ref.target);
+            }
+            tags.push((remote_ref.name.clone(), remote_ref.target));
+        }
+    }
+
+    if !objects.is_empty() {
+        let mut pack = Vec::new();
+        write_pack(
+            remote_repo,
+            &objects,
+            &mut pack,
+            &mut progress,
+            should_interrupt,
+        )
+        .map_err(GitGixError::Fetch)?;
+        receive_pack(git_repo, &pack, &mut progress, should_interrupt)
+            .map_err(GitGixError::Fetch)?;
+    }
+    if !objects.shallow.is_empty() {
+        add_shallow_commits(git_repo, &objects.shallow).map_err(GitGixError::Fetch)?;
+    }
+
+    let mut edits = Vec::new();
+    for (name, id) in &mappings {
+        let current = find_ref_target(git_repo, name.as_bstr()).map_err(GitGixError::Fetch)?;
+        if current != Some(*id) {
+            edits.push(ref_edit(
+                name.as_bstr(),
+                PreviousValue::Any,
+                Some(*id),
+                "fetch",
+            )?);
+        }
+    }
+    for (name, id) in &tags {
+        edits.push(ref_edit(
+            name.as_bstr(),
+            PreviousValue::MustNotExist,
+            Some(*id),
+            "fetch",
+        )?);
+    }
+    if !edits.is_empty() {
+        git_repo
+            .edit_references(edits)
+            .map_err(|err| GitGixError::Fetch(err.into()))?;
+    }
+
+    let fetched_refs: HashSet<BString> = mappings.into_iter().map(|(name, _)| name).collect();
+    prune_remote_tracking_refs(git_repo, remote_name, refspecs, &fetched_refs)
+        .map_err(GitGixError::Prune)
+}
+
+/// Result of checking a ref update against the actual position of the ref on
+/// the remote
+#[derive(Debug, Eq, PartialEq)]
+enum LeaseCheck {
+    /// The ref is already at the new position.
+    UpToDate,
+    /// The ref has unexpectedly moved on the remote.
+    Stale,
+    /// The ref should be moved from `old` to `new`.
+    Update {
+        old: Option<ObjectId>,
+        new: Option<ObjectId>,
+    },
+}
+
+/// Checks the `update` like `git push --force-with-lease=<ref>:<expected>`
+/// would.
+fn check_lease(update: &GitRefUpdate, actual: Option<ObjectId>) -> LeaseCheck {
+    let expected = update.expected_current_target.as_ref().map(to_git_id);
+    let new = update.new_target.as_ref().map(to_git_id);
+    // Moving the ref to where it already is succeeds even if it wasn't expected
+    // to be there.
+    if new.is_some() && new == actual {
+        LeaseCheck::UpToDate
+    } else if actual != expected {
+        LeaseCheck::Stale
+    } else if new.is_none() && actual.is_none() {
+        LeaseCheck::UpToDate
+    } else {
+        LeaseCheck::Update { old: actual, new }
+    }
+}
+
+fn push_to_local_repo(
+    git_repo: &gix::Repository,
+    remote_repo: &gix::Repository,
+    updates: &[GitRefUpdate],
+    mut progress: tree::Item,
+    should_interrupt: &AtomicBool,
+) -> Result<GitPushStats, GitGixError> {
+    let mut push_stats = GitPushStats::default();
+    let mut tips = Vec::new();
+    let mut edits = Vec::new();
+    for update in updates {
+        let name = &update.qualified_name;
+        let actual =
+            find_ref_target(remote_repo, name.as_str().into()).map_err(GitGixError::Push)?;
+        match check_lease(update, actual) {
+            LeaseCheck::UpToDate => push_stats.pushed.push(name.clone()),
+            LeaseCheck::Stale => push_stats
+                .rejected
+                .push((name.clone(), Some("stale info".to_owned()))),
+            LeaseCheck::Update { old, new } => {
+                if let Some(reason) = check_current_branch(remote_repo, name, new.is_some()) {
+                    push_stats
+                        .remote_rejected
+                        .push((name.clone(), Some(reason.to_owned())));
+                    continue;
+                }
+                let expected = match old {
+                    Some(id) => PreviousValue::MustExistAndMatch(Target::Object(id)),
+                    None => PreviousValue::MustNotExist,
+                };
+                edits.push(ref_edit(name.as_str().into(), expected, new, "push")?);
+                tips.extend(new);
+                push_stats.pushed.push(name.clone());
+            }
+        }
+    }
+
+    let objects = find_objects_to_send(git_repo, tips, |id| remote_repo.has_object(id), None)
+        .map_err(GitGixError::Push)?;
+    if !objects.is_empty() {
+        let mut pack = Vec::new();
+        write_pack(
+            git_repo,
+            &objects,
+            &mut pack,
+            &mut progress,
+            should_interrupt,
+        )
+        .map_err(GitGixError::Push)?;
+        receive_pack(remote_repo, &pack, &mut progress, should_interrupt)
+            .map_err(GitGixError::Push)?;
+    }
+    if !edits.is_empty() {
+        let committer = git_repo
+            .committer()
+            .transpose()
+            .map_err(|err| GitGixError::Push(err.into()))?;
+        remote_repo
+            .edit_references_as(edits, committer)
+            .map_err(|err| GitGixError::Push(err.into()))?;
+    }
+    Ok(push_stats)
+}
+
+/// Returns the reason why `git-receive-pack` would refuse to update or delete
+/// the branch checked out in a non-bare repository.
+fn check_current_branch(
+    remote_repo: &gix::Repository,
+    name: &GitRefName,
+    is_update: bool,
+) -> Option<&'static str> {
+    if remote_repo.is_bare() {
+        return None;
+    }
+    let head_name = remote_repo.head_name().ok()??;
+    if head_name.as_bstr() != name.as_str() {
+        return None;
+    }
+    let (key, reason) = if is_update {
+        (
+            "receive.denyCurrentBranch",
+            "branch is currently checked out",
+        )
+    } else {
+        (
+            "receive.denyDeleteCurrent",
+            "deletion of the current branch prohibited",
+        )
+    };
+    let config = remote_repo.config_snapshot();
+    match config.string(key).as_deref().map(|value| value.as_bytes()) {
+        Some(b"ignore" | b"warn" | b"false") => None,
+        _ => Some(reason),
+    }
+}
+
+/// Pushes by talking to `git-receive-pack` over the transport of the remote.
+fn push_to_transport(
+    remote: &gix::Remote,
+    updates: &[GitRefUpdate],
+    mut progress: tree::Item,
+    should_interrupt: &AtomicBool,
+) -> Result<GitPushStats, GitGixError> {
+    let git_repo = remote.repo();
+    let mut connection = remote
+        .connect(Direction::Push)
+        .map_err(|err| GitGixError::Connect(Box::new(err)))?;
+    let url = remote
+        .url(Direction::Push)
+        .expect("connected remote should have URL")
+        .to_owned();
+    let mut authenticate = connection
+        .configured_credentials(url.clone())
+        .map_err(|err| GitGixError::RefMap(err.into()))?;
+    let transport = connection.transport_mut();
+    let transport_options = git_repo
+        .transport_options(
+            url.to_bstring().as_bstr(),
+            remote.name().map(|name| name.as_bstr()),
+        )
+        .map_err(|err| GitGixError::RefMap(err.into()))?;
+    if let Some(options) = &transport_options {
+        transport
+            .configure(&**options)
+            .map_err(GitGixError::RefMap)?;
+    }
+    let handshake = gix::protocol::handshake(
+        &mut *transport,
+        Service::ReceivePack,
+        &mut authenticate,
+        Vec::new(),
+        &mut progress.add_child("list refs"),
+    )
+    .map_err(|err| GitGixError::RefMap(err.into()))?;
+    let remote_refs: HashMap<&[u8], ObjectId> = handshake
+        .refs
+        .iter()
+        .flatten()
+        .filter_map(|remote_ref| {
+            let (name, target, _peeled) = remote_ref.unpack();
+            Some((name.as_bytes(), target?.to_owned()))
+        })
+        .collect();
+
+    let mut push_stats = GitPushStats::default();
+    let mut commands = Vec::new();
+    for update in updates {
+        let name = &update.qualified_name;
+        let actual = remote_refs.get(name.as_str().as_bytes()).copied();
+        match check_lease(update, actual) {
+            LeaseCheck::UpToDate => push_stats.pushed.push(name.clone()),
+            LeaseCheck::Stale => push_stats
+                .rejected
+                .push((name.clone(), Some("stale info".to_owned()))),
+            LeaseCheck::Update { new: None, .. }
+                if !handshake.capabilities.contains("delete-refs") =>
+            {
+                push_stats.remote_rejected.push((
+                    name.clone(),
+                    Some("remote does not support deleting refs".to_owned()),
+                ));
+            }
+            LeaseCheck::Update { old, new } => commands.push((name, old, new)),
+        }
+    }
+    if commands.is_empty() {
+        // Dropping the connection tells the remote that there are no updates.
+        return Ok(push_stats);
+    }
+
+    let null_id = ObjectId::null(git_repo.object_hash());
+    let report_status = handshake.capabilities.contains("report-status");
+    let capabilities = ["report-status", "delete-refs", "ofs-delta"]
+        .into_iter()
+        .filter(|name| handshake.capabilities.contains(name))
+        .join(" ");
+    let has_ids = remote_refs
+        .values()
+        .filter(|id| git_repo.has_object(id))
+        .copied()
+        .collect_vec();
+    let objects = find_objects_to_send_with_walk(
+        git_repo,
+        commands.iter().filter_map(|(_, _, new)| *new),
+        has_ids,
+    )
+    .map_err(GitGixError::Push)?;
+
+    let io_error = |err: io::Error| GitGixError::Push(err.into());
+    let mut writer = transport
+        .request(WriteMode::Binary, MessageKind::Flush, false)
+        .map_err(|err| GitGixError::Push(err.into()))?;
+    for (i, (name, old, new)) in commands.iter().enumerate() {
+        let mut line = format!(
+            "{old} {new} {name}",
+            old = old.unwrap_or(null_id),
+            new = new.unwrap_or(null_id),
+            name = name.as_str(),
+        );
+        if i == 0 {
+            line.push('\0');
+            line.push_str(&capabilities);
+        }
+        writer.write_all(line.as_bytes()).map_err(io_error)?;
+    }
+    let reader = if commands.iter().any(|(_, _, new)| new.is_some()) {
+        writer.write_message(MessageKind::Flush).map_err(io_error)?;
+        let (mut pack_writer, reader) = writer.into_parts();
+        write_pack(
+            git_repo,
+            &objects,
+            &mut pack_writer,
+            &mut progress,
+            should_interrupt,
+        )
+        .map_err(GitGixError::Push)?;
+        pack_writer.flush().map_err(io_error)?;
+        reader
+    } else {
+        // Deletions don't send a pack.
+        writer.into_read().map_err(io_error)?
+    };
+
+    if report_status {
+        let report = parse_report_status(reader)?;
+        for (name, _, _) in commands {
+            match report.get(name.as_str()) {
+                Some(None) => push_stats.pushed.push(name.clone()),
+                Some(Some(reason)) => push_stats
+                    .remote_rejected
+                    .push((name.clone(), Some(reason.clone()))),
+                None => {
+                    return Err(GitGixError::Protocol(format!(
+                        "no status reported for {name}",
+                        name = name.as_str()
+                    )));
+                }
+            }
+        }
+    } else {
+        push_stats
+            .pushed
+            .extend(commands.into_iter().map(|(name, _, _)| name.clone()));
+    }
+    Ok(push_stats)
+}
+
+/// Parses the `report-status` response of `git-receive-pack` into the
+/// rejection reason of each ref, or `None` if it was updated.
+fn parse_report_status(
+    reader: impl io::BufRead,
+) -> Result<HashMap<String, Option<String>>, GitGixError> {
+    let mut lines = reader.lines();
+    let unpack_status = lines
+        .next()
+        .transpose()
+        .map_err(|err| GitGixError::Push(err.into()))?
+        .ok_or_else(|| GitGixError::Protocol("missing unpack status".to_owned()))?;
+    if unpack_status.strip_prefix("unpack ").is_none() {
+        return Err(GitGixError::Protocol(format!(
+            "unknown unpack status: {unpack_status}"
+        )));
+    }
+    let mut report = HashMap::new();
+    for line in lines {
+        let line = line.map_err(|err| GitGixError::Push(err.into()))?;
+        if let Some(name) = line.strip_prefix("ok ") {
+            report.insert(name.to_owned(), None);
+        } else if let Some(rest) = line.strip_prefix("ng ") {
+            let (name, reason) = rest.split_once(' ').unwrap_or((rest, ""));
+            report.insert(name.to_owned(), Some(reason.to_owned()));
+        } else {
+            return Err(GitGixError::Protocol(format!("unknown ref status: {line}")));
+        }
+    }
+    Ok(report)
+}
+
+/// Sets the remote-tracking branches of the pushed branches, like `git push`
+/// does.
+fn update_remote_tracking_refs(
+    git_repo: &gix::Repository,
+    remote_name: &RemoteName,
+    updates: &[GitRefUpdate],
+    pushed: &[GitRefNameBuf],
+) -> Result<(), BoxedError> {
+    let mut edits = Vec::new();
+    for update in updates {
+        if !pushed.contains(&update.qualified_name) {
+            continue;
+        }
+        let Some(branch) = update.qualified_name.as_str().strip_prefix("refs/heads/") else {
+            continue;
+        };
+        let name = format!(
+            "refs/remotes/{remote}/{branch}",
+            remote = remote_name.as_str()
+        );
+        let current = find_ref_target(git_repo, name.as_str().into())?;
+        let new = update.new_target.as_ref().map(to_git_id);
+        if current != new {
+            edits.push(ref_edit(
+                name.as_str().into(),
+                PreviousValue::Any,
+                new,
+                "update by push",
+            )?);
+        }
+    }
+    if !edits.is_empty() {
+        git_repo.edit_references(edits)?;
+    }
+    Ok(())
+}
+
+/// Objects to be sent in a pack
+#[derive(Debug, Default)]
+struct ObjectsToSend {
+    /// Commits to be sent along with the objects they add compared to their
+    /// parents.
+    commits: Vec<ObjectId>,
+    /// Commits to be sent along with their whole tree since their parents
+    /// aren't.
+    shallow: Vec<ObjectId>,
+    /// Objects to be sent without the objects they refer to.
+    as_is: Vec<ObjectId>,
+    /// Trees and blobs to be sent along with the objects they contain.
+    trees: Vec<ObjectId>,
+}
+
+impl ObjectsToSend {
+    fn is_empty(&self) -> bool {
+        self.commits.is_empty()
+            && self.shallow.is_empty()
+            && self.as_is.is_empty()
+            && self.trees.is_empty()
+    }
+
+    fn commits(&self) -> impl Iterator<Item = ObjectId> {
+        self.commits.iter().chain(&self.shallow).copied()
+    }
+}
+
+/// Finds the objects reachable from the `tips` that the receiving side
+/// doesn't have according to `is_known`. Commits deeper than `depth` from the
+/// tips aren't included.
+///
+/// The receiving side is assumed to have all ancestors of the commits it
+/// has.
+fn find_objects_to_send(
+    git_repo: &gix::Repository,
+    tips: impl IntoIterator<Item = ObjectId>,
+    is_known: impl Fn(&gix::oid) -> bool,
+    depth: Option<NonZeroU32>,
+) -> Result<ObjectsToSend, BoxedError> {
+    let mut objects = ObjectsToSend::default();
+    let mut seen = HashSet::new();
+    let mut queue = VecDeque::new();
+    for mut id in tips {
+        while !is_known(&id) && seen.insert(id) {
+            match git_repo.find_header(id)?.kind() {
+                gix::object::Kind::Tag => {
+                    objects.as_is.push(id);
+                    id = git_repo.find_tag(id)?.target_id()?.detach();
+                }
+                gix::object::Kind::Commit => {
+                    queue.push_back((id, 1));
+                    break;
+                }
+                gix::object::Kind::Tree | gix::object::Kind::Blob => {
+                    objects.trees.push(id);
+                    break;
+                }
+            }
+        }
+    }
+    // Visit the commits breadth-first so they are first reached at their
+    // minimum depth.
+    while let Some((id, level)) = queue.pop_front() {
+        let commit = git_repo.find_commit(id)?;
+        let parent_ids = commit
+            .parent_ids()
+            .map(|id| id.detach())
+            .filter(|id| !is_known(id))
+            .collect_vec();
+        if depth.is_some_and(|depth| level >= depth.get()) && !parent_ids.is_empty() {
+            objects.shallow.push(id);
+            continue;
+        }
+        objects.commits.push(id);
+        for parent_id in parent_ids {
+            if seen.insert(parent_id) {
+                queue.push_back((parent_id, level + 1));
+            }
+        }
+    }
+    Ok(objects)
+}
+
+/// Finds the commits reachable from the `tips` but not from the commits the
+/// receiving side `has`.
+fn find_objects_to_send_with_walk(
+    git_repo: &gix::Repository,
+    tips: impl IntoIterator<Item = ObjectId>,
+    has: Vec<ObjectId>,
+) -> Result<ObjectsToSend, BoxedError> {
+    let commits = git_repo
+        .rev_walk(tips)
+        .with_hidden(has)
+        .all()?
+        .map_ok(|info| info.id)
+        .try_collect()?;
+    Ok(ObjectsToSend {
+        commits,
+        ..Default::default()
+    })
+}
+
+/// Writes a pack of the `objects` to `out`.
+fn write_pack(
+    git_repo: &gix::Repository,
+    objects: &ObjectsToSend,
+    out: &mut dyn io::Write,
+    progress: &mut tree::Item,
+    should_interrupt: &AtomicBool,
+) -> Result<(), BoxedError> {
+    use output::count::objects::ObjectExpansion;
+
+    let mut db = (*git_repo.objects).clone();
+    db.prevent_pack_unload();
+    let db = WithEmptyTree(db);
+    let counter = progress.add_child("counting objects");
+    counter.init(None, gix::progress::count("objects"));
+    let mut counts = Vec::new();
+    for (ids, expansion) in [
+        (
+            &objects.commits,
+            ObjectExpansion::TreeAdditionsComparedToAncestor,
+        ),
+        (&objects.shallow, ObjectExpansion::TreeContents),
+        (&objects.as_is, ObjectExpansion::AsIs),
+        (&objects.trees, ObjectExpansion::TreeContents),
+    ] {
+        let (new_counts, _) = output::count::objects_unthreaded(
+            &db,
+            &mut ids.iter().map(|id| Ok(*id)),
+            &counter,
+            should_interrupt,
+            expansion,
+        )?;
+        counts.extend(new_counts);
+    }
+    let mut seen = HashSet::new();
+    counts.retain(|count| seen.insert(count.id));
+
+    let num_entries = u32::try_from(counts.len())?;
+    let entries = output::entry::iter_from_counts(
+        counts,
+        db,
+        Box::new(progress.add_child("compressing objects")),
+        Default::default(),
+    );
+    let entries = gix::features::parallel::InOrderIter::from(entries);
+    let pack = output::bytes::FromEntriesIter::new(
+        entries,
+        out,
+        num_entries,
+        Default::default(),
+        git_repo.object_hash(),
+    );
+    for written in pack {
+        written?;
+    }
+    Ok(())
+}
+
+/// Object database which also finds the empty tree, which Git doesn't
+/// necessarily store but considers to always exist.
+#[derive(Clone)]
+struct WithEmptyTree<T>(T);
+
+impl<T: gix_pack::Find> gix_pack::Find for WithEmptyTree<T> {
+    fn contains(&self, id: &gix::oid) -> bool {
+        id.to_owned().is_empty_tree() || self.0.contains(id)
+    }
+
+    fn try_find_cached<'a>(
+        &self,
+        id: &gix::oid,
+        buffer: &'a mut Vec<u8>,
+        pack_cache: &mut dyn gix_pack::cache::DecodeEntry,
+    ) -> Result<
+        Option<(gix::objs::Data<'a>, Option<gix_pack::data::entry::Location>)>,
+        gix::objs::find::Error,
+    > {
+        if id.to_owned().is_empty_tree() && !self.0.contains(id) {
+            buffer.clear();
+            return Ok(Some((
+                gix::objs::Data::new(gix::object::Kind::Tree, buffer),
+                None,
+            )));
+        }
+        self.0.try_find_cached(id, buffer, pack_cache)
+    }
+
+    fn location_by_oid(
+        &self,
+        id: &gix::oid,
+        buf: &mut Vec<u8>,
+    ) -> Option<gix_pack::data::entry::Location> {
+        self.0.location_by_oid(id, buf)
+    }
+
+    fn pack_offsets_and_oid(
+        &self,
+        pack_id: u32,
+    ) -> Option<Vec<(gix_pack::data::Offset, ObjectId)>> {
+        self.0.pack_offsets_and_oid(pack_id)
+    }
+
+    fn entry_by_location(
+        &self,
+        location: &gix_pack::data::entry::Location,
+    ) -> Option<gix_pack::find::Entry> {
+        self.0.entry_by_location(location)
+    }
+}
+
+/// Indexes the `pack` into the object database of the `git_repo`.
+fn receive_pack(
+    git_repo: &gix::Repository,
+    pack: &[u8],
+    progress: &mut tree::Item,
+    should_interrupt: &AtomicBool,
+) -> Result<(), BoxedError> {
+    let pack_dir = git_repo.objects.store_ref().path().join("pack");
+    gix::odb::pack::Bundle::write_to_directory(
+        &mut &pack[..],
+        Some(&pack_dir),
+        progress,
+        should_interrupt,
+        None::<gix::objs::find::Never>,
+        gix::odb::pack::bundle::write::Options {
+            object_hash: git_repo.object_hash(),
+            ..Default::default()
+        },
+    )?;
+    Ok(())
+}
+
+/// Records the `commits` as the shallow boundary of the `git_repo`.
+fn add_shallow_commits(git_repo: &gix::Repository, commits: &[ObjectId]) -> Result<(), BoxedError> {
+    let mut shallow_commits = git_repo
+        .shallow_commits()?
+        .map(|commits| commits.to_vec())
+        .unwrap_or_default();
+    shallow_commits.extend_from_slice(commits);
+    shallow_commits.sort();
+    shallow_commits.dedup();
+    let mut file = gix::lock::File::acquire_to_update_resource(
+        git_repo.shallow_file(),
+        gix::lock::acquire::Fail::Immediately,
+        None,
+    )?;
+    for id in shallow_commits {
+        writeln!(file, "{id}")?;
+    }
+    file.commit().map_err(|err| err.error)?;
+    Ok(())
+}
+
+/// Returns the object the ref points to, following symbolic refs.
+fn find_ref_target(
+    git_repo: &gix::Repository,
+    name: &bstr::BStr,
+) -> Result<Option<ObjectId>, BoxedError> {
+    let Some(mut reference) = git_repo.try_find_reference(name)? else {
+        return Ok(None);
+    };
+    Ok(Some(reference.follow_to_object()?.detach()))
+}
+
+/// Creates an edit that points the ref to `new`, or deletes it if `new` is
+/// `None`.
+fn ref_edit(
+    name: &bstr::BStr,
+    expected: PreviousValue,
+    new: Option<ObjectId>,
+    message: &str,
+) -> Result<RefEdit, GitGixError> {
+    let change = match new {
+        Some(id) => Change::Update {
+            log: LogChange {
+                message: message.into(),
+                ..Default::default()
+            },
+            expected,
+            new: Target::Object(id),
+        },
+        None => Change::Delete {
+            expected,
+            log: RefLog::AndReference,
+        },
+    };
+    let name = name
+        .try_into()
+        .map_err(|err: gix::validate::reference::name::Error| GitGixError::RefMap(err.into()))?;
+    Ok(RefEdit {
+        change,
+        name,
+        deref: false,
+    })
+}
+
+fn to_git_id(id: &crate::backend::CommitId) -> ObjectId {
+    ObjectId::from_bytes_or_panic(id.as_bytes())
+}
+
+/// Deletes remote-tracking refs matching the destination of the `refspecs`
+/// unless they have just been fetched.
+fn prune_remote_tracking_refs(
+    git_repo: &gix::Repository,
+    remote_name: &RemoteName,
+    refspecs: &[RefSpec],
+    fetched_refs: &HashSet<BString>,
+) -> Result<(), BoxedError> {
+    let prefix = format!("refs/remotes/{remote}/", remote = remote_name.as_str());
+    let edits: Vec<_> = git_repo
+        .references()?
+        .prefixed(prefix.as_str())?
+        .filter_ok(|reference| {
+            let name = reference.name().as_bstr();
+            !fetched_refs.contains(name)
+                && refspecs
+                    .iter()
+                    .any(|spec| refspec_destination_matches(&spec.destination, name))
+        })
+        .map_ok(crate::git::remove_ref)
+        .try_collect()?;
+    if !edits.is_empty() {
+        tracing::debug!(?edits, "pruning remote-tracking refs");
+        git_repo.edit_references(edits)?;
+    }
+    Ok(())
+}
+
+/// Matches ref name against the refspec destination, which may contain one
+/// `*` that matches any sequence of characters.
+fn refspec_destination_matches(destination: &str, name: &[u8]) -> bool {
+    if let Some((prefix, suffix)) = destination.split_once('*') {
+        name.len() >= prefix.len() + suffix.len()
+            && name.starts_with(prefix.as_bytes())
+            && name.ends_with(suffix.as_bytes())
+    } else {
+        name == destination.as_bytes()
+    }
+}
+
+/// Sums up the progress of the tasks with known total.
+fn to_progress<K>(tasks: &[(K, Task)]) -> Option<Progress> {
+    let (done, total) = tasks
+        .iter()
+        .filter_map(|(_, task)| task.progress.as_ref())
+        .filter_map(|value| {
+            let total = value.done_at?;
+            Some((value.step.load(Ordering::Relaxed).min(total), total))
+        })
+        .fold((0, 0), |(done, total), (d, t)| (done + d, total + t));
+    (total > 0).then(|| Progress {
+        bytes_downloaded: None,
+        overall: done as f32 / total as f32,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_refspec_destination_matches() {
+        assert!(refspec_destination_matches(
+            "refs/remotes/origin/main",
+            b"refs/remotes/origin/main"
+        ));
+        assert!(!refspec_destination_matches(
+            "refs/remotes/origin/main",
+            b"refs/remotes/origin/main2"
+        ));
+        assert!(refspec_destination_matches(
+            "refs/remotes/origin/*",
+            b"refs/remotes/origin/feature/a"
+        ));
+        assert!(refspec_destination_matches(
+            "refs/remotes/origin/f*x",
+            b"refs/remotes/origin/fix"
+        ));
+        assert!(!refspec_destination_matches(
+            "refs/remotes/origin/f*x",
+            b"refs/remotes/origin/fox/y"
+        ));
+        assert!(!refspec_destination_matches(
+            "refs/remotes/origin/ab*ba",
+            b"refs/remotes/origin/aba"
+        ));
+    }
+
+    #[test]
+    fn test_parse_report_status() {
+        let report = parse_report_status(
+            &b"unpack ok\nok refs/heads/main\nng refs/heads/topic non-fast-forward\n"[..],
+        )
+        .unwrap();
+        assert_eq!(
+            report,
+            HashMap::from([
+                ("refs/heads/main".to_owned(), None),
+                (
+                    "refs/heads/topic".to_owned(),
+                    Some("non-fast-forward".to_owned())
+                ),
+            ])
+        );
+        assert!(parse_report_status(&b""[..]).is_err());
+        assert!(parse_report_status(&b"unpack ok\nfoo\n"[..]).is_err());
+    }
+}
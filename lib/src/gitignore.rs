@@ -21,6 +21,8 @@ use std::path::Path;
 use std::path::PathBuf;
 use std::sync::Arc;
 
+use blake2::Blake2b512;
+use digest::Digest as _;
 use ignore::gitignore;
 use thiserror::Error;
 
@@ -47,6 +49,7 @@ pub enum GitIgnoreError {
 pub struct GitIgnoreFile {
     parent: Option<Arc<GitIgnoreFile>>,
     matcher: gitignore::Gitignore,
+    fingerprint: digest::Output<Blake2b512>,
 }
 
 impl GitIgnoreFile {
@@ -54,6 +57,7 @@ impl GitIgnoreFile {
         Arc::new(Self {
             parent: None,
             matcher: gitignore::Gitignore::empty(),
+            fingerprint: Blake2b512::digest(b""),
         })
     }
 
@@ -95,7 +99,17 @@ impl GitIgnoreFile {
         } else {
             Some(self.clone())
         };
-        Ok(Arc::new(Self { parent, matcher }))
+        let fingerprint = Blake2b512::new()
+            .chain_update(self.fingerprint)
+            .chain_update((prefix.len() as u64).to_le_bytes())
+            .chain_update(prefix)
+            .chain_update(input)
+            .finalize();
+        Ok(Arc::new(Self {
+            parent,
+            matcher,
+            fingerprint,
+        }))
     }
 
     /// Concatenates new `.gitignore` file at the `prefix` directory.
@@ -118,6 +132,12 @@ impl GitIgnoreFile {
         }
     }
 
+    /// Returns a hash of the patterns and their locations. Ignore files with
+    /// the same fingerprint match the same paths.
+    pub fn fingerprint(&self) -> &[u8] {
+        &self.fingerprint
+    }
+
     fn matches_helper(&self, path: &str, is_dir: bool) -> bool {
         iter::successors(Some(self), |file| file.parent.as_deref())
             .find_map(|file| {
@@ -457,4 +477,23 @@ mod tests {
             .unwrap();
         assert!(ignore.matches("foo/bar.ext"));
     }
+
+    #[test]
+    fn test_gitignore_fingerprint() {
+        let chain = |prefix: &str, input: &[u8]| {
+            GitIgnoreFile::empty()
+                .chain("", Path::new(""), b"foo\n")
+                .unwrap()
+                .chain(prefix, Path::new(""), input)
+                .unwrap()
+        };
+        let base = chain("dir/", b"bar\n");
+        assert_eq!(base.fingerprint(), chain("dir/", b"bar\n").fingerprint());
+        assert_ne!(base.fingerprint(), chain("dir/", b"baz\n").fingerprint());
+        assert_ne!(base.fingerprint(), chain("di/", b"r/bar\n").fingerprint());
+        assert_ne!(
+            base.fingerprint(),
+            GitIgnoreFile::empty().fingerprint(),
+        );
+    }
 }
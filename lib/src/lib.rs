@@ -31,6 +31,7 @@ pub mod content_hash;
 pub mod absorb;
 pub mod annotate;
 pub mod backend;
+pub mod bisect;
 pub mod commit;
 pub mod commit_builder;
 pub mod config;
@@ -52,6 +53,8 @@ mod fileset_parser;
 pub mod fix;
 pub mod fmt_util;
 pub mod fsmonitor;
+#[cfg(all(target_os = "linux", feature = "fuse"))]
+pub mod fuse_working_copy;
 #[cfg(feature = "git")]
 pub mod git;
 #[cfg(not(feature = "git"))]
@@ -70,6 +73,8 @@ pub mod git {
 #[cfg(feature = "git")]
 pub mod git_backend;
 #[cfg(feature = "git")]
+mod git_gix;
+#[cfg(feature = "git")]
 mod git_subprocess;
 pub mod gitignore;
 pub mod gpg_signing;
@@ -87,12 +92,15 @@ pub mod op_heads_store;
 pub mod op_store;
 pub mod op_walk;
 pub mod operation;
+pub mod patch;
+pub mod path_collision;
 #[expect(missing_docs)]
 pub mod protos;
 pub mod ref_name;
 pub mod refs;
 pub mod repo;
 pub mod repo_path;
+pub mod rerere;
 pub mod revset;
 mod revset_parser;
 pub mod rewrite;
@@ -106,6 +114,7 @@ pub mod signing;
 pub mod simple_backend;
 pub mod simple_op_heads_store;
 pub mod simple_op_store;
+pub mod sparse;
 pub mod ssh_signing;
 pub mod stacked_table;
 pub mod store;
@@ -122,6 +131,7 @@ pub mod union_find;
 pub mod view;
 pub mod working_copy;
 pub mod workspace;
+pub mod workspace_store;
 
 #[cfg(test)]
 mod tests {
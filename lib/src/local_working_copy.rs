@@ -17,6 +17,7 @@
 
 use std::any::Any;
 use std::cmp::Ordering;
+use std::collections::BTreeMap;
 use std::collections::HashSet;
 use std::error::Error;
 use std::fs;
@@ -35,14 +36,21 @@ use std::os::unix::fs::PermissionsExt as _;
 use std::path::Path;
 use std::path::PathBuf;
 use std::slice;
+use std::str;
 use std::sync::Arc;
 use std::sync::OnceLock;
+use std::sync::atomic;
+use std::sync::atomic::AtomicBool;
 use std::sync::mpsc::Sender;
 use std::sync::mpsc::channel;
+use std::time::Duration;
+use std::time::Instant;
 use std::time::UNIX_EPOCH;
 
+use bstr::BString;
 use either::Either;
 use futures::StreamExt as _;
+use futures::future;
 use itertools::EitherOrBoth;
 use itertools::Itertools as _;
 use once_cell::unsync::OnceCell;
@@ -72,6 +80,7 @@ use crate::config::ConfigGetError;
 use crate::conflicts;
 use crate::conflicts::ConflictMarkerStyle;
 use crate::conflicts::MIN_CONFLICT_MARKER_LEN;
+use crate::conflicts::MaterializedFileConflictValue;
 use crate::conflicts::MaterializedTreeValue;
 use crate::conflicts::choose_materialized_conflict_marker_len;
 use crate::conflicts::materialize_merge_result_to_bytes_with_marker_len;
@@ -79,13 +88,18 @@ use crate::conflicts::materialize_tree_value;
 pub use crate::eol::EolConversionMode;
 use crate::eol::TargetEolStrategy;
 use crate::eol::create_target_eol_strategy;
+use crate::eol::is_binary;
 use crate::file_util::BlockingAsyncReader;
 use crate::file_util::check_symlink_support;
 use crate::file_util::copy_async_to_sync;
 use crate::file_util::try_symlink;
 use crate::fsmonitor::FsmonitorSettings;
+#[cfg(all(target_os = "linux", feature = "inotify"))]
+use crate::fsmonitor::InotifyConfig;
 #[cfg(feature = "watchman")]
 use crate::fsmonitor::WatchmanConfig;
+#[cfg(all(target_os = "linux", feature = "inotify"))]
+use crate::fsmonitor::inotify;
 #[cfg(feature = "watchman")]
 use crate::fsmonitor::watchman;
 use crate::gitignore::GitIgnoreFile;
@@ -96,6 +110,7 @@ use crate::matchers::FilesMatcher;
 use crate::matchers::IntersectionMatcher;
 use crate::matchers::Matcher;
 use crate::matchers::PrefixMatcher;
+use crate::matchers::Visit;
 use crate::merge::Merge;
 use crate::merge::MergeBuilder;
 use crate::merge::MergedTreeValue;
@@ -104,12 +119,20 @@ use crate::merged_tree::MergedTreeBuilder;
 use crate::merged_tree::TreeDiffEntry;
 use crate::object_id::ObjectId as _;
 use crate::op_store::OperationId;
+use crate::path_collision::PathCollision;
+use crate::path_collision::PathFolding;
+use crate::path_collision::PathFoldingProber;
+use crate::path_collision::find_added_path_collisions;
 use crate::ref_name::WorkspaceName;
 use crate::ref_name::WorkspaceNameBuf;
 use crate::repo_path::RepoPath;
 use crate::repo_path::RepoPathBuf;
 use crate::repo_path::RepoPathComponent;
 use crate::settings::UserSettings;
+use crate::sparse::SparseMode;
+use crate::sparse::SparseProfileError;
+use crate::sparse::new_sparse_matcher;
+use crate::sparse::read_sparse_profile;
 use crate::store::Store;
 use crate::tree::Tree;
 use crate::working_copy::CheckoutError;
@@ -120,7 +143,11 @@ use crate::working_copy::ResetError;
 use crate::working_copy::SnapshotError;
 use crate::working_copy::SnapshotOptions;
 use crate::working_copy::SnapshotProgress;
+use crate::working_copy::SnapshotRuleAction;
+use crate::working_copy::SnapshotRuleCheck;
+use crate::working_copy::SnapshotRules;
 use crate::working_copy::SnapshotStats;
+use crate::working_copy::SnapshotWarning;
 use crate::working_copy::UntrackedReason;
 use crate::working_copy::WorkingCopy;
 use crate::working_copy::WorkingCopyFactory;
@@ -165,6 +192,9 @@ pub enum FileType {
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
 pub struct MaterializedConflictData {
     pub conflict_marker_len: u32,
+    /// Whether the terms of the conflict were written to sidecar files next to
+    /// the conflicted file.
+    pub sidecar_files: bool,
 }
 
 #[derive(Debug, PartialEq, Eq, Clone)]
@@ -448,6 +478,10 @@ pub struct TreeStateSettings {
     /// file to the backend, and vice versa when it checks out code onto your
     /// filesystem.
     pub eol_conversion_mode: EolConversionMode,
+    /// How conflicts in binary files are written to the working copy.
+    pub binary_conflict_style: BinaryConflictStyle,
+    /// How conflicts between symlinks are written to the working copy.
+    pub symlink_conflict_style: SymlinkConflictStyle,
 }
 
 impl TreeStateSettings {
@@ -455,10 +489,119 @@ impl TreeStateSettings {
     pub fn try_from_user_settings(user_settings: &UserSettings) -> Result<Self, ConfigGetError> {
         Ok(Self {
             eol_conversion_mode: EolConversionMode::try_from_settings(user_settings)?,
+            binary_conflict_style: user_settings.get("working-copy.binary-conflicts")?,
+            symlink_conflict_style: user_settings.get("working-copy.symlink-conflicts")?,
         })
     }
 }
 
+/// How conflicts in binary files are written to the working copy.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum BinaryConflictStyle {
+    /// Write conflict markers like for text files.
+    #[default]
+    Markers,
+    /// Write each side and base to a sidecar file next to the conflicted file,
+    /// such as `file.jjconflict-side-1`. Editing a sidecar file resolves the
+    /// conflict to its contents.
+    SidecarFiles,
+}
+
+/// How conflicts between symlinks are written to the working copy.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum SymlinkConflictStyle {
+    /// Write a file describing the conflict. Edits to it are ignored.
+    #[default]
+    Description,
+    /// Write a file with conflict markers between the symlink targets. The
+    /// conflict is resolved to a symlink once the markers are removed.
+    Text,
+}
+
+/// Infix of the names of the sidecar files of a conflicted file.
+const CONFLICT_SIDECAR_INFIX: &str = ".jjconflict-";
+
+/// Returns the file names of the sidecar files of a conflict at `file_name`,
+/// paired with the terms of `merge` they're for. The bases come first. The
+/// sides are numbered from 1 like in conflict markers, and the bases from 0.
+fn conflict_sidecar_names<'a, T>(file_name: &str, merge: &'a Merge<T>) -> Vec<(String, &'a T)> {
+    let bases = merge
+        .removes()
+        .enumerate()
+        .map(|(i, term)| (format!("{file_name}{CONFLICT_SIDECAR_INFIX}base-{i}"), term));
+    let sides = merge.adds().enumerate().map(|(i, term)| {
+        (
+            format!("{file_name}{CONFLICT_SIDECAR_INFIX}side-{}", i + 1),
+            term,
+        )
+    });
+    bases.chain(sides).collect()
+}
+
+/// Contents of a binary file whose conflict was written to sidecar files.
+fn conflict_sidecar_description(file_name: &str, file_ids: &Merge<Option<FileId>>) -> String {
+    let mut description = "This binary file has conflicts. The sides and bases of the conflict \
+                           were written to these files:\n"
+        .to_owned();
+    for (sidecar_name, id) in conflict_sidecar_names(file_name, file_ids) {
+        if id.is_some() {
+            description.push_str(&format!("  {sidecar_name}\n"));
+        }
+    }
+    description.push_str(
+        "To resolve the conflict, replace this file or modify one of the sidecar files.\n",
+    );
+    description
+}
+
+/// Returns the name of the conflicted file if `file_name` looks like the name
+/// of one of its sidecar files.
+fn conflict_sidecar_owner(file_name: &str) -> Option<&str> {
+    let (owner, suffix) = file_name.rsplit_once(CONFLICT_SIDECAR_INFIX)?;
+    let number = suffix
+        .strip_prefix("base-")
+        .or_else(|| suffix.strip_prefix("side-"))?;
+    (!owner.is_empty() && !number.is_empty() && number.bytes().all(|b| b.is_ascii_digit()))
+        .then_some(owner)
+}
+
+/// Returns the symlink ids of a conflict whose terms are all symlinks or absent.
+fn to_symlink_merge(value: &MergedTreeValue) -> Option<Merge<Option<SymlinkId>>> {
+    value
+        .try_map(|term| match term {
+            None => Ok(None),
+            Some(TreeValue::Symlink(id)) => Ok(Some(id.clone())),
+            Some(_) => Err(()),
+        })
+        .ok()
+}
+
+/// Removes the sidecar files of the conflicted file at `disk_path`.
+fn remove_conflict_sidecars(disk_path: &Path) -> io::Result<()> {
+    let (Some(dir), Some(file_name)) = (disk_path.parent(), disk_path.file_name()) else {
+        return Ok(());
+    };
+    let entries = match dir.read_dir() {
+        Ok(entries) => entries,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(()),
+        Err(err) => return Err(err),
+    };
+    for entry in entries {
+        let entry = entry?;
+        let name = entry.file_name();
+        let is_sidecar = name
+            .to_str()
+            .and_then(conflict_sidecar_owner)
+            .is_some_and(|owner| file_name.to_str() == Some(owner));
+        if is_sidecar {
+            fs::remove_file(entry.path())?;
+        }
+    }
+    Ok(())
+}
+
 pub struct TreeState {
     store: Arc<Store>,
     working_copy_path: PathBuf,
@@ -467,6 +610,8 @@ pub struct TreeState {
     file_states: FileStatesMap,
     // Currently only path prefixes
     sparse_patterns: Vec<RepoPathBuf>,
+    sparse_mode: SparseMode,
+    sparse_profile: Option<RepoPathBuf>,
     own_mtime: MillisSinceEpoch,
     symlink_support: bool,
 
@@ -474,8 +619,39 @@ pub struct TreeState {
     /// the repo is configured to use the Watchman filesystem monitor and
     /// Watchman has been queried at least once.
     watchman_clock: Option<crate::protos::working_copy::WatchmanClock>,
+    /// The most recent cursor returned by the inotify daemon. Will only be set
+    /// if the repo is configured to use the inotify filesystem monitor.
+    inotify_cursor: Option<crate::protos::working_copy::InotifyCursor>,
+    /// Cached listings of the directories which had no untracked entries when
+    /// they were last read.
+    directory_states: BTreeMap<RepoPathBuf, DirectoryState>,
+    /// Progress of the checkout being done, or of an interrupted one.
+    pending_checkout: Option<PendingCheckout>,
 
     target_eol_strategy: TargetEolStrategy,
+    binary_conflict_style: BinaryConflictStyle,
+    symlink_conflict_style: SymlinkConflictStyle,
+}
+
+/// Progress of a checkout. The paths sorting before `next_path` were updated to
+/// the target tree, and the others still match the current tree.
+#[derive(Clone, Debug, Eq, PartialEq)]
+struct PendingCheckout {
+    target_tree_id: MergedTreeId,
+    next_path: RepoPathBuf,
+}
+
+/// How often the progress of a checkout is saved.
+const CHECKOUT_JOURNAL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Cached listing of a directory. If the directory mtime and the ignore
+/// patterns haven't changed, the directory doesn't have to be read again.
+#[derive(Clone, Debug, Eq, PartialEq)]
+struct DirectoryState {
+    mtime: MillisSinceEpoch,
+    ignore_fingerprint: Vec<u8>,
+    /// Names of the subdirectories which aren't ignored, sorted.
+    dirs: Vec<String>,
 }
 
 fn file_state_from_proto(proto: &crate::protos::working_copy::FileState) -> FileState {
@@ -501,6 +677,7 @@ fn file_state_from_proto(proto: &crate::protos::working_copy::FileState) -> File
         materialized_conflict_data: proto.materialized_conflict_data.as_ref().map(|data| {
             MaterializedConflictData {
                 conflict_marker_len: data.conflict_marker_len,
+                sidecar_files: data.sidecar_files,
             }
         }),
     }
@@ -525,6 +702,7 @@ fn file_state_to_proto(file_state: &FileState) -> crate::protos::working_copy::F
     proto.materialized_conflict_data = file_state.materialized_conflict_data.map(|data| {
         crate::protos::working_copy::MaterializedConflictData {
             conflict_marker_len: data.conflict_marker_len,
+            sidecar_files: data.sidecar_files,
         }
     });
     proto
@@ -555,6 +733,22 @@ fn is_file_state_entries_proto_unique_and_sorted(
         .is_sorted_by(|path1, path2| path1 < path2)
 }
 
+fn sparse_mode_from_proto(
+    proto: Option<&crate::protos::working_copy::SparsePatterns>,
+) -> SparseMode {
+    match proto.map(|proto| proto.mode()) {
+        None | Some(crate::protos::working_copy::SparseMode::Prefix) => SparseMode::Prefix,
+        Some(crate::protos::working_copy::SparseMode::Cone) => SparseMode::Cone,
+    }
+}
+
+fn sparse_mode_to_proto(mode: SparseMode) -> crate::protos::working_copy::SparseMode {
+    match mode {
+        SparseMode::Prefix => crate::protos::working_copy::SparseMode::Prefix,
+        SparseMode::Cone => crate::protos::working_copy::SparseMode::Cone,
+    }
+}
+
 fn sparse_patterns_from_proto(
     proto: Option<&crate::protos::working_copy::SparsePatterns>,
 ) -> Vec<RepoPathBuf> {
@@ -571,6 +765,41 @@ fn sparse_patterns_from_proto(
     sparse_patterns
 }
 
+fn directory_states_from_proto(
+    data: Vec<crate::protos::working_copy::DirectoryStateEntry>,
+) -> BTreeMap<RepoPathBuf, DirectoryState> {
+    data.into_iter()
+        .filter_map(|entry| {
+            let path = RepoPathBuf::from_internal_string(entry.path).ok()?;
+            let proto = entry.state?;
+            let state = DirectoryState {
+                mtime: MillisSinceEpoch(proto.mtime_millis_since_epoch),
+                ignore_fingerprint: proto.ignore_fingerprint,
+                dirs: proto.dirs,
+            };
+            Some((path, state))
+        })
+        .collect()
+}
+
+fn directory_states_to_proto(
+    directory_states: &BTreeMap<RepoPathBuf, DirectoryState>,
+) -> Vec<crate::protos::working_copy::DirectoryStateEntry> {
+    directory_states
+        .iter()
+        .map(
+            |(path, state)| crate::protos::working_copy::DirectoryStateEntry {
+                path: path.as_internal_file_string().to_owned(),
+                state: Some(crate::protos::working_copy::DirectoryState {
+                    mtime_millis_since_epoch: state.mtime.0,
+                    ignore_fingerprint: state.ignore_fingerprint.clone(),
+                    dirs: state.dirs.clone(),
+                }),
+            },
+        )
+        .collect()
+}
+
 /// Creates intermediate directories from the `working_copy_path` to the
 /// `repo_path` parent. Returns disk path for the `repo_path` file.
 ///
@@ -697,6 +926,45 @@ fn can_create_new_file(disk_path: &Path) -> Result<bool, CheckoutError> {
     Ok(new_file_created)
 }
 
+/// Outcome of updating a single path on disk.
+enum PathUpdate {
+    /// The path couldn't be written, for example because an untracked file is
+    /// in the way.
+    Skipped,
+    Removed,
+    Written(FileState),
+}
+
+/// Returns true if a checkout from `old_tree` to `new_tree` can be resumed by
+/// updating `path` and the paths sorting after it.
+///
+/// The diff for file system puts a file after the directory it replaces, so
+/// that isn't the case if `path` or its parent is a directory replaced by a
+/// file or vice versa.
+fn is_checkout_resumable_at(
+    old_tree: &MergedTree,
+    new_tree: &MergedTree,
+    path: &RepoPath,
+) -> BackendResult<bool> {
+    for dir in path.ancestors().filter(|dir| !dir.is_root()) {
+        let before = old_tree.path_value(dir)?;
+        let after = new_tree.path_value(dir)?;
+        if (before.is_tree() && after.is_file_like()) || (before.is_file_like() && after.is_tree())
+        {
+            return Ok(false);
+        }
+    }
+    Ok(true)
+}
+
+fn add_checkout_stats(stats: &mut CheckoutStats, other: CheckoutStats) {
+    stats.updated_files += other.updated_files;
+    stats.added_files += other.added_files;
+    stats.removed_files += other.removed_files;
+    stats.skipped_files += other.skipped_files;
+    stats.failed_files.extend(other.failed_files);
+}
+
 const RESERVED_DIR_NAMES: &[&str] = &[".git", ".jj"];
 
 /// Suppose the `disk_path` exists, checks if the last component points to
@@ -772,6 +1040,7 @@ fn file_state(metadata: &Metadata) -> Option<FileState> {
 struct FsmonitorMatcher {
     matcher: Option<Box<dyn Matcher>>,
     watchman_clock: Option<crate::protos::working_copy::WatchmanClock>,
+    inotify_cursor: Option<crate::protos::working_copy::InotifyCursor>,
 }
 
 #[derive(Debug, Error)]
@@ -800,6 +1069,13 @@ impl TreeState {
         &self.tree_id
     }
 
+    /// The tree an interrupted checkout was updating the files to, if any.
+    pub fn interrupted_checkout(&self) -> Option<&MergedTreeId> {
+        self.pending_checkout
+            .as_ref()
+            .map(|pending| &pending.target_tree_id)
+    }
+
     pub fn file_states(&self) -> FileStates<'_> {
         self.file_states.all()
     }
@@ -808,8 +1084,16 @@ impl TreeState {
         &self.sparse_patterns
     }
 
+    pub fn sparse_mode(&self) -> SparseMode {
+        self.sparse_mode
+    }
+
+    pub fn sparse_profile(&self) -> Option<&RepoPath> {
+        self.sparse_profile.as_deref()
+    }
+
     fn sparse_matcher(&self) -> Box<dyn Matcher> {
-        Box::new(PrefixMatcher::new(&self.sparse_patterns))
+        new_sparse_matcher(&self.sparse_patterns, self.sparse_mode)
     }
 
     pub fn init(
@@ -818,8 +1102,7 @@ impl TreeState {
         state_path: PathBuf,
         tree_state_settings: &TreeStateSettings,
     ) -> Result<Self, TreeStateError> {
-        let target_eol_strategy = create_target_eol_strategy(tree_state_settings);
-        let mut wc = Self::empty(store, working_copy_path, state_path, target_eol_strategy);
+        let mut wc = Self::empty(store, working_copy_path, state_path, tree_state_settings);
         wc.save()?;
         Ok(wc)
     }
@@ -828,7 +1111,7 @@ impl TreeState {
         store: Arc<Store>,
         working_copy_path: PathBuf,
         state_path: PathBuf,
-        target_eol_strategy: TargetEolStrategy,
+        tree_state_settings: &TreeStateSettings,
     ) -> Self {
         let tree_id = store.empty_merged_tree_id();
         Self {
@@ -838,10 +1121,17 @@ impl TreeState {
             tree_id,
             file_states: FileStatesMap::new(),
             sparse_patterns: vec![RepoPathBuf::root()],
+            sparse_mode: SparseMode::Prefix,
+            sparse_profile: None,
             own_mtime: MillisSinceEpoch(0),
             symlink_support: check_symlink_support().unwrap_or(false),
             watchman_clock: None,
-            target_eol_strategy,
+            inotify_cursor: None,
+            directory_states: BTreeMap::new(),
+            pending_checkout: None,
+            target_eol_strategy: create_target_eol_strategy(tree_state_settings),
+            binary_conflict_style: tree_state_settings.binary_conflict_style,
+            symlink_conflict_style: tree_state_settings.symlink_conflict_style,
         }
     }
 
@@ -851,7 +1141,6 @@ impl TreeState {
         state_path: PathBuf,
         tree_state_settings: &TreeStateSettings,
     ) -> Result<Self, TreeStateError> {
-        let target_eol_strategy = create_target_eol_strategy(tree_state_settings);
         let tree_state_path = state_path.join("tree_state");
         let file = match File::open(&tree_state_path) {
             Err(ref err) if err.kind() == io::ErrorKind::NotFound => {
@@ -866,7 +1155,7 @@ impl TreeState {
             Ok(file) => file,
         };
 
-        let mut wc = Self::empty(store, working_copy_path, state_path, target_eol_strategy);
+        let mut wc = Self::empty(store, working_copy_path, state_path, tree_state_settings);
         wc.read(&tree_state_path, file)?;
         Ok(wc)
     }
@@ -906,7 +1195,23 @@ impl TreeState {
         self.file_states =
             FileStatesMap::from_proto(proto.file_states, proto.is_file_states_sorted);
         self.sparse_patterns = sparse_patterns_from_proto(proto.sparse_patterns.as_ref());
+        self.sparse_mode = sparse_mode_from_proto(proto.sparse_patterns.as_ref());
+        self.sparse_profile = proto
+            .sparse_patterns
+            .as_ref()
+            .and_then(|proto| proto.profile.as_ref())
+            .map(|path| RepoPathBuf::from_internal_string(path).unwrap());
         self.watchman_clock = proto.watchman_clock;
+        self.inotify_cursor = proto.inotify_cursor;
+        self.directory_states = directory_states_from_proto(proto.directory_states);
+        self.pending_checkout = proto.pending_checkout.map(|proto| {
+            let target_tree_ids: MergeBuilder<TreeId> =
+                proto.target_tree_ids.into_iter().map(TreeId::new).collect();
+            PendingCheckout {
+                target_tree_id: MergedTreeId::Merge(target_tree_ids.build()),
+                next_path: RepoPathBuf::from_internal_string(proto.next_path).unwrap(),
+            }
+        });
         Ok(())
     }
 
@@ -931,8 +1236,27 @@ impl TreeState {
                 .prefixes
                 .push(path.as_internal_file_string().to_owned());
         }
+        sparse_patterns.set_mode(sparse_mode_to_proto(self.sparse_mode));
+        sparse_patterns.profile = self
+            .sparse_profile
+            .as_ref()
+            .map(|path| path.as_internal_file_string().to_owned());
         proto.sparse_patterns = Some(sparse_patterns);
         proto.watchman_clock = self.watchman_clock.clone();
+        proto.inotify_cursor = self.inotify_cursor.clone();
+        proto.directory_states = directory_states_to_proto(&self.directory_states);
+        proto.pending_checkout = self.pending_checkout.as_ref().map(|pending| {
+            let target_tree_ids = pending
+                .target_tree_id
+                .to_merge()
+                .iter()
+                .map(|id| id.to_bytes())
+                .collect();
+            crate::protos::working_copy::PendingCheckout {
+                target_tree_ids,
+                next_path: pending.next_path.as_internal_file_string().to_owned(),
+            }
+        });
 
         let mut temp_file = NamedTempFile::new_in(&self.state_path).unwrap();
         temp_file
@@ -967,6 +1291,35 @@ impl TreeState {
         self.watchman_clock.take();
     }
 
+    fn reset_inotify(&mut self) {
+        self.inotify_cursor.take();
+    }
+
+    /// Makes this a copy of the `source` state, whose working-copy files have
+    /// been cloned into this working copy. The filesystem monitor state and
+    /// the cached directory listings only apply to the source directory, so
+    /// they are cleared.
+    fn adopt(&mut self, source: &Self) {
+        self.tree_id = source.tree_id.clone();
+        self.file_states = source.file_states.clone();
+        // Files which may have changed after the source state was saved have
+        // to be read again.
+        let racy_file_states = source
+            .file_states
+            .all()
+            .iter()
+            .filter(|(_, state)| state.mtime >= source.own_mtime)
+            .map(|(path, _)| (path.to_owned(), FileState::placeholder()))
+            .collect_vec();
+        self.file_states.merge_in(racy_file_states, &HashSet::new());
+        self.sparse_patterns = source.sparse_patterns.clone();
+        self.sparse_mode = source.sparse_mode;
+        self.sparse_profile = source.sparse_profile.clone();
+        self.watchman_clock = None;
+        self.inotify_cursor = None;
+        self.directory_states.clear();
+    }
+
     #[cfg(feature = "watchman")]
     #[tokio::main(flavor = "current_thread")]
     #[instrument(skip(self))]
@@ -1000,6 +1353,28 @@ impl TreeState {
             .await
             .map_err(|err| TreeStateError::Fsmonitor(Box::new(err)))
     }
+
+    #[cfg(all(target_os = "linux", feature = "inotify"))]
+    fn inotify_fsmonitor(
+        &self,
+        config: &InotifyConfig,
+    ) -> Result<inotify::Fsmonitor, TreeStateError> {
+        inotify::Fsmonitor::init(&self.working_copy_path, &self.state_path, config)
+            .map_err(|err| TreeStateError::Fsmonitor(Box::new(err)))
+    }
+
+    #[cfg(all(target_os = "linux", feature = "inotify"))]
+    #[instrument(skip(self))]
+    pub fn query_inotify(
+        &self,
+        config: &InotifyConfig,
+    ) -> Result<(inotify::Cursor, Option<Vec<PathBuf>>), TreeStateError> {
+        let fsmonitor = self.inotify_fsmonitor(config)?;
+        let previous_cursor = self.inotify_cursor.clone().map(inotify::Cursor::from);
+        fsmonitor
+            .query_changed_files(previous_cursor)
+            .map_err(|err| TreeStateError::Fsmonitor(Box::new(err)))
+    }
 }
 
 /// Functions to snapshot local-disk files to the store.
@@ -1017,8 +1392,12 @@ impl TreeState {
             progress,
             start_tracking_matcher,
             max_new_file_size,
+            ref snapshot_rules,
             conflict_marker_style,
         } = options;
+        if self.pending_checkout.is_some() {
+            return Err(SnapshotError::InterruptedCheckout);
+        }
 
         let sparse_matcher = self.sparse_matcher();
 
@@ -1027,7 +1406,11 @@ impl TreeState {
         let FsmonitorMatcher {
             matcher: fsmonitor_matcher,
             watchman_clock,
+            inotify_cursor,
         } = self.make_fsmonitor_matcher(fsmonitor_settings)?;
+        // The filesystem monitor already narrows down the paths to visit, and
+        // the directory listings wouldn't be complete.
+        let use_directory_cache = fsmonitor_matcher.is_none();
         let fsmonitor_matcher = match fsmonitor_matcher.as_ref() {
             None => &EverythingMatcher,
             Some(fsmonitor_matcher) => fsmonitor_matcher.as_ref(),
@@ -1037,13 +1420,16 @@ impl TreeState {
         if matcher.visit(RepoPath::root()).is_nothing() {
             // No need to load the current tree, set up channels, etc.
             self.watchman_clock = watchman_clock;
+            self.inotify_cursor = inotify_cursor;
             return Ok((is_dirty, SnapshotStats::default()));
         }
 
         let (tree_entries_tx, tree_entries_rx) = channel();
         let (file_states_tx, file_states_rx) = channel();
         let (untracked_paths_tx, untracked_paths_rx) = channel();
+        let (warned_paths_tx, warned_paths_rx) = channel();
         let (deleted_files_tx, deleted_files_rx) = channel();
+        let (directory_states_tx, directory_states_rx) = channel();
 
         trace_span!("traverse filesystem").in_scope(|| -> Result<(), SnapshotError> {
             let snapshotter = FileSnapshotter {
@@ -1055,10 +1441,14 @@ impl TreeState {
                 tree_entries_tx,
                 file_states_tx,
                 untracked_paths_tx,
+                warned_paths_tx,
                 deleted_files_tx,
+                directory_states_tx,
+                use_directory_cache,
                 error: OnceLock::new(),
                 progress,
                 max_new_file_size,
+                snapshot_rules,
                 conflict_marker_style,
                 target_eol_strategy: self.target_eol_strategy.clone(),
             };
@@ -1079,6 +1469,7 @@ impl TreeState {
 
         let stats = SnapshotStats {
             untracked_paths: untracked_paths_rx.into_iter().collect(),
+            warned_paths: warned_paths_rx.into_iter().collect(),
         };
         let mut tree_builder = MergedTreeBuilder::new(self.tree_id.clone());
         trace_span!("process tree entries").in_scope(|| {
@@ -1103,6 +1494,15 @@ impl TreeState {
             self.file_states
                 .merge_in(changed_file_states, &deleted_files);
         });
+        trace_span!("process directory states").in_scope(|| {
+            let directory_states: BTreeMap<_, _> = if use_directory_cache {
+                directory_states_rx.into_iter().collect()
+            } else {
+                BTreeMap::new()
+            };
+            is_dirty |= directory_states != self.directory_states;
+            self.directory_states = directory_states;
+        });
         trace_span!("write tree").in_scope(|| {
             let new_tree_id = tree_builder.write_tree(&self.store).unwrap();
             is_dirty |= new_tree_id != self.tree_id;
@@ -1121,10 +1521,12 @@ impl TreeState {
         // Since untracked paths aren't cached in the tree state, we'll need to
         // rescan the working directory changes to report or track them later.
         // TODO: store untracked paths and update watchman_clock?
-        if stats.untracked_paths.is_empty() || watchman_clock.is_none() {
+        let has_fsmonitor_clock = watchman_clock.is_some() || inotify_cursor.is_some();
+        if stats.untracked_paths.is_empty() || !has_fsmonitor_clock {
             self.watchman_clock = watchman_clock;
+            self.inotify_cursor = inotify_cursor;
         } else {
-            tracing::info!("not updating watchman clock because there are untracked files");
+            tracing::info!("not updating fsmonitor clock because there are untracked files");
         }
         Ok((is_dirty, stats))
     }
@@ -1134,15 +1536,25 @@ impl TreeState {
         &self,
         fsmonitor_settings: &FsmonitorSettings,
     ) -> Result<FsmonitorMatcher, SnapshotError> {
-        let (watchman_clock, changed_files) = match fsmonitor_settings {
-            FsmonitorSettings::None => (None, None),
-            FsmonitorSettings::Test { changed_files } => (None, Some(changed_files.clone())),
+        let (watchman_clock, inotify_cursor, changed_files) = match fsmonitor_settings {
+            FsmonitorSettings::None => (None, None, None),
+            FsmonitorSettings::Test { changed_files } => (None, None, Some(changed_files.clone())),
             #[cfg(feature = "watchman")]
             FsmonitorSettings::Watchman(config) => match self.query_watchman(config) {
-                Ok((watchman_clock, changed_files)) => (Some(watchman_clock.into()), changed_files),
+                Ok((watchman_clock, changed_files)) => {
+                    (Some(watchman_clock.into()), None, changed_files)
+                }
+                Err(err) => {
+                    tracing::warn!(?err, "Failed to query filesystem monitor");
+                    (None, None, None)
+                }
+            },
+            #[cfg(all(target_os = "linux", feature = "inotify"))]
+            FsmonitorSettings::Inotify(config) => match self.query_inotify(config) {
+                Ok((cursor, changed_paths)) => (None, Some(cursor.into()), changed_paths),
                 Err(err) => {
                     tracing::warn!(?err, "Failed to query filesystem monitor");
-                    (None, None)
+                    (None, None, None)
                 }
             },
             #[cfg(not(feature = "watchman"))]
@@ -1154,6 +1566,15 @@ impl TreeState {
                         .into(),
                 });
             }
+            #[cfg(not(all(target_os = "linux", feature = "inotify")))]
+            FsmonitorSettings::Inotify(_) => {
+                return Err(SnapshotError::Other {
+                    message: "Failed to query the filesystem monitor".to_string(),
+                    err: "The inotify filesystem monitor is only available on Linux with the \
+                          `inotify` feature (consider disabling `fsmonitor.backend`)"
+                        .into(),
+                });
+            }
         };
         let matcher: Option<Box<dyn Matcher>> = match changed_files {
             None => None,
@@ -1165,16 +1586,36 @@ impl TreeState {
                         .collect_vec()
                 });
 
-                Some(Box::new(FilesMatcher::new(repo_paths)))
+                // The inotify daemon reports changed directories as a whole.
+                if matches!(fsmonitor_settings, FsmonitorSettings::Inotify(_)) {
+                    Some(Box::new(PrefixMatcher::new(repo_paths)))
+                } else {
+                    Some(Box::new(FilesMatcher::new(repo_paths)))
+                }
             }
         };
         Ok(FsmonitorMatcher {
             matcher,
             watchman_clock,
+            inotify_cursor,
         })
     }
 }
 
+/// Extracts `<name>` from `<dir>`, `<dir>/<name>`, or `<dir>/<name>/**`.
+///
+/// (file_states may contain `<dir>` file on file->dir transition.)
+fn dir_entry_name_of<'a>(dir: &RepoPath, path: &'a RepoPath) -> (PresentDirEntryKind, &'a str) {
+    debug_assert!(path.starts_with(dir));
+    let slash = !dir.is_root() as usize;
+    let len = dir.as_internal_file_string().len() + slash;
+    let tail = path.as_internal_file_string().get(len..).unwrap_or("");
+    match tail.split_once('/') {
+        Some((name, _)) => (PresentDirEntryKind::Dir, name),
+        None => (PresentDirEntryKind::File, tail),
+    }
+}
+
 struct DirectoryToVisit<'a> {
     dir: RepoPathBuf,
     disk_dir: PathBuf,
@@ -1203,10 +1644,14 @@ struct FileSnapshotter<'a> {
     tree_entries_tx: Sender<(RepoPathBuf, MergedTreeValue)>,
     file_states_tx: Sender<(RepoPathBuf, FileState)>,
     untracked_paths_tx: Sender<(RepoPathBuf, UntrackedReason)>,
+    warned_paths_tx: Sender<(RepoPathBuf, SnapshotWarning)>,
     deleted_files_tx: Sender<RepoPathBuf>,
+    directory_states_tx: Sender<(RepoPathBuf, DirectoryState)>,
+    use_directory_cache: bool,
     error: OnceLock<SnapshotError>,
     progress: Option<&'a SnapshotProgress<'a>>,
     max_new_file_size: u64,
+    snapshot_rules: &'a SnapshotRules,
     conflict_marker_style: ConflictMarkerStyle,
     target_eol_strategy: TargetEolStrategy,
 }
@@ -1251,6 +1696,48 @@ impl FileSnapshotter<'_> {
 
         let git_ignore = git_ignore
             .chain_with_file(&dir.to_internal_dir_string(), disk_dir.join(".gitignore"))?;
+        let dir_mtime =
+            if self.use_directory_cache && self.matcher.visit(&dir) == Visit::AllRecursively {
+                let metadata = match disk_dir.metadata() {
+                    Ok(metadata) => metadata,
+                    Err(err) if err.kind() == io::ErrorKind::NotFound => {
+                        // The directory was removed after its parent was visited.
+                        let present_entries = PresentDirEntries {
+                            dirs: HashSet::new(),
+                            files: HashSet::new(),
+                        };
+                        self.emit_deleted_files(&dir, file_states, &present_entries);
+                        return Ok(());
+                    }
+                    Err(err) => {
+                        return Err(SnapshotError::Other {
+                            message: format!("Failed to stat directory {}", disk_dir.display()),
+                            err: err.into(),
+                        });
+                    }
+                };
+                let mtime = mtime_from_metadata(&metadata);
+                // If the directory was modified at the same time as the state
+                // file, its cached listing may be outdated.
+                let cached_state = self.tree_state.directory_states.get(&dir).filter(|state| {
+                    state.mtime == mtime
+                        && mtime < self.tree_state.own_mtime
+                        && state.ignore_fingerprint == git_ignore.fingerprint()
+                });
+                if let Some(state) = cached_state {
+                    let directory_to_visit = DirectoryToVisit {
+                        dir,
+                        disk_dir,
+                        git_ignore,
+                        file_states,
+                    };
+                    return self.visit_cached_directory(directory_to_visit, state, scope);
+                }
+                Some(mtime)
+            } else {
+                None
+            };
+        let has_untracked = AtomicBool::new(false);
         let dir_entries: Vec<_> = disk_dir
             .read_dir()
             .and_then(|entries| entries.try_collect())
@@ -1264,8 +1751,15 @@ impl FileSnapshotter<'_> {
             // sequential scan should be fast enough.
             .with_min_len(100)
             .filter_map(|entry| {
-                self.process_dir_entry(&dir, &git_ignore, file_states, &entry, scope)
-                    .transpose()
+                self.process_dir_entry(
+                    &dir,
+                    &git_ignore,
+                    file_states,
+                    &entry,
+                    &has_untracked,
+                    scope,
+                )
+                .transpose()
             })
             .map(|item| match item {
                 Ok((PresentDirEntryKind::Dir, name)) => Ok(Either::Left(name)),
@@ -1275,6 +1769,74 @@ impl FileSnapshotter<'_> {
             .collect::<Result<_, _>>()?;
         let present_entries = PresentDirEntries { dirs, files };
         self.emit_deleted_files(&dir, file_states, &present_entries);
+        if let Some(mtime) = dir_mtime {
+            if !has_untracked.into_inner() {
+                let dirs = present_entries
+                    .dirs
+                    .into_iter()
+                    .filter(|name| {
+                        let path = dir.join(RepoPathComponent::new(name).unwrap());
+                        !git_ignore.matches(&path.to_internal_dir_string())
+                    })
+                    .sorted_unstable()
+                    .collect();
+                let state = DirectoryState {
+                    mtime,
+                    ignore_fingerprint: git_ignore.fingerprint().to_vec(),
+                    dirs,
+                };
+                self.directory_states_tx.send((dir, state)).ok();
+            }
+        }
+        Ok(())
+    }
+
+    /// Visits the directory whose entries haven't changed since the last
+    /// snapshot. Only the tracked files are checked, and the subdirectories
+    /// are visited recursively.
+    fn visit_cached_directory<'scope>(
+        &'scope self,
+        directory_to_visit: DirectoryToVisit<'scope>,
+        state: &'scope DirectoryState,
+        scope: &rayon::Scope<'scope>,
+    ) -> Result<(), SnapshotError> {
+        let DirectoryToVisit {
+            dir,
+            disk_dir,
+            git_ignore,
+            file_states,
+        } = directory_to_visit;
+
+        for name in &state.dirs {
+            let name = RepoPathComponent::new(name).unwrap();
+            let directory_to_visit = DirectoryToVisit {
+                dir: dir.join(name),
+                disk_dir: disk_dir.join(name.as_internal_str()),
+                git_ignore: git_ignore.clone(),
+                file_states: file_states.prefixed_at(&dir, name),
+            };
+            self.spawn_ok(scope, |scope| {
+                self.visit_directory(directory_to_visit, scope)
+            });
+        }
+        // Files in this directory and in ignored subdirectories
+        for ((kind, name), chunk) in &file_states
+            .iter()
+            .chunk_by(|(path, _state)| dir_entry_name_of(&dir, path))
+        {
+            if kind == PresentDirEntryKind::Dir
+                && state
+                    .dirs
+                    .binary_search_by(|n| n.as_str().cmp(name))
+                    .is_ok()
+            {
+                continue;
+            }
+            for (path, file_state) in chunk {
+                self.visit_tracked_file(path, &file_state)?;
+            }
+        }
+        self.directory_states_tx.send((dir, state.clone())).ok();
         Ok(())
     }
 
@@ -1284,6 +1846,7 @@ impl FileSnapshotter<'_> {
         git_ignore: &Arc<GitIgnoreFile>,
         file_states: FileStates<'scope>,
         entry: &DirEntry,
+        has_untracked: &AtomicBool,
         scope: &rayon::Scope<'scope>,
     ) -> Result<Option<(PresentDirEntryKind, String)>, SnapshotError> {
         let file_type = entry.file_type().unwrap();
@@ -1298,6 +1861,11 @@ impl FileSnapshotter<'_> {
         let name = RepoPathComponent::new(&name_string).unwrap();
         let path = dir.join(name);
         let maybe_current_file_state = file_states.get_at(dir, name);
+        if maybe_current_file_state.is_none() && self.is_conflict_sidecar(dir, file_states, name) {
+            // TODO: With a filesystem monitor, a modified sidecar file should
+            // also make us look at the conflicted file.
+            return Ok(None);
+        }
         if let Some(file_state) = &maybe_current_file_state {
             if file_state.file_type == FileType::GitSubmodule {
                 return Ok(None);
@@ -1341,6 +1909,7 @@ impl FileSnapshotter<'_> {
                 && !self.start_tracking_matcher.matches(&path)
             {
                 // Leave the file untracked
+                has_untracked.store(true, atomic::Ordering::Relaxed);
                 self.untracked_paths_tx
                     .send((path, UntrackedReason::FileNotAutoTracked))
                     .ok();
@@ -1350,15 +1919,45 @@ impl FileSnapshotter<'_> {
                     message: format!("Failed to stat file {}", entry.path().display()),
                     err: err.into(),
                 })?;
-                if maybe_current_file_state.is_none() && metadata.len() > self.max_new_file_size {
-                    // Leave the large file untracked
-                    let reason = UntrackedReason::FileTooLarge {
-                        size: metadata.len(),
-                        max_size: self.max_new_file_size,
+                let mut warning = None;
+                if maybe_current_file_state.is_none() {
+                    let size = metadata.len();
+                    let reason = match self.snapshot_rules.check(&path, size) {
+                        SnapshotRuleCheck::Unmatched if size > self.max_new_file_size => {
+                            Some(UntrackedReason::FileTooLarge {
+                                size,
+                                max_size: self.max_new_file_size,
+                            })
+                        }
+                        SnapshotRuleCheck::Unmatched | SnapshotRuleCheck::Allowed => None,
+                        SnapshotRuleCheck::Applied(rule) => match rule.action {
+                            SnapshotRuleAction::Warn => {
+                                warning = Some(SnapshotWarning {
+                                    rule: rule.name.clone(),
+                                    size,
+                                });
+                                None
+                            }
+                            SnapshotRuleAction::Ignore => Some(UntrackedReason::IgnoredByRule {
+                                rule: rule.name.clone(),
+                            }),
+                            SnapshotRuleAction::Refuse => Some(UntrackedReason::RefusedByRule {
+                                rule: rule.name.clone(),
+                                size,
+                            }),
+                        },
                     };
-                    self.untracked_paths_tx.send((path, reason)).ok();
-                    Ok(None)
-                } else if let Some(new_file_state) = file_state(&metadata) {
+                    if let Some(reason) = reason {
+                        // Leave the file untracked
+                        has_untracked.store(true, atomic::Ordering::Relaxed);
+                        self.untracked_paths_tx.send((path, reason)).ok();
+                        return Ok(None);
+                    }
+                }
+                if let Some(new_file_state) = file_state(&metadata) {
+                    if let Some(warning) = warning {
+                        self.warned_paths_tx.send((path.clone(), warning)).ok();
+                    }
                     self.process_present_file(
                         path,
                         &entry.path(),
@@ -1376,36 +1975,65 @@ impl FileSnapshotter<'_> {
         }
     }
 
+    /// Returns true if `name` is a sidecar file of a tracked conflict in `dir`.
+    fn is_conflict_sidecar(
+        &self,
+        dir: &RepoPath,
+        file_states: FileStates<'_>,
+        name: &RepoPathComponent,
+    ) -> bool {
+        let Some(owner) = conflict_sidecar_owner(name.as_internal_str()) else {
+            return false;
+        };
+        let Ok(owner) = RepoPathComponent::new(owner) else {
+            return false;
+        };
+        file_states
+            .get_at(dir, owner)
+            .and_then(|state| state.materialized_conflict_data)
+            .is_some_and(|data| data.sidecar_files)
+    }
+
     /// Visits only paths we're already tracking.
     fn visit_tracked_files(&self, file_states: FileStates<'_>) -> Result<(), SnapshotError> {
         for (tracked_path, current_file_state) in file_states {
-            if current_file_state.file_type == FileType::GitSubmodule {
-                continue;
-            }
-            if !self.matcher.matches(tracked_path) {
-                continue;
-            }
-            let disk_path = tracked_path.to_fs_path(&self.tree_state.working_copy_path)?;
-            let metadata = match disk_path.symlink_metadata() {
-                Ok(metadata) => Some(metadata),
-                Err(err) if err.kind() == io::ErrorKind::NotFound => None,
-                Err(err) => {
-                    return Err(SnapshotError::Other {
-                        message: format!("Failed to stat file {}", disk_path.display()),
-                        err: err.into(),
-                    });
-                }
-            };
-            if let Some(new_file_state) = metadata.as_ref().and_then(file_state) {
-                self.process_present_file(
-                    tracked_path.to_owned(),
-                    &disk_path,
-                    Some(&current_file_state),
-                    new_file_state,
-                )?;
-            } else {
-                self.deleted_files_tx.send(tracked_path.to_owned()).ok();
+            self.visit_tracked_file(tracked_path, &current_file_state)?;
+        }
+        Ok(())
+    }
+
+    fn visit_tracked_file(
+        &self,
+        tracked_path: &RepoPath,
+        current_file_state: &FileState,
+    ) -> Result<(), SnapshotError> {
+        if current_file_state.file_type == FileType::GitSubmodule {
+            return Ok(());
+        }
+        if !self.matcher.matches(tracked_path) {
+            return Ok(());
+        }
+        let disk_path = tracked_path.to_fs_path(&self.tree_state.working_copy_path)?;
+        let metadata = match disk_path.symlink_metadata() {
+            Ok(metadata) => Some(metadata),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => None,
+            Err(err) => {
+                return Err(SnapshotError::Other {
+                    message: format!("Failed to stat file {}", disk_path.display()),
+                    err: err.into(),
+                });
             }
+        };
+        if let Some(new_file_state) = metadata.as_ref().and_then(file_state) {
+            self.process_present_file(
+                tracked_path.to_owned(),
+                &disk_path,
+                Some(current_file_state),
+                new_file_state,
+            )?;
+        } else {
+            self.remove_stale_conflict_sidecars(&disk_path, current_file_state)?;
+            self.deleted_files_tx.send(tracked_path.to_owned()).ok();
         }
         Ok(())
     }
@@ -1417,12 +2045,27 @@ impl FileSnapshotter<'_> {
         maybe_current_file_state: Option<&FileState>,
         mut new_file_state: FileState,
     ) -> Result<(), SnapshotError> {
+        if let Some((tree_value, new_file_state)) = self.resolve_conflict_on_disk(
+            &path,
+            disk_path,
+            maybe_current_file_state,
+            &new_file_state,
+        )? {
+            self.tree_entries_tx.send((path.clone(), tree_value)).ok();
+            self.file_states_tx.send((path, new_file_state)).ok();
+            return Ok(());
+        }
         let update = self.get_updated_tree_value(
             &path,
             disk_path,
             maybe_current_file_state,
             &new_file_state,
         )?;
+        if let Some(current_file_state) = maybe_current_file_state {
+            if update.as_ref().is_some_and(|update| update.is_resolved()) {
+                self.remove_stale_conflict_sidecars(disk_path, current_file_state)?;
+            }
+        }
         // Preserve materialized conflict data for normal, non-resolved files
         if matches!(new_file_state.file_type, FileType::Normal { .. })
             && !update.as_ref().is_some_and(|update| update.is_resolved())
@@ -1446,18 +2089,9 @@ impl FileSnapshotter<'_> {
         file_states: FileStates<'_>,
         present_entries: &PresentDirEntries,
     ) {
-        let file_state_chunks = file_states.iter().chunk_by(|(path, _state)| {
-            // Extract <name> from <dir>, <dir>/<name>, or <dir>/<name>/**.
-            // (file_states may contain <dir> file on file->dir transition.)
-            debug_assert!(path.starts_with(dir));
-            let slash = !dir.is_root() as usize;
-            let len = dir.as_internal_file_string().len() + slash;
-            let tail = path.as_internal_file_string().get(len..).unwrap_or("");
-            match tail.split_once('/') {
-                Some((name, _)) => (PresentDirEntryKind::Dir, name),
-                None => (PresentDirEntryKind::File, tail),
-            }
-        });
+        let file_state_chunks = file_states
+            .iter()
+            .chunk_by(|(path, _state)| dir_entry_name_of(dir, path));
         file_state_chunks
             .into_iter()
             .filter(|&((kind, name), _)| match kind {
@@ -1468,10 +2102,191 @@ impl FileSnapshotter<'_> {
             // Whether or not the entry exists, submodule should be ignored
             .filter(|(_, state)| state.file_type != FileType::GitSubmodule)
             .filter(|(path, _)| self.matcher.matches(path))
-            .try_for_each(|(path, _)| self.deleted_files_tx.send(path.to_owned()))
+            .try_for_each(|(path, state)| {
+                if let Ok(disk_path) = path.to_fs_path(&self.tree_state.working_copy_path) {
+                    // Failing to clean up is harmless since the sidecar files
+                    // of deleted conflicts are never tracked.
+                    self.remove_stale_conflict_sidecars(&disk_path, &state).ok();
+                }
+                self.deleted_files_tx.send(path.to_owned())
+            })
             .ok();
     }
 
+    /// Removes the sidecar files of the conflict which was at `disk_path`
+    /// when it's resolved or deleted.
+    fn remove_stale_conflict_sidecars(
+        &self,
+        disk_path: &Path,
+        current_file_state: &FileState,
+    ) -> Result<(), SnapshotError> {
+        let had_sidecar_files = current_file_state
+            .materialized_conflict_data
+            .is_some_and(|data| data.sidecar_files);
+        if had_sidecar_files {
+            remove_conflict_sidecars(disk_path).map_err(|err| SnapshotError::Other {
+                message: format!(
+                    "Failed to remove the conflict sidecar files of {}",
+                    disk_path.display()
+                ),
+                err: err.into(),
+            })?;
+        }
+        Ok(())
+    }
+
+    /// Resolves the conflict at `path` if the user resolved it in a way that
+    /// requires updating the working copy: by modifying one of its sidecar
+    /// files, or by removing the conflict markers between symlink targets.
+    /// Returns the resolved value and the new state of the file.
+    fn resolve_conflict_on_disk(
+        &self,
+        path: &RepoPath,
+        disk_path: &Path,
+        maybe_current_file_state: Option<&FileState>,
+        new_file_state: &FileState,
+    ) -> Result<Option<(MergedTreeValue, FileState)>, SnapshotError> {
+        let Some(current_file_state) = maybe_current_file_state else {
+            return Ok(None);
+        };
+        let Some(conflict_data) = current_file_state.materialized_conflict_data else {
+            return Ok(None);
+        };
+        let current_tree_values = self.current_tree.path_value(path)?;
+        if conflict_data.sidecar_files {
+            let Some(file_ids) = current_tree_values.to_file_merge() else {
+                return Ok(None);
+            };
+            self.resolve_from_conflict_sidecar(path, disk_path, &current_tree_values, &file_ids)
+        } else if let Some(symlink_ids) = to_symlink_merge(&current_tree_values) {
+            let clean = new_file_state.is_clean(current_file_state)
+                && current_file_state.mtime < self.tree_state.own_mtime;
+            if clean || !matches!(new_file_state.file_type, FileType::Normal { .. }) {
+                return Ok(None);
+            }
+            self.resolve_symlink_conflict(
+                path,
+                disk_path,
+                &symlink_ids,
+                conflict_data.conflict_marker_len as usize,
+                new_file_state,
+            )
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Looks for a modified sidecar file of the conflict at `path`. If there's
+    /// one, it replaces the conflicted file, and the conflict is resolved to
+    /// its contents.
+    fn resolve_from_conflict_sidecar(
+        &self,
+        path: &RepoPath,
+        disk_path: &Path,
+        current_tree_values: &MergedTreeValue,
+        file_ids: &Merge<Option<FileId>>,
+    ) -> Result<Option<(MergedTreeValue, FileState)>, SnapshotError> {
+        let file_ids = file_ids.simplify();
+        // Repo paths are valid UTF-8.
+        let file_name = disk_path.file_name().unwrap().to_str().unwrap();
+        for (sidecar_name, id) in conflict_sidecar_names(file_name, &file_ids) {
+            let Some(id) = id else {
+                continue;
+            };
+            let sidecar_path = disk_path.with_file_name(&sidecar_name);
+            let Ok(metadata) = sidecar_path.symlink_metadata() else {
+                continue;
+            };
+            // Sidecar files written before the state was saved are unmodified.
+            if !metadata.is_file() || mtime_from_metadata(&metadata) < self.tree_state.own_mtime {
+                continue;
+            }
+            let new_id = self.write_file_to_store(path, &sidecar_path).block_on()?;
+            if new_id == *id {
+                continue;
+            }
+            let io_error = |err: io::Error| SnapshotError::Other {
+                message: format!(
+                    "Failed to replace {} with {sidecar_name}",
+                    disk_path.display()
+                ),
+                err: err.into(),
+            };
+            fs::rename(&sidecar_path, disk_path).map_err(io_error)?;
+            remove_conflict_sidecars(disk_path).map_err(io_error)?;
+            let metadata = disk_path.symlink_metadata().map_err(io_error)?;
+            let Some(new_file_state) = file_state(&metadata) else {
+                return Ok(None);
+            };
+            let FileType::Normal { executable } = &new_file_state.file_type else {
+                return Ok(None);
+            };
+            // On Windows, we preserve the executable bit from the merged trees.
+            let executable = (*executable).unwrap_or_else(|| {
+                current_tree_values
+                    .to_executable_merge()
+                    .and_then(|merge| conflicts::resolve_file_executable(&merge))
+                    .unwrap_or(false)
+            });
+            let copy_id = current_tree_values
+                .to_copy_id_merge()
+                .and_then(|merge| merge.resolve_trivial().cloned().flatten())
+                .unwrap_or_else(CopyId::placeholder);
+            let tree_value = Merge::normal(TreeValue::File {
+                id: new_id,
+                executable,
+                copy_id,
+            });
+            return Ok(Some((tree_value, new_file_state)));
+        }
+        Ok(None)
+    }
+
+    /// Parses the conflict markers between symlink targets at `path`. Once
+    /// they're removed, the file is replaced with a symlink to the remaining
+    /// target.
+    fn resolve_symlink_conflict(
+        &self,
+        path: &RepoPath,
+        disk_path: &Path,
+        symlink_ids: &Merge<Option<SymlinkId>>,
+        conflict_marker_len: usize,
+        new_file_state: &FileState,
+    ) -> Result<Option<(MergedTreeValue, FileState)>, SnapshotError> {
+        let contents = fs::read(disk_path).map_err(|err| SnapshotError::Other {
+            message: format!("Failed to read file {}", disk_path.display()),
+            err: err.into(),
+        })?;
+        let num_sides = symlink_ids.simplify().num_sides();
+        if conflicts::parse_conflict(&contents, num_sides, conflict_marker_len).is_some() {
+            return Ok(None);
+        }
+        let target = contents.strip_suffix(b"\n").unwrap_or(&contents);
+        let target = target.strip_suffix(b"\r").unwrap_or(target);
+        let Ok(target) = str::from_utf8(target) else {
+            return Ok(None);
+        };
+        let id = self.store().write_symlink(path, target).block_on()?;
+        let new_file_state = if self.tree_state.symlink_support {
+            let io_error = |err: io::Error| SnapshotError::Other {
+                message: format!("Failed to replace {} with a symlink", disk_path.display()),
+                err: err.into(),
+            };
+            fs::remove_file(disk_path).map_err(io_error)?;
+            try_symlink(Path::new(target), disk_path).map_err(io_error)?;
+            FileState::for_symlink(&disk_path.symlink_metadata().map_err(io_error)?)
+        } else {
+            FileState {
+                materialized_conflict_data: None,
+                ..new_file_state.clone()
+            }
+        };
+        Ok(Some((
+            Merge::normal(TreeValue::Symlink(id)),
+            new_file_state,
+        )))
+    }
+
     fn get_updated_tree_value(
         &self,
         repo_path: &RepoPath,
@@ -1603,6 +2418,15 @@ impl FileSnapshotter<'_> {
                     message: "Failed to read the EOL converted contents".to_string(),
                     err: err.into(),
                 })?;
+            if materialized_conflict_data.is_some_and(|data| data.sidecar_files) {
+                // The file describes where the sides of the conflict are. Any
+                // other contents resolve the conflict.
+                let file_name = disk_path.file_name().unwrap().to_str().unwrap();
+                let description = conflict_sidecar_description(file_name, &old_file_ids.simplify());
+                if contents == description.as_bytes() {
+                    return Ok(current_tree_values.clone());
+                }
+            }
             // If the file contained a conflict before and is a normal file on
             // disk, we try to parse any conflict markers in the file into a
             // conflict.
@@ -1807,6 +2631,76 @@ impl TreeState {
         ))
     }
 
+    /// Writes the terms of a conflict in a binary file to sidecar files, and
+    /// a description of the conflict to `disk_path`.
+    async fn write_conflict_sidecars(
+        &self,
+        disk_path: &Path,
+        file: &MaterializedFileConflictValue,
+    ) -> Result<FileState, CheckoutError> {
+        // Repo paths are valid UTF-8.
+        let file_name = disk_path.file_name().unwrap().to_str().unwrap();
+        let executable = file.executable.unwrap_or(false);
+        let sidecars = iter::zip(
+            conflict_sidecar_names(file_name, &file.ids),
+            conflict_sidecar_names(file_name, &file.contents),
+        );
+        for ((sidecar_name, id), (_, content)) in sidecars {
+            if id.is_none() {
+                continue;
+            }
+            let sidecar_path = disk_path.with_file_name(sidecar_name);
+            self.write_file(&sidecar_path, content.as_slice(), executable, false)
+                .await?;
+        }
+        let data = conflict_sidecar_description(file_name, &file.ids).into_bytes();
+        let materialized_conflict_data = MaterializedConflictData {
+            conflict_marker_len: MIN_CONFLICT_MARKER_LEN as u32,
+            sidecar_files: true,
+        };
+        self.write_conflict(
+            disk_path,
+            data,
+            executable,
+            Some(materialized_conflict_data),
+        )
+        .await
+    }
+
+    /// Materializes a conflict between symlinks as conflict markers between
+    /// their targets, if configured to. Returns the contents and the length of
+    /// the conflict markers.
+    async fn materialize_symlink_conflict(
+        &self,
+        path: &RepoPath,
+        value: &MergedTreeValue,
+        conflict_marker_style: ConflictMarkerStyle,
+    ) -> Result<Option<(Vec<u8>, usize)>, CheckoutError> {
+        if self.symlink_conflict_style != SymlinkConflictStyle::Text {
+            return Ok(None);
+        }
+        let Some(symlink_ids) = to_symlink_merge(value) else {
+            return Ok(None);
+        };
+        let mut targets = vec![];
+        for id in symlink_ids.simplify().iter() {
+            let target = match id {
+                // Each target is a line so that the markers can separate them.
+                Some(id) => self.store.read_symlink(path, id).await? + "\n",
+                None => String::new(),
+            };
+            targets.push(BString::from(target));
+        }
+        let targets = Merge::from_vec(targets);
+        let conflict_marker_len = choose_materialized_conflict_marker_len(&targets);
+        let data = materialize_merge_result_to_bytes_with_marker_len(
+            &targets,
+            conflict_marker_style,
+            conflict_marker_len,
+        );
+        Ok(Some((data.into(), conflict_marker_len)))
+    }
+
     #[cfg_attr(windows, allow(unused_variables))]
     fn set_executable(&self, disk_path: &Path, executable: bool) -> Result<(), CheckoutError> {
         #[cfg(unix)]
@@ -1823,21 +2717,73 @@ impl TreeState {
         new_tree: &MergedTree,
         options: &CheckoutOptions,
     ) -> Result<CheckoutStats, CheckoutError> {
-        let old_tree = self.current_tree().map_err(|err| match err {
+        let mut old_tree = self.current_tree().map_err(|err| match err {
             err @ BackendError::ObjectNotFound { .. } => CheckoutError::SourceNotFound {
                 source: Box::new(err),
             },
             other => CheckoutError::InternalBackendError(other),
         })?;
-        let stats = self
+        // Read the profile before touching any files. The profile may have been
+        // removed in the new tree, in which case the current patterns are kept.
+        let profile_patterns = match &self.sparse_profile {
+            Some(path) => match read_sparse_profile(new_tree, path).block_on() {
+                Ok(patterns) => Some(patterns),
+                Err(SparseProfileError::NotFound) => None,
+                Err(source) => {
+                    return Err(CheckoutError::SparseProfile {
+                        path: path.clone(),
+                        source,
+                    });
+                }
+            },
+            None => None,
+        };
+        let sparse_matcher = self.sparse_matcher();
+        let mut stats = CheckoutStats::default();
+        if let Some(pending) = self.pending_checkout.clone() {
+            // Finish the interrupted checkout first, so that the files match a
+            // single tree again.
+            let target_tree = self.store.get_root_tree(&pending.target_tree_id)?;
+            let resume_stats = self
+                .update(
+                    &old_tree,
+                    &target_tree,
+                    sparse_matcher.as_ref(),
+                    options,
+                    true,
+                    Some(&pending.next_path),
+                )
+                .block_on()?;
+            add_checkout_stats(&mut stats, resume_stats);
+            self.tree_id = target_tree.id();
+            self.pending_checkout = None;
+            old_tree = target_tree;
+        }
+        self.check_path_collisions(
+            &old_tree,
+            new_tree,
+            sparse_matcher.as_ref(),
+            sparse_matcher.as_ref(),
+        )?;
+        let update_stats = self
             .update(
                 &old_tree,
                 new_tree,
-                self.sparse_matcher().as_ref(),
-                options.conflict_marker_style,
+                sparse_matcher.as_ref(),
+                options,
+                true,
+                None,
             )
             .block_on()?;
+        add_checkout_stats(&mut stats, update_stats);
         self.tree_id = new_tree.id();
+        self.pending_checkout = None;
+        if let Some(patterns) = profile_patterns {
+            if patterns != self.sparse_patterns {
+                let sparse_stats = self.update_sparse(patterns, self.sparse_mode, options)?;
+                add_checkout_stats(&mut stats, sparse_stats);
+            }
+        }
         Ok(stats)
     }
 
@@ -1845,6 +2791,46 @@ impl TreeState {
         &mut self,
         sparse_patterns: Vec<RepoPathBuf>,
         options: &CheckoutOptions,
+    ) -> Result<CheckoutStats, CheckoutError> {
+        self.set_sparse_patterns_and_mode(sparse_patterns, self.sparse_mode, options)
+    }
+
+    pub fn set_sparse_patterns_and_mode(
+        &mut self,
+        sparse_patterns: Vec<RepoPathBuf>,
+        sparse_mode: SparseMode,
+        options: &CheckoutOptions,
+    ) -> Result<CheckoutStats, CheckoutError> {
+        let stats = self.update_sparse(sparse_patterns, sparse_mode, options)?;
+        self.sparse_profile = None;
+        Ok(stats)
+    }
+
+    /// Reads the sparse patterns from the profile at `profile_path` in the
+    /// current tree. The patterns will be re-read on checkout.
+    pub fn set_sparse_profile(
+        &mut self,
+        profile_path: RepoPathBuf,
+        sparse_mode: SparseMode,
+        options: &CheckoutOptions,
+    ) -> Result<CheckoutStats, CheckoutError> {
+        let tree = self.current_tree()?;
+        let sparse_patterns = read_sparse_profile(&tree, &profile_path)
+            .block_on()
+            .map_err(|source| CheckoutError::SparseProfile {
+                path: profile_path.clone(),
+                source,
+            })?;
+        let stats = self.update_sparse(sparse_patterns, sparse_mode, options)?;
+        self.sparse_profile = Some(profile_path);
+        Ok(stats)
+    }
+
+    fn update_sparse(
+        &mut self,
+        sparse_patterns: Vec<RepoPathBuf>,
+        sparse_mode: SparseMode,
+        options: &CheckoutOptions,
     ) -> Result<CheckoutStats, CheckoutError> {
         let tree = self.current_tree().map_err(|err| match err {
             err @ BackendError::ObjectNotFound { .. } => CheckoutError::SourceNotFound {
@@ -1852,70 +2838,133 @@ impl TreeState {
             },
             other => CheckoutError::InternalBackendError(other),
         })?;
-        let old_matcher = PrefixMatcher::new(&self.sparse_patterns);
-        let new_matcher = PrefixMatcher::new(&sparse_patterns);
+        let old_matcher = self.sparse_matcher();
+        let new_matcher = new_sparse_matcher(&sparse_patterns, sparse_mode);
         let added_matcher = DifferenceMatcher::new(&new_matcher, &old_matcher);
         let removed_matcher = DifferenceMatcher::new(&old_matcher, &new_matcher);
         let empty_tree = MergedTree::resolved(Tree::empty(self.store.clone(), RepoPathBuf::root()));
+        self.check_path_collisions(&empty_tree, &tree, &added_matcher, &new_matcher)?;
         let added_stats = self
-            .update(
-                &empty_tree,
-                &tree,
-                &added_matcher,
-                options.conflict_marker_style,
-            )
+            .update(&empty_tree, &tree, &added_matcher, options, false, None)
             .block_on()?;
         let removed_stats = self
-            .update(
-                &tree,
-                &empty_tree,
-                &removed_matcher,
-                options.conflict_marker_style,
-            )
+            .update(&tree, &empty_tree, &removed_matcher, options, false, None)
             .block_on()?;
         self.sparse_patterns = sparse_patterns;
+        self.sparse_mode = sparse_mode;
         assert_eq!(added_stats.updated_files, 0);
         assert_eq!(added_stats.removed_files, 0);
         assert_eq!(removed_stats.updated_files, 0);
         assert_eq!(removed_stats.added_files, 0);
         assert_eq!(removed_stats.skipped_files, 0);
+        let mut failed_files = added_stats.failed_files;
+        failed_files.extend(removed_stats.failed_files);
         Ok(CheckoutStats {
             updated_files: 0,
             added_files: added_stats.added_files,
             removed_files: removed_stats.removed_files,
             skipped_files: added_stats.skipped_files,
+            failed_files,
         })
     }
 
+    /// Fails if a path added by updating from `old_tree` to `new_tree` would
+    /// refer to the same file on disk as another path in `new_tree`. Only
+    /// paths matched by `sparse_matcher` are checked out.
+    fn check_path_collisions(
+        &self,
+        old_tree: &MergedTree,
+        new_tree: &MergedTree,
+        matcher: &dyn Matcher,
+        sparse_matcher: &dyn Matcher,
+    ) -> Result<(), CheckoutError> {
+        // Find the paths which would collide on any file system first, so only
+        // the directories containing them have to be probed.
+        let candidates = async {
+            let mut added_paths = vec![];
+            let mut diff_stream = old_tree.diff_stream(new_tree, matcher);
+            while let Some(TreeDiffEntry { path, values }) = diff_stream.next().await {
+                let (before, after) = values?;
+                if before.is_absent() && after.is_present() {
+                    added_paths.push(path);
+                }
+            }
+            find_added_path_collisions(new_tree, &added_paths, sparse_matcher, PathFolding::ALL)
+                .await
+        }
+        .block_on()?;
+        let mut prober = PathFoldingProber::new(&self.working_copy_path);
+        for PathCollision { path, other_path } in candidates {
+            // Colliding paths are in the same directory.
+            let (dir, name) = path.split().unwrap();
+            let (_, other_name) = other_path.split().unwrap();
+            let folding = prober.probe(dir).map_err(|err| CheckoutError::Other {
+                message: format!(
+                    "Failed to check how {} compares file names",
+                    dir.to_fs_path_unchecked(&self.working_copy_path).display()
+                ),
+                err: err.into(),
+            })?;
+            if folding.fold(name.as_internal_str()) == folding.fold(other_name.as_internal_str()) {
+                return Err(CheckoutError::PathCollision { path, other_path });
+            }
+        }
+        Ok(())
+    }
+
+    /// Updates the files matched by `matcher` from `old_tree` to `new_tree`.
+    ///
+    /// If `journal` is set, the progress is saved periodically so an
+    /// interrupted update can be resumed from where it stopped, which is done
+    /// by passing the saved `start_path`. The paths sorting before it are
+    /// assumed to be updated already.
     async fn update(
         &mut self,
         old_tree: &MergedTree,
         new_tree: &MergedTree,
         matcher: &dyn Matcher,
-        conflict_marker_style: ConflictMarkerStyle,
+        options: &CheckoutOptions<'_>,
+        journal: bool,
+        start_path: Option<&RepoPath>,
     ) -> Result<CheckoutStats, CheckoutError> {
         // TODO: maybe it's better not include the skipped counts in the "intended"
         // counts
-        let mut stats = CheckoutStats {
-            updated_files: 0,
-            added_files: 0,
-            removed_files: 0,
-            skipped_files: 0,
-        };
+        let mut stats = CheckoutStats::default();
         let mut changed_file_states = Vec::new();
         let mut deleted_files = HashSet::new();
+        let mut last_journal_time = Instant::now();
+        let store = self.store.clone();
         let mut diff_stream = old_tree
             .diff_stream_for_file_system(new_tree, matcher)
+            .filter(|entry| {
+                let done = start_path.is_some_and(|start_path| entry.path.as_ref() < start_path);
+                future::ready(!done)
+            })
             .map(async |TreeDiffEntry { path, values }| match values {
                 Ok((before, after)) => {
-                    let result = materialize_tree_value(&self.store, &path, after).await;
+                    let result = materialize_tree_value(&store, &path, after).await;
                     (path, result.map(|value| (before, value)))
                 }
                 Err(err) => (path, Err(err)),
             })
-            .buffered(self.store.concurrency().max(1));
+            .buffered(store.concurrency().max(1));
         while let Some((path, data)) = diff_stream.next().await {
             let (before, after) = data?;
+            if journal
+                && last_journal_time.elapsed() >= CHECKOUT_JOURNAL_INTERVAL
+                && is_checkout_resumable_at(old_tree, new_tree, &path)?
+            {
+                self.save_checkout_journal(
+                    new_tree,
+                    &path,
+                    mem::take(&mut changed_file_states),
+                    mem::take(&mut deleted_files),
+                )?;
+                last_journal_time = Instant::now();
+            }
+            if let Some(progress) = options.progress {
+                progress(&path);
+            }
             if after.is_absent() {
                 stats.removed_files += 1;
             } else if before.is_absent() {
@@ -1940,75 +2989,148 @@ impl TreeState {
                 continue;
             }
 
-            // Create parent directories no matter if after.is_present(). This
-            // ensures that the path never traverses symlinks.
-            let Some(disk_path) = create_parent_dirs(&self.working_copy_path, &path)? else {
-                changed_file_states.push((path, FileState::placeholder()));
-                stats.skipped_files += 1;
-                continue;
-            };
-            // If the path was present, check reserved path first and delete it.
-            let present_file_deleted = before.is_present() && remove_old_file(&disk_path)?;
-            // If not, create temporary file to test the path validity.
-            if !present_file_deleted && !can_create_new_file(&disk_path)? {
-                changed_file_states.push((path, FileState::placeholder()));
-                stats.skipped_files += 1;
-                continue;
-            }
-
-            // TODO: Check that the file has not changed before overwriting/removing it.
-            let file_state = match after {
-                MaterializedTreeValue::Absent | MaterializedTreeValue::AccessDenied(_) => {
-                    let mut parent_dir = disk_path.parent().unwrap();
-                    loop {
-                        if fs::remove_dir(parent_dir).is_err() {
-                            break;
-                        }
-                        parent_dir = parent_dir.parent().unwrap();
-                    }
+            match self
+                .update_path(&path, before.is_present(), after, options)
+                .await
+            {
+                Ok(PathUpdate::Skipped) => {
+                    changed_file_states.push((path, FileState::placeholder()));
+                    stats.skipped_files += 1;
+                }
+                Ok(PathUpdate::Removed) => {
                     deleted_files.insert(path);
-                    continue;
                 }
-                MaterializedTreeValue::File(file) => {
-                    self.write_file(&disk_path, file.reader, file.executable, true)
-                        .await?
+                Ok(PathUpdate::Written(file_state)) => {
+                    changed_file_states.push((path, file_state));
                 }
-                MaterializedTreeValue::Symlink { id: _, target } => {
-                    if self.symlink_support {
-                        self.write_symlink(&disk_path, target)?
-                    } else {
-                        self.write_file(&disk_path, target.as_bytes(), false, false)
-                            .await?
-                    }
+                Err(CheckoutError::Other { message, err }) => {
+                    // Keep going so a single unwritable file doesn't leave the
+                    // rest of the working copy half-updated. The placeholder
+                    // state makes the next snapshot compare the file contents.
+                    stats
+                        .failed_files
+                        .insert(path.clone(), format!("{message}: {err}"));
+                    changed_file_states.push((path, FileState::placeholder()));
                 }
-                MaterializedTreeValue::GitSubmodule(_) => {
-                    eprintln!("ignoring git submodule at {path:?}");
-                    FileState::for_gitsubmodule()
+                Err(err) => return Err(err),
+            }
+        }
+        self.invalidate_directory_states(
+            changed_file_states
+                .iter()
+                .map(|(path, _)| path)
+                .chain(&deleted_files),
+        );
+        self.file_states
+            .merge_in(changed_file_states, &deleted_files);
+        Ok(stats)
+    }
+
+    /// Updates the file at `path` on disk to `after`.
+    async fn update_path(
+        &self,
+        path: &RepoPath,
+        was_present: bool,
+        after: MaterializedTreeValue,
+        options: &CheckoutOptions<'_>,
+    ) -> Result<PathUpdate, CheckoutError> {
+        // Create parent directories no matter if after.is_present(). This
+        // ensures that the path never traverses symlinks.
+        let Some(disk_path) = create_parent_dirs(&self.working_copy_path, path)? else {
+            return Ok(PathUpdate::Skipped);
+        };
+        // The sidecar files of a previous conflict at the path are stale.
+        let had_sidecar_files = self
+            .file_states
+            .all()
+            .get(path)
+            .and_then(|state| state.materialized_conflict_data)
+            .is_some_and(|data| data.sidecar_files);
+        if had_sidecar_files {
+            remove_conflict_sidecars(&disk_path).map_err(|err| CheckoutError::Other {
+                message: format!(
+                    "Failed to remove the conflict sidecar files of {}",
+                    disk_path.display()
+                ),
+                err: err.into(),
+            })?;
+        }
+        // If the path was present, check reserved path first and delete it.
+        let present_file_deleted = was_present && remove_old_file(&disk_path)?;
+        // If not, create temporary file to test the path validity.
+        if !present_file_deleted && !can_create_new_file(&disk_path)? {
+            return Ok(PathUpdate::Skipped);
+        }
+
+        // TODO: Check that the file has not changed before overwriting/removing it.
+        let file_state = match after {
+            MaterializedTreeValue::Absent | MaterializedTreeValue::AccessDenied(_) => {
+                let mut parent_dir = disk_path.parent().unwrap();
+                loop {
+                    if fs::remove_dir(parent_dir).is_err() {
+                        break;
+                    }
+                    parent_dir = parent_dir.parent().unwrap();
                 }
-                MaterializedTreeValue::Tree(_) => {
-                    panic!("unexpected tree entry in diff at {path:?}");
+                return Ok(PathUpdate::Removed);
+            }
+            MaterializedTreeValue::File(file) => {
+                self.write_file(&disk_path, file.reader, file.executable, true)
+                    .await?
+            }
+            MaterializedTreeValue::Symlink { id: _, target } => {
+                if self.symlink_support {
+                    self.write_symlink(&disk_path, target)?
+                } else {
+                    self.write_file(&disk_path, target.as_bytes(), false, false)
+                        .await?
                 }
-                MaterializedTreeValue::FileConflict(file) => {
-                    let conflict_marker_len =
-                        choose_materialized_conflict_marker_len(&file.contents);
-                    let data = materialize_merge_result_to_bytes_with_marker_len(
-                        &file.contents,
-                        conflict_marker_style,
-                        conflict_marker_len,
-                    )
-                    .into();
+            }
+            MaterializedTreeValue::GitSubmodule(_) => {
+                eprintln!("ignoring git submodule at {path:?}");
+                FileState::for_gitsubmodule()
+            }
+            MaterializedTreeValue::Tree(_) => {
+                panic!("unexpected tree entry in diff at {path:?}");
+            }
+            MaterializedTreeValue::FileConflict(file)
+                if self.binary_conflict_style == BinaryConflictStyle::SidecarFiles
+                    && file.contents.iter().any(|content| is_binary(content)) =>
+            {
+                self.write_conflict_sidecars(&disk_path, &file).await?
+            }
+            MaterializedTreeValue::FileConflict(file) => {
+                let conflict_marker_len = choose_materialized_conflict_marker_len(&file.contents);
+                let data = materialize_merge_result_to_bytes_with_marker_len(
+                    &file.contents,
+                    options.conflict_marker_style,
+                    conflict_marker_len,
+                )
+                .into();
+                let materialized_conflict_data = MaterializedConflictData {
+                    conflict_marker_len: conflict_marker_len.try_into().unwrap_or(u32::MAX),
+                    sidecar_files: false,
+                };
+                self.write_conflict(
+                    &disk_path,
+                    data,
+                    file.executable.unwrap_or(false),
+                    Some(materialized_conflict_data),
+                )
+                .await?
+            }
+            MaterializedTreeValue::OtherConflict { id } => {
+                if let Some((data, conflict_marker_len)) = self
+                    .materialize_symlink_conflict(path, &id, options.conflict_marker_style)
+                    .await?
+                {
                     let materialized_conflict_data = MaterializedConflictData {
                         conflict_marker_len: conflict_marker_len.try_into().unwrap_or(u32::MAX),
+                        sidecar_files: false,
                     };
-                    self.write_conflict(
-                        &disk_path,
-                        data,
-                        file.executable.unwrap_or(false),
-                        Some(materialized_conflict_data),
-                    )
-                    .await?
-                }
-                MaterializedTreeValue::OtherConflict { id } => {
+                    self.write_conflict(&disk_path, data, false, Some(materialized_conflict_data))
+                        .await?
+                } else {
                     // Unless all terms are regular files, we can't do much
                     // better than trying to describe the merge.
                     let data = id.describe().into_bytes();
@@ -2016,12 +3138,37 @@ impl TreeState {
                     self.write_conflict(&disk_path, data, executable, None)
                         .await?
                 }
-            };
-            changed_file_states.push((path, file_state));
-        }
+            }
+        };
+        Ok(PathUpdate::Written(file_state))
+    }
+
+    /// Records the file states updated so far and persists the progress of
+    /// the checkout to `new_tree`. The files at `next_path` and later haven't
+    /// been updated yet.
+    fn save_checkout_journal(
+        &mut self,
+        new_tree: &MergedTree,
+        next_path: &RepoPath,
+        changed_file_states: Vec<(RepoPathBuf, FileState)>,
+        deleted_files: HashSet<RepoPathBuf>,
+    ) -> Result<(), CheckoutError> {
+        self.invalidate_directory_states(
+            changed_file_states
+                .iter()
+                .map(|(path, _)| path)
+                .chain(&deleted_files),
+        );
         self.file_states
             .merge_in(changed_file_states, &deleted_files);
-        Ok(stats)
+        self.pending_checkout = Some(PendingCheckout {
+            target_tree_id: new_tree.id(),
+            next_path: next_path.to_owned(),
+        });
+        self.save().map_err(|err| CheckoutError::Other {
+            message: "Failed to save the checkout progress".to_string(),
+            err: err.into(),
+        })
     }
 
     pub async fn reset(&mut self, new_tree: &MergedTree) -> Result<(), ResetError> {
@@ -2033,11 +3180,28 @@ impl TreeState {
         })?;
 
         let matcher = self.sparse_matcher();
-        let mut changed_file_states = Vec::new();
-        let mut deleted_files = HashSet::new();
+        let mut new_values = BTreeMap::new();
         let mut diff_stream = old_tree.diff_stream_for_file_system(new_tree, matcher.as_ref());
         while let Some(TreeDiffEntry { path, values }) = diff_stream.next().await {
             let (_before, after) = values?;
+            new_values.insert(path, after);
+        }
+        if let Some(pending) = &self.pending_checkout {
+            // Some files of the interrupted checkout may have been updated to
+            // the target tree, so they have to be compared against the new
+            // tree too.
+            let target_tree = self.store.get_root_tree(&pending.target_tree_id)?;
+            let mut diff_stream =
+                target_tree.diff_stream_for_file_system(new_tree, matcher.as_ref());
+            while let Some(TreeDiffEntry { path, values }) = diff_stream.next().await {
+                let (_before, after) = values?;
+                new_values.insert(path, after);
+            }
+        }
+
+        let mut changed_file_states = Vec::new();
+        let mut deleted_files = HashSet::new();
+        for (path, after) in new_values {
             if after.is_absent() {
                 deleted_files.insert(path);
             } else {
@@ -2078,17 +3242,39 @@ impl TreeState {
                 changed_file_states.push((path, file_state));
             }
         }
+        self.invalidate_directory_states(
+            changed_file_states
+                .iter()
+                .map(|(path, _)| path)
+                .chain(&deleted_files),
+        );
         self.file_states
             .merge_in(changed_file_states, &deleted_files);
         self.tree_id = new_tree.id();
+        self.pending_checkout = None;
         Ok(())
     }
 
     pub async fn recover(&mut self, new_tree: &MergedTree) -> Result<(), ResetError> {
         self.file_states.clear();
+        self.directory_states.clear();
         self.tree_id = self.store.empty_merged_tree_id();
+        self.pending_checkout = None;
         self.reset(new_tree).await
     }
+
+    /// Forgets the cached listings of the directories containing `paths`. The
+    /// listings may include files which are no longer tracked.
+    fn invalidate_directory_states<'a>(
+        &mut self,
+        paths: impl IntoIterator<Item = &'a RepoPathBuf>,
+    ) {
+        for path in paths {
+            if let Some(dir) = path.parent() {
+                self.directory_states.remove(dir);
+            }
+        }
+    }
 }
 
 fn checkout_error_for_stat_error(err: io::Error, path: &Path) -> CheckoutError {
@@ -2139,6 +3325,18 @@ impl WorkingCopy for LocalWorkingCopy {
         Ok(self.tree_state()?.sparse_patterns())
     }
 
+    fn sparse_mode(&self) -> Result<SparseMode, WorkingCopyStateError> {
+        Ok(self.tree_state()?.sparse_mode())
+    }
+
+    fn sparse_profile(&self) -> Result<Option<&RepoPath>, WorkingCopyStateError> {
+        Ok(self.tree_state()?.sparse_profile())
+    }
+
+    fn interrupted_checkout(&self) -> Result<Option<&MergedTreeId>, WorkingCopyStateError> {
+        Ok(self.tree_state()?.interrupted_checkout())
+    }
+
     fn start_mutation(&self) -> Result<Box<dyn LockedWorkingCopy>, WorkingCopyStateError> {
         let lock_path = self.state_path.join("working_copy.lock");
         let lock = FileLock::lock(lock_path).map_err(|err| WorkingCopyStateError {
@@ -2337,6 +3535,34 @@ impl LocalWorkingCopy {
                 err: err.into(),
             })
     }
+
+    #[cfg(all(target_os = "linux", feature = "inotify"))]
+    pub fn query_inotify(
+        &self,
+        config: &InotifyConfig,
+    ) -> Result<(inotify::Cursor, Option<Vec<PathBuf>>), WorkingCopyStateError> {
+        self.tree_state()?
+            .query_inotify(config)
+            .map_err(|err| WorkingCopyStateError {
+                message: "Failed to query the inotify daemon".to_string(),
+                err: err.into(),
+            })
+    }
+
+    /// Connects to the inotify daemon for this working copy, starting it if
+    /// configured to.
+    #[cfg(all(target_os = "linux", feature = "inotify"))]
+    pub fn inotify_fsmonitor(
+        &self,
+        config: &InotifyConfig,
+    ) -> Result<inotify::Fsmonitor, WorkingCopyStateError> {
+        self.tree_state()?
+            .inotify_fsmonitor(config)
+            .map_err(|err| WorkingCopyStateError {
+                message: "Failed to connect to the inotify daemon".to_string(),
+                err: err.into(),
+            })
+    }
 }
 
 pub struct LocalWorkingCopyFactory {}
@@ -2427,8 +3653,6 @@ impl LockedWorkingCopy for LockedLocalWorkingCopy {
         commit: &Commit,
         options: &CheckoutOptions,
     ) -> Result<CheckoutStats, CheckoutError> {
-        // TODO: Write a "pending_checkout" file with the new TreeId so we can
-        // continue an interrupted update if we find such a file.
         let new_tree = commit.tree()?;
         let tree_state = self
             .wc
@@ -2437,7 +3661,9 @@ impl LockedWorkingCopy for LockedLocalWorkingCopy {
                 message: "Failed to load the working copy state".to_string(),
                 err: err.into(),
             })?;
-        if tree_state.tree_id != *commit.tree_id() {
+        // An interrupted checkout has to be finished even if the tree is the
+        // same.
+        if tree_state.tree_id != *commit.tree_id() || tree_state.pending_checkout.is_some() {
             let stats = tree_state.check_out(&new_tree, options)?;
             self.tree_state_dirty = true;
             Ok(stats)
@@ -2482,6 +3708,14 @@ impl LockedWorkingCopy for LockedLocalWorkingCopy {
         self.wc.sparse_patterns()
     }
 
+    fn sparse_mode(&self) -> Result<SparseMode, WorkingCopyStateError> {
+        self.wc.sparse_mode()
+    }
+
+    fn sparse_profile(&self) -> Result<Option<&RepoPath>, WorkingCopyStateError> {
+        self.wc.sparse_profile()
+    }
+
     fn set_sparse_patterns(
         &mut self,
         new_sparse_patterns: Vec<RepoPathBuf>,
@@ -2501,6 +3735,42 @@ impl LockedWorkingCopy for LockedLocalWorkingCopy {
         Ok(stats)
     }
 
+    fn set_sparse_patterns_and_mode(
+        &mut self,
+        new_sparse_patterns: Vec<RepoPathBuf>,
+        new_sparse_mode: SparseMode,
+        options: &CheckoutOptions,
+    ) -> Result<CheckoutStats, CheckoutError> {
+        let stats = self
+            .wc
+            .tree_state_mut()
+            .map_err(|err| CheckoutError::Other {
+                message: "Failed to load the working copy state".to_string(),
+                err: err.into(),
+            })?
+            .set_sparse_patterns_and_mode(new_sparse_patterns, new_sparse_mode, options)?;
+        self.tree_state_dirty = true;
+        Ok(stats)
+    }
+
+    fn set_sparse_profile(
+        &mut self,
+        profile_path: RepoPathBuf,
+        new_sparse_mode: SparseMode,
+        options: &CheckoutOptions,
+    ) -> Result<CheckoutStats, CheckoutError> {
+        let stats = self
+            .wc
+            .tree_state_mut()
+            .map_err(|err| CheckoutError::Other {
+                message: "Failed to load the working copy state".to_string(),
+                err: err.into(),
+            })?
+            .set_sparse_profile(profile_path, new_sparse_mode, options)?;
+        self.tree_state_dirty = true;
+        Ok(stats)
+    }
+
     #[instrument(skip_all)]
     fn finish(
         mut self: Box<Self>,
@@ -2540,6 +3810,32 @@ impl LockedLocalWorkingCopy {
         self.tree_state_dirty = true;
         Ok(())
     }
+
+    pub fn reset_inotify(&mut self) -> Result<(), SnapshotError> {
+        self.wc
+            .tree_state_mut()
+            .map_err(|err| SnapshotError::Other {
+                message: "Failed to read the working copy state".to_string(),
+                err: err.into(),
+            })?
+            .reset_inotify();
+        self.tree_state_dirty = true;
+        Ok(())
+    }
+
+    /// Takes over the state of the `source` working copy, whose files have
+    /// been cloned into this working copy (e.g. by
+    /// [`crate::file_util::clone_dir_all()`]). Only the files which differ from
+    /// the source will have to be updated by the next checkout.
+    pub fn adopt_tree_state(
+        &mut self,
+        source: &LocalWorkingCopy,
+    ) -> Result<(), WorkingCopyStateError> {
+        let source_tree_state = source.tree_state()?;
+        self.wc.tree_state_mut()?.adopt(source_tree_state);
+        self.tree_state_dirty = true;
+        Ok(())
+    }
 }
 
 #[cfg(test)]
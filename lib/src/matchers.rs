@@ -238,6 +238,56 @@ fn prefix_tree_to_visit_sets(tree: &RepoPathTree<PrefixNodeKind>) -> Visit {
     Visit::sets(dirs, files)
 }
 
+/// Matches file paths under the given prefixes, plus the files directly in
+/// the ancestor directories of the prefixes. This is the "cone mode" of Git's
+/// sparse checkouts.
+#[derive(Debug)]
+pub struct ConeMatcher {
+    tree: RepoPathTree<PrefixNodeKind>,
+}
+
+impl ConeMatcher {
+    #[instrument(skip(prefixes))]
+    pub fn new(prefixes: impl IntoIterator<Item = impl AsRef<RepoPath>>) -> Self {
+        let mut tree = RepoPathTree::default();
+        for prefix in prefixes {
+            tree.add(prefix.as_ref()).value = PrefixNodeKind::Prefix;
+        }
+        Self { tree }
+    }
+}
+
+impl Matcher for ConeMatcher {
+    fn matches(&self, file: &RepoPath) -> bool {
+        self.tree.walk_to(file).any(|(sub, tail_path)| {
+            // ancestor of 'file' matches prefix paths, or 'file' is directly
+            // in an ancestor directory of prefix paths
+            sub.value == PrefixNodeKind::Prefix
+                || (!sub.entries.is_empty() && tail_path.components().count() == 1)
+        })
+    }
+
+    fn visit(&self, dir: &RepoPath) -> Visit {
+        for (sub, tail_path) in self.tree.walk_to(dir) {
+            // ancestor of 'dir' matches prefix paths
+            if sub.value == PrefixNodeKind::Prefix {
+                return Visit::AllRecursively;
+            }
+            // 'dir' found, and is an ancestor of prefix paths
+            if tail_path.is_root() {
+                if sub.entries.is_empty() {
+                    break;
+                }
+                return Visit::Specific {
+                    dirs: VisitDirs::Set(sub.entries.keys().cloned().collect()),
+                    files: VisitFiles::All,
+                };
+            }
+        }
+        Visit::Nothing
+    }
+}
+
 /// Matches file paths with glob patterns.
 ///
 /// Patterns are provided as `(dir, pattern)` pairs, where `dir` should be the
@@ -649,6 +699,58 @@ mod tests {
         assert_eq!(m.visit(repo_path("bar")), Visit::Nothing);
     }
 
+    #[test]
+    fn test_conematcher_empty() {
+        let m = ConeMatcher::new([] as [&RepoPath; 0]);
+        assert!(!m.matches(repo_path("file")));
+        assert!(!m.matches(repo_path("dir/file")));
+        assert_eq!(m.visit(RepoPath::root()), Visit::Nothing);
+    }
+
+    #[test]
+    fn test_conematcher_root() {
+        let m = ConeMatcher::new([RepoPath::root()]);
+        assert!(m.matches(repo_path("file")));
+        assert!(m.matches(repo_path("dir/file")));
+        assert_eq!(m.visit(RepoPath::root()), Visit::AllRecursively);
+        assert_eq!(m.visit(repo_path("foo/bar")), Visit::AllRecursively);
+    }
+
+    #[test]
+    fn test_conematcher_single_prefix() {
+        let m = ConeMatcher::new([repo_path("foo/bar")]);
+
+        // Files in the ancestor directories of the prefix should match
+        assert!(m.matches(repo_path("file")));
+        assert!(m.matches(repo_path("foo/file")));
+        assert!(m.matches(repo_path("foo/bar")));
+        // Files in the prefix directory should match recursively
+        assert!(m.matches(repo_path("foo/bar/baz")));
+        assert!(m.matches(repo_path("foo/bar/baz/qux")));
+        // Files in sibling directories should not match
+        assert!(!m.matches(repo_path("foo/baz/file")));
+        assert!(!m.matches(repo_path("baz/file")));
+
+        assert_eq!(
+            m.visit(RepoPath::root()),
+            Visit::Specific {
+                dirs: VisitDirs::Set(hashset! {repo_path_component_buf("foo")}),
+                files: VisitFiles::All,
+            }
+        );
+        assert_eq!(
+            m.visit(repo_path("foo")),
+            Visit::Specific {
+                dirs: VisitDirs::Set(hashset! {repo_path_component_buf("bar")}),
+                files: VisitFiles::All,
+            }
+        );
+        assert_eq!(m.visit(repo_path("foo/bar")), Visit::AllRecursively);
+        assert_eq!(m.visit(repo_path("foo/bar/baz")), Visit::AllRecursively);
+        assert_eq!(m.visit(repo_path("foo/baz")), Visit::Nothing);
+        assert_eq!(m.visit(repo_path("bar")), Visit::Nothing);
+    }
+
     #[test]
     fn test_prefixmatcher_nested_prefixes() {
         let m = PrefixMatcher::new([repo_path("foo"), repo_path("foo/bar/baz")]);
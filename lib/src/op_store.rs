@@ -30,6 +30,9 @@ use crate::backend::CommitId;
 use crate::backend::MillisSinceEpoch;
 use crate::backend::Timestamp;
 use crate::content_hash::ContentHash;
+use crate::content_hash::DigestUpdate;
+use crate::content_hash::blake2b_hash;
+use crate::hex_util;
 use crate::merge::Merge;
 use crate::object_id::HexPrefix;
 use crate::object_id::ObjectId as _;
@@ -372,7 +375,7 @@ pub struct TimestampRange {
 /// taken and it will be checked that the current head of the operation
 /// graph is unchanged. If the current head has changed, there has been
 /// concurrent operation.
-#[derive(ContentHash, PartialEq, Eq, Clone, Debug, serde::Serialize)]
+#[derive(PartialEq, Eq, Clone, Debug, serde::Serialize)]
 pub struct Operation {
     #[serde(skip)] // TODO: should be exposed?
     pub view_id: ViewId,
@@ -396,6 +399,21 @@ pub struct Operation {
     // cost matters, maybe this can be changed to sorted Vec.
     #[serde(skip)] // TODO: should be exposed?
     pub commit_predecessors: Option<BTreeMap<CommitId, Vec<CommitId>>>,
+    /// Cryptographic signature of [`Self::signed_data()`], or `None` if the
+    /// operation isn't signed.
+    #[serde(skip)]
+    pub signature: Option<Vec<u8>>,
+}
+
+impl ContentHash for Operation {
+    fn hash(&self, state: &mut impl DigestUpdate) {
+        UnsignedOperation(self).hash(state);
+        // Omitted if unsigned so the hash of unsigned operations is compatible
+        // with the one computed before the signature field was introduced.
+        if let Some(signature) = &self.signature {
+            signature.hash(state);
+        }
+    }
 }
 
 impl Operation {
@@ -424,8 +442,36 @@ impl Operation {
             // may be other commits created within the abandoned operations.
             // They don't have any predecessors records as well.
             commit_predecessors: Some(BTreeMap::new()),
+            signature: None,
         }
     }
+
+    /// Returns the data to be signed.
+    ///
+    /// This is the hex-encoded content hash of the operation excluding the
+    /// signature, so it doesn't depend on how the operation is serialized.
+    pub fn signed_data(&self) -> Vec<u8> {
+        hex_util::encode_hex(&blake2b_hash(&UnsignedOperation(self))).into_bytes()
+    }
+}
+
+/// Content of the operation excluding the signature.
+struct UnsignedOperation<'a>(&'a Operation);
+
+impl ContentHash for UnsignedOperation<'_> {
+    fn hash(&self, state: &mut impl DigestUpdate) {
+        let Operation {
+            view_id,
+            parents,
+            metadata,
+            commit_predecessors,
+            signature: _,
+        } = self.0;
+        view_id.hash(state);
+        parents.hash(state);
+        metadata.hash(state);
+        commit_predecessors.hash(state);
+    }
 }
 
 #[derive(ContentHash, PartialEq, Eq, Clone, Debug, serde::Serialize)]
@@ -375,6 +375,8 @@ pub fn reparent_range(
             .filter_map(|id| rewritten_ids.get(id).or_else(|| dest_once.take()))
             .cloned()
             .collect();
+        // The signature covers the parents, so it can't be preserved.
+        data.signature = None;
         let new_id = op_store.write_operation(&data)?;
         rewritten_ids.insert(old_op.id().clone(), new_id);
     }
@@ -0,0 +1,1734 @@
+// Copyright 2025 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Parsing, applying and formatting patches.
+//!
+//! Plain unified diffs are supported, as well as git's extended diffs with
+//! renames, mode changes and binary patches, and mailboxes produced by
+//! `git format-patch`.
+
+use std::collections::BTreeMap;
+use std::io::Read as _;
+use std::io::Write as _;
+use std::sync::Arc;
+
+use bstr::BString;
+use bstr::ByteSlice as _;
+use chrono::DateTime;
+use flate2::Compression;
+use flate2::read::ZlibDecoder;
+use flate2::write::ZlibEncoder;
+use itertools::Itertools as _;
+use pollster::FutureExt as _;
+use thiserror::Error;
+use tokio::io::AsyncReadExt as _;
+
+use crate::backend::BackendError;
+use crate::backend::CopyId;
+use crate::backend::MergedTreeId;
+use crate::backend::Timestamp;
+use crate::backend::TreeValue;
+use crate::merge::Merge;
+use crate::merge::MergedTreeValue;
+use crate::merged_tree::MergedTree;
+use crate::merged_tree::MergedTreeBuilder;
+use crate::repo_path::RepoPath;
+use crate::repo_path::RepoPathBuf;
+use crate::store::Store;
+
+/// The maximum number of context lines which may be ignored at either end of a
+/// hunk when it doesn't apply as is.
+const MAX_FUZZ: usize = 2;
+
+/// The characters used by git's base85 encoding of binary patches.
+const BASE85_ALPHABET: &[u8; 85] =
+    b"0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz!#$%&()*+-;<=>?@^_`{|}~";
+
+/// Error while parsing a patch.
+#[derive(Debug, Error)]
+pub enum PatchParseError {
+    /// A hunk header couldn't be parsed.
+    #[error("Invalid hunk header on line {line_number}")]
+    InvalidHunkHeader {
+        /// The 1-based line number in the input.
+        line_number: usize,
+    },
+    /// The lines of a hunk don't match the line counts in its header.
+    #[error("Hunk starting on line {line_number} doesn't match its header")]
+    InvalidHunk {
+        /// The 1-based line number of the hunk header in the input.
+        line_number: usize,
+    },
+    /// A file path couldn't be parsed.
+    #[error("Invalid path on line {line_number}")]
+    InvalidPath {
+        /// The 1-based line number in the input.
+        line_number: usize,
+    },
+    /// A file mode couldn't be parsed.
+    #[error("Invalid file mode on line {line_number}")]
+    InvalidMode {
+        /// The 1-based line number in the input.
+        line_number: usize,
+    },
+    /// The data of a binary patch couldn't be decoded.
+    #[error("Invalid binary patch on line {line_number}")]
+    InvalidBinaryPatch {
+        /// The 1-based line number in the input.
+        line_number: usize,
+    },
+    /// The body of a mail couldn't be decoded according to its
+    /// `Content-Transfer-Encoding`.
+    #[error("Invalid {encoding} mail body on line {line_number}")]
+    InvalidMailBody {
+        /// The transfer encoding of the body.
+        encoding: String,
+        /// The 1-based line number of the start of the body in the input.
+        line_number: usize,
+    },
+}
+
+/// Error while applying a patch to a tree.
+#[derive(Debug, Error)]
+pub enum PatchApplyError {
+    /// The file to change doesn't exist.
+    #[error("Cannot patch {path}: the file doesn't exist", path = path.as_internal_file_string())]
+    MissingFile {
+        /// The path of the file.
+        path: RepoPathBuf,
+    },
+    /// The file to change has conflicts.
+    #[error("Cannot patch {path}: the file has conflicts", path = path.as_internal_file_string())]
+    ConflictedFile {
+        /// The path of the file.
+        path: RepoPathBuf,
+    },
+    /// The file isn't a regular file or a symlink.
+    #[error("Cannot patch {path}: unsupported file type", path = path.as_internal_file_string())]
+    UnsupportedFileType {
+        /// The path of the file.
+        path: RepoPathBuf,
+    },
+    /// The patched symlink target isn't valid UTF-8.
+    #[error("Cannot patch {path}: invalid symlink target", path = path.as_internal_file_string())]
+    InvalidSymlinkTarget {
+        /// The path of the symlink.
+        path: RepoPathBuf,
+    },
+    /// A binary patch couldn't be applied.
+    #[error("Cannot apply binary patch to {path}: {reason}", path = path.as_internal_file_string())]
+    BinaryPatch {
+        /// The path of the file.
+        path: RepoPathBuf,
+        /// Why the patch couldn't be applied.
+        reason: &'static str,
+    },
+    /// Failed to read or write a file.
+    #[error(transparent)]
+    Backend(#[from] BackendError),
+}
+
+/// A patch read from a mail or from a patch file.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct PatchMessage {
+    /// The name and email of the author, from the `From` header.
+    pub author: Option<(String, String)>,
+    /// The author timestamp, from the `Date` header.
+    pub date: Option<Timestamp>,
+    /// The subject and body of the mail, or the empty string if the patch
+    /// isn't a mail.
+    pub description: String,
+    /// The changes to the files.
+    pub files: Vec<FilePatch>,
+}
+
+/// Mode of a file in a git diff.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PatchFileMode {
+    /// A regular file.
+    Normal,
+    /// An executable file.
+    Executable,
+    /// A symbolic link.
+    Symlink,
+    /// A git submodule.
+    GitSubmodule,
+}
+
+/// The changes to a single file.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct FilePatch {
+    /// The path before the change, or `None` if the file is added.
+    pub old_path: Option<RepoPathBuf>,
+    /// The path after the change, or `None` if the file is deleted.
+    pub new_path: Option<RepoPathBuf>,
+    /// The mode before the change, if known.
+    pub old_mode: Option<PatchFileMode>,
+    /// The mode after the change, if known.
+    pub new_mode: Option<PatchFileMode>,
+    /// Whether the file is copied rather than renamed when the paths differ.
+    pub is_copy: bool,
+    /// The changes to the text of the file.
+    pub hunks: Vec<Hunk>,
+    /// The changes to the content of a binary file.
+    pub binary: Option<BinaryPatch>,
+}
+
+/// A range of changed lines with some surrounding context.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Hunk {
+    /// The 1-based line number where the hunk starts in the old file. If the
+    /// hunk doesn't contain any old line, this is the line after which the
+    /// new lines are inserted.
+    pub old_start: usize,
+    /// The lines of the hunk, each including its newline unless it's the last
+    /// line of a file without a trailing newline.
+    pub lines: Vec<HunkLine>,
+}
+
+/// A line of a hunk.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum HunkLine {
+    /// A line which is kept.
+    Context(BString),
+    /// A line which is removed.
+    Removed(BString),
+    /// A line which is added.
+    Added(BString),
+}
+
+impl HunkLine {
+    fn text_mut(&mut self) -> &mut BString {
+        match self {
+            Self::Context(text) | Self::Removed(text) | Self::Added(text) => text,
+        }
+    }
+}
+
+impl Hunk {
+    fn old_lines(lines: &[HunkLine]) -> Vec<&[u8]> {
+        lines
+            .iter()
+            .filter_map(|line| match line {
+                HunkLine::Context(text) | HunkLine::Removed(text) => Some(text.as_slice()),
+                HunkLine::Added(_) => None,
+            })
+            .collect()
+    }
+
+    fn new_lines(lines: &[HunkLine]) -> Vec<&[u8]> {
+        lines
+            .iter()
+            .filter_map(|line| match line {
+                HunkLine::Context(text) | HunkLine::Added(text) => Some(text.as_slice()),
+                HunkLine::Removed(_) => None,
+            })
+            .collect()
+    }
+
+    /// The 0-based index of the first old line of the hunk.
+    fn old_index(&self) -> usize {
+        if Self::old_lines(&self.lines).is_empty() {
+            self.old_start
+        } else {
+            self.old_start.saturating_sub(1)
+        }
+    }
+
+    fn context_lengths(&self) -> (usize, usize) {
+        let is_context = |line: &&HunkLine| matches!(line, HunkLine::Context(_));
+        let leading = self.lines.iter().take_while(is_context).count();
+        let trailing = self.lines.iter().rev().take_while(is_context).count();
+        (leading, trailing)
+    }
+}
+
+/// The new content of a binary file.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum BinaryPatch {
+    /// The full new content.
+    Literal(Vec<u8>),
+    /// A git delta to apply to the old content.
+    Delta(Vec<u8>),
+    /// The diff only says that the files differ.
+    Unavailable,
+}
+
+/// The result of applying patches to a tree.
+#[derive(Debug)]
+pub struct AppliedPatches {
+    /// The new tree.
+    pub tree_id: MergedTreeId,
+    /// The paths where some hunks couldn't be applied, or where a file to add
+    /// already existed. The changes are recorded as conflicts in the files.
+    pub conflicted_paths: Vec<RepoPathBuf>,
+    /// The file patches which couldn't be applied at all, such as changes to
+    /// a missing file. These files are left unchanged.
+    pub skipped: Vec<PatchApplyError>,
+}
+
+struct Lines<'a> {
+    lines: &'a [&'a [u8]],
+    pos: usize,
+    first_line_number: usize,
+}
+
+impl<'a> Lines<'a> {
+    fn new(lines: &'a [&'a [u8]], first_line_number: usize) -> Self {
+        Self {
+            lines,
+            pos: 0,
+            first_line_number,
+        }
+    }
+
+    fn peek(&self) -> Option<&'a [u8]> {
+        self.lines.get(self.pos).copied()
+    }
+
+    fn peek_at(&self, offset: usize) -> Option<&'a [u8]> {
+        self.lines.get(self.pos + offset).copied()
+    }
+
+    fn advance(&mut self) {
+        self.pos += 1;
+    }
+
+    /// The 1-based line number of the next line.
+    fn line_number(&self) -> usize {
+        self.first_line_number + self.pos
+    }
+}
+
+fn trim_newline(line: &[u8]) -> &[u8] {
+    let line = line.strip_suffix(b"\n").unwrap_or(line);
+    line.strip_suffix(b"\r").unwrap_or(line)
+}
+
+fn is_blank(line: &[u8]) -> bool {
+    line.trim().is_empty()
+}
+
+/// Parses a patch file, or a mailbox containing one mail per patch.
+///
+/// If the input isn't a mail, it's returned as a single message without
+/// author, date or description.
+pub fn parse_mailbox(data: &[u8]) -> Result<Vec<PatchMessage>, PatchParseError> {
+    let lines: Vec<&[u8]> = data.split_inclusive(|&b| b == b'\n').collect();
+    if !looks_like_mail(&lines) {
+        let files = parse_file_patches(Lines::new(&lines, 1))?;
+        return Ok(vec![PatchMessage {
+            files,
+            ..Default::default()
+        }]);
+    }
+    let separators = (0..lines.len())
+        .filter(|&index| is_mbox_separator(&lines, index))
+        .collect::<Vec<_>>();
+    if separators.is_empty() {
+        return Ok(vec![parse_message(Lines::new(&lines, 1))?]);
+    }
+    let mut messages = vec![];
+    for (i, &separator) in separators.iter().enumerate() {
+        let start = separator + 1;
+        let end = separators.get(i + 1).copied().unwrap_or(lines.len());
+        messages.push(parse_message(Lines::new(&lines[start..end], start + 1))?);
+    }
+    Ok(messages)
+}
+
+fn header_name(line: &[u8]) -> Option<&[u8]> {
+    let (name, _) = line.split_once_str(":")?;
+    (!name.is_empty() && name.iter().all(|&b| b.is_ascii_alphanumeric() || b == b'-'))
+        .then_some(name)
+}
+
+/// Whether the line starts a new mail in an mbox file.
+fn is_mbox_separator(lines: &[&[u8]], index: usize) -> bool {
+    lines[index].starts_with(b"From ")
+        && (index == 0 || is_blank(lines[index - 1]))
+        && lines
+            .get(index + 1)
+            .is_some_and(|line| header_name(line).is_some())
+}
+
+fn looks_like_mail(lines: &[&[u8]]) -> bool {
+    let Some(first_line) = lines.first() else {
+        return false;
+    };
+    is_mbox_separator(lines, 0)
+        || header_name(first_line).is_some_and(|name| {
+            [b"From".as_slice(), b"Subject", b"Date"]
+                .iter()
+                .any(|known| name.eq_ignore_ascii_case(known))
+        })
+}
+
+fn parse_message(mut lines: Lines<'_>) -> Result<PatchMessage, PatchParseError> {
+    let mut headers: Vec<(String, String)> = vec![];
+    while let Some(line) = lines.peek() {
+        if is_blank(line) {
+            lines.advance();
+            break;
+        }
+        let line = String::from_utf8_lossy(trim_newline(line));
+        if line.starts_with([' ', '\t']) {
+            // Continuation of a folded header
+            if let Some((_, value)) = headers.last_mut() {
+                value.push(' ');
+                value.push_str(line.trim());
+            }
+        } else if let Some((name, value)) = line.split_once(':') {
+            headers.push((name.to_ascii_lowercase(), value.trim().to_owned()));
+        }
+        lines.advance();
+    }
+    let header = |name: &str| {
+        headers
+            .iter()
+            .find(|(header_name, _)| header_name == name)
+            .map(|(_, value)| decode_encoded_words(value))
+    };
+
+    let author = header("from").map(|from| parse_address(&from));
+    let date = header("date")
+        .and_then(|date| DateTime::parse_from_rfc2822(&date).ok())
+        .map(Timestamp::from_datetime);
+    let subject = header("subject").unwrap_or_default();
+    let encoding = header("content-transfer-encoding").unwrap_or_default();
+    let encoding = encoding.trim().to_ascii_lowercase();
+
+    let (body, files) = match encoding.as_str() {
+        "" | "7bit" | "8bit" | "binary" => parse_message_body(lines)?,
+        _ => {
+            let data = lines.lines[lines.pos..].concat();
+            let decoded = match encoding.as_str() {
+                "quoted-printable" => decode_quoted_printable_body(&data),
+                "base64" => decode_base64_body(&data),
+                _ => None,
+            }
+            .ok_or_else(|| PatchParseError::InvalidMailBody {
+                encoding: encoding.clone(),
+                line_number: lines.line_number(),
+            })?;
+            // Line numbers in errors are counted from the start of the body,
+            // since the decoded lines don't match the lines in the input.
+            let decoded_lines: Vec<&[u8]> = decoded.split_inclusive(|&b| b == b'\n').collect();
+            parse_message_body(Lines::new(&decoded_lines, lines.line_number()))?
+        }
+    };
+
+    let mut description = strip_subject_prefixes(&subject).to_owned();
+    if !body.is_empty() {
+        description.push_str("\n\n");
+        description.push_str(&body);
+    }
+    if !description.is_empty() {
+        description.push('\n');
+    }
+    Ok(PatchMessage {
+        author,
+        date,
+        description,
+        files,
+    })
+}
+
+/// Parses the body of a mail into the description and the file patches.
+fn parse_message_body(mut lines: Lines<'_>) -> Result<(String, Vec<FilePatch>), PatchParseError> {
+    // The body ends at the "---" line before the diffstat, or where the diff
+    // starts if there's no such line.
+    let body_start = lines.pos;
+    while let Some(line) = lines.peek() {
+        if trim_newline(line) == b"---"
+            || line.starts_with(b"diff --git ")
+            || (line.starts_with(b"--- ")
+                && lines
+                    .peek_at(1)
+                    .is_some_and(|next| next.starts_with(b"+++ ")))
+        {
+            break;
+        }
+        lines.advance();
+    }
+    let body = lines.lines[body_start..lines.pos].concat();
+    let body = String::from_utf8_lossy(&body)
+        .trim_start_matches(['\n', '\r'])
+        .trim_end()
+        .to_owned();
+    let files = parse_file_patches(lines)?;
+    Ok((body, files))
+}
+
+/// Parses an address such as `Jane Doe <jane@example.com>` into a name and an
+/// email.
+fn parse_address(address: &str) -> (String, String) {
+    match address.rsplit_once('<') {
+        Some((name, email)) => {
+            let name = name.trim();
+            let name = name
+                .strip_prefix('"')
+                .and_then(|name| name.strip_suffix('"'))
+                .unwrap_or(name);
+            let email = email.split_once('>').map_or(email, |(email, _)| email);
+            (name.to_owned(), email.trim().to_owned())
+        }
+        None => (String::new(), address.trim().to_owned()),
+    }
+}
+
+/// Removes prefixes such as `[PATCH v2 1/3]` from a subject.
+fn strip_subject_prefixes(mut subject: &str) -> &str {
+    loop {
+        subject = subject.trim_start();
+        let Some(rest) = subject.strip_prefix('[') else {
+            break;
+        };
+        let Some(end) = rest.find(']') else {
+            break;
+        };
+        subject = &rest[end + 1..];
+    }
+    subject.trim_end()
+}
+
+/// Decodes the RFC 2047 encoded words, such as `=?UTF-8?q?Ren=C3=A9?=`, in a
+/// header value.
+fn decode_encoded_words(value: &str) -> String {
+    let mut result = String::new();
+    let mut rest = value;
+    let mut after_encoded_word = false;
+    while let Some(start) = rest.find("=?") {
+        let (before, candidate) = rest.split_at(start);
+        let Some((decoded, len)) = decode_encoded_word(candidate) else {
+            result.push_str(&rest[..start + 2]);
+            rest = &rest[start + 2..];
+            after_encoded_word = false;
+            continue;
+        };
+        // Whitespace between adjacent encoded words is ignored.
+        if !(after_encoded_word && before.trim().is_empty()) {
+            result.push_str(before);
+        }
+        result.push_str(&decoded);
+        rest = &candidate[len..];
+        after_encoded_word = true;
+    }
+    result.push_str(rest);
+    result
+}
+
+/// Decodes the encoded word at the start of `text`, returning the decoded text
+/// and the length of the encoded word.
+fn decode_encoded_word(text: &str) -> Option<(String, usize)> {
+    let (charset, rest) = text.strip_prefix("=?")?.split_once('?')?;
+    let (encoding, rest) = rest.split_once('?')?;
+    let end = rest.find("?=")?;
+    let encoded = &rest[..end];
+    let bytes = if encoding.eq_ignore_ascii_case("q") {
+        decode_quoted_printable(encoded)?
+    } else if encoding.eq_ignore_ascii_case("b") {
+        decode_base64(encoded)?
+    } else {
+        return None;
+    };
+    let decoded = if charset.eq_ignore_ascii_case("iso-8859-1") {
+        bytes.iter().map(|&b| char::from(b)).collect()
+    } else {
+        String::from_utf8_lossy(&bytes).into_owned()
+    };
+    Some((decoded, text.len() - rest.len() + end + 2))
+}
+
+fn decode_quoted_printable(text: &str) -> Option<Vec<u8>> {
+    let mut bytes = vec![];
+    let mut iter = text.bytes();
+    while let Some(b) = iter.next() {
+        match b {
+            b'_' => bytes.push(b' '),
+            b'=' => {
+                let hex = [iter.next()?, iter.next()?];
+                bytes.push(u8::from_str_radix(std::str::from_utf8(&hex).ok()?, 16).ok()?);
+            }
+            _ => bytes.push(b),
+        }
+    }
+    Some(bytes)
+}
+
+/// Decodes a quoted-printable mail body. Unlike in encoded words, `_` isn't
+/// a space, and a `=` at the end of a line joins it with the next line.
+fn decode_quoted_printable_body(data: &[u8]) -> Option<Vec<u8>> {
+    let mut bytes = vec![];
+    for line in data.split_inclusive(|&b| b == b'\n') {
+        let content = trim_newline(line);
+        let (content, newline) = match content.strip_suffix(b"=") {
+            Some(content) => (content, [].as_slice()),
+            None => (content, &line[content.len()..]),
+        };
+        let mut iter = content.iter();
+        while let Some(&b) = iter.next() {
+            if b == b'=' {
+                let hex = [*iter.next()?, *iter.next()?];
+                bytes.push(u8::from_str_radix(std::str::from_utf8(&hex).ok()?, 16).ok()?);
+            } else {
+                bytes.push(b);
+            }
+        }
+        bytes.extend_from_slice(newline);
+    }
+    Some(bytes)
+}
+
+/// Decodes a base64 mail body, which is split into lines.
+fn decode_base64_body(data: &[u8]) -> Option<Vec<u8>> {
+    let text: Vec<u8> = data
+        .iter()
+        .copied()
+        .filter(|b| !b.is_ascii_whitespace())
+        .collect();
+    decode_base64(std::str::from_utf8(&text).ok()?)
+}
+
+fn decode_base64(text: &str) -> Option<Vec<u8>> {
+    let mut bytes = vec![];
+    let mut acc: u32 = 0;
+    let mut bits = 0;
+    for c in text.bytes().take_while(|&c| c != b'=') {
+        let value = match c {
+            b'A'..=b'Z' => c - b'A',
+            b'a'..=b'z' => c - b'a' + 26,
+            b'0'..=b'9' => c - b'0' + 52,
+            b'+' => 62,
+            b'/' => 63,
+            _ => return None,
+        };
+        acc = (acc << 6) | u32::from(value);
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            bytes.push((acc >> bits) as u8);
+            acc &= (1 << bits) - 1;
+        }
+    }
+    Some(bytes)
+}
+
+fn parse_file_patches(mut lines: Lines<'_>) -> Result<Vec<FilePatch>, PatchParseError> {
+    let mut patches = vec![];
+    while let Some(line) = lines.peek() {
+        if let Some(paths) = line.strip_prefix(b"diff --git ") {
+            let line_number = lines.line_number();
+            lines.advance();
+            patches.push(parse_git_file_patch(&mut lines, paths, line_number)?);
+        } else if line.starts_with(b"--- ")
+            && lines
+                .peek_at(1)
+                .is_some_and(|next| next.starts_with(b"+++ "))
+        {
+            patches.push(parse_unified_file_patch(&mut lines)?);
+        } else {
+            // Commentary, a diffstat, or a mail signature
+            lines.advance();
+        }
+    }
+    Ok(patches)
+}
+
+fn parse_git_file_patch(
+    lines: &mut Lines<'_>,
+    paths: &[u8],
+    line_number: usize,
+) -> Result<FilePatch, PatchParseError> {
+    let (old_path, new_path) = parse_git_diff_paths(trim_newline(paths))
+        .ok_or(PatchParseError::InvalidPath { line_number })?;
+    let mut patch = FilePatch {
+        old_path: Some(to_repo_path(strip_path_prefix(&old_path), line_number)?),
+        new_path: Some(to_repo_path(strip_path_prefix(&new_path), line_number)?),
+        ..Default::default()
+    };
+
+    // Extended header lines
+    while let Some(line) = lines.peek() {
+        let line_number = lines.line_number();
+        let line = trim_newline(line);
+        let parse_mode = |mode: &[u8]| parse_mode(mode, line_number);
+        let parse_path = |path: &[u8]| {
+            parse_path_field(path)
+                .ok_or(PatchParseError::InvalidPath { line_number })
+                .and_then(|path| to_repo_path(&path, line_number))
+        };
+        if let Some(mode) = line.strip_prefix(b"old mode ") {
+            patch.old_mode = Some(parse_mode(mode)?);
+        } else if let Some(mode) = line.strip_prefix(b"new mode ") {
+            patch.new_mode = Some(parse_mode(mode)?);
+        } else if let Some(mode) = line.strip_prefix(b"deleted file mode ") {
+            patch.old_mode = Some(parse_mode(mode)?);
+            patch.new_path = None;
+        } else if let Some(mode) = line.strip_prefix(b"new file mode ") {
+            patch.new_mode = Some(parse_mode(mode)?);
+            patch.old_path = None;
+        } else if let Some(path) = line.strip_prefix(b"rename from ") {
+            patch.old_path = Some(parse_path(path)?);
+        } else if let Some(path) = line.strip_prefix(b"rename to ") {
+            patch.new_path = Some(parse_path(path)?);
+        } else if let Some(path) = line.strip_prefix(b"copy from ") {
+            patch.old_path = Some(parse_path(path)?);
+            patch.is_copy = true;
+        } else if let Some(path) = line.strip_prefix(b"copy to ") {
+            patch.new_path = Some(parse_path(path)?);
+            patch.is_copy = true;
+        } else if let Some(index) = line.strip_prefix(b"index ") {
+            // The mode is only given here if it's unchanged.
+            if let Some((_, mode)) = index.split_once_str(" ") {
+                let mode = parse_mode(mode)?;
+                patch.old_mode.get_or_insert(mode);
+                patch.new_mode.get_or_insert(mode);
+            }
+        } else if !line.starts_with(b"similarity index ")
+            && !line.starts_with(b"dissimilarity index ")
+        {
+            break;
+        }
+        lines.advance();
+    }
+
+    match lines.peek() {
+        Some(line)
+            if line.starts_with(b"--- ")
+                && lines
+                    .peek_at(1)
+                    .is_some_and(|next| next.starts_with(b"+++ ")) =>
+        {
+            // The file names were already parsed from the extended headers,
+            // which are more reliable.
+            lines.advance();
+            lines.advance();
+            patch.hunks = parse_hunks(lines)?;
+        }
+        Some(line) if trim_newline(line) == b"GIT binary patch" => {
+            lines.advance();
+            patch.binary = Some(parse_binary_patch(lines)?);
+        }
+        Some(line) if line.starts_with(b"Binary files ") => {
+            lines.advance();
+            patch.binary = Some(BinaryPatch::Unavailable);
+        }
+        _ => {}
+    }
+    Ok(patch)
+}
+
+fn parse_unified_file_patch(lines: &mut Lines<'_>) -> Result<FilePatch, PatchParseError> {
+    let mut parse_path_line = |prefix: &[u8]| -> Result<Option<RepoPathBuf>, PatchParseError> {
+        let line_number = lines.line_number();
+        let path = lines
+            .peek()
+            .and_then(|line| line.strip_prefix(prefix))
+            .and_then(parse_path_field)
+            .ok_or(PatchParseError::InvalidPath { line_number })?;
+        lines.advance();
+        if path == b"/dev/null" {
+            Ok(None)
+        } else {
+            to_repo_path(strip_path_prefix(&path), line_number).map(Some)
+        }
+    };
+    let old_path = parse_path_line(b"--- ")?;
+    let new_path = parse_path_line(b"+++ ")?;
+    // Plain diffs can't express renames, so a file with different names on
+    // both sides is patched in place, under its new name.
+    let old_path = match (old_path, &new_path) {
+        (Some(_), Some(new_path)) => Some(new_path.clone()),
+        (old_path, _) => old_path,
+    };
+    Ok(FilePatch {
+        old_path,
+        new_path,
+        hunks: parse_hunks(lines)?,
+        ..Default::default()
+    })
+}
+
+/// Parses the paths of a `diff --git` line.
+fn parse_git_diff_paths(text: &[u8]) -> Option<(Vec<u8>, Vec<u8>)> {
+    if text.starts_with(b"\"") {
+        let (old_path, rest) = unquote_path(text)?;
+        let rest = rest.strip_prefix(b" ")?;
+        let new_path = if rest.starts_with(b"\"") {
+            unquote_path(rest)?.0
+        } else {
+            rest.to_vec()
+        };
+        return Some((old_path, new_path));
+    }
+    // The names are separated by a space, but may contain spaces themselves.
+    // Unless the file is renamed, both names are the same, which resolves the
+    // ambiguity.
+    if text.len() % 2 == 1 && text[text.len() / 2] == b' ' {
+        let (old_path, new_path) = (&text[..text.len() / 2], &text[text.len() / 2 + 1..]);
+        if strip_path_prefix(old_path) == strip_path_prefix(new_path) {
+            return Some((old_path.to_vec(), new_path.to_vec()));
+        }
+    }
+    let (old_path, new_path) = text.split_once_str(" ")?;
+    let new_path = if new_path.starts_with(b"\"") {
+        unquote_path(new_path)?.0
+    } else {
+        new_path.to_vec()
+    };
+    Some((old_path.to_vec(), new_path))
+}
+
+/// Parses a path which may be quoted, and may be followed by a tab and a
+/// timestamp.
+fn parse_path_field(text: &[u8]) -> Option<Vec<u8>> {
+    let text = trim_newline(text);
+    if text.starts_with(b"\"") {
+        unquote_path(text).map(|(path, _)| path)
+    } else {
+        let end = text.find_byte(b'\t').unwrap_or(text.len());
+        Some(text[..end].to_vec())
+    }
+}
+
+/// Parses a path quoted by git, returning the path and the remaining text.
+fn unquote_path(text: &[u8]) -> Option<(Vec<u8>, &[u8])> {
+    let mut path = vec![];
+    let mut i = 1;
+    loop {
+        match *text.get(i)? {
+            b'"' => return Some((path, &text[i + 1..])),
+            b'\\' => {
+                let c = *text.get(i + 1)?;
+                match c {
+                    b'0'..=b'7' => {
+                        let digits = std::str::from_utf8(text.get(i + 1..i + 4)?).ok()?;
+                        path.push(u8::from_str_radix(digits, 8).ok()?);
+                        i += 4;
+                        continue;
+                    }
+                    b'a' => path.push(0x07),
+                    b'b' => path.push(0x08),
+                    b'f' => path.push(0x0c),
+                    b'n' => path.push(b'\n'),
+                    b'r' => path.push(b'\r'),
+                    b't' => path.push(b'\t'),
+                    b'v' => path.push(0x0b),
+                    _ => path.push(c),
+                }
+                i += 2;
+            }
+            c => {
+                path.push(c);
+                i += 1;
+            }
+        }
+    }
+}
+
+/// Removes the first component, such as `a/`, of a path in a diff.
+fn strip_path_prefix(path: &[u8]) -> &[u8] {
+    path.find_byte(b'/').map_or(path, |i| &path[i + 1..])
+}
+
+fn to_repo_path(path: &[u8], line_number: usize) -> Result<RepoPathBuf, PatchParseError> {
+    std::str::from_utf8(path)
+        .ok()
+        .filter(|path| !path.is_empty())
+        .and_then(|path| RepoPathBuf::from_internal_string(path).ok())
+        .ok_or(PatchParseError::InvalidPath { line_number })
+}
+
+fn parse_mode(text: &[u8], line_number: usize) -> Result<PatchFileMode, PatchParseError> {
+    let mode = std::str::from_utf8(text)
+        .ok()
+        .and_then(|text| u32::from_str_radix(text.trim(), 8).ok())
+        .ok_or(PatchParseError::InvalidMode { line_number })?;
+    match mode & 0o170000 {
+        0o100000 if mode & 0o111 != 0 => Ok(PatchFileMode::Executable),
+        0o100000 => Ok(PatchFileMode::Normal),
+        0o120000 => Ok(PatchFileMode::Symlink),
+        0o160000 => Ok(PatchFileMode::GitSubmodule),
+        _ => Err(PatchParseError::InvalidMode { line_number }),
+    }
+}
+
+/// Parses a hunk header such as `@@ -1,3 +1,4 @@`, returning the start line
+/// and line count of the old side, and the line count of the new side.
+fn parse_hunk_header(line: &[u8]) -> Option<(usize, usize, usize)> {
+    let header = line.strip_prefix(b"@@ -")?;
+    let end = header.find(" @@")?;
+    let ranges = std::str::from_utf8(&header[..end]).ok()?;
+    let (old_range, new_range) = ranges.split_once(" +")?;
+    let parse_range = |range: &str| -> Option<(usize, usize)> {
+        match range.split_once(',') {
+            Some((start, count)) => Some((start.parse().ok()?, count.parse().ok()?)),
+            None => Some((range.parse().ok()?, 1)),
+        }
+    };
+    let (old_start, old_count) = parse_range(old_range)?;
+    let (_, new_count) = parse_range(new_range)?;
+    Some((old_start, old_count, new_count))
+}
+
+fn parse_hunks(lines: &mut Lines<'_>) -> Result<Vec<Hunk>, PatchParseError> {
+    let mut hunks = vec![];
+    while let Some(line) = lines.peek() {
+        if !line.starts_with(b"@@ ") {
+            break;
+        }
+        let line_number = lines.line_number();
+        let (old_start, old_count, new_count) =
+            parse_hunk_header(line).ok_or(PatchParseError::InvalidHunkHeader { line_number })?;
+        lines.advance();
+        let invalid_hunk = || PatchParseError::InvalidHunk { line_number };
+        let mut hunk = Hunk {
+            old_start,
+            lines: vec![],
+        };
+        let mut old_remaining = old_count;
+        let mut new_remaining = new_count;
+        loop {
+            // A "\ No newline at end of file" marker applies to the previous
+            // line.
+            if lines.peek().is_some_and(|line| line.starts_with(b"\\")) {
+                let text = hunk.lines.last_mut().ok_or_else(invalid_hunk)?.text_mut();
+                if text.ends_with(b"\n") {
+                    text.pop();
+                }
+                lines.advance();
+                continue;
+            }
+            if old_remaining == 0 && new_remaining == 0 {
+                break;
+            }
+            let line = lines.peek().ok_or_else(invalid_hunk)?;
+            let (hunk_line, is_old, is_new) = match line[0] {
+                b' ' => (HunkLine::Context(line[1..].into()), true, true),
+                // Some mail clients strip the trailing space of empty context
+                // lines.
+                b'\n' | b'\r' => (HunkLine::Context(line.into()), true, true),
+                b'-' => (HunkLine::Removed(line[1..].into()), true, false),
+                b'+' => (HunkLine::Added(line[1..].into()), false, true),
+                _ => return Err(invalid_hunk()),
+            };
+            if is_old {
+                old_remaining = old_remaining.checked_sub(1).ok_or_else(invalid_hunk)?;
+            }
+            if is_new {
+                new_remaining = new_remaining.checked_sub(1).ok_or_else(invalid_hunk)?;
+            }
+            hunk.lines.push(hunk_line);
+            lines.advance();
+        }
+        hunks.push(hunk);
+    }
+    Ok(hunks)
+}
+
+fn parse_binary_patch(lines: &mut Lines<'_>) -> Result<BinaryPatch, PatchParseError> {
+    let line_number = lines.line_number();
+    let invalid = || PatchParseError::InvalidBinaryPatch { line_number };
+    let header = trim_newline(lines.peek().ok_or_else(invalid)?);
+    let (is_delta, size) = if let Some(size) = header.strip_prefix(b"literal ") {
+        (false, size)
+    } else if let Some(size) = header.strip_prefix(b"delta ") {
+        (true, size)
+    } else {
+        return Err(invalid());
+    };
+    let size: usize = std::str::from_utf8(size)
+        .ok()
+        .and_then(|size| size.parse().ok())
+        .ok_or_else(invalid)?;
+    lines.advance();
+
+    let mut compressed = vec![];
+    while let Some(line) = lines.peek() {
+        lines.advance();
+        let line = trim_newline(line);
+        if line.is_empty() {
+            break;
+        }
+        decode_base85_line(line, &mut compressed).ok_or_else(invalid)?;
+    }
+    // The size comes from the patch, so it's only trusted after the data has
+    // been inflated. Reading one more byte than expected detects longer data
+    // without inflating all of it.
+    let limit = u64::try_from(size)
+        .map_err(|_| invalid())?
+        .saturating_add(1);
+    let mut data = vec![];
+    ZlibDecoder::new(compressed.as_slice())
+        .take(limit)
+        .read_to_end(&mut data)
+        .map_err(|_| invalid())?;
+    if data.len() != size {
+        return Err(invalid());
+    }
+
+    // Skip the reverse patch, which isn't needed.
+    if lines
+        .peek()
+        .is_some_and(|line| line.starts_with(b"literal ") || line.starts_with(b"delta "))
+    {
+        while let Some(line) = lines.peek() {
+            lines.advance();
+            if is_blank(line) {
+                break;
+            }
+        }
+    }
+
+    if is_delta {
+        Ok(BinaryPatch::Delta(data))
+    } else {
+        Ok(BinaryPatch::Literal(data))
+    }
+}
+
+/// Decodes a line of a binary patch. The first character encodes the number of
+/// decoded bytes, and each group of 5 characters encodes 4 bytes.
+fn decode_base85_line(line: &[u8], out: &mut Vec<u8>) -> Option<()> {
+    let (&len_char, encoded) = line.split_first()?;
+    let len = usize::from(match len_char {
+        b'A'..=b'Z' => len_char - b'A' + 1,
+        b'a'..=b'z' => len_char - b'a' + 27,
+        _ => return None,
+    });
+    if encoded.len() != len.div_ceil(4) * 5 {
+        return None;
+    }
+    let mut decoded = Vec::with_capacity(encoded.len() / 5 * 4);
+    for chunk in encoded.chunks(5) {
+        let mut value: u32 = 0;
+        for c in chunk {
+            let digit = BASE85_ALPHABET.iter().position(|a| a == c)?;
+            value = value.checked_mul(85)?.checked_add(digit as u32)?;
+        }
+        decoded.extend_from_slice(&value.to_be_bytes());
+    }
+    out.extend_from_slice(&decoded[..len]);
+    Some(())
+}
+
+/// Encodes a line of a binary patch, which holds up to 52 bytes.
+fn encode_base85_line(data: &[u8]) -> String {
+    let len = data.len() as u8;
+    let mut line = String::new();
+    line.push(char::from(if len <= 26 {
+        b'A' + len - 1
+    } else {
+        b'a' + len - 27
+    }));
+    for chunk in data.chunks(4) {
+        let mut bytes = [0; 4];
+        bytes[..chunk.len()].copy_from_slice(chunk);
+        let mut value = u32::from_be_bytes(bytes);
+        let mut digits = [0; 5];
+        for digit in digits.iter_mut().rev() {
+            *digit = BASE85_ALPHABET[(value % 85) as usize];
+            value /= 85;
+        }
+        line.extend(digits.map(char::from));
+    }
+    line
+}
+
+/// Formats the change from `old` to `new` as a git binary patch, like
+/// `git diff --binary` does. The reverse patch is included.
+pub fn format_binary_patch(old: &[u8], new: &[u8]) -> String {
+    let mut patch = "GIT binary patch\n".to_owned();
+    for content in [new, old] {
+        patch.push_str(&format!("literal {}\n", content.len()));
+        let mut encoder = ZlibEncoder::new(vec![], Compression::default());
+        // Writing to a Vec can't fail.
+        encoder.write_all(content).unwrap();
+        let compressed = encoder.finish().unwrap();
+        for chunk in compressed.chunks(52) {
+            patch.push_str(&encode_base85_line(chunk));
+            patch.push('\n');
+        }
+        patch.push('\n');
+    }
+    patch
+}
+
+fn read_delta_size(delta: &mut &[u8]) -> Option<usize> {
+    let mut size = 0;
+    let mut shift = 0;
+    loop {
+        let (&b, rest) = delta.split_first()?;
+        *delta = rest;
+        size |= usize::from(b & 0x7f).checked_shl(shift)?;
+        if b & 0x80 == 0 {
+            return Some(size);
+        }
+        shift += 7;
+    }
+}
+
+/// Applies a git delta to `base`, or returns `None` if the delta is invalid or
+/// was made for another base.
+fn apply_git_delta(base: &[u8], mut delta: &[u8]) -> Option<Vec<u8>> {
+    let base_size = read_delta_size(&mut delta)?;
+    let result_size = read_delta_size(&mut delta)?;
+    if base_size != base.len() {
+        return None;
+    }
+    let mut result = vec![];
+    let next_byte = |delta: &mut &[u8]| -> Option<usize> {
+        let (&b, rest) = delta.split_first()?;
+        *delta = rest;
+        Some(usize::from(b))
+    };
+    while let Some(cmd) = next_byte(&mut delta) {
+        if cmd & 0x80 != 0 {
+            // Copy from the base
+            let mut offset = 0;
+            let mut size = 0;
+            for i in 0..4 {
+                if cmd & (1 << i) != 0 {
+                    offset |= next_byte(&mut delta)? << (8 * i);
+                }
+            }
+            for i in 0..3 {
+                if cmd & (0x10 << i) != 0 {
+                    size |= next_byte(&mut delta)? << (8 * i);
+                }
+            }
+            if size == 0 {
+                size = 0x10000;
+            }
+            result.extend_from_slice(base.get(offset..offset.checked_add(size)?)?);
+        } else if cmd != 0 {
+            // Insert the following bytes
+            result.extend_from_slice(delta.get(..cmd)?);
+            delta = &delta[cmd..];
+        } else {
+            return None;
+        }
+    }
+    (result.len() == result_size).then_some(result)
+}
+
+/// Finds the position of `needle` in `haystack` nearest to `expected`, not
+/// before `min`.
+fn find_lines(haystack: &[&[u8]], needle: &[&[u8]], expected: usize, min: usize) -> Option<usize> {
+    let max = haystack.len().checked_sub(needle.len())?;
+    if min > max {
+        return None;
+    }
+    let expected = expected.clamp(min, max);
+    let matches_at = |pos: usize| haystack[pos..pos + needle.len()] == *needle;
+    (0..=max - min)
+        .flat_map(|distance| [expected.checked_sub(distance), Some(expected + distance)])
+        .flatten()
+        .find(|&pos| (min..=max).contains(&pos) && matches_at(pos))
+}
+
+/// The result of applying hunks to the content of a file.
+struct AppliedHunks<'a> {
+    content: Vec<u8>,
+    /// The hunks which couldn't be applied, with the line of the new content
+    /// where they would have been applied.
+    rejected: Vec<(usize, &'a Hunk)>,
+}
+
+/// Applies the hunks in order, searching for each one near its recorded
+/// position and ignoring up to `MAX_FUZZ` context lines at either end if
+/// needed.
+fn apply_hunks<'a>(content: &[u8], hunks: &'a [Hunk]) -> AppliedHunks<'a> {
+    let old_lines: Vec<&[u8]> = content.split_inclusive(|&b| b == b'\n').collect();
+    let mut new_lines: Vec<&[u8]> = vec![];
+    // The old position and old and new line counts of the applied hunks
+    let mut applied: Vec<(usize, usize, usize)> = vec![];
+    let mut rejected = vec![];
+    // The number of old lines which were copied to the new lines
+    let mut copied = 0;
+    // The difference between the actual and recorded positions of the last
+    // applied hunk
+    let mut offset: isize = 0;
+    for hunk in hunks {
+        let expected = hunk.old_index().saturating_add_signed(offset);
+        let (leading_context, trailing_context) = hunk.context_lengths();
+        let found = (0..=MAX_FUZZ).find_map(|fuzz| {
+            let leading = fuzz.min(leading_context);
+            let trailing = fuzz.min(trailing_context);
+            if fuzz > 0 && leading < fuzz && trailing < fuzz {
+                // Same as the previous fuzz level
+                return None;
+            }
+            let lines = &hunk.lines[leading..hunk.lines.len() - trailing];
+            let pre = Hunk::old_lines(lines);
+            let pos = find_lines(&old_lines, &pre, expected + leading, copied)?;
+            Some((pos, leading, pre.len(), Hunk::new_lines(lines)))
+        });
+        match found {
+            Some((pos, leading, old_len, post)) => {
+                new_lines.extend_from_slice(&old_lines[copied..pos]);
+                applied.push((pos, old_len, post.len()));
+                new_lines.extend(post);
+                copied = pos + old_len;
+                offset = pos as isize - (hunk.old_index() + leading) as isize;
+            }
+            None => rejected.push((expected.clamp(copied, old_lines.len()), hunk)),
+        }
+    }
+    new_lines.extend_from_slice(&old_lines[copied..]);
+
+    // Translate the positions of the rejected hunks to the new content.
+    let rejected = rejected
+        .into_iter()
+        .map(|(old_pos, hunk)| {
+            let mut pos = old_pos;
+            let mut new_pos = old_pos as isize;
+            for &(start, old_len, new_len) in &applied {
+                if start >= pos {
+                    break;
+                }
+                // Rejected hunks go after the applied hunks they overlap.
+                new_pos += (pos.max(start + old_len) - pos) as isize;
+                pos = pos.max(start + old_len);
+                new_pos += new_len as isize - old_len as isize;
+            }
+            (new_pos as usize, hunk)
+        })
+        .collect();
+    AppliedHunks {
+        content: new_lines.concat(),
+        rejected,
+    }
+}
+
+/// Returns the sides and bases of a conflict recording the rejected hunks.
+///
+/// There's one base and one extra side per hunk. The base has the old lines of
+/// the hunk inserted into `content`, and the side has the new lines, so each
+/// hunk shows up as a conflict at the position where it should have applied.
+fn rejected_hunk_conflict(
+    content: &[u8],
+    rejected: &[(usize, &Hunk)],
+) -> (Vec<Vec<u8>>, Vec<Vec<u8>>) {
+    let lines: Vec<&[u8]> = content.split_inclusive(|&b| b == b'\n').collect();
+    let insert_at = |pos: usize, inserted: Vec<&[u8]>| {
+        [&lines[..pos], &inserted, &lines[pos..]].concat().concat()
+    };
+    let removes = rejected
+        .iter()
+        .map(|&(pos, hunk)| insert_at(pos, Hunk::old_lines(&hunk.lines)))
+        .collect();
+    let adds = [content.to_vec()]
+        .into_iter()
+        .chain(
+            rejected
+                .iter()
+                .map(|&(pos, hunk)| insert_at(pos, Hunk::new_lines(&hunk.lines))),
+        )
+        .collect();
+    (removes, adds)
+}
+
+fn read_content(
+    store: &Store,
+    path: &RepoPath,
+    value: &TreeValue,
+) -> Result<Vec<u8>, PatchApplyError> {
+    match value {
+        TreeValue::File { id, .. } => {
+            let mut content = vec![];
+            store
+                .read_file(path, id)
+                .block_on()?
+                .read_to_end(&mut content)
+                .block_on()
+                .map_err(|err| BackendError::ReadFile {
+                    path: path.to_owned(),
+                    id: id.clone(),
+                    source: err.into(),
+                })?;
+            Ok(content)
+        }
+        TreeValue::Symlink(id) => Ok(store.read_symlink(path, id).block_on()?.into_bytes()),
+        _ => Err(PatchApplyError::UnsupportedFileType {
+            path: path.to_owned(),
+        }),
+    }
+}
+
+fn write_content(
+    store: &Store,
+    path: &RepoPath,
+    mode: PatchFileMode,
+    content: Vec<u8>,
+) -> Result<TreeValue, PatchApplyError> {
+    match mode {
+        PatchFileMode::Normal | PatchFileMode::Executable => {
+            let id = store.write_file(path, &mut content.as_slice()).block_on()?;
+            Ok(TreeValue::File {
+                id,
+                executable: mode == PatchFileMode::Executable,
+                copy_id: CopyId::placeholder(),
+            })
+        }
+        PatchFileMode::Symlink => {
+            let target =
+                String::from_utf8(content).map_err(|_| PatchApplyError::InvalidSymlinkTarget {
+                    path: path.to_owned(),
+                })?;
+            Ok(TreeValue::Symlink(
+                store.write_symlink(path, &target).block_on()?,
+            ))
+        }
+        PatchFileMode::GitSubmodule => Err(PatchApplyError::UnsupportedFileType {
+            path: path.to_owned(),
+        }),
+    }
+}
+
+fn current_value(
+    tree: &MergedTree,
+    new_values: &BTreeMap<RepoPathBuf, MergedTreeValue>,
+    path: &RepoPath,
+) -> Result<MergedTreeValue, BackendError> {
+    match new_values.get(path) {
+        Some(value) => Ok(value.clone()),
+        None => tree.path_value(path),
+    }
+}
+
+/// Applies the changes to `tree`.
+///
+/// Hunks which can't be applied, and files which are added but already exist,
+/// are recorded as conflicts in the files instead of failing. Patches to
+/// missing files and binary patches which don't match the file are skipped.
+pub fn apply_file_patches(
+    store: &Arc<Store>,
+    tree: &MergedTree,
+    patches: &[FilePatch],
+) -> Result<AppliedPatches, PatchApplyError> {
+    // The values written so far, so a file can be patched more than once
+    let mut new_values: BTreeMap<RepoPathBuf, MergedTreeValue> = BTreeMap::new();
+    let mut conflicted_paths = vec![];
+    let mut skipped = vec![];
+    for patch in patches {
+        let old_value = match &patch.old_path {
+            Some(path) => {
+                let value = current_value(tree, &new_values, path)?
+                    .into_resolved()
+                    .map_err(|_| PatchApplyError::ConflictedFile { path: path.clone() })?;
+                let Some(value) = value else {
+                    skipped.push(PatchApplyError::MissingFile { path: path.clone() });
+                    continue;
+                };
+                Some((path, value))
+            }
+            None => None,
+        };
+        let old_content = match &old_value {
+            Some((path, value)) => read_content(store, path, value)?,
+            None => vec![],
+        };
+        let Some(new_path) = &patch.new_path else {
+            if let Some((path, _)) = old_value {
+                new_values.insert(path.clone(), Merge::absent());
+            }
+            continue;
+        };
+        let existing_value = match &old_value {
+            Some(_) => None,
+            None => Some(current_value(tree, &new_values, new_path)?).filter(|v| v.is_present()),
+        };
+
+        let old_mode = match &old_value {
+            Some((_, TreeValue::File { executable, .. })) => Some(if *executable {
+                PatchFileMode::Executable
+            } else {
+                PatchFileMode::Normal
+            }),
+            Some((_, TreeValue::Symlink(_))) => Some(PatchFileMode::Symlink),
+            _ => None,
+        };
+        let mode = patch.new_mode.or(old_mode).unwrap_or(PatchFileMode::Normal);
+        let binary_error = |reason| PatchApplyError::BinaryPatch {
+            path: new_path.clone(),
+            reason,
+        };
+        let new_value = match &patch.binary {
+            Some(BinaryPatch::Literal(data)) => {
+                Merge::normal(write_content(store, new_path, mode, data.clone())?)
+            }
+            Some(BinaryPatch::Delta(delta)) => {
+                let Some(content) = apply_git_delta(&old_content, delta) else {
+                    skipped.push(binary_error("the delta doesn't match the file"));
+                    continue;
+                };
+                Merge::normal(write_content(store, new_path, mode, content)?)
+            }
+            Some(BinaryPatch::Unavailable) => {
+                skipped.push(binary_error("the patch doesn't contain the binary data"));
+                continue;
+            }
+            None => {
+                let applied = apply_hunks(&old_content, &patch.hunks);
+                if applied.rejected.is_empty() {
+                    Merge::normal(write_content(store, new_path, mode, applied.content)?)
+                } else {
+                    conflicted_paths.push(new_path.clone());
+                    let (removes, adds) =
+                        rejected_hunk_conflict(&applied.content, &applied.rejected);
+                    let write = |content| write_content(store, new_path, mode, content).map(Some);
+                    let removes: Vec<_> = removes.into_iter().map(write).try_collect()?;
+                    let adds: Vec<_> = adds.into_iter().map(write).try_collect()?;
+                    Merge::from_removes_adds(removes, adds)
+                }
+            }
+        };
+        // A file which already exists is treated like a file added on both
+        // sides.
+        let new_value = match existing_value {
+            Some(existing_value) => {
+                if !conflicted_paths.contains(new_path) {
+                    conflicted_paths.push(new_path.clone());
+                }
+                Merge::from_vec(vec![existing_value, Merge::absent(), new_value])
+                    .flatten()
+                    .simplify()
+            }
+            None => new_value,
+        };
+
+        if let Some((old_path, _)) = old_value {
+            if old_path != new_path && !patch.is_copy {
+                new_values.insert(old_path.clone(), Merge::absent());
+            }
+        }
+        new_values.insert(new_path.clone(), new_value);
+    }
+
+    let mut tree_builder = MergedTreeBuilder::new(tree.id());
+    for (path, value) in new_values {
+        tree_builder.set_or_remove(path, value);
+    }
+    let tree_id = tree_builder.write_tree(store)?;
+    Ok(AppliedPatches {
+        tree_id,
+        conflicted_paths,
+        skipped,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use assert_matches::assert_matches;
+    use indoc::indoc;
+
+    use super::*;
+
+    fn repo_path(value: &str) -> RepoPathBuf {
+        RepoPathBuf::from_internal_string(value).unwrap()
+    }
+
+    fn hunk_lines(text: &str) -> Vec<HunkLine> {
+        text.split_inclusive('\n')
+            .map(|line| {
+                let (kind, text) = line.split_at(1);
+                match kind {
+                    " " => HunkLine::Context(text.into()),
+                    "-" => HunkLine::Removed(text.into()),
+                    "+" => HunkLine::Added(text.into()),
+                    _ => panic!("invalid hunk line {line:?}"),
+                }
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_parse_mailbox() {
+        let data = indoc! {b"
+            From 0123456789abcdef Mon Sep 17 00:00:00 2001
+            From: =?UTF-8?q?Ren=C3=A9=20Doe?= <rene@example.com>
+            Date: Sat, 3 Feb 2001 04:05:06 +0700
+            Subject: [PATCH 1/2] Add a
+             file
+
+            Some details.
+            ---
+             a | 1 +
+             1 file changed, 1 insertion(+)
+
+            diff --git a/a b/a
+            new file mode 100755
+            index 0000000..7898192
+            --- /dev/null
+            +++ b/a
+            @@ -0,0 +1 @@
+            +a
+            --
+            2.40.0
+
+            From 0123456789abcdef Mon Sep 17 00:00:00 2001
+            From: Jane Doe <jane@example.com>
+            Subject: [PATCH 2/2] Rename a
+
+            diff --git a/a b/my file
+            similarity index 100%
+            rename from a
+            rename to my file
+        "};
+        let messages = parse_mailbox(data).unwrap();
+        assert_eq!(messages.len(), 2);
+        assert_eq!(
+            messages[0].author,
+            Some(("René Doe".to_owned(), "rene@example.com".to_owned()))
+        );
+        assert_eq!(messages[0].date.unwrap().timestamp.0, 981_147_906_000);
+        assert_eq!(messages[0].date.unwrap().tz_offset, 7 * 60);
+        assert_eq!(messages[0].description, "Add a file\n\nSome details.\n");
+        assert_eq!(
+            messages[0].files,
+            vec![FilePatch {
+                old_path: None,
+                new_path: Some(repo_path("a")),
+                new_mode: Some(PatchFileMode::Executable),
+                hunks: vec![Hunk {
+                    old_start: 0,
+                    lines: hunk_lines("+a\n"),
+                }],
+                ..Default::default()
+            }]
+        );
+        assert_eq!(messages[1].description, "Rename a\n");
+        assert_eq!(messages[1].date, None);
+        assert_eq!(
+            messages[1].files,
+            vec![FilePatch {
+                old_path: Some(repo_path("a")),
+                new_path: Some(repo_path("my file")),
+                ..Default::default()
+            }]
+        );
+    }
+
+    #[test]
+    fn test_parse_mailbox_transfer_encoding() {
+        let data = indoc! {b"
+            From: Jane Doe <jane@example.com>
+            Subject: [PATCH] Change a
+            Content-Transfer-Encoding: quoted-printable
+
+            Caf=C3=A9 au lait, with a long line which is wrapped by the m=
+            ailer.
+            ---
+            diff --git a/a b/a
+            --- a/a
+            +++ b/a
+            @@ -1 +1 @@
+            -a=3D1
+            +a=3D2
+        "};
+        let messages = parse_mailbox(data).unwrap();
+        assert_eq!(
+            messages[0].description,
+            "Change a\n\nCafé au lait, with a long line which is wrapped by the mailer.\n"
+        );
+        assert_eq!(
+            messages[0].files[0].hunks[0].lines,
+            hunk_lines("-a=1\n+a=2\n")
+        );
+
+        // "Change b\n---\n--- a/b\n+++ b/b\n@@ -1 +1 @@\n-b\n+c\n"
+        let data = indoc! {b"
+            From: Jane Doe <jane@example.com>
+            Subject: [PATCH] Change b
+            Content-Transfer-Encoding: base64
+
+            Q2hhbmdlIGIKLS0tCi0tLSBhL2IKKysrIGIvYgpAQCAtMSArMSBAQAotYgorYwo=
+        "};
+        let messages = parse_mailbox(data).unwrap();
+        assert_eq!(messages[0].description, "Change b\n\nChange b\n");
+        assert_eq!(messages[0].files[0].hunks[0].lines, hunk_lines("-b\n+c\n"));
+
+        let data = indoc! {b"
+            Subject: [PATCH] Change c
+            Content-Transfer-Encoding: base64
+
+            not base64!
+        "};
+        assert_matches!(
+            parse_mailbox(data),
+            Err(PatchParseError::InvalidMailBody { line_number: 4, .. })
+        );
+    }
+
+    #[test]
+    fn test_parse_plain_diff() {
+        let data = indoc! {b"
+            Some commentary
+            --- old/file\t2001-02-03 04:05:06
+            +++ new/file\t2001-02-03 04:05:07
+            @@ -1,2 +1,2 @@
+             a
+            -b
+            \\ No newline at end of file
+            +c
+            \\ No newline at end of file
+        "};
+        let messages = parse_mailbox(data).unwrap();
+        assert_eq!(
+            messages,
+            vec![PatchMessage {
+                files: vec![FilePatch {
+                    old_path: Some(repo_path("file")),
+                    new_path: Some(repo_path("file")),
+                    hunks: vec![Hunk {
+                        old_start: 1,
+                        lines: vec![
+                            HunkLine::Context("a\n".into()),
+                            HunkLine::Removed("b".into()),
+                            HunkLine::Added("c".into()),
+                        ],
+                    }],
+                    ..Default::default()
+                }],
+                ..Default::default()
+            }]
+        );
+
+        let data = indoc! {b"
+            --- a/file
+            +++ b/file
+            @@ -1,2 +1,2 @@
+             a
+        "};
+        assert_matches!(
+            parse_mailbox(data),
+            Err(PatchParseError::InvalidHunk { line_number: 3 })
+        );
+    }
+
+    #[test]
+    fn test_parse_git_paths() {
+        assert_eq!(
+            parse_git_diff_paths(b"a/my file b/my file"),
+            Some((b"a/my file".to_vec(), b"b/my file".to_vec()))
+        );
+        assert_eq!(
+            parse_git_diff_paths(b"a/old b/new"),
+            Some((b"a/old".to_vec(), b"b/new".to_vec()))
+        );
+        assert_eq!(
+            parse_git_diff_paths(br#""a/tab\there" "b/\303\251""#),
+            Some((b"a/tab\there".to_vec(), "b/é".as_bytes().to_vec()))
+        );
+    }
+
+    #[test]
+    fn test_parse_binary_patch() {
+        // "hello\n" as written by `git diff --binary`
+        let data = indoc! {b"
+            diff --git a/bin b/bin
+            new file mode 100644
+            index 0000000000000000000000000000000000000000..ce013625030ba8dba906f756967f9e9ca394464a
+            GIT binary patch
+            literal 6
+            Nc-qTI&B@8<0ssh00v`YX
+
+            literal 0
+            Hc-jL100001
+
+        "};
+        let messages = parse_mailbox(data).unwrap();
+        assert_eq!(
+            messages[0].files[0].binary,
+            Some(BinaryPatch::Literal(b"hello\n".to_vec()))
+        );
+    }
+
+    #[test]
+    fn test_parse_binary_patch_size_mismatch() {
+        // "hello\n" with the wrong size in the header
+        for size in ["5", "7", "18446744073709551615"] {
+            let data = format!(
+                "diff --git a/bin b/bin\nnew file mode 100644\nindex 0000..ce01\nGIT binary \
+                 patch\nliteral {size}\nNc-qTI&B@8<0ssh00v`YX\n\n"
+            );
+            assert_matches!(
+                parse_mailbox(data.as_bytes()),
+                Err(PatchParseError::InvalidBinaryPatch { .. })
+            );
+        }
+    }
+
+    #[test]
+    fn test_format_binary_patch_round_trip() {
+        let old = b"\0old".to_vec();
+        let new: Vec<u8> = (0..=255).cycle().take(1000).collect();
+        let data = format!(
+            "diff --git a/bin b/bin\nindex 1234..5678 100644\n{}",
+            format_binary_patch(&old, &new)
+        );
+        let messages = parse_mailbox(data.as_bytes()).unwrap();
+        assert_eq!(messages[0].files[0].binary, Some(BinaryPatch::Literal(new)));
+    }
+
+    #[test]
+    fn test_apply_git_delta() {
+        // Copy 5 bytes at offset 0, then insert "!\n".
+        let delta = [6, 7, 0x90, 5, 2, b'!', b'\n'];
+        assert_eq!(
+            apply_git_delta(b"hello\n", &delta),
+            Some(b"hello!\n".to_vec())
+        );
+        assert_eq!(apply_git_delta(b"hi\n", &delta), None);
+    }
+
+    #[test]
+    fn test_apply_hunks_with_offset_and_fuzz() {
+        let content = b"0\n1\n2\n3\n4\n5\n6\n7\n";
+        // Recorded 2 lines too early, with a wrong first context line
+        let hunk = Hunk {
+            old_start: 1,
+            lines: hunk_lines(" x\n 3\n-4\n+four\n 5\n"),
+        };
+        let applied = apply_hunks(content, std::slice::from_ref(&hunk));
+        assert_eq!(applied.content, b"0\n1\n2\n3\nfour\n5\n6\n7\n");
+        assert!(applied.rejected.is_empty());
+    }
+
+    #[test]
+    fn test_apply_hunks_rejected() {
+        let content = b"a\nb\nc\nd\n";
+        let hunks = vec![
+            Hunk {
+                old_start: 1,
+                lines: hunk_lines("-a\n+A\n b\n"),
+            },
+            Hunk {
+                old_start: 3,
+                lines: hunk_lines(" x\n-y\n+Y\n z\n"),
+            },
+        ];
+        let applied = apply_hunks(content, &hunks);
+        assert_eq!(applied.content, b"A\nb\nc\nd\n");
+        assert_eq!(applied.rejected, vec![(2, &hunks[1])]);
+        let (removes, adds) = rejected_hunk_conflict(&applied.content, &applied.rejected);
+        assert_eq!(removes, vec![b"A\nb\nx\ny\nz\nc\nd\n".to_vec()]);
+        assert_eq!(
+            adds,
+            vec![b"A\nb\nc\nd\n".to_vec(), b"A\nb\nx\nY\nz\nc\nd\n".to_vec()]
+        );
+    }
+}
@@ -0,0 +1,297 @@
+// Copyright 2025 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Detection of paths which would refer to the same file on file systems that
+//! fold case or normalize Unicode.
+
+use std::borrow::Cow;
+use std::collections::BTreeSet;
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::collections::hash_map;
+use std::io;
+use std::path::Path;
+use std::path::PathBuf;
+
+use unicode_normalization::UnicodeNormalization as _;
+use unicode_normalization::is_nfc;
+
+use crate::backend::BackendResult;
+use crate::matchers::Matcher;
+use crate::merged_tree::MergedTree;
+use crate::repo_path::RepoPath;
+use crate::repo_path::RepoPathBuf;
+
+/// How a file system folds file names before comparing them.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct PathFolding {
+    /// Names differing only in case refer to the same file.
+    pub case: bool,
+    /// Names differing only in Unicode normalization (such as NFC and NFD)
+    /// refer to the same file.
+    pub unicode: bool,
+}
+
+impl PathFolding {
+    /// Folding which makes paths collide if they would collide on any
+    /// supported file system.
+    pub const ALL: Self = Self {
+        case: true,
+        unicode: true,
+    };
+
+    /// Returns true if names are compared as is.
+    pub fn is_exact(&self) -> bool {
+        !self.case && !self.unicode
+    }
+
+    /// Detects how the file system containing `dir` folds names by creating
+    /// temporary files in it.
+    pub fn probe(dir: &Path) -> io::Result<Self> {
+        // The suffix contains a lowercase letter and a precomposed (NFC)
+        // character.
+        let file = tempfile::Builder::new()
+            .prefix(".jj-probe-")
+            .suffix("-\u{e9}")
+            .tempfile_in(dir)?;
+        let name = file.path().file_name().unwrap().to_str().unwrap();
+        let case = dir.join(name.to_uppercase()).try_exists()?;
+        let unicode = dir.join(name.nfd().collect::<String>()).try_exists()?;
+        Ok(Self { case, unicode })
+    }
+
+    /// Returns the form of `name` in which colliding names are equal.
+    pub fn fold<'a>(&self, name: &'a str) -> Cow<'a, str> {
+        let mut folded = Cow::Borrowed(name);
+        if self.case && folded.chars().any(|c| c.is_uppercase()) {
+            folded = Cow::Owned(folded.to_lowercase());
+        }
+        if self.unicode && !is_nfc(&folded) {
+            folded = Cow::Owned(folded.nfc().collect());
+        }
+        folded
+    }
+}
+
+/// Detects how names are folded in the directories of a working copy.
+///
+/// Some file systems, such as ext4 with the `casefold` feature, fold case in
+/// some directories only, so each directory is probed separately. A directory
+/// which doesn't exist yet is assumed to fold names like its nearest existing
+/// ancestor, from which it would inherit the setting.
+#[derive(Debug)]
+pub struct PathFoldingProber {
+    root: PathBuf,
+    cache: HashMap<RepoPathBuf, PathFolding>,
+}
+
+impl PathFoldingProber {
+    /// Creates a prober for the working copy at `root`.
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self {
+            root: root.into(),
+            cache: HashMap::new(),
+        }
+    }
+
+    /// Returns how names are folded in the directory `dir`. The result is
+    /// cached.
+    pub fn probe(&mut self, dir: &RepoPath) -> io::Result<PathFolding> {
+        if let Some(folding) = self.cache.get(dir) {
+            return Ok(*folding);
+        }
+        let disk_dir = dir.to_fs_path_unchecked(&self.root);
+        let folding = match dir.parent() {
+            Some(parent) if !disk_dir.is_dir() => self.probe(parent)?,
+            _ => PathFolding::probe(&disk_dir)?,
+        };
+        self.cache.insert(dir.to_owned(), folding);
+        Ok(folding)
+    }
+}
+
+/// Two paths which would refer to the same file on disk.
+///
+/// The paths may be directories, in which case their contents would be merged
+/// on disk.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct PathCollision {
+    /// The path which sorts first.
+    pub path: RepoPathBuf,
+    /// The path colliding with `path`.
+    pub other_path: RepoPathBuf,
+}
+
+impl PathCollision {
+    fn new(path1: &RepoPath, path2: &RepoPath) -> Self {
+        let (path, other_path) = if path1 < path2 {
+            (path1, path2)
+        } else {
+            (path2, path1)
+        };
+        Self {
+            path: path.to_owned(),
+            other_path: other_path.to_owned(),
+        }
+    }
+}
+
+/// Finds the collisions between `paths` and their parent directories.
+pub fn find_path_collisions<'a>(
+    paths: impl IntoIterator<Item = &'a RepoPath>,
+    folding: PathFolding,
+) -> Vec<PathCollision> {
+    // Maps the folded form of each path and parent directory to the first path
+    // seen with that form.
+    let mut seen: HashMap<String, &RepoPath> = HashMap::new();
+    let mut collisions = BTreeSet::new();
+    for path in paths {
+        for prefix in path.ancestors().filter(|prefix| !prefix.is_root()) {
+            let folded = folding.fold(prefix.as_internal_file_string());
+            match seen.entry(folded.into_owned()) {
+                hash_map::Entry::Occupied(entry) => {
+                    if *entry.get() != prefix {
+                        collisions.insert(PathCollision::new(entry.get(), prefix));
+                    }
+                }
+                hash_map::Entry::Vacant(entry) => {
+                    entry.insert(prefix);
+                }
+            }
+        }
+    }
+    collisions.into_iter().collect()
+}
+
+/// Finds the paths in `tree` which collide with `added_paths` or their parent
+/// directories. Paths not matched by `matcher` are ignored.
+///
+/// This only reads the directories containing the added paths, so it's
+/// cheaper than [`find_path_collisions()`] over all paths in the tree.
+pub async fn find_added_path_collisions(
+    tree: &MergedTree,
+    added_paths: &[RepoPathBuf],
+    matcher: &dyn Matcher,
+    folding: PathFolding,
+) -> BackendResult<Vec<PathCollision>> {
+    let mut dirs: HashMap<&RepoPath, Option<MergedTree>> = HashMap::new();
+    let mut checked_paths: HashSet<&RepoPath> = HashSet::new();
+    let mut collisions = BTreeSet::new();
+    for path in added_paths {
+        for prefix in path.ancestors() {
+            let Some((dir, name)) = prefix.split() else {
+                continue;
+            };
+            if !checked_paths.insert(prefix) {
+                // The parent directories were checked too.
+                break;
+            }
+            let sub_tree = match dirs.entry(dir) {
+                hash_map::Entry::Occupied(entry) => entry.into_mut(),
+                hash_map::Entry::Vacant(entry) => entry.insert(tree.sub_tree_recursive(dir).await?),
+            };
+            let Some(sub_tree) = sub_tree else {
+                continue;
+            };
+            let folded_name = folding.fold(name.as_internal_str());
+            for other_name in sub_tree.names() {
+                if other_name == name || folding.fold(other_name.as_internal_str()) != folded_name {
+                    continue;
+                }
+                let other_path = dir.join(other_name);
+                if matcher.matches(&other_path) || !matcher.visit(&other_path).is_nothing() {
+                    collisions.insert(PathCollision::new(prefix, &other_path));
+                }
+            }
+        }
+    }
+    Ok(collisions.into_iter().collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn repo_path(value: &str) -> &RepoPath {
+        RepoPath::from_internal_string(value).unwrap()
+    }
+
+    #[test]
+    fn test_fold() {
+        let case = PathFolding {
+            case: true,
+            unicode: false,
+        };
+        let unicode = PathFolding {
+            case: false,
+            unicode: true,
+        };
+        assert_eq!(case.fold("Foo.TXT"), "foo.txt");
+        assert_eq!(case.fold("e\u{301}"), "e\u{301}");
+        assert_eq!(unicode.fold("Foo.TXT"), "Foo.TXT");
+        assert_eq!(unicode.fold("e\u{301}"), "\u{e9}");
+        assert_eq!(PathFolding::ALL.fold("E\u{301}"), "\u{e9}");
+        assert!(matches!(PathFolding::ALL.fold("foo"), Cow::Borrowed(_)));
+    }
+
+    #[test]
+    fn test_prober_missing_dir() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir(temp_dir.path().join("dir")).unwrap();
+        let root_folding = PathFolding::probe(temp_dir.path()).unwrap();
+        let mut prober = PathFoldingProber::new(temp_dir.path());
+        // Directories which don't exist fold names like their existing ancestor
+        assert_eq!(prober.probe(repo_path("dir")).unwrap(), root_folding);
+        assert_eq!(
+            prober.probe(repo_path("dir/new/sub")).unwrap(),
+            root_folding
+        );
+        assert_eq!(prober.cache.len(), 3);
+    }
+
+    #[test]
+    fn test_find_path_collisions() {
+        let paths = [
+            repo_path("Dir/a"),
+            repo_path("Foo.txt"),
+            repo_path("bar"),
+            repo_path("caf\u{e9}"),
+            repo_path("cafe\u{301}"),
+            repo_path("dir/b"),
+            repo_path("foo.txt"),
+        ];
+        assert_eq!(
+            find_path_collisions(paths, PathFolding::ALL),
+            vec![
+                PathCollision::new(repo_path("Dir"), repo_path("dir")),
+                PathCollision::new(repo_path("Foo.txt"), repo_path("foo.txt")),
+                PathCollision::new(repo_path("caf\u{e9}"), repo_path("cafe\u{301}")),
+            ]
+        );
+        assert_eq!(
+            find_path_collisions(
+                paths,
+                PathFolding {
+                    case: false,
+                    unicode: true
+                }
+            ),
+            vec![PathCollision::new(
+                repo_path("caf\u{e9}"),
+                repo_path("cafe\u{301}")
+            )]
+        );
+        assert_eq!(find_path_collisions(paths, PathFolding::default()), vec![]);
+    }
+}
@@ -127,6 +127,9 @@ pub struct Operation {
     /// Whether or not `commit_predecessors` is recorded.
     #[prost(bool, tag = "5")]
     pub stores_commit_predecessors: bool,
+    /// Cryptographic signature of the operation content. Introduced in jj 0.32.
+    #[prost(bytes = "vec", optional, tag = "6")]
+    pub signature: ::core::option::Option<::prost::alloc::vec::Vec<u8>>,
 }
 /// TODO: Share with store.proto? Do we even need the timezone here?
 #[derive(Clone, Copy, PartialEq, ::prost::Message)]
@@ -4,6 +4,9 @@ pub struct MaterializedConflictData {
     /// TODO: maybe we should store num_sides here as well
     #[prost(uint32, tag = "1")]
     pub conflict_marker_len: u32,
+    /// Whether the terms of the conflict were written to sidecar files.
+    #[prost(bool, tag = "2")]
+    pub sidecar_files: bool,
 }
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct FileState {
@@ -27,10 +30,35 @@ pub struct FileStateEntry {
     #[prost(message, optional, tag = "2")]
     pub state: ::core::option::Option<FileState>,
 }
+/// Cached listing of a directory, used to skip reading directories which
+/// haven't changed since the last snapshot.
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct DirectoryState {
+    #[prost(int64, tag = "1")]
+    pub mtime_millis_since_epoch: i64,
+    /// Fingerprint of the ignore patterns applying to the directory.
+    #[prost(bytes = "vec", tag = "2")]
+    pub ignore_fingerprint: ::prost::alloc::vec::Vec<u8>,
+    /// Names of the subdirectories which aren't ignored.
+    #[prost(string, repeated, tag = "3")]
+    pub dirs: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct DirectoryStateEntry {
+    #[prost(string, tag = "1")]
+    pub path: ::prost::alloc::string::String,
+    #[prost(message, optional, tag = "2")]
+    pub state: ::core::option::Option<DirectoryState>,
+}
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct SparsePatterns {
     #[prost(string, repeated, tag = "1")]
     pub prefixes: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+    #[prost(enumeration = "SparseMode", tag = "2")]
+    pub mode: i32,
+    /// Path to the profile in the tree the prefixes are read from.
+    #[prost(string, optional, tag = "3")]
+    pub profile: ::core::option::Option<::prost::alloc::string::String>,
 }
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct TreeState {
@@ -48,6 +76,22 @@ pub struct TreeState {
     pub sparse_patterns: ::core::option::Option<SparsePatterns>,
     #[prost(message, optional, tag = "4")]
     pub watchman_clock: ::core::option::Option<WatchmanClock>,
+    #[prost(message, optional, tag = "7")]
+    pub inotify_cursor: ::core::option::Option<InotifyCursor>,
+    #[prost(message, repeated, tag = "8")]
+    pub directory_states: ::prost::alloc::vec::Vec<DirectoryStateEntry>,
+    /// Set while a checkout is in progress, and left behind if it's interrupted.
+    #[prost(message, optional, tag = "9")]
+    pub pending_checkout: ::core::option::Option<PendingCheckout>,
+}
+/// Progress of a checkout. The paths sorting before `next_path` were updated to
+/// `target_tree_ids`, and the others still match `TreeState.tree_ids`.
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct PendingCheckout {
+    #[prost(bytes = "vec", repeated, tag = "1")]
+    pub target_tree_ids: ::prost::alloc::vec::Vec<::prost::alloc::vec::Vec<u8>>,
+    #[prost(string, tag = "2")]
+    pub next_path: ::prost::alloc::string::String,
 }
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct WatchmanClock {
@@ -65,6 +109,14 @@ pub mod watchman_clock {
     }
 }
 #[derive(Clone, PartialEq, ::prost::Message)]
+pub struct InotifyCursor {
+    /// Identifies the daemon instance which issued the cursor.
+    #[prost(string, tag = "1")]
+    pub instance_id: ::prost::alloc::string::String,
+    #[prost(uint64, tag = "2")]
+    pub sequence: u64,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
 pub struct Checkout {
     /// The operation at which the working copy was updated.
     #[prost(bytes = "vec", tag = "2")]
@@ -76,6 +128,21 @@ pub struct Checkout {
     #[prost(string, tag = "3")]
     pub workspace_name: ::prost::alloc::string::String,
 }
+/// State of a FUSE working copy. The files are served from the base tree, with
+/// the changes recorded in the overlay directory of the current generation.
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct FuseState {
+    /// The tree the overlay applies to.
+    #[prost(bytes = "vec", repeated, tag = "1")]
+    pub base_tree_ids: ::prost::alloc::vec::Vec<::prost::alloc::vec::Vec<u8>>,
+    /// The tree at the last snapshot or checkout.
+    #[prost(bytes = "vec", repeated, tag = "2")]
+    pub tree_ids: ::prost::alloc::vec::Vec<::prost::alloc::vec::Vec<u8>>,
+    /// Bumped whenever the base tree changes, so that the daemon switches to the
+    /// new overlay directory.
+    #[prost(uint64, tag = "3")]
+    pub generation: u64,
+}
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
 #[repr(i32)]
 pub enum FileType {
@@ -111,3 +178,29 @@ impl FileType {
         }
     }
 }
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
+#[repr(i32)]
+pub enum SparseMode {
+    Prefix = 0,
+    Cone = 1,
+}
+impl SparseMode {
+    /// String value of the enum field names used in the ProtoBuf definition.
+    ///
+    /// The values are not transformed in any way and thus are considered stable
+    /// (if the ProtoBuf definition does not change) and safe for programmatic use.
+    pub fn as_str_name(&self) -> &'static str {
+        match self {
+            Self::Prefix => "Prefix",
+            Self::Cone => "Cone",
+        }
+    }
+    /// Creates an enum from field names used in the ProtoBuf definition.
+    pub fn from_str_name(value: &str) -> ::core::option::Option<Self> {
+        match value {
+            "Prefix" => Some(Self::Prefix),
+            "Cone" => Some(Self::Cone),
+            _ => None,
+        }
+    }
+}
@@ -936,6 +936,12 @@ impl MutableRepo {
         // `self.rewritten_commits`
     }
 
+    /// Returns the commits written in this transaction, mapped to their
+    /// predecessors.
+    pub fn commit_predecessors(&self) -> &BTreeMap<CommitId, Vec<CommitId>> {
+        &self.commit_predecessors
+    }
+
     pub(crate) fn set_predecessors(&mut self, id: CommitId, predecessors: Vec<CommitId>) {
         self.commit_predecessors.insert(id, predecessors);
     }
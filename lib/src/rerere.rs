@@ -0,0 +1,453 @@
+// Copyright 2025 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Recording conflict resolutions and reusing them when the same conflicts
+//! appear again ("rerere").
+//!
+//! Resolutions are recorded per conflict hunk, keyed by a hash of the hunk's
+//! terms. The terms are sorted, so a conflict which reappears with its sides
+//! swapped (e.g. after rebasing the other way around) still matches.
+
+use std::fs;
+use std::io;
+use std::io::Write as _;
+use std::path::PathBuf;
+
+use blake2::Blake2b512;
+use blake2::Digest as _;
+use bstr::BString;
+use bstr::ByteSlice as _;
+use itertools::Itertools as _;
+use pollster::FutureExt as _;
+use tempfile::NamedTempFile;
+use thiserror::Error;
+
+use crate::backend::BackendError;
+use crate::backend::CommitId;
+use crate::backend::CopyId;
+use crate::backend::FileId;
+use crate::backend::MergedTreeId;
+use crate::backend::TreeValue;
+use crate::commit::Commit;
+use crate::conflicts::extract_as_single_hunk;
+use crate::conflicts::resolve_file_executable;
+use crate::file_util::IoResultExt as _;
+use crate::file_util::PathError;
+use crate::files::MergeResult;
+use crate::files::merge_hunks;
+use crate::hex_util::encode_hex;
+use crate::merge::Merge;
+use crate::merge::MergedTreeValue;
+use crate::merged_tree::MergedTree;
+use crate::merged_tree::MergedTreeBuilder;
+use crate::repo::MutableRepo;
+use crate::repo::Repo as _;
+use crate::repo_path::RepoPath;
+use crate::repo_path::RepoPathBuf;
+use crate::revset::RevsetEvaluationError;
+use crate::revset::RevsetExpression;
+use crate::revset::RevsetFilterPredicate;
+use crate::store::Store;
+
+/// Error recording or reusing conflict resolutions.
+#[derive(Debug, Error)]
+pub enum RerereError {
+    /// Error while contacting the Backend.
+    #[error(transparent)]
+    Backend(#[from] BackendError),
+    /// Error reading or writing the recorded resolutions.
+    #[error("Failed to access recorded conflict resolutions")]
+    Io(#[from] PathError),
+    /// Error finding the conflicted commits.
+    #[error(transparent)]
+    RevsetEvaluation(#[from] RevsetEvaluationError),
+}
+
+/// Conflicts resolved with recorded resolutions in a commit.
+#[derive(Clone, Debug)]
+pub struct ReusedResolutions {
+    /// The commit with the resolved files.
+    pub commit: Commit,
+    /// The paths of the files which were resolved.
+    pub paths: Vec<RepoPathBuf>,
+}
+
+/// Stores the resolutions of conflict hunks, one file per hunk.
+#[derive(Clone, Debug)]
+pub struct ResolutionCache {
+    dir: PathBuf,
+}
+
+impl ResolutionCache {
+    /// Creates a cache storing resolutions in `dir`. The directory is created
+    /// when the first resolution is recorded.
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    /// Records how the file conflict `conflict` at `path` was resolved to
+    /// `resolution`. Returns the number of recorded hunks.
+    ///
+    /// Nothing is recorded if the resolution isn't a regular file, or if the
+    /// resolved hunks of the conflict can't be found in the resolved content,
+    /// since then it's unclear which part of the content resolves which
+    /// conflict hunk.
+    pub fn record(
+        &self,
+        store: &Store,
+        path: &RepoPath,
+        conflict: &MergedTreeValue,
+        resolution: &MergedTreeValue,
+    ) -> Result<usize, RerereError> {
+        let Some(hunks) = conflict_hunks(store, path, conflict)? else {
+            return Ok(0);
+        };
+        let Some(Some(TreeValue::File { id, .. })) = resolution.as_resolved() else {
+            return Ok(0);
+        };
+        let content = read_file(store, path, id)?;
+        let Some(resolutions) = split_resolution(&hunks, &content) else {
+            return Ok(0);
+        };
+        let conflicted_hunks = hunks.iter().filter(|hunk| !hunk.is_resolved());
+        for (hunk, resolution) in conflicted_hunks.zip_eq(&resolutions) {
+            self.write_resolution(&hunk_key(hunk), resolution)?;
+        }
+        Ok(resolutions.len())
+    }
+
+    /// Resolves the file conflict `conflict` at `path` if a resolution was
+    /// recorded for each of its hunks.
+    pub fn resolve(
+        &self,
+        store: &Store,
+        path: &RepoPath,
+        conflict: &MergedTreeValue,
+    ) -> Result<Option<MergedTreeValue>, RerereError> {
+        let Some(hunks) = conflict_hunks(store, path, conflict)? else {
+            return Ok(None);
+        };
+        let Some(executable) = conflict
+            .to_executable_merge()
+            .and_then(|merge| resolve_file_executable(&merge))
+        else {
+            return Ok(None);
+        };
+        let mut content = vec![];
+        for hunk in &hunks {
+            if let Some(resolved) = hunk.as_resolved() {
+                content.extend_from_slice(resolved);
+            } else if let Some(resolution) = self.read_resolution(&hunk_key(hunk))? {
+                content.extend(resolution);
+            } else {
+                return Ok(None);
+            }
+        }
+        let id = store.write_file(path, &mut content.as_slice()).block_on()?;
+        Ok(Some(Merge::normal(TreeValue::File {
+            id,
+            executable,
+            copy_id: CopyId::placeholder(),
+        })))
+    }
+
+    /// Resolves the conflicted files in `tree` which only consist of hunks
+    /// with recorded resolutions. Returns the new tree and the resolved paths.
+    pub fn resolve_tree(
+        &self,
+        store: &Store,
+        tree: &MergedTree,
+    ) -> Result<(MergedTreeId, Vec<RepoPathBuf>), RerereError> {
+        let mut tree_builder = MergedTreeBuilder::new(tree.id());
+        let mut resolved_paths = vec![];
+        for (path, conflict) in tree.conflicts() {
+            if let Some(value) = self.resolve(store, &path, &conflict?)? {
+                tree_builder.set_or_remove(path.clone(), value);
+                resolved_paths.push(path);
+            }
+        }
+        if resolved_paths.is_empty() {
+            return Ok((tree.id(), resolved_paths));
+        }
+        let tree_id = tree_builder.write_tree(tree.store())?;
+        Ok((tree_id, resolved_paths))
+    }
+
+    /// Drops the recorded resolutions of the hunks of the file conflict
+    /// `conflict` at `path`. Returns the number of dropped resolutions.
+    pub fn forget(
+        &self,
+        store: &Store,
+        path: &RepoPath,
+        conflict: &MergedTreeValue,
+    ) -> Result<usize, RerereError> {
+        let Some(hunks) = conflict_hunks(store, path, conflict)? else {
+            return Ok(0);
+        };
+        let mut count = 0;
+        for hunk in hunks.iter().filter(|hunk| !hunk.is_resolved()) {
+            let path = self.dir.join(hunk_key(hunk));
+            match fs::remove_file(&path) {
+                Ok(()) => count += 1,
+                Err(err) if err.kind() == io::ErrorKind::NotFound => {}
+                Err(err) => return Err(PathError { path, error: err }.into()),
+            }
+        }
+        Ok(count)
+    }
+
+    fn read_resolution(&self, key: &str) -> Result<Option<Vec<u8>>, PathError> {
+        let path = self.dir.join(key);
+        match fs::read(&path) {
+            Ok(content) => Ok(Some(content)),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(PathError { path, error: err }),
+        }
+    }
+
+    fn write_resolution(&self, key: &str, resolution: &[u8]) -> Result<(), PathError> {
+        fs::create_dir_all(&self.dir).context(&self.dir)?;
+        let mut temp_file = NamedTempFile::new_in(&self.dir).context(&self.dir)?;
+        temp_file.write_all(resolution).context(temp_file.path())?;
+        let path = self.dir.join(key);
+        temp_file
+            .persist(&path)
+            .map_err(|err| err.error)
+            .context(&path)?;
+        Ok(())
+    }
+}
+
+/// Records the conflict resolutions made in the commits written in the
+/// transaction.
+///
+/// A file counts as resolved in a commit if it was conflicted in a predecessor
+/// with the same parents, so conflicts which merely disappeared because the
+/// commit was rebased aren't recorded. Returns the number of recorded hunks.
+pub fn record_resolutions(
+    repo: &MutableRepo,
+    cache: &ResolutionCache,
+) -> Result<usize, RerereError> {
+    let store = repo.store();
+    let mut count = 0;
+    for (commit_id, predecessor_ids) in repo.commit_predecessors() {
+        let commit = store.get_commit(commit_id)?;
+        for predecessor_id in predecessor_ids {
+            let predecessor = store.get_commit(predecessor_id)?;
+            if predecessor.parent_ids() != commit.parent_ids() || !predecessor.has_conflict()? {
+                continue;
+            }
+            let new_tree = commit.tree()?;
+            for (path, conflict) in predecessor.tree()?.conflicts() {
+                let resolution = new_tree.path_value(&path)?;
+                if resolution.is_resolved() {
+                    count += cache.record(store, &path, &conflict?, &resolution)?;
+                }
+            }
+        }
+    }
+    Ok(count)
+}
+
+/// Resolves conflicts in the commits written in the transaction using the
+/// recorded resolutions, and rebases their descendants.
+pub fn reuse_resolutions(
+    mut_repo: &mut MutableRepo,
+    cache: &ResolutionCache,
+) -> Result<Vec<ReusedResolutions>, RerereError> {
+    let new_commit_ids: Vec<CommitId> = mut_repo.commit_predecessors().keys().cloned().collect();
+    let conflicted_ids: Vec<CommitId> = RevsetExpression::commits(new_commit_ids)
+        .intersection(&RevsetExpression::visible_heads().ancestors())
+        .filtered(RevsetFilterPredicate::HasConflict)
+        .evaluate(&*mut_repo)?
+        .iter()
+        .try_collect()?;
+    if conflicted_ids.is_empty() {
+        return Ok(vec![]);
+    }
+
+    let store = mut_repo.store().clone();
+    let mut reused = vec![];
+    let mut cache_error = None;
+    mut_repo
+        .transform_descendants(conflicted_ids, |rewriter| {
+            let parents_changed = rewriter.parents_changed();
+            let commit_builder = if parents_changed {
+                rewriter.rebase()?
+            } else if rewriter.old_commit().has_conflict()? {
+                rewriter.reparent()
+            } else {
+                return Ok(());
+            };
+            let tree = store.get_root_tree(commit_builder.tree_id())?;
+            let (tree_id, paths) = if tree.has_conflict() {
+                cache.resolve_tree(&store, &tree).map_err(|err| {
+                    let message = err.to_string();
+                    cache_error = Some(err);
+                    BackendError::Other(message.into())
+                })?
+            } else {
+                (tree.id(), vec![])
+            };
+            if paths.is_empty() {
+                if parents_changed {
+                    commit_builder.write()?;
+                }
+                return Ok(());
+            }
+            let commit = commit_builder.set_tree_id(tree_id).write()?;
+            reused.push(ReusedResolutions { commit, paths });
+            Ok(())
+        })
+        .map_err(|err| cache_error.take().unwrap_or_else(|| err.into()))?;
+    // All descendants have been visited, so this only clears the rewrites.
+    mut_repo.rebase_descendants()?;
+    Ok(reused)
+}
+
+/// Returns the hunks of a file conflict, or `None` if the value isn't a file
+/// conflict or its content can be merged.
+fn conflict_hunks(
+    store: &Store,
+    path: &RepoPath,
+    conflict: &MergedTreeValue,
+) -> Result<Option<Vec<Merge<BString>>>, RerereError> {
+    if conflict.is_resolved() {
+        return Ok(None);
+    }
+    let Some(file_ids) = conflict.to_file_merge() else {
+        return Ok(None);
+    };
+    let contents = extract_as_single_hunk(&file_ids.simplify(), store, path).block_on()?;
+    match merge_hunks(&contents) {
+        MergeResult::Resolved(_) => Ok(None),
+        MergeResult::Conflict(hunks) => Ok(Some(hunks)),
+    }
+}
+
+fn read_file(store: &Store, path: &RepoPath, id: &FileId) -> Result<BString, RerereError> {
+    let contents = extract_as_single_hunk(&Merge::normal(id.clone()), store, path).block_on()?;
+    Ok(contents.into_resolved().unwrap())
+}
+
+/// Returns the key of a conflict hunk, which doesn't depend on the order of
+/// its terms.
+fn hunk_key(hunk: &Merge<BString>) -> String {
+    let hunk = hunk.simplify();
+    let mut hasher = Blake2b512::new();
+    for terms in [hunk.removes().sorted(), hunk.adds().sorted()] {
+        hasher.update((terms.len() as u64).to_le_bytes());
+        for term in terms {
+            hasher.update((term.len() as u64).to_le_bytes());
+            hasher.update(term);
+        }
+    }
+    encode_hex(&hasher.finalize()[..32])
+}
+
+/// Splits the resolved content of a file into the resolutions of its
+/// conflicted hunks, using the resolved hunks around them as anchors.
+fn split_resolution<'a>(hunks: &[Merge<BString>], content: &'a [u8]) -> Option<Vec<&'a [u8]>> {
+    let mut resolutions = vec![];
+    let mut pos = 0;
+    let mut pending_conflict = false;
+    for (i, hunk) in hunks.iter().enumerate() {
+        let Some(anchor) = hunk.as_resolved() else {
+            if pending_conflict {
+                // Adjacent conflicted hunks can't be told apart
+                return None;
+            }
+            pending_conflict = true;
+            continue;
+        };
+        let rest = &content[pos..];
+        let offset = if !pending_conflict {
+            rest.starts_with(anchor).then_some(0)?
+        } else if i == hunks.len() - 1 {
+            rest.ends_with(anchor).then(|| rest.len() - anchor.len())?
+        } else {
+            rest.find(anchor)?
+        };
+        if pending_conflict {
+            resolutions.push(&rest[..offset]);
+            pending_conflict = false;
+        }
+        pos += offset + anchor.len();
+    }
+    if pending_conflict {
+        resolutions.push(&content[pos..]);
+    } else if pos != content.len() {
+        return None;
+    }
+    Some(resolutions)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hunk(removes: &[&str], adds: &[&str]) -> Merge<BString> {
+        Merge::from_removes_adds(
+            removes.iter().map(|&term| BString::from(term)),
+            adds.iter().map(|&term| BString::from(term)),
+        )
+    }
+
+    fn resolved(content: &str) -> Merge<BString> {
+        Merge::resolved(BString::from(content))
+    }
+
+    #[test]
+    fn test_hunk_key_ignores_order_of_sides() {
+        let key = hunk_key(&hunk(&["base\n"], &["left\n", "right\n"]));
+        assert_eq!(key, hunk_key(&hunk(&["base\n"], &["right\n", "left\n"])));
+        assert_ne!(key, hunk_key(&hunk(&["base\n"], &["left\n", "other\n"])));
+        assert_ne!(key, hunk_key(&hunk(&["left\n"], &["base\n", "right\n"])));
+    }
+
+    #[test]
+    fn test_split_resolution() {
+        let hunks = [
+            resolved("a\n"),
+            hunk(&["b\n"], &["b1\n", "b2\n"]),
+            resolved("c\n"),
+            hunk(&["d\n"], &["d1\n", "d2\n"]),
+            resolved("e\n"),
+        ];
+        assert_eq!(
+            split_resolution(&hunks, b"a\nB\nc\nD\nD\ne\n"),
+            Some(vec![&b"B\n"[..], b"D\nD\n"])
+        );
+        // Resolutions can be empty
+        assert_eq!(
+            split_resolution(&hunks, b"a\nc\ne\n"),
+            Some(vec![&b""[..], b""])
+        );
+        // The resolved hunks must be kept as they were
+        assert_eq!(split_resolution(&hunks, b"a\nB\nC\nD\ne\n"), None);
+        assert_eq!(split_resolution(&hunks, b"A\nB\nc\nD\ne\n"), None);
+        assert_eq!(split_resolution(&hunks, b"a\nB\nc\nD\nE\n"), None);
+
+        // Conflicts at the start and the end of the file
+        let hunks = [
+            hunk(&["a\n"], &["a1\n", "a2\n"]),
+            resolved("b\n"),
+            hunk(&["c\n"], &["c1\n", "c2\n"]),
+        ];
+        assert_eq!(
+            split_resolution(&hunks, b"A\nb\nC\n"),
+            Some(vec![&b"A\n"[..], b"C\n"])
+        );
+    }
+}
@@ -55,6 +55,7 @@ struct UserSettingsData {
     operation_username: String,
     signing_behavior: SignBehavior,
     signing_key: Option<String>,
+    sign_operations: bool,
 }
 
 #[derive(Debug, Clone)]
@@ -63,6 +64,7 @@ pub struct GitSettings {
     pub abandon_unreachable_commits: bool,
     pub executable_path: PathBuf,
     pub write_change_id_header: bool,
+    pub transport: GitTransport,
 }
 
 impl GitSettings {
@@ -72,6 +74,7 @@ impl GitSettings {
             abandon_unreachable_commits: settings.get_bool("git.abandon-unreachable-commits")?,
             executable_path: settings.get("git.executable-path")?,
             write_change_id_header: settings.get("git.write-change-id-header")?,
+            transport: settings.get("git.transport")?,
         })
     }
 }
@@ -83,10 +86,21 @@ impl Default for GitSettings {
             abandon_unreachable_commits: true,
             executable_path: PathBuf::from("git"),
             write_change_id_header: true,
+            transport: GitTransport::Subprocess,
         }
     }
 }
 
+/// How to communicate with Git remotes.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum GitTransport {
+    /// Spawn the `git` executable.
+    Subprocess,
+    /// Fetch and push in-process by using gitoxide.
+    Gix,
+}
+
 /// Commit signing settings, describes how to and if to sign commits.
 #[derive(Debug, Clone)]
 pub struct SignSettings {
@@ -148,6 +162,7 @@ impl UserSettings {
         let operation_username = config.get("operation.username")?;
         let signing_behavior = config.get("signing.behavior")?;
         let signing_key = config.get("signing.key").optional()?;
+        let sign_operations = config.get("signing.sign-operations")?;
         let data = UserSettingsData {
             user_name,
             user_email,
@@ -157,6 +172,7 @@ impl UserSettings {
             operation_username,
             signing_behavior,
             signing_key,
+            sign_operations,
         };
         Ok(Self {
             config: Arc::new(config),
@@ -246,6 +262,12 @@ impl UserSettings {
             key: self.data.signing_key.clone(),
         }
     }
+
+    /// Whether new operations should be signed by the configured signing
+    /// backend.
+    pub fn sign_operations(&self) -> bool {
+        self.data.sign_operations
+    }
 }
 
 /// General-purpose accessors.
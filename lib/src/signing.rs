@@ -122,6 +122,9 @@ pub enum SignError {
     /// The verification failed because the signature *format* was invalid.
     #[error("Invalid signature")]
     InvalidSignatureFormat,
+    /// Signing was requested, but no signing backend is configured.
+    #[error("No signing backend is configured")]
+    NoBackend,
     /// A generic error from the backend impl.
     #[error("Signing error")]
     Backend(#[source] Box<dyn std::error::Error + Send + Sync>),
@@ -240,21 +243,7 @@ impl Signer {
             return Ok(check);
         }
 
-        let verification = self
-            .main_backend
-            .iter()
-            .chain(self.backends.iter())
-            .filter(|b| b.can_read(signature))
-            // skip unknown and invalid sigs to allow other backends that can read to try
-            // for example, we might have gpg and sq, both of which could read a PGP signature
-            .find_map(|backend| match backend.verify(data, signature) {
-                Ok(check) if check.status == SigStatus::Unknown => None,
-                Err(SignError::InvalidSignatureFormat) => None,
-                e => Some(e),
-            })
-            .transpose()?;
-
-        if let Some(verification) = verification {
+        if let Some(verification) = self.verify_with_backends(data, signature)? {
             // a key might get imported before next call?.
             // realistically this is unlikely, but technically
             // it's correct to not cache unknowns here
@@ -277,4 +266,31 @@ impl Signer {
             Ok(Verification::unknown())
         }
     }
+
+    /// Like [`Self::verify()`], but doesn't cache the result. This is used to
+    /// verify signatures of objects other than commits, such as operations.
+    pub fn verify_uncached(&self, data: &[u8], signature: &[u8]) -> SignResult<Verification> {
+        Ok(self
+            .verify_with_backends(data, signature)?
+            .unwrap_or_else(Verification::unknown))
+    }
+
+    fn verify_with_backends(
+        &self,
+        data: &[u8],
+        signature: &[u8],
+    ) -> SignResult<Option<Verification>> {
+        self.main_backend
+            .iter()
+            .chain(self.backends.iter())
+            .filter(|b| b.can_read(signature))
+            // skip unknown and invalid sigs to allow other backends that can read to try
+            // for example, we might have gpg and sq, both of which could read a PGP signature
+            .find_map(|backend| match backend.verify(data, signature) {
+                Ok(check) if check.status == SigStatus::Unknown => None,
+                Err(SignError::InvalidSignatureFormat) => None,
+                e => Some(e),
+            })
+            .transpose()
+    }
 }
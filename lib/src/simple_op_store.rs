@@ -502,6 +502,7 @@ fn operation_to_proto(operation: &Operation) -> crate::protos::op_store::Operati
         metadata: Some(operation_metadata_to_proto(&operation.metadata)),
         commit_predecessors,
         stores_commit_predecessors,
+        signature: operation.signature.clone(),
     };
     for parent in &operation.parents {
         proto.parents.push(parent.to_bytes());
@@ -527,6 +528,7 @@ fn operation_from_proto(
         parents,
         metadata,
         commit_predecessors,
+        signature: proto.signature,
     })
 }
 
@@ -884,6 +886,7 @@ mod tests {
                     CommitId::from_hex("444444"),
                 ],
             }),
+            signature: None,
         }
     }
 
@@ -931,6 +934,27 @@ mod tests {
         assert_eq!(read_operation, operation);
     }
 
+    #[test]
+    fn test_read_write_signed_operation() {
+        let temp_dir = new_temp_dir();
+        let root_data = RootOperationData {
+            root_commit_id: CommitId::from_hex("000000"),
+        };
+        let store = SimpleOpStore::init(temp_dir.path(), root_data).unwrap();
+        let unsigned_operation = create_operation();
+        let operation = Operation {
+            signature: Some(b"signature".to_vec()),
+            ..unsigned_operation.clone()
+        };
+        let op_id = store.write_operation(&operation).unwrap();
+        let unsigned_op_id = store.write_operation(&unsigned_operation).unwrap();
+        assert_ne!(op_id, unsigned_op_id);
+        let read_operation = store.read_operation(&op_id).unwrap();
+        assert_eq!(read_operation, operation);
+        // The signed data doesn't depend on the signature.
+        assert_eq!(operation.signed_data(), unsigned_op_id.hex().into_bytes());
+    }
+
     #[test]
     fn test_bookmark_views_legacy_roundtrip() {
         let new_remote_ref = |target: &RefTarget| RemoteRef {
@@ -0,0 +1,145 @@
+// Copyright 2025 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Sparse checkout patterns and profiles.
+
+use std::io;
+
+use thiserror::Error;
+use tokio::io::AsyncReadExt as _;
+
+use crate::backend::BackendError;
+use crate::backend::TreeValue;
+use crate::matchers::ConeMatcher;
+use crate::matchers::Matcher;
+use crate::matchers::PrefixMatcher;
+use crate::merged_tree::MergedTree;
+use crate::repo_path::RelativePathParseError;
+use crate::repo_path::RepoPath;
+use crate::repo_path::RepoPathBuf;
+
+/// How sparse patterns select the paths to check out.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum SparseMode {
+    /// Each pattern includes all files under the path.
+    #[default]
+    Prefix,
+    /// Like `Prefix`, but the files directly in the ancestor directories of
+    /// each pattern are also included.
+    Cone,
+}
+
+/// Creates a matcher for the sparse `patterns` interpreted in `mode`.
+pub fn new_sparse_matcher(patterns: &[RepoPathBuf], mode: SparseMode) -> Box<dyn Matcher> {
+    match mode {
+        SparseMode::Prefix => Box::new(PrefixMatcher::new(patterns)),
+        SparseMode::Cone => Box::new(ConeMatcher::new(patterns)),
+    }
+}
+
+/// Error while reading a sparse profile.
+#[derive(Debug, Error)]
+pub enum SparseProfileError {
+    /// The profile doesn't exist in the tree, or isn't a regular file.
+    #[error("No such file")]
+    NotFound,
+    /// A line of the profile isn't a valid repo-relative path.
+    #[error("Invalid path on line {line_number}")]
+    InvalidPath {
+        /// 1-based line number.
+        line_number: usize,
+        /// The underlying error.
+        source: RelativePathParseError,
+    },
+    /// Failed to read the profile from the backend.
+    #[error(transparent)]
+    Backend(#[from] BackendError),
+    /// Failed to read the profile content.
+    #[error(transparent)]
+    Io(#[from] io::Error),
+}
+
+/// Parses the content of a sparse profile.
+///
+/// Each line is a path relative to the repository root, using `/` as
+/// separator. Blank lines and lines starting with `#` are ignored.
+pub fn parse_sparse_profile(content: &str) -> Result<Vec<RepoPathBuf>, SparseProfileError> {
+    let mut patterns: Vec<RepoPathBuf> = content
+        .lines()
+        .enumerate()
+        .map(|(i, line)| (i + 1, line.trim()))
+        .filter(|(_, line)| !line.is_empty() && !line.starts_with('#'))
+        .map(|(line_number, line)| {
+            let line = line.trim_end_matches('/');
+            let line = if line.is_empty() { "." } else { line };
+            RepoPathBuf::from_relative_path(line).map_err(|source| {
+                SparseProfileError::InvalidPath {
+                    line_number,
+                    source,
+                }
+            })
+        })
+        .collect::<Result<_, _>>()?;
+    patterns.sort_unstable();
+    patterns.dedup();
+    Ok(patterns)
+}
+
+/// Reads and parses the sparse profile at `path` in the `tree`.
+pub async fn read_sparse_profile(
+    tree: &MergedTree,
+    path: &RepoPath,
+) -> Result<Vec<RepoPathBuf>, SparseProfileError> {
+    let id = match tree.path_value(path)?.into_resolved() {
+        Ok(Some(TreeValue::File { id, .. })) => id,
+        _ => return Err(SparseProfileError::NotFound),
+    };
+    let mut reader = tree.store().read_file(path, &id).await?;
+    let mut content = String::new();
+    reader.read_to_string(&mut content).await?;
+    parse_sparse_profile(&content)
+}
+
+#[cfg(test)]
+mod tests {
+    use assert_matches::assert_matches;
+
+    use super::*;
+
+    fn repo_path_buf(value: &str) -> RepoPathBuf {
+        RepoPathBuf::from_internal_string(value).unwrap()
+    }
+
+    #[test]
+    fn test_parse_sparse_profile() {
+        let content = "# Backend team\n\nlib/\n  tools/build  \ndocs\nlib\n";
+        assert_eq!(
+            parse_sparse_profile(content).unwrap(),
+            vec![
+                repo_path_buf("docs"),
+                repo_path_buf("lib"),
+                repo_path_buf("tools/build"),
+            ]
+        );
+        assert_eq!(
+            parse_sparse_profile("/\n").unwrap(),
+            vec![RepoPathBuf::root()]
+        );
+        assert_eq!(parse_sparse_profile("").unwrap(), vec![]);
+        assert_matches!(
+            parse_sparse_profile("lib\n../outside\n"),
+            Err(SparseProfileError::InvalidPath { line_number: 2, .. })
+        );
+    }
+}
@@ -35,7 +35,9 @@ use crate::repo::ReadonlyRepo;
 use crate::repo::Repo as _;
 use crate::repo::RepoLoader;
 use crate::repo::RepoLoaderError;
+use crate::settings::SignSettings;
 use crate::settings::UserSettings;
+use crate::signing::SignError;
 use crate::view::View;
 
 /// Error from attempts to write and publish transaction.
@@ -45,6 +47,7 @@ pub enum TransactionCommitError {
     IndexWrite(#[from] IndexWriteError),
     OpHeadsStore(#[from] OpHeadsStoreError),
     OpStore(#[from] OpStoreError),
+    Sign(#[from] SignError),
 }
 
 /// An in-memory representation of a repo and any changes being made to it.
@@ -63,6 +66,8 @@ pub struct Transaction {
     parent_ops: Vec<Operation>,
     op_metadata: OperationMetadata,
     end_time: Option<Timestamp>,
+    /// How to sign the operation, or `None` if it shouldn't be signed.
+    sign_settings: Option<SignSettings>,
 }
 
 impl Transaction {
@@ -70,11 +75,15 @@ impl Transaction {
         let parent_ops = vec![mut_repo.base_repo().operation().clone()];
         let op_metadata = create_op_metadata(user_settings, "".to_string(), false);
         let end_time = user_settings.operation_timestamp();
+        let sign_settings = user_settings
+            .sign_operations()
+            .then(|| user_settings.sign_settings());
         Self {
             mut_repo,
             parent_ops,
             op_metadata,
             end_time,
+            sign_settings,
         }
     }
 
@@ -144,12 +153,24 @@ impl Transaction {
             self.op_metadata.description = description.into();
             self.op_metadata.time.end = self.end_time.unwrap_or_else(Timestamp::now);
             let parents = self.parent_ops.iter().map(|op| op.id().clone()).collect();
-            let store_operation = op_store::Operation {
+            let mut store_operation = op_store::Operation {
                 view_id,
                 parents,
                 metadata: self.op_metadata,
                 commit_predecessors: Some(predecessors),
+                signature: None,
             };
+            let signer = base_repo.store().signer();
+            if let Some(sign_settings) = &self.sign_settings {
+                // Writing the operation unsigned would defeat the purpose of
+                // signing operations.
+                if !signer.can_sign() {
+                    return Err(SignError::NoBackend.into());
+                }
+                let data = store_operation.signed_data();
+                let signature = signer.sign(&data, sign_settings.key.as_deref())?;
+                store_operation.signature = Some(signature);
+            }
             let new_op_id = base_repo.op_store().write_operation(&store_operation)?;
             Operation::new(base_repo.op_store().clone(), new_op_id, store_operation)
         };
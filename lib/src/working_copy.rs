@@ -47,6 +47,8 @@ use crate::repo_path::InvalidRepoPathError;
 use crate::repo_path::RepoPath;
 use crate::repo_path::RepoPathBuf;
 use crate::settings::UserSettings;
+use crate::sparse::SparseMode;
+use crate::sparse::SparseProfileError;
 use crate::store::Store;
 use crate::transaction::TransactionCommitError;
 
@@ -74,9 +76,23 @@ pub trait WorkingCopy: Send {
     /// that all files should be checked out.
     fn sparse_patterns(&self) -> Result<&[RepoPathBuf], WorkingCopyStateError>;
 
+    /// How the sparse patterns select the paths to check out.
+    fn sparse_mode(&self) -> Result<SparseMode, WorkingCopyStateError>;
+
+    /// Path to the sparse profile the sparse patterns are read from, if any.
+    /// The patterns are re-read from the profile on every checkout.
+    fn sparse_profile(&self) -> Result<Option<&RepoPath>, WorkingCopyStateError>;
+
     /// Locks the working copy and returns an instance with methods for updating
     /// the working copy files and state.
     fn start_mutation(&self) -> Result<Box<dyn LockedWorkingCopy>, WorkingCopyStateError>;
+
+    /// The tree an interrupted checkout was updating the files to, if any. The
+    /// files then match neither that tree nor `tree_id()`, and the working
+    /// copy can't be snapshotted until the next checkout finishes.
+    fn interrupted_checkout(&self) -> Result<Option<&MergedTreeId>, WorkingCopyStateError> {
+        Ok(None)
+    }
 }
 
 /// The factory which creates and loads a specific type of working copy.
@@ -142,8 +158,15 @@ pub trait LockedWorkingCopy {
     /// See `WorkingCopy::sparse_patterns()`
     fn sparse_patterns(&self) -> Result<&[RepoPathBuf], WorkingCopyStateError>;
 
+    /// See `WorkingCopy::sparse_mode()`
+    fn sparse_mode(&self) -> Result<SparseMode, WorkingCopyStateError>;
+
+    /// See `WorkingCopy::sparse_profile()`
+    fn sparse_profile(&self) -> Result<Option<&RepoPath>, WorkingCopyStateError>;
+
     /// Updates the patterns that decide which paths from the current tree
-    /// should be checked out in the working copy.
+    /// should be checked out in the working copy. The patterns will no longer
+    /// be read from the sparse profile.
     // TODO: Use a different error type here so we can include a
     // `SparseNotSupported` variants for working copies that don't support sparse
     // checkouts (e.g. because they use a virtual file system so there's no reason
@@ -154,6 +177,24 @@ pub trait LockedWorkingCopy {
         options: &CheckoutOptions,
     ) -> Result<CheckoutStats, CheckoutError>;
 
+    /// Like `set_sparse_patterns()`, but also updates how the patterns select
+    /// the paths to check out.
+    fn set_sparse_patterns_and_mode(
+        &mut self,
+        new_sparse_patterns: Vec<RepoPathBuf>,
+        new_sparse_mode: SparseMode,
+        options: &CheckoutOptions,
+    ) -> Result<CheckoutStats, CheckoutError>;
+
+    /// Reads the sparse patterns from the profile at `profile_path` in the
+    /// current tree, and keeps them in sync with the profile on checkout.
+    fn set_sparse_profile(
+        &mut self,
+        profile_path: RepoPathBuf,
+        new_sparse_mode: SparseMode,
+        options: &CheckoutOptions,
+    ) -> Result<CheckoutStats, CheckoutError>;
+
     /// Finish the modifications to the working copy by writing the updated
     /// states to disk. Returns the new (unlocked) working copy.
     fn finish(
@@ -187,6 +228,9 @@ pub enum SnapshotError {
     /// Checking path with ignore patterns failed.
     #[error(transparent)]
     GitIgnoreError(#[from] GitIgnoreError),
+    /// A checkout was interrupted, so the files are partially updated.
+    #[error("The working copy has an interrupted checkout")]
+    InterruptedCheckout,
     /// Some other error happened while snapshotting the working copy.
     #[error("{message}")]
     Other {
@@ -223,6 +267,9 @@ pub struct SnapshotOptions<'a> {
     /// (depending on implementation)
     /// return `SnapshotError::NewFileTooLarge`.
     pub max_new_file_size: u64,
+    /// Per-path rules for new files. Files matching a rule aren't subject to
+    /// `max_new_file_size`.
+    pub snapshot_rules: Arc<SnapshotRules>,
     /// Expected conflict marker style for checking for changed files.
     pub conflict_marker_style: ConflictMarkerStyle,
 }
@@ -236,11 +283,91 @@ impl SnapshotOptions<'_> {
             progress: None,
             start_tracking_matcher: &EverythingMatcher,
             max_new_file_size: u64::MAX,
+            snapshot_rules: Arc::default(),
             conflict_marker_style: ConflictMarkerStyle::default(),
         }
     }
 }
 
+/// What to do with a new file matched by a [`SnapshotRule`].
+///
+/// The variants are ordered by precedence: if several rules apply to a file,
+/// the greatest action wins.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd, serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum SnapshotRuleAction {
+    /// Track the file, but report it.
+    Warn,
+    /// Leave the file untracked. It's only reported by `jj status`.
+    Ignore,
+    /// Leave the file untracked and report it on every snapshot.
+    Refuse,
+}
+
+/// Policy for new files matching some paths.
+#[derive(Debug)]
+pub struct SnapshotRule {
+    /// Name of the rule, used when reporting files.
+    pub name: String,
+    /// New files matching this are subject to the rule.
+    pub matcher: Box<dyn Matcher>,
+    /// If set, the rule only applies to files larger than this many bytes.
+    /// Smaller files are tracked.
+    pub max_new_file_size: Option<u64>,
+    /// What to do with the files the rule applies to.
+    pub action: SnapshotRuleAction,
+}
+
+/// Set of [`SnapshotRule`]s.
+#[derive(Debug, Default)]
+pub struct SnapshotRules {
+    rules: Vec<SnapshotRule>,
+}
+
+/// Result of checking a new file against [`SnapshotRules`].
+#[derive(Debug)]
+pub enum SnapshotRuleCheck<'a> {
+    /// No rule matches the path.
+    Unmatched,
+    /// Some rules match the path, but none of them applies to a file of that
+    /// size.
+    Allowed,
+    /// The rule which decides what to do with the file.
+    Applied(&'a SnapshotRule),
+}
+
+impl SnapshotRules {
+    /// Creates a set from the given `rules`.
+    pub fn new(rules: Vec<SnapshotRule>) -> Self {
+        Self { rules }
+    }
+
+    /// Returns true if there are no rules.
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// Checks the new file at `path` of `size` bytes against the rules.
+    pub fn check(&self, path: &RepoPath, size: u64) -> SnapshotRuleCheck<'_> {
+        let matching_rules = self
+            .rules
+            .iter()
+            .filter(|rule| rule.matcher.matches(path))
+            .collect_vec();
+        if matching_rules.is_empty() {
+            return SnapshotRuleCheck::Unmatched;
+        }
+        matching_rules
+            .into_iter()
+            .filter(|rule| {
+                rule.max_new_file_size
+                    .is_none_or(|max_size| size > max_size)
+            })
+            .max_by_key(|rule| rule.action)
+            .map_or(SnapshotRuleCheck::Allowed, SnapshotRuleCheck::Applied)
+    }
+}
+
 /// A callback for getting progress updates.
 pub type SnapshotProgress<'a> = dyn Fn(&RepoPath) + 'a + Sync;
 
@@ -249,6 +376,18 @@ pub type SnapshotProgress<'a> = dyn Fn(&RepoPath) + 'a + Sync;
 pub struct SnapshotStats {
     /// List of new (previously untracked) files which are still untracked.
     pub untracked_paths: BTreeMap<RepoPathBuf, UntrackedReason>,
+    /// List of new files which were tracked, but matched a snapshot rule
+    /// asking to report them.
+    pub warned_paths: BTreeMap<RepoPathBuf, SnapshotWarning>,
+}
+
+/// Details about a new file tracked despite matching a snapshot rule.
+#[derive(Clone, Debug)]
+pub struct SnapshotWarning {
+    /// Name of the matching rule.
+    pub rule: String,
+    /// Size of the file.
+    pub size: u64,
 }
 
 /// Reason why the new path isn't tracked.
@@ -263,24 +402,43 @@ pub enum UntrackedReason {
     },
     /// File does not match the fileset specified in snapshot.auto-track.
     FileNotAutoTracked,
+    /// File matches a snapshot rule which ignores it.
+    IgnoredByRule {
+        /// Name of the matching rule.
+        rule: String,
+    },
+    /// File matches a snapshot rule which refuses to track it.
+    RefusedByRule {
+        /// Name of the matching rule.
+        rule: String,
+        /// Size of the file.
+        size: u64,
+    },
 }
 
 /// Options used when checking out a tree in the working copy.
 #[derive(Clone)]
-pub struct CheckoutOptions {
+pub struct CheckoutOptions<'a> {
     /// Conflict marker style to use when materializing files
     pub conflict_marker_style: ConflictMarkerStyle,
+    /// A callback for the UI to display progress.
+    pub progress: Option<&'a CheckoutProgress<'a>>,
 }
 
-impl CheckoutOptions {
+impl CheckoutOptions<'_> {
     /// Create an instance for use in tests.
     pub fn empty_for_test() -> Self {
         Self {
             conflict_marker_style: ConflictMarkerStyle::default(),
+            progress: None,
         }
     }
 }
 
+/// A callback for getting progress updates, called with each path before it's
+/// updated.
+pub type CheckoutProgress<'a> = dyn Fn(&RepoPath) + 'a + Sync;
+
 /// Stats about a checkout operation on a working copy. All "files" mentioned
 /// below may also be symlinks or materialized conflicts.
 #[derive(Debug, PartialEq, Eq, Clone, Default)]
@@ -296,6 +454,9 @@ pub struct CheckoutStats {
     /// working copy but were skipped because there was an untracked (probably
     /// ignored) file in its place.
     pub skipped_files: u32,
+    /// Files which couldn't be written or removed, with the error message.
+    /// They're left as they were on disk, and will be snapshotted as such.
+    pub failed_files: BTreeMap<RepoPathBuf, String>,
 }
 
 /// The working-copy checkout failed.
@@ -323,9 +484,30 @@ pub enum CheckoutError {
         /// The reserved path component.
         name: &'static str,
     },
+    /// Two paths in the commit would refer to the same file on this file
+    /// system, which folds case or normalizes Unicode in file names.
+    #[error(
+        "Paths {} and {} would refer to the same file on this file system",
+        path.as_internal_file_string(),
+        other_path.as_internal_file_string()
+    )]
+    PathCollision {
+        /// The first of the colliding paths.
+        path: RepoPathBuf,
+        /// The path colliding with `path`.
+        other_path: RepoPathBuf,
+    },
     /// Reading or writing from the commit backend failed.
     #[error("Internal backend error")]
     InternalBackendError(#[from] BackendError),
+    /// The sparse profile couldn't be read from the tree to check out.
+    #[error("Failed to read sparse profile {}", path.as_internal_file_string())]
+    SparseProfile {
+        /// The profile path.
+        path: RepoPathBuf,
+        /// The underlying error.
+        source: SparseProfileError,
+    },
     /// Some other error happened while checking out the working copy.
     #[error("{message}")]
     Other {
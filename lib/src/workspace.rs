@@ -30,6 +30,10 @@ use crate::file_util;
 use crate::file_util::BadPathEncoding;
 use crate::file_util::IoResultExt as _;
 use crate::file_util::PathError;
+#[cfg(all(target_os = "linux", feature = "fuse"))]
+use crate::fuse_working_copy::FuseWorkingCopy;
+#[cfg(all(target_os = "linux", feature = "fuse"))]
+use crate::fuse_working_copy::FuseWorkingCopyFactory;
 use crate::local_working_copy::LocalWorkingCopy;
 use crate::local_working_copy::LocalWorkingCopyFactory;
 use crate::op_heads_store::OpHeadsStoreError;
@@ -61,6 +65,8 @@ use crate::working_copy::LockedWorkingCopy;
 use crate::working_copy::WorkingCopy;
 use crate::working_copy::WorkingCopyFactory;
 use crate::working_copy::WorkingCopyStateError;
+use crate::workspace_store::SimpleWorkspaceStore;
+use crate::workspace_store::WorkspaceStoreError;
 
 #[derive(Error, Debug)]
 pub enum WorkspaceInitError {
@@ -82,6 +88,8 @@ pub enum WorkspaceInitError {
     SignInit(#[from] SignInitError),
     #[error(transparent)]
     TransactionCommit(#[from] TransactionCommitError),
+    #[error(transparent)]
+    WorkspaceStore(#[from] WorkspaceStoreError),
 }
 
 #[derive(Error, Debug)]
@@ -126,6 +134,22 @@ fn create_jj_dir(workspace_root: &Path) -> Result<PathBuf, WorkspaceInitError> {
     }
 }
 
+/// Points the workspace at `workspace_root` to the repo at `repo_path`.
+///
+/// The workspace must not contain the repo itself. Returns the canonical repo
+/// path written to `.jj/repo`.
+pub fn set_workspace_repo_path(
+    workspace_root: &Path,
+    repo_path: &Path,
+) -> Result<PathBuf, WorkspaceInitError> {
+    let repo_dir = dunce::canonicalize(repo_path).context(repo_path)?;
+    let repo_dir_bytes =
+        file_util::path_to_bytes(&repo_dir).map_err(WorkspaceInitError::EncodeRepoPath)?;
+    let repo_file_path = workspace_root.join(".jj").join("repo");
+    fs::write(&repo_file_path, repo_dir_bytes).context(&repo_file_path)?;
+    Ok(repo_dir)
+}
+
 fn init_working_copy(
     repo: &Arc<ReadonlyRepo>,
     workspace_root: &Path,
@@ -321,6 +345,8 @@ impl Workspace {
             )?;
             let repo_loader = repo.loader().clone();
             let workspace = Self::new(workspace_root, repo_dir, working_copy, repo_loader)?;
+            SimpleWorkspaceStore::load(workspace.repo_path())
+                .add(workspace.workspace_name(), workspace.workspace_root())?;
             Ok((workspace, repo))
         })()
         .inspect_err(|_err| {
@@ -356,12 +382,7 @@ impl Workspace {
         workspace_name: WorkspaceNameBuf,
     ) -> Result<(Self, Arc<ReadonlyRepo>), WorkspaceInitError> {
         let jj_dir = create_jj_dir(workspace_root)?;
-
-        let repo_dir = dunce::canonicalize(repo_path).context(repo_path)?;
-        let repo_dir_bytes =
-            file_util::path_to_bytes(&repo_dir).map_err(WorkspaceInitError::EncodeRepoPath)?;
-        let repo_file_path = jj_dir.join("repo");
-        fs::write(&repo_file_path, repo_dir_bytes).context(&repo_file_path)?;
+        let repo_dir = set_workspace_repo_path(workspace_root, repo_path)?;
 
         let (working_copy, repo) = init_working_copy(
             repo,
@@ -376,6 +397,8 @@ impl Workspace {
             working_copy,
             repo.loader().clone(),
         )?;
+        SimpleWorkspaceStore::load(workspace.repo_path())
+            .add(workspace.workspace_name(), workspace.workspace_root())?;
         Ok((workspace, repo))
     }
 
@@ -555,7 +578,11 @@ impl DefaultWorkspaceLoader {
             let buf = fs::read(&repo_dir).context(&repo_dir)?;
             let repo_path =
                 file_util::path_from_bytes(&buf).map_err(WorkspaceLoadError::DecodeRepoPath)?;
-            repo_dir = dunce::canonicalize(jj_dir.join(repo_path)).context(repo_path)?;
+            let canonical_repo_dir = dunce::canonicalize(jj_dir.join(repo_path));
+            if matches!(&canonical_repo_dir, Err(err) if err.kind() == io::ErrorKind::NotFound) {
+                return Err(WorkspaceLoadError::RepoDoesNotExist(jj_dir.join(repo_path)));
+            }
+            repo_dir = canonical_repo_dir.context(repo_path)?;
             if !repo_dir.is_dir() {
                 return Err(WorkspaceLoadError::RepoDoesNotExist(repo_dir));
             }
@@ -613,6 +640,11 @@ pub fn default_working_copy_factories() -> WorkingCopyFactories {
         LocalWorkingCopy::name().to_owned(),
         Box::new(LocalWorkingCopyFactory {}),
     );
+    #[cfg(all(target_os = "linux", feature = "fuse"))]
+    factories.insert(
+        FuseWorkingCopy::name().to_owned(),
+        Box::new(FuseWorkingCopyFactory {}),
+    );
     factories
 }
 
@@ -0,0 +1,237 @@
+// Copyright 2025 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Records where the workspaces of a repo live on disk.
+//!
+//! The view only knows the names of the workspaces. The paths are kept in the
+//! repo directory so that workspaces can find each other, e.g. to update their
+//! repo pointers when the repo has been moved.
+
+use std::collections::BTreeMap;
+use std::fmt::Debug;
+use std::fmt::Formatter;
+use std::fs;
+use std::io;
+use std::io::Write as _;
+use std::path::Path;
+use std::path::PathBuf;
+
+use prost::Message as _;
+use tempfile::NamedTempFile;
+use thiserror::Error;
+
+use crate::file_util;
+use crate::file_util::BadPathEncoding;
+use crate::file_util::IoResultExt as _;
+use crate::file_util::PathError;
+use crate::lock::FileLock;
+use crate::lock::FileLockError;
+use crate::protos::workspace_store as proto;
+use crate::ref_name::WorkspaceName;
+use crate::ref_name::WorkspaceNameBuf;
+
+/// Error that may occur when reading or updating the workspace store.
+#[derive(Debug, Error)]
+pub enum WorkspaceStoreError {
+    /// The index file couldn't be decoded.
+    #[error("Failed to decode workspace store index {path}")]
+    Decode {
+        /// Path to the index file.
+        path: PathBuf,
+        /// The underlying error.
+        source: prost::DecodeError,
+    },
+    /// A workspace path couldn't be encoded or decoded.
+    #[error("Workspace path could not be encoded or decoded")]
+    PathEncoding(#[from] BadPathEncoding),
+    /// Failed to lock the store.
+    #[error(transparent)]
+    Lock(#[from] FileLockError),
+    /// Failed to read or write the store.
+    #[error(transparent)]
+    Path(#[from] PathError),
+}
+
+/// Maps workspace names to the paths of their roots.
+///
+/// The records are stored in `workspace_store/index` within the repo
+/// directory. The directory is created on first write, so repos initialized
+/// before the store existed simply have no records.
+pub struct SimpleWorkspaceStore {
+    dir: PathBuf,
+}
+
+impl Debug for SimpleWorkspaceStore {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SimpleWorkspaceStore")
+            .field("dir", &self.dir)
+            .finish()
+    }
+}
+
+impl SimpleWorkspaceStore {
+    /// Returns the store of the repo at `repo_path`.
+    pub fn load(repo_path: &Path) -> Self {
+        Self {
+            dir: repo_path.join("workspace_store"),
+        }
+    }
+
+    /// Returns the recorded root of the workspace `name`, if any.
+    pub fn get_workspace_path(
+        &self,
+        name: &WorkspaceName,
+    ) -> Result<Option<PathBuf>, WorkspaceStoreError> {
+        Ok(self.read_index()?.remove(name))
+    }
+
+    /// Returns all recorded workspace roots.
+    pub fn workspace_paths(
+        &self,
+    ) -> Result<BTreeMap<WorkspaceNameBuf, PathBuf>, WorkspaceStoreError> {
+        self.read_index()
+    }
+
+    /// Records `path` as the root of the workspace `name`, replacing any
+    /// previous record.
+    pub fn add(&self, name: &WorkspaceName, path: &Path) -> Result<(), WorkspaceStoreError> {
+        self.update_index(|index| {
+            index.insert(name.to_owned(), path.to_owned());
+        })
+    }
+
+    /// Removes the records of the workspaces `names`.
+    pub fn forget(&self, names: &[&WorkspaceName]) -> Result<(), WorkspaceStoreError> {
+        self.update_index(|index| {
+            for name in names {
+                index.remove(*name);
+            }
+        })
+    }
+
+    /// Moves the record of the workspace `old_name` to `new_name`.
+    pub fn rename(
+        &self,
+        old_name: &WorkspaceName,
+        new_name: &WorkspaceName,
+    ) -> Result<(), WorkspaceStoreError> {
+        self.update_index(|index| {
+            if let Some(path) = index.remove(old_name) {
+                index.insert(new_name.to_owned(), path);
+            }
+        })
+    }
+
+    fn index_path(&self) -> PathBuf {
+        self.dir.join("index")
+    }
+
+    fn read_index(&self) -> Result<BTreeMap<WorkspaceNameBuf, PathBuf>, WorkspaceStoreError> {
+        let index_path = self.index_path();
+        let buf = match fs::read(&index_path).context(&index_path) {
+            Ok(buf) => buf,
+            Err(err) if err.error.kind() == io::ErrorKind::NotFound => {
+                return Ok(BTreeMap::new());
+            }
+            Err(err) => return Err(err.into()),
+        };
+        let proto =
+            proto::Workspaces::decode(&*buf).map_err(|source| WorkspaceStoreError::Decode {
+                path: index_path,
+                source,
+            })?;
+        proto
+            .workspaces
+            .into_iter()
+            .map(|workspace| {
+                let path = file_util::path_from_bytes(&workspace.path)?;
+                Ok((workspace.name.into(), path.to_owned()))
+            })
+            .collect()
+    }
+
+    fn update_index(
+        &self,
+        f: impl FnOnce(&mut BTreeMap<WorkspaceNameBuf, PathBuf>),
+    ) -> Result<(), WorkspaceStoreError> {
+        file_util::create_or_reuse_dir(&self.dir).context(&self.dir)?;
+        let _lock = FileLock::lock(self.dir.join("lock"))?;
+        let mut index = self.read_index()?;
+        f(&mut index);
+        let proto = proto::Workspaces {
+            workspaces: index
+                .iter()
+                .map(|(name, path)| {
+                    Ok(proto::Workspace {
+                        name: name.as_str().to_owned(),
+                        path: file_util::path_to_bytes(path)?.to_owned(),
+                    })
+                })
+                .collect::<Result<_, BadPathEncoding>>()?,
+        };
+        let index_path = self.index_path();
+        let mut temp_file = NamedTempFile::new_in(&self.dir).context(&self.dir)?;
+        temp_file
+            .as_file_mut()
+            .write_all(&proto.encode_to_vec())
+            .context(temp_file.path())?;
+        temp_file
+            .persist(&index_path)
+            .map_err(|err| err.error)
+            .context(&index_path)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::new_temp_dir;
+
+    #[test]
+    fn test_workspace_store() {
+        let temp_dir = new_temp_dir();
+        let store = SimpleWorkspaceStore::load(temp_dir.path());
+        let default = WorkspaceName::DEFAULT;
+        let second = WorkspaceName::new("second");
+        assert_eq!(store.get_workspace_path(default).unwrap(), None);
+
+        store.add(default, Path::new("/repo")).unwrap();
+        store.add(second, Path::new("/second")).unwrap();
+        assert_eq!(
+            store.get_workspace_path(default).unwrap(),
+            Some(PathBuf::from("/repo"))
+        );
+
+        store.add(second, Path::new("/moved")).unwrap();
+        store.rename(second, WorkspaceName::new("third")).unwrap();
+        assert_eq!(store.get_workspace_path(second).unwrap(), None);
+        assert_eq!(
+            store
+                .get_workspace_path(WorkspaceName::new("third"))
+                .unwrap(),
+            Some(PathBuf::from("/moved"))
+        );
+
+        store.forget(&[default]).unwrap();
+        assert_eq!(
+            store
+                .workspace_paths()
+                .unwrap()
+                .into_keys()
+                .collect::<Vec<_>>(),
+            vec![WorkspaceName::new("third").to_owned()]
+        );
+    }
+}
@@ -15,6 +15,8 @@ mod test_default_revset_graph_iterator;
 mod test_eol;
 mod test_evolution_predecessors;
 mod test_fix;
+#[cfg(all(target_os = "linux", feature = "fuse"))]
+mod test_fuse_working_copy;
 mod test_git;
 mod test_git_backend;
 mod test_gpg;
@@ -23,6 +23,8 @@ use jj_lib::fix::FileToFix;
 use jj_lib::fix::FixError;
 use jj_lib::fix::ParallelFileFixer;
 use jj_lib::fix::fix_files;
+use jj_lib::fix::restrict_changes_to_lines;
+use jj_lib::fix::translate_changed_lines;
 use jj_lib::matchers::EverythingMatcher;
 use jj_lib::repo::Repo as _;
 use jj_lib::store::Store;
@@ -585,3 +587,49 @@ fn test_fix_multiple_revisions() {
         .unwrap();
     assert_eq!(*new_commit_a.tree_id(), expected_tree_a.id());
 }
+
+#[test]
+#[expect(clippy::single_range_in_vec_init)]
+fn test_translate_changed_lines() {
+    // Modified and added lines are changed
+    assert_eq!(
+        translate_changed_lines(b"a\nb\nc\n", b"a\nx\nc\nd\n", &[]),
+        vec![1..2, 3..4]
+    );
+    // Removed lines are not
+    assert!(translate_changed_lines(b"a\nb\nc\n", b"a\nc\n", &[]).is_empty());
+    // Unchanged lines in the old ranges are moved with the surrounding lines
+    assert_eq!(
+        translate_changed_lines(b"a\nb\nc\n", b"x\ny\na\nb\nc\n", &[1..3]),
+        vec![0..2, 3..5]
+    );
+    // Adjacent ranges are merged
+    assert_eq!(
+        translate_changed_lines(b"a\nb\nc\n", b"a\nx\nc\n", &[0..1]),
+        vec![0..2]
+    );
+}
+
+#[test]
+#[expect(clippy::single_range_in_vec_init)]
+fn test_restrict_changes_to_lines() {
+    let old = b"a\nb\nc\nd\n";
+    let new = b"A\nb\nC\nd\nE\n";
+    assert_eq!(restrict_changes_to_lines(old, new, &[]), old);
+    assert_eq!(
+        restrict_changes_to_lines(old, new, &[0..1]),
+        b"A\nb\nc\nd\n"
+    );
+    assert_eq!(
+        restrict_changes_to_lines(old, new, &[2..4]),
+        b"a\nb\nC\nd\nE\n"
+    );
+    assert_eq!(restrict_changes_to_lines(old, new, &[0..4]), new);
+
+    // Adjacent lines which were all changed are selected individually
+    let new = b"A\nB\nC\nD\n";
+    assert_eq!(
+        restrict_changes_to_lines(old, new, &[1..2, 3..4]),
+        b"a\nB\nc\nD\n"
+    );
+}
@@ -0,0 +1,293 @@
+// Copyright 2025 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::fs;
+use std::os::unix::fs::symlink;
+
+use jj_lib::conflicts::ConflictMarkerStyle;
+use jj_lib::fuse_working_copy::FuseWorkingCopy;
+use jj_lib::fuse_working_copy::daemon;
+use jj_lib::matchers::NothingMatcher;
+use jj_lib::op_store::OperationId;
+use jj_lib::ref_name::WorkspaceName;
+use jj_lib::repo::Repo as _;
+use jj_lib::repo_path::RepoPathBuf;
+use jj_lib::working_copy::CheckoutOptions;
+use jj_lib::working_copy::CheckoutStats;
+use jj_lib::working_copy::SnapshotOptions;
+use jj_lib::working_copy::WorkingCopy as _;
+use tempfile::TempDir;
+use testutils::TestRepo;
+use testutils::commit_with_tree;
+use testutils::create_tree;
+use testutils::new_temp_dir;
+use testutils::repo_path;
+
+fn init_fuse_wc(test_repo: &TestRepo) -> (TempDir, FuseWorkingCopy) {
+    let temp_dir = new_temp_dir();
+    let working_copy_path = temp_dir.path().join("workspace");
+    let state_path = temp_dir.path().join("state");
+    fs::create_dir(&working_copy_path).unwrap();
+    fs::create_dir(&state_path).unwrap();
+    let wc = FuseWorkingCopy::init(
+        test_repo.repo.store().clone(),
+        working_copy_path,
+        state_path,
+        test_repo.repo.op_id().clone(),
+        WorkspaceName::DEFAULT.to_owned(),
+    )
+    .unwrap();
+    (temp_dir, wc)
+}
+
+#[test]
+fn test_snapshot_overlay() {
+    let test_repo = TestRepo::init();
+    let repo = &test_repo.repo;
+    let (_temp_dir, wc) = init_fuse_wc(&test_repo);
+
+    let tree1 = create_tree(repo, &[(repo_path("a"), "a\n"), (repo_path("b"), "b\n")]);
+    let commit1 = commit_with_tree(repo.store(), tree1.id());
+    let mut locked_wc = wc.start_mutation().unwrap();
+    locked_wc
+        .check_out(&commit1, &CheckoutOptions::empty_for_test())
+        .unwrap();
+    let wc = locked_wc.finish(repo.op_id().clone()).unwrap();
+    let wc: &FuseWorkingCopy = wc.as_any().downcast_ref().unwrap();
+
+    // Nothing written through the mount yet
+    let mut locked_wc = wc.start_mutation().unwrap();
+    let (tree_id, _stats) = locked_wc
+        .snapshot(&SnapshotOptions::empty_for_test())
+        .unwrap();
+    assert_eq!(tree_id, tree1.id());
+
+    // Modified and added files are picked up from the overlay
+    let overlay_path = wc.overlay_path().unwrap();
+    fs::write(overlay_path.join("a"), "modified\n").unwrap();
+    fs::create_dir(overlay_path.join("dir")).unwrap();
+    fs::write(overlay_path.join("dir").join("c"), "c\n").unwrap();
+    let (tree_id, _stats) = locked_wc
+        .snapshot(&SnapshotOptions::empty_for_test())
+        .unwrap();
+    let expected_tree = create_tree(
+        repo,
+        &[
+            (repo_path("a"), "modified\n"),
+            (repo_path("b"), "b\n"),
+            (repo_path("dir/c"), "c\n"),
+        ],
+    );
+    assert_eq!(tree_id, expected_tree.id());
+}
+
+#[test]
+fn test_check_out_keeps_untracked_files() {
+    let test_repo = TestRepo::init();
+    let repo = &test_repo.repo;
+    let (_temp_dir, wc) = init_fuse_wc(&test_repo);
+
+    let tree1 = create_tree(repo, &[(repo_path("a"), "a\n")]);
+    let tree2 = create_tree(
+        repo,
+        &[(repo_path("a"), "changed\n"), (repo_path("b"), "b\n")],
+    );
+    let commit1 = commit_with_tree(repo.store(), tree1.id());
+    let commit2 = commit_with_tree(repo.store(), tree2.id());
+
+    let mut locked_wc = wc.start_mutation().unwrap();
+    let stats = locked_wc
+        .check_out(&commit1, &CheckoutOptions::empty_for_test())
+        .unwrap();
+    assert_eq!(
+        stats,
+        CheckoutStats {
+            added_files: 1,
+            ..CheckoutStats::default()
+        }
+    );
+
+    let wc = locked_wc.finish(repo.op_id().clone()).unwrap();
+    let wc: &FuseWorkingCopy = wc.as_any().downcast_ref().unwrap();
+
+    let overlay_path = wc.overlay_path().unwrap();
+    fs::write(overlay_path.join("untracked"), "untracked\n").unwrap();
+    let options = SnapshotOptions {
+        start_tracking_matcher: &NothingMatcher,
+        ..SnapshotOptions::empty_for_test()
+    };
+    let mut locked_wc = wc.start_mutation().unwrap();
+    let (tree_id, stats) = locked_wc.snapshot(&options).unwrap();
+    assert_eq!(tree_id, tree1.id());
+    assert!(stats.untracked_paths.contains_key(repo_path("untracked")));
+
+    let stats = locked_wc
+        .check_out(&commit2, &CheckoutOptions::empty_for_test())
+        .unwrap();
+    assert_eq!(
+        stats,
+        CheckoutStats {
+            updated_files: 1,
+            added_files: 1,
+            ..CheckoutStats::default()
+        }
+    );
+    let wc = locked_wc.finish(OperationId::from_bytes(b"op")).unwrap();
+    let wc: &FuseWorkingCopy = wc.as_any().downcast_ref().unwrap();
+    assert_eq!(*wc.tree_id().unwrap(), tree2.id());
+
+    // The untracked file moved to the overlay of the new tree, and the files of
+    // the new tree aren't written out.
+    let overlay_path = wc.overlay_path().unwrap();
+    assert!(overlay_path.join("untracked").exists());
+    assert!(!overlay_path.join("a").exists());
+    assert!(!overlay_path.join("b").exists());
+}
+
+#[test]
+fn test_reset_keeps_files() {
+    let test_repo = TestRepo::init();
+    let repo = &test_repo.repo;
+    let (_temp_dir, wc) = init_fuse_wc(&test_repo);
+
+    let tree1 = create_tree(repo, &[(repo_path("a"), "a\n"), (repo_path("b"), "b\n")]);
+    let tree2 = create_tree(repo, &[(repo_path("a"), "a\n"), (repo_path("c"), "c\n")]);
+    let commit1 = commit_with_tree(repo.store(), tree1.id());
+    let commit2 = commit_with_tree(repo.store(), tree2.id());
+
+    let mut locked_wc = wc.start_mutation().unwrap();
+    locked_wc
+        .check_out(&commit1, &CheckoutOptions::empty_for_test())
+        .unwrap();
+    // Resetting only changes the recorded tree, so the visible files still
+    // match the first tree.
+    locked_wc.reset(&commit2).unwrap();
+    let wc = locked_wc.finish(repo.op_id().clone()).unwrap();
+    assert_eq!(*wc.tree_id().unwrap(), tree2.id());
+
+    let mut locked_wc = wc.start_mutation().unwrap();
+    let (tree_id, _stats) = locked_wc
+        .snapshot(&SnapshotOptions::empty_for_test())
+        .unwrap();
+    assert_eq!(tree_id, tree1.id());
+}
+
+#[test]
+fn test_sparse_patterns_unsupported() {
+    let test_repo = TestRepo::init();
+    let (_temp_dir, wc) = init_fuse_wc(&test_repo);
+
+    let mut locked_wc = wc.start_mutation().unwrap();
+    assert!(
+        locked_wc
+            .set_sparse_patterns(
+                vec![repo_path("dir").to_owned()],
+                &CheckoutOptions::empty_for_test()
+            )
+            .is_err()
+    );
+    assert!(
+        locked_wc
+            .set_sparse_patterns(
+                vec![RepoPathBuf::root()],
+                &CheckoutOptions::empty_for_test()
+            )
+            .is_ok()
+    );
+}
+
+#[test]
+fn test_daemon_serves_working_copy() {
+    let test_repo = TestRepo::init();
+    let repo = &test_repo.repo;
+    let (temp_dir, wc) = init_fuse_wc(&test_repo);
+    let working_copy_path = temp_dir.path().join("workspace");
+    symlink(wc.state_path(), working_copy_path.join(".jj")).unwrap();
+
+    let tree1 = create_tree(
+        repo,
+        &[
+            (repo_path("a"), "a\n"),
+            (repo_path("dir/b"), "b\n"),
+            (repo_path("dir/sub/c"), "c\n"),
+        ],
+    );
+    let commit1 = commit_with_tree(repo.store(), tree1.id());
+    let mut locked_wc = wc.start_mutation().unwrap();
+    locked_wc
+        .check_out(&commit1, &CheckoutOptions::empty_for_test())
+        .unwrap();
+    let wc = locked_wc.finish(repo.op_id().clone()).unwrap();
+    let wc: &FuseWorkingCopy = wc.as_any().downcast_ref().unwrap();
+
+    let session = match daemon::spawn_daemon(
+        repo.store().clone(),
+        &working_copy_path,
+        wc.state_path(),
+        ConflictMarkerStyle::Diff,
+    ) {
+        Ok(session) => session,
+        Err(err) => {
+            eprintln!("Skipping test because the working copy couldn't be mounted: {err}");
+            return;
+        }
+    };
+
+    // The checked-out files are served from the base tree
+    assert_eq!(
+        fs::read_to_string(working_copy_path.join("a")).unwrap(),
+        "a\n"
+    );
+    let mut names: Vec<_> = fs::read_dir(&working_copy_path)
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name())
+        .collect();
+    names.sort();
+    assert_eq!(names, [".jj", "a", "dir"]);
+
+    // Files can be modified, added, removed, and moved, including directories
+    // with entries that have been looked up
+    fs::write(working_copy_path.join("a"), "modified\n").unwrap();
+    fs::write(working_copy_path.join("dir/sub/d"), "d\n").unwrap();
+    assert_eq!(
+        fs::read_to_string(working_copy_path.join("dir/sub/c")).unwrap(),
+        "c\n"
+    );
+    fs::rename(
+        working_copy_path.join("dir"),
+        working_copy_path.join("moved"),
+    )
+    .unwrap();
+    assert!(!working_copy_path.join("dir").exists());
+    assert_eq!(
+        fs::read_to_string(working_copy_path.join("moved/sub/c")).unwrap(),
+        "c\n"
+    );
+    fs::remove_file(working_copy_path.join("moved/b")).unwrap();
+    drop(session);
+
+    let mut locked_wc = wc.start_mutation().unwrap();
+    let (tree_id, _stats) = locked_wc
+        .snapshot(&SnapshotOptions::empty_for_test())
+        .unwrap();
+    let expected_tree = create_tree(
+        repo,
+        &[
+            (repo_path("a"), "modified\n"),
+            (repo_path("moved/sub/c"), "c\n"),
+            (repo_path("moved/sub/d"), "d\n"),
+        ],
+    );
+    assert_eq!(tree_id, expected_tree.id());
+}
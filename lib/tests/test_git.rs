@@ -64,6 +64,7 @@ use jj_lib::repo::MutableRepo;
 use jj_lib::repo::ReadonlyRepo;
 use jj_lib::repo::Repo as _;
 use jj_lib::settings::GitSettings;
+use jj_lib::settings::GitTransport;
 use jj_lib::settings::UserSettings;
 use jj_lib::signing::Signer;
 use jj_lib::str_util::StringPattern;
@@ -2819,10 +2820,14 @@ fn test_init() {
     assert!(!repo.view().heads().contains(&jj_id(initial_git_commit)));
 }
 
-#[test]
-fn test_fetch_empty_repo() {
+#[test_case(GitTransport::Subprocess; "subprocess")]
+#[test_case(GitTransport::Gix; "gix")]
+fn test_fetch_empty_repo(transport: GitTransport) {
     let test_data = GitRepoData::create();
-    let git_settings = GitSettings::default();
+    let git_settings = GitSettings {
+        transport,
+        ..GitSettings::default()
+    };
 
     let mut tx = test_data.repo.start_transaction();
     let stats = git_fetch(
@@ -2839,11 +2844,13 @@ fn test_fetch_empty_repo() {
     assert_eq!(tx.repo().view().bookmarks().count(), 0);
 }
 
-#[test]
-fn test_fetch_initial_commit_head_is_not_set() {
+#[test_case(GitTransport::Subprocess; "subprocess")]
+#[test_case(GitTransport::Gix; "gix")]
+fn test_fetch_initial_commit_head_is_not_set(transport: GitTransport) {
     let test_data = GitRepoData::create();
     let git_settings = GitSettings {
         auto_local_bookmark: true,
+        transport,
         ..GitSettings::default()
     };
     let initial_git_commit = empty_git_commit(&test_data.origin_repo, "refs/heads/main", &[]);
@@ -2887,11 +2894,13 @@ fn test_fetch_initial_commit_head_is_not_set() {
     );
 }
 
-#[test]
-fn test_fetch_initial_commit_head_is_set() {
+#[test_case(GitTransport::Subprocess; "subprocess")]
+#[test_case(GitTransport::Gix; "gix")]
+fn test_fetch_initial_commit_head_is_set(transport: GitTransport) {
     let test_data = GitRepoData::create();
     let git_settings = GitSettings {
         auto_local_bookmark: true,
+        transport,
         ..GitSettings::default()
     };
     let initial_git_commit = empty_git_commit(&test_data.origin_repo, "refs/heads/main", &[]);
@@ -2924,11 +2933,13 @@ fn test_fetch_initial_commit_head_is_set() {
     assert!(stats.import_stats.abandoned_commits.is_empty());
 }
 
-#[test]
-fn test_fetch_success() {
+#[test_case(GitTransport::Subprocess; "subprocess")]
+#[test_case(GitTransport::Gix; "gix")]
+fn test_fetch_success(transport: GitTransport) {
     let mut test_data = GitRepoData::create();
     let git_settings = GitSettings {
         auto_local_bookmark: true,
+        transport,
         ..GitSettings::default()
     };
     let initial_git_commit = empty_git_commit(&test_data.origin_repo, "refs/heads/main", &[]);
@@ -3005,11 +3016,13 @@ fn test_fetch_success() {
     );
 }
 
-#[test]
-fn test_fetch_prune_deleted_ref() {
+#[test_case(GitTransport::Subprocess; "subprocess")]
+#[test_case(GitTransport::Gix; "gix")]
+fn test_fetch_prune_deleted_ref(transport: GitTransport) {
     let test_data = GitRepoData::create();
     let git_settings = GitSettings {
         auto_local_bookmark: true,
+        transport,
         ..GitSettings::default()
     };
     let commit = empty_git_commit(&test_data.origin_repo, "refs/heads/main", &[]);
@@ -3053,11 +3066,13 @@ fn test_fetch_prune_deleted_ref() {
     );
 }
 
-#[test]
-fn test_fetch_no_default_branch() {
+#[test_case(GitTransport::Subprocess; "subprocess")]
+#[test_case(GitTransport::Gix; "gix")]
+fn test_fetch_no_default_branch(transport: GitTransport) {
     let test_data = GitRepoData::create();
     let git_settings = GitSettings {
         auto_local_bookmark: true,
+        transport,
         ..GitSettings::default()
     };
     let initial_git_commit = empty_git_commit(&test_data.origin_repo, "refs/heads/main", &[]);
@@ -3129,12 +3144,14 @@ fn test_fetch_no_such_remote() {
     assert!(matches!(result, Err(GitFetchError::NoSuchRemote(_))));
 }
 
-#[test]
-fn test_fetch_multiple_branches() {
+#[test_case(GitTransport::Subprocess; "subprocess")]
+#[test_case(GitTransport::Gix; "gix")]
+fn test_fetch_multiple_branches(transport: GitTransport) {
     let test_data = GitRepoData::create();
     let _initial_git_commit = empty_git_commit(&test_data.origin_repo, "refs/heads/main", &[]);
     let git_settings = GitSettings {
         auto_local_bookmark: true,
+        transport,
         ..Default::default()
     };
 
@@ -3162,6 +3179,100 @@ fn test_fetch_multiple_branches() {
     );
 }
 
+/// Serves the repositories in `base_path` by `git daemon` until dropped.
+struct GitDaemon {
+    child: std::process::Child,
+    port: u16,
+}
+
+impl GitDaemon {
+    fn spawn(base_path: &Path) -> Self {
+        let port = std::net::TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+        let child = std::process::Command::new("git")
+            .args([
+                "daemon",
+                "--export-all",
+                "--enable=receive-pack",
+                "--reuseaddr",
+                "--listen=127.0.0.1",
+            ])
+            .arg(format!("--port={port}"))
+            .arg(format!("--base-path={}", base_path.display()))
+            .arg(base_path)
+            .spawn()
+            .unwrap();
+        let mut daemon = Self { child, port };
+        for _ in 0..100 {
+            if std::net::TcpStream::connect(("127.0.0.1", port)).is_ok() {
+                return daemon;
+            }
+            thread::sleep(std::time::Duration::from_millis(50));
+        }
+        daemon.child.kill().ok();
+        panic!("git daemon didn't start");
+    }
+}
+
+impl Drop for GitDaemon {
+    fn drop(&mut self) {
+        self.child.kill().ok();
+        self.child.wait().ok();
+    }
+}
+
+fn assert_fetch_main_with_gix(test_data: &GitRepoData, remote_url: &str) {
+    let git_settings = GitSettings {
+        transport: GitTransport::Gix,
+        ..GitSettings::default()
+    };
+    let commit = empty_git_commit(&test_data.origin_repo, "refs/heads/main", &[]);
+    git::set_remote_url(test_data.repo.store(), "origin".as_ref(), remote_url).unwrap();
+
+    let mut tx = test_data.repo.start_transaction();
+    git_fetch(
+        tx.repo_mut(),
+        "origin".as_ref(),
+        &[StringPattern::everything()],
+        &git_settings,
+    )
+    .unwrap();
+    assert_eq!(
+        tx.repo()
+            .get_remote_bookmark(remote_symbol("main", "origin")),
+        RemoteRef {
+            target: RefTarget::normal(jj_id(commit)),
+            state: RemoteRefState::New,
+        }
+    );
+}
+
+#[cfg(unix)]
+#[test]
+fn test_fetch_with_gix_from_file_url() {
+    let test_data = GitRepoData::create();
+    let origin_path = test_data.origin_repo.path().canonicalize().unwrap();
+    let remote_url = format!("file://{}", origin_path.display());
+    assert_fetch_main_with_gix(&test_data, &remote_url);
+}
+
+#[cfg(unix)]
+#[test]
+fn test_fetch_with_gix_from_git_daemon() {
+    let test_data = GitRepoData::create();
+    let origin_path = test_data.origin_repo.path();
+    let daemon = GitDaemon::spawn(origin_path.parent().unwrap());
+    let remote_url = format!(
+        "git://127.0.0.1:{port}/{name}",
+        port = daemon.port,
+        name = origin_path.file_name().unwrap().to_str().unwrap(),
+    );
+    assert_fetch_main_with_gix(&test_data, &remote_url);
+}
+
 struct PushTestSetup {
     source_repo_dir: PathBuf,
     jj_repo: Arc<ReadonlyRepo>,
@@ -3251,14 +3362,18 @@ fn set_up_push_repos(settings: &UserSettings, temp_dir: &TempDir) -> PushTestSet
     }
 }
 
-#[test]
-fn test_push_bookmarks_success() {
+#[test_case(GitTransport::Subprocess; "subprocess")]
+#[test_case(GitTransport::Gix; "gix")]
+fn test_push_bookmarks_success(transport: GitTransport) {
     let settings = testutils::user_settings();
     let temp_dir = testutils::new_temp_dir();
     let mut setup = set_up_push_repos(&settings, &temp_dir);
     let clone_repo = get_git_repo(&setup.jj_repo);
     let mut tx = setup.jj_repo.start_transaction();
-    let git_settings = GitSettings::default();
+    let git_settings = GitSettings {
+        transport,
+        ..Default::default()
+    };
 
     let targets = GitBranchPushTargets {
         branch_updates: vec![(
@@ -3319,14 +3434,18 @@ fn test_push_bookmarks_success() {
     assert!(!tx.repo().has_changes());
 }
 
-#[test]
-fn test_push_bookmarks_deletion() {
+#[test_case(GitTransport::Subprocess; "subprocess")]
+#[test_case(GitTransport::Gix; "gix")]
+fn test_push_bookmarks_deletion(transport: GitTransport) {
     let settings = testutils::user_settings();
     let temp_dir = testutils::new_temp_dir();
     let mut setup = set_up_push_repos(&settings, &temp_dir);
     let clone_repo = get_git_repo(&setup.jj_repo);
     let mut tx = setup.jj_repo.start_transaction();
-    let git_settings = GitSettings::default();
+    let git_settings = GitSettings {
+        transport,
+        ..Default::default()
+    };
 
     let source_repo = testutils::git::open(&setup.source_repo_dir);
     // Test the setup
@@ -3386,13 +3505,17 @@ fn test_push_bookmarks_deletion() {
     assert!(!tx.repo().has_changes());
 }
 
-#[test]
-fn test_push_bookmarks_mixed_deletion_and_addition() {
+#[test_case(GitTransport::Subprocess; "subprocess")]
+#[test_case(GitTransport::Gix; "gix")]
+fn test_push_bookmarks_mixed_deletion_and_addition(transport: GitTransport) {
     let settings = testutils::user_settings();
     let temp_dir = testutils::new_temp_dir();
     let mut setup = set_up_push_repos(&settings, &temp_dir);
     let mut tx = setup.jj_repo.start_transaction();
-    let git_settings = GitSettings::default();
+    let git_settings = GitSettings {
+        transport,
+        ..Default::default()
+    };
 
     let targets = GitBranchPushTargets {
         branch_updates: vec![
@@ -3467,13 +3590,17 @@ fn test_push_bookmarks_mixed_deletion_and_addition() {
     assert!(!tx.repo().has_changes());
 }
 
-#[test]
-fn test_push_bookmarks_not_fast_forward() {
+#[test_case(GitTransport::Subprocess; "subprocess")]
+#[test_case(GitTransport::Gix; "gix")]
+fn test_push_bookmarks_not_fast_forward(transport: GitTransport) {
     let settings = testutils::user_settings();
     let temp_dir = testutils::new_temp_dir();
     let setup = set_up_push_repos(&settings, &temp_dir);
     let mut tx = setup.jj_repo.start_transaction();
-    let git_settings = GitSettings::default();
+    let git_settings = GitSettings {
+        transport,
+        ..Default::default()
+    };
 
     let targets = GitBranchPushTargets {
         branch_updates: vec![(
@@ -3509,12 +3636,16 @@ fn test_push_bookmarks_not_fast_forward() {
 // may want to add tests for when a bookmark unexpectedly moved backwards or
 // unexpectedly does not exist for bookmark deletion.
 
-#[test]
-fn test_push_updates_unexpectedly_moved_sideways_on_remote() {
+#[test_case(GitTransport::Subprocess; "subprocess")]
+#[test_case(GitTransport::Gix; "gix")]
+fn test_push_updates_unexpectedly_moved_sideways_on_remote(transport: GitTransport) {
     let settings = testutils::user_settings();
     let temp_dir = testutils::new_temp_dir();
     let setup = set_up_push_repos(&settings, &temp_dir);
-    let git_settings = GitSettings::default();
+    let git_settings = GitSettings {
+        transport,
+        ..Default::default()
+    };
 
     // The main bookmark is actually at `main_commit` on the remote. If we expect
     // it to be at `sideways_commit`, it unexpectedly moved sideways from our
@@ -3585,12 +3716,16 @@ fn test_push_updates_unexpectedly_moved_sideways_on_remote() {
     );
 }
 
-#[test]
-fn test_push_updates_unexpectedly_moved_forward_on_remote() {
+#[test_case(GitTransport::Subprocess; "subprocess")]
+#[test_case(GitTransport::Gix; "gix")]
+fn test_push_updates_unexpectedly_moved_forward_on_remote(transport: GitTransport) {
     let settings = testutils::user_settings();
     let temp_dir = testutils::new_temp_dir();
     let setup = set_up_push_repos(&settings, &temp_dir);
-    let git_settings = GitSettings::default();
+    let git_settings = GitSettings {
+        transport,
+        ..Default::default()
+    };
 
     // The main bookmark is actually at `main_commit` on the remote. If we
     // expected it to be at `parent_of_commit`, it unexpectedly moved forward
@@ -3653,12 +3788,16 @@ fn test_push_updates_unexpectedly_moved_forward_on_remote() {
     );
 }
 
-#[test]
-fn test_push_updates_unexpectedly_exists_on_remote() {
+#[test_case(GitTransport::Subprocess; "subprocess")]
+#[test_case(GitTransport::Gix; "gix")]
+fn test_push_updates_unexpectedly_exists_on_remote(transport: GitTransport) {
     let settings = testutils::user_settings();
     let temp_dir = testutils::new_temp_dir();
     let setup = set_up_push_repos(&settings, &temp_dir);
-    let git_settings = GitSettings::default();
+    let git_settings = GitSettings {
+        transport,
+        ..Default::default()
+    };
 
     // The main bookmark is actually at `main_commit` on the remote. In this test,
     // we expect it to not exist on the remote at all.
@@ -3700,12 +3839,16 @@ fn test_push_updates_unexpectedly_exists_on_remote() {
     );
 }
 
-#[test]
-fn test_push_updates_success() {
+#[test_case(GitTransport::Subprocess; "subprocess")]
+#[test_case(GitTransport::Gix; "gix")]
+fn test_push_updates_success(transport: GitTransport) {
     let settings = testutils::user_settings();
     let temp_dir = testutils::new_temp_dir();
     let setup = set_up_push_repos(&settings, &temp_dir);
-    let git_settings = GitSettings::default();
+    let git_settings = GitSettings {
+        transport,
+        ..Default::default()
+    };
     let clone_repo = get_git_repo(&setup.jj_repo);
     let result = git::push_updates(
         setup.jj_repo.as_ref(),
@@ -3741,12 +3884,16 @@ fn test_push_updates_success() {
     assert_eq!(new_target.target().id(), new_oid);
 }
 
-#[test]
-fn test_push_updates_no_such_remote() {
+#[test_case(GitTransport::Subprocess; "subprocess")]
+#[test_case(GitTransport::Gix; "gix")]
+fn test_push_updates_no_such_remote(transport: GitTransport) {
     let settings = testutils::user_settings();
     let temp_dir = testutils::new_temp_dir();
     let setup = set_up_push_repos(&settings, &temp_dir);
-    let git_settings = GitSettings::default();
+    let git_settings = GitSettings {
+        transport,
+        ..Default::default()
+    };
     let result = git::push_updates(
         setup.jj_repo.as_ref(),
         &git_settings,
@@ -3761,12 +3908,16 @@ fn test_push_updates_no_such_remote() {
     assert!(matches!(result, Err(GitPushError::NoSuchRemote(_))));
 }
 
-#[test]
-fn test_push_updates_invalid_remote() {
+#[test_case(GitTransport::Subprocess; "subprocess")]
+#[test_case(GitTransport::Gix; "gix")]
+fn test_push_updates_invalid_remote(transport: GitTransport) {
     let settings = testutils::user_settings();
     let temp_dir = testutils::new_temp_dir();
     let setup = set_up_push_repos(&settings, &temp_dir);
-    let git_settings = GitSettings::default();
+    let git_settings = GitSettings {
+        transport,
+        ..Default::default()
+    };
     let result = git::push_updates(
         setup.jj_repo.as_ref(),
         &git_settings,
@@ -3781,6 +3932,95 @@ fn test_push_updates_invalid_remote() {
     assert!(matches!(result, Err(GitPushError::NoSuchRemote(_))));
 }
 
+#[cfg(unix)]
+#[test]
+fn test_push_updates_with_gix_to_git_daemon() {
+    let settings = testutils::user_settings();
+    let temp_dir = testutils::new_temp_dir();
+    let setup = set_up_push_repos(&settings, &temp_dir);
+    let git_settings = GitSettings {
+        transport: GitTransport::Gix,
+        ..Default::default()
+    };
+    let daemon = GitDaemon::spawn(temp_dir.path());
+    let remote_url = format!("git://127.0.0.1:{port}/source", port = daemon.port);
+    git::set_remote_url(setup.jj_repo.store(), "origin".as_ref(), &remote_url).unwrap();
+    let push = |updates: &[GitRefUpdate]| {
+        git::push_updates(
+            setup.jj_repo.as_ref(),
+            &git_settings,
+            "origin".as_ref(),
+            updates,
+            git::RemoteCallbacks::default(),
+        )
+        .unwrap()
+    };
+
+    // The lease is checked against the refs advertised by the remote
+    let stats = push(&[GitRefUpdate {
+        qualified_name: "refs/heads/main".into(),
+        expected_current_target: Some(setup.parent_of_main_commit.id().clone()),
+        new_target: Some(setup.child_of_main_commit.id().clone()),
+    }]);
+    assert_eq!(
+        push_status_rejected_references(stats),
+        ["refs/heads/main"].map(GitRefNameBuf::from)
+    );
+
+    let stats = push(&[
+        GitRefUpdate {
+            qualified_name: "refs/heads/main".into(),
+            expected_current_target: Some(setup.main_commit.id().clone()),
+            new_target: Some(setup.child_of_main_commit.id().clone()),
+        },
+        GitRefUpdate {
+            qualified_name: "refs/heads/topic".into(),
+            expected_current_target: None,
+            new_target: Some(setup.sideways_commit.id().clone()),
+        },
+    ]);
+    assert_eq!(
+        stats,
+        GitPushStats {
+            pushed: vec!["refs/heads/main".into(), "refs/heads/topic".into()],
+            ..Default::default()
+        }
+    );
+    let source_repo = testutils::git::open(&setup.source_repo_dir);
+    let new_target = source_repo.find_reference("refs/heads/main").unwrap();
+    assert_eq!(
+        new_target.target().id(),
+        git_id(&setup.child_of_main_commit)
+    );
+    let new_target = source_repo.find_reference("refs/heads/topic").unwrap();
+    assert_eq!(new_target.target().id(), git_id(&setup.sideways_commit));
+    let clone_repo = get_git_repo(&setup.jj_repo);
+    let new_target = clone_repo
+        .find_reference("refs/remotes/origin/topic")
+        .unwrap();
+    assert_eq!(new_target.target().id(), git_id(&setup.sideways_commit));
+
+    let stats = push(&[GitRefUpdate {
+        qualified_name: "refs/heads/topic".into(),
+        expected_current_target: Some(setup.sideways_commit.id().clone()),
+        new_target: None,
+    }]);
+    assert_eq!(
+        stats,
+        GitPushStats {
+            pushed: vec!["refs/heads/topic".into()],
+            ..Default::default()
+        }
+    );
+    let source_repo = testutils::git::open(&setup.source_repo_dir);
+    assert!(source_repo.find_reference("refs/heads/topic").is_err());
+    assert!(
+        clone_repo
+            .find_reference("refs/remotes/origin/topic")
+            .is_err()
+    );
+}
+
 #[test]
 fn test_bulk_update_extra_on_import_refs() {
     let git_settings = GitSettings::default();
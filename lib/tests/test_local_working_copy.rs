@@ -27,15 +27,19 @@ use jj_lib::backend::CopyId;
 use jj_lib::backend::MergedTreeId;
 use jj_lib::backend::TreeId;
 use jj_lib::backend::TreeValue;
+use jj_lib::config::ConfigLayer;
+use jj_lib::config::ConfigSource;
 use jj_lib::file_util::check_symlink_support;
 use jj_lib::file_util::try_symlink;
 use jj_lib::fsmonitor::FsmonitorSettings;
 use jj_lib::gitignore::GitIgnoreFile;
 use jj_lib::local_working_copy::LocalWorkingCopy;
+use jj_lib::matchers::PrefixMatcher;
 use jj_lib::merge::Merge;
 use jj_lib::merge::MergedTreeValue;
 use jj_lib::merged_tree::MergedTree;
 use jj_lib::merged_tree::MergedTreeBuilder;
+use jj_lib::object_id::ObjectId as _;
 use jj_lib::op_store::OperationId;
 use jj_lib::ref_name::WorkspaceName;
 use jj_lib::repo::ReadonlyRepo;
@@ -43,17 +47,23 @@ use jj_lib::repo::Repo as _;
 use jj_lib::repo_path::RepoPath;
 use jj_lib::repo_path::RepoPathBuf;
 use jj_lib::secret_backend::SecretBackend;
+use jj_lib::settings::UserSettings;
 use jj_lib::tree_builder::TreeBuilder;
 use jj_lib::working_copy::CheckoutError;
 use jj_lib::working_copy::CheckoutOptions;
 use jj_lib::working_copy::CheckoutStats;
+use jj_lib::working_copy::SnapshotError;
 use jj_lib::working_copy::SnapshotOptions;
+use jj_lib::working_copy::SnapshotRule;
+use jj_lib::working_copy::SnapshotRuleAction;
+use jj_lib::working_copy::SnapshotRules;
 use jj_lib::working_copy::UntrackedReason;
 use jj_lib::working_copy::WorkingCopy as _;
 use jj_lib::workspace::LockedWorkspace;
 use jj_lib::workspace::Workspace;
 use jj_lib::workspace::default_working_copy_factories;
 use pollster::FutureExt as _;
+use prost::Message as _;
 use test_case::test_case;
 use testutils::TestRepoBackend;
 use testutils::TestWorkspace;
@@ -99,6 +109,12 @@ fn check_vfat(dir: &Path) -> bool {
     dir.join(short_name).try_exists().unwrap()
 }
 
+fn user_settings_with_extra_config(extra_config: &str) -> UserSettings {
+    let mut config = testutils::base_user_config();
+    config.add_layer(ConfigLayer::parse(ConfigSource::User, extra_config).unwrap());
+    UserSettings::from_config(config).unwrap()
+}
+
 fn to_owned_path_vec(paths: &[&RepoPath]) -> Vec<RepoPathBuf> {
     paths.iter().map(|&path| path.to_owned()).collect()
 }
@@ -704,7 +720,8 @@ fn test_conflicting_changes_on_disk() {
             updated_files: 0,
             added_files: 3,
             removed_files: 0,
-            skipped_files: 3
+            skipped_files: 3,
+            ..CheckoutStats::default()
         }
     );
 
@@ -851,6 +868,115 @@ fn test_checkout_discard() {
     assert!(!reloaded_wc.file_states().unwrap().contains_path(file2_path));
 }
 
+#[test]
+fn test_checkout_resume_interrupted() {
+    let mut test_workspace = TestWorkspace::init();
+    let repo = test_workspace.repo.clone();
+    let workspace_root = test_workspace.workspace.workspace_root().to_owned();
+
+    let file1_path = repo_path("file1");
+    let file2_path = repo_path("file2");
+    let file3_path = repo_path("file3");
+    let tree1 = create_tree(
+        &repo,
+        &[
+            (file1_path, "old"),
+            (file2_path, "old"),
+            (file3_path, "old"),
+        ],
+    );
+    let tree2 = create_tree(
+        &repo,
+        &[(file1_path, "new contents"), (file2_path, "new contents")],
+    );
+    let commit1 = commit_with_tree(repo.store(), tree1.id());
+    let commit2 = commit_with_tree(repo.store(), tree2.id());
+
+    let ws = &mut test_workspace.workspace;
+    ws.check_out(
+        repo.op_id().clone(),
+        None,
+        &commit1,
+        &CheckoutOptions::empty_for_test(),
+    )
+    .unwrap();
+
+    // Simulate a checkout of commit2 which was interrupted after updating file1.
+    std::fs::write(
+        file1_path.to_fs_path_unchecked(&workspace_root),
+        "new contents",
+    )
+    .unwrap();
+    let wc: &LocalWorkingCopy = ws.working_copy().as_any().downcast_ref().unwrap();
+    let tree_state_path = wc.state_path().join("tree_state");
+    let mut proto =
+        jj_lib::protos::working_copy::TreeState::decode(&*std::fs::read(&tree_state_path).unwrap())
+            .unwrap();
+    proto.pending_checkout = Some(jj_lib::protos::working_copy::PendingCheckout {
+        target_tree_ids: tree2
+            .id()
+            .to_merge()
+            .iter()
+            .map(|id| id.to_bytes())
+            .collect(),
+        next_path: file2_path.as_internal_file_string().to_owned(),
+    });
+    std::fs::write(&tree_state_path, proto.encode_to_vec()).unwrap();
+    test_workspace.workspace = Workspace::load(
+        repo.settings(),
+        &workspace_root,
+        &test_workspace.env.default_store_factories(),
+        &default_working_copy_factories(),
+    )
+    .unwrap();
+    // Reload commits from the store associated with the workspace
+    let repo = test_workspace
+        .workspace
+        .repo_loader()
+        .load_at(repo.operation())
+        .unwrap();
+    let commit2 = repo.store().get_commit(commit2.id()).unwrap();
+    assert_eq!(
+        test_workspace
+            .workspace
+            .working_copy()
+            .interrupted_checkout()
+            .unwrap(),
+        Some(&tree2.id())
+    );
+
+    // The files can't be snapshotted until the checkout is finished.
+    assert_matches!(
+        test_workspace.snapshot(),
+        Err(SnapshotError::InterruptedCheckout)
+    );
+
+    // Checking out the target commit updates the remaining files only.
+    let ws = &mut test_workspace.workspace;
+    let mut locked_ws = ws.start_working_copy_mutation().unwrap();
+    let stats = locked_ws
+        .locked_wc()
+        .check_out(&commit2, &CheckoutOptions::empty_for_test())
+        .unwrap();
+    locked_ws.finish(repo.op_id().clone()).unwrap();
+    assert_eq!(
+        stats,
+        CheckoutStats {
+            updated_files: 1,
+            removed_files: 1,
+            ..CheckoutStats::default()
+        }
+    );
+    assert_eq!(ws.working_copy().interrupted_checkout().unwrap(), None);
+    assert_eq!(
+        std::fs::read_to_string(file2_path.to_fs_path_unchecked(&workspace_root)).unwrap(),
+        "new contents"
+    );
+    assert!(!file3_path.to_fs_path_unchecked(&workspace_root).exists());
+    let new_tree = test_workspace.snapshot().unwrap();
+    assert_eq!(new_tree.id(), tree2.id());
+}
+
 #[test]
 fn test_snapshot_file_directory_transition() {
     let mut test_workspace = TestWorkspace::init();
@@ -940,7 +1066,8 @@ fn test_materialize_snapshot_conflicted_files() {
             updated_files: 0,
             added_files: 2,
             removed_files: 0,
-            skipped_files: 0
+            skipped_files: 0,
+            ..CheckoutStats::default()
         }
     );
 
@@ -1023,6 +1150,105 @@ fn test_materialize_snapshot_conflicted_files() {
     );
 }
 
+#[test]
+fn test_materialize_snapshot_binary_conflict_sidecars() {
+    let settings =
+        user_settings_with_extra_config(r#"working-copy.binary-conflicts = "sidecar-files""#);
+    let mut test_workspace = TestWorkspace::init_with_settings(&settings);
+    let repo = &test_workspace.repo.clone();
+    let workspace_root = test_workspace.workspace.workspace_root().to_owned();
+
+    let file_path = repo_path("file");
+    let disk_path = file_path.to_fs_path_unchecked(&workspace_root);
+    let sidecar_path = |suffix: &str| workspace_root.join(format!("file.jjconflict-{suffix}"));
+    let base_tree = create_tree(repo, &[(file_path, "\0base")]);
+    let side1_tree = create_tree(repo, &[(file_path, "\0side1")]);
+    let side2_tree = create_tree(repo, &[(file_path, "\0side2")]);
+    let merged_tree = side1_tree.merge(base_tree, side2_tree).block_on().unwrap();
+    let commit = commit_with_tree(repo.store(), merged_tree.id());
+    test_workspace
+        .workspace
+        .check_out(
+            repo.op_id().clone(),
+            None,
+            &commit,
+            &CheckoutOptions::empty_for_test(),
+        )
+        .unwrap();
+
+    // Each term is written to a sidecar file, and the conflicted file lists them
+    assert_eq!(std::fs::read(sidecar_path("base-0")).unwrap(), b"\0base");
+    assert_eq!(std::fs::read(sidecar_path("side-1")).unwrap(), b"\0side1");
+    assert_eq!(std::fs::read(sidecar_path("side-2")).unwrap(), b"\0side2");
+    let description = std::fs::read_to_string(&disk_path).unwrap();
+    assert!(
+        description.contains("file.jjconflict-side-2"),
+        "{description}"
+    );
+
+    // The sidecar files aren't tracked, and the conflict is kept as is
+    let tree = test_workspace.snapshot().unwrap();
+    assert_eq!(tree.id(), merged_tree.id());
+
+    // Modifying a sidecar file resolves the conflict to its contents
+    std::fs::write(sidecar_path("side-2"), b"\0resolved").unwrap();
+    let tree = test_workspace.snapshot().unwrap();
+    let expected_tree = create_tree(repo, &[(file_path, "\0resolved")]);
+    assert_eq!(tree.id(), expected_tree.id());
+    assert_eq!(std::fs::read(&disk_path).unwrap(), b"\0resolved");
+    assert!(!sidecar_path("base-0").exists());
+    assert!(!sidecar_path("side-1").exists());
+    assert!(!sidecar_path("side-2").exists());
+}
+
+#[test]
+fn test_materialize_snapshot_symlink_conflict_as_text() {
+    let settings = user_settings_with_extra_config(r#"working-copy.symlink-conflicts = "text""#);
+    let mut test_workspace = TestWorkspace::init_with_settings(&settings);
+    let repo = &test_workspace.repo.clone();
+    let workspace_root = test_workspace.workspace.workspace_root().to_owned();
+
+    let link_path = repo_path("link");
+    let disk_path = link_path.to_fs_path_unchecked(&workspace_root);
+    let symlink_tree = |target: &str| {
+        create_tree_with(repo, |builder| {
+            builder.symlink(link_path, target);
+        })
+    };
+    let merged_tree = symlink_tree("a")
+        .merge(symlink_tree("base"), symlink_tree("b"))
+        .block_on()
+        .unwrap();
+    let commit = commit_with_tree(repo.store(), merged_tree.id());
+    test_workspace
+        .workspace
+        .check_out(
+            repo.op_id().clone(),
+            None,
+            &commit,
+            &CheckoutOptions::empty_for_test(),
+        )
+        .unwrap();
+    insta::assert_snapshot!(std::fs::read_to_string(&disk_path).unwrap(), @r"
+    <<<<<<< Conflict 1 of 1
+    %%%%%%% Changes from base to side #1
+    -base
+    +a
+    +++++++ Contents of side #2
+    b
+    >>>>>>> Conflict 1 of 1 ends
+    ");
+
+    // Removing the conflict markers resolves the conflict to a symlink
+    testutils::write_working_copy_file(&workspace_root, link_path, "b\n");
+    let tree = test_workspace.snapshot().unwrap();
+    assert_eq!(tree.id(), symlink_tree("b").id());
+    if check_symlink_support().unwrap() {
+        assert!(disk_path.symlink_metadata().unwrap().is_symlink());
+        assert_eq!(std::fs::read_link(&disk_path).unwrap(), Path::new("b"));
+    }
+}
+
 #[test]
 fn test_snapshot_racy_timestamps() {
     // Tests that file modifications are detected even if they happen the same
@@ -1048,6 +1274,74 @@ fn test_snapshot_racy_timestamps() {
     }
 }
 
+#[test]
+fn test_snapshot_directory_cache() {
+    // Tests that changes are detected in directories whose listings were cached
+    // by the previous snapshot.
+    let mut test_workspace = TestWorkspace::init();
+    let repo = test_workspace.repo.clone();
+    let op_id = repo.op_id().clone();
+    let workspace_root = test_workspace.workspace.workspace_root().to_owned();
+
+    let gitignore_path = repo_path(".gitignore");
+    let ignored_path = repo_path("dir/ignored");
+    let modified_path = repo_path("dir/modified");
+    let added_path = repo_path("dir/sub/added");
+    let nested_path = repo_path("dir/sub/nested");
+
+    testutils::write_working_copy_file(&workspace_root, gitignore_path, "ignored\n");
+    std::fs::create_dir_all(workspace_root.join("dir").join("sub")).unwrap();
+    testutils::write_working_copy_file(&workspace_root, ignored_path, "1");
+    testutils::write_working_copy_file(&workspace_root, modified_path, "1");
+    testutils::write_working_copy_file(&workspace_root, nested_path, "1");
+    // The directories must be older than the working-copy state to be cached.
+    std::thread::sleep(std::time::Duration::from_millis(10));
+    let tree1 = test_workspace.snapshot().unwrap();
+    assert_eq!(
+        tree1.entries().map(|(path, _value)| path).collect_vec(),
+        to_owned_path_vec(&[gitignore_path, modified_path, nested_path])
+    );
+
+    // Modifying a file doesn't change the directory mtime
+    testutils::write_working_copy_file(&workspace_root, modified_path, "2");
+    let tree2 = test_workspace.snapshot().unwrap();
+    assert_ne!(
+        tree2.path_value(modified_path).unwrap(),
+        tree1.path_value(modified_path).unwrap()
+    );
+
+    // Adding a file to a subdirectory of a cached directory
+    testutils::write_working_copy_file(&workspace_root, added_path, "1");
+    let tree3 = test_workspace.snapshot().unwrap();
+    assert_eq!(
+        tree3.entries().map(|(path, _value)| path).collect_vec(),
+        to_owned_path_vec(&[gitignore_path, modified_path, added_path, nested_path])
+    );
+
+    // Changing the ignore patterns of the parent directory
+    testutils::write_working_copy_file(&workspace_root, gitignore_path, "");
+    let tree4 = test_workspace.snapshot().unwrap();
+    assert_eq!(
+        tree4.entries().map(|(path, _value)| path).collect_vec(),
+        to_owned_path_vec(&[
+            gitignore_path,
+            ignored_path,
+            modified_path,
+            added_path,
+            nested_path,
+        ])
+    );
+
+    // Files which become untracked by reset should be added back
+    let ws = &mut test_workspace.workspace;
+    let commit = commit_with_tree(repo.store(), tree1.id());
+    let mut locked_ws = ws.start_working_copy_mutation().unwrap();
+    locked_ws.locked_wc().reset(&commit).unwrap();
+    locked_ws.finish(op_id).unwrap();
+    let tree5 = test_workspace.snapshot().unwrap();
+    assert_eq!(tree5.id(), tree4.id());
+}
+
 #[cfg(unix)]
 #[test]
 fn test_snapshot_special_file() {
@@ -1527,10 +1821,15 @@ fn test_check_out_existing_file_cannot_be_removed() {
 
     // TODO: find a way to trigger the error on Windows
     if !cfg!(windows) {
+        // The checkout succeeds, but the file is reported and left as it was.
+        let stats = result.unwrap();
+        assert_eq!(stats.failed_files.len(), 1);
         assert_matches!(
-            result,
-            Err(CheckoutError::Other { message, .. }) if message.contains("Failed to remove")
+            stats.failed_files.get(file_path),
+            Some(message) if message.contains("Failed to remove")
         );
+        let new_tree = test_workspace.snapshot().unwrap();
+        assert_eq!(new_tree.id(), tree1.id());
     }
 }
 
@@ -1606,6 +1905,46 @@ fn test_check_out_existing_directory_symlink() {
     assert!(!workspace_root.parent().unwrap().join("escaped").exists());
 }
 
+#[test]
+fn test_check_out_path_collision_icase_fs() {
+    let mut test_workspace = TestWorkspace::init();
+    let repo = &test_workspace.repo;
+    let workspace_root = test_workspace.workspace.workspace_root().to_owned();
+    let is_icase_fs = check_icase_fs(&workspace_root);
+
+    let tree = create_tree(
+        repo,
+        &[
+            (repo_path("Dir/a"), "a"),
+            (repo_path("dir/b"), "b"),
+            (repo_path("file"), "file"),
+        ],
+    );
+    let commit = commit_with_tree(repo.store(), tree.id());
+
+    // On icase fs, "Dir" and "dir" would be the same directory
+    let ws = &mut test_workspace.workspace;
+    let result = ws.check_out(
+        repo.op_id().clone(),
+        None,
+        &commit,
+        &CheckoutOptions::empty_for_test(),
+    );
+    if is_icase_fs {
+        assert_matches!(
+            result,
+            Err(CheckoutError::PathCollision { path, other_path })
+                if &*path == repo_path("Dir") && &*other_path == repo_path("dir")
+        );
+        // Nothing was written
+        assert!(!workspace_root.join("file").exists());
+    } else {
+        assert_eq!(result.unwrap().added_files, 3);
+        assert!(workspace_root.join("Dir").join("a").exists());
+        assert!(workspace_root.join("dir").join("b").exists());
+    }
+}
+
 #[test]
 fn test_check_out_existing_directory_symlink_icase_fs() {
     if !check_symlink_support().unwrap() {
@@ -2187,6 +2526,72 @@ fn test_fsmonitor() {
     }
 }
 
+#[cfg(all(target_os = "linux", feature = "inotify"))]
+#[test]
+fn test_fsmonitor_inotify() {
+    use jj_lib::fsmonitor::InotifyConfig;
+    use jj_lib::fsmonitor::inotify;
+
+    let mut test_workspace = TestWorkspace::init();
+    let repo = &test_workspace.repo;
+    let workspace_root = test_workspace.workspace.workspace_root().to_owned();
+    let state_path = workspace_root.join(".jj").join("working_copy");
+    let ws = &mut test_workspace.workspace;
+    let config = InotifyConfig {
+        spawn_daemon: false,
+        daemon_command: vec![],
+        max_tracked_paths: 100,
+    };
+
+    let foo_path = repo_path("foo");
+    let nested_path = repo_path("path/to/nested");
+    testutils::write_working_copy_file(&workspace_root, foo_path, "foo\n");
+    testutils::write_working_copy_file(&workspace_root, nested_path, "nested\n");
+
+    let mut snapshot = || {
+        let mut locked_ws = ws.start_working_copy_mutation().unwrap();
+        let (tree_id, _stats) = locked_ws
+            .locked_wc()
+            .snapshot(&SnapshotOptions {
+                fsmonitor_settings: FsmonitorSettings::Inotify(config.clone()),
+                ..SnapshotOptions::empty_for_test()
+            })
+            .unwrap();
+        locked_ws.finish(repo.op_id().clone()).unwrap();
+        tree_id
+    };
+
+    std::thread::scope(|s| {
+        // Run the daemon in this process instead of spawning it.
+        let daemon =
+            s.spawn(|| inotify::run_daemon(&workspace_root, &state_path, config.max_tracked_paths));
+        let monitor = loop {
+            match inotify::Fsmonitor::init(&workspace_root, &state_path, &config) {
+                Ok(monitor) => break monitor,
+                Err(inotify::Error::DaemonNotRunning) => {
+                    std::thread::sleep(std::time::Duration::from_millis(10));
+                }
+                Err(err) => panic!("daemon didn't start: {err}"),
+            }
+        };
+
+        // The first snapshot scans the whole working copy.
+        let tree_id = snapshot();
+        let expected_tree = create_tree(repo, &[(foo_path, "foo\n"), (nested_path, "nested\n")]);
+        assert_eq!(tree_id, expected_tree.id());
+
+        // Later snapshots pick up the changes reported by the daemon.
+        testutils::write_working_copy_file(&workspace_root, foo_path, "updated foo\n");
+        std::fs::remove_file(nested_path.to_fs_path_unchecked(&workspace_root)).unwrap();
+        let tree_id = snapshot();
+        let expected_tree = create_tree(repo, &[(foo_path, "updated foo\n")]);
+        assert_eq!(tree_id, expected_tree.id());
+
+        monitor.stop().unwrap();
+        daemon.join().unwrap().unwrap();
+    });
+}
+
 #[test]
 fn test_snapshot_max_new_file_size() {
     let mut test_workspace = TestWorkspace::init();
@@ -2269,3 +2674,84 @@ fn test_snapshot_max_new_file_size() {
         UntrackedReason::FileTooLarge { .. }
     );
 }
+
+#[test]
+fn test_snapshot_rules() {
+    let mut test_workspace = TestWorkspace::init();
+    let workspace_root = test_workspace.workspace.workspace_root().to_owned();
+    let rule = |name: &str, prefix: &str, max_new_file_size, action| SnapshotRule {
+        name: name.to_owned(),
+        matcher: Box::new(PrefixMatcher::new([repo_path(prefix)])),
+        max_new_file_size,
+        action,
+    };
+    #[expect(clippy::arc_with_non_send_sync)]
+    let options = SnapshotOptions {
+        max_new_file_size: 10,
+        snapshot_rules: Arc::new(SnapshotRules::new(vec![
+            rule("logs", "logs", None, SnapshotRuleAction::Ignore),
+            rule("assets", "assets", Some(100), SnapshotRuleAction::Warn),
+            rule(
+                "secrets",
+                "assets/secrets",
+                None,
+                SnapshotRuleAction::Refuse,
+            ),
+        ])),
+        ..SnapshotOptions::empty_for_test()
+    };
+    for (path, size) in [
+        ("logs/a.log", 1),
+        ("assets/medium", 50),
+        ("assets/large", 200),
+        ("assets/secrets/key", 200),
+        ("large", 50),
+    ] {
+        let path = repo_path(path).to_fs_path_unchecked(&workspace_root);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, vec![0; size]).unwrap();
+    }
+
+    let (tree, stats) = test_workspace.snapshot_with_options(&options).unwrap();
+    // Files matching a rule aren't subject to the global size limit, and
+    // files over the limit of a warning rule are tracked.
+    assert_eq!(
+        tree.entries().map(|(path, _)| path).collect_vec(),
+        [
+            repo_path_buf("assets/large"),
+            repo_path_buf("assets/medium")
+        ]
+    );
+    assert_eq!(
+        stats.warned_paths.keys().map(AsRef::as_ref).collect_vec(),
+        [repo_path("assets/large")]
+    );
+    assert_eq!(stats.warned_paths.values().next().unwrap().rule, "assets");
+    assert_eq!(
+        stats
+            .untracked_paths
+            .keys()
+            .map(AsRef::as_ref)
+            .collect_vec(),
+        [
+            repo_path("assets/secrets/key"),
+            repo_path("large"),
+            repo_path("logs/a.log"),
+        ]
+    );
+    let reasons = stats.untracked_paths.values().collect_vec();
+    // The refusing rule takes precedence over the warning one.
+    assert_matches!(
+        reasons[0],
+        UntrackedReason::RefusedByRule { rule, size: 200 } if rule == "secrets"
+    );
+    assert_matches!(reasons[1], UntrackedReason::FileTooLarge { .. });
+    assert_matches!(
+        reasons[2],
+        UntrackedReason::IgnoredByRule { rule } if rule == "logs"
+    );
+
+    // Tracked files aren't subject to the rules.
+    let (_tree, stats) = test_workspace.snapshot_with_options(&options).unwrap();
+    assert!(stats.warned_paths.is_empty());
+}
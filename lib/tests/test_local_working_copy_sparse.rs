@@ -86,6 +86,7 @@ fn test_sparse_checkout() {
             added_files: 0,
             removed_files: 3,
             skipped_files: 0,
+            ..CheckoutStats::default()
         }
     );
     assert_eq!(
@@ -159,6 +160,7 @@ fn test_sparse_checkout() {
             added_files: 2,
             removed_files: 2,
             skipped_files: 0,
+            ..CheckoutStats::default()
         }
     );
     assert_eq!(locked_wc.sparse_patterns().unwrap(), sparse_patterns);
@@ -3,6 +3,7 @@ use jj_lib::backend::Signature;
 use jj_lib::backend::Timestamp;
 use jj_lib::config::ConfigLayer;
 use jj_lib::config::ConfigSource;
+use jj_lib::operation::Operation;
 use jj_lib::repo::Repo as _;
 use jj_lib::settings::UserSettings;
 use jj_lib::signing::SigStatus;
@@ -208,3 +209,65 @@ fn drop_behavior(backend: TestRepoBackend) {
     let rewritten_commit = repo.store().get_commit(rewritten.id()).unwrap();
     assert_eq!(rewritten_commit.verification().unwrap(), None);
 }
+
+fn operation_signing_settings(sign_operations: bool) -> UserSettings {
+    let mut config = testutils::base_user_config();
+    config.add_layer(
+        ConfigLayer::parse(
+            ConfigSource::User,
+            &format!(
+                r#"
+                signing.key = "impeccable"
+                signing.sign-operations = {sign_operations}
+                "#,
+            ),
+        )
+        .unwrap(),
+    );
+    UserSettings::from_config(config).unwrap()
+}
+
+#[test_case(false ; "disabled")]
+#[test_case(true ; "enabled")]
+fn sign_operations(sign_operations: bool) {
+    let settings = operation_signing_settings(sign_operations);
+
+    let signer = Signer::new(Some(Box::new(TestSigningBackend)), vec![]);
+    let test_workspace =
+        TestWorkspace::init_with_backend_and_signer(TestRepoBackend::Simple, signer, &settings);
+
+    let repo = &test_workspace.repo;
+    let mut tx = repo.start_transaction();
+    write_random_commit(tx.repo_mut());
+    let repo = tx.commit("test").unwrap();
+
+    let op = repo.operation();
+    assert_eq!(op.is_signed(), sign_operations);
+    let verification = op.verification(repo.store().signer()).unwrap();
+    if sign_operations {
+        assert_eq!(verification, good_verification());
+    } else {
+        assert_eq!(verification, None);
+    }
+}
+
+#[test]
+fn tampered_operation() {
+    let settings = operation_signing_settings(true);
+
+    let signer = Signer::new(Some(Box::new(TestSigningBackend)), vec![]);
+    let test_workspace =
+        TestWorkspace::init_with_backend_and_signer(TestRepoBackend::Simple, signer, &settings);
+
+    let repo = &test_workspace.repo;
+    let mut tx = repo.start_transaction();
+    write_random_commit(tx.repo_mut());
+    let repo = tx.commit("test").unwrap();
+
+    let mut data = repo.operation().store_operation().clone();
+    data.metadata.description = "tampered".to_owned();
+    let op_id = repo.op_store().write_operation(&data).unwrap();
+    let op = Operation::new(repo.op_store().clone(), op_id, data);
+    let verification = op.verification(repo.store().signer()).unwrap().unwrap();
+    assert_eq!(verification.status, SigStatus::Bad);
+}